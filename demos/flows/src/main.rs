@@ -136,14 +136,12 @@ async fn demo_function_calling(provider: &Arc<dyn LLMProvider>) -> Result<(), Bo
 
     // Handle tool calls
     if !response.message.tool_calls.is_empty() {
-        // Execute tool calls in parallel but preserve order for LLM expectations
-        use futures_util::future::join_all;
-
-        let tool_futures: Vec<_> = response.message.tool_calls.iter()
-            .map(|call| registry.invoke(&call.function))
+        // Execute tool calls in parallel; results come back in call order
+        let calls: Vec<_> = response.message.tool_calls.iter()
+            .map(|call| call.function.clone())
             .collect();
 
-        let results = join_all(tool_futures).await;
+        let results = registry.invoke_all_ordered(&calls).await;
 
         // Process results in original call order
         for (call, result) in response.message.tool_calls.iter().zip(results) {
@@ -195,6 +193,9 @@ async fn demo_sequential(provider: &Arc<dyn LLMProvider>) -> Result<(), Box<dyn
             denkwerk::SequentialEvent::Step { agent, output } => {
                 println!("{}: {}", colorize_agent(agent), output);
             }
+            denkwerk::SequentialEvent::StepSkipped { agent, reason } => {
+                println!("{} skipped: {}", colorize_agent(agent), reason);
+            }
             denkwerk::SequentialEvent::Completed { agent, output } => {
                 if let Some(result) = output {
                     println!("{} finalized: {}", colorize_agent(agent), result);
@@ -369,6 +370,9 @@ async fn demo_handoff(provider: &Arc<dyn LLMProvider>) -> Result<(), Box<dyn std
                 println!("{}: {}", colorize_agent(agent), message);
                 last_agent_message = Some((agent.clone(), message.clone()));
             }
+            denkwerk::HandoffEvent::ToolCall { agent, function } => {
+                println!("{} called {function}", colorize_agent(agent));
+            }
             denkwerk::HandoffEvent::HandOff { from, to, .. } => {
                 // Show the agent's reasoning for the handoff in color
                 if let Some((agent, reasoning)) = &last_agent_message {
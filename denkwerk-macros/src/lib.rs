@@ -1,8 +1,10 @@
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
 use quote::{format_ident, quote};
+use heck::{ToShoutySnakeCase, ToSnakeCase};
 use syn::{
-    parse::Parser,
+    ext::IdentExt,
+    parse::{Parse, ParseStream, Parser},
     parse_macro_input,
     punctuated::Punctuated,
     spanned::Spanned,
@@ -28,6 +30,13 @@ pub fn kernel_function(attr: TokenStream, item: TokenStream) -> TokenStream {
     }
 }
 
+/// Expose `#[kernel_function]` methods of an impl block as kernel functions.
+///
+/// On `impl Type` this adds `kernel_functions` / `register_kernel_functions` to `Type`. On
+/// `impl Trait for Type` each method gets a `{method}_kernel_from_{trait}` constructor, with
+/// the trait name in snake case (`add_kernel_from_calculator`), taking
+/// `Arc<dyn Trait + Send + Sync>`; place `#[kernel_module]` above `#[async_trait]` so it sees
+/// the methods before they are desugared.
 #[proc_macro_attribute]
 pub fn kernel_module(attr: TokenStream, item: TokenStream) -> TokenStream {
     if !attr.is_empty() {
@@ -48,9 +57,119 @@ pub fn kernel_module(attr: TokenStream, item: TokenStream) -> TokenStream {
     }
 }
 
+/// Generate typed accessors for shared state keys.
+///
+/// `shared_state_keys! { "user_profile" => UserProfile: Profile }` expands to a
+/// `USER_PROFILE_KEY` constant, a `UserProfile` marker implementing
+/// `denkwerk::shared_state::StateKey`, and async `get_user_profile` / `set_user_profile`
+/// functions that (de)serialize `Profile` through any `SharedStateContext`.
+#[proc_macro]
+pub fn shared_state_keys(input: TokenStream) -> TokenStream {
+    match expand_shared_state_keys(input.into()) {
+        Ok(expansion) => expansion.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+struct StateKeyDefinition {
+    key: syn::LitStr,
+    marker: Ident,
+    value_ty: Type,
+}
+
+impl Parse for StateKeyDefinition {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        input.parse::<syn::Token![=>]>()?;
+        let marker = input.parse()?;
+        input.parse::<syn::Token![:]>()?;
+        let value_ty = input.parse()?;
+        Ok(Self { key, marker, value_ty })
+    }
+}
+
+fn expand_shared_state_keys(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let parser = Punctuated::<StateKeyDefinition, syn::Token![,]>::parse_terminated;
+    let definitions = parser.parse2(input)?;
+
+    let mut seen = std::collections::HashSet::new();
+    let mut items = Vec::new();
+
+    for definition in &definitions {
+        let StateKeyDefinition { key, marker, value_ty } = definition;
+        let key_value = key.value();
+        validate_state_key(key)?;
+        if !seen.insert(key_value.clone()) {
+            return Err(Error::new(key.span(), format!("duplicate shared state key `{key_value}`")));
+        }
+
+        let snake = key_value.to_snake_case();
+        let const_ident = format_ident!("{}_KEY", key_value.to_shouty_snake_case(), span = key.span());
+        let getter_ident = format_ident!("get_{}", snake, span = key.span());
+        let setter_ident = format_ident!("set_{}", snake, span = key.span());
+        let const_doc = format!("Shared state key `{key_value}`.");
+
+        items.push(quote! {
+            #[doc = #const_doc]
+            pub const #const_ident: &str = #key;
+
+            #[doc = #const_doc]
+            pub struct #marker;
+
+            impl ::denkwerk::shared_state::StateKey for #marker {
+                const KEY: &'static str = #const_ident;
+                type Value = #value_ty;
+            }
+
+            pub async fn #getter_ident<S>(
+                store: &S,
+                scope: Option<&str>,
+            ) -> Result<Option<#value_ty>, ::denkwerk::LLMError>
+            where
+                S: ::denkwerk::SharedStateContext + ?Sized,
+            {
+                ::denkwerk::shared_state::read_typed::<#marker, S>(store, scope).await
+            }
+
+            pub async fn #setter_ident<S>(
+                store: &S,
+                scope: Option<&str>,
+                value: #value_ty,
+            ) -> Result<(), ::denkwerk::LLMError>
+            where
+                S: ::denkwerk::SharedStateContext + ?Sized,
+            {
+                ::denkwerk::shared_state::write_typed::<#marker, S>(store, scope, value).await
+            }
+        });
+    }
+
+    Ok(quote! { #(#items)* })
+}
+
+/// Keys must start with a letter and contain only ASCII alphanumerics and `_`, `-` or `.`
+/// separators, so the derived constant and function names are valid identifiers.
+fn validate_state_key(key: &syn::LitStr) -> Result<(), Error> {
+    let value = key.value();
+    let valid = value.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if !valid {
+        return Err(Error::new(
+            key.span(),
+            format!("shared state key `{value}` does not convert to a valid identifier"),
+        ));
+    }
+    Ok(())
+}
+
 struct KernelMeta {
     kernel_name: String,
     description: Option<String>,
+    openapi: bool,
+    /// Deprecation note, from `deprecated = "..."` or an existing `#[deprecated]` attribute
+    deprecated: Option<String>,
 }
 
 type MetaList = Vec<Meta>;
@@ -62,9 +181,20 @@ fn parse_kernel_meta(
 ) -> Result<KernelMeta, Error> {
     let mut kernel_name: Option<String> = None;
     let mut description: Option<String> = None;
+    let mut openapi = false;
+    let mut deprecated: Option<String> = None;
 
     for meta in args {
         match meta {
+            Meta::NameValue(kv) if kv.path.is_ident("deprecated") => {
+                deprecated = Some(expect_string_literal(&kv.value)?);
+            }
+            Meta::NameValue(kv) if kv.path.is_ident("openapi") => {
+                openapi = expect_bool_literal(&kv.value)?;
+            }
+            Meta::Path(path) if path.is_ident("openapi") => {
+                openapi = true;
+            }
             Meta::NameValue(kv) if kv.path.is_ident("name") => {
                 kernel_name = Some(expect_string_literal(&kv.value)?);
             }
@@ -88,12 +218,67 @@ fn parse_kernel_meta(
         }
     });
 
+    if deprecated.is_none() {
+        deprecated = attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("deprecated"))
+            .map(deprecation_note)
+            .transpose()?;
+    }
+
+    // Surface the deprecation to the model as well, since it never sees Rust warnings.
+    if let Some(note) = &deprecated {
+        description = Some(match description {
+            Some(text) => format!("{text} (DEPRECATED: {note})"),
+            None => format!("(DEPRECATED: {note})"),
+        });
+    }
+
     Ok(KernelMeta {
         kernel_name: kernel_name.unwrap_or_else(|| fallback.to_string()),
         description,
+        openapi,
+        deprecated,
     })
 }
 
+/// Note of a `#[deprecated]`, `#[deprecated = "..."]` or `#[deprecated(note = "...")]` attribute.
+fn deprecation_note(attr: &Attribute) -> Result<String, Error> {
+    const FALLBACK: &str = "this function is deprecated";
+    match &attr.meta {
+        Meta::Path(_) => Ok(FALLBACK.to_string()),
+        Meta::NameValue(kv) => expect_string_literal(&kv.value),
+        Meta::List(_) => {
+            let entries = attr.parse_args_with(Punctuated::<Meta, syn::Token![,]>::parse_terminated)?;
+            for entry in entries {
+                if let Meta::NameValue(kv) = entry {
+                    if kv.path.is_ident("note") {
+                        return expect_string_literal(&kv.value);
+                    }
+                }
+            }
+            Ok(FALLBACK.to_string())
+        }
+    }
+}
+
+fn deprecated_attr(deprecated: &Option<String>) -> TokenStream2 {
+    match deprecated {
+        Some(note) => quote! { #[deprecated(note = #note)] },
+        None => TokenStream2::new(),
+    }
+}
+
+fn expect_bool_literal(expr: &Expr) -> Result<bool, Error> {
+    if let Expr::Lit(expr_lit) = expr {
+        if let Lit::Bool(lit_bool) = &expr_lit.lit {
+            return Ok(lit_bool.value);
+        }
+    }
+
+    Err(Error::new(expr.span(), "expected boolean literal"))
+}
+
 fn expect_string_literal(expr: &Expr) -> Result<String, Error> {
     if let Expr::Lit(expr_lit) = expr {
         if let Lit::Str(lit_str) = &expr_lit.lit {
@@ -106,8 +291,11 @@ fn expect_string_literal(expr: &Expr) -> Result<String, Error> {
 
 struct ParameterMeta {
     ident: Ident,
+    json_name: String,
     ty: Type,
     schema_ty: Type,
+    /// `schema_as` override: the schema type and the literal it was parsed from
+    schema_as: Option<(Type, syn::LitStr)>,
     description: Option<String>,
     default: Option<Expr>,
     optional: bool,
@@ -126,6 +314,8 @@ fn parse_parameters(inputs: &mut Punctuated<FnArg, syn::Token![,]>) -> Result<Ve
         let mut description = None;
         let mut default = None;
         let mut optional = false;
+        let mut json_name = None;
+        let mut schema_as = None;
         let mut retained_attrs = Vec::new();
 
         for attr in &pat_ty.attrs {
@@ -148,6 +338,23 @@ fn parse_parameters(inputs: &mut Punctuated<FnArg, syn::Token![,]>) -> Result<Ve
                         Meta::NameValue(kv) if kv.path.is_ident("default") => {
                             default = Some(kv.value.clone());
                         }
+                        // `json_name` reads better when remapping to an external casing
+                        // convention; both set the JSON key and keep the Rust ident.
+                        Meta::NameValue(kv)
+                            if kv.path.is_ident("name") || kv.path.is_ident("json_name") =>
+                        {
+                            let name = expect_string_literal(&kv.value)?;
+                            if is_reserved_parameter_name(&name) {
+                                return Err(Error::new_spanned(&kv.value, RESERVED_PARAMETER_MESSAGE));
+                            }
+                            json_name = Some(name);
+                        }
+                        Meta::NameValue(kv) if kv.path.is_ident("schema_as") => {
+                            let Expr::Lit(syn::ExprLit { lit: Lit::Str(literal), .. }) = &kv.value else {
+                                return Err(Error::new_spanned(&kv.value, "expected string literal"));
+                            };
+                            schema_as = Some((literal.parse::<Type>()?, literal.clone()));
+                        }
                         Meta::Path(path) if path.is_ident("optional") => {
                             optional = true;
                         }
@@ -162,17 +369,35 @@ fn parse_parameters(inputs: &mut Punctuated<FnArg, syn::Token![,]>) -> Result<Ve
             retained_attrs.push(attr.clone());
         }
 
-        let (schema_ty, is_option) = extract_schema_type(&pat_ty.ty);
+        let (value_ty, is_option) = extract_schema_type(&pat_ty.ty);
         if is_option {
             optional = true;
         }
+        let schema_ty = match &schema_as {
+            Some((schema_ty, _)) => schema_ty.clone(),
+            None => value_ty,
+        };
 
         pat_ty.attrs = retained_attrs;
 
+        let ident = &pat_ident.ident;
+        let json_name = match json_name {
+            Some(name) => name,
+            None => {
+                let name = ident.unraw().to_string();
+                if is_reserved_parameter_name(&name) {
+                    return Err(Error::new_spanned(ident, RESERVED_PARAMETER_MESSAGE));
+                }
+                name
+            }
+        };
+
         params.push(ParameterMeta {
-            ident: pat_ident.ident.clone(),
+            ident: ident.clone(),
+            json_name,
             ty: (*pat_ty.ty).clone(),
             schema_ty,
+            schema_as,
             description,
             default,
             optional,
@@ -182,6 +407,61 @@ fn parse_parameters(inputs: &mut Punctuated<FnArg, syn::Token![,]>) -> Result<Ve
     Ok(params)
 }
 
+const RESERVED_PARAMETER_MESSAGE: &str =
+    "parameter name is a reserved identifier; rename it or use #[param(name = \"alt_name\")]";
+
+/// Rust keywords plus JSON Schema keywords that would be confusing as argument names.
+const RESERVED_PARAMETER_NAMES: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true",
+    "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final",
+    "gen", "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+    "properties", "required", "$schema",
+];
+
+fn is_reserved_parameter_name(name: &str) -> bool {
+    RESERVED_PARAMETER_NAMES.contains(&name)
+}
+
+/// For `#[param(schema_as = "S")]` on a parameter of type `T`, emit a probe that resolves to
+/// a deprecated method (and so a compiler warning at the attribute) unless `T: From<S>`.
+/// A missing `From` is only a warning because `#[serde(transparent)]` also makes `T`
+/// deserialize from `S`.
+fn schema_as_check(param: &ParameterMeta) -> TokenStream2 {
+    let Some((schema_ty, literal)) = &param.schema_as else {
+        return TokenStream2::new();
+    };
+    let (value_ty, _) = extract_schema_type(&param.ty);
+    let note = format!(
+        "schema_as: `{}` does not implement `From<{}>`; make sure it deserializes from the schema type, e.g. with #[serde(transparent)]",
+        quote! { #value_ty },
+        literal.value()
+    );
+
+    quote::quote_spanned! {literal.span()=>
+        #[allow(dead_code)]
+        const _: () = {
+            struct SchemaAsProbe<T, S>(::core::marker::PhantomData<(T, S)>);
+
+            trait ConvertsFromSchema {
+                fn check(&self) {}
+            }
+            impl<T: ::core::convert::From<S>, S> ConvertsFromSchema for SchemaAsProbe<T, S> {}
+
+            trait MissingFromSchema {
+                #[deprecated(note = #note)]
+                fn check(&self) {}
+            }
+            impl<T, S> MissingFromSchema for &SchemaAsProbe<T, S> {}
+
+            fn probe() {
+                (&SchemaAsProbe::<#value_ty, #schema_ty>(::core::marker::PhantomData)).check();
+            }
+        };
+    }
+}
+
 fn extract_schema_type(ty: &Type) -> (Type, bool) {
     if let Type::Path(TypePath { path, .. }) = ty {
         if let Some(segment) = path.segments.last() {
@@ -237,10 +517,19 @@ fn parse_result_type(ty: &Type) -> Option<(Type, Type)> {
 }
 
 fn expand_kernel_function(args: MetaList, function: &mut ItemFn) -> Result<TokenStream2, Error> {
+    if let Some(FnArg::Receiver(receiver)) = function.sig.inputs.first() {
+        return Err(Error::new_spanned(
+            receiver,
+            "kernel_function on a method needs #[kernel_module] on the enclosing impl block",
+        ));
+    }
+
     let original_ident = function.sig.ident.clone();
     let KernelMeta {
         kernel_name,
         description,
+        openapi,
+        deprecated,
     } = parse_kernel_meta(args, &mut function.attrs, &original_ident)?;
 
     let params = parse_parameters(&mut function.sig.inputs)?;
@@ -260,6 +549,7 @@ fn expand_kernel_function(args: MetaList, function: &mut ItemFn) -> Result<Token
 
     for param in &params {
         let ident = &param.ident;
+        let json_name = &param.json_name;
         assignment_idents.push(ident.clone());
 
         let field_ty = &param.ty;
@@ -272,11 +562,13 @@ fn expand_kernel_function(args: MetaList, function: &mut ItemFn) -> Result<Token
             .unwrap_or_else(TokenStream2::new);
 
         let mut parameter_expr = quote! {
-            ::denkwerk::functions::FunctionParameter::new(stringify!(#ident), ::denkwerk::functions::json_schema_for::<#schema_ty>())
+            ::denkwerk::functions::FunctionParameter::new(#json_name, ::denkwerk::functions::json_schema_for::<#schema_ty>())
                 #description_expr
         };
 
-        let mut field_attrs = Vec::new();
+        helper_items.push(schema_as_check(param));
+
+        let mut field_attrs = vec![quote! { #[serde(rename = #json_name)] }];
 
         if param.optional || param.default.is_some() {
             parameter_expr = quote! { #parameter_expr.optional() };
@@ -343,18 +635,42 @@ fn expand_kernel_function(args: MetaList, function: &mut ItemFn) -> Result<Token
 
     let invoke_body = build_invoke_body(&return_kind, call_expr, &kernel_name);
 
+    let deprecated_attr = deprecated_attr(&deprecated);
+    let (openapi_method, openapi_export) = if openapi {
+        let openapi_ident = format_ident!("{}_openapi", export_ident);
+        let return_schema = return_schema_expr(&function.sig.output);
+        (
+            quote! {
+                fn openapi_operation(&self) -> Option<::serde_json::Value> {
+                    Some(#openapi_ident())
+                }
+            },
+            quote! {
+                pub fn #openapi_ident() -> ::serde_json::Value {
+                    ::denkwerk::functions::openapi_operation(&#definition_ident(), #return_schema)
+                }
+            },
+        )
+    } else {
+        (TokenStream2::new(), TokenStream2::new())
+    };
+
     let expansion = quote! {
         #args_struct
         #(#helper_items)*
 
+        #deprecated_attr
         pub struct #wrapper_ident;
 
+        #[allow(deprecated)]
         #[::async_trait::async_trait]
         impl ::denkwerk::functions::KernelFunction for #wrapper_ident {
             fn definition(&self) -> ::denkwerk::FunctionDefinition {
                 #definition_ident()
             }
 
+            #openapi_method
+
             async fn invoke(&self, arguments: &::serde_json::Value) -> Result<::serde_json::Value, ::denkwerk::LLMError> {
                 let args: #args_struct_ident = ::serde_json::from_value(arguments.clone())
                     .map_err(|error| ::denkwerk::LLMError::InvalidFunctionArguments(error.to_string()))?;
@@ -363,14 +679,30 @@ fn expand_kernel_function(args: MetaList, function: &mut ItemFn) -> Result<Token
             }
         }
 
+        #deprecated_attr
+        #[allow(deprecated)]
         pub fn #export_ident() -> ::denkwerk::DynKernelFunction {
             ::std::sync::Arc::new(#wrapper_ident)
         }
+
+        #openapi_export
     };
 
     Ok(expansion)
 }
 
+/// JSON Schema expression for what the kernel function returns: the `Ok` type of a `Result`,
+/// the plain return type, or `null` for unit.
+fn return_schema_expr(output: &ReturnType) -> TokenStream2 {
+    match output {
+        ReturnType::Default => quote! { ::serde_json::json!({ "type": "null" }) },
+        ReturnType::Type(_, ty) => {
+            let ty = parse_result_type(ty).map(|(ok, _)| ok).unwrap_or_else(|| (**ty).clone());
+            quote! { ::denkwerk::functions::json_schema_for::<#ty>() }
+        }
+    }
+}
+
 fn build_invoke_body(return_kind: &ReturnKind, call: TokenStream2, kernel_name: &str) -> TokenStream2 {
     match return_kind {
         ReturnKind::Unit => {
@@ -403,6 +735,19 @@ fn expand_kernel_module(item_impl: &mut ItemImpl) -> Result<TokenStream2, Error>
     let mut expansions = Vec::new();
     let mut register_statements = Vec::new();
     let self_ty = &*item_impl.self_ty;
+    let generics = item_impl.generics.clone();
+    let target = match &item_impl.trait_ {
+        Some((_, trait_path, _)) => {
+            if !generics.params.is_empty() {
+                return Err(Error::new_spanned(
+                    &generics,
+                    "kernel_module does not support generic trait impls",
+                ));
+            }
+            MethodTarget::Trait(trait_path)
+        }
+        None => MethodTarget::Inherent(self_ty),
+    };
 
     for item in item_impl.items.iter_mut() {
         let ImplItem::Fn(method) = item else { continue };
@@ -418,10 +763,15 @@ fn expand_kernel_module(item_impl: &mut ItemImpl) -> Result<TokenStream2, Error>
         let Some(index) = kernel_attr_index else { continue };
 
         let attr = method.attrs.remove(index);
-        let parsed = attr.parse_args_with(Punctuated::<Meta, syn::Token![,]>::parse_terminated)?;
-        let args: MetaList = parsed.into_iter().collect();
+        let args: MetaList = match &attr.meta {
+            Meta::Path(_) => Vec::new(),
+            _ => attr
+                .parse_args_with(Punctuated::<Meta, syn::Token![,]>::parse_terminated)?
+                .into_iter()
+                .collect(),
+        };
 
-        let expansion = expand_kernel_method(args, method, self_ty)?;
+        let expansion = expand_kernel_method(args, method, &target, &generics)?;
         expansions.push(expansion.tokens);
         register_statements.push(expansion.register_stmt);
     }
@@ -430,15 +780,31 @@ fn expand_kernel_module(item_impl: &mut ItemImpl) -> Result<TokenStream2, Error>
         return Ok(TokenStream2::new());
     }
 
+    // Trait impls get one `{method}_kernel_from_{trait}` constructor per method instead, since
+    // an inherent `kernel_functions` would collide across several trait impls on one type.
+    if let MethodTarget::Trait(_) = target {
+        return Ok(quote! {
+            #(#expansions)*
+            #(#register_statements)*
+        });
+    }
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
     let register_impl = quote! {
-        impl #self_ty {
-            pub fn kernel_functions(self: ::std::sync::Arc<Self>) -> Vec<::denkwerk::DynKernelFunction> {
+        impl #impl_generics #self_ty #where_clause {
+            pub fn kernel_functions(self: ::std::sync::Arc<Self>) -> Vec<::denkwerk::DynKernelFunction>
+            where
+                Self: Send + Sync + 'static,
+            {
                 let mut functions = Vec::new();
                 #(#register_statements)*
                 functions
             }
 
-            pub fn register_kernel_functions(self: ::std::sync::Arc<Self>, registry: &mut ::denkwerk::FunctionRegistry) {
+            pub fn register_kernel_functions(self: ::std::sync::Arc<Self>, registry: &mut ::denkwerk::FunctionRegistry)
+            where
+                Self: Send + Sync + 'static,
+            {
                 for function in self.kernel_functions() {
                     registry.register(function);
                 }
@@ -452,6 +818,30 @@ fn expand_kernel_module(item_impl: &mut ItemImpl) -> Result<TokenStream2, Error>
     })
 }
 
+/// The impl block a kernel method lives in.
+enum MethodTarget<'a> {
+    /// `impl Type`: wrappers hold `Arc<Type>`.
+    Inherent(&'a Type),
+    /// `impl Trait for Type`: wrappers hold `Arc<dyn Trait + Send + Sync>`.
+    Trait(&'a syn::Path),
+}
+
+impl MethodTarget<'_> {
+    fn token(&self) -> String {
+        match self {
+            MethodTarget::Inherent(ty) => type_token(ty),
+            MethodTarget::Trait(path) => path_token(path),
+        }
+    }
+
+    fn instance_ty(&self) -> TokenStream2 {
+        match self {
+            MethodTarget::Inherent(ty) => quote! { #ty },
+            MethodTarget::Trait(path) => quote! { dyn #path + Send + Sync },
+        }
+    }
+}
+
 struct MethodExpansion {
     tokens: TokenStream2,
     register_stmt: TokenStream2,
@@ -460,12 +850,15 @@ struct MethodExpansion {
 fn expand_kernel_method(
     args: MetaList,
     method: &mut ImplItemFn,
-    self_ty: &Type,
+    target: &MethodTarget,
+    generics: &syn::Generics,
 ) -> Result<MethodExpansion, Error> {
     let method_ident = method.sig.ident.clone();
     let KernelMeta {
         kernel_name,
         description,
+        openapi,
+        deprecated,
     } = parse_kernel_meta(args, &mut method.attrs, &method_ident)?;
 
     let has_self = method
@@ -483,17 +876,17 @@ fn expand_kernel_method(
 
     let args_struct_ident = format_ident!(
         "__{}_{}_Args",
-        type_token(self_ty),
+        target.token(),
         method_ident.to_string().to_uppercase()
     );
     let wrapper_ident = format_ident!(
         "{}{}KernelFunction",
-        type_token(self_ty),
+        target.token(),
         method_ident.to_string().to_uppercase()
     );
     let definition_ident = format_ident!(
         "__{}_{}_definition",
-        type_token(self_ty),
+        target.token(),
         method_ident.to_string().to_uppercase()
     );
 
@@ -504,6 +897,7 @@ fn expand_kernel_method(
 
     for param in &params {
         let ident = &param.ident;
+        let json_name = &param.json_name;
         assignment_idents.push(ident.clone());
         let field_ty = &param.ty;
         let schema_ty = &param.schema_ty;
@@ -514,11 +908,13 @@ fn expand_kernel_method(
             .unwrap_or_else(TokenStream2::new);
 
         let mut parameter_expr = quote! {
-            ::denkwerk::functions::FunctionParameter::new(stringify!(#ident), ::denkwerk::functions::json_schema_for::<#schema_ty>())
+            ::denkwerk::functions::FunctionParameter::new(#json_name, ::denkwerk::functions::json_schema_for::<#schema_ty>())
                 #description_expr
         };
 
-        let mut field_attrs = Vec::new();
+        helper_items.push(schema_as_check(param));
+
+        let mut field_attrs = vec![quote! { #[serde(rename = #json_name)] }];
 
         if param.optional || param.default.is_some() {
             parameter_expr = quote! { #parameter_expr.optional() };
@@ -527,7 +923,7 @@ fn expand_kernel_method(
         if let Some(default_expr) = &param.default {
             let helper_ident = format_ident!(
                 "__{}_{}_{}_default",
-                type_token(self_ty),
+                target.token(),
                 method_ident.to_string().to_uppercase(),
                 ident.to_string().to_uppercase()
             );
@@ -586,20 +982,44 @@ fn expand_kernel_method(
 
     let invoke_body = build_invoke_body(&return_kind, call_expr, &kernel_name);
 
+    let openapi_method = if openapi {
+        let return_schema = return_schema_expr(&method.sig.output);
+        quote! {
+            fn openapi_operation(&self) -> Option<::serde_json::Value> {
+                Some(::denkwerk::functions::openapi_operation(&#definition_ident(), #return_schema))
+            }
+        }
+    } else {
+        TokenStream2::new()
+    };
+
+    let instance_ty = target.instance_ty();
+    // Generic impls carry their parameters and bounds over to the wrapper; the wrapper impl
+    // additionally needs the instance to be shareable across tasks.
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut wrapper_where = generics.clone();
+    wrapper_where
+        .make_where_clause()
+        .predicates
+        .push(syn::parse_quote! { #instance_ty: Send + Sync + 'static });
+    let wrapper_where_clause = &wrapper_where.where_clause;
     let tokens = quote! {
         #args_struct
         #(#helper_items)*
 
-        struct #wrapper_ident {
-            instance: ::std::sync::Arc<#self_ty>,
+        struct #wrapper_ident #impl_generics #where_clause {
+            instance: ::std::sync::Arc<#instance_ty>,
         }
 
+        #[allow(deprecated)]
         #[::async_trait::async_trait]
-        impl ::denkwerk::functions::KernelFunction for #wrapper_ident {
+        impl #impl_generics ::denkwerk::functions::KernelFunction for #wrapper_ident #ty_generics #wrapper_where_clause {
             fn definition(&self) -> ::denkwerk::FunctionDefinition {
                 #definition_ident()
             }
 
+            #openapi_method
+
             async fn invoke(&self, arguments: &::serde_json::Value) -> Result<::serde_json::Value, ::denkwerk::LLMError> {
                 let args: #args_struct_ident = ::serde_json::from_value(arguments.clone())
                     .map_err(|error| ::denkwerk::LLMError::InvalidFunctionArguments(error.to_string()))?;
@@ -609,10 +1029,23 @@ fn expand_kernel_method(
         }
     };
 
-    let register_stmt = quote! {
-        {
-            let function: ::denkwerk::DynKernelFunction = ::std::sync::Arc::new(#wrapper_ident { instance: ::std::sync::Arc::clone(&self) });
-            functions.push(function);
+    let register_stmt = match target {
+        MethodTarget::Inherent(_) => quote! {
+            {
+                let function: ::denkwerk::DynKernelFunction = ::std::sync::Arc::new(#wrapper_ident { instance: ::std::sync::Arc::clone(&self) });
+                functions.push(function);
+            }
+        },
+        MethodTarget::Trait(path) => {
+            let trait_ident = &path.segments.last().expect("trait paths are never empty").ident;
+            let constructor_ident = format_ident!("{}_kernel_from_{}", method_ident, trait_ident.to_string().to_snake_case());
+            let deprecated_attr = deprecated_attr(&deprecated);
+            quote! {
+                #deprecated_attr
+                pub fn #constructor_ident(instance: ::std::sync::Arc<#instance_ty>) -> ::denkwerk::DynKernelFunction {
+                    ::std::sync::Arc::new(#wrapper_ident { instance })
+                }
+            }
         }
     };
 
@@ -620,7 +1053,157 @@ fn expand_kernel_method(
 }
 
 fn type_token(ty: &Type) -> String {
+    if let Type::Path(TypePath { path, .. }) = ty {
+        return path_token(path);
+    }
+
     let mut text = quote! { #ty }.to_string();
     text.retain(|c| c.is_alphanumeric() || c == '_');
     text
 }
+
+/// Join the segment idents of `path` with `_`, dropping generics, so `repo::DataFetcher<T>`
+/// becomes `repo_DataFetcher` and stays apart from `cache::DataFetcher`.
+fn path_token(path: &syn::Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: TokenStream2) -> String {
+        expand_shared_state_keys(input).unwrap().to_string()
+    }
+
+    #[test]
+    fn shared_state_keys_generate_constants_and_accessors() {
+        let output = expand(quote! {
+            "user_profile" => UserProfile: MyStruct,
+            "retry_count" => RetryCount: u32,
+        });
+
+        assert!(output.contains("pub const USER_PROFILE_KEY : & str = \"user_profile\""));
+        assert!(output.contains("pub const RETRY_COUNT_KEY : & str = \"retry_count\""));
+        assert!(output.contains("fn get_user_profile"));
+        assert!(output.contains("fn set_retry_count"));
+        assert!(output.contains("type Value = u32"));
+    }
+
+    #[test]
+    fn shared_state_keys_reject_unconvertible_keys() {
+        for input in [
+            quote! { "" => Empty: u32 },
+            quote! { "9lives" => Lives: u32 },
+            quote! { "user profile!" => Profile: u32 },
+        ] {
+            let error = expand_shared_state_keys(input).unwrap_err();
+            assert!(error.to_string().contains("valid identifier"), "{error}");
+        }
+    }
+
+    fn expand_function(function: TokenStream2) -> Result<String, Error> {
+        let mut function: ItemFn = syn::parse2(function).unwrap();
+        expand_kernel_function(Vec::new(), &mut function).map(|tokens| tokens.to_string())
+    }
+
+    #[test]
+    fn kernel_function_rejects_reserved_parameter_names() {
+        for function in [
+            quote! { fn foo(r#type: String) -> String { r#type } },
+            quote! { fn foo(properties: String) -> String { properties } },
+            quote! { fn foo(#[param(name = "$schema")] schema: String) -> String { schema } },
+        ] {
+            let error = expand_function(function).unwrap_err();
+            assert_eq!(error.to_string(), RESERVED_PARAMETER_MESSAGE);
+        }
+    }
+
+    #[test]
+    fn kernel_function_param_name_overrides_json_key() {
+        let output = expand_function(quote! {
+            fn foo(#[param(name = "kind")] r#type: String) -> String { r#type }
+        })
+        .unwrap();
+        assert!(output.contains("FunctionParameter :: new (\"kind\""));
+        assert!(output.contains("# [serde (rename = \"kind\")] pub r#type : String"));
+    }
+
+    #[test]
+    fn kernel_function_json_name_renames_field() {
+        let output = expand_function(quote! {
+            fn weather(#[param(json_name = "targetCity")] target_city: String) -> String { target_city }
+        })
+        .unwrap();
+        assert!(output.contains("FunctionParameter :: new (\"targetCity\""));
+        assert!(output.contains("# [serde (rename = \"targetCity\")] pub target_city : String"));
+        assert!(output.contains("let __WEATHER_Args { target_city } = args"));
+    }
+
+    #[test]
+    fn kernel_function_schema_as_replaces_schema_type() {
+        let output = expand_function(quote! {
+            fn send(#[param(schema_as = "String")] to: Option<Email>) {}
+        })
+        .unwrap();
+        assert!(output.contains("json_schema_for :: < String > ()"));
+        assert!(output.contains("pub to : Option < Email >"));
+        assert!(output.contains("SchemaAsProbe :: < Email , String >"));
+    }
+
+    #[test]
+    fn kernel_function_forwards_deprecation() {
+        let output = expand_function(quote! {
+            fn foo(value: String) -> String { value }
+        })
+        .unwrap();
+        assert!(!output.contains("deprecated (note"));
+
+        let mut function: ItemFn = syn::parse2(quote! {
+            fn foo(value: String) -> String { value }
+        })
+        .unwrap();
+        let args = vec![syn::parse_quote!(deprecated = "Use foo_v2 instead")];
+        let output = expand_kernel_function(args, &mut function).unwrap().to_string();
+        assert!(output.contains(
+            "# [deprecated (note = \"Use foo_v2 instead\")] pub struct FOOKernelFunction"
+        ));
+        assert!(output.contains("# [deprecated (note = \"Use foo_v2 instead\")] # [allow (deprecated)] pub fn foo_kernel"));
+        assert!(output.contains("(DEPRECATED: Use foo_v2 instead)"));
+    }
+
+    #[test]
+    fn kernel_function_inherits_deprecated_attribute() {
+        let output = expand_function(quote! {
+            #[deprecated(since = "0.2", note = "Use bar instead")]
+            fn foo(value: String) -> String { value }
+        })
+        .unwrap();
+        assert!(output.contains("# [deprecated (note = \"Use bar instead\")] pub struct FOOKernelFunction"));
+    }
+
+    #[test]
+    fn type_tokens_keep_the_path_and_drop_generics() {
+        let token = |ty: Type| type_token(&ty);
+        assert_eq!(token(syn::parse_quote!(DataFetcher<T>)), "DataFetcher");
+        assert_eq!(token(syn::parse_quote!(repo::DataFetcher<T>)), "repo_DataFetcher");
+        assert_ne!(
+            token(syn::parse_quote!(a::DataFetcher)),
+            token(syn::parse_quote!(b::DataFetcher<T>))
+        );
+    }
+
+    #[test]
+    fn shared_state_keys_reject_duplicates() {
+        let error = expand_shared_state_keys(quote! {
+            "plan" => Plan: String,
+            "plan" => OtherPlan: String,
+        })
+        .unwrap_err();
+        assert!(error.to_string().contains("duplicate"));
+    }
+}
@@ -0,0 +1,89 @@
+use denkwerk::{kernel_function, FunctionCall, FunctionRegistry};
+use serde_json::json;
+
+#[kernel_function(name = "describe")]
+fn describe(#[param(name = "kind", description = "Kind of item")] r#type: String, count: u32) -> String {
+    format!("{count} x {type}", type = r#type)
+}
+
+#[tokio::test]
+async fn renamed_parameter_uses_json_key() {
+    let mut registry = FunctionRegistry::new();
+    registry.register(describe_kernel());
+
+    let definition = registry.get("describe").unwrap().definition();
+    assert!(definition.parameters.properties.contains_key("kind"));
+    assert!(!definition.parameters.properties.contains_key("type"));
+
+    let result = registry
+        .invoke(&FunctionCall::new("describe", json!({ "kind": "widget", "count": 2 })))
+        .await
+        .unwrap();
+    assert_eq!(result, json!("2 x widget"));
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Email(String);
+
+impl From<String> for Email {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+#[kernel_function(name = "email_domain")]
+fn email_domain(#[param(schema_as = "String")] email: Email) -> String {
+    email.0.split('@').nth(1).unwrap_or_default().to_string()
+}
+
+#[tokio::test]
+async fn newtype_parameter_uses_schema_type() {
+    let mut registry = FunctionRegistry::new();
+    registry.register(email_domain_kernel());
+
+    let definition = registry.get("email_domain").unwrap().definition();
+    assert_eq!(definition.parameters.properties["email"]["type"], json!("string"));
+
+    let result = registry
+        .invoke(&FunctionCall::new("email_domain", json!({ "email": "ada@example.com" })))
+        .await
+        .unwrap();
+    assert_eq!(result, json!("example.com"));
+}
+
+#[kernel_function(name = "legacy_sum", description = "Sum two numbers", deprecated = "Use add_numbers instead")]
+fn legacy_sum(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+#[test]
+#[allow(deprecated)]
+fn deprecation_reaches_function_description() {
+    let definition = legacy_sum_kernel().definition();
+    assert_eq!(
+        definition.description.as_deref(),
+        Some("Sum two numbers (DEPRECATED: Use add_numbers instead)")
+    );
+}
+
+#[kernel_function(name = "weather")]
+fn weather(#[param(json_name = "targetCity", description = "City to look up")] target_city: String) -> String {
+    format!("Sunny in {target_city}")
+}
+
+#[tokio::test]
+async fn json_name_maps_camel_case_arguments() {
+    let mut registry = FunctionRegistry::new();
+    registry.register(weather_kernel());
+
+    let definition = registry.get("weather").unwrap().definition();
+    assert_eq!(definition.parameters.required, vec!["targetCity".to_string()]);
+    assert!(definition.parameters.properties.contains_key("targetCity"));
+
+    let result = registry
+        .invoke(&FunctionCall::new("weather", json!({ "targetCity": "Paris" })))
+        .await
+        .unwrap();
+    assert_eq!(result, json!("Sunny in Paris"));
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use denkwerk::{kernel_module, FunctionCall, FunctionRegistry};
+use serde_json::json;
+
+pub trait Repository {
+    fn find(&self, id: &str) -> Option<String>;
+}
+
+#[derive(Default)]
+pub struct InMemoryRepository {
+    records: HashMap<String, String>,
+}
+
+impl Repository for InMemoryRepository {
+    fn find(&self, id: &str) -> Option<String> {
+        self.records.get(id).cloned()
+    }
+}
+
+pub struct DataFetcher<T> {
+    repository: T,
+}
+
+#[kernel_module]
+impl<T: Repository> DataFetcher<T>
+where
+    T: Send + Sync,
+{
+    #[kernel_function(name = "fetch_record", description = "Look up a record by id")]
+    async fn fetch(&self, id: String) -> Option<String> {
+        self.repository.find(&id)
+    }
+}
+
+#[tokio::test]
+async fn generic_module_functions_are_callable() {
+    let mut repository = InMemoryRepository::default();
+    repository.records.insert("42".to_string(), "answer".to_string());
+    let fetcher = Arc::new(DataFetcher { repository });
+
+    let mut registry = FunctionRegistry::new();
+    fetcher.register_kernel_functions(&mut registry);
+
+    let found = registry
+        .invoke(&FunctionCall::new("fetch_record", json!({ "id": "42" })))
+        .await
+        .unwrap();
+    assert_eq!(found, json!("answer"));
+
+    let missing = registry
+        .invoke(&FunctionCall::new("fetch_record", json!({ "id": "7" })))
+        .await
+        .unwrap();
+    assert_eq!(missing, json!(null));
+}
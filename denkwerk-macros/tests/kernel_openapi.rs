@@ -0,0 +1,49 @@
+use denkwerk::{kernel_function, FunctionRegistry};
+use serde_json::json;
+
+#[kernel_function(name = "add_numbers", description = "Add two numbers", openapi = true)]
+fn add_numbers(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+#[kernel_function(name = "shout", openapi = true)]
+fn shout(text: String) -> Result<String, std::fmt::Error> {
+    Ok(text.to_uppercase())
+}
+
+#[kernel_function(name = "private_helper")]
+fn private_helper(value: i64) -> i64 {
+    value
+}
+
+#[test]
+fn operation_fragment_describes_request_and_response() {
+    let operation = add_numbers_kernel_openapi();
+    assert_eq!(operation["operationId"], json!("add_numbers"));
+    assert_eq!(operation["description"], json!("Add two numbers"));
+    let request = &operation["requestBody"]["content"]["application/json"]["schema"];
+    assert_eq!(request["required"], json!(["a", "b"]));
+    let response = &operation["responses"]["200"]["content"]["application/json"]["schema"];
+    assert_eq!(response["type"], json!("integer"));
+
+    let response = &shout_kernel_openapi()["responses"]["200"]["content"]["application/json"]["schema"];
+    assert_eq!(response["type"], json!("string"));
+}
+
+#[test]
+fn registry_builds_document_from_openapi_functions() {
+    let mut registry = FunctionRegistry::new();
+    registry.register(add_numbers_kernel());
+    registry.register(shout_kernel());
+    registry.register(private_helper_kernel());
+
+    let spec = registry.openapi_spec("Agent tools", "1.2.0");
+    assert_eq!(spec["openapi"], json!("3.1.0"));
+    assert_eq!(spec["info"]["title"], json!("Agent tools"));
+    assert_eq!(spec["info"]["version"], json!("1.2.0"));
+
+    let paths = spec["paths"].as_object().unwrap();
+    assert_eq!(paths.len(), 2);
+    assert_eq!(paths["/functions/add_numbers"]["post"]["operationId"], json!("add_numbers"));
+    assert_eq!(paths["/functions/shout"]["post"]["operationId"], json!("shout"));
+}
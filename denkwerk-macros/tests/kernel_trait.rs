@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use denkwerk::{kernel_module, FunctionCall, FunctionRegistry};
+use serde_json::json;
+
+#[async_trait]
+pub trait Calculator: Send + Sync {
+    async fn add(&self, a: i64, b: i64) -> i64;
+    async fn negate(&self, value: i64) -> i64;
+}
+
+struct OffsetCalculator {
+    offset: i64,
+}
+
+#[kernel_module]
+#[async_trait]
+impl Calculator for OffsetCalculator {
+    #[kernel_function(name = "calculator_add", description = "Add two numbers")]
+    async fn add(&self, a: i64, b: i64) -> i64 {
+        a + b + self.offset
+    }
+
+    #[kernel_function]
+    async fn negate(&self, value: i64) -> i64 {
+        -value
+    }
+}
+
+#[tokio::test]
+async fn trait_methods_are_callable_through_registry() {
+    let calculator: Arc<dyn Calculator + Send + Sync> = Arc::new(OffsetCalculator { offset: 1 });
+
+    let mut registry = FunctionRegistry::new();
+    registry.register(add_kernel_from_calculator(Arc::clone(&calculator)));
+    registry.register(negate_kernel_from_calculator(calculator));
+
+    let definition = registry.get("calculator_add").unwrap().definition();
+    assert_eq!(definition.description.as_deref(), Some("Add two numbers"));
+    assert_eq!(definition.parameters.required, vec!["a".to_string(), "b".to_string()]);
+
+    let sum = registry
+        .invoke(&FunctionCall::new("calculator_add", json!({ "a": 2, "b": 3 })))
+        .await
+        .unwrap();
+    assert_eq!(sum, json!(6));
+
+    let negated = registry
+        .invoke(&FunctionCall::new("negate", json!({ "value": 4 })))
+        .await
+        .unwrap();
+    assert_eq!(negated, json!(-4));
+}
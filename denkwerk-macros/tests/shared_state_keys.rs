@@ -0,0 +1,91 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use denkwerk::{shared_state_keys, InMemorySharedStateStore, LLMError, SharedStateContext, StateKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    name: String,
+}
+
+shared_state_keys! {
+    "user_profile" => UserProfile: Profile,
+    "retry-count" => RetryCount: u32,
+}
+
+/// Store that records the keys it is asked for and delegates to the in-memory store.
+#[derive(Default)]
+struct RecordingStore {
+    inner: InMemorySharedStateStore,
+    calls: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl SharedStateContext for RecordingStore {
+    async fn queue_state_update(
+        &self,
+        id: String,
+        value: Value,
+        scope: Option<String>,
+        ttl: Option<Duration>,
+    ) -> Result<(), LLMError> {
+        self.calls.lock().unwrap().push(format!("write {id} {scope:?}"));
+        self.inner.queue_state_update(id, value, scope, ttl).await
+    }
+
+    async fn read_state(&self, id: &str, scope: Option<&str>) -> Result<Option<Value>, LLMError> {
+        self.calls.lock().unwrap().push(format!("read {id} {scope:?}"));
+        self.inner.read_state(id, scope).await
+    }
+
+    async fn list_state_ids(&self, scope: Option<&str>) -> Result<Vec<String>, LLMError> {
+        self.inner.list_state_ids(scope).await
+    }
+
+    async fn remove_state(&self, id: &str, scope: Option<&str>) -> Result<bool, LLMError> {
+        self.inner.remove_state(id, scope).await
+    }
+
+    async fn clear_states(&self, scope: Option<&str>) -> Result<usize, LLMError> {
+        self.inner.clear_states(scope).await
+    }
+}
+
+#[test]
+fn constants_and_markers_use_key_strings() {
+    assert_eq!(USER_PROFILE_KEY, "user_profile");
+    assert_eq!(RETRY_COUNT_KEY, "retry-count");
+    assert_eq!(<UserProfile as StateKey>::KEY, "user_profile");
+    assert_eq!(<RetryCount as StateKey>::KEY, "retry-count");
+}
+
+#[tokio::test]
+async fn accessors_round_trip_through_store() {
+    let store = RecordingStore::default();
+    let profile = Profile { name: "Ada".to_string() };
+
+    assert_eq!(get_user_profile(&store, Some("session")).await.unwrap(), None);
+    set_user_profile(&store, Some("session"), profile.clone()).await.unwrap();
+    set_retry_count(&store, None, 3).await.unwrap();
+
+    assert_eq!(get_user_profile(&store, Some("session")).await.unwrap(), Some(profile));
+    assert_eq!(get_retry_count(&store, None).await.unwrap(), Some(3));
+    assert_eq!(
+        store.inner.read_state("user_profile", Some("session")).await.unwrap(),
+        Some(json!({ "name": "Ada" }))
+    );
+
+    assert_eq!(
+        *store.calls.lock().unwrap(),
+        vec![
+            "read user_profile Some(\"session\")",
+            "write user_profile Some(\"session\")",
+            "write retry-count None",
+            "read user_profile Some(\"session\")",
+            "read retry-count None",
+        ]
+    );
+}
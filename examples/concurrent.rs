@@ -43,6 +43,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             ConcurrentEvent::Message { agent, output } => {
                 println!("Message from {agent}: {output}");
             }
+            ConcurrentEvent::Aggregated { output } => {
+                println!("Aggregated: {output}");
+            }
             ConcurrentEvent::Completed { agent, output } => {
                 if let Some(text) = output {
                     println!("{agent} completed with: {text}");
@@ -47,6 +47,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             GroupChatEvent::UserMessage { message } => {
                 println!("[User]: {message}\n");
             }
+            GroupChatEvent::MessageModerated { agent, reason } => {
+                println!("[Moderator removed a message from {agent}] {reason}\n");
+            }
             GroupChatEvent::Terminated { reason } => {
                 println!("[Manager terminated] {reason}\n");
             }
@@ -53,6 +53,9 @@ fn print_run(run: &GroupChatRun) {
                 println!("{agent} complete: {}", message.clone().unwrap_or_default());
             }
             GroupChatEvent::UserMessage { message } => println!("[User]: {message}"),
+            GroupChatEvent::MessageModerated { agent, reason } => {
+                println!("[Moderator removed a message from {agent}] {reason}");
+            }
             GroupChatEvent::Terminated { reason } => println!("[Manager terminated] {reason}"),
         }
     }
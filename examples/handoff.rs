@@ -92,6 +92,9 @@ fn render_turn(turn: &HandoffTurn) {
                 }
                 println!("{}", format!("🔄 [handoff] {} -> {}", colorize_agent(from), colorize_agent(to)).yellow().bold());
             }
+            HandoffEvent::ToolCall { agent, function } => {
+                println!("{}", format!("🔧 {} called {function}", colorize_agent(agent)).dimmed());
+            }
             HandoffEvent::Completed { agent } => {
                 println!("{}", format!("[completed by {}]", colorize_agent(agent)).green().bold());
             }
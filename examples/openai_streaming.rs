@@ -30,6 +30,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             StreamEvent::ToolCallDelta { index, arguments } => {
                 eprintln!("[tool #{index}] {arguments}");
             }
+            StreamEvent::ToolCallComplete { call } => {
+                eprintln!("[tool call] {} {}", call.function.name, call.function.arguments);
+            }
             StreamEvent::Completed(response) => {
                 println!("\n---\nFull response: {}", response.message.text().unwrap_or_default());
                 if let Some(reasoning) = response.reasoning {
@@ -44,6 +47,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     );
                 }
             }
+            _ => {}
         }
     }
 
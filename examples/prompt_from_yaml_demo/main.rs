@@ -81,6 +81,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 FlowNodeKind::Parallel { .. } => "parallel",
                 FlowNodeKind::Loop { .. } => "loop",
                 FlowNodeKind::Subflow { .. } => "subflow",
+                FlowNodeKind::Condition { .. } => "condition",
+                FlowNodeKind::Transform { .. } => "transform",
+                FlowNodeKind::HumanInLoop { .. } => "human_in_loop",
+                FlowNodeKind::MapEach { .. } => "map_each",
+                FlowNodeKind::RetryOnFail { .. } => "retry_on_fail",
             };
             *counts.entry(label).or_default() += 1;
         }
@@ -97,7 +102,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     for (idx, step) in plan.iter().enumerate() {
         match step {
             ExecutionStep::Agent(agent) => println!("  {idx}: agent -> {}", agent.name()),
-            ExecutionStep::Tool { tool, arguments } => {
+            ExecutionStep::Tool { tool, arguments, .. } => {
                 if let Some(args) = arguments {
                     println!("  {idx}: tool -> {tool} (args from YAML)");
                     println!("        args: {args}");
@@ -116,6 +121,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         SequentialEvent::Step { agent, output } => {
             println!("\n[{agent}] produced:\n{output}\n");
         }
+        SequentialEvent::StepSkipped { agent, reason } => {
+            println!("[{agent}] skipped: {reason}\n");
+        }
         SequentialEvent::Completed { agent, output } => {
             if let Some(text) = output {
                 println!("[{agent}] completed with:\n{text}\n");
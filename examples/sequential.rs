@@ -43,6 +43,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             SequentialEvent::Step { agent, output } => {
                 println!("{agent}:\n{output}\n");
             }
+            SequentialEvent::StepSkipped { agent, reason } => {
+                println!("-- {agent} skipped: {reason} --\n");
+            }
             SequentialEvent::Completed { agent, output } => {
                 if let Some(result) = output {
                     println!("-- {agent} finalized the copy --\n{result}\n");
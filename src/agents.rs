@@ -1,17 +1,21 @@
 use std::{
+    borrow::Cow,
     fmt,
     fs,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
 use handlebars::Handlebars;
-use serde::Serialize;
+use jsonschema::{Draft, JSONSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::{
     functions::{FunctionRegistry, ToolChoice},
+    history::{ChatHistory, ChatHistoryCompressor},
     skills::SkillStub,
-    types::{ChatMessage, CompletionRequest},
+    types::{ChatMessage, CompletionRequest, CorrelationId, MessageRole},
     flows::handoffflow::{AgentAction, AgentTurn, ActionEnvelope},
     LLMError, LLMProvider,
 };
@@ -40,14 +44,52 @@ pub enum AgentError {
     InvalidManagerDecision(String),
     #[error("provider call timed out")]
     ProviderTimeout,
+    #[error("run aborted")]
+    Aborted,
     #[error(transparent)]
     Provider(#[from] LLMError),
 }
 
+/// Observes an agent's turns. Every method defaults to doing nothing.
+pub trait AgentHook: Send + Sync {
+    fn on_turn_start(&self, _agent: &str, _model: &str) {}
+
+    fn on_tool_result(&self, _agent: &str, _tool: &str, _result: Result<&Value, &LLMError>) {}
+
+    /// Called with the final response text, or the error that ended the turn.
+    fn on_turn_end(&self, _agent: &str, _result: Result<&str, &LLMError>) {}
+
+    /// Called after a successful turn; `compressed` tells whether the history was
+    /// summarized first (see [`Agent::with_auto_summarize`]).
+    fn after_execute(&self, _agent: &str, _compressed: bool) {}
+}
+
+/// Something an agent declares it can do, used to route handoffs that name a skill
+/// instead of an agent (see [`crate::flows::handoffflow::CapabilityRouter`]).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentCapability {
+    WebSearch,
+    CodeExecution,
+    FileAccess,
+    DataAnalysis,
+    ImageGeneration,
+    Custom(String),
+}
+
+pub type SharedHistoryCompressor = Arc<Mutex<dyn ChatHistoryCompressor + Send>>;
+
+#[derive(Clone)]
+struct AutoSummarize {
+    every_n_turns: usize,
+    compressor: SharedHistoryCompressor,
+}
+
 #[derive(Clone)]
 pub struct Agent {
     name: String,
     description: Option<String>,
+    capabilities: Vec<AgentCapability>,
     instructions: String,
     functions: Option<Arc<FunctionRegistry>>,
     tool_ids: Vec<String>,
@@ -58,6 +100,13 @@ pub struct Agent {
     tool_choice: Option<ToolChoice>,
     provider_override: Option<Arc<dyn LLMProvider>>,
     model_override: Option<String>,
+    output_schema: Option<OutputSchema>,
+    schema_fixer_prompt: Option<String>,
+    max_schema_retries: usize,
+    hooks: Vec<Arc<dyn AgentHook>>,
+    auto_summarize: Option<AutoSummarize>,
+    #[cfg(feature = "telemetry")]
+    otel_context: Option<opentelemetry::Context>,
 }
 
 impl fmt::Debug for Agent {
@@ -78,6 +127,7 @@ impl Agent {
         Self {
             name: name.into(),
             description: None,
+            capabilities: Vec::new(),
             instructions: instructions.into(),
             functions: None,
             tool_ids: Vec::new(),
@@ -88,6 +138,13 @@ impl Agent {
             tool_choice: None,
             provider_override: None,
             model_override: None,
+            output_schema: None,
+            schema_fixer_prompt: None,
+            max_schema_retries: 0,
+            hooks: Vec::new(),
+            auto_summarize: None,
+            #[cfg(feature = "telemetry")]
+            otel_context: None,
         }
     }
 
@@ -122,6 +179,15 @@ impl Agent {
         self
     }
 
+    pub fn capabilities(&self) -> &[AgentCapability] {
+        &self.capabilities
+    }
+
+    pub fn with_capabilities(mut self, capabilities: Vec<AgentCapability>) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
     pub fn with_tool_ids(mut self, tool_ids: Vec<String>) -> Self {
         self.tool_ids = tool_ids;
         self
@@ -192,6 +258,62 @@ impl Agent {
         self
     }
 
+    /// Request structured output matching `schema` on every completion and reject final
+    /// responses that don't validate against it. Requires a provider with
+    /// `supports_structured_output`.
+    pub fn with_output_schema_enforced(self, schema: Value) -> Self {
+        self.with_output_schema(OutputSchema::new(schema))
+    }
+
+    /// Like [`Agent::with_output_schema_enforced`], with examples quoted in validation errors.
+    pub fn with_output_schema(mut self, schema: OutputSchema) -> Self {
+        self.output_schema = Some(schema);
+        self
+    }
+
+    /// Ask the model to fix a response that fails schema validation up to `n` times
+    /// before the turn fails with [`LLMError::SchemaViolation`]. Defaults to 0.
+    pub fn with_max_schema_retries(mut self, n: usize) -> Self {
+        self.max_schema_retries = n;
+        self
+    }
+
+    /// Message sent with each schema retry instead of the default one. `{errors}` and
+    /// `{original_response}` are replaced with the validation errors and the rejected
+    /// response; anything else, such as few-shot examples, is sent as written.
+    pub fn with_output_schema_fixer(mut self, fixer_prompt: String) -> Self {
+        self.schema_fixer_prompt = Some(fixer_prompt);
+        self
+    }
+
+    /// Parent `agent.execute` spans under `cx` when the agent runs outside any active span,
+    /// e.g. on a task spawned away from the request that started the trace.
+    #[cfg(feature = "telemetry")]
+    pub fn with_otel_context(mut self, cx: opentelemetry::Context) -> Self {
+        self.otel_context = Some(cx);
+        self
+    }
+
+    pub fn with_hook(mut self, hook: Arc<dyn AgentHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Compress the history with `summarizer` before a call once it holds more than
+    /// `every_n_turns` non-system messages. In a handoff session the stored transcript is
+    /// compressed too; other orchestrators only send the compressed copy.
+    pub fn with_auto_summarize(mut self, every_n_turns: usize, summarizer: SharedHistoryCompressor) -> Self {
+        self.auto_summarize = Some(AutoSummarize {
+            every_n_turns,
+            compressor: summarizer,
+        });
+        self
+    }
+
+    pub fn output_schema(&self) -> Option<&Value> {
+        self.output_schema.as_ref().map(OutputSchema::schema)
+    }
+
     pub fn temperature(&self) -> Option<f32> {
         self.temperature
     }
@@ -228,6 +350,101 @@ impl Agent {
         history: &[ChatMessage],
         additional_functions: Option<&FunctionRegistry>,
         tool_choice: Option<ToolChoice>,
+    ) -> Result<AgentTurn, LLMError> {
+        let mut working = Cow::Borrowed(history);
+        let compressed = self.needs_summary(history) && self.summarize_history(working.to_mut());
+        self.execute_summarized(provider, model, &working, additional_functions, tool_choice, compressed)
+            .await
+    }
+
+    /// Whether `history` is past the auto-summarize threshold.
+    fn needs_summary(&self, history: &[ChatMessage]) -> bool {
+        self.auto_summarize.as_ref().is_some_and(|auto| {
+            history.iter().filter(|message| message.role != MessageRole::System).count() > auto.every_n_turns
+        })
+    }
+
+    /// Compress `history` in place if it is past the auto-summarize threshold.
+    pub(crate) fn summarize_history(&self, history: &mut Vec<ChatMessage>) -> bool {
+        let Some(auto) = self.auto_summarize.as_ref().filter(|_| self.needs_summary(history)) else {
+            return false;
+        };
+        let mut chat = ChatHistory::with_messages(std::mem::take(history));
+        let compressed = auto.compressor.lock().unwrap().compress(&mut chat);
+        *history = chat.into_messages();
+        compressed
+    }
+
+    /// [`Agent::execute_with_tools`] for a history the caller already ran through
+    /// [`Agent::summarize_history`].
+    pub(crate) async fn execute_summarized(
+        &self,
+        provider: &(dyn LLMProvider + Send + Sync),
+        model: &str,
+        history: &[ChatMessage],
+        additional_functions: Option<&FunctionRegistry>,
+        tool_choice: Option<ToolChoice>,
+        compressed: bool,
+    ) -> Result<AgentTurn, LLMError> {
+        let target_model = self.model_override.as_deref().unwrap_or(model);
+        for hook in &self.hooks {
+            hook.on_turn_start(&self.name, target_model);
+        }
+        let turn = async {
+            let result = self
+                .run_turn(provider, model, history, additional_functions, tool_choice)
+                .await
+                .map(|turn| AgentTurn { compressed, ..turn });
+            for hook in &self.hooks {
+                hook.on_turn_end(&self.name, result.as_ref().map(|turn| turn.raw_content.as_str()));
+                if let Ok(turn) = &result {
+                    hook.after_execute(&self.name, turn.compressed);
+                }
+            }
+            #[cfg(feature = "tracing")]
+            if let Err(error) = &result {
+                tracing::warn!(agent.name = %self.name, %error, "agent turn failed");
+            }
+            result
+        };
+
+        #[cfg(feature = "tracing")]
+        let turn = tracing::Instrument::instrument(
+            turn,
+            tracing::info_span!(
+                "agent.execute",
+                agent.name = %self.name,
+                agent.model = %target_model,
+                round = tracing::field::Empty,
+            ),
+        );
+
+        #[cfg(feature = "telemetry")]
+        {
+            use opentelemetry::KeyValue;
+
+            let cx = crate::telemetry::start_span(
+                "agent.execute",
+                &crate::telemetry::agent_parent(self.otel_context.as_ref()),
+                vec![
+                    KeyValue::new("agent.name", self.name.clone()),
+                    KeyValue::new("agent.model", target_model.to_string()),
+                ],
+            );
+            crate::telemetry::traced(cx, turn, crate::telemetry::record_agent_turn).await
+        }
+
+        #[cfg(not(feature = "telemetry"))]
+        turn.await
+    }
+
+    async fn run_turn(
+        &self,
+        provider: &(dyn LLMProvider + Send + Sync),
+        model: &str,
+        history: &[ChatMessage],
+        additional_functions: Option<&FunctionRegistry>,
+        tool_choice: Option<ToolChoice>,
     ) -> Result<AgentTurn, LLMError> {
         let mut messages = Vec::with_capacity(history.len() + 1);
         messages.push(ChatMessage::system(self.instructions.clone()));
@@ -240,18 +457,8 @@ impl Agent {
 
         let target_model = self.model_override.as_deref().unwrap_or(model);
 
-        let mut request = CompletionRequest::new(target_model.to_string(), messages.clone());
-
-        if let Some(max_tokens) = self.max_tokens {
-            request = request.with_max_tokens(max_tokens);
-        }
-
-        if let Some(temperature) = self.temperature {
-            request = request.with_temperature(temperature);
-        }
-
-        if let Some(top_p) = self.top_p {
-            request = request.with_top_p(top_p);
+        if self.output_schema.is_some() && !active_provider.capabilities().supports_structured_output {
+            return Err(LLMError::Unsupported("structured_output"));
         }
 
         // Merge internal/extra functions with agent functions when both exist.
@@ -275,14 +482,31 @@ impl Agent {
                 _ => None,
             });
 
-        if let Some(functions) = functions_to_use {
-            request = request.with_function_registry(functions);
-        }
-
         let effective_tool_choice = tool_choice.or_else(|| self.tool_choice.clone());
-        if let Some(tool_choice) = &effective_tool_choice {
-            request = request.with_tool_choice(tool_choice.clone());
-        }
+        let build_request = |messages: &[ChatMessage]| {
+            let mut request = CompletionRequest::new(target_model.to_string(), messages.to_vec());
+            request.correlation_id = CorrelationId::current();
+            if let Some(schema) = &self.output_schema {
+                request = request.with_response_schema(schema.schema.clone(), true);
+            }
+            if let Some(max_tokens) = self.max_tokens {
+                request = request.with_max_tokens(max_tokens);
+            }
+            if let Some(temperature) = self.temperature {
+                request = request.with_temperature(temperature);
+            }
+            if let Some(top_p) = self.top_p {
+                request = request.with_top_p(top_p);
+            }
+            if let Some(functions) = functions_to_use {
+                request = request.with_function_registry(functions);
+            }
+            if let Some(tool_choice) = &effective_tool_choice {
+                request = request.with_tool_choice(tool_choice.clone());
+            }
+            request
+        };
+        let mut request = build_request(&messages);
 
         let max_tool_rounds = 4;
         let mut all_tool_calls = Vec::new();
@@ -291,6 +515,8 @@ impl Agent {
         let mut action_override: Option<AgentAction> = None;
 
         for round in 0..max_tool_rounds {
+            #[cfg(feature = "tracing")]
+            tracing::Span::current().record("round", round);
             let response = active_provider.complete(request).await?;
             let mut assistant_msg = response.message.clone();
             last_usage = response.usage;
@@ -315,7 +541,22 @@ impl Agent {
 
             for call in assistant_msg.tool_calls {
                 let id = call.id.clone().unwrap_or_else(|| format!("tool_call_{round}_x"));
-                let tool_result = functions.invoke(&call.function).await;
+                #[cfg(feature = "telemetry")]
+                let tool_result = crate::telemetry::traced(
+                    crate::telemetry::start_span(
+                        "tool.invoke",
+                        &opentelemetry::Context::current(),
+                        vec![opentelemetry::KeyValue::new("tool.name", call.function.name.clone())],
+                    ),
+                    functions.invoke_as(&call.function, &self.name),
+                    |_, _| {},
+                )
+                .await;
+                #[cfg(not(feature = "telemetry"))]
+                let tool_result = functions.invoke_as(&call.function, &self.name).await;
+                for hook in &self.hooks {
+                    hook.on_tool_result(&self.name, &call.function.name, tool_result.as_ref());
+                }
                 let tool_value = match tool_result {
                     Ok(value) => value,
                     Err(err) => serde_json::json!({ "error": err.to_string() }),
@@ -336,24 +577,27 @@ impl Agent {
                 break;
             }
 
-            let mut next_request = CompletionRequest::new(target_model.to_string(), messages.clone());
-            if let Some(max_tokens) = self.max_tokens {
-                next_request = next_request.with_max_tokens(max_tokens);
-            }
-            if let Some(temperature) = self.temperature {
-                next_request = next_request.with_temperature(temperature);
-            }
-            if let Some(top_p) = self.top_p {
-                next_request = next_request.with_top_p(top_p);
-            }
-            if let Some(functions) = functions_to_use {
-                next_request = next_request.with_function_registry(functions);
-            }
-            if let Some(tool_choice) = &effective_tool_choice {
-                next_request = next_request.with_tool_choice(tool_choice.clone());
-            }
+            request = build_request(&messages);
+        }
 
-            request = next_request;
+        if let (Some(schema), None) = (&self.output_schema, &action_override) {
+            let mut retries = 0;
+            while let Some(errors) = schema.violations(&last_content)? {
+                if retries == self.max_schema_retries {
+                    return Err(LLMError::SchemaViolation(errors));
+                }
+                retries += 1;
+
+                let template = self.schema_fixer_prompt.as_deref().unwrap_or(DEFAULT_SCHEMA_FIXER_PROMPT);
+                let fix = template
+                    .replace("{errors}", &errors)
+                    .replace("{original_response}", &last_content);
+                messages.push(ChatMessage::user(fix));
+                let response = active_provider.complete(build_request(&messages)).await?;
+                last_usage = response.usage;
+                last_content = response.message.text().unwrap_or_default().to_string();
+                messages.push(response.message);
+            }
         }
 
         let action = action_override.unwrap_or_else(|| AgentAction::from_response(&last_content));
@@ -363,6 +607,304 @@ impl Agent {
             tool_calls: all_tool_calls,
             usage: last_usage,
             raw_content: last_content,
+            compressed: false,
+        })
+    }
+}
+
+/// Sent back to the model when its response fails schema validation; `{errors}` and
+/// `{original_response}` are filled in.
+const DEFAULT_SCHEMA_FIXER_PROMPT: &str =
+    "Your previous response failed validation with errors: {errors}. Please correct it:\n{original_response}";
+
+/// JSON Schema an agent's responses must satisfy, see [`Agent::with_output_schema`].
+#[derive(Debug, Clone)]
+pub struct OutputSchema {
+    schema: Value,
+    examples: Vec<Value>,
+}
+
+impl OutputSchema {
+    pub fn new(schema: Value) -> Self {
+        Self {
+            schema,
+            examples: Vec::new(),
+        }
+    }
+
+    /// A valid response, quoted in validation errors so the model sees what is expected.
+    pub fn with_example(mut self, example: Value) -> Self {
+        self.examples.push(example);
+        self
+    }
+
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+
+    /// Why `content` does not satisfy the schema, or `None` if it does.
+    fn violations(&self, content: &str) -> Result<Option<String>, LLMError> {
+        let compiled = JSONSchema::options()
+            .with_draft(Draft::Draft7)
+            .compile(&self.schema)
+            .map_err(|err| LLMError::Provider(format!("invalid output schema: {err}")))?;
+
+        let mut details = match serde_json::from_str::<Value>(content.trim()) {
+            Err(err) => format!("response is not valid JSON: {err}"),
+            Ok(value) => match compiled.validate(&value) {
+                Ok(()) => return Ok(None),
+                Err(errors) => errors.map(|err| err.to_string()).collect::<Vec<_>>().join("; "),
+            },
+        };
+        for example in &self.examples {
+            details.push_str(&format!("; example of a valid response: {example}"));
+        }
+        Ok(Some(details))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{openai::{OpenAI, OpenAIConfig}, scripted::ScriptedProvider};
+    use wiremock::matchers::{body_partial_json, body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn city_schema() -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": { "city": { "type": "string" } },
+            "required": ["city"],
+            "additionalProperties": false
         })
     }
+
+    async fn mock_openai(content: &str) -> (MockServer, OpenAI) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": { "name": "response", "strict": true, "schema": city_schema() }
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": content },
+                    "finish_reason": "stop"
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider =
+            OpenAI::from_config(OpenAIConfig::new("test-key").with_base_url(server.uri())).unwrap();
+        (server, provider)
+    }
+
+    #[tokio::test]
+    async fn enforced_schema_is_sent_and_validated() {
+        let (_server, provider) = mock_openai(r#"{"city": "Berlin"}"#).await;
+        let agent = Agent::from_string("extractor", "Extract the city.")
+            .with_output_schema_enforced(city_schema());
+
+        let turn = agent
+            .execute(&provider, "gpt-4o-mini", &[ChatMessage::user("I live in Berlin")])
+            .await
+            .unwrap();
+        assert_eq!(turn.raw_content, r#"{"city": "Berlin"}"#);
+    }
+
+    #[tokio::test]
+    async fn responses_violating_the_schema_are_rejected() {
+        let (_server, provider) = mock_openai(r#"{"town": "Berlin"}"#).await;
+        let agent = Agent::from_string("extractor", "Extract the city.")
+            .with_output_schema_enforced(city_schema());
+
+        let err = agent
+            .execute(&provider, "gpt-4o-mini", &[ChatMessage::user("I live in Berlin")])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LLMError::SchemaViolation(_)), "{err}");
+    }
+
+    fn chat_completion(content: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": content },
+                "finish_reason": "stop"
+            }]
+        }))
+    }
+
+    #[tokio::test]
+    async fn schema_violations_are_retried_with_the_fixer_prompt() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_string_contains("Your previous response failed validation with errors"))
+            .and(body_string_contains("example of a valid response"))
+            .respond_with(chat_completion(r#"{"city": "Berlin"}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(chat_completion("city: Berlin"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let provider =
+            OpenAI::from_config(OpenAIConfig::new("test-key").with_base_url(server.uri())).unwrap();
+
+        let agent = Agent::from_string("extractor", "Extract the city.")
+            .with_output_schema(
+                OutputSchema::new(city_schema()).with_example(serde_json::json!({ "city": "Paris" })),
+            )
+            .with_max_schema_retries(1);
+        let turn = agent
+            .execute(&provider, "gpt-4o-mini", &[ChatMessage::user("I live in Berlin")])
+            .await
+            .unwrap();
+        assert_eq!(turn.raw_content, r#"{"city": "Berlin"}"#);
+    }
+
+    #[tokio::test]
+    async fn providers_without_structured_output_are_rejected() {
+        let provider = ScriptedProvider::new();
+        let agent = Agent::from_string("extractor", "Extract the city.")
+            .with_output_schema_enforced(city_schema());
+
+        let err = agent
+            .execute(&provider, "scripted", &[ChatMessage::user("Berlin")])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LLMError::Unsupported("structured_output")));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[crate::kernel_function(name = "lookup_weather", description = "Look up the weather")]
+    fn lookup_weather(city: String) -> Result<String, String> {
+        Ok(format!("sunny in {city}"))
+    }
+
+    #[cfg(feature = "tracing")]
+    async fn run_weather_turn() {
+        let mut registry = FunctionRegistry::new();
+        registry.register(lookup_weather_kernel());
+        let agent = Agent::from_string("Forecaster", "Answer weather questions.")
+            .with_function_registry(Arc::new(registry))
+            .with_hook(Arc::new(crate::metrics::TracingAgentHook));
+        let mut provider = ScriptedProvider::new();
+        provider.add_tool_call_response(
+            "lookup_weather",
+            serde_json::json!({ "city": "Berlin" }),
+            serde_json::json!("It is sunny."),
+        );
+
+        let turn = agent
+            .execute(&provider, "m", &[ChatMessage::user("Use lookup_weather for Berlin")])
+            .await
+            .unwrap();
+        assert_eq!(turn.raw_content, "It is sunny.");
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn tracing_hook_reports_turns_and_tool_calls() {
+        run_weather_turn().await;
+
+        assert!(logs_contain("agent turn started"));
+        assert!(logs_contain("agent.model=\"m\""));
+        assert!(logs_contain("tool call succeeded"));
+        assert!(logs_contain("tool.name=\"lookup_weather\""));
+        assert!(logs_contain("agent turn finished"));
+    }
+
+    /// Records how many messages each request carried.
+    struct CountingProvider {
+        inner: ScriptedProvider,
+        request_sizes: std::sync::Mutex<Vec<usize>>,
+    }
+
+    #[async_trait::async_trait]
+    impl LLMProvider for CountingProvider {
+        async fn complete(
+            &self,
+            request: CompletionRequest,
+        ) -> Result<crate::types::CompletionResponse, LLMError> {
+            self.request_sizes.lock().unwrap().push(request.messages.len());
+            self.inner.complete(request).await
+        }
+
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+    }
+
+    #[derive(Default)]
+    struct CompressionHook {
+        seen: std::sync::Mutex<Vec<bool>>,
+    }
+
+    impl AgentHook for CompressionHook {
+        fn after_execute(&self, _agent: &str, compressed: bool) {
+            self.seen.lock().unwrap().push(compressed);
+        }
+    }
+
+    #[tokio::test]
+    async fn auto_summarize_compresses_long_history() {
+        use crate::history::{ConciseSummarizer, FixedWindowCompressor};
+
+        let mut inner = ScriptedProvider::new();
+        inner.echo_user_messages();
+        let provider = CountingProvider {
+            inner,
+            request_sizes: std::sync::Mutex::new(Vec::new()),
+        };
+        let hook = Arc::new(CompressionHook::default());
+        let summarizer: SharedHistoryCompressor =
+            Arc::new(Mutex::new(FixedWindowCompressor::new(4, ConciseSummarizer::default())));
+        let agent = Agent::from_string("assistant", "Help out.")
+            .with_auto_summarize(4, summarizer)
+            .with_hook(hook.clone());
+
+        let mut history = Vec::new();
+        for i in 0..4 {
+            history.push(if i % 2 == 0 {
+                ChatMessage::user(format!("question {i}"))
+            } else {
+                ChatMessage::assistant(format!("answer {i}"))
+            });
+        }
+        let turn = agent.execute(&provider, "m", &history).await.unwrap();
+        assert!(!turn.compressed);
+
+        history.push(ChatMessage::user("question 4"));
+        let turn = agent.execute(&provider, "m", &history).await.unwrap();
+        assert!(turn.compressed);
+
+        let sizes = provider.request_sizes.lock().unwrap();
+        assert_eq!(sizes[0], 5);
+        assert!(sizes[1] < 6, "provider received {} messages", sizes[1]);
+        assert_eq!(*hook.seen.lock().unwrap(), [false, true]);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn agent_and_function_spans_are_recorded() {
+        run_weather_turn().await;
+
+        assert!(logs_contain("agent.execute{"));
+        assert!(logs_contain("round=1"));
+        assert!(logs_contain("function.invoke{function.name=lookup_weather}"));
+        assert!(logs_contain("tool call finished"));
+    }
 }
@@ -0,0 +1,240 @@
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use serde::Serialize;
+use tokio::sync::Semaphore;
+
+use super::{run_case, BenchCase, CaseRunResult};
+use crate::LLMProvider;
+
+/// Rounds of the completion -> tool loop each case gets unless it sets `max_rounds`.
+const DEFAULT_MAX_ROUNDS: usize = 8;
+
+/// Runs one benchmark suite against several providers and compares them side by side.
+pub struct BenchLeaderboard {
+    suite: Vec<BenchCase>,
+    entries: Vec<LeaderboardEntry>,
+    /// USD per prompt and completion token, keyed by entry name.
+    pricing: HashMap<String, (f64, f64)>,
+}
+
+struct LeaderboardEntry {
+    name: String,
+    provider: Arc<dyn LLMProvider>,
+    model: String,
+}
+
+/// One provider's results over the whole suite. A case the provider errored on counts as
+/// failed with a score of zero.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderRow {
+    pub name: String,
+    pub model: String,
+    pub pass_rate: f64,
+    pub avg_score: f64,
+    pub avg_latency_ms: f64,
+    /// Zero unless pricing was set with [`BenchLeaderboard::set_pricing`].
+    pub total_cost_usd: f64,
+}
+
+/// Rows come in the order the providers were added.
+#[derive(Debug, Clone, Serialize)]
+pub struct LeaderboardResult {
+    pub providers: Vec<ProviderRow>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaderboardMetric {
+    PassRate,
+    AvgScore,
+    AvgLatency,
+    TotalCost,
+}
+
+impl BenchLeaderboard {
+    pub fn new(suite: Vec<BenchCase>) -> Self {
+        Self {
+            suite,
+            entries: Vec::new(),
+            pricing: HashMap::new(),
+        }
+    }
+
+    pub fn add_provider(&mut self, name: String, provider: Arc<dyn LLMProvider>, model: String) -> &mut Self {
+        self.entries.push(LeaderboardEntry { name, provider, model });
+        self
+    }
+
+    /// Price the tokens the provider added as `name` reports, in USD per token.
+    pub fn set_pricing(&mut self, name: &str, input_per_token: f64, output_per_token: f64) -> &mut Self {
+        self.pricing
+            .insert(name.to_string(), (input_per_token, output_per_token));
+        self
+    }
+
+    /// Run every case against every provider, with at most `concurrency` cases in flight
+    /// across all providers.
+    pub async fn run_all(&self, system_prompt: &str, concurrency: usize) -> LeaderboardResult {
+        let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+        let rows = self.entries.iter().map(|entry| {
+            let permits = Arc::clone(&permits);
+            async move {
+                let runs = self.suite.iter().map(|case| {
+                    let permits = Arc::clone(&permits);
+                    async move {
+                        let _permit = permits.acquire().await.expect("semaphore is never closed");
+                        let started = Instant::now();
+                        let result = run_case(
+                            entry.provider.as_ref(),
+                            &entry.model,
+                            system_prompt,
+                            case,
+                            DEFAULT_MAX_ROUNDS,
+                        )
+                        .await;
+                        (result.ok(), started.elapsed().as_secs_f64() * 1000.0)
+                    }
+                });
+                let results = futures_util::future::join_all(runs).await;
+                self.row(entry, &results)
+            }
+        });
+
+        LeaderboardResult {
+            providers: futures_util::future::join_all(rows).await,
+        }
+    }
+
+    fn row(&self, entry: &LeaderboardEntry, results: &[(Option<CaseRunResult>, f64)]) -> ProviderRow {
+        let (input_cost, output_cost) = self.pricing.get(&entry.name).copied().unwrap_or_default();
+        let cases = results.len().max(1) as f64;
+
+        let mut passed = 0usize;
+        let mut score = 0.0;
+        let mut latency = 0.0;
+        let mut cost = 0.0;
+        for (result, latency_ms) in results {
+            latency += latency_ms;
+            let Some(result) = result else {
+                continue;
+            };
+            passed += usize::from(result.pass);
+            score += result.scores.total;
+            if let Some(usage) = &result.usage {
+                cost += usage.prompt_tokens as f64 * input_cost + usage.completion_tokens as f64 * output_cost;
+            }
+        }
+
+        ProviderRow {
+            name: entry.name.clone(),
+            model: entry.model.clone(),
+            pass_rate: passed as f64 / cases,
+            avg_score: score / cases,
+            avg_latency_ms: latency / cases,
+            total_cost_usd: cost,
+        }
+    }
+}
+
+impl LeaderboardResult {
+    /// Rows best first: highest pass rate or score, lowest latency or cost. Ties keep the
+    /// order the providers were added in.
+    pub fn rank_by(&self, metric: LeaderboardMetric) -> Vec<ProviderRow> {
+        let mut rows = self.providers.clone();
+        rows.sort_by(|a, b| match metric {
+            LeaderboardMetric::PassRate => b.pass_rate.total_cmp(&a.pass_rate),
+            LeaderboardMetric::AvgScore => b.avg_score.total_cmp(&a.avg_score),
+            LeaderboardMetric::AvgLatency => a.avg_latency_ms.total_cmp(&b.avg_latency_ms),
+            LeaderboardMetric::TotalCost => a.total_cost_usd.total_cmp(&b.total_cost_usd),
+        });
+        rows
+    }
+
+    pub fn to_markdown_table(&self) -> String {
+        let mut table = String::from(
+            "| Provider | Model | Pass rate | Avg score | Avg latency (ms) | Cost (USD) |\n\
+             | --- | --- | ---: | ---: | ---: | ---: |\n",
+        );
+        for row in &self.providers {
+            table.push_str(&format!(
+                "| {} | {} | {:.1}% | {:.3} | {:.0} | {:.4} |\n",
+                row.name,
+                row.model,
+                row.pass_rate * 100.0,
+                row.avg_score,
+                row.avg_latency_ms,
+                row.total_cost_usd
+            ));
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::OracleSpec;
+    use crate::providers::scripted::ScriptedProvider;
+    use crate::ScriptedTurn;
+
+    fn case(id: &str, prompt: &str, expected: &str) -> BenchCase {
+        let oracle: OracleSpec = serde_json::from_value(serde_json::json!({
+            "final_contains": [expected],
+        }))
+        .unwrap();
+        BenchCase {
+            id: id.to_string(),
+            description: None,
+            prompt: prompt.to_string(),
+            system_prompt: None,
+            max_rounds: None,
+            tools: Vec::new(),
+            oracle,
+        }
+    }
+
+    fn provider(answers: &[(&str, &str)]) -> Arc<dyn LLMProvider> {
+        let mut provider = ScriptedProvider::new();
+        for (prompt, answer) in answers {
+            provider.add_response_for_message(
+                prompt,
+                ScriptedTurn {
+                    agent: "any".to_string(),
+                    response: answer.to_string(),
+                    latency_ms: None,
+                },
+            );
+        }
+        Arc::new(provider)
+    }
+
+    #[tokio::test]
+    async fn ranks_providers_by_pass_rate() {
+        let suite = vec![case("capital", "Capital of France?", "Paris"), case("sum", "What is 2 + 2?", "4")];
+        let mut leaderboard = BenchLeaderboard::new(suite);
+        leaderboard
+            .add_provider(
+                "weak".to_string(),
+                provider(&[("France", "Lyon"), ("2 + 2", "4")]),
+                "weak-model".to_string(),
+            )
+            .add_provider(
+                "strong".to_string(),
+                provider(&[("France", "Paris"), ("2 + 2", "4")]),
+                "strong-model".to_string(),
+            );
+
+        let result = leaderboard.run_all("Answer briefly.", 2).await;
+        assert_eq!(result.providers[0].name, "weak");
+        assert_eq!(result.providers[0].pass_rate, 0.5);
+
+        let ranked = result.rank_by(LeaderboardMetric::PassRate);
+        assert_eq!(ranked[0].name, "strong");
+        assert_eq!(ranked[0].pass_rate, 1.0);
+        assert_eq!(ranked[1].name, "weak");
+
+        let table = result.to_markdown_table();
+        assert!(table.starts_with("| Provider | Model |"));
+        assert!(table.contains("| strong | strong-model | 100.0% |"));
+        assert!(table.contains("| weak | weak-model | 50.0% |"));
+    }
+}
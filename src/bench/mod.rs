@@ -9,9 +9,13 @@ use jsonschema::{Draft, JSONSchema};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+mod leaderboard;
+
+pub use leaderboard::{BenchLeaderboard, LeaderboardMetric, LeaderboardResult, ProviderRow};
+
 use crate::{
     functions::KernelFunction,
-    types::ChatMessage,
+    types::{ChatMessage, TokenUsage},
     CompletionRequest, FunctionDefinition, FunctionRegistry, LLMError, LLMProvider, ToolChoice,
 };
 
@@ -56,7 +60,7 @@ pub enum ToolResultSpec {
     Err { message: String },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct OracleSpec {
     #[serde(default)]
     pub allowed_tools: Option<Vec<String>>,
@@ -126,6 +130,9 @@ pub struct CaseRunResult {
     pub failures: Vec<String>,
     pub final_answer: String,
     pub tool_calls: Vec<ToolCallRecord>,
+    /// Tokens summed over every completion of the case, if the provider reported any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -232,6 +239,7 @@ pub async fn run_case(
     let mut tool_calls: Vec<ToolCallRecord> = Vec::new();
     let mut final_answer = String::new();
     let mut hit_max_calls = false;
+    let mut usage: Option<TokenUsage> = None;
 
     for round in 0..max_rounds {
         let request = CompletionRequest::new(model.to_string(), messages.clone())
@@ -239,6 +247,17 @@ pub async fn run_case(
             .with_tool_choice(ToolChoice::auto());
 
         let response = provider.complete(request).await?;
+        if let Some(round_usage) = &response.usage {
+            let total = usage.get_or_insert(TokenUsage {
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                total_tokens: 0,
+                cached_tokens: None,
+            });
+            total.prompt_tokens += round_usage.prompt_tokens;
+            total.completion_tokens += round_usage.completion_tokens;
+            total.total_tokens += round_usage.total_tokens;
+        }
         let mut assistant_msg = response.message.clone();
 
         for (i, call) in assistant_msg.tool_calls.iter_mut().enumerate() {
@@ -292,7 +311,9 @@ pub async fn run_case(
         }
     }
 
-    Ok(score_case(case, final_answer, tool_calls))
+    let mut result = score_case(case, final_answer, tool_calls);
+    result.usage = usage;
+    Ok(result)
 }
 
 fn build_schema_validators(
@@ -473,6 +494,7 @@ fn score_case(case: &BenchCase, final_answer: String, tool_calls: Vec<ToolCallRe
         failures,
         final_answer,
         tool_calls,
+        usage: None,
     }
 }
 
@@ -655,14 +677,14 @@ fn call_matches_expected(
 }
 
 #[derive(Clone)]
-struct StubKernelFunction {
+pub(crate) struct StubKernelFunction {
     definition: FunctionDefinition,
     fixtures: Vec<ToolFixture>,
     default: Option<ToolResultSpec>,
 }
 
 impl StubKernelFunction {
-    fn new(tool: ToolSpec) -> Self {
+    pub(crate) fn new(tool: ToolSpec) -> Self {
         let mut def = FunctionDefinition::new(tool.name.clone());
         if let Some(desc) = tool.description.clone() {
             def = def.with_description(desc);
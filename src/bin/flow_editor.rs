@@ -1,4 +1,5 @@
 use std::fs;
+use std::time::{Duration, Instant};
 use denkwerk::{
     DecisionStrategy, FlowDocument, FlowEdge, FlowNode, FlowNodeBase, FlowNodeKind, NodeLayout,
     NodeOutput,
@@ -8,13 +9,16 @@ use iced::widget::{
     Canvas, Column, Row,
 };
 use iced::{
-    alignment, executor, mouse, Application, Color, Command, Element, Length, Point, Rectangle,
-    Renderer, Settings, Subscription, Theme, Vector,
+    alignment, executor, keyboard, mouse, theme, Application, Color, Command, Element, Length, Point,
+    Rectangle, Renderer, Settings, Subscription, Theme, Vector,
 };
 
 const NODE_WIDTH: f32 = 160.0;
 const NODE_HEIGHT: f32 = 90.0;
 const GRID: f32 = 20.0;
+const HISTORY_LIMIT: usize = 50;
+const MAX_OPEN_TABS: usize = 8;
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
 
 fn main() -> iced::Result {
     if !prepare_display_env() {
@@ -225,12 +229,80 @@ struct FlowEditor {
     edge_target: Option<String>,
     drag: Option<DragState>,
     new_node_counter: u32,
+    history: CommandHistory,
+    export_path: String,
+    /// Flow ids shown in the tab bar; `selected_tab` indexes into it.
+    open_tabs: Vec<String>,
+    selected_tab: usize,
+    /// Open flow ids from least to most recently used.
+    tab_recency: Vec<String>,
+    last_flow_click: Option<(String, Instant)>,
+    node_filter: String,
+    /// Flow coordinate shown at the middle of the canvas; `None` keeps the origin top-left.
+    canvas_center: Option<Point>,
 }
 
 #[derive(Debug, Clone)]
 struct DragState {
     node_id: String,
     offset: Vector,
+    /// Document before the drag began, recorded as a single undo step when it ends.
+    document_before: FlowDocument,
+}
+
+/// Undo/redo snapshots of the whole document.
+#[derive(Debug, Default)]
+struct CommandHistory {
+    past: Vec<FlowDocument>,
+    future: Vec<FlowDocument>,
+    /// Text input changed by the latest undo step; further keystrokes in it join that step.
+    editing: Option<TextEdit>,
+}
+
+/// A text input of the properties panel, identified by the message that edits it and the
+/// flow and node it belongs to.
+#[derive(Debug, Clone, PartialEq)]
+struct TextEdit {
+    field: (std::mem::Discriminant<Message>, Option<usize>),
+    flow: usize,
+    node: Option<String>,
+}
+
+impl CommandHistory {
+    fn record(&mut self, document: FlowDocument) {
+        if self.past.len() == HISTORY_LIMIT {
+            self.past.remove(0);
+        }
+        self.past.push(document);
+        self.future.clear();
+        self.editing = None;
+    }
+
+    fn undo(&mut self, current: &mut FlowDocument) -> bool {
+        let Some(previous) = self.past.pop() else {
+            return false;
+        };
+        self.future.push(std::mem::replace(current, previous));
+        self.editing = None;
+        true
+    }
+
+    fn redo(&mut self, current: &mut FlowDocument) -> bool {
+        let Some(next) = self.future.pop() else {
+            return false;
+        };
+        self.past.push(std::mem::replace(current, next));
+        self.editing = None;
+        true
+    }
+
+    fn can_undo(&self) -> bool {
+        !self.past.is_empty()
+    }
+
+    fn can_redo(&self) -> bool {
+        !self.future.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -244,6 +316,11 @@ enum NodeTemplate {
     Parallel,
     Loop,
     Subflow,
+    Condition,
+    Transform,
+    HumanInLoop,
+    MapEach,
+    RetryOnFail,
 }
 
 #[derive(Debug, Clone)]
@@ -252,7 +329,14 @@ enum Message {
     LoadFile,
     SaveFile,
     NewDocument,
-    SelectFlow(String),
+    FlowListClicked(String),
+    OpenFlowInTab(String),
+    OpenTab(String),
+    CloseTab(usize),
+    SwitchTab(usize),
+    FilterNodes(String),
+    FocusNode(String),
+    PanCanvasTo(Point),
     AddNode(NodeTemplate),
     DeleteSelectedNode,
     StartDrag { node_id: String, offset: Vector },
@@ -269,6 +353,18 @@ enum Message {
     UpdateSubflowId(String),
     UpdateLoopCondition(String),
     UpdateLoopMax(String),
+    UpdateConditionExpression(String),
+    UpdateTrueBranch(String),
+    UpdateFalseBranch(String),
+    UpdateTransformFunction(String),
+    UpdateInputVar(String),
+    UpdateOutputVar(String),
+    UpdateHumanPrompt(String),
+    UpdateHumanTimeout(String),
+    UpdateMapConcurrency(String),
+    UpdateRetryTarget(String),
+    UpdateRetryMax(String),
+    UpdateRetryBackoff(String),
     SelectEdgeOutput(String),
     SelectEdgeTarget(String),
     AddEdge,
@@ -276,6 +372,78 @@ enum Message {
     UpdateOutputLabel(usize, String),
     UpdateOutputCondition(usize, String),
     RemoveOutput(usize),
+    Undo,
+    Redo,
+    DuplicateNode,
+    SelectAll,
+    Deselect,
+    AutoLayout,
+    ExportPathChanged(String),
+    ExportPng(String),
+    ExportSvg(String),
+}
+
+impl Message {
+    /// Whether a document change caused by this message becomes an undo step. Drags are
+    /// recorded once when they end; saving never changes the document.
+    fn records_history(&self) -> bool {
+        !matches!(
+            self,
+            Message::FilePathChanged(_)
+                | Message::SaveFile
+                | Message::FlowListClicked(_)
+                | Message::OpenFlowInTab(_)
+                | Message::OpenTab(_)
+                | Message::CloseTab(_)
+                | Message::SwitchTab(_)
+                | Message::FilterNodes(_)
+                | Message::FocusNode(_)
+                | Message::PanCanvasTo(_)
+                | Message::StartDrag { .. }
+                | Message::DragTo(_)
+                | Message::EndDrag
+                | Message::SelectEdgeOutput(_)
+                | Message::SelectEdgeTarget(_)
+                | Message::Undo
+                | Message::Redo
+                | Message::SelectAll
+                | Message::Deselect
+                | Message::ExportPathChanged(_)
+                | Message::ExportPng(_)
+                | Message::ExportSvg(_)
+        )
+    }
+
+    /// The text input this message edits, if any, with the output index for output rows.
+    fn text_field(&self) -> Option<(std::mem::Discriminant<Message>, Option<usize>)> {
+        match self {
+            Message::UpdateOutputLabel(index, _) | Message::UpdateOutputCondition(index, _) => {
+                Some((std::mem::discriminant(self), Some(*index)))
+            }
+            Message::UpdateNodeId(_)
+            | Message::UpdateNodeName(_)
+            | Message::UpdateNodeDescription(_)
+            | Message::UpdateAgentId(_)
+            | Message::UpdatePromptId(_)
+            | Message::UpdateTools(_)
+            | Message::UpdateSubflowId(_)
+            | Message::UpdateLoopCondition(_)
+            | Message::UpdateLoopMax(_)
+            | Message::UpdateConditionExpression(_)
+            | Message::UpdateTrueBranch(_)
+            | Message::UpdateFalseBranch(_)
+            | Message::UpdateTransformFunction(_)
+            | Message::UpdateInputVar(_)
+            | Message::UpdateOutputVar(_)
+            | Message::UpdateHumanPrompt(_)
+            | Message::UpdateHumanTimeout(_)
+            | Message::UpdateMapConcurrency(_)
+            | Message::UpdateRetryTarget(_)
+            | Message::UpdateRetryMax(_)
+            | Message::UpdateRetryBackoff(_) => Some((std::mem::discriminant(self), None)),
+            _ => None,
+        }
+    }
 }
 
 impl Application for FlowEditor {
@@ -298,6 +466,14 @@ impl Application for FlowEditor {
                 edge_target: None,
                 drag: None,
                 new_node_counter: 0,
+                history: CommandHistory::default(),
+                export_path: "flow.svg".to_string(),
+                open_tabs: vec!["main".to_string()],
+                selected_tab: 0,
+                tab_recency: vec!["main".to_string()],
+                last_flow_click: None,
+                node_filter: String::new(),
+                canvas_center: None,
             },
             Command::none(),
         )
@@ -308,10 +484,17 @@ impl Application for FlowEditor {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        Subscription::none()
+        keyboard::on_key_press(shortcut)
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
+        let before = message.records_history().then(|| self.document.clone());
+        let text_edit = message.text_field().map(|field| TextEdit {
+            field,
+            flow: self.selected_flow,
+            node: self.selected_node.clone(),
+        });
+
         match message {
             Message::FilePathChanged(path) => self.file_path = path,
             Message::LoadFile => self.load_file(),
@@ -321,24 +504,41 @@ impl Application for FlowEditor {
                 ensure_layouts(&mut self.document);
                 self.selected_flow = 0;
                 self.selected_node = None;
+                self.reset_tabs();
                 self.status = "New document created".to_string();
             }
-            Message::SelectFlow(flow_id) => {
-                if let Some(idx) = self
-                    .document
-                    .flows
-                    .iter()
-                    .position(|flow| flow.id == flow_id)
-                {
-                    self.selected_flow = idx;
-                    self.selected_node = None;
+            Message::FlowListClicked(flow_id) => {
+                let now = Instant::now();
+                let double_click = self.last_flow_click.as_ref().is_some_and(|(id, at)| {
+                    *id == flow_id && now.duration_since(*at) <= DOUBLE_CLICK
+                });
+                if double_click {
+                    self.last_flow_click = None;
+                    return self.update(Message::OpenFlowInTab(flow_id));
+                }
+                self.last_flow_click = Some((flow_id.clone(), now));
+                self.show_in_current_tab(flow_id);
+            }
+            Message::OpenFlowInTab(flow_id) | Message::OpenTab(flow_id) => self.open_tab(flow_id),
+            Message::CloseTab(index) => self.close_tab(index),
+            Message::SwitchTab(index) => self.switch_tab(index),
+            Message::FilterNodes(query) => self.node_filter = query,
+            Message::FocusNode(node_id) => {
+                if let Some(center) = node_center(self.flow(), &node_id) {
+                    self.selected_node = Some(node_id);
+                    return self.update(Message::PanCanvasTo(center));
                 }
             }
+            Message::PanCanvasTo(point) => self.canvas_center = Some(point),
             Message::AddNode(template) => self.add_node(template),
             Message::DeleteSelectedNode => self.delete_selected(),
             Message::StartDrag { node_id, offset } => {
                 self.selected_node = Some(node_id.clone());
-                self.drag = Some(DragState { node_id, offset });
+                self.drag = Some(DragState {
+                    node_id,
+                    offset,
+                    document_before: self.document.clone(),
+                });
             }
             Message::DragTo(point) => {
                 if let Some(drag) = &self.drag {
@@ -347,7 +547,13 @@ impl Application for FlowEditor {
                     self.move_node(&node_id, point - offset);
                 }
             }
-            Message::EndDrag => self.drag = None,
+            Message::EndDrag => {
+                if let Some(drag) = self.drag.take() {
+                    if drag.document_before != self.document {
+                        self.history.record(drag.document_before);
+                    }
+                }
+            }
             Message::UpdateNodeId(id) => self.update_node_id(id),
             Message::UpdateNodeName(name) => {
                 self.update_node_field(|base| base.name = some(name.clone()))
@@ -360,6 +566,7 @@ impl Application for FlowEditor {
                     match &mut node.kind {
                         FlowNodeKind::Agent { agent: a, .. } => *a = agent.clone(),
                         FlowNodeKind::Tool { tool, .. } => *tool = agent.clone(),
+                        FlowNodeKind::MapEach { agent: a, .. } => *a = agent.clone(),
                         _ => {}
                     }
                 }
@@ -411,6 +618,104 @@ impl Application for FlowEditor {
                     }
                 }
             }
+            Message::UpdateConditionExpression(value) => {
+                if let Some(node) = self.selected_node_mut() {
+                    if let FlowNodeKind::Condition { expression, .. } = &mut node.kind {
+                        *expression = value;
+                    }
+                }
+            }
+            Message::UpdateTrueBranch(value) => {
+                if let Some(node) = self.selected_node_mut() {
+                    if let FlowNodeKind::Condition { true_branch, .. } = &mut node.kind {
+                        *true_branch = value;
+                    }
+                }
+            }
+            Message::UpdateFalseBranch(value) => {
+                if let Some(node) = self.selected_node_mut() {
+                    if let FlowNodeKind::Condition { false_branch, .. } = &mut node.kind {
+                        *false_branch = value;
+                    }
+                }
+            }
+            Message::UpdateTransformFunction(value) => {
+                if let Some(node) = self.selected_node_mut() {
+                    if let FlowNodeKind::Transform { function, .. } = &mut node.kind {
+                        *function = value;
+                    }
+                }
+            }
+            Message::UpdateInputVar(value) => {
+                if let Some(node) = self.selected_node_mut() {
+                    match &mut node.kind {
+                        FlowNodeKind::Transform { input_var, .. }
+                        | FlowNodeKind::MapEach { input_var, .. } => *input_var = value,
+                        _ => {}
+                    }
+                }
+            }
+            Message::UpdateOutputVar(value) => {
+                if let Some(node) = self.selected_node_mut() {
+                    match &mut node.kind {
+                        FlowNodeKind::Transform { output_var, .. }
+                        | FlowNodeKind::MapEach { output_var, .. } => *output_var = value,
+                        _ => {}
+                    }
+                }
+            }
+            Message::UpdateMapConcurrency(value) => {
+                let parsed = if value.is_empty() { Ok(None) } else { value.parse::<usize>().map(Some) };
+                if let Ok(parsed) = parsed {
+                    if let Some(node) = self.selected_node_mut() {
+                        if let FlowNodeKind::MapEach { concurrency, .. } = &mut node.kind {
+                            *concurrency = parsed;
+                        }
+                    }
+                }
+            }
+            Message::UpdateHumanPrompt(value) => {
+                if let Some(node) = self.selected_node_mut() {
+                    if let FlowNodeKind::HumanInLoop { prompt, .. } = &mut node.kind {
+                        *prompt = value;
+                    }
+                }
+            }
+            Message::UpdateHumanTimeout(value) => {
+                let parsed = if value.is_empty() { Ok(None) } else { value.parse::<u64>().map(Some) };
+                if let Ok(parsed) = parsed {
+                    if let Some(node) = self.selected_node_mut() {
+                        if let FlowNodeKind::HumanInLoop { timeout_ms, .. } = &mut node.kind {
+                            *timeout_ms = parsed;
+                        }
+                    }
+                }
+            }
+            Message::UpdateRetryTarget(value) => {
+                if let Some(node) = self.selected_node_mut() {
+                    if let FlowNodeKind::RetryOnFail { target_node, .. } = &mut node.kind {
+                        *target_node = value;
+                    }
+                }
+            }
+            Message::UpdateRetryMax(value) => {
+                if let Ok(parsed) = value.parse::<u32>() {
+                    if let Some(node) = self.selected_node_mut() {
+                        if let FlowNodeKind::RetryOnFail { max_retries, .. } = &mut node.kind {
+                            *max_retries = parsed;
+                        }
+                    }
+                }
+            }
+            Message::UpdateRetryBackoff(value) => {
+                if let Ok(parsed) = value.parse::<u64>() {
+                    if let Some(node) = self.selected_node_mut() {
+                        if let FlowNodeKind::RetryOnFail { backoff_ms, .. } = &mut node.kind {
+                            *backoff_ms = parsed;
+                        }
+                    }
+                }
+            }
             Message::SelectEdgeOutput(label) => self.edge_output = Some(label),
             Message::SelectEdgeTarget(target) => self.edge_target = Some(target),
             Message::AddEdge => self.add_edge(),
@@ -424,17 +729,62 @@ impl Application for FlowEditor {
                 })
             }
             Message::RemoveOutput(index) => self.remove_output(index),
+            Message::Undo => {
+                if self.history.undo(&mut self.document) {
+                    self.restore_selection();
+                    self.status = "Undone".to_string();
+                }
+            }
+            Message::Redo => {
+                if self.history.redo(&mut self.document) {
+                    self.restore_selection();
+                    self.status = "Redone".to_string();
+                }
+            }
+            Message::DuplicateNode => self.duplicate_selected(),
+            Message::SelectAll => self.select_next_node(),
+            Message::Deselect => self.selected_node = None,
+            Message::AutoLayout => {
+                auto_layout(self.flow_mut());
+                ensure_layouts(&mut self.document);
+                self.status = "Applied auto-layout".to_string();
+            }
+            Message::ExportPathChanged(path) => self.export_path = path,
+            Message::ExportPng(path) => {
+                let result = render_png(self.flow(), self.selected_node.as_deref())
+                    .and_then(|bytes| fs::write(&path, bytes).map_err(|err| err.to_string()));
+                self.status = match result {
+                    Ok(()) => format!("Exported PNG to {path}"),
+                    Err(err) => format!("PNG export error: {err}"),
+                };
+            }
+            Message::ExportSvg(path) => {
+                let svg = render_svg(self.flow(), self.selected_node.as_deref());
+                self.status = match fs::write(&path, svg) {
+                    Ok(()) => format!("Exported SVG to {path}"),
+                    Err(err) => format!("SVG export error: {err}"),
+                };
+            }
+        }
+
+        if let Some(before) = before {
+            if before != self.document {
+                // Typing into the input edited last extends that undo step.
+                if text_edit.is_none() || text_edit != self.history.editing {
+                    self.history.record(before);
+                }
+                // Renaming a node changes its id, so the next keystroke is matched against
+                // the id it has now.
+                self.history.editing = text_edit.map(|edit| TextEdit {
+                    node: self.selected_node.clone(),
+                    ..edit
+                });
+            }
         }
         Command::none()
     }
 
     fn view(&self) -> Element<'_, Message> {
-        let flows = self
-            .document
-            .flows
-            .iter()
-            .map(|f| f.id.clone())
-            .collect::<Vec<_>>();
         let current_flow = self.flow();
 
         let file_controls = column![
@@ -442,15 +792,50 @@ impl Application for FlowEditor {
             text_input("path", &self.file_path).on_input(Message::FilePathChanged),
             row![button("Load").on_press(Message::LoadFile), button("Save").on_press(Message::SaveFile)]
                 .spacing(8),
-            button("New document").on_press(Message::NewDocument)
+            button("New document").on_press(Message::NewDocument),
+            row![
+                button("Undo").on_press_maybe(self.history.can_undo().then_some(Message::Undo)),
+                button("Redo").on_press_maybe(self.history.can_redo().then_some(Message::Redo)),
+                button("Auto-layout").on_press(Message::AutoLayout)
+            ]
+            .spacing(8)
         ]
         .spacing(8);
 
-        let flow_picker = row![
-            text("Flow:"),
-            pick_list(flows, Some(current_flow.id.clone()), Message::SelectFlow)
-        ]
-        .spacing(8);
+        let flow_list = self.document.flows.iter().fold(
+            column![text("Flows").size(20), text("Double-click to open in a new tab").size(12)]
+                .spacing(4),
+            |list, flow| {
+                let style = if flow.id == current_flow.id {
+                    theme::Button::Primary
+                } else {
+                    theme::Button::Text
+                };
+                list.push(
+                    button(text(&flow.id))
+                        .style(style)
+                        .width(Length::Fill)
+                        .on_press(Message::FlowListClicked(flow.id.clone())),
+                )
+            },
+        );
+
+        let node_search = self.filtered_nodes().into_iter().fold(
+            column![
+                text("Find node").size(20),
+                text_input("id, name, agent or tool", &self.node_filter)
+                    .on_input(Message::FilterNodes)
+            ]
+            .spacing(4),
+            |list, node_id| {
+                list.push(
+                    button(text(node_id))
+                        .style(theme::Button::Text)
+                        .width(Length::Fill)
+                        .on_press(Message::FocusNode(node_id.to_string())),
+                )
+            },
+        );
 
         let palette = column![
             text("Add node").size(20),
@@ -464,20 +849,47 @@ impl Application for FlowEditor {
                 ("Parallel", NodeTemplate::Parallel),
                 ("Loop", NodeTemplate::Loop),
                 ("Subflow", NodeTemplate::Subflow),
+                ("Condition", NodeTemplate::Condition),
+                ("Transform", NodeTemplate::Transform),
+                ("Human", NodeTemplate::HumanInLoop),
+                ("Map each", NodeTemplate::MapEach),
+                ("Retry", NodeTemplate::RetryOnFail),
             ])
         ]
         .spacing(8);
 
-        let left_panel = scrollable(column![file_controls, flow_picker, palette].spacing(16))
+        let export_controls = column![
+            text("Export").size(20),
+            text_input("export path", &self.export_path).on_input(Message::ExportPathChanged),
+            row![
+                button("Export PNG").on_press(Message::ExportPng(self.export_path.clone())),
+                button("Export SVG").on_press(Message::ExportSvg(self.export_path.clone()))
+            ]
+            .spacing(8)
+        ]
+        .spacing(8);
+
+        let left_panel = scrollable(
+            column![file_controls, flow_list, node_search, palette, export_controls].spacing(16),
+        )
             .width(Length::Fixed(240.0));
 
         let canvas_view: Element<Message> = Canvas::new(GraphView {
             flow: current_flow,
             selected: self.selected_node.clone(),
+            matched: if self.node_filter.trim().is_empty() {
+                Vec::new()
+            } else {
+                self.filtered_nodes()
+            },
+            center: self.canvas_center,
         })
-        .width(Length::FillPortion(3))
+        .width(Length::Fill)
         .height(Length::Fill)
         .into();
+        let canvas_area = column![self.tab_bar(), canvas_view]
+            .spacing(4)
+            .width(Length::FillPortion(3));
 
         let inspector = self.inspector_view();
         let yaml_preview = self.yaml_preview();
@@ -487,7 +899,7 @@ impl Application for FlowEditor {
         )
         .width(Length::Fixed(360.0));
 
-        let content = row![left_panel, canvas_view, right_panel].spacing(8);
+        let content = row![left_panel, canvas_area, right_panel].spacing(8);
 
         container(column![content, text(&self.status)]).padding(8).into()
     }
@@ -502,6 +914,121 @@ impl FlowEditor {
         &mut self.document.flows[self.selected_flow]
     }
 
+    /// Ids of nodes in the current flow whose id, name, agent or tools contain the filter.
+    fn filtered_nodes(&self) -> Vec<&str> {
+        let query = self.node_filter.trim().to_lowercase();
+        self.flow()
+            .nodes
+            .iter()
+            .filter(|node| query.is_empty() || node_matches(node, &query))
+            .map(|node| node.base.id.as_str())
+            .collect()
+    }
+
+    fn tab_bar(&self) -> Element<'_, Message> {
+        let tabs = self
+            .open_tabs
+            .iter()
+            .enumerate()
+            .fold(Row::new().spacing(4), |tabs, (index, flow_id)| {
+                let style = || {
+                    if index == self.selected_tab {
+                        theme::Button::Primary
+                    } else {
+                        theme::Button::Secondary
+                    }
+                };
+                let close = button(text("×"))
+                    .style(style())
+                    .on_press_maybe((self.open_tabs.len() > 1).then_some(Message::CloseTab(index)));
+                tabs.push(row![
+                    button(text(flow_id)).style(style()).on_press(Message::SwitchTab(index)),
+                    close
+                ])
+            });
+
+        scrollable(tabs)
+            .direction(scrollable::Direction::Horizontal(
+                scrollable::Properties::default(),
+            ))
+            .width(Length::Fill)
+            .into()
+    }
+
+    /// Open `flow_id` in a new tab, or switch to it if it is already open. The least recently
+    /// used tab is closed once more than [`MAX_OPEN_TABS`] are open.
+    fn open_tab(&mut self, flow_id: String) {
+        if !self.document.flows.iter().any(|flow| flow.id == flow_id) {
+            return;
+        }
+        if let Some(index) = self.open_tabs.iter().position(|id| *id == flow_id) {
+            self.switch_tab(index);
+            return;
+        }
+
+        if self.open_tabs.len() >= MAX_OPEN_TABS {
+            let evicted = self.tab_recency.remove(0);
+            self.open_tabs.retain(|id| *id != evicted);
+        }
+        self.open_tabs.push(flow_id);
+        self.switch_tab(self.open_tabs.len() - 1);
+    }
+
+    /// Close a tab without touching the flow it shows. The last tab stays open.
+    fn close_tab(&mut self, index: usize) {
+        if index >= self.open_tabs.len() || self.open_tabs.len() == 1 {
+            return;
+        }
+        let closed = self.open_tabs.remove(index);
+        self.tab_recency.retain(|id| *id != closed);
+        if index < self.selected_tab || self.selected_tab == self.open_tabs.len() {
+            self.selected_tab -= 1;
+        }
+        self.switch_tab(self.selected_tab);
+    }
+
+    fn switch_tab(&mut self, index: usize) {
+        let Some(flow_id) = self.open_tabs.get(index).cloned() else {
+            return;
+        };
+        let Some(flow_index) = self.document.flows.iter().position(|flow| flow.id == flow_id) else {
+            return;
+        };
+        if flow_index != self.selected_flow {
+            self.selected_node = None;
+            self.canvas_center = None;
+        }
+        self.selected_tab = index;
+        self.selected_flow = flow_index;
+        self.tab_recency.retain(|id| *id != flow_id);
+        self.tab_recency.push(flow_id);
+    }
+
+    /// Replace the flow shown in the current tab, like a preview.
+    fn show_in_current_tab(&mut self, flow_id: String) {
+        if let Some(index) = self.open_tabs.iter().position(|id| *id == flow_id) {
+            self.switch_tab(index);
+            return;
+        }
+        if !self.document.flows.iter().any(|flow| flow.id == flow_id) {
+            return;
+        }
+        let replaced = std::mem::replace(&mut self.open_tabs[self.selected_tab], flow_id);
+        self.tab_recency.retain(|id| *id != replaced);
+        self.switch_tab(self.selected_tab);
+    }
+
+    /// Show only the selected flow after the document was replaced.
+    fn reset_tabs(&mut self) {
+        self.open_tabs.clear();
+        self.tab_recency.clear();
+        self.selected_tab = 0;
+        if let Some(flow) = self.document.flows.get(self.selected_flow) {
+            self.open_tabs.push(flow.id.clone());
+            self.tab_recency.push(flow.id.clone());
+        }
+    }
+
     fn load_file(&mut self) {
         match fs::read_to_string(&self.file_path) {
             Ok(content) => match FlowDocument::from_yaml_str(&content) {
@@ -510,6 +1037,7 @@ impl FlowEditor {
                     self.document = doc;
                     self.selected_flow = 0;
                     self.selected_node = None;
+                    self.reset_tabs();
                     self.status = "Loaded flow file".to_string();
                 }
                 Err(err) => self.status = format!("Parse error: {err}"),
@@ -555,6 +1083,45 @@ impl FlowEditor {
         }
     }
 
+    fn duplicate_selected(&mut self) {
+        let Some(id) = self.selected_node.clone() else {
+            return;
+        };
+        let Some(mut node) = self.flow().nodes.iter().find(|n| n.base.id == id).cloned() else {
+            return;
+        };
+
+        let mut copy_id = format!("{id}_copy");
+        let mut suffix = 2;
+        while self.flow().nodes.iter().any(|n| n.base.id == copy_id) {
+            copy_id = format!("{id}_copy{suffix}");
+            suffix += 1;
+        }
+
+        let layout = node.base.layout.clone().unwrap_or(NodeLayout { x: 80.0, y: 80.0 });
+        node.base.id = copy_id.clone();
+        node.base.layout = Some(NodeLayout {
+            x: layout.x + GRID * 3.0,
+            y: layout.y + GRID * 3.0,
+        });
+        self.flow_mut().nodes.push(node);
+        self.selected_node = Some(copy_id);
+    }
+
+    /// Step the selection through the nodes of the current flow, wrapping around.
+    fn select_next_node(&mut self) {
+        let nodes = &self.flow().nodes;
+        let next = match &self.selected_node {
+            Some(id) => nodes
+                .iter()
+                .position(|n| n.base.id == *id)
+                .map(|idx| (idx + 1) % nodes.len())
+                .unwrap_or(0),
+            None => 0,
+        };
+        self.selected_node = nodes.get(next).map(|n| n.base.id.clone());
+    }
+
     fn move_node(&mut self, node_id: &str, position: Point) {
         if let Some(node) = self
             .flow_mut()
@@ -618,6 +1185,25 @@ impl FlowEditor {
             })
             .unwrap_or_else(|| "out".to_string());
 
+        // Wiring a condition node's true/false output also picks that branch, and wiring a
+        // retry node picks the node it guards.
+        let branch_target = to.clone();
+        if let Some(node) = self.flow_mut().nodes.iter_mut().find(|n| n.base.id == from_node) {
+            match &mut node.kind {
+                FlowNodeKind::Condition {
+                    true_branch,
+                    false_branch,
+                    ..
+                } => match label.as_str() {
+                    "true" => *true_branch = branch_target,
+                    "false" => *false_branch = branch_target,
+                    _ => {}
+                },
+                FlowNodeKind::RetryOnFail { target_node, .. } => *target_node = branch_target,
+                _ => {}
+            }
+        }
+
         let from = format!("{}/{}", from_node, label);
         self.flow_mut().edges.push(FlowEdge {
             from,
@@ -657,6 +1243,18 @@ impl FlowEditor {
         }
     }
 
+    /// Keep the flow and node selection valid after the document was swapped.
+    fn restore_selection(&mut self) {
+        self.selected_flow = self.selected_flow.min(self.document.flows.len().saturating_sub(1));
+        let exists = self
+            .selected_node
+            .as_ref()
+            .is_some_and(|id| self.flow().nodes.iter().any(|n| n.base.id == *id));
+        if !exists {
+            self.selected_node = None;
+        }
+    }
+
     fn selected_node_mut(&mut self) -> Option<&mut FlowNode> {
         let id = self.selected_node.clone()?;
         self.flow_mut().nodes.iter_mut().find(|n| n.base.id == id)
@@ -724,8 +1322,80 @@ impl FlowEditor {
                     .spacing(6)
                     .into(),
                     FlowNodeKind::Subflow { flow } => {
-                        column![text_input("subflow id", flow).on_input(Message::UpdateSubflowId)].into()
+                        column![
+                            text_input("subflow id", flow).on_input(Message::UpdateSubflowId),
+                            button("Open subflow").on_press_maybe(
+                                self.document
+                                    .flows
+                                    .iter()
+                                    .any(|f| f.id == *flow)
+                                    .then(|| Message::OpenTab(flow.clone()))
+                            )
+                        ]
+                        .spacing(6)
+                        .into()
                     }
+                    FlowNodeKind::Condition {
+                        expression,
+                        true_branch,
+                        false_branch,
+                    } => column![
+                        text_input("expression, e.g. score >= 0.5", expression)
+                            .on_input(Message::UpdateConditionExpression),
+                        text_input("true branch node", true_branch).on_input(Message::UpdateTrueBranch),
+                        text_input("false branch node", false_branch).on_input(Message::UpdateFalseBranch)
+                    ]
+                    .spacing(6)
+                    .into(),
+                    FlowNodeKind::Transform {
+                        function,
+                        input_var,
+                        output_var,
+                    } => column![
+                        text_input("transform function", function).on_input(Message::UpdateTransformFunction),
+                        text_input("input variable", input_var).on_input(Message::UpdateInputVar),
+                        text_input("output variable", output_var).on_input(Message::UpdateOutputVar)
+                    ]
+                    .spacing(6)
+                    .into(),
+                    FlowNodeKind::HumanInLoop { prompt, timeout_ms } => column![
+                        text_input("prompt for the reviewer", prompt).on_input(Message::UpdateHumanPrompt),
+                        text_input(
+                            "timeout ms (optional)",
+                            &timeout_ms.map(|ms| ms.to_string()).unwrap_or_default()
+                        )
+                        .on_input(Message::UpdateHumanTimeout)
+                    ]
+                    .spacing(6)
+                    .into(),
+                    FlowNodeKind::MapEach {
+                        agent,
+                        input_var,
+                        output_var,
+                        concurrency,
+                    } => column![
+                        text_input("agent id", agent).on_input(Message::UpdateAgentId),
+                        text_input("input list variable", input_var).on_input(Message::UpdateInputVar),
+                        text_input("output variable", output_var).on_input(Message::UpdateOutputVar),
+                        text_input(
+                            "concurrency (optional)",
+                            &concurrency.map(|n| n.to_string()).unwrap_or_default()
+                        )
+                        .on_input(Message::UpdateMapConcurrency)
+                    ]
+                    .spacing(6)
+                    .into(),
+                    FlowNodeKind::RetryOnFail {
+                        target_node,
+                        max_retries,
+                        backoff_ms,
+                    } => column![
+                        text_input("node to retry", target_node).on_input(Message::UpdateRetryTarget),
+                        text_input("max retries", &max_retries.to_string()).on_input(Message::UpdateRetryMax),
+                        text_input("backoff ms", &backoff_ms.to_string()).on_input(Message::UpdateRetryBackoff)
+                    ]
+                    .spacing(6)
+                    .into(),
                 };
 
                 let mut view = column![
@@ -834,7 +1504,7 @@ fn default_node(template: NodeTemplate) -> (FlowNodeKind, Vec<NodeOutput>) {
     match template {
         NodeTemplate::Input => (FlowNodeKind::Input {}, vec![NodeOutput { label: "out".to_string(), condition: None }]),
         NodeTemplate::Output => (FlowNodeKind::Output {}, vec![]),
-        NodeTemplate::Agent => (FlowNodeKind::Agent { agent: "agent_id".to_string(), prompt: None, tools: vec![], parameters: None }, vec![NodeOutput { label: "out".to_string(), condition: None }]),
+        NodeTemplate::Agent => (FlowNodeKind::Agent { agent: "agent_id".to_string(), prompt: None, tools: vec![], parameters: None, skip_if: None }, vec![NodeOutput { label: "out".to_string(), condition: None }]),
         NodeTemplate::Decision => (FlowNodeKind::Decision { prompt: None, strategy: Some(DecisionStrategy::Llm) }, vec![
             NodeOutput { label: "yes".to_string(), condition: Some("yes".to_string()) },
             NodeOutput { label: "no".to_string(), condition: Some("no".to_string()) },
@@ -853,6 +1523,63 @@ fn default_node(template: NodeTemplate) -> (FlowNodeKind, Vec<NodeOutput>) {
         NodeTemplate::Parallel => (FlowNodeKind::Parallel { converge: Some(true) }, vec![NodeOutput { label: "out".to_string(), condition: None }]),
         NodeTemplate::Loop => (FlowNodeKind::Loop { max_iterations: 3, condition: None }, vec![NodeOutput { label: "next".to_string(), condition: None }]),
         NodeTemplate::Subflow => (FlowNodeKind::Subflow { flow: "subflow_id".to_string() }, vec![NodeOutput { label: "out".to_string(), condition: None }]),
+        NodeTemplate::Condition => (
+            FlowNodeKind::Condition {
+                expression: String::new(),
+                true_branch: String::new(),
+                false_branch: String::new(),
+            },
+            vec![
+                NodeOutput { label: "true".to_string(), condition: None },
+                NodeOutput { label: "false".to_string(), condition: None },
+            ],
+        ),
+        NodeTemplate::Transform => (
+            FlowNodeKind::Transform {
+                function: "transform_id".to_string(),
+                input_var: "input".to_string(),
+                output_var: "output".to_string(),
+            },
+            vec![NodeOutput { label: "out".to_string(), condition: None }],
+        ),
+        NodeTemplate::HumanInLoop => (
+            FlowNodeKind::HumanInLoop {
+                prompt: "Approve?".to_string(),
+                timeout_ms: None,
+            },
+            vec![NodeOutput { label: "out".to_string(), condition: None }],
+        ),
+        NodeTemplate::MapEach => (
+            FlowNodeKind::MapEach {
+                agent: "agent_id".to_string(),
+                input_var: "items".to_string(),
+                output_var: "results".to_string(),
+                concurrency: None,
+            },
+            vec![NodeOutput { label: "out".to_string(), condition: None }],
+        ),
+        NodeTemplate::RetryOnFail => (
+            FlowNodeKind::RetryOnFail {
+                target_node: String::new(),
+                max_retries: 3,
+                backoff_ms: 500,
+            },
+            vec![NodeOutput { label: "out".to_string(), condition: None }],
+        ),
+    }
+}
+
+fn shortcut(key: keyboard::Key, modifiers: keyboard::Modifiers) -> Option<Message> {
+    if !modifiers.command() {
+        return None;
+    }
+
+    match key.as_ref() {
+        keyboard::Key::Character(c) if c.eq_ignore_ascii_case("z") => {
+            Some(if modifiers.shift() { Message::Redo } else { Message::Undo })
+        }
+        keyboard::Key::Character(c) if c.eq_ignore_ascii_case("y") => Some(Message::Redo),
+        _ => None,
     }
 }
 
@@ -881,6 +1608,307 @@ fn ensure_layouts(document: &mut FlowDocument) {
     }
 }
 
+/// Layered (Sugiyama-style) layout: nodes are placed in columns by their depth from the
+/// entry node, and stacked within a column in traversal order. Cycles are broken by
+/// ignoring the back edges found during a depth-first search.
+fn auto_layout(flow: &mut denkwerk::FlowDefinition) {
+    let ids: Vec<String> = flow.nodes.iter().map(|n| n.base.id.clone()).collect();
+    let index_of = |id: &str| ids.iter().position(|candidate| candidate == id);
+
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+    for edge in &flow.edges {
+        let (from, to) = parse_edge_targets(edge);
+        if let (Some(from), Some(to)) = (index_of(&from), index_of(&to)) {
+            if !successors[from].contains(&to) {
+                successors[from].push(to);
+            }
+        }
+    }
+
+    // Depth-first search from the entry node first, then any unreached nodes.
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        Unvisited,
+        Active,
+        Done,
+    }
+    let mut marks = vec![Mark::Unvisited; ids.len()];
+    let mut postorder = Vec::with_capacity(ids.len());
+    let mut forward: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+    let roots = index_of(&flow.entry).into_iter().chain(0..ids.len());
+
+    for root in roots {
+        if marks[root] != Mark::Unvisited {
+            continue;
+        }
+        marks[root] = Mark::Active;
+        let mut stack = vec![(root, 0usize)];
+        while let Some((node, next_child)) = stack.pop() {
+            if let Some(&child) = successors[node].get(next_child) {
+                stack.push((node, next_child + 1));
+                match marks[child] {
+                    Mark::Unvisited => {
+                        forward[node].push(child);
+                        marks[child] = Mark::Active;
+                        stack.push((child, 0));
+                    }
+                    Mark::Done => forward[node].push(child),
+                    // Back edge: part of a cycle, ignored for layering.
+                    Mark::Active => {}
+                }
+            } else {
+                marks[node] = Mark::Done;
+                postorder.push(node);
+            }
+        }
+    }
+
+    // Longest-path layering over the acyclic edges, in topological order.
+    let topological: Vec<usize> = postorder.into_iter().rev().collect();
+    let mut layers = vec![0usize; ids.len()];
+    for &node in &topological {
+        for &child in &forward[node] {
+            layers[child] = layers[child].max(layers[node] + 1);
+        }
+    }
+
+    let mut filled: Vec<usize> = Vec::new();
+    let mut positions = vec![(0usize, 0usize); ids.len()];
+    for &node in &topological {
+        let layer = layers[node];
+        if filled.len() <= layer {
+            filled.resize(layer + 1, 0);
+        }
+        positions[node] = (layer, filled[layer]);
+        filled[layer] += 1;
+    }
+
+    for (node, (layer, row)) in flow.nodes.iter_mut().zip(positions) {
+        node.base.layout = Some(NodeLayout {
+            x: layer as f32 * (NODE_WIDTH + 60.0),
+            y: row as f32 * (NODE_HEIGHT + 40.0),
+        });
+    }
+}
+
+const EXPORT_MARGIN: f32 = 20.0;
+const EXPORT_BACKGROUND: (u8, u8, u8) = (30, 30, 33);
+const EXPORT_NODE: (u8, u8, u8) = (59, 59, 66);
+const EXPORT_SELECTED: (u8, u8, u8) = (46, 89, 158);
+const EXPORT_EDGE: (u8, u8, u8) = (230, 230, 230);
+
+/// Bounding box of all laid-out nodes, grown by the export margin.
+fn export_bounds(flow: &denkwerk::FlowDefinition) -> Rectangle {
+    let layouts: Vec<&NodeLayout> = flow
+        .nodes
+        .iter()
+        .filter_map(|n| n.base.layout.as_ref())
+        .collect();
+    if layouts.is_empty() {
+        return Rectangle::new(Point::ORIGIN, iced::Size::new(EXPORT_MARGIN * 2.0, EXPORT_MARGIN * 2.0));
+    }
+
+    let min_x = layouts.iter().map(|l| l.x).fold(f32::INFINITY, f32::min);
+    let min_y = layouts.iter().map(|l| l.y).fold(f32::INFINITY, f32::min);
+    let max_x = layouts.iter().map(|l| l.x + NODE_WIDTH).fold(f32::NEG_INFINITY, f32::max);
+    let max_y = layouts.iter().map(|l| l.y + NODE_HEIGHT).fold(f32::NEG_INFINITY, f32::max);
+
+    Rectangle {
+        x: min_x - EXPORT_MARGIN,
+        y: min_y - EXPORT_MARGIN,
+        width: max_x - min_x + EXPORT_MARGIN * 2.0,
+        height: max_y - min_y + EXPORT_MARGIN * 2.0,
+    }
+}
+
+fn edge_endpoints(flow: &denkwerk::FlowDefinition) -> Vec<(Point, Point)> {
+    flow.edges
+        .iter()
+        .filter_map(|edge| {
+            let (from, to) = parse_edge_targets(edge);
+            Some((node_center(flow, &from)?, node_center(flow, &to)?))
+        })
+        .collect()
+}
+
+fn render_svg(flow: &denkwerk::FlowDefinition, selected: Option<&str>) -> String {
+    use std::fmt::Write;
+
+    let bounds = export_bounds(flow);
+    let rgb = |(r, g, b): (u8, u8, u8)| format!("rgb({r},{g},{b})");
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}" style="background:{}">"#,
+        bounds.x,
+        bounds.y,
+        bounds.width,
+        bounds.height,
+        bounds.width,
+        bounds.height,
+        rgb(EXPORT_BACKGROUND)
+    );
+
+    for (from, to) in edge_endpoints(flow) {
+        let _ = writeln!(
+            svg,
+            r#"  <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="2"/>"#,
+            from.x,
+            from.y,
+            to.x,
+            to.y,
+            rgb(EXPORT_EDGE)
+        );
+    }
+
+    for node in &flow.nodes {
+        let Some(layout) = &node.base.layout else { continue };
+        let fill = if selected == Some(node.base.id.as_str()) {
+            EXPORT_SELECTED
+        } else {
+            EXPORT_NODE
+        };
+        let _ = writeln!(
+            svg,
+            r#"  <rect x="{}" y="{}" width="{NODE_WIDTH}" height="{NODE_HEIGHT}" fill="{}" stroke="white" stroke-width="2"/>"#,
+            layout.x,
+            layout.y,
+            rgb(fill)
+        );
+        let _ = writeln!(
+            svg,
+            r#"  <text x="{}" y="{}" fill="white" font-family="sans-serif" font-size="16">{}</text>"#,
+            layout.x + 8.0,
+            layout.y + 18.0,
+            xml_escape(&node.base.id)
+        );
+        let _ = writeln!(
+            svg,
+            r#"  <text x="{}" y="{}" fill="white" fill-opacity="0.7" font-family="sans-serif" font-size="12">{}</text>"#,
+            layout.x + 8.0,
+            layout.y + 38.0,
+            xml_escape(node_kind_label(&node.kind))
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn node_kind_label(kind: &FlowNodeKind) -> &'static str {
+    match kind {
+        FlowNodeKind::Input {} => "Input",
+        FlowNodeKind::Output {} => "Output",
+        FlowNodeKind::Agent { .. } => "Agent",
+        FlowNodeKind::Decision { .. } => "Decision",
+        FlowNodeKind::Tool { .. } => "Tool",
+        FlowNodeKind::Merge {} => "Merge",
+        FlowNodeKind::Parallel { .. } => "Parallel",
+        FlowNodeKind::Loop { .. } => "Loop",
+        FlowNodeKind::Subflow { .. } => "Subflow",
+        FlowNodeKind::Condition { .. } => "Condition",
+        FlowNodeKind::Transform { .. } => "Transform",
+        FlowNodeKind::HumanInLoop { .. } => "Human",
+        FlowNodeKind::MapEach { .. } => "MapEach",
+        FlowNodeKind::RetryOnFail { .. } => "Retry",
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Rasterise the flow like [`GraphView::draw`]. tiny-skia has no text support, so node
+/// labels are left out of the PNG.
+fn render_png(flow: &denkwerk::FlowDefinition, selected: Option<&str>) -> Result<Vec<u8>, String> {
+    use tiny_skia::{Paint, PathBuilder, Pixmap, Rect, Stroke, Transform};
+
+    let bounds = export_bounds(flow);
+    let width = bounds.width.ceil() as u32;
+    let height = bounds.height.ceil() as u32;
+    let mut pixmap = Pixmap::new(width, height).ok_or("flow is too large to export")?;
+    let (r, g, b) = EXPORT_BACKGROUND;
+    pixmap.fill(tiny_skia::Color::from_rgba8(r, g, b, 255));
+
+    let transform = Transform::from_translate(-bounds.x, -bounds.y);
+    let paint = |(r, g, b): (u8, u8, u8), alpha: u8| {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(r, g, b, alpha);
+        paint.anti_alias = true;
+        paint
+    };
+    let thin = Stroke {
+        width: 1.0,
+        ..Stroke::default()
+    };
+    let thick = Stroke {
+        width: 2.0,
+        ..Stroke::default()
+    };
+
+    // Grid
+    let grid = paint((153, 153, 153), 51);
+    let mut path = PathBuilder::new();
+    let mut x = (bounds.x / GRID).floor() * GRID;
+    while x < bounds.x + bounds.width {
+        path.move_to(x, bounds.y);
+        path.line_to(x, bounds.y + bounds.height);
+        x += GRID;
+    }
+    let mut y = (bounds.y / GRID).floor() * GRID;
+    while y < bounds.y + bounds.height {
+        path.move_to(bounds.x, y);
+        path.line_to(bounds.x + bounds.width, y);
+        y += GRID;
+    }
+    if let Some(path) = path.finish() {
+        pixmap.stroke_path(&path, &grid, &thin, transform, None);
+    }
+
+    // Edges
+    let edge_paint = paint(EXPORT_EDGE, 255);
+    for (from, to) in edge_endpoints(flow) {
+        let mut path = PathBuilder::new();
+        path.move_to(from.x, from.y);
+        path.line_to(to.x, to.y);
+        if let Some(path) = path.finish() {
+            pixmap.stroke_path(&path, &edge_paint, &thick, transform, None);
+        }
+    }
+
+    // Nodes
+    let border = paint((255, 255, 255), 255);
+    for node in &flow.nodes {
+        let Some(layout) = &node.base.layout else { continue };
+        let Some(rect) = Rect::from_xywh(layout.x, layout.y, NODE_WIDTH, NODE_HEIGHT) else {
+            continue;
+        };
+        let fill = if selected == Some(node.base.id.as_str()) {
+            EXPORT_SELECTED
+        } else {
+            EXPORT_NODE
+        };
+        pixmap.fill_rect(rect, &paint(fill, 255), transform, None);
+        pixmap.stroke_path(&PathBuilder::from_rect(rect), &border, &thick, transform, None);
+    }
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|err| err.to_string())?;
+    // The pixmap is opaque, so its premultiplied data is plain RGBA.
+    writer
+        .write_image_data(pixmap.data())
+        .map_err(|err| err.to_string())?;
+    writer.finish().map_err(|err| err.to_string())?;
+    Ok(bytes)
+}
+
 fn wrapped_row(entries: Vec<(&str, NodeTemplate)>) -> Element<'_, Message> {
     let mut col = Column::new().spacing(6);
     let mut current = Row::new().spacing(6);
@@ -901,10 +1929,30 @@ fn wrapped_row(entries: Vec<(&str, NodeTemplate)>) -> Element<'_, Message> {
 struct GraphView<'a> {
     flow: &'a denkwerk::FlowDefinition,
     selected: Option<String>,
+    /// Nodes matching the search filter, drawn with a highlighted border.
+    matched: Vec<&'a str>,
+    center: Option<Point>,
+}
+
+impl GraphView<'_> {
+    /// Translation from flow coordinates to canvas coordinates.
+    fn pan(&self, bounds: Rectangle) -> Vector {
+        self.center
+            .map(|center| {
+                Vector::new(bounds.width / 2.0 - center.x, bounds.height / 2.0 - center.y)
+            })
+            .unwrap_or(Vector::new(0.0, 0.0))
+    }
+}
+
+/// Canvas interaction state; keyboard shortcuts only apply while the canvas has focus.
+#[derive(Debug, Default)]
+struct GraphState {
+    focused: bool,
 }
 
 impl<'a> canvas::Program<Message> for GraphView<'a> {
-    type State = ();
+    type State = GraphState;
 
     fn draw(
         &self,
@@ -943,6 +1991,8 @@ impl<'a> canvas::Program<Message> for GraphView<'a> {
             x += GRID;
         }
 
+        frame.translate(self.pan(bounds));
+
         // Edges
         for edge in &self.flow.edges {
             let (from, to) = parse_edge_targets(edge);
@@ -977,12 +2027,17 @@ impl<'a> canvas::Program<Message> for GraphView<'a> {
             } else {
                 Color::from_rgb(0.23, 0.23, 0.26)
             };
+            let border = if self.matched.contains(&node.base.id.as_str()) {
+                Color::from_rgb(1.0, 0.75, 0.2)
+            } else {
+                Color::WHITE
+            };
             frame.fill_rectangle(rect.position(), rect.size(), color);
             frame.stroke(
                 &canvas::Path::rectangle(rect.position(), rect.size()),
                 canvas::Stroke {
                     width: 2.0,
-                    style: canvas::Style::Solid(Color::WHITE),
+                    style: canvas::Style::Solid(border),
                     ..Default::default()
                 },
             );
@@ -1007,15 +2062,23 @@ impl<'a> canvas::Program<Message> for GraphView<'a> {
 
     fn update(
         &self,
-        _state: &mut Self::State,
+        state: &mut Self::State,
         event: canvas::Event,
-        _bounds: Rectangle,
+        bounds: Rectangle,
         cursor: mouse::Cursor,
     ) -> (iced::event::Status, Option<Message>) {
         match event {
+            canvas::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. })
+                if state.focused =>
+            {
+                if let Some(message) = canvas_shortcut(key, modifiers) {
+                    return (iced::event::Status::Captured, Some(message));
+                }
+            }
             canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                state.focused = cursor.is_over(bounds);
                 if let Some(position) = cursor.position() {
-                    if let Some((id, offset)) = hit_node(self.flow, position) {
+                    if let Some((id, offset)) = hit_node(self.flow, position - self.pan(bounds)) {
                         return (
                             iced::event::Status::Captured,
                             Some(Message::StartDrag { node_id: id, offset }),
@@ -1027,7 +2090,10 @@ impl<'a> canvas::Program<Message> for GraphView<'a> {
                 return (iced::event::Status::Captured, Some(Message::EndDrag));
             }
             canvas::Event::Mouse(mouse::Event::CursorMoved { position }) => {
-                return (iced::event::Status::Captured, Some(Message::DragTo(position)));
+                return (
+                    iced::event::Status::Captured,
+                    Some(Message::DragTo(position - self.pan(bounds))),
+                );
             }
             _ => {}
         }
@@ -1037,11 +2103,11 @@ impl<'a> canvas::Program<Message> for GraphView<'a> {
     fn mouse_interaction(
         &self,
         _state: &Self::State,
-        _bounds: Rectangle,
+        bounds: Rectangle,
         cursor: mouse::Cursor,
     ) -> mouse::Interaction {
         if let Some(position) = cursor.position() {
-            if hit_node(self.flow, position).is_some() {
+            if hit_node(self.flow, position - self.pan(bounds)).is_some() {
                 return mouse::Interaction::Grab;
             }
         }
@@ -1049,6 +2115,20 @@ impl<'a> canvas::Program<Message> for GraphView<'a> {
     }
 }
 
+fn canvas_shortcut(key: keyboard::Key, modifiers: keyboard::Modifiers) -> Option<Message> {
+    match key.as_ref() {
+        keyboard::Key::Named(keyboard::key::Named::Delete) => Some(Message::DeleteSelectedNode),
+        keyboard::Key::Named(keyboard::key::Named::Escape) => Some(Message::Deselect),
+        keyboard::Key::Character(c) if modifiers.command() && c.eq_ignore_ascii_case("d") => {
+            Some(Message::DuplicateNode)
+        }
+        keyboard::Key::Character(c) if modifiers.command() && c.eq_ignore_ascii_case("a") => {
+            Some(Message::SelectAll)
+        }
+        _ => None,
+    }
+}
+
 fn parse_edge_targets(edge: &FlowEdge) -> (String, String) {
     let from = edge
         .from
@@ -1067,6 +2147,21 @@ fn node_center(flow: &denkwerk::FlowDefinition, id: &str) -> Option<Point> {
         .map(|layout| Point::new(layout.x + NODE_WIDTH / 2.0, layout.y + NODE_HEIGHT / 2.0))
 }
 
+fn node_matches(node: &FlowNode, query: &str) -> bool {
+    let mut fields = vec![node.base.id.as_str()];
+    fields.extend(node.base.name.as_deref());
+    match &node.kind {
+        FlowNodeKind::Agent { agent, tools, .. } => {
+            fields.push(agent);
+            fields.extend(tools.iter().map(String::as_str));
+        }
+        FlowNodeKind::Tool { tool, .. } => fields.push(tool),
+        FlowNodeKind::MapEach { agent, .. } => fields.push(agent),
+        _ => {}
+    }
+    fields.iter().any(|field| field.to_lowercase().contains(query))
+}
+
 fn hit_node(flow: &denkwerk::FlowDefinition, position: Point) -> Option<(String, Vector)> {
     for node in &flow.nodes {
         if let Some(layout) = &node.base.layout {
@@ -1096,3 +2191,407 @@ fn some(value: String) -> Option<String> {
         Some(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor() -> FlowEditor {
+        FlowEditor::new(()).0
+    }
+
+    #[test]
+    fn undo_reverts_added_nodes_in_order() {
+        let mut editor = editor();
+        let original = editor.document.clone();
+
+        for _ in 0..3 {
+            let _ = editor.update(Message::AddNode(NodeTemplate::Agent));
+        }
+        assert_eq!(editor.flow().nodes.len(), 4);
+
+        for _ in 0..3 {
+            let _ = editor.update(Message::Undo);
+        }
+        assert_eq!(editor.document, original);
+        assert!(!editor.history.can_undo());
+        assert!(editor.selected_node.is_none());
+
+        let _ = editor.update(Message::Redo);
+        assert_eq!(editor.flow().nodes.len(), 2);
+        assert!(editor.history.can_redo());
+    }
+
+    #[test]
+    fn new_edits_clear_redo_and_saves_are_not_recorded() {
+        let mut editor = editor();
+        editor.file_path = std::env::temp_dir()
+            .join("denkwerk_flow_editor_undo.yaml")
+            .display()
+            .to_string();
+
+        let _ = editor.update(Message::AddNode(NodeTemplate::Tool));
+        let _ = editor.update(Message::SaveFile);
+        assert_eq!(editor.history.past.len(), 1);
+
+        let _ = editor.update(Message::Undo);
+        let _ = editor.update(Message::AddNode(NodeTemplate::Merge));
+        assert!(!editor.history.can_redo());
+        let _ = fs::remove_file(&editor.file_path);
+    }
+
+    fn editor_with_flows(count: usize) -> FlowEditor {
+        let mut editor = editor();
+        let template = editor.document.flows[0].clone();
+        for index in 1..count {
+            let mut flow = template.clone();
+            flow.id = format!("flow_{index}");
+            editor.document.flows.push(flow);
+        }
+        editor
+    }
+
+    #[test]
+    fn tabs_open_switch_and_close() {
+        let mut editor = editor_with_flows(3);
+        for flow_id in ["main", "flow_1", "flow_2"] {
+            let _ = editor.update(Message::OpenTab(flow_id.to_string()));
+        }
+        assert_eq!(editor.open_tabs.len(), 3);
+        assert_eq!(editor.flow().id, "flow_2");
+
+        let _ = editor.update(Message::SwitchTab(0));
+        assert_eq!(editor.flow().id, "main");
+
+        let _ = editor.update(Message::CloseTab(1));
+        assert_eq!(editor.open_tabs.len(), 2);
+        assert_eq!(editor.open_tabs, ["main", "flow_2"]);
+        assert_eq!(editor.flow().id, "main");
+        assert_eq!(editor.document.flows.len(), 3);
+        assert!(!editor.history.can_undo());
+
+        let _ = editor.update(Message::CloseTab(0));
+        let _ = editor.update(Message::CloseTab(0));
+        assert_eq!(editor.open_tabs, ["flow_2"]);
+        assert_eq!(editor.flow().id, "flow_2");
+    }
+
+    #[test]
+    fn least_recently_used_tab_is_closed() {
+        let mut editor = editor_with_flows(MAX_OPEN_TABS + 1);
+        for index in 1..MAX_OPEN_TABS {
+            let _ = editor.update(Message::OpenTab(format!("flow_{index}")));
+        }
+        let _ = editor.update(Message::SwitchTab(0));
+        let _ = editor.update(Message::OpenTab(format!("flow_{MAX_OPEN_TABS}")));
+
+        assert_eq!(editor.open_tabs.len(), MAX_OPEN_TABS);
+        assert!(editor.open_tabs.contains(&"main".to_string()));
+        assert!(!editor.open_tabs.contains(&"flow_1".to_string()));
+        assert_eq!(editor.flow().id, format!("flow_{MAX_OPEN_TABS}"));
+    }
+
+    #[test]
+    fn double_clicking_a_flow_opens_a_tab() {
+        let mut editor = editor_with_flows(3);
+        let _ = editor.update(Message::FlowListClicked("flow_1".to_string()));
+        assert_eq!(editor.open_tabs, ["flow_1"]);
+
+        let _ = editor.update(Message::FlowListClicked("flow_2".to_string()));
+        let _ = editor.update(Message::FlowListClicked("flow_2".to_string()));
+        assert_eq!(editor.open_tabs, ["flow_2"]);
+
+        let _ = editor.update(Message::SwitchTab(0));
+        let _ = editor.update(Message::OpenFlowInTab("main".to_string()));
+        assert_eq!(editor.open_tabs, ["flow_2", "main"]);
+        assert_eq!(editor.selected_tab, 1);
+    }
+
+    #[test]
+    fn filter_lists_matching_nodes_and_focus_pans() {
+        let mut editor = editor();
+        for id in ["fetch_orders", "summarize", "notify_user"] {
+            let _ = editor.update(Message::AddNode(NodeTemplate::Agent));
+            let _ = editor.update(Message::UpdateNodeId(id.to_string()));
+        }
+        assert_eq!(editor.filtered_nodes().len(), 4);
+        let recorded = editor.history.past.len();
+
+        let _ = editor.update(Message::FilterNodes("ORDER".to_string()));
+        assert_eq!(editor.filtered_nodes(), ["fetch_orders"]);
+        assert_eq!(editor.history.past.len(), recorded);
+
+        let _ = editor.update(Message::FilterNodes("Input".to_string()));
+        assert_eq!(editor.filtered_nodes(), ["input"]);
+
+        let _ = editor.update(Message::FocusNode("input".to_string()));
+        assert_eq!(editor.selected_node.as_deref(), Some("input"));
+        assert_eq!(
+            editor.canvas_center,
+            Some(Point::new(40.0 + NODE_WIDTH / 2.0, 80.0 + NODE_HEIGHT / 2.0))
+        );
+
+        let view = GraphView {
+            flow: editor.flow(),
+            selected: None,
+            matched: Vec::new(),
+            center: editor.canvas_center,
+        };
+        let bounds = Rectangle::new(Point::ORIGIN, iced::Size::new(800.0, 600.0));
+        assert_eq!(view.pan(bounds), Vector::new(280.0, 175.0));
+    }
+
+    #[test]
+    fn condition_edges_set_branches() {
+        let mut editor = editor();
+        let _ = editor.update(Message::AddNode(NodeTemplate::Condition));
+        let condition = editor.selected_node.clone().unwrap();
+
+        let _ = editor.update(Message::SelectEdgeOutput("false".to_string()));
+        let _ = editor.update(Message::SelectEdgeTarget("input".to_string()));
+        let _ = editor.update(Message::AddEdge);
+
+        let node = editor.flow().nodes.iter().find(|n| n.base.id == condition).unwrap();
+        assert_eq!(node.base.outputs.len(), 2);
+        assert!(matches!(
+            &node.kind,
+            FlowNodeKind::Condition { true_branch, false_branch, .. }
+                if true_branch.is_empty() && false_branch == "input"
+        ));
+    }
+
+    #[test]
+    fn drags_are_recorded_as_one_step() {
+        let mut editor = editor();
+        let original = editor.document.clone();
+
+        let _ = editor.update(Message::StartDrag {
+            node_id: "input".to_string(),
+            offset: Vector::new(0.0, 0.0),
+        });
+        let _ = editor.update(Message::DragTo(Point::new(200.0, 200.0)));
+        let _ = editor.update(Message::DragTo(Point::new(300.0, 240.0)));
+        let _ = editor.update(Message::EndDrag);
+        assert_eq!(editor.history.past.len(), 1);
+
+        let _ = editor.update(Message::Undo);
+        assert_eq!(editor.document, original);
+    }
+
+    #[test]
+    fn typing_into_one_field_is_one_undo_step() {
+        let mut editor = editor();
+        let _ = editor.update(Message::AddNode(NodeTemplate::Agent));
+        let added = editor.document.clone();
+        let node = editor.selected_node.clone().unwrap();
+
+        for id in ["w", "wr", "wri", "writer"] {
+            let _ = editor.update(Message::UpdateNodeId(id.to_string()));
+        }
+        for name in ["W", "Writer"] {
+            let _ = editor.update(Message::UpdateNodeName(name.to_string()));
+        }
+        assert_eq!(editor.history.past.len(), 3);
+
+        let _ = editor.update(Message::Undo);
+        let _ = editor.update(Message::Undo);
+        assert_eq!(editor.document, added);
+
+        let _ = editor.update(Message::FocusNode(node));
+        let _ = editor.update(Message::UpdateNodeName("A".to_string()));
+        let _ = editor.update(Message::UpdateNodeName("AB".to_string()));
+        assert_eq!(editor.history.past.len(), 2);
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut history = CommandHistory::default();
+        for _ in 0..(HISTORY_LIMIT + 5) {
+            history.record(default_document());
+        }
+        assert_eq!(history.past.len(), HISTORY_LIMIT);
+    }
+
+    fn key_event(key: keyboard::Key, modifiers: keyboard::Modifiers) -> canvas::Event {
+        canvas::Event::Keyboard(keyboard::Event::KeyPressed {
+            key,
+            location: keyboard::Location::Standard,
+            modifiers,
+            text: None,
+        })
+    }
+
+    fn press(
+        view: &GraphView<'_>,
+        state: &mut GraphState,
+        key: keyboard::Key,
+        modifiers: keyboard::Modifiers,
+    ) -> Option<Message> {
+        let bounds = Rectangle::new(Point::ORIGIN, iced::Size::new(800.0, 600.0));
+        let (_, message) = canvas::Program::update(
+            view,
+            state,
+            key_event(key, modifiers),
+            bounds,
+            mouse::Cursor::Unavailable,
+        );
+        message
+    }
+
+    #[test]
+    fn canvas_keys_map_to_messages_only_when_focused() {
+        let editor = editor();
+        let view = GraphView {
+            flow: editor.flow(),
+            selected: None,
+            matched: Vec::new(),
+            center: None,
+        };
+        let command = keyboard::Modifiers::COMMAND;
+        let none = keyboard::Modifiers::empty();
+        let delete = || keyboard::Key::Named(keyboard::key::Named::Delete);
+
+        let mut state = GraphState::default();
+        assert!(press(&view, &mut state, delete(), none).is_none());
+
+        state.focused = true;
+        assert!(matches!(
+            press(&view, &mut state, delete(), none),
+            Some(Message::DeleteSelectedNode)
+        ));
+        assert!(matches!(
+            press(&view, &mut state, keyboard::Key::Character("d".into()), command),
+            Some(Message::DuplicateNode)
+        ));
+        assert!(matches!(
+            press(&view, &mut state, keyboard::Key::Character("a".into()), command),
+            Some(Message::SelectAll)
+        ));
+        assert!(matches!(
+            press(&view, &mut state, keyboard::Key::Named(keyboard::key::Named::Escape), none),
+            Some(Message::Deselect)
+        ));
+        assert!(press(&view, &mut state, keyboard::Key::Character("d".into()), none).is_none());
+    }
+
+    #[test]
+    fn duplicate_and_select_all() {
+        let mut editor = editor();
+        let _ = editor.update(Message::AddNode(NodeTemplate::Agent));
+        let _ = editor.update(Message::DuplicateNode);
+
+        let copy = editor.flow().nodes.last().unwrap();
+        assert_eq!(copy.base.id, "node_0_copy");
+        let layout = copy.base.layout.as_ref().unwrap();
+        assert_eq!((layout.x, layout.y), (80.0 + GRID * 3.0, 80.0 + GRID * 3.0));
+        assert_eq!(editor.selected_node.as_deref(), Some("node_0_copy"));
+
+        let _ = editor.update(Message::Deselect);
+        let ids: Vec<_> = (0..4)
+            .map(|_| {
+                let _ = editor.update(Message::SelectAll);
+                editor.selected_node.clone().unwrap()
+            })
+            .collect();
+        assert_eq!(ids, ["input", "node_0", "node_0_copy", "input"]);
+    }
+
+    fn linear_flow(edges: &[(&str, &str)]) -> denkwerk::FlowDefinition {
+        let mut flow = default_document().flows.remove(0);
+        for (id, template) in [("agent", NodeTemplate::Agent), ("output", NodeTemplate::Output)] {
+            let (kind, outputs) = default_node(template);
+            flow.nodes.push(FlowNode {
+                base: FlowNodeBase {
+                    id: id.to_string(),
+                    name: None,
+                    description: None,
+                    inputs: vec![],
+                    outputs,
+                    layout: Some(NodeLayout { x: 80.0, y: 80.0 }),
+                },
+                kind,
+            });
+        }
+        flow.edges = edges
+            .iter()
+            .map(|(from, to)| FlowEdge {
+                from: format!("{from}/out"),
+                to: to.to_string(),
+                condition: None,
+                label: None,
+            })
+            .collect();
+        flow
+    }
+
+    fn x_positions(flow: &denkwerk::FlowDefinition) -> Vec<f32> {
+        flow.nodes
+            .iter()
+            .map(|n| n.base.layout.as_ref().unwrap().x)
+            .collect()
+    }
+
+    #[test]
+    fn auto_layout_orders_linear_flow_left_to_right() {
+        let mut flow = linear_flow(&[("input", "agent"), ("agent", "output")]);
+        auto_layout(&mut flow);
+
+        let xs = x_positions(&flow);
+        assert!(xs.windows(2).all(|pair| pair[0] < pair[1]), "{xs:?}");
+        assert_eq!(xs[1], NODE_WIDTH + 60.0);
+    }
+
+    #[test]
+    fn auto_layout_breaks_cycles() {
+        let mut flow = linear_flow(&[("input", "agent"), ("agent", "output"), ("output", "agent")]);
+        auto_layout(&mut flow);
+
+        let xs = x_positions(&flow);
+        assert!(xs.windows(2).all(|pair| pair[0] < pair[1]), "{xs:?}");
+    }
+
+    #[test]
+    fn auto_layout_stacks_siblings() {
+        let mut flow = linear_flow(&[("input", "agent"), ("input", "output")]);
+        auto_layout(&mut flow);
+
+        let layouts: Vec<_> = flow.nodes.iter().map(|n| n.base.layout.clone().unwrap()).collect();
+        assert_eq!(layouts[1].x, layouts[2].x);
+        assert_eq!((layouts[1].y - layouts[2].y).abs(), NODE_HEIGHT + 40.0);
+    }
+
+    #[test]
+    fn svg_export_draws_each_node() {
+        let mut flow = linear_flow(&[("input", "agent")]);
+        flow.nodes.truncate(2);
+        flow.nodes[1].base.layout = Some(NodeLayout { x: 300.0, y: 120.0 });
+
+        let svg = render_svg(&flow, Some("agent"));
+        assert_eq!(svg.matches("<rect").count(), 2);
+        assert_eq!(svg.matches("<line").count(), 1);
+        assert!(svg.contains(">agent</text>"));
+        // Nodes span x 40..460 and y 80..210; the view box adds a 20px margin.
+        assert!(svg.contains(r#"viewBox="20 60 460 170""#), "{svg}");
+    }
+
+    #[test]
+    fn png_export_encodes_an_image() {
+        let flow = linear_flow(&[("input", "agent"), ("agent", "output")]);
+        let bytes = render_png(&flow, None).unwrap();
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn undo_shortcuts() {
+        let ctrl = keyboard::Modifiers::COMMAND;
+        let z = || keyboard::Key::Character("z".into());
+
+        assert!(matches!(shortcut(z(), ctrl), Some(Message::Undo)));
+        assert!(matches!(shortcut(z(), ctrl | keyboard::Modifiers::SHIFT), Some(Message::Redo)));
+        assert!(matches!(
+            shortcut(keyboard::Key::Character("y".into()), ctrl),
+            Some(Message::Redo)
+        ));
+        assert!(shortcut(z(), keyboard::Modifiers::empty()).is_none());
+    }
+}
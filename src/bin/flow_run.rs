@@ -0,0 +1,136 @@
+use std::{collections::HashMap, path::PathBuf, process::ExitCode, sync::Arc};
+
+use clap::{Parser, ValueEnum};
+use denkwerk::{
+    providers::{azure_openai::AzureOpenAI, openai::OpenAI, openrouter::OpenRouter, scripted::ScriptedProvider},
+    FlowBuilder, FlowContext, LLMProvider, ProxyProvider, SequentialEvent,
+};
+
+#[derive(Parser)]
+#[command(name = "flow_run")]
+#[command(about = "Run a YAML flow against an LLM provider")]
+struct Args {
+    /// Path to the flow YAML file
+    #[arg(long)]
+    file: PathBuf,
+
+    /// Id of the flow to run
+    #[arg(long, default_value = "main")]
+    flow: String,
+
+    /// Task handed to the first agent
+    #[arg(long)]
+    task: String,
+
+    /// Provider to call (API keys are read from the usual environment variables)
+    #[arg(long, value_enum, default_value_t = ProviderKind::Openrouter)]
+    provider: ProviderKind,
+
+    /// Model used for every agent in the flow
+    #[arg(long, default_value = "openai/gpt-4o-mini")]
+    model: String,
+
+    /// Flow context variable as KEY=VALUE; VALUE is parsed as JSON when possible. Repeatable.
+    #[arg(long = "context", value_parser = parse_context_var)]
+    context: Vec<(String, serde_json::Value)>,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ProviderKind {
+    Openai,
+    Openrouter,
+    Azure,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+fn parse_context_var(raw: &str) -> Result<(String, serde_json::Value), String> {
+    let (key, value) = raw
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got `{raw}`"))?;
+    let value = serde_json::from_str(value).unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+    Ok((key.to_string(), value))
+}
+
+/// `DENKWERK_SCRIPTED=1` swaps the real provider for one that echoes the task, for testing flows
+/// without API keys.
+fn build_provider(kind: ProviderKind) -> Result<Arc<dyn LLMProvider>, String> {
+    if std::env::var("DENKWERK_SCRIPTED").is_ok_and(|value| value == "1") {
+        let mut provider = ScriptedProvider::new();
+        provider.echo_user_messages();
+        return Ok(Arc::new(provider));
+    }
+
+    let provider: Arc<dyn LLMProvider> = match kind {
+        ProviderKind::Openai => Arc::new(OpenAI::from_env().map_err(|e| e.to_string())?),
+        ProviderKind::Openrouter => Arc::new(OpenRouter::from_env().map_err(|e| e.to_string())?),
+        ProviderKind::Azure => Arc::new(AzureOpenAI::from_env().map_err(|e| e.to_string())?),
+    };
+    Ok(provider)
+}
+
+async fn run(args: Args) -> Result<(), String> {
+    let yaml = std::fs::read_to_string(&args.file)
+        .map_err(|e| format!("failed to read {}: {e}", args.file.display()))?;
+    let base_dir = args.file.parent().map(PathBuf::from).unwrap_or_default();
+    let builder = FlowBuilder::from_yaml_str(&base_dir, &yaml)
+        .map_err(|e| format!("failed to load flow: {e}"))?;
+    let tool_registries = builder
+        .build_tool_registries(&HashMap::new())
+        .map_err(|e| format!("failed to load tools: {e}"))?;
+
+    let model = args.model;
+    let provider = ProxyProvider::new(build_provider(args.provider)?).with_request_transform(
+        move |mut request| {
+            request.model = model.clone();
+            request
+        },
+    );
+
+    let ctx = args
+        .context
+        .into_iter()
+        .fold(FlowContext::default(), |ctx, (key, value)| ctx.with_var(key, value));
+
+    let (run, tool_runs) = builder
+        .run_sequential_flow(
+            &args.flow,
+            &ctx,
+            &tool_registries,
+            Arc::new(provider),
+            args.task,
+            None::<fn(&SequentialEvent)>,
+        )
+        .await
+        .map_err(|e| format!("flow `{}` failed: {e}", args.flow))?;
+
+    match args.output_format {
+        OutputFormat::Text => println!("{}", run.final_output.unwrap_or_default()),
+        OutputFormat::Json => {
+            let output = serde_json::json!({
+                "final_output": run.final_output,
+                "tool_runs": tool_runs,
+            });
+            println!("{}", serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?);
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Args::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
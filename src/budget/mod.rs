@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{
+    history::{CharEstimateTokenCounter, TokenCounter},
+    providers::LLMProvider,
+    types::{
+        CompletionRequest, CompletionResponse, CompletionStream, EmbeddingRequest,
+        EmbeddingResponse, ImageUploadRequest, ImageUploadResponse, ModelInfo,
+        ProviderCapabilities,
+    },
+    LLMError,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BudgetError {
+    #[error("insufficient token budget: requested {requested}, {remaining} remaining")]
+    InsufficientBudget { remaining: u32, requested: u32 },
+}
+
+/// Per-user token quotas shared across every provider and agent of an application.
+/// Users without a budget have nothing to spend.
+#[derive(Debug, Default)]
+pub struct BudgetManager {
+    budgets: Arc<RwLock<HashMap<String, u32>>>,
+}
+
+impl BudgetManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `tokens` on top of the user's remaining budget.
+    pub fn add_budget(&self, user_id: &str, tokens: u32) {
+        let mut budgets = self.budgets.write().unwrap();
+        let remaining = budgets.entry(user_id.to_string()).or_default();
+        *remaining = remaining.saturating_add(tokens);
+    }
+
+    /// Replaces the user's remaining budget with `tokens`.
+    pub fn reset(&self, user_id: &str, tokens: u32) {
+        self.budgets.write().unwrap().insert(user_id.to_string(), tokens);
+    }
+
+    pub fn remaining(&self, user_id: &str) -> Option<u32> {
+        self.budgets.read().unwrap().get(user_id).copied()
+    }
+
+    /// Deducts `tokens` from the user's budget, or leaves it untouched if it can't cover them.
+    pub fn consume(&self, user_id: &str, tokens: u32) -> Result<(), BudgetError> {
+        let mut budgets = self.budgets.write().unwrap();
+        let remaining = budgets.get_mut(user_id);
+        match remaining {
+            Some(remaining) if *remaining >= tokens => {
+                *remaining -= tokens;
+                Ok(())
+            }
+            remaining => Err(BudgetError::InsufficientBudget {
+                remaining: remaining.map_or(0, |remaining| *remaining),
+                requested: tokens,
+            }),
+        }
+    }
+}
+
+/// Charges a user's budget with the estimated prompt size before each completion and rejects
+/// the call with [`LLMError::Budget`] once the budget runs out.
+pub struct BudgetEnforcingProvider<P: LLMProvider> {
+    inner: P,
+    manager: Arc<BudgetManager>,
+    user_id: String,
+    token_counter: Arc<dyn TokenCounter>,
+}
+
+impl<P: LLMProvider> BudgetEnforcingProvider<P> {
+    pub fn new(inner: P, manager: Arc<BudgetManager>, user_id: impl Into<String>) -> Self {
+        Self {
+            inner,
+            manager,
+            user_id: user_id.into(),
+            token_counter: Arc::new(CharEstimateTokenCounter),
+        }
+    }
+
+    /// Replaces the default four-characters-per-token estimate, e.g. with the exact
+    /// `TiktokenCounter` from the `tiktoken` feature.
+    pub fn with_token_counter(mut self, token_counter: Arc<dyn TokenCounter>) -> Self {
+        self.token_counter = token_counter;
+        self
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    fn charge(&self, request: &CompletionRequest) -> Result<(), LLMError> {
+        let estimated_tokens = self.token_counter.count(&request.messages);
+        self.manager.consume(&self.user_id, estimated_tokens)?;
+        Ok(())
+    }
+}
+
+/// Utility trait for putting any provider behind a user's token budget
+pub trait WithBudget: LLMProvider + Sized {
+    fn with_budget_manager(
+        self,
+        manager: Arc<BudgetManager>,
+        user_id: impl Into<String>,
+    ) -> BudgetEnforcingProvider<Self> {
+        BudgetEnforcingProvider::new(self, manager, user_id)
+    }
+}
+
+impl<P: LLMProvider> WithBudget for P {}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for BudgetEnforcingProvider<P> {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.charge(&request)?;
+        self.inner.complete(request).await
+    }
+
+    async fn stream_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionStream, LLMError> {
+        self.charge(&request)?;
+        self.inner.stream_completion(request).await
+    }
+
+    async fn upload_image(
+        &self,
+        request: ImageUploadRequest,
+    ) -> Result<ImageUploadResponse, LLMError> {
+        self.inner.upload_image(request).await
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, LLMError> {
+        self.inner.create_embeddings(request).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
+        self.inner.model_info(id).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LLMError> {
+        self.inner.list_models().await
+    }
+
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        self.inner.count_tokens(request).await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{providers::scripted::ScriptedProvider, types::ChatMessage};
+
+    #[test]
+    fn consume_draws_down_and_rejects_overdraft() {
+        let manager = BudgetManager::new();
+        manager.add_budget("alice", 10);
+        manager.add_budget("alice", 5);
+
+        manager.consume("alice", 12).unwrap();
+        assert_eq!(manager.remaining("alice"), Some(3));
+        assert_eq!(
+            manager.consume("alice", 4),
+            Err(BudgetError::InsufficientBudget { remaining: 3, requested: 4 })
+        );
+        assert_eq!(manager.remaining("alice"), Some(3));
+
+        manager.reset("alice", 1);
+        assert_eq!(manager.remaining("alice"), Some(1));
+        assert_eq!(manager.remaining("bob"), None);
+        assert_eq!(
+            manager.consume("bob", 1),
+            Err(BudgetError::InsufficientBudget { remaining: 0, requested: 1 })
+        );
+    }
+
+    #[tokio::test]
+    async fn provider_spends_the_last_token_then_rejects() {
+        let manager = Arc::new(BudgetManager::new());
+        // "abcd" is estimated at one token.
+        manager.add_budget("alice", 2);
+        let mut scripted = ScriptedProvider::new();
+        scripted.echo_user_messages();
+        let provider = scripted.with_budget_manager(Arc::clone(&manager), "alice");
+        let request = CompletionRequest::new("m", vec![ChatMessage::user("abcd")]);
+
+        provider.complete(request.clone()).await.unwrap();
+        provider.complete(request.clone()).await.unwrap();
+        assert_eq!(manager.remaining("alice"), Some(0));
+
+        let error = provider.complete(request).await.unwrap_err();
+        assert!(matches!(
+            error,
+            LLMError::Budget(BudgetError::InsufficientBudget { remaining: 0, requested: 1 })
+        ));
+        assert_eq!(provider.inner().calls(), 2);
+    }
+}
@@ -1,6 +1,9 @@
+use std::time::Duration;
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum LLMError {
     #[error("http error: {0}")]
     Http(#[from] reqwest::Error),
@@ -20,6 +23,10 @@ pub enum LLMError {
     #[error("operation not supported: {0}")]
     Unsupported(&'static str),
 
+    /// Providers were set up inconsistently, e.g. a name that was never registered.
+    #[error("configuration error: {0}")]
+    Configuration(String),
+
     #[error("unknown function: {0}")]
     UnknownFunction(String),
 
@@ -28,4 +35,197 @@ pub enum LLMError {
 
     #[error("kernel function execution failed ({function}): {message}")]
     FunctionExecution { function: String, message: String },
+
+    #[error("operation timed out")]
+    Timeout,
+
+    #[error("permission denied: {0}")]
+    PermissionDenied(String),
+
+    /// A response still broke the agent's output schema once its fix-up retries ran out;
+    /// holds the validation errors.
+    #[error("response did not match output schema: {0}")]
+    SchemaViolation(String),
+
+    #[error("circuit breaker is open; provider calls are failing fast")]
+    CircuitOpen,
+
+    #[error("no provider is available; all are cooling down after recent errors")]
+    Unavailable,
+
+    /// HTTP 429 from the provider, with the delay its `Retry-After` header asked for.
+    /// Returned by the OpenAI, Azure OpenAI, Gemini and OpenRouter providers; the others
+    /// report rate limits as [`LLMError::Provider`] or [`LLMError::Http`].
+    #[error("rate limited by provider: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+
+    #[error("{0}")]
+    Budget(#[from] crate::budget::BudgetError),
+
+    /// An error raised while `agent` ran its `turn`-th (zero-based) orchestration step; see
+    /// [`LLMError::with_agent_context`].
+    #[error("agent `{agent}` failed at turn {turn}: {inner}")]
+    WithContext {
+        inner: Box<LLMError>,
+        agent: String,
+        turn: usize,
+    },
+}
+
+/// Markers in `LLMError::Provider` messages (lowercased) that point at an overloaded or
+/// rate-limited backend rather than a bad request.
+const TRANSIENT_PROVIDER_MARKERS: [&str; 4] = ["429", "503", "timeout", "rate limit"];
+
+impl LLMError {
+    /// Wrap this error with the agent and turn that produced it.
+    pub fn with_agent_context(self, agent_name: String, turn: usize) -> LLMError {
+        LLMError::WithContext {
+            inner: Box::new(self),
+            agent: agent_name,
+            turn,
+        }
+    }
+
+    /// The innermost error beneath any [`LLMError::WithContext`] wrappers.
+    pub fn root_cause(&self) -> &LLMError {
+        let mut error = self;
+        while let LLMError::WithContext { inner, .. } = error {
+            error = inner;
+        }
+        error
+    }
+
+    /// Agent name and turn of the outermost [`LLMError::WithContext`] wrapper, if any.
+    pub fn agent_context(&self) -> Option<(&str, usize)> {
+        match self {
+            LLMError::WithContext { agent, turn, .. } => Some((agent, *turn)),
+            _ => None,
+        }
+    }
+
+    /// Whether the same request may succeed if sent again later: timeouts, rate limits,
+    /// unavailable backends and an open circuit breaker.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            LLMError::Http(error) => {
+                error.is_timeout()
+                    || error.is_connect()
+                    || error
+                        .status()
+                        .is_some_and(|status| status.as_u16() == 429 || status.is_server_error())
+            }
+            LLMError::Provider(message) => {
+                let message = message.to_lowercase();
+                TRANSIENT_PROVIDER_MARKERS
+                    .iter()
+                    .any(|marker| message.contains(marker))
+            }
+            LLMError::Timeout
+            | LLMError::CircuitOpen
+            | LLMError::Unavailable
+            | LLMError::RateLimited { .. } => true,
+            LLMError::WithContext { inner, .. } => inner.is_transient(),
+            LLMError::Serialization(_)
+            | LLMError::MissingApiKey(_)
+            | LLMError::InvalidResponse(_)
+            | LLMError::Unsupported(_)
+            | LLMError::Configuration(_)
+            | LLMError::UnknownFunction(_)
+            | LLMError::InvalidFunctionArguments(_)
+            | LLMError::FunctionExecution { .. }
+            | LLMError::PermissionDenied(_)
+            | LLMError::SchemaViolation(_)
+            | LLMError::Budget(_) => false,
+        }
+    }
+
+    /// How long the provider asked callers to wait before retrying, if it said.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.root_cause() {
+            LLMError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// [`LLMError::is_transient`], plus failed function executions, which usually wrap a call
+    /// to an external API that may succeed on a second attempt.
+    pub fn is_retryable(&self) -> bool {
+        self.is_transient() || matches!(self.root_cause(), LLMError::FunctionExecution { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::BudgetError;
+
+    #[test]
+    fn classifies_transient_errors() {
+        let transient = [
+            LLMError::Provider("unexpected status 503 Service Unavailable: busy".to_string()),
+            LLMError::Provider("unexpected status 429 Too Many Requests: slow down".to_string()),
+            LLMError::Provider("upstream Timeout".to_string()),
+            LLMError::Provider("rate limited".to_string()),
+            LLMError::Timeout,
+            LLMError::CircuitOpen,
+            LLMError::Unavailable,
+            LLMError::RateLimited {
+                message: "slow down".to_string(),
+                retry_after: None,
+            },
+        ];
+        for error in &transient {
+            assert!(error.is_transient(), "{error} should be transient");
+            assert!(error.is_retryable(), "{error} should be retryable");
+        }
+
+        let permanent = [
+            LLMError::Provider("unexpected status 400 Bad Request: bad model".to_string()),
+            LLMError::Serialization(serde_json::from_str::<u32>("x").unwrap_err()),
+            LLMError::MissingApiKey("OPENAI_API_KEY"),
+            LLMError::InvalidResponse("no choices"),
+            LLMError::Unsupported("embeddings"),
+            LLMError::UnknownFunction("nope".to_string()),
+            LLMError::InvalidFunctionArguments("missing text".to_string()),
+            LLMError::PermissionDenied("analyst".to_string()),
+            LLMError::Budget(BudgetError::InsufficientBudget { remaining: 1, requested: 2 }),
+        ];
+        for error in &permanent {
+            assert!(!error.is_transient(), "{error} should not be transient");
+            assert!(!error.is_retryable(), "{error} should not be retryable");
+        }
+    }
+
+    #[test]
+    fn function_execution_is_retryable_but_not_transient() {
+        let error = LLMError::FunctionExecution {
+            function: "weather".to_string(),
+            message: "handler returned 502".to_string(),
+        };
+        assert!(!error.is_transient());
+        assert!(error.is_retryable());
+    }
+
+    #[test]
+    fn context_wrappers_keep_root_cause_and_classification() {
+        let error = LLMError::Timeout
+            .with_agent_context("researcher".to_string(), 2)
+            .with_agent_context("planner".to_string(), 0);
+
+        assert!(matches!(error.root_cause(), LLMError::Timeout));
+        assert_eq!(error.agent_context(), Some(("planner", 0)));
+        let LLMError::WithContext { inner, .. } = &error else {
+            panic!("expected context wrapper");
+        };
+        assert_eq!(inner.agent_context(), Some(("researcher", 2)));
+        assert!(error.is_transient());
+        assert_eq!(
+            error.to_string(),
+            "agent `planner` failed at turn 0: agent `researcher` failed at turn 2: operation timed out"
+        );
+        assert_eq!(LLMError::Timeout.agent_context(), None);
+    }
 }
@@ -1,3 +1,4 @@
+pub mod adversarial;
 pub mod scenario;
 pub mod runner;
 pub mod report;
\ No newline at end of file
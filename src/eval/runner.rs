@@ -84,15 +84,22 @@ impl EvalRunner {
 
         // Create dummy agents
         for name in &agent_names {
-            let agent = Agent::from_string(name.clone(), format!("You are agent {}.", name));
+            let agent = Agent::from_string(name.clone(), scenario_agent_prompt(name));
             orchestrator.register_agent(agent);
         }
 
         // Collect events
         let actual_events = Arc::new(Mutex::new(Vec::new()));
         let actual_events_clone = Arc::clone(&actual_events);
+        let tool_calls = Arc::new(Mutex::new(Vec::new()));
+        let tool_calls_clone = Arc::clone(&tool_calls);
+        // Expected traces don't describe tool calls, so they are left out of the comparison.
         orchestrator = orchestrator.with_event_callback(move |event| {
-            actual_events_clone.lock().unwrap().push(event.clone());
+            if let HandoffEvent::ToolCall { function, .. } = event {
+                tool_calls_clone.lock().unwrap().push(function.clone());
+            } else {
+                actual_events_clone.lock().unwrap().push(event.clone());
+            }
         });
 
         let mut session = orchestrator
@@ -140,6 +147,21 @@ impl EvalRunner {
             }
         }
 
+        // Check oracle
+        if let Some(oracle) = &scenario.oracle {
+            let reply = result.as_ref().ok().and_then(|turn| turn.reply.as_deref()).unwrap_or_default();
+            for needle in &oracle.final_not_contains {
+                if reply.contains(needle.as_str()) {
+                    failures.push(format!("Final reply contains forbidden '{}'", needle));
+                }
+            }
+            for function in tool_calls.lock().unwrap().iter() {
+                if oracle.forbidden_tools.contains(function) {
+                    failures.push(format!("Forbidden tool called: {}", function));
+                }
+            }
+        }
+
         CaseReport {
             name: scenario.name.clone(),
             pass: failures.is_empty(),
@@ -166,7 +188,7 @@ impl EvalRunner {
 
         // Create dummy agents
         for name in &agent_names {
-            let agent = Agent::from_string(name.clone(), format!("You are agent {}.", name));
+            let agent = Agent::from_string(name.clone(), scenario_agent_prompt(name));
             orchestrator.register_agent(agent);
         }
 
@@ -205,6 +227,11 @@ impl EvalRunner {
     }
 }
 
+/// System prompt of the stand-in agents the runner registers for a scenario.
+pub(crate) fn scenario_agent_prompt(name: &str) -> String {
+    format!("You are agent {}.", name)
+}
+
 fn matches_step(expect: &ExpectStep, actual: &HandoffEvent) -> bool {
     match (expect, actual) {
         (ExpectStep::Msg { agent, contains }, HandoffEvent::Message { agent: a, message: m }) => {
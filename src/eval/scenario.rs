@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::bench::OracleSpec;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EvalScenario {
     pub name: String,
@@ -8,6 +10,10 @@ pub struct EvalScenario {
     pub user_input: String,
     pub scripted: Vec<ScriptedTurn>,
     pub expect: ExpectedTrace,
+    /// Checked against the final reply and the tools called; only `final_not_contains` and
+    /// `forbidden_tools` are enforced by [`crate::EvalRunner`].
+    #[serde(default)]
+    pub oracle: Option<OracleSpec>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
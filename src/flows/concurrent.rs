@@ -1,13 +1,14 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
+use async_trait::async_trait;
 use futures_util::{stream::FuturesUnordered, StreamExt};
 
 use crate::{
     agents::{Agent, AgentError},
     metrics::{AgentMetrics, ExecutionTimer, MetricsCollector, WithMetrics},
     skills::SkillRuntime,
-    types::ChatMessage,
-    LLMProvider,
+    types::{ChatMessage, CompletionRequest},
+    LLMError, LLMProvider,
 };
 
 use super::handoffflow::AgentAction;
@@ -17,6 +18,8 @@ use crate::shared_state::SharedStateContext;
 pub enum ConcurrentEvent {
     Message { agent: String, output: String },
     Completed { agent: String, output: Option<String> },
+    /// The configured [`ConcurrentAggregator`] merged all results into `output`.
+    Aggregated { output: String },
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +34,101 @@ pub struct ConcurrentRun {
     pub events: Vec<ConcurrentEvent>,
     pub transcript: Vec<ChatMessage>,
     pub metrics: Option<Vec<AgentMetrics>>,
+    /// Set when the orchestrator has an aggregator.
+    pub aggregated_output: Option<String>,
+}
+
+/// Merges the results of a concurrent run into one output. Results are passed in the order
+/// the agents were registered, not the order they finished in.
+#[async_trait]
+pub trait ConcurrentAggregator: Send + Sync {
+    async fn aggregate(&self, results: &[ConcurrentResult]) -> Result<String, LLMError>;
+}
+
+/// Joins every output with `separator`, skipping agents that returned nothing.
+#[derive(Debug, Clone)]
+pub struct ConcatAggregator {
+    pub separator: String,
+}
+
+#[async_trait]
+impl ConcurrentAggregator for ConcatAggregator {
+    async fn aggregate(&self, results: &[ConcurrentResult]) -> Result<String, LLMError> {
+        Ok(results
+            .iter()
+            .filter_map(|result| result.output.as_deref())
+            .collect::<Vec<_>>()
+            .join(&self.separator))
+    }
+}
+
+/// Lists outputs from the highest to the lowest weighted agent, each labelled with its
+/// weight. Agents without a weight count as 1.0; agents weighted 0 or less are dropped.
+#[derive(Debug, Clone, Default)]
+pub struct WeightedConcatAggregator {
+    pub weights: HashMap<String, f64>,
+}
+
+#[async_trait]
+impl ConcurrentAggregator for WeightedConcatAggregator {
+    async fn aggregate(&self, results: &[ConcurrentResult]) -> Result<String, LLMError> {
+        let mut weighted: Vec<(f64, &str, &str)> = results
+            .iter()
+            .filter_map(|result| {
+                let weight = self.weights.get(&result.agent).copied().unwrap_or(1.0);
+                let output = result.output.as_deref()?;
+                (weight > 0.0).then_some((weight, result.agent.as_str(), output))
+            })
+            .collect();
+        weighted.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        Ok(weighted
+            .iter()
+            .map(|(weight, agent, output)| format!("{agent} (weight {weight:.2}): {output}"))
+            .collect::<Vec<_>>()
+            .join("\n\n"))
+    }
+}
+
+/// Asks `provider` to synthesize the outputs. `{outputs}` in `prompt_template` is replaced
+/// with one `agent: output` line per result.
+#[derive(Clone)]
+pub struct SummaryAggregator {
+    pub provider: Arc<dyn LLMProvider>,
+    pub model: String,
+    pub prompt_template: String,
+}
+
+impl SummaryAggregator {
+    pub fn new(provider: Arc<dyn LLMProvider>, model: impl Into<String>) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            prompt_template: "Combine these answers into a single response:\n\n{outputs}".to_string(),
+        }
+    }
+
+    pub fn with_prompt_template(mut self, template: impl Into<String>) -> Self {
+        self.prompt_template = template.into();
+        self
+    }
+}
+
+#[async_trait]
+impl ConcurrentAggregator for SummaryAggregator {
+    async fn aggregate(&self, results: &[ConcurrentResult]) -> Result<String, LLMError> {
+        let outputs = results
+            .iter()
+            .filter_map(|result| Some(format!("{}: {}", result.agent, result.output.as_deref()?)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt = self.prompt_template.replace("{outputs}", &outputs);
+        let response = self
+            .provider
+            .complete(CompletionRequest::new(self.model.clone(), vec![ChatMessage::user(prompt)]))
+            .await?;
+        Ok(response.message.text().unwrap_or_default().to_string())
+    }
 }
 
 pub struct ConcurrentOrchestrator {
@@ -41,6 +139,7 @@ pub struct ConcurrentOrchestrator {
     shared_state: Option<Arc<dyn SharedStateContext>>,
     skill_runtime: Option<Arc<SkillRuntime>>,
     metrics_collector: Option<Arc<dyn MetricsCollector>>,
+    aggregator: Option<Arc<dyn ConcurrentAggregator>>,
 }
 
 impl ConcurrentOrchestrator {
@@ -53,6 +152,7 @@ impl ConcurrentOrchestrator {
             shared_state: None,
             skill_runtime: None,
             metrics_collector: None,
+            aggregator: None,
         }
     }
 
@@ -83,6 +183,12 @@ impl ConcurrentOrchestrator {
         self
     }
 
+    /// Merge the results of every run into [`ConcurrentRun::aggregated_output`].
+    pub fn with_aggregator(mut self, aggregator: Arc<dyn ConcurrentAggregator>) -> Self {
+        self.aggregator = Some(aggregator);
+        self
+    }
+
     pub fn shared_state(&self) -> Option<&Arc<dyn SharedStateContext>> {
         self.shared_state.as_ref()
     }
@@ -99,11 +205,17 @@ impl ConcurrentOrchestrator {
     }
 
     pub async fn run(&self, task: impl Into<String>) -> Result<ConcurrentRun, AgentError> {
+        let run = self.run_agents(task.into());
+        #[cfg(feature = "tracing")]
+        let run = tracing::Instrument::instrument(run, tracing::info_span!("concurrent.run"));
+        run.await
+    }
+
+    async fn run_agents(&self, task: String) -> Result<ConcurrentRun, AgentError> {
         if self.agents.is_empty() {
             return Err(AgentError::NoAgentsRegistered);
         }
 
-        let task = task.into();
         let mut transcript = vec![ChatMessage::user(task.clone())];
         let mut events = Vec::new();
         let mut results = Vec::new();
@@ -181,7 +293,8 @@ impl ConcurrentOrchestrator {
                                 collector.record_metrics(m.clone());
                             }
                         }
-                        Err(AgentError::from(err))
+                        // Every agent gets a single turn in a concurrent run.
+                        Err(AgentError::from(err.with_agent_context(agent.name().to_string(), 0)))
                     }
                 }
             });
@@ -237,11 +350,28 @@ impl ConcurrentOrchestrator {
             }
         }
 
+        let aggregated_output = match &self.aggregator {
+            Some(aggregator) => {
+                let ordered: Vec<ConcurrentResult> = self
+                    .agents
+                    .iter()
+                    .filter_map(|agent| results.iter().find(|result| result.agent == agent.name()).cloned())
+                    .collect();
+                let output = aggregator.aggregate(&ordered).await?;
+                let event = ConcurrentEvent::Aggregated { output: output.clone() };
+                self.emit_event(&event);
+                events.push(event);
+                Some(output)
+            }
+            None => None,
+        };
+
         Ok(ConcurrentRun {
             results,
             events,
             transcript,
             metrics: collected_metrics,
+            aggregated_output,
         })
     }
 }
@@ -274,7 +404,8 @@ mod tests {
         LLMError,
     };
 
-    use super::{ConcurrentEvent, ConcurrentOrchestrator};
+    use super::{ConcatAggregator, ConcurrentEvent, ConcurrentOrchestrator, ConcurrentResult};
+    use super::{ConcurrentAggregator, WeightedConcatAggregator};
 
     struct TestProvider {
         responses: Mutex<Vec<(String, Option<Duration>)>>,
@@ -337,6 +468,48 @@ mod tests {
         assert_eq!(run.transcript.len(), 3); // user + two replies
     }
 
+    #[tokio::test]
+    async fn concat_aggregator_joins_outputs_in_agent_order() {
+        let provider: Arc<dyn LLMProvider> = Arc::new(TestProvider::new(vec![
+            ("Wear a jacket.".to_string(), Some(Duration::from_millis(40))),
+            ("Rain after noon.".to_string(), None),
+        ]));
+
+        let orchestrator = ConcurrentOrchestrator::new(provider, "model")
+            .with_agents(vec![
+                Agent::from_string("Stylist", "Suggest clothes."),
+                Agent::from_string("Forecaster", "Predict the weather."),
+            ])
+            .with_aggregator(Arc::new(ConcatAggregator {
+                separator: "\n\n---\n\n".to_string(),
+            }));
+
+        let run = orchestrator.run("Plan my day").await.expect("run should succeed");
+
+        let expected = "Wear a jacket.\n\n---\n\nRain after noon.";
+        assert_eq!(run.aggregated_output.as_deref(), Some(expected));
+        assert!(matches!(run.events.last(), Some(ConcurrentEvent::Aggregated { output }) if output == expected));
+    }
+
+    #[tokio::test]
+    async fn weighted_aggregator_orders_by_weight_and_drops_zero_weights() {
+        let result = |agent: &str, output: &str| ConcurrentResult {
+            agent: agent.to_string(),
+            output: Some(output.to_string()),
+        };
+        let aggregator = WeightedConcatAggregator {
+            weights: [("junior".to_string(), 0.25), ("senior".to_string(), 0.75), ("spam".to_string(), 0.0)]
+                .into_iter()
+                .collect(),
+        };
+
+        let output = aggregator
+            .aggregate(&[result("junior", "maybe"), result("spam", "buy now"), result("senior", "yes")])
+            .await
+            .unwrap();
+        assert_eq!(output, "senior (weight 0.75): yes\n\njunior (weight 0.25): maybe");
+    }
+
     #[tokio::test]
     async fn errors_when_no_agents() {
         let provider: Arc<dyn LLMProvider> = Arc::new(TestProvider::new(vec![]));
@@ -5,12 +5,17 @@ use crate::{
     metrics::{AgentMetrics, ExecutionTimer, MetricsCollector, WithMetrics},
     skills::SkillRuntime,
     types::ChatMessage,
-    LLMProvider,
+    LLMError, LLMProvider,
 };
 
-use super::handoffflow::AgentAction;
+use super::handoffflow::{AgentAction, AgentTurn};
 use crate::shared_state::SharedStateContext;
 
+const MODERATION_PROMPT: &str = "Does this message violate any guidelines? Reply OK or REMOVE";
+
+/// Rejected attempts in a row after which an agent loses its turn.
+const MAX_MODERATION_ATTEMPTS: usize = 3;
+
 pub trait GroupChatManager: Send + Sync {
     /// Called before the orchestration starts so the manager can reset its state.
     fn on_start(&mut self, roster: &[Agent]);
@@ -115,6 +120,8 @@ pub enum GroupChatEvent {
     AgentMessage { agent: String, message: String },
     AgentCompletion { agent: String, message: Option<String> },
     UserMessage { message: String },
+    /// The moderator rejected a message from `agent`; it never entered the transcript.
+    MessageModerated { agent: String, reason: String },
     Terminated { reason: String },
 }
 
@@ -137,6 +144,7 @@ pub struct GroupChatOrchestrator<M: GroupChatManager + 'static> {
     shared_state: Option<Arc<dyn SharedStateContext>>,
     skill_runtime: Option<Arc<SkillRuntime>>,
     metrics_collector: Option<Arc<dyn MetricsCollector>>,
+    moderator: Option<Agent>,
 }
 
 impl<M: GroupChatManager + 'static> GroupChatOrchestrator<M> {
@@ -151,6 +159,7 @@ impl<M: GroupChatManager + 'static> GroupChatOrchestrator<M> {
             shared_state: None,
             skill_runtime: None,
             metrics_collector: None,
+            moderator: None,
         }
     }
 
@@ -198,6 +207,14 @@ impl<M: GroupChatManager + 'static> GroupChatOrchestrator<M> {
         self
     }
 
+    /// Have `moderator` review every agent message before it enters the transcript. A
+    /// rejected message is dropped and the agent asked again; after three rejections in a
+    /// row the agent loses its turn. The moderator never takes part in the chat.
+    pub fn with_moderator(mut self, moderator: Agent) -> Self {
+        self.moderator = Some(moderator);
+        self
+    }
+
     fn emit_event(&self, event: &GroupChatEvent) {
         if let Some(callback) = &self.event_callback {
             callback(event);
@@ -278,30 +295,52 @@ impl<M: GroupChatManager + 'static> GroupChatOrchestrator<M> {
                 .skill_runtime
                 .as_ref()
                 .and_then(|runtime| runtime.registry_for_agent(&agent, history.as_ref()));
-            let turn = agent
-                .execute_with_tools(
-                    self.provider.as_ref(),
-                    &self.model,
-                    history.as_ref(),
-                    skill_tools.as_ref(),
-                    None,
-                )
-                .await;
-
-            let turn = match turn {
-                Ok(turn) => turn,
-                Err(err) => {
-                    if let (Some(ref mut m), Some(collector)) = (&mut metrics, &self.metrics_collector) {
-                        m.record_error(&err);
-                        m.execution.total_duration = execution_timer.elapsed();
-                        m.finalize(false, final_output.as_ref().map(|s| s.len()).unwrap_or(0), rounds);
-                        collector.record_metrics(m.clone());
+
+            let mut rejections = 0;
+            let turn = loop {
+                let turn = agent
+                    .execute_with_tools(
+                        self.provider.as_ref(),
+                        &self.model,
+                        history.as_ref(),
+                        skill_tools.as_ref(),
+                        None,
+                    )
+                    .await;
+                let turn = match self.moderate(turn).await {
+                    Ok(turn) => turn,
+                    Err(err) => {
+                        if let (Some(ref mut m), Some(collector)) = (&mut metrics, &self.metrics_collector) {
+                            m.record_error(&err);
+                            m.execution.total_duration = execution_timer.elapsed();
+                            m.finalize(false, final_output.as_ref().map(|s| s.len()).unwrap_or(0), rounds);
+                            collector.record_metrics(m.clone());
+                        }
+                        return Err(AgentError::Provider(err));
+                    }
+                };
+
+                match turn {
+                    Ok(turn) => break Some(turn),
+                    Err(reason) => {
+                        let event = GroupChatEvent::MessageModerated {
+                            agent: agent.name().to_string(),
+                            reason,
+                        };
+                        self.emit_event(&event);
+                        events.push(event);
+                        rejections += 1;
+                        if rejections == MAX_MODERATION_ATTEMPTS {
+                            break None;
+                        }
                     }
-                    return Err(AgentError::Provider(err));
                 }
             };
 
             rounds += 1;
+            let Some(turn) = turn else {
+                continue;
+            };
 
             if let (Some(ref mut m), Some(usage)) = (&mut metrics, turn.usage.as_ref()) {
                 let input_cost = m.token_usage.cost_per_input_token;
@@ -380,6 +419,40 @@ impl<M: GroupChatManager + 'static> GroupChatOrchestrator<M> {
     }
 }
 
+impl<M: GroupChatManager + 'static> GroupChatOrchestrator<M> {
+    /// Pass `turn` through the moderator, if there is one. The inner `Err` carries the
+    /// moderator's reply when it rejected the message.
+    async fn moderate(
+        &self,
+        turn: Result<AgentTurn, LLMError>,
+    ) -> Result<Result<AgentTurn, String>, LLMError> {
+        let turn = turn?;
+        let Some(moderator) = &self.moderator else {
+            return Ok(Ok(turn));
+        };
+        let message = match &turn.action {
+            AgentAction::Respond { message } => message.as_str(),
+            AgentAction::HandOff { message, .. } | AgentAction::Complete { message } => {
+                match message.as_deref() {
+                    Some(message) => message,
+                    None => return Ok(Ok(turn)),
+                }
+            }
+        };
+
+        let request = ChatMessage::user(format!("{MODERATION_PROMPT}\n\n{message}"));
+        let verdict = moderator
+            .execute(self.provider.as_ref(), &self.model, &[request])
+            .await?
+            .raw_content;
+        if verdict.trim().to_ascii_uppercase().starts_with("REMOVE") {
+            Ok(Err(verdict.trim().to_string()))
+        } else {
+            Ok(Ok(turn))
+        }
+    }
+}
+
 fn push_agent_message(transcript: &mut Vec<ChatMessage>, agent: &Agent, content: &str) {
     let mut message = ChatMessage::assistant(content.to_string());
     message.name = Some(agent.name().to_string());
@@ -516,6 +589,74 @@ mod tests {
         assert_eq!(user_messages.lock().unwrap().len(), 1);
         assert!(run.transcript.iter().any(|msg| matches!(msg.role, crate::types::MessageRole::User) && msg.text() == Some("User clarifies")));
     }
+
+    /// Agents take their replies from the queue; moderation requests get `REMOVE` when the
+    /// message under review mentions spam.
+    struct ModeratedProvider {
+        agent_replies: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for ModeratedProvider {
+        async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+            let last = request.messages.last().and_then(|message| message.text()).unwrap_or_default();
+            let content = if last.starts_with(super::MODERATION_PROMPT) {
+                if last.contains("spam") { "REMOVE: spam" } else { "OK" }.to_string()
+            } else {
+                self.agent_replies.lock().unwrap().remove(0)
+            };
+
+            Ok(CompletionResponse {
+                message: ChatMessage::assistant(content),
+                usage: None,
+                reasoning: None,
+            })
+        }
+
+        fn name(&self) -> &'static str {
+            "moderated"
+        }
+    }
+
+    fn moderated_chat(agent_replies: &[&str]) -> GroupChatOrchestrator<RoundRobinGroupChatManager> {
+        let provider: Arc<dyn LLMProvider> = Arc::new(ModeratedProvider {
+            agent_replies: Mutex::new(agent_replies.iter().map(|reply| reply.to_string()).collect()),
+        });
+        let manager = RoundRobinGroupChatManager::new().with_maximum_rounds(Some(1));
+        GroupChatOrchestrator::new(provider, "model", manager)
+            .with_agents(vec![Agent::from_string("Writer", "Draft copy.")])
+            .with_moderator(Agent::from_string("Moderator", "Enforce the guidelines."))
+    }
+
+    #[tokio::test]
+    async fn moderator_removes_message_and_agent_retries() {
+        let mut orchestrator = moderated_chat(&["spam content", "clean content"]);
+        let run = orchestrator.run("Create a slogan").await.expect("run should succeed");
+
+        assert!(matches!(
+            run.events.first(),
+            Some(GroupChatEvent::MessageModerated { agent, reason }) if agent == "Writer" && reason == "REMOVE: spam"
+        ));
+        assert_eq!(run.final_output.as_deref(), Some("clean content"));
+        assert!(run.transcript.iter().all(|message| !message.text().unwrap_or_default().contains("spam")));
+        assert!(run.transcript.iter().all(|message| message.name.as_deref() != Some("Moderator")));
+    }
+
+    #[tokio::test]
+    async fn three_rejections_skip_the_turn() {
+        let mut orchestrator = moderated_chat(&["spam", "more spam", "still spam"]);
+        let run = orchestrator.run("Create a slogan").await.expect("run should succeed");
+
+        let moderated = run
+            .events
+            .iter()
+            .filter(|event| matches!(event, GroupChatEvent::MessageModerated { .. }))
+            .count();
+        assert_eq!(moderated, 3);
+        assert_eq!(run.rounds, 1);
+        assert!(run.final_output.is_none());
+        assert!(!run.events.iter().any(|event| matches!(event, GroupChatEvent::AgentMessage { .. })));
+    }
 }
 
 impl<M: GroupChatManager + 'static> WithMetrics for GroupChatOrchestrator<M> {
@@ -13,10 +13,11 @@ use crate::{
     eval::scenario::DecisionSource,
     functions::{FunctionRegistry, ToolChoice, json_schema_for, to_value},
     skills::SkillRuntime,
-    types::{ChatMessage, TokenUsage},
-    Agent, AgentError, LLMError, LLMProvider,
+    types::{ChatMessage, CorrelationId, TokenUsage},
+    Agent, AgentCapability, AgentError, LLMError, LLMProvider,
 };
 
+use crate::history::{ChatHistory, ChatHistorySnapshot};
 use crate::shared_state::SharedStateContext;
 use crate::metrics::{AgentMetrics, ExecutionTimer, MetricsCollector, WithMetrics};
 
@@ -39,6 +40,9 @@ pub enum HandoffMatcher {
 
     /// Full-power check: (transcript, last_assistant_text) -> Option<Directive>
     Predicate(Arc<dyn Fn(&[ChatMessage], &str) -> Option<HandoffDirective> + Send + Sync>),
+
+    /// The assistant text asks for this capability, per the capability router's synonyms
+    RequiresCapability(AgentCapability),
 }
 
 /// One rule = matcher + target resolver (static or dynamic)
@@ -65,11 +69,99 @@ impl HandoffRule {
     }
 }
 
+static DEFAULT_CAPABILITY_ROUTER: Lazy<CapabilityRouter> = Lazy::new(CapabilityRouter::new);
+
+/// Routes handoffs that describe what the next agent should do ("an agent that can search
+/// the web") instead of naming it. Phrases map to capabilities through a synonym table,
+/// capabilities to the agents that declare them.
+#[derive(Debug, Clone)]
+pub struct CapabilityRouter {
+    agents: HashMap<AgentCapability, Vec<String>>,
+    synonyms: HashMap<String, AgentCapability>,
+}
+
+impl CapabilityRouter {
+    /// A router with no agents and synonyms for the built-in capabilities.
+    pub fn new() -> Self {
+        let synonyms = [
+            ("web search", AgentCapability::WebSearch),
+            ("search the web", AgentCapability::WebSearch),
+            ("browse the web", AgentCapability::WebSearch),
+            ("internet", AgentCapability::WebSearch),
+            ("code execution", AgentCapability::CodeExecution),
+            ("execute code", AgentCapability::CodeExecution),
+            ("run code", AgentCapability::CodeExecution),
+            ("file access", AgentCapability::FileAccess),
+            ("read files", AgentCapability::FileAccess),
+            ("data analysis", AgentCapability::DataAnalysis),
+            ("analyze data", AgentCapability::DataAnalysis),
+            ("image generation", AgentCapability::ImageGeneration),
+            ("generate images", AgentCapability::ImageGeneration),
+        ]
+        .into_iter()
+        .map(|(phrase, capability)| (phrase.to_string(), capability))
+        .collect();
+
+        Self {
+            agents: HashMap::new(),
+            synonyms,
+        }
+    }
+
+    /// Record that `name` has each of `capabilities`.
+    pub fn add_agent(&mut self, name: &str, capabilities: &[AgentCapability]) -> &mut Self {
+        for capability in capabilities {
+            let names = self.agents.entry(capability.clone()).or_default();
+            if !names.iter().any(|existing| existing == name) {
+                names.push(name.to_string());
+            }
+        }
+        self
+    }
+
+    pub fn with_synonym(mut self, phrase: impl Into<String>, capability: AgentCapability) -> Self {
+        self.synonyms.insert(normalize_phrase(&phrase.into()), capability);
+        self
+    }
+
+    /// Agents with `capability`, in the order they were added.
+    pub fn agents_with(&self, capability: &AgentCapability) -> &[String] {
+        self.agents.get(capability).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The capability whose longest synonym appears as whole words in `text`.
+    pub fn capability_for(&self, text: &str) -> Option<&AgentCapability> {
+        let text = format!(" {} ", normalize_phrase(text));
+        self.synonyms
+            .iter()
+            .filter(|(phrase, _)| text.contains(&format!(" {phrase} ")))
+            .max_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| b.cmp(a)))
+            .map(|(_, capability)| capability)
+    }
+}
+
+impl Default for CapabilityRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lowercase words separated by single spaces.
+fn normalize_phrase(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 #[derive(Debug)]
 pub struct HandoffTurn {
     pub reply: Option<String>,
     pub events: Vec<HandoffEvent>,
     pub metrics: Option<AgentMetrics>,
+    /// Shared by every turn of the session that produced it.
+    pub correlation_id: CorrelationId,
 }
 
 struct HandoffFunction;
@@ -317,11 +409,16 @@ pub(crate) struct AgentTurn {
     pub(crate) tool_calls: Vec<crate::functions::ToolCall>,
     pub(crate) usage: Option<TokenUsage>,
     pub(crate) raw_content: String,
+    /// The history was compressed by the agent's auto-summarizer before the call.
+    pub(crate) compressed: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum HandoffEvent {
     Message { agent: String, message: String },
+    /// `agent` called `function` during its turn; the internal `handoff` tool is not reported.
+    ToolCall { agent: String, function: String },
     HandOff { from: String, to: String, because: DecisionSource },
     Completed { agent: String },
 }
@@ -332,6 +429,7 @@ pub struct HandoffOrchestrator {
     agents: HashMap<String, Agent>,
     rules: Vec<HandoffRule>,
     aliases: HashMap<String, String>,
+    capability_router: Option<CapabilityRouter>,
     max_handoffs: Option<usize>,
     max_rounds: usize,
     llm_timeout_ms: u64,
@@ -350,6 +448,7 @@ impl HandoffOrchestrator {
             agents: HashMap::new(),
             rules: Vec::new(),
             aliases: HashMap::new(),
+            capability_router: None,
             max_handoffs: Some(4),
             max_rounds: 32,
             llm_timeout_ms: 60_000,
@@ -363,6 +462,9 @@ impl HandoffOrchestrator {
 
     pub fn register_agent(&mut self, agent: Agent) -> Option<Agent> {
         let name = agent.name().to_string();
+        if let Some(router) = self.capability_router.as_mut() {
+            router.add_agent(&name, agent.capabilities());
+        }
         let previous = self.agents.insert(name, agent);
         self.refresh_handoff_instructions();
         previous
@@ -402,6 +504,10 @@ impl HandoffOrchestrator {
                 HandoffMatcher::Predicate(pred) => {
                     pred(transcript, last_message).is_some()
                 }
+                HandoffMatcher::RequiresCapability(capability) => {
+                    let router = self.capability_router.as_ref().unwrap_or(&DEFAULT_CAPABILITY_ROUTER);
+                    router.capability_for(last_message) == Some(capability)
+                }
             };
 
             if matches {
@@ -418,6 +524,17 @@ impl HandoffOrchestrator {
         // For now, this method is kept for API compatibility but doesn't modify anything.
     }
 
+    /// Resolve handoff targets that name no agent by capability, picking the first agent
+    /// that declares it (see [`Agent::with_capabilities`]). Agents registered before or
+    /// after this call are added to the router.
+    pub fn with_capability_router(mut self, mut router: CapabilityRouter) -> Self {
+        for (name, agent) in &self.agents {
+            router.add_agent(name, agent.capabilities());
+        }
+        self.capability_router = Some(router);
+        self
+    }
+
     pub fn with_max_handoffs(mut self, max_handoffs: Option<usize>) -> Self {
         self.max_handoffs = max_handoffs;
         self
@@ -533,6 +650,19 @@ impl HandoffOrchestrator {
             }
         }
 
+        // 4) capability ("someone who can search the web")
+        if let Some(router) = &self.capability_router {
+            if let Some(capability) = router.capability_for(raw_target) {
+                if let Some(name) = router
+                    .agents_with(capability)
+                    .iter()
+                    .find(|name| self.agents.contains_key(*name) && normalize_agent_key(name) != normalize_agent_key(current))
+                {
+                    return Ok(name.clone());
+                }
+            }
+        }
+
         Err(AgentError::UnknownAgent(raw_target.to_string()))
     }
 
@@ -551,16 +681,27 @@ impl HandoffOrchestrator {
             active_agent: agent_name,
             remaining_handoffs: self.max_handoffs,
             metrics_collector: self.metrics_collector.clone(),
+            correlation_id: None,
         })
     }
 }
 
+/// Answers collected by [`HandoffSession::consult`].
+#[derive(Debug, Clone, Default)]
+pub struct ConsultationResult {
+    /// Reply of each consulted agent, keyed by agent name.
+    pub responses: HashMap<String, String>,
+    /// `agent: reply` lines in consultation order, ready to hand to the coordinator.
+    pub combined: String,
+}
+
 pub struct HandoffSession<'a> {
     orchestrator: &'a HandoffOrchestrator,
     transcript: Vec<ChatMessage>,
     active_agent: String,
     remaining_handoffs: Option<usize>,
     metrics_collector: Option<Arc<dyn MetricsCollector>>,
+    correlation_id: Option<CorrelationId>,
 }
 
 impl<'a> HandoffSession<'a> {
@@ -576,6 +717,24 @@ impl<'a> HandoffSession<'a> {
         self.transcript = history;
     }
 
+    /// Checkpoint the transcript so it can be replayed later via [`ChatHistory::restore`].
+    pub fn snapshot(&self) -> ChatHistorySnapshot {
+        ChatHistorySnapshot::from_messages(self.transcript.clone())
+    }
+
+    /// Branch the session at its current state. The fork keeps the active agent and handoff
+    /// budget but owns an independent copy of the transcript.
+    pub fn fork(&self) -> HandoffSession<'a> {
+        HandoffSession {
+            orchestrator: self.orchestrator,
+            transcript: ChatHistory::restore(self.snapshot()).into_messages(),
+            active_agent: self.active_agent.clone(),
+            remaining_handoffs: self.remaining_handoffs,
+            metrics_collector: self.metrics_collector.clone(),
+            correlation_id: self.correlation_id,
+        }
+    }
+
     pub fn set_max_handoffs(&mut self, max: Option<usize>) {
         self.remaining_handoffs = max;
     }
@@ -584,14 +743,111 @@ impl<'a> HandoffSession<'a> {
         self.remaining_handoffs
     }
 
+    /// Assigned on the first [`HandoffSession::send`] and reused for the rest of the session.
+    pub fn correlation_id(&self) -> Option<CorrelationId> {
+        self.correlation_id
+    }
+
+    /// Continue an existing request's correlation id instead of starting a new one.
+    pub fn set_correlation_id(&mut self, id: CorrelationId) {
+        self.correlation_id = Some(id);
+    }
+
+    /// Ask every agent in `agents` about `message` concurrently, each on its own fork of the
+    /// transcript and without handoff tools. The session itself is left untouched: its
+    /// transcript does not grow and the active agent stays the coordinator.
+    pub async fn consult(
+        &self,
+        agents: &[&str],
+        message: impl Into<String>,
+    ) -> Result<ConsultationResult, AgentError> {
+        let mut history = self.transcript.clone();
+        history.push(ChatMessage::user(message));
+        let history = &history;
+
+        let consultations = agents.iter().map(|name| async move {
+            let agent = self
+                .orchestrator
+                .agents
+                .get(*name)
+                .ok_or_else(|| AgentError::UnknownAgent(name.to_string()))?;
+            let turn = time::timeout(
+                std::time::Duration::from_millis(self.orchestrator.llm_timeout_ms),
+                agent.execute(self.orchestrator.provider.as_ref(), &self.orchestrator.model, history),
+            )
+            .await
+            .map_err(|_| AgentError::ProviderTimeout)?
+            .map_err(|err| err.with_agent_context(name.to_string(), 0))?;
+
+            let reply = match turn.action {
+                AgentAction::Respond { message } => message,
+                AgentAction::Complete { message: Some(message) } => message,
+                _ => turn.raw_content,
+            };
+            Ok::<_, AgentError>((name.to_string(), reply))
+        });
+        let answers = futures_util::future::try_join_all(consultations).await?;
+
+        let combined = answers
+            .iter()
+            .map(|(agent, reply)| format!("{agent}: {reply}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(ConsultationResult {
+            responses: answers.into_iter().collect(),
+            combined,
+        })
+    }
+
     pub async fn send(&mut self, user_input: impl Into<String>) -> Result<HandoffTurn, AgentError> {
-        self.transcript.push(ChatMessage::user(user_input.into()));
+        let correlation_id = *self.correlation_id.get_or_insert_with(CorrelationId::new);
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("handoff.send", active.agent = %self.active_agent);
+
+        #[cfg(feature = "telemetry")]
+        {
+            use opentelemetry::KeyValue;
+
+            let turn_index = self
+                .transcript
+                .iter()
+                .filter(|message| message.role == crate::types::MessageRole::User)
+                .count();
+            let cx = crate::telemetry::start_span(
+                "handoff.session.send",
+                &opentelemetry::Context::current(),
+                vec![
+                    KeyValue::new("agent.name", self.active_agent.clone()),
+                    KeyValue::new("turn.index", turn_index as i64),
+                ],
+            );
+            let turn = correlation_id.scope(self.send_turn(user_input.into(), correlation_id));
+            #[cfg(feature = "tracing")]
+            let turn = tracing::Instrument::instrument(turn, span);
+            crate::telemetry::traced(cx, turn, |_, _| {}).await
+        }
+
+        #[cfg(not(feature = "telemetry"))]
+        {
+            let turn = correlation_id.scope(self.send_turn(user_input.into(), correlation_id));
+            #[cfg(feature = "tracing")]
+            let turn = tracing::Instrument::instrument(turn, span);
+            turn.await
+        }
+    }
+
+    async fn send_turn(
+        &mut self,
+        user_input: String,
+        correlation_id: CorrelationId,
+    ) -> Result<HandoffTurn, AgentError> {
+        self.transcript.push(ChatMessage::user(user_input));
         let mut events = Vec::new();
         let mut rounds = 0usize;
         let mut metrics = self
             .metrics_collector
             .as_ref()
-            .map(|_| AgentMetrics::new("handoff_flow".to_string()));
+            .map(|_| AgentMetrics::new("handoff_flow".to_string()).with_correlation_id(correlation_id));
         let execution_timer = ExecutionTimer::new();
 
         loop {
@@ -611,6 +867,7 @@ impl<'a> HandoffSession<'a> {
                 .get(&self.active_agent)
                 .ok_or_else(|| AgentError::UnknownAgent(self.active_agent.clone()))?;
 
+            let compressed = agent.summarize_history(&mut self.transcript);
             let mut internal_tools = self.orchestrator.internal_tools();
             // See `flows::prefill`: after a handoff the transcript ends
             // with an assistant message; for qwen-family models we'd get an
@@ -624,12 +881,13 @@ impl<'a> HandoffSession<'a> {
                     internal_tools.extend_from(&skill_tools);
                 }
             }
-            let fut = agent.execute_with_tools(
+            let fut = agent.execute_summarized(
                 self.orchestrator.provider.as_ref(),
                 &self.orchestrator.model,
                 history.as_ref(),
                 Some(&internal_tools),
                 Some(ToolChoice::auto()),
+                compressed,
             );
 
             let turn = match time::timeout(
@@ -658,7 +916,7 @@ impl<'a> HandoffSession<'a> {
                         metrics.finalize(false, 0, rounds);
                         collector.record_metrics(metrics.clone());
                     }
-                    return Err(err.into());
+                    return Err(err.with_agent_context(self.active_agent.clone(), rounds - 1).into());
                 }
             };
 
@@ -681,6 +939,18 @@ impl<'a> HandoffSession<'a> {
                 }
             }
 
+            for tool_call in &turn.tool_calls {
+                if tool_call.function.name == "handoff" {
+                    continue;
+                }
+                let event = HandoffEvent::ToolCall {
+                    agent: agent.name().to_string(),
+                    function: tool_call.function.name.clone(),
+                };
+                self.orchestrator.emit_event(&event);
+                events.push(event);
+            }
+
             // Check if handoff tool was called
             let handoff_tool_called = turn.tool_calls.iter().any(|tc| tc.function.name == "handoff");
 
@@ -734,6 +1004,7 @@ impl<'a> HandoffSession<'a> {
                         reply: Some(message),
                         events,
                         metrics,
+                        correlation_id,
                     });
                 }
                 AgentAction::HandOff { target, message } => {
@@ -804,6 +1075,7 @@ impl<'a> HandoffSession<'a> {
                         reply: message,
                         events,
                         metrics,
+                        correlation_id,
                     });
                 }
             }
@@ -820,8 +1092,28 @@ impl WithMetrics for HandoffOrchestrator {
 
 #[cfg(test)]
 mod tests {
-    use super::{AgentAction, HandoffMatcher, HandoffRule};
+    use super::{AgentAction, CapabilityRouter, HandoffMatcher, HandoffOrchestrator, HandoffRule};
+    use crate::providers::scripted::ScriptedProvider;
+    use crate::{Agent, ChatMessage};
     use regex::Regex;
+    use std::sync::Arc;
+
+    #[test]
+    fn forked_session_has_independent_transcript() {
+        let mut orchestrator = HandoffOrchestrator::new(Arc::new(ScriptedProvider::new()), "model");
+        orchestrator.register_agent(Agent::from_string("greeter", "Greet the user."));
+
+        let mut session = orchestrator.session("greeter").expect("session");
+        session.set_history(vec![ChatMessage::user("Hi"), ChatMessage::assistant("Hello")]);
+
+        let mut fork = session.fork();
+        assert_eq!(fork.active_agent(), "greeter");
+        assert_eq!(fork.transcript().len(), 2);
+
+        fork.set_history(vec![ChatMessage::user("Different start")]);
+        assert_eq!(session.transcript().len(), 2);
+        assert_eq!(session.snapshot().turn(), 2);
+    }
 
     #[test]
     fn parses_inline_json() {
@@ -963,4 +1255,162 @@ mod tests {
         let directive = (rule.resolve)(&transcript, message);
         assert_eq!(directive.unwrap().target, "weather");
     }
+
+    struct RecordingProvider {
+        inner: ScriptedProvider,
+        seen: std::sync::Mutex<Vec<Option<crate::CorrelationId>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::LLMProvider for RecordingProvider {
+        async fn complete(
+            &self,
+            request: crate::CompletionRequest,
+        ) -> Result<crate::CompletionResponse, crate::LLMError> {
+            self.seen.lock().unwrap().push(request.correlation_id);
+            self.inner.complete(request).await
+        }
+
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+    }
+
+    #[tokio::test]
+    async fn session_calls_share_one_correlation_id() {
+        let provider = Arc::new(RecordingProvider {
+            inner: ScriptedProvider::with_responses(&[
+                r#"{"action":"hand_off","target":"billing"}"#,
+                "Your invoice is on its way.",
+                "Anything else?",
+            ]),
+            seen: std::sync::Mutex::new(Vec::new()),
+        });
+        let mut orchestrator = HandoffOrchestrator::new(provider.clone(), "model");
+        orchestrator.register_agent(Agent::from_string("triage", "Route the user."));
+        orchestrator.register_agent(Agent::from_string("billing", "Handle invoices."));
+
+        let mut session = orchestrator.session("triage").expect("session");
+        assert!(session.correlation_id().is_none());
+        let first = session.send("Where is my invoice?").await.expect("first turn");
+        let second = session.send("Thanks").await.expect("second turn");
+
+        assert_eq!(first.correlation_id, second.correlation_id);
+        assert_eq!(session.correlation_id(), Some(first.correlation_id));
+        let seen = provider.seen.lock().unwrap();
+        assert_eq!(seen.len(), 3);
+        assert!(seen.iter().all(|id| *id == Some(first.correlation_id)));
+    }
+
+    #[tokio::test]
+    async fn consult_collects_specialist_answers_without_handing_off() {
+        let answer = |agent: &str, response: &str| crate::ScriptedTurn {
+            agent: agent.to_string(),
+            response: response.to_string(),
+            latency_ms: None,
+        };
+        let mut provider = ScriptedProvider::new();
+        provider.add_response_for_agent("specialist_a", answer("specialist_a", "X is a letter."));
+        provider.add_response_for_agent("specialist_b", answer("specialist_b", "X is a variable."));
+
+        let mut orchestrator = HandoffOrchestrator::new(Arc::new(provider), "model");
+        orchestrator.register_agent(Agent::from_string("coordinator", "Coordinate specialists."));
+        orchestrator.register_agent(Agent::from_string("specialist_a", "You are specialist_a, a linguist."));
+        orchestrator.register_agent(Agent::from_string("specialist_b", "You are specialist_b, a mathematician."));
+
+        let mut session = orchestrator.session("coordinator").expect("session");
+        session.set_history(vec![ChatMessage::user("Hi")]);
+        let result = session
+            .consult(&["specialist_a", "specialist_b"], "What is X?")
+            .await
+            .expect("consultation");
+
+        assert_eq!(result.responses["specialist_a"], "X is a letter.");
+        assert_eq!(result.responses["specialist_b"], "X is a variable.");
+        assert_eq!(
+            result.combined,
+            "specialist_a: X is a letter.\nspecialist_b: X is a variable."
+        );
+        assert_eq!(session.active_agent(), "coordinator");
+        assert_eq!(session.transcript().len(), 1);
+
+        let unknown = session.consult(&["specialist_c"], "What is X?").await;
+        assert!(matches!(unknown, Err(crate::AgentError::UnknownAgent(name)) if name == "specialist_c"));
+    }
+
+    #[tokio::test]
+    async fn auto_summarize_compresses_session_transcript() {
+        use crate::history::{ConciseSummarizer, FixedWindowCompressor};
+
+        let mut provider = ScriptedProvider::new();
+        provider.echo_user_messages();
+        let summarizer: crate::SharedHistoryCompressor = Arc::new(std::sync::Mutex::new(
+            FixedWindowCompressor::new(4, ConciseSummarizer::default()),
+        ));
+        let mut orchestrator = HandoffOrchestrator::new(Arc::new(provider), "model");
+        orchestrator.register_agent(Agent::from_string("assistant", "Help out.").with_auto_summarize(4, summarizer));
+
+        let mut session = orchestrator.session("assistant").expect("session");
+        session.send("one").await.expect("first turn");
+        session.send("two").await.expect("second turn");
+        assert_eq!(session.transcript().len(), 4);
+
+        session.send("three").await.expect("third turn");
+        let transcript = session.transcript();
+        assert!(transcript.len() < 6);
+        assert_eq!(transcript[0].name.as_deref(), Some("history-summary"));
+        assert_eq!(transcript.last().and_then(|message| message.text()), Some("three"));
+    }
+
+    #[tokio::test]
+    async fn capability_rule_routes_to_capable_agent() {
+        use crate::AgentCapability;
+
+        let answer = |agent: &str, response: &str| crate::ScriptedTurn {
+            agent: agent.to_string(),
+            response: response.to_string(),
+            latency_ms: None,
+        };
+        let mut provider = ScriptedProvider::new();
+        provider.add_response_for_agent("triage", answer("triage", "This needs a quick web search."));
+        provider.add_response_for_agent("researcher", answer("researcher", "Found three sources."));
+        provider.add_response_for_agent("coder", answer("coder", "Ran the script."));
+
+        let mut orchestrator = HandoffOrchestrator::new(Arc::new(provider), "model")
+            .with_capability_router(CapabilityRouter::new());
+        orchestrator.register_agent(Agent::from_string("triage", "You are triage. Route the user."));
+        orchestrator.register_agent(
+            Agent::from_string("coder", "You are coder. Run code.")
+                .with_capabilities(vec![AgentCapability::CodeExecution]),
+        );
+        orchestrator.register_agent(
+            Agent::from_string("researcher", "You are researcher. Look things up.")
+                .with_capabilities(vec![AgentCapability::WebSearch]),
+        );
+        orchestrator.define_handoff(HandoffRule::to(
+            "web search",
+            HandoffMatcher::RequiresCapability(AgentCapability::WebSearch),
+        ));
+
+        let mut session = orchestrator.session("triage").expect("session");
+        let turn = session.send("Who won the match yesterday?").await.expect("turn");
+
+        assert_eq!(session.active_agent(), "researcher");
+        assert_eq!(turn.reply.as_deref(), Some("Found three sources."));
+        assert!(turn.events.iter().any(|event| matches!(
+            event,
+            super::HandoffEvent::HandOff { to, because: crate::eval::scenario::DecisionSource::Rule, .. } if to == "researcher"
+        )));
+    }
+
+    #[test]
+    fn capability_router_matches_whole_words() {
+        let router = CapabilityRouter::new().with_synonym("Spreadsheets", crate::AgentCapability::DataAnalysis);
+        assert_eq!(
+            router.capability_for("Hand off to an agent that can search the web"),
+            Some(&crate::AgentCapability::WebSearch)
+        );
+        assert_eq!(router.capability_for("someone good with spreadsheets"), Some(&crate::AgentCapability::DataAnalysis));
+        assert_eq!(router.capability_for("an internetwork engineer"), None);
+    }
 }
@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::LLMError;
+
+/// Supplies answers for `human_in_loop` flow nodes.
+#[async_trait]
+pub trait HumanInputProvider: Send + Sync {
+    async fn request_input(&self, prompt: &str) -> Result<String, LLMError>;
+}
+
+/// Prints the prompt and reads one line from stdin.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdinHumanInputProvider;
+
+#[async_trait]
+impl HumanInputProvider for StdinHumanInputProvider {
+    async fn request_input(&self, prompt: &str) -> Result<String, LLMError> {
+        let prompt = prompt.to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut stdout = io::stdout();
+            write!(stdout, "{prompt} ").and_then(|_| stdout.flush())?;
+            let mut line = String::new();
+            io::stdin().lock().read_line(&mut line)?;
+            Ok::<_, io::Error>(line.trim_end_matches(['\r', '\n']).to_string())
+        })
+        .await
+        .map_err(|err| LLMError::Provider(format!("stdin reader failed: {err}")))?
+        .map_err(|err| LLMError::Provider(format!("failed to read from stdin: {err}")))
+    }
+}
+
+/// Returns canned responses in order and records the prompts it was asked.
+#[derive(Debug, Default)]
+pub struct MockHumanInputProvider {
+    responses: Mutex<VecDeque<String>>,
+    prompts: Mutex<Vec<String>>,
+}
+
+impl MockHumanInputProvider {
+    pub fn new(responses: Vec<String>) -> Self {
+        Self {
+            responses: Mutex::new(responses.into()),
+            prompts: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().expect("mock prompts lock").clone()
+    }
+}
+
+#[async_trait]
+impl HumanInputProvider for MockHumanInputProvider {
+    async fn request_input(&self, prompt: &str) -> Result<String, LLMError> {
+        self.prompts
+            .lock()
+            .expect("mock prompts lock")
+            .push(prompt.to_string());
+        self.responses
+            .lock()
+            .expect("mock responses lock")
+            .pop_front()
+            .ok_or_else(|| LLMError::Provider("no mock human input left".to_string()))
+    }
+}
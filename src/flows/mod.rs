@@ -7,3 +7,4 @@ pub mod dispatch;
 pub mod spec;
 pub mod flow_builder;
 pub mod prefill;
+pub mod human_input;
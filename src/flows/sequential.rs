@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
     agents::{Agent, AgentError},
@@ -8,6 +8,7 @@ use crate::{
 };
 
 use serde::Serialize;
+use tokio::{sync::mpsc, task::JoinHandle};
 
 use super::handoffflow::AgentAction;
 use super::prefill::history_for_llm;
@@ -20,20 +21,84 @@ pub enum SequentialEvent {
         agent: String,
         output: String,
     },
+    StepSkipped {
+        agent: String,
+        reason: String,
+    },
     Completed {
         agent: String,
         output: Option<String>,
     },
 }
 
+/// What a step preprocessor sees before its step runs.
+#[derive(Debug, Clone, Copy)]
+pub struct SequentialContext<'a> {
+    /// The task the run was started with.
+    pub task: &'a str,
+    /// Output handed on by the previous step, or the task for the first step.
+    pub previous_output: &'a str,
+    pub transcript: &'a [ChatMessage],
+    /// Output of every step so far, `None` for skipped steps.
+    pub step_outputs: &'a [Option<String>],
+}
+
+type StepCondition = Arc<dyn Fn(&SequentialContext<'_>) -> bool + Send + Sync>;
+type StepPreprocessor = Arc<dyn Fn(usize, &SequentialContext<'_>) -> String + Send + Sync>;
+type StepPostprocessor = Arc<dyn Fn(usize, String) -> String + Send + Sync>;
+
+enum StepCommand {
+    InsertAfter(usize, Box<Agent>),
+    Remove(usize),
+}
+
+/// Controls a run started with [`SequentialOrchestrator::run_dynamic`]. Step indices
+/// refer to the pipeline as it currently stands, including earlier insertions and
+/// removals. Changes only touch steps that have not started; once the run has finished
+/// they are ignored.
+pub struct SequentialRunHandle {
+    commands: mpsc::UnboundedSender<StepCommand>,
+    task: JoinHandle<Result<SequentialRun, AgentError>>,
+}
+
+impl SequentialRunHandle {
+    /// Run `agent` right after the step at `step_index`, or as the next step if that
+    /// position has already passed.
+    pub fn insert_step_after(&self, step_index: usize, agent: Agent) {
+        let _ = self.commands.send(StepCommand::InsertAfter(step_index, Box::new(agent)));
+    }
+
+    /// Drop the step at `step_index` unless it has already started.
+    pub fn remove_step(&self, step_index: usize) {
+        let _ = self.commands.send(StepCommand::Remove(step_index));
+    }
+
+    /// Cancel the run; [`SequentialRunHandle::join`] then returns [`AgentError::Aborted`].
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+
+    pub async fn join(self) -> Result<SequentialRun, AgentError> {
+        match self.task.await {
+            Ok(result) => result,
+            Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+            Err(_) => Err(AgentError::Aborted),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SequentialRun {
     pub final_output: Option<String>,
     pub events: Vec<SequentialEvent>,
     pub transcript: Vec<ChatMessage>,
     pub metrics: Option<AgentMetrics>,
+    /// Output of each step in pipeline order, `None` for skipped steps and steps that never
+    /// ran because an earlier one completed the run.
+    pub step_outputs: Vec<Option<String>>,
 }
 
+#[derive(Clone)]
 pub struct SequentialOrchestrator {
     provider: Arc<dyn LLMProvider>,
     model: String,
@@ -42,6 +107,9 @@ pub struct SequentialOrchestrator {
     shared_state: Option<Arc<dyn SharedStateContext>>,
     skill_runtime: Option<Arc<SkillRuntime>>,
     metrics_collector: Option<Arc<dyn MetricsCollector>>,
+    step_preprocessor: Option<StepPreprocessor>,
+    step_postprocessor: Option<StepPostprocessor>,
+    step_conditions: HashMap<usize, StepCondition>,
 }
 
 impl SequentialOrchestrator {
@@ -54,6 +122,9 @@ impl SequentialOrchestrator {
             shared_state: None,
             skill_runtime: None,
             metrics_collector: None,
+            step_preprocessor: None,
+            step_postprocessor: None,
+            step_conditions: HashMap::new(),
         }
     }
 
@@ -84,6 +155,38 @@ impl SequentialOrchestrator {
         self
     }
 
+    /// Replace the input fed to each step (the task for the first step, the previous
+    /// step's output after that) with whatever `preprocessor` returns for the step index
+    /// and the run so far.
+    pub fn with_step_preprocessor(
+        mut self,
+        preprocessor: impl Fn(usize, &SequentialContext<'_>) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.step_preprocessor = Some(Arc::new(preprocessor));
+        self
+    }
+
+    /// Rewrite each step's output before it enters the transcript, so later steps, events
+    /// and the final output only see the rewritten text.
+    pub fn with_step_postprocessor(
+        mut self,
+        postprocessor: impl Fn(usize, String) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.step_postprocessor = Some(Arc::new(postprocessor));
+        self
+    }
+
+    /// Run the step at `step_index` only if `predicate` holds for the run so far; otherwise
+    /// it is skipped with a [`SequentialEvent::StepSkipped`].
+    pub fn with_step_condition(
+        mut self,
+        step_index: usize,
+        predicate: impl Fn(&SequentialContext<'_>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.step_conditions.insert(step_index, Arc::new(predicate));
+        self
+    }
+
     pub fn shared_state(&self) -> Option<&Arc<dyn SharedStateContext>> {
         self.shared_state.as_ref()
     }
@@ -104,14 +207,77 @@ impl SequentialOrchestrator {
     }
 
     pub async fn run(&self, task: impl Into<String>) -> Result<SequentialRun, AgentError> {
+        self.run_controlled(task.into(), None).await
+    }
+
+    /// Start the run on a background task whose pending steps can still be changed
+    /// through the returned handle. Events arrive on the receiver as well as at the event
+    /// callback.
+    pub fn run_dynamic(
+        &self,
+        task: impl Into<String>,
+    ) -> (SequentialRunHandle, mpsc::UnboundedReceiver<SequentialEvent>) {
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+
+        let mut orchestrator = self.clone();
+        let callback = orchestrator.event_callback.take();
+        orchestrator.event_callback = Some(Arc::new(move |event: &SequentialEvent| {
+            if let Some(callback) = &callback {
+                callback(event);
+            }
+            let _ = event_tx.send(event.clone());
+        }));
+
+        let task = task.into();
+        let handle = tokio::spawn(async move { orchestrator.run_controlled(task, Some(&mut command_rx)).await });
+        (
+            SequentialRunHandle {
+                commands: command_tx,
+                task: handle,
+            },
+            event_rx,
+        )
+    }
+
+    async fn run_controlled(
+        &self,
+        task: String,
+        control: Option<&mut mpsc::UnboundedReceiver<StepCommand>>,
+    ) -> Result<SequentialRun, AgentError> {
+        let run = self.run_pipeline(task, control);
+        #[cfg(feature = "tracing")]
+        let run = tracing::Instrument::instrument(run, tracing::info_span!("sequential.run"));
+
+        #[cfg(feature = "telemetry")]
+        {
+            let cx = crate::telemetry::start_span(
+                "sequential.run",
+                &opentelemetry::Context::current(),
+                vec![opentelemetry::KeyValue::new("step.count", self.pipeline.len() as i64)],
+            );
+            crate::telemetry::traced(cx, run, |_, _| {}).await
+        }
+
+        #[cfg(not(feature = "telemetry"))]
+        run.await
+    }
+
+    async fn run_pipeline(
+        &self,
+        task: String,
+        mut control: Option<&mut mpsc::UnboundedReceiver<StepCommand>>,
+    ) -> Result<SequentialRun, AgentError> {
         if self.pipeline.is_empty() {
             return Err(AgentError::NoAgentsRegistered);
         }
 
-        let task = task.into();
+        let mut pipeline = self.pipeline.clone();
         let mut transcript = vec![ChatMessage::user(task.clone())];
         let mut events = Vec::new();
-        let mut payload = task;
+        let mut payload = task.clone();
+        let mut step_outputs: Vec<Option<String>> = Vec::with_capacity(pipeline.len());
+        let mut last_agent: Option<String> = None;
 
         // Initialize metrics collection
         let execution_timer = ExecutionTimer::new();
@@ -121,15 +287,54 @@ impl SequentialOrchestrator {
             None
         };
 
-        for (index, agent) in self.pipeline.iter().enumerate() {
+        let mut next = 0;
+        loop {
+            if let Some(control) = control.as_deref_mut() {
+                apply_step_commands(control, &mut pipeline, next);
+            }
+            let Some(agent) = pipeline.get(next) else {
+                break;
+            };
+            let index = next;
+            next += 1;
+
             let call_timer = ExecutionTimer::new();
+            let context = SequentialContext {
+                task: &task,
+                previous_output: &payload,
+                transcript: &transcript,
+                step_outputs: &step_outputs,
+            };
+            if let Some(condition) = self.step_conditions.get(&index) {
+                if !condition(&context) {
+                    let event = SequentialEvent::StepSkipped {
+                        agent: agent.name().to_string(),
+                        reason: format!("condition for step {index} not met"),
+                    };
+                    self.emit_event(&event);
+                    events.push(event);
+                    step_outputs.push(None);
+                    continue;
+                }
+            }
+            let step_input = self.step_preprocessor.as_ref().map(|preprocessor| {
+                let mut messages = transcript.clone();
+                messages.pop();
+                messages.push(ChatMessage::user(preprocessor(index, &context)));
+                messages
+            });
+            last_agent = Some(agent.name().to_string());
+            let postprocess = |output: String| match &self.step_postprocessor {
+                Some(postprocessor) => postprocessor(index, output),
+                None => output,
+            };
             // Compensate for chat templates (e.g. Qwen3's) that treat a
             // trailing assistant turn as a prefill cue — see
             // `flows::prefill` for the mechanism. Only adds a synthetic
             // user turn for known-affected models and only when the last
             // message is already an assistant reply.
             let effective_model = agent.model_override().unwrap_or(self.model.as_str());
-            let history = history_for_llm(&transcript, effective_model);
+            let history = history_for_llm(step_input.as_deref().unwrap_or(&transcript), effective_model);
             let skill_tools = self
                 .skill_runtime
                 .as_ref()
@@ -153,7 +358,9 @@ impl SequentialOrchestrator {
                         metrics.finalize(false, payload.len(), index + 1);
                         collector.record_metrics(metrics.clone());
                     }
-                    return Err(AgentError::Provider(error));
+                    return Err(AgentError::Provider(
+                        error.with_agent_context(agent.name().to_string(), index),
+                    ));
                 }
             };
 
@@ -176,8 +383,10 @@ impl SequentialOrchestrator {
 
             match turn.action {
                 AgentAction::Respond { message } => {
+                    let message = postprocess(message);
                     push_agent_message(&mut transcript, agent, &message);
                     payload = message.clone();
+                    step_outputs.push(Some(message.clone()));
                     let event = SequentialEvent::Step {
                         agent: agent.name().to_string(),
                         output: message,
@@ -186,11 +395,12 @@ impl SequentialOrchestrator {
                     events.push(event);
                 }
                 AgentAction::HandOff { target: _, message } => {
-                    let text = message.unwrap_or_default();
+                    let text = message.map(postprocess).unwrap_or_default();
                     push_agent_message(&mut transcript, agent, &text);
                     if !text.is_empty() {
                         payload = text.clone();
                     }
+                    step_outputs.push(Some(text.clone()));
                     let event = SequentialEvent::Step {
                         agent: agent.name().to_string(),
                         output: text,
@@ -199,11 +409,13 @@ impl SequentialOrchestrator {
                     events.push(event);
                 }
                 AgentAction::Complete { message } => {
-                    let text = message.clone();
+                    let text = message.map(postprocess);
                     if let Some(ref content) = text {
                         push_agent_message(&mut transcript, agent, content);
                         payload = content.clone();
                     }
+                    step_outputs.push(text.clone());
+                    step_outputs.resize(pipeline.len(), None);
                     let event = SequentialEvent::Completed {
                         agent: agent.name().to_string(),
                         output: text.clone(),
@@ -226,40 +438,38 @@ impl SequentialOrchestrator {
                         events,
                         transcript,
                         metrics: final_metrics,
+                        step_outputs,
                     });
                 }
             }
-
-            // If this was the last agent, mark completion with its output.
-            if index == self.pipeline.len() - 1 {
-                let event = SequentialEvent::Completed {
-                    agent: agent.name().to_string(),
-                    output: Some(payload.clone()),
-                };
-                self.emit_event(&event);
-                events.push(event);
-
-                // Finalize and collect metrics
-                let final_metrics = if let (Some(mut metrics), Some(collector)) = (overall_metrics, &self.metrics_collector) {
-                    metrics.execution.total_duration = execution_timer.elapsed();
-                    metrics.finalize(true, payload.len(), self.pipeline.len());
-                    collector.record_metrics(metrics.clone());
-                    Some(metrics)
-                } else {
-                    None
-                };
-
-                return Ok(SequentialRun {
-                    final_output: Some(payload),
-                    events,
-                    transcript,
-                    metrics: final_metrics,
-                });
-            }
         }
 
-        // Should not reach here because loop returns on last agent.
-        unreachable!("sequential orchestrator exited without completion");
+        // Mark completion with the output of the last agent that ran.
+        let last_agent = last_agent.or_else(|| pipeline.last().map(|agent| agent.name().to_string()));
+        let event = SequentialEvent::Completed {
+            agent: last_agent.unwrap_or_default(),
+            output: Some(payload.clone()),
+        };
+        self.emit_event(&event);
+        events.push(event);
+
+        // Finalize and collect metrics
+        let final_metrics = if let (Some(mut metrics), Some(collector)) = (overall_metrics, &self.metrics_collector) {
+            metrics.execution.total_duration = execution_timer.elapsed();
+            metrics.finalize(true, payload.len(), pipeline.len());
+            collector.record_metrics(metrics.clone());
+            Some(metrics)
+        } else {
+            None
+        };
+
+        Ok(SequentialRun {
+            final_output: Some(payload),
+            events,
+            transcript,
+            metrics: final_metrics,
+            step_outputs,
+        })
     }
 }
 
@@ -270,6 +480,24 @@ impl WithMetrics for SequentialOrchestrator {
     }
 }
 
+/// Apply the changes queued through a [`SequentialRunHandle`]; `next` is the first step
+/// that has not started.
+fn apply_step_commands(control: &mut mpsc::UnboundedReceiver<StepCommand>, pipeline: &mut Vec<Agent>, next: usize) {
+    while let Ok(command) = control.try_recv() {
+        match command {
+            StepCommand::InsertAfter(step_index, agent) => {
+                let position = step_index.saturating_add(1).clamp(next, pipeline.len());
+                pipeline.insert(position, *agent);
+            }
+            StepCommand::Remove(step_index) => {
+                if step_index >= next && step_index < pipeline.len() {
+                    pipeline.remove(step_index);
+                }
+            }
+        }
+    }
+}
+
 fn push_agent_message(transcript: &mut Vec<ChatMessage>, agent: &Agent, content: &str) {
     let mut message = ChatMessage::assistant(content.to_string());
     message.name = Some(agent.name().to_string());
@@ -278,6 +506,7 @@ fn push_agent_message(transcript: &mut Vec<ChatMessage>, agent: &Agent, content:
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::sync::{Arc, Mutex};
 
     use async_trait::async_trait;
@@ -293,19 +522,22 @@ mod tests {
 
     struct TestProvider {
         responses: Mutex<Vec<String>>,
+        requests: Mutex<Vec<CompletionRequest>>,
     }
 
     impl TestProvider {
         fn new(responses: Vec<String>) -> Self {
             Self {
                 responses: Mutex::new(responses),
+                requests: Mutex::new(Vec::new()),
             }
         }
     }
 
     #[async_trait]
     impl LLMProvider for TestProvider {
-        async fn complete(&self, _request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+            self.requests.lock().unwrap().push(request);
             let mut guard = self.responses.lock().unwrap();
             let content = guard.remove(0);
             drop(guard);
@@ -357,6 +589,84 @@ mod tests {
         assert_eq!(run.transcript.len(), 4); // initial user + three agent replies
     }
 
+    #[tokio::test]
+    async fn step_processors_shape_what_the_next_agent_sees() {
+        let provider = Arc::new(TestProvider::new(vec![
+            "Summary: fast\nDetails: benchmarks attached".to_string(),
+            "Headline: Fast\nBody: more".to_string(),
+        ]));
+
+        let orchestrator = SequentialOrchestrator::new(provider.clone(), "model")
+            .with_agents(vec![
+                Agent::from_string("Analyst", "Analyse."),
+                Agent::from_string("Writer", "Write."),
+            ])
+            .with_step_preprocessor(|index, context| {
+                format!("Step {index} of {}: {}", context.task, context.previous_output)
+            })
+            .with_step_postprocessor(|_, output| output.lines().next().unwrap_or_default().to_string());
+
+        let run = orchestrator.run("launch").await.expect("run should succeed");
+        assert_eq!(run.final_output.as_deref(), Some("Headline: Fast"));
+
+        let requests = provider.requests.lock().unwrap();
+        let writer_input: Vec<&str> = requests[1]
+            .messages
+            .iter()
+            .filter_map(|message| message.text())
+            .collect();
+        assert_eq!(writer_input.last(), Some(&"Step 1 of launch: Summary: fast"));
+        assert!(!writer_input.contains(&"Summary: fast"));
+
+        let analyst_input: Vec<&str> = requests[0]
+            .messages
+            .iter()
+            .filter_map(|message| message.text())
+            .collect();
+        assert_eq!(analyst_input.last(), Some(&"Step 0 of launch: launch"));
+        assert!(!analyst_input.contains(&"launch"));
+        assert!(writer_input.iter().all(|text| !text.contains("benchmarks")));
+    }
+
+    #[tokio::test]
+    async fn step_condition_skips_step_once_answer_is_found() {
+        let provider = Arc::new(TestProvider::new(vec![
+            "The answer is 42.".to_string(),
+            "Report: 42".to_string(),
+        ]));
+
+        let metadata: Arc<Mutex<HashMap<String, String>>> = Arc::default();
+        let found = Arc::clone(&metadata);
+        let orchestrator = SequentialOrchestrator::new(provider.clone(), "model")
+            .with_agents(vec![
+                Agent::from_string("Researcher", "Find the answer."),
+                Agent::from_string("Verifier", "Double-check the answer."),
+                Agent::from_string("Writer", "Write it up."),
+            ])
+            .with_step_postprocessor(move |_, output| {
+                if output.contains("answer is") {
+                    found.lock().unwrap().insert("answer_found".to_string(), "true".to_string());
+                }
+                output
+            })
+            .with_step_condition(1, move |_| {
+                metadata.lock().unwrap().get("answer_found").map(String::as_str) != Some("true")
+            });
+
+        let run = orchestrator.run("What is the answer?").await.expect("run should succeed");
+
+        match &run.events[1] {
+            SequentialEvent::StepSkipped { agent, .. } => assert_eq!(agent, "Verifier"),
+            other => panic!("unexpected event: {other:?}"),
+        }
+        assert_eq!(
+            run.step_outputs,
+            vec![Some("The answer is 42.".to_string()), None, Some("Report: 42".to_string())]
+        );
+        assert_eq!(run.final_output.as_deref(), Some("Report: 42"));
+        assert_eq!(provider.requests.lock().unwrap().len(), 2);
+    }
+
     #[tokio::test]
     async fn errors_when_no_agents() {
         let provider: Arc<dyn LLMProvider> = Arc::new(TestProvider::new(vec![]));
@@ -390,4 +700,113 @@ mod tests {
         let recorded = events.lock().unwrap().clone();
         assert_eq!(recorded, vec!["A".to_string(), "B".to_string()]);
     }
+
+    #[tokio::test]
+    async fn surfaces_injected_provider_errors() {
+        use crate::providers::scripted::ScriptedProvider;
+
+        let mut provider = ScriptedProvider::with_responses(&["draft", "unused"]);
+        provider.inject_error_at_turn(1, LLMError::Provider("timeout".into()));
+
+        let steps: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let steps_clone = Arc::clone(&steps);
+        let orchestrator = SequentialOrchestrator::new(Arc::new(provider), "model")
+            .with_agents(vec![
+                Agent::from_string("Writer", "Write a draft."),
+                Agent::from_string("Editor", "Edit the draft."),
+            ])
+            .with_event_callback(move |event| {
+                if let SequentialEvent::Step { agent, .. } = event {
+                    steps_clone.lock().unwrap().push(agent.clone());
+                }
+            });
+
+        let error = orchestrator.run("task").await.unwrap_err();
+        let AgentError::Provider(error) = error else {
+            panic!("expected provider error, got {error:?}");
+        };
+        assert_eq!(error.agent_context(), Some(("Editor", 1)));
+        assert!(matches!(error.root_cause(), LLMError::Provider(message) if message == "timeout"));
+        assert_eq!(*steps.lock().unwrap(), vec!["Writer".to_string()]);
+    }
+
+    /// Answers each request with its system prompt, one permit per request, so a test can
+    /// hold the run between steps.
+    struct GatedProvider {
+        permits: Arc<tokio::sync::Semaphore>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for GatedProvider {
+        async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+            self.permits.acquire().await.expect("semaphore open").forget();
+            let instructions = request.messages[0].text().unwrap_or_default().to_string();
+            Ok(CompletionResponse {
+                message: ChatMessage::assistant(instructions),
+                usage: None,
+                reasoning: None,
+            })
+        }
+
+        fn name(&self) -> &'static str {
+            "gated"
+        }
+    }
+
+    fn gated_pipeline(permits: usize) -> (SequentialOrchestrator, Arc<tokio::sync::Semaphore>) {
+        let gate = Arc::new(tokio::sync::Semaphore::new(permits));
+        let provider = GatedProvider { permits: Arc::clone(&gate) };
+        let orchestrator = SequentialOrchestrator::new(Arc::new(provider), "model").with_agents(vec![
+            Agent::from_string("First", "first"),
+            Agent::from_string("Second", "second"),
+            Agent::from_string("Third", "third"),
+        ]);
+        (orchestrator, gate)
+    }
+
+    #[tokio::test]
+    async fn dynamic_run_inserts_step_while_running() {
+        let (orchestrator, gate) = gated_pipeline(1);
+        let (handle, mut events) = orchestrator.run_dynamic("task");
+
+        let first = events.recv().await.expect("first step event");
+        assert!(matches!(first, SequentialEvent::Step { ref agent, .. } if agent == "First"));
+        handle.insert_step_after(1, Agent::from_string("Inserted", "inserted"));
+        gate.add_permits(10);
+
+        let run = handle.join().await.expect("run should succeed");
+        let steps: Vec<&str> = run
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                SequentialEvent::Step { agent, .. } => Some(agent.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(steps, ["First", "Second", "Inserted", "Third"]);
+        assert_eq!(run.final_output.as_deref(), Some("third"));
+
+        let mut streamed = 1;
+        while events.recv().await.is_some() {
+            streamed += 1;
+        }
+        assert_eq!(streamed, run.events.len());
+    }
+
+    #[tokio::test]
+    async fn dynamic_run_removes_pending_step_and_aborts() {
+        let (orchestrator, gate) = gated_pipeline(1);
+        let (handle, mut events) = orchestrator.run_dynamic("task");
+        events.recv().await.expect("first step event");
+        handle.remove_step(0);
+        handle.remove_step(2);
+        gate.add_permits(10);
+        let run = handle.join().await.expect("run should succeed");
+        assert_eq!(run.step_outputs, [Some("first".to_string()), Some("second".to_string())]);
+
+        let (orchestrator, _gate) = gated_pipeline(0);
+        let (handle, _events) = orchestrator.run_dynamic("task");
+        handle.abort();
+        assert!(matches!(handle.join().await, Err(AgentError::Aborted)));
+    }
 }
@@ -6,19 +6,24 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use futures_util::future::{BoxFuture, FutureExt};
+use tera::Tera;
 use evalexpr::{
     eval_with_context, ContextWithMutableVariables, DefaultNumericTypes, HashMapContext,
     Value as EvalValue,
 };
 
-use super::sequential::{SequentialEvent, SequentialOrchestrator, SequentialRun};
+use super::human_input::HumanInputProvider;
+use crate::providers::retry::{retry_delay, RetryDecisionPolicy, RetryingProvider};
+use super::sequential::{SequentialContext, SequentialEvent, SequentialOrchestrator, SequentialRun};
 use crate::flows::handoffflow::{HandoffDirective, HandoffMatcher, HandoffRule};
 use crate::functions::http::load_http_function;
 use crate::skills::{SkillCatalog, SkillDefinition, SkillRuntime, SkillStub};
 use crate::{
     agents::{Agent, AgentError},
     functions::{FunctionCall, FunctionRegistry},
-    LLMProvider,
+    types::ChatMessage,
+    LLMError, LLMProvider,
 };
 
 fn default_version() -> String {
@@ -195,6 +200,10 @@ pub enum FlowNodeKind {
         tools: Vec<String>,
         #[serde(default, skip_serializing_if = "Option::is_none")]
         parameters: Option<CallSettings>,
+        /// Condition under which a sequential run skips this step. Besides the flow
+        /// variables it can use `output`, the previous step's output.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        skip_if: Option<String>,
     },
     Decision {
         #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -221,6 +230,43 @@ pub enum FlowNodeKind {
     Subflow {
         flow: String,
     },
+    /// Branches on an edge-condition expression evaluated against the [`FlowContext`],
+    /// without calling an LLM. The branches name the node to continue with.
+    Condition {
+        expression: String,
+        true_branch: String,
+        false_branch: String,
+    },
+    /// Applies a transform registered with [`FlowBuilder::register_transform`] to
+    /// `input_var` and stores the result in `output_var`.
+    Transform {
+        function: String,
+        input_var: String,
+        output_var: String,
+    },
+    /// Pauses planning until a [`HumanInputProvider`] answers `prompt`; the answer is stored
+    /// in the `human_input` context variable.
+    HumanInLoop {
+        prompt: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_ms: Option<u64>,
+    },
+    /// Runs `agent` once per element of the `input_var` array, each with a fresh transcript,
+    /// and stores the replies as an array in `output_var`.
+    MapEach {
+        agent: String,
+        input_var: String,
+        output_var: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        concurrency: Option<usize>,
+    },
+    /// Continues with `target_node` and retries it up to `max_retries` times when it fails,
+    /// waiting `backoff_ms * 2^attempt` (capped at 30s) between attempts.
+    RetryOnFail {
+        target_node: String,
+        max_retries: u32,
+        backoff_ms: u64,
+    },
 }
 
 fn default_loop_iterations() -> u32 {
@@ -343,6 +389,18 @@ pub enum FlowLoadError {
     FunctionNotFound(String, String),
     #[error("invalid regex {0}: {1}")]
     InvalidRegex(String, String),
+    #[error("transform not found at node {0}: {1}")]
+    TransformNotFound(String, String),
+    #[error("transform failed at node {0}: {1}")]
+    TransformFailed(String, String),
+    #[error("system prompt of agent {0} failed to render: {1}")]
+    PromptTemplate(String, String),
+    #[error("map_each node {0} needs a JSON array in {1}")]
+    MapEachInput(String, String),
+    /// A `human_in_loop` or `map_each` node was reached by a planner that cannot answer or
+    /// run it; see [`FlowBuilder::plan_execution_steps_with_agents`].
+    #[error("node needs a human input provider or an LLM provider to be planned: {0}")]
+    RuntimeRequired(String),
 }
 
 #[derive(Debug, Error)]
@@ -365,12 +423,75 @@ pub enum FlowRunError {
     Agent(#[from] AgentError),
     #[error("no agents in flow: {0}")]
     NoAgents(String),
+    #[error("human input failed: {0}")]
+    HumanInput(LLMError),
 }
 
-#[derive(Debug, Clone)]
+/// Progress reported while a flow is planned and run.
+#[derive(Debug, Clone, Serialize)]
+pub enum FlowProgressEvent {
+    RetryAttempt {
+        node_id: String,
+        attempt: u32,
+        reason: String,
+    },
+}
+
+type ProgressCallback = Arc<dyn Fn(&FlowProgressEvent) + Send + Sync>;
+
+/// Provider and tool registries used to run agents while planning.
+type AgentRuntime<'a> = (Arc<dyn LLMProvider>, &'a HashMap<String, Arc<FunctionRegistry>>);
+
+/// Agents of one parallel branch and the `merge` node it ends at, if any.
+type PlannedBranch = (Vec<PlannedAgent>, Option<String>);
+
+/// How a planning pass treats `human_in_loop` and `map_each` nodes.
+#[derive(Clone, Copy)]
+enum PlanMode<'a> {
+    /// Such nodes stop planning with [`FlowLoadError::RuntimeRequired`]; nothing is awaited.
+    Offline,
+    /// `human_in_loop` nodes ask the human input provider on every visit and `map_each` nodes
+    /// run when agents are given. Nodes guarded by `retry_on_fail` are retried in place.
+    Interactive(Option<&'a AgentRuntime<'a>>),
+}
+
+/// A pure function applied by `transform` flow nodes.
+pub trait TransformFn: Send + Sync {
+    fn transform(&self, input: Value) -> Result<Value, String>;
+}
+
+impl<F> TransformFn for F
+where
+    F: Fn(Value) -> Result<Value, String> + Send + Sync,
+{
+    fn transform(&self, input: Value) -> Result<Value, String> {
+        self(input)
+    }
+}
+
+#[derive(Clone)]
 pub struct FlowBuilder {
     base_dir: PathBuf,
     document: FlowDocument,
+    transforms: HashMap<String, Arc<dyn TransformFn>>,
+    human_input: Option<Arc<dyn HumanInputProvider>>,
+    progress: Option<ProgressCallback>,
+    template_engine: Option<Arc<std::sync::Mutex<Tera>>>,
+}
+
+impl std::fmt::Debug for FlowBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut transforms: Vec<&String> = self.transforms.keys().collect();
+        transforms.sort();
+        f.debug_struct("FlowBuilder")
+            .field("base_dir", &self.base_dir)
+            .field("document", &self.document)
+            .field("transforms", &transforms)
+            .field("human_input", &self.human_input.is_some())
+            .field("progress", &self.progress.is_some())
+            .field("template_engine", &self.template_engine.is_some())
+            .finish()
+    }
 }
 
 impl FlowBuilder {
@@ -379,6 +500,10 @@ impl FlowBuilder {
         Ok(Self {
             base_dir: base_dir.as_ref().to_path_buf(),
             document,
+            transforms: HashMap::new(),
+            human_input: None,
+            progress: None,
+            template_engine: None,
         })
     }
 
@@ -386,6 +511,75 @@ impl FlowBuilder {
         &self.document
     }
 
+    /// Make `f` available to transform nodes under `name`, replacing any earlier transform
+    /// with that name.
+    pub fn register_transform(&mut self, name: &str, f: Arc<dyn TransformFn>) {
+        self.transforms.insert(name.to_string(), f);
+    }
+
+    /// Answer `human_in_loop` nodes through `provider` when running flows.
+    pub fn with_human_input_provider(mut self, provider: Arc<dyn HumanInputProvider>) -> Self {
+        self.human_input = Some(provider);
+        self
+    }
+
+    pub fn with_progress_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&FlowProgressEvent) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Render system prompts passed to [`FlowBuilder::build_agents_with_context`] with `tera`,
+    /// so its registered filters, functions and templates are available to prompts.
+    pub fn with_template_engine(mut self, tera: Tera) -> Self {
+        self.template_engine = Some(Arc::new(std::sync::Mutex::new(tera)));
+        self
+    }
+
+    fn emit_progress(&self, event: FlowProgressEvent) {
+        if let Some(callback) = &self.progress {
+            callback(&event);
+        }
+    }
+
+    fn render_prompt(&self, agent_id: &str, prompt: &str, ctx: &FlowContext) -> Result<String, FlowLoadError> {
+        let template_error = |err: tera::Error| {
+            let message = match std::error::Error::source(&err) {
+                Some(source) => format!("{err}: {source}"),
+                None => err.to_string(),
+            };
+            FlowLoadError::PromptTemplate(agent_id.to_string(), message)
+        };
+        let context = tera::Context::from_serialize(&ctx.vars).map_err(template_error)?;
+        match &self.template_engine {
+            Some(tera) => tera.lock().unwrap().render_str(prompt, &context),
+            // Prompts are plain text, so values are not HTML-escaped.
+            None => Tera::one_off(prompt, &context, false),
+        }
+        .map_err(template_error)
+    }
+
+    /// The retry settings of the `retry_on_fail` node of `flow` wrapping `node_id`, if any.
+    /// Node ids are only unique within a flow, so other flows are not searched.
+    fn retry_for_node(&self, flow: &FlowDefinition, node_id: &str) -> Option<NodeRetry> {
+        flow.nodes
+            .iter()
+            .find_map(|node| match &node.kind {
+                FlowNodeKind::RetryOnFail {
+                    target_node,
+                    max_retries,
+                    backoff_ms,
+                } if target_node == node_id => Some(NodeRetry {
+                    node_id: node_id.to_string(),
+                    max_retries: *max_retries,
+                    backoff_ms: *backoff_ms,
+                }),
+                _ => None,
+            })
+    }
+
     fn flow(&self, flow_id: &str) -> Result<&FlowDefinition, FlowLoadError> {
         self.document
             .flows
@@ -397,6 +591,25 @@ impl FlowBuilder {
     pub fn build_agents(
         &self,
         tool_registries: &HashMap<String, Arc<FunctionRegistry>>,
+    ) -> Result<HashMap<String, Agent>, FlowLoadError> {
+        self.build_agents_from(None, tool_registries)
+    }
+
+    /// Like [`FlowBuilder::build_agents`], but renders every system prompt as a Tera template
+    /// over the variables of `ctx`, e.g. `{{role | upper}}` or
+    /// `{{domain | default(value='general')}}`. Prompts are rendered once, here.
+    pub fn build_agents_with_context(
+        &self,
+        ctx: &FlowContext,
+        tool_registries: &HashMap<String, Arc<FunctionRegistry>>,
+    ) -> Result<HashMap<String, Agent>, FlowLoadError> {
+        self.build_agents_from(Some(ctx), tool_registries)
+    }
+
+    fn build_agents_from(
+        &self,
+        ctx: Option<&FlowContext>,
+        tool_registries: &HashMap<String, Arc<FunctionRegistry>>,
     ) -> Result<HashMap<String, Agent>, FlowLoadError> {
         let mut agents = HashMap::new();
 
@@ -437,7 +650,10 @@ impl FlowBuilder {
                 skill_stubs.push(stub);
             }
 
-            let mut instructions = instructions;
+            let mut instructions = match ctx {
+                Some(ctx) => self.render_prompt(&def.id, &instructions, ctx)?,
+                None => instructions,
+            };
             if let Some(directory) = format_skill_directory(&skill_stubs) {
                 if !instructions.is_empty() {
                     instructions.push_str("\n\n");
@@ -510,7 +726,8 @@ impl FlowBuilder {
     ) -> Result<SequentialOrchestrator, FlowLoadError> {
         let agents = self.build_agents(tool_registries)?;
         let mut visited = Vec::new();
-        let planned = self.plan_nodes(flow_id, &FlowContext::default(), &mut visited)?;
+        let mut ctx = FlowContext::default();
+        let planned = plan_offline(self.plan_nodes(flow_id, &mut ctx, &mut visited, PlanMode::Offline))?;
 
         if planned.is_empty() {
             return Err(FlowLoadError::MissingOutput(flow_id.to_string()));
@@ -541,6 +758,12 @@ impl FlowBuilder {
 
         let provider_clone = Arc::clone(&provider);
         let mut orchestrator = SequentialOrchestrator::new(provider, model.clone()).with_agents(pipeline);
+        for (index, step) in planned.iter().enumerate() {
+            if let Some(expression) = &step.skip_if {
+                orchestrator = orchestrator
+                    .with_step_condition(index, skip_condition(expression.clone(), FlowContext::default()));
+            }
+        }
         if let Some(runtime) = self.build_skill_runtime(provider_clone, &model, tool_registries) {
             orchestrator = orchestrator.with_skill_runtime(runtime);
         }
@@ -767,7 +990,8 @@ impl FlowBuilder {
     ) -> Result<Vec<Agent>, FlowLoadError> {
         let agents = self.build_agents(tool_registries)?;
         let mut visited_flows = Vec::new();
-        let path = self.plan_nodes(flow_id, ctx, &mut visited_flows)?;
+        let mut ctx = ctx.clone();
+        let path = plan_offline(self.plan_nodes(flow_id, &mut ctx, &mut visited_flows, PlanMode::Offline))?;
         let mut result = Vec::new();
         for step in path {
             let agent = agents
@@ -784,9 +1008,19 @@ impl FlowBuilder {
         &self,
         flow_id: &str,
         ctx: &FlowContext,
+    ) -> Result<Vec<PlannedStep>, FlowLoadError> {
+        self.plan_execution_steps_in(flow_id, &mut ctx.clone())
+    }
+
+    /// Like [`Self::plan_execution_steps`], but transform nodes write their outputs back
+    /// into `ctx`.
+    pub fn plan_execution_steps_in(
+        &self,
+        flow_id: &str,
+        ctx: &mut FlowContext,
     ) -> Result<Vec<PlannedStep>, FlowLoadError> {
         let mut visited_flows = Vec::new();
-        self.plan_steps(flow_id, ctx, &mut visited_flows)
+        plan_offline(self.plan_steps(flow_id, ctx, &mut visited_flows, PlanMode::Offline))
     }
 
     pub fn build_execution_plan(
@@ -796,6 +1030,226 @@ impl FlowBuilder {
         tool_registries: &HashMap<String, Arc<FunctionRegistry>>,
     ) -> Result<Vec<ExecutionStep>, FlowLoadError> {
         let planned = self.plan_execution_steps(flow_id, ctx)?;
+        self.resolve_execution_plan(planned, tool_registries)
+    }
+
+    /// Plan like [`Self::plan_execution_steps_in`], asking the registered
+    /// [`HumanInputProvider`] each time a `human_in_loop` node is reached. The answer is
+    /// stored in `ctx` before planning moves on.
+    pub async fn plan_execution_steps_interactive(
+        &self,
+        flow_id: &str,
+        ctx: &mut FlowContext,
+    ) -> Result<Vec<PlannedStep>, FlowRunError> {
+        self.plan_interactive(flow_id, ctx, None).await
+    }
+
+    /// Like [`Self::plan_execution_steps_interactive`], additionally running `map_each`
+    /// nodes through `provider` so later nodes can route on their output.
+    pub async fn plan_execution_steps_with_agents(
+        &self,
+        flow_id: &str,
+        ctx: &mut FlowContext,
+        provider: Arc<dyn LLMProvider>,
+        tool_registries: &HashMap<String, Arc<FunctionRegistry>>,
+    ) -> Result<Vec<PlannedStep>, FlowRunError> {
+        let runtime = (provider, tool_registries);
+        self.plan_interactive(flow_id, ctx, Some(&runtime)).await
+    }
+
+    async fn plan_interactive(
+        &self,
+        flow_id: &str,
+        ctx: &mut FlowContext,
+        runtime: Option<&AgentRuntime<'_>>,
+    ) -> Result<Vec<PlannedStep>, FlowRunError> {
+        // Plan against a copy so a failed pass leaves no partial state behind.
+        let mut planned = ctx.clone();
+        let mut visited_flows = Vec::new();
+        let steps = self
+            .plan_steps(flow_id, &mut planned, &mut visited_flows, PlanMode::Interactive(runtime))
+            .await?;
+        *ctx = planned;
+        Ok(steps)
+    }
+
+    /// Run a `transform`, `human_in_loop` or `map_each` node. In interactive passes a node
+    /// guarded by `retry_on_fail` is retried; once retries run out the first error is returned.
+    async fn run_node(
+        &self,
+        flow: &FlowDefinition,
+        node: &FlowNode,
+        ctx: &mut FlowContext,
+        mode: PlanMode<'_>,
+    ) -> Result<(), FlowRunError> {
+        let retry = match mode {
+            PlanMode::Offline => None,
+            PlanMode::Interactive(_) => self.retry_for_node(flow, &node.base.id),
+        };
+        let mut first_error = None;
+        let mut attempts = 0;
+        loop {
+            let error = match self.apply_node(node, ctx, mode).await {
+                Ok(()) => return Ok(()),
+                Err(error) => error,
+            };
+            let retryable = match &error {
+                FlowRunError::Load(FlowLoadError::TransformFailed(..)) => true,
+                FlowRunError::Load(_) => false,
+                _ => true,
+            };
+            match &retry {
+                Some(retry) if retryable && attempts < retry.max_retries => {
+                    let delay = retry.delay(attempts);
+                    attempts += 1;
+                    self.emit_progress(FlowProgressEvent::RetryAttempt {
+                        node_id: node.base.id.clone(),
+                        attempt: attempts,
+                        reason: error.to_string(),
+                    });
+                    first_error.get_or_insert(error);
+                    tokio::time::sleep(delay).await;
+                }
+                _ => return Err(first_error.unwrap_or(error)),
+            }
+        }
+    }
+
+    /// Apply one node to `ctx`; nodes other than `transform`, `human_in_loop` and `map_each`
+    /// leave it untouched. Failed nodes write nothing.
+    async fn apply_node(&self, node: &FlowNode, ctx: &mut FlowContext, mode: PlanMode<'_>) -> Result<(), FlowRunError> {
+        match &node.kind {
+            FlowNodeKind::Transform { .. } => self.apply_transform(node, ctx)?,
+            FlowNodeKind::HumanInLoop { prompt, timeout_ms } => {
+                let provider = match (mode, &self.human_input) {
+                    (PlanMode::Interactive(_), Some(provider)) => provider,
+                    _ => return Err(FlowLoadError::RuntimeRequired(node.base.id.clone()).into()),
+                };
+                let request = provider.request_input(prompt);
+                let answer = match timeout_ms {
+                    Some(ms) => tokio::time::timeout(std::time::Duration::from_millis(*ms), request)
+                        .await
+                        .unwrap_or(Err(LLMError::Timeout)),
+                    None => request.await,
+                }
+                .map_err(FlowRunError::HumanInput)?;
+                ctx.human_inputs.push((node.base.id.clone(), answer.clone()));
+                ctx.set_by_node("human_input", answer, &node.base.id);
+            }
+            FlowNodeKind::MapEach {
+                agent,
+                input_var,
+                output_var,
+                concurrency,
+            } => {
+                let Some(Value::Array(items)) = ctx.vars.get(input_var) else {
+                    return Err(FlowLoadError::MapEachInput(node.base.id.clone(), input_var.clone()).into());
+                };
+                let PlanMode::Interactive(Some((provider, tool_registries))) = mode else {
+                    return Err(FlowLoadError::RuntimeRequired(node.base.id.clone()).into());
+                };
+                let results = self
+                    .run_map_each(agent, items.clone(), *concurrency, provider, tool_registries)
+                    .await?;
+                ctx.set_by_node(output_var.clone(), Value::Array(results), &node.base.id);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Give agents wrapped by a `retry_on_fail` node a provider that retries failed calls.
+    fn apply_agent_retries(
+        &self,
+        planned: &[PlannedStep],
+        plan: &mut [ExecutionStep],
+        provider: &Arc<dyn LLMProvider>,
+    ) {
+        let wrap = |planned: &PlannedAgent, agent: &mut Agent| {
+            let Some(retry) = &planned.retry else { return };
+            let inner = agent.provider_override().unwrap_or_else(|| Arc::clone(provider));
+            let progress = self.progress.clone();
+            let node_id = retry.node_id.clone();
+            let retrying = RetryingProvider::new(inner, Arc::new(retry.clone()))
+                .with_retry_callback(move |attempt, error| {
+                    if let Some(callback) = &progress {
+                        callback(&FlowProgressEvent::RetryAttempt {
+                            node_id: node_id.clone(),
+                            attempt,
+                            reason: error.to_string(),
+                        });
+                    }
+                });
+            *agent = agent.clone().with_provider(Arc::new(retrying));
+        };
+
+        for (planned, step) in planned.iter().zip(plan.iter_mut()) {
+            match (planned, step) {
+                (PlannedStep::Agent(planned), ExecutionStep::Agent(agent)) => wrap(planned, agent),
+                (
+                    PlannedStep::Parallel { branches: planned, .. },
+                    ExecutionStep::Parallel { branches, .. },
+                ) => {
+                    for (planned, agents) in planned.iter().zip(branches.iter_mut()) {
+                        for (planned, agent) in planned.iter().zip(agents.iter_mut()) {
+                            wrap(planned, agent);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Run `agent_id` on every item with its own transcript, at most `concurrency` at a time.
+    async fn run_map_each(
+        &self,
+        agent_id: &str,
+        items: Vec<Value>,
+        concurrency: Option<usize>,
+        provider: &Arc<dyn LLMProvider>,
+        tool_registries: &HashMap<String, Arc<FunctionRegistry>>,
+    ) -> Result<Vec<Value>, FlowRunError> {
+        let agent = self
+            .build_agents(tool_registries)?
+            .remove(agent_id)
+            .ok_or_else(|| FlowLoadError::AgentNotFound(agent_id.to_string()))?;
+        let model = self
+            .document
+            .agents
+            .iter()
+            .find(|a| a.id == agent_id)
+            .map(|a| a.model.clone())
+            .unwrap_or_else(|| "gpt-4o".to_string());
+        let permits = Arc::new(tokio::sync::Semaphore::new(
+            concurrency.unwrap_or(items.len()).max(1),
+        ));
+
+        let runs = items.into_iter().map(|item| {
+            let permits = Arc::clone(&permits);
+            let agent = &agent;
+            let model = &model;
+            async move {
+                let _permit = permits.acquire().await.expect("semaphore is never closed");
+                let input = match item {
+                    Value::String(text) => text,
+                    other => other.to_string(),
+                };
+                let turn = agent
+                    .execute(provider.as_ref(), model, &[ChatMessage::user(input)])
+                    .await
+                    .map_err(AgentError::from)?;
+                Ok::<_, FlowRunError>(Value::String(turn.raw_content))
+            }
+        });
+        futures_util::future::try_join_all(runs).await
+    }
+
+    fn resolve_execution_plan(
+        &self,
+        planned: Vec<PlannedStep>,
+        tool_registries: &HashMap<String, Arc<FunctionRegistry>>,
+    ) -> Result<Vec<ExecutionStep>, FlowLoadError> {
         let agents = self.build_agents(tool_registries)?;
 
         planned
@@ -806,7 +1260,7 @@ impl FlowBuilder {
                         .get(&plan.id)
                         .cloned()
                         .ok_or_else(|| FlowLoadError::AgentNotFound(plan.id.clone()))?;
-                    Ok(ExecutionStep::Agent(apply_call_settings(agent, plan.params.as_ref())))
+                    Ok(ExecutionStep::Agent(Box::new(apply_call_settings(agent, plan.params.as_ref()))))
                 }
                 PlannedStep::Parallel { branches, converge } => {
                     let mapped = branches
@@ -829,7 +1283,7 @@ impl FlowBuilder {
                         converge,
                     })
                 }
-                PlannedStep::Tool { tool, arguments } => Ok(ExecutionStep::Tool { tool, arguments }),
+                PlannedStep::Tool { tool, arguments, retry } => Ok(ExecutionStep::Tool { tool, arguments, retry }),
             })
             .collect()
     }
@@ -848,10 +1302,18 @@ impl FlowBuilder {
     where
         F: Fn(&SequentialEvent) + Send + Sync + 'static,
     {
-        let plan = self.build_execution_plan(flow_id, ctx, tool_registries)?;
-
-        let tool_runs = execute_tool_steps(&plan, tool_registries).await?;
+        let mut ctx = ctx.clone();
+        let planned = self
+            .plan_execution_steps_with_agents(flow_id, &mut ctx, provider.clone(), tool_registries)
+            .await?;
+        let mut plan = self.resolve_execution_plan(planned.clone(), tool_registries)?;
+        self.apply_agent_retries(&planned, &mut plan, &provider);
+
+        let tool_runs = run_tool_steps(&plan, tool_registries, self.progress.as_ref()).await?;
         let mut task_with_tools = task.clone();
+        for (node, answer) in &ctx.human_inputs {
+            task_with_tools.push_str(&format!("\n[human:{node}] {answer}\n"));
+        }
         for run in &tool_runs {
             task_with_tools.push_str(&format!("\n[tool:{}] {}\n", run.tool, run.value));
         }
@@ -874,7 +1336,17 @@ impl FlowBuilder {
             .unwrap_or_else(|| "gpt-4o".to_string());
 
         let orchestrator = {
-            let base = SequentialOrchestrator::new(provider, model).with_agents(pipeline);
+            let mut base = SequentialOrchestrator::new(provider, model).with_agents(pipeline);
+            let agents = planned.iter().flat_map(|step| match step {
+                PlannedStep::Agent(agent) => vec![agent],
+                PlannedStep::Parallel { branches, .. } => branches.iter().flatten().collect(),
+                PlannedStep::Tool { .. } => Vec::new(),
+            });
+            for (index, agent) in agents.enumerate() {
+                if let Some(expression) = &agent.skip_if {
+                    base = base.with_step_condition(index, skip_condition(expression.clone(), ctx.clone()));
+                }
+            }
             if let Some(cb) = event_callback {
                 base.with_event_callback(cb)
             } else {
@@ -887,154 +1359,188 @@ impl FlowBuilder {
         Ok((run, tool_runs))
     }
 
-    fn plan_steps(
-        &self,
-        flow_id: &str,
-        ctx: &FlowContext,
-        visited_flows: &mut Vec<String>,
-    ) -> Result<Vec<PlannedStep>, FlowLoadError> {
-        if visited_flows.contains(&flow_id.to_string()) {
-            return Err(FlowLoadError::SubflowCycle(flow_id.to_string()));
-        }
-        visited_flows.push(flow_id.to_string());
-
-        let flow = self.flow(flow_id)?;
-        let mut current = flow.entry.clone();
-        let mut steps = Vec::new();
-        let mut loop_counters: HashMap<String, u32> = HashMap::new();
-
-        loop {
-            let node = flow
-                .nodes
-                .iter()
-                .find(|n| n.base.id == current)
-                .ok_or_else(|| FlowLoadError::NodeNotFound(current.clone()))?;
-
-            match &node.kind {
-                FlowNodeKind::Input {} => {}
-                FlowNodeKind::Agent { agent, parameters, .. } => {
-                    steps.push(PlannedStep::Agent(PlannedAgent {
-                        id: agent.clone(),
-                        params: parameters.clone(),
-                    }));
-                }
-                FlowNodeKind::Tool { tool, arguments } => {
-                    steps.push(PlannedStep::Tool { tool: tool.clone(), arguments: arguments.clone() });
-                }
-                FlowNodeKind::Parallel { converge } => {
-                    let converge = converge.unwrap_or(true);
-                    let outgoing: Vec<&FlowEdge> = flow
-                        .edges
-                        .iter()
-                        .filter(|e| edge_base(&e.from) == node.base.id)
-                        .collect();
-
-                    if outgoing.is_empty() {
-                        return Err(FlowLoadError::MissingParallelBranches(node.base.id.clone()));
+    fn plan_steps<'a>(
+        &'a self,
+        flow_id: &'a str,
+        ctx: &'a mut FlowContext,
+        visited_flows: &'a mut Vec<String>,
+        mode: PlanMode<'a>,
+    ) -> BoxFuture<'a, Result<Vec<PlannedStep>, FlowRunError>> {
+        Box::pin(async move {
+            if visited_flows.contains(&flow_id.to_string()) {
+                return Err(FlowLoadError::SubflowCycle(flow_id.to_string()).into());
+            }
+            visited_flows.push(flow_id.to_string());
+
+            let flow = self.flow(flow_id)?;
+            let mut current = flow.entry.clone();
+            let mut steps = Vec::new();
+            let mut loop_counters: HashMap<String, u32> = HashMap::new();
+
+            loop {
+                let node = flow
+                    .nodes
+                    .iter()
+                    .find(|n| n.base.id == current)
+                    .ok_or_else(|| FlowLoadError::NodeNotFound(current.clone()))?;
+
+                match &node.kind {
+                    FlowNodeKind::Input {} => {}
+                    FlowNodeKind::Agent { agent, parameters, skip_if, .. } => {
+                        steps.push(PlannedStep::Agent(PlannedAgent {
+                            id: agent.clone(),
+                            params: parameters.clone(),
+                            retry: self.retry_for_node(flow, &node.base.id),
+                            skip_if: skip_if.clone(),
+                        }));
+                    }
+                    FlowNodeKind::Tool { tool, arguments } => {
+                        steps.push(PlannedStep::Tool {
+                            tool: tool.clone(),
+                            arguments: arguments.clone(),
+                            retry: self.retry_for_node(flow, &node.base.id),
+                        });
                     }
+                    FlowNodeKind::Parallel { converge } => {
+                        let converge = converge.unwrap_or(true);
+                        let outgoing: Vec<&FlowEdge> = flow
+                            .edges
+                            .iter()
+                            .filter(|e| edge_base(&e.from) == node.base.id)
+                            .collect();
+
+                        if outgoing.is_empty() {
+                            return Err(FlowLoadError::MissingParallelBranches(node.base.id.clone()).into());
+                        }
 
-                    let mut branches = Vec::new();
-                    let mut join_target: Option<Option<String>> = None;
-                    for edge in outgoing {
-                        let (branch, join) = self.collect_parallel_branch(
-                            flow,
-                            &edge.to,
-                            ctx,
-                            visited_flows,
-                            HashMap::new(),
-                        )?;
-                        branches.push(branch);
-
-                        if converge {
-                            if let Some(existing) = &join_target {
-                                if existing != &join {
-                                    return Err(FlowLoadError::ParallelConvergence(node.base.id.clone()));
+                        let mut branches = Vec::new();
+                        let mut join_target: Option<Option<String>> = None;
+                        for edge in outgoing {
+                            let mut child = ctx.clone();
+                            let (branch, join) = self.collect_parallel_branch(
+                                flow,
+                                &edge.to,
+                                &mut child,
+                                visited_flows,
+                                HashMap::new(),
+                                mode,
+                            )
+                            .await?;
+                            branches.push(branch);
+                            ctx.merge_writes(&edge.to, child);
+
+                            if converge {
+                                if let Some(existing) = &join_target {
+                                    if existing != &join {
+                                        return Err(FlowLoadError::ParallelConvergence(node.base.id.clone()).into());
+                                    }
                                 }
-                            }
-                            if join_target.is_none() {
+                                if join_target.is_none() {
+                                    join_target = Some(join.clone());
+                                }
+                            } else if join_target.is_none() {
                                 join_target = Some(join.clone());
                             }
-                        } else if join_target.is_none() {
-                            join_target = Some(join.clone());
                         }
-                    }
 
-                    steps.push(PlannedStep::Parallel { branches, converge });
+                        steps.push(PlannedStep::Parallel { branches, converge });
 
-                    if let Some(next) = join_target.flatten() {
-                        current = next;
-                        continue;
-                    } else {
-                        break;
+                        if let Some(next) = join_target.flatten() {
+                            current = next;
+                            continue;
+                        } else {
+                            break;
+                        }
                     }
+                    FlowNodeKind::Decision { .. } => {}
+                    FlowNodeKind::Condition { .. } => {}
+                    FlowNodeKind::Transform { .. }
+                    | FlowNodeKind::HumanInLoop { .. }
+                    | FlowNodeKind::MapEach { .. } => self.run_node(flow, node, ctx, mode).await?,
+                    FlowNodeKind::RetryOnFail { .. } => {}
+                    FlowNodeKind::Merge {} => {}
+                    FlowNodeKind::Loop { .. } => {}
+                    FlowNodeKind::Subflow { flow } => {
+                        let mut child = ctx.clone();
+                        let mut nested = self.plan_steps(flow, &mut child, visited_flows, mode).await?;
+                        steps.append(&mut nested);
+                        ctx.merge_writes(flow, child);
+                    }
+                    FlowNodeKind::Output {} => break,
                 }
-                FlowNodeKind::Decision { .. } => {}
-                FlowNodeKind::Merge {} => {}
-                FlowNodeKind::Loop { .. } => {}
-                FlowNodeKind::Subflow { flow } => {
-                    let mut nested = self.plan_steps(flow, ctx, visited_flows)?;
-                    steps.append(&mut nested);
-                }
-                FlowNodeKind::Output {} => break,
-            }
 
-            let next = self.next_node(flow, node, ctx, &mut loop_counters)?;
-            match next {
-                Some(next_id) => {
-                    current = next_id;
+                let next = self.next_node(flow, node, ctx, &mut loop_counters)?;
+                match next {
+                    Some(next_id) => {
+                        current = next_id;
+                    }
+                    None => break,
                 }
-                None => break,
             }
-        }
 
-        visited_flows.retain(|f| f != flow_id);
-        Ok(steps)
+            visited_flows.retain(|f| f != flow_id);
+            Ok(steps)
+        })
     }
 
-    fn collect_parallel_branch(
-        &self,
-        flow: &FlowDefinition,
-        start: &str,
-        ctx: &FlowContext,
-        visited_flows: &mut Vec<String>,
+    fn collect_parallel_branch<'a>(
+        &'a self,
+        flow: &'a FlowDefinition,
+        start: &'a str,
+        ctx: &'a mut FlowContext,
+        visited_flows: &'a mut Vec<String>,
         mut loop_counters: HashMap<String, u32>,
-    ) -> Result<(Vec<PlannedAgent>, Option<String>), FlowLoadError> {
-        let mut current = start.to_string();
-        let mut branch = Vec::new();
-
-        loop {
-            let node = flow
-                .nodes
-                .iter()
-                .find(|n| n.base.id == current)
-                .ok_or_else(|| FlowLoadError::NodeNotFound(current.clone()))?;
-
-            match &node.kind {
-                FlowNodeKind::Input {} => {}
-                FlowNodeKind::Agent { agent, parameters, .. } => {
-                    branch.push(PlannedAgent {
-                        id: agent.clone(),
-                        params: parameters.clone(),
-                    });
-                }
-                FlowNodeKind::Decision { .. } => {}
-                FlowNodeKind::Tool { .. } => {}
-                FlowNodeKind::Merge {} => return Ok((branch, Some(node.base.id.clone()))),
-                FlowNodeKind::Parallel { .. } => return Err(FlowLoadError::UnsupportedNode(node.base.id.clone())),
-                FlowNodeKind::Loop { .. } => {}
-                FlowNodeKind::Subflow { flow } => {
-                    let mut nested = self.plan_nodes(flow, ctx, visited_flows)?;
-                    branch.append(&mut nested);
+        mode: PlanMode<'a>,
+    ) -> BoxFuture<'a, Result<PlannedBranch, FlowRunError>> {
+        Box::pin(async move {
+            let mut current = start.to_string();
+            let mut branch = Vec::new();
+
+            loop {
+                let node = flow
+                    .nodes
+                    .iter()
+                    .find(|n| n.base.id == current)
+                    .ok_or_else(|| FlowLoadError::NodeNotFound(current.clone()))?;
+
+                match &node.kind {
+                    FlowNodeKind::Input {} => {}
+                    FlowNodeKind::Agent { agent, parameters, skip_if, .. } => {
+                        branch.push(PlannedAgent {
+                            id: agent.clone(),
+                            params: parameters.clone(),
+                            retry: self.retry_for_node(flow, &node.base.id),
+                            skip_if: skip_if.clone(),
+                        });
+                    }
+                    FlowNodeKind::Decision { .. } => {}
+                    FlowNodeKind::Condition { .. } => {}
+                    FlowNodeKind::Transform { .. }
+                    | FlowNodeKind::HumanInLoop { .. }
+                    | FlowNodeKind::MapEach { .. } => self.run_node(flow, node, ctx, mode).await?,
+                    FlowNodeKind::RetryOnFail { .. } => {}
+                    FlowNodeKind::Tool { .. } => {}
+                    FlowNodeKind::Merge {} => return Ok((branch, Some(node.base.id.clone()))),
+                    FlowNodeKind::Parallel { .. } => {
+                        return Err(FlowLoadError::UnsupportedNode(node.base.id.clone()).into())
+                    }
+                    FlowNodeKind::Loop { .. } => {}
+                    FlowNodeKind::Subflow { flow } => {
+                        let mut child = ctx.clone();
+                        let mut nested = self.plan_nodes(flow, &mut child, visited_flows, mode).await?;
+                        branch.append(&mut nested);
+                        ctx.merge_writes(flow, child);
+                    }
+                    FlowNodeKind::Output {} => return Ok((branch, None)),
                 }
-                FlowNodeKind::Output {} => return Ok((branch, None)),
-            }
 
-            let next = self.next_node(flow, node, ctx, &mut loop_counters)?;
-            match next {
-                Some(next_id) => current = next_id,
-                None => return Ok((branch, None)),
+                let next = self.next_node(flow, node, ctx, &mut loop_counters)?;
+                match next {
+                    Some(next_id) => current = next_id,
+                    None => return Ok((branch, None)),
+                }
             }
-        }
+        })
     }
 
     fn next_node(
@@ -1044,6 +1550,23 @@ impl FlowBuilder {
         ctx: &FlowContext,
         loop_counters: &mut HashMap<String, u32>,
     ) -> Result<Option<String>, FlowLoadError> {
+        if let FlowNodeKind::Condition {
+            expression,
+            true_branch,
+            false_branch,
+        } = &node.kind
+        {
+            let branch = if condition_matches(Some(expression), ctx, None) {
+                true_branch
+            } else {
+                false_branch
+            };
+            return Ok(Some(branch.clone()));
+        }
+        if let FlowNodeKind::RetryOnFail { target_node, .. } = &node.kind {
+            return Ok(Some(target_node.clone()));
+        }
+
         let outgoing: Vec<&FlowEdge> = flow
             .edges
             .iter()
@@ -1082,99 +1605,100 @@ impl FlowBuilder {
             .map(Some)
     }
 
-    fn plan_nodes(
-        &self,
-        flow_id: &str,
-        ctx: &FlowContext,
-        visited_flows: &mut Vec<String>,
-    ) -> Result<Vec<PlannedAgent>, FlowLoadError> {
-        if visited_flows.contains(&flow_id.to_string()) {
-            return Err(FlowLoadError::SubflowCycle(flow_id.to_string()));
-        }
-        visited_flows.push(flow_id.to_string());
-
-        let flow = self
-            .document
-            .flows
-            .iter()
-            .find(|f| f.id == flow_id)
-            .ok_or_else(|| FlowLoadError::FlowNotFound(flow_id.to_string()))?;
-
-        let mut current = flow.entry.clone();
-        let mut path = Vec::new();
-        let mut loop_counters: HashMap<String, u32> = HashMap::new();
-
-        loop {
-            let node = flow
-                .nodes
-                .iter()
-                .find(|n| n.base.id == current)
-                .ok_or_else(|| FlowLoadError::NodeNotFound(current.clone()))?;
-
-            match &node.kind {
-                FlowNodeKind::Input {} => {}
-                FlowNodeKind::Agent { agent, parameters, .. } => {
-                    let id = agent.clone();
-                    path.push(PlannedAgent {
-                        id,
-                        params: parameters.clone(),
-                    });
-                }
-                FlowNodeKind::Decision { .. } => {}
-                FlowNodeKind::Tool { .. } => {}
-                FlowNodeKind::Merge {} => {}
-                FlowNodeKind::Parallel { .. } => {
-                    return Err(FlowLoadError::UnsupportedNode(node.base.id.clone()));
-                }
-                FlowNodeKind::Loop { .. } => {}
-                FlowNodeKind::Subflow { flow } => {
-                    let mut nested = self.plan_nodes(flow, ctx, visited_flows)?;
-                    path.append(&mut nested);
-                }
-                FlowNodeKind::Output {} => break,
+    fn plan_nodes<'a>(
+        &'a self,
+        flow_id: &'a str,
+        ctx: &'a mut FlowContext,
+        visited_flows: &'a mut Vec<String>,
+        mode: PlanMode<'a>,
+    ) -> BoxFuture<'a, Result<Vec<PlannedAgent>, FlowRunError>> {
+        Box::pin(async move {
+            if visited_flows.contains(&flow_id.to_string()) {
+                return Err(FlowLoadError::SubflowCycle(flow_id.to_string()).into());
             }
+            visited_flows.push(flow_id.to_string());
 
-            let outgoing: Vec<&FlowEdge> = flow
-                .edges
+            let flow = self
+                .document
+                .flows
                 .iter()
-                .filter(|e| edge_base(&e.from) == node.base.id)
-                .collect();
-
-            if outgoing.is_empty() {
-                break;
-            }
-
-            let mut selected: Option<String> = None;
-            let iteration_value = *loop_counters.get(&node.base.id).unwrap_or(&0);
-            for edge in &outgoing {
-                if condition_matches(edge.condition.as_deref(), ctx, Some(iteration_value)) {
-                    selected = Some(edge.to.clone());
-                    break;
+                .find(|f| f.id == flow_id)
+                .ok_or_else(|| FlowLoadError::FlowNotFound(flow_id.to_string()))?;
+
+            let mut current = flow.entry.clone();
+            let mut path = Vec::new();
+            let mut loop_counters: HashMap<String, u32> = HashMap::new();
+
+            loop {
+                let node = flow
+                    .nodes
+                    .iter()
+                    .find(|n| n.base.id == current)
+                    .ok_or_else(|| FlowLoadError::NodeNotFound(current.clone()))?;
+
+                match &node.kind {
+                    FlowNodeKind::Input {} => {}
+                    FlowNodeKind::Agent { agent, parameters, skip_if, .. } => {
+                        let id = agent.clone();
+                        path.push(PlannedAgent {
+                            id,
+                            params: parameters.clone(),
+                            retry: self.retry_for_node(flow, &node.base.id),
+                            skip_if: skip_if.clone(),
+                        });
+                    }
+                    FlowNodeKind::Decision { .. } => {}
+                    FlowNodeKind::Condition { .. } => {}
+                    FlowNodeKind::Transform { .. }
+                    | FlowNodeKind::HumanInLoop { .. }
+                    | FlowNodeKind::MapEach { .. } => self.run_node(flow, node, ctx, mode).await?,
+                    FlowNodeKind::RetryOnFail { .. } => {}
+                    FlowNodeKind::Tool { .. } => {}
+                    FlowNodeKind::Merge {} => {}
+                    FlowNodeKind::Parallel { .. } => {
+                        return Err(FlowLoadError::UnsupportedNode(node.base.id.clone()).into());
+                    }
+                    FlowNodeKind::Loop { .. } => {}
+                    FlowNodeKind::Subflow { flow } => {
+                        let mut child = ctx.clone();
+                        let mut nested = self.plan_nodes(flow, &mut child, visited_flows, mode).await?;
+                        path.append(&mut nested);
+                        ctx.merge_writes(flow, child);
+                    }
+                    FlowNodeKind::Output {} => break,
                 }
-            }
 
-            if selected.is_none() {
-                // Try a default if conditions are on outputs
-                for edge in &outgoing {
-                    if edge.condition.is_none() {
-                        selected = Some(edge.to.clone());
-                        break;
-                    }
+                match self.next_node(flow, node, ctx, &mut loop_counters)? {
+                    Some(next) => current = next,
+                    None => break,
                 }
             }
 
-            let next = selected.ok_or_else(|| FlowLoadError::NoMatchingEdge(node.base.id.clone()))?;
-
-            if let FlowNodeKind::Loop { .. } = &node.kind {
-                let counter = loop_counters.entry(node.base.id.clone()).or_insert(0);
-                *counter += 1;
-            }
+            visited_flows.retain(|f| f != flow_id);
+            Ok(path)
+        })
+    }
 
-            current = next;
-        }
+    /// Run a transform node against the context before planning moves past it.
+    fn apply_transform(&self, node: &FlowNode, ctx: &mut FlowContext) -> Result<(), FlowLoadError> {
+        let FlowNodeKind::Transform {
+            function,
+            input_var,
+            output_var,
+        } = &node.kind
+        else {
+            return Ok(());
+        };
 
-        visited_flows.retain(|f| f != flow_id);
-        Ok(path)
+        let transform = self.transforms.get(function).ok_or_else(|| {
+            FlowLoadError::TransformNotFound(node.base.id.clone(), function.clone())
+        })?;
+        let input = ctx.vars.get(input_var).cloned().unwrap_or(Value::Null);
+        let output = transform
+            .transform(input)
+            .map_err(|err| FlowLoadError::TransformFailed(node.base.id.clone(), err))?;
+        ctx.set_by_node(output_var.clone(), output, &node.base.id);
+        Ok(())
     }
 
     fn flow_agents(&self, flow_id: &str) -> Result<Vec<String>, FlowLoadError> {
@@ -1199,12 +1723,55 @@ impl FlowBuilder {
 #[derive(Debug, Default, Clone)]
 pub struct FlowContext {
     pub vars: HashMap<String, serde_json::Value>,
+    /// Answers collected for `human_in_loop` nodes with the node that asked, one per visit,
+    /// in the order they were given.
+    human_inputs: Vec<(String, String)>,
+    tracking: bool,
+    mutations: Vec<ContextMutation>,
+}
+
+/// One variable write recorded by a [`FlowContext`] with tracking enabled.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextMutation {
+    /// Node whose execution made the write; `None` for writes from outside the flow.
+    pub node_id: Option<String>,
+    pub key: String,
+    pub old_value: Option<Value>,
+    pub new_value: Value,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct PlannedAgent {
     id: String,
     params: Option<CallSettings>,
+    retry: Option<NodeRetry>,
+    skip_if: Option<String>,
+}
+
+/// Settings of the `retry_on_fail` node guarding a step. Every failure is retried, not
+/// only transient ones, waiting `backoff_ms * 2^attempt` between attempts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeRetry {
+    node_id: String,
+    max_retries: u32,
+    backoff_ms: u64,
+}
+
+impl NodeRetry {
+    fn delay(&self, attempt: u32) -> std::time::Duration {
+        retry_delay(std::time::Duration::from_millis(self.backoff_ms), attempt)
+    }
+}
+
+impl RetryDecisionPolicy for NodeRetry {
+    fn should_retry(&self, _error: &LLMError, attempt: usize) -> bool {
+        attempt < self.max_retries as usize
+    }
+
+    fn backoff(&self, attempt: usize) -> std::time::Duration {
+        self.delay(u32::try_from(attempt).unwrap_or(u32::MAX))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -1213,6 +1780,7 @@ pub enum PlannedStep {
     Tool {
         tool: String,
         arguments: Option<serde_json::Value>,
+        retry: Option<NodeRetry>,
     },
     Parallel {
         branches: Vec<Vec<PlannedAgent>>,
@@ -1222,10 +1790,11 @@ pub enum PlannedStep {
 
 #[derive(Debug, Clone)]
 pub enum ExecutionStep {
-    Agent(Agent),
+    Agent(Box<Agent>),
     Tool {
         tool: String,
         arguments: Option<serde_json::Value>,
+        retry: Option<NodeRetry>,
     },
     Parallel {
         branches: Vec<Vec<Agent>>,
@@ -1246,7 +1815,7 @@ pub fn flatten_agent_pipeline(steps: &[ExecutionStep]) -> Vec<Agent> {
     let mut pipeline = Vec::new();
     for step in steps {
         match step {
-            ExecutionStep::Agent(agent) => pipeline.push(agent.clone()),
+            ExecutionStep::Agent(agent) => pipeline.push(agent.as_ref().clone()),
             ExecutionStep::Parallel { branches, .. } => {
                 for branch in branches {
                     for agent in branch {
@@ -1262,14 +1831,24 @@ pub fn flatten_agent_pipeline(steps: &[ExecutionStep]) -> Vec<Agent> {
 
 /// Execute all tool steps in a plan, returning their outputs in order.
 /// Tool arguments must be JSON objects; a missing or invalid registry results in an error.
+/// Invocations guarded by a `retry_on_fail` node are retried before giving up with the
+/// first error.
 pub async fn execute_tool_steps(
     steps: &[ExecutionStep],
     tool_registries: &HashMap<String, Arc<FunctionRegistry>>,
+) -> Result<Vec<ToolRunResult>, ToolExecutionError> {
+    run_tool_steps(steps, tool_registries, None).await
+}
+
+async fn run_tool_steps(
+    steps: &[ExecutionStep],
+    tool_registries: &HashMap<String, Arc<FunctionRegistry>>,
+    progress: Option<&ProgressCallback>,
 ) -> Result<Vec<ToolRunResult>, ToolExecutionError> {
     let mut results = Vec::new();
 
     for step in steps {
-        if let ExecutionStep::Tool { tool, arguments } = step {
+        if let ExecutionStep::Tool { tool, arguments, retry } = step {
             let registry = tool_registries
                 .get(tool)
                 .ok_or_else(|| ToolExecutionError::RegistryMissing(tool.clone()))?;
@@ -1290,10 +1869,33 @@ pub async fn execute_tool_steps(
                 raw_arguments: None,
             };
 
-            let value = registry
-                .invoke(&call)
-                .await
-                .map_err(|err| ToolExecutionError::InvocationFailed(tool.clone(), err.to_string()))?;
+            let mut first_error = None;
+            let mut attempts = 0;
+            let value = loop {
+                let error = match registry.invoke(&call).await {
+                    Ok(value) => break value,
+                    Err(error) => error,
+                };
+                match retry {
+                    Some(retry) if attempts < retry.max_retries => {
+                        let delay = retry.delay(attempts);
+                        attempts += 1;
+                        if let Some(callback) = progress {
+                            callback(&FlowProgressEvent::RetryAttempt {
+                                node_id: retry.node_id.clone(),
+                                attempt: attempts,
+                                reason: error.to_string(),
+                            });
+                        }
+                        first_error.get_or_insert(error);
+                        tokio::time::sleep(delay).await;
+                    }
+                    _ => {
+                        let error = first_error.unwrap_or(error);
+                        return Err(ToolExecutionError::InvocationFailed(tool.clone(), error.to_string()));
+                    }
+                }
+            };
 
             results.push(ToolRunResult {
                 tool: tool.clone(),
@@ -1311,10 +1913,126 @@ impl FlowContext {
         self
     }
 
-}
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.vars.get(key)
+    }
 
-fn condition_matches(condition: Option<&str>, ctx: &FlowContext, iteration: Option<u32>) -> bool {
-    match condition {
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.write(None, key.into(), value.into());
+    }
+
+    /// Like [`FlowContext::set`], attributing the write to `node_id` in the mutation log.
+    pub fn set_by_node(&mut self, key: impl Into<String>, value: impl Into<Value>, node_id: &str) {
+        self.write(Some(node_id.to_string()), key.into(), value.into());
+    }
+
+    /// Record every write made through [`FlowContext::set`] and [`FlowContext::set_by_node`].
+    pub fn with_tracking(mut self, enabled: bool) -> Self {
+        self.tracking = enabled;
+        self
+    }
+
+    pub fn mutation_log(&self) -> &[ContextMutation] {
+        &self.mutations
+    }
+
+    pub fn mutations_for_key(&self, key: &str) -> Vec<&ContextMutation> {
+        self.mutations.iter().filter(|mutation| mutation.key == key).collect()
+    }
+
+    fn write(&mut self, node_id: Option<String>, key: String, value: Value) {
+        let old_value = self.vars.insert(key.clone(), value.clone());
+        if self.tracking {
+            self.mutations.push(ContextMutation {
+                node_id,
+                key,
+                old_value,
+                new_value: value,
+                timestamp: chrono::Utc::now(),
+            });
+        }
+    }
+
+    /// View of this context that reads and writes `{name}.{key}` instead of `key`.
+    pub fn scope(&mut self, name: &str) -> ScopedFlowContext<'_> {
+        ScopedFlowContext {
+            parent: self,
+            prefix: format!("{name}."),
+        }
+    }
+
+    /// Copy every variable of `source` into this context as `{scope_name}.{key}`.
+    pub fn merge_scope(&mut self, scope_name: &str, source: FlowContext) {
+        let mut scoped = self.scope(scope_name);
+        for (key, value) in source.vars {
+            scoped.set(key, value);
+        }
+    }
+
+    /// Keep what a subflow or parallel branch planned against a copy of this context wrote,
+    /// under `{scope_name}.`, so two of them setting the same variable don't overwrite each other.
+    fn merge_writes(&mut self, scope_name: &str, mut child: FlowContext) {
+        // The child's log starts out as a copy of this one; keep only what it added, with the
+        // node that made each write, instead of logging the merge itself.
+        let mut mutations = child.mutations.split_off(self.mutations.len());
+        for mutation in &mut mutations {
+            mutation.key = format!("{scope_name}.{}", mutation.key);
+        }
+        let mut answers = child.human_inputs.split_off(self.human_inputs.len());
+        self.human_inputs.append(&mut answers);
+
+        let vars = child
+            .vars
+            .into_iter()
+            .filter(|(key, value)| self.vars.get(key) != Some(value))
+            .collect();
+        let tracking = std::mem::replace(&mut self.tracking, false);
+        self.merge_scope(scope_name, FlowContext { vars, ..Default::default() });
+        self.tracking = tracking;
+        self.mutations.append(&mut mutations);
+    }
+}
+
+/// A [`FlowContext`] seen through [`FlowContext::scope`].
+#[derive(Debug)]
+pub struct ScopedFlowContext<'a> {
+    parent: &'a mut FlowContext,
+    prefix: String,
+}
+
+impl ScopedFlowContext<'_> {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.parent.get(&format!("{}{key}", self.prefix))
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.parent.set(format!("{}{}", self.prefix, key.into()), value);
+    }
+}
+
+/// Drive a planning pass in [`PlanMode::Offline`], which completes without waiting.
+fn plan_offline<T>(pass: BoxFuture<'_, Result<T, FlowRunError>>) -> Result<T, FlowLoadError> {
+    match pass.now_or_never().expect("offline planning never waits") {
+        Ok(value) => Ok(value),
+        Err(FlowRunError::Load(err)) => Err(err),
+        Err(err) => unreachable!("offline planning only fails to load: {err}"),
+    }
+}
+
+/// Step condition for an agent node with `skip_if`: the step runs unless `expression` holds
+/// for `ctx` plus `output`, the previous step's output.
+fn skip_condition(
+    expression: String,
+    ctx: FlowContext,
+) -> impl Fn(&SequentialContext<'_>) -> bool + Send + Sync + 'static {
+    move |step| {
+        let ctx = ctx.clone().with_var("output", step.previous_output);
+        !condition_matches(Some(&expression), &ctx, None)
+    }
+}
+
+fn condition_matches(condition: Option<&str>, ctx: &FlowContext, iteration: Option<u32>) -> bool {
+    match condition {
         None => true,
         Some(text) if text.trim().eq_ignore_ascii_case("else") => true,
         Some(text) => {
@@ -1505,6 +2223,7 @@ mod tests {
     use super::*;
     use crate::providers::scripted::ScriptedProvider;
     use crate::eval::scenario::ScriptedTurn;
+    use crate::flows::human_input::MockHumanInputProvider;
     use std::time::{SystemTime, UNIX_EPOCH};
     use std::sync::Mutex;
     use crate::functions::{KernelFunction, FunctionDefinition, FunctionParameter, json_schema_for};
@@ -1738,6 +2457,7 @@ flows:
             prompt,
             tools,
             parameters,
+            ..
         } = &flow.nodes[1].kind
         {
             assert_eq!(agent, "analyst");
@@ -1858,6 +2578,7 @@ flows:
                                     backoff_ms: None,
                                 }),
                             }),
+                            skip_if: None,
                         },
                     },
                     FlowNode {
@@ -1945,6 +2666,39 @@ flows:
         assert_eq!(agents.get("a2").unwrap().instructions(), "inline prompt");
     }
 
+    #[test]
+    fn renders_system_prompts_with_context_variables() {
+        let yaml = r#"
+agents:
+  - id: billing
+    model: m
+    system_prompt: "You are {{role | upper}} specialist for {{domain | default(value='general')}} tasks.{% if notes %} {{notes | truncate(length=10)}}{% endif %}"
+flows:
+  - id: main
+    entry: n1
+    nodes:
+      - id: n1
+        type: input
+      - id: n2
+        type: output
+"#;
+
+        let builder = FlowBuilder::from_yaml_str(".", yaml).expect("builder");
+        let ctx = FlowContext::default().with_var("role", "billing");
+        let agents = builder.build_agents_with_context(&ctx, &HashMap::new()).expect("agents");
+        assert_eq!(agents["billing"].instructions(), "You are BILLING specialist for general tasks.");
+
+        let ctx = ctx.with_var("notes", "Be brief & polite.");
+        let agents = builder.build_agents_with_context(&ctx, &HashMap::new()).expect("agents");
+        assert!(agents["billing"].instructions().ends_with("tasks. Be brief &…"));
+
+        let untouched = builder.build_agents(&HashMap::new()).expect("agents");
+        assert!(untouched["billing"].instructions().contains("{{role | upper}}"));
+
+        let failed = builder.build_agents_with_context(&FlowContext::default(), &HashMap::new());
+        assert!(matches!(failed, Err(FlowLoadError::PromptTemplate(agent, _)) if agent == "billing"));
+    }
+
     #[tokio::test]
     async fn builds_sequential_orchestrator_for_linear_flow() {
         let yaml = r#"
@@ -1994,6 +2748,64 @@ flows:
         assert_eq!(run.final_output.as_deref(), Some("done"));
     }
 
+    #[tokio::test]
+    async fn skip_if_skips_agent_steps_in_sequential_flows() {
+        let yaml = r#"
+agents:
+  - id: researcher
+    model: scripted
+    system_prompt: research
+  - id: verifier
+    model: scripted
+    system_prompt: verify
+  - id: writer
+    model: scripted
+    system_prompt: write
+flows:
+  - id: main
+    entry: start
+    nodes:
+      - id: start
+        type: input
+      - id: research
+        type: agent
+        agent: researcher
+      - id: verify
+        type: agent
+        agent: verifier
+        skip_if: "output == '42'"
+      - id: write
+        type: agent
+        agent: writer
+      - id: end
+        type: output
+    edges:
+      - from: start
+        to: research
+      - from: research
+        to: verify
+      - from: verify
+        to: write
+      - from: write
+        to: end
+"#;
+
+        let builder = FlowBuilder::from_yaml_str(".", yaml).expect("builder");
+        let provider = Arc::new(ScriptedProvider::from_scripted_turns(&[
+            ScriptedTurn { agent: "researcher".to_string(), response: "42".to_string(), latency_ms: None },
+            ScriptedTurn { agent: "writer".to_string(), response: "It is 42".to_string(), latency_ms: None },
+        ]));
+
+        let orchestrator = builder
+            .build_sequential_orchestrator(provider, "main", &HashMap::new())
+            .expect("orchestrator");
+        let run = orchestrator.run("task").await.expect("run");
+
+        assert!(matches!(&run.events[1], SequentialEvent::StepSkipped { agent, .. } if agent == "verifier"));
+        assert_eq!(run.step_outputs, vec![Some("42".to_string()), None, Some("It is 42".to_string())]);
+        assert_eq!(run.final_output.as_deref(), Some("It is 42"));
+    }
+
     #[test]
     fn rejects_non_sequential_flow() {
         let yaml = r#"
@@ -2119,6 +2931,597 @@ flows:
         assert_eq!(plan[0].name(), "a2");
     }
 
+    #[test]
+    fn plans_condition_branch_without_llm() {
+        let yaml = r#"
+agents:
+  - id: approve
+    model: m
+    system_prompt: p1
+  - id: review
+    model: m
+    system_prompt: p2
+flows:
+  - id: main
+    entry: start
+    nodes:
+      - id: start
+        type: input
+      - id: check
+        type: condition
+        expression: "score >= 0.5"
+        true_branch: approve_node
+        false_branch: review_node
+        outputs:
+          - label: "true"
+          - label: "false"
+      - id: approve_node
+        type: agent
+        agent: approve
+      - id: review_node
+        type: agent
+        agent: review
+      - id: end
+        type: output
+    edges:
+      - from: start
+        to: check
+      - from: check:true
+        to: approve_node
+      - from: check:false
+        to: review_node
+      - from: approve_node
+        to: end
+      - from: review_node
+        to: end
+"#;
+
+        let builder = FlowBuilder::from_yaml_str(".", yaml).expect("builder");
+        let flow = &builder.document().flows[0];
+        assert!(matches!(
+            &flow.nodes[1].kind,
+            FlowNodeKind::Condition { expression, .. } if expression == "score >= 0.5"
+        ));
+
+        let ctx = FlowContext::default().with_var("score", 0.7);
+        let steps = builder.plan_execution_steps("main", &ctx).expect("steps");
+        assert_eq!(
+            steps,
+            vec![PlannedStep::Agent(PlannedAgent {
+                id: "approve".to_string(),
+                params: None,
+                retry: None,
+                skip_if: None,
+            })]
+        );
+
+        let ctx = FlowContext::default().with_var("score", 0.2);
+        let plan = builder.plan_sequential_path("main", &ctx, &HashMap::new()).expect("plan");
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].name(), "review");
+    }
+
+    #[test]
+    fn transform_nodes_update_context_before_branching() {
+        let yaml = r#"
+agents:
+  - id: big
+    model: m
+    system_prompt: p1
+  - id: small
+    model: m
+    system_prompt: p2
+flows:
+  - id: main
+    entry: start
+    nodes:
+      - id: start
+        type: input
+      - id: double
+        type: transform
+        function: double
+        input_var: amount
+        output_var: doubled
+      - id: check
+        type: condition
+        expression: "doubled > 10"
+        true_branch: big_node
+        false_branch: small_node
+      - id: big_node
+        type: agent
+        agent: big
+      - id: small_node
+        type: agent
+        agent: small
+      - id: end
+        type: output
+    edges:
+      - from: start
+        to: double
+      - from: double
+        to: check
+      - from: big_node
+        to: end
+      - from: small_node
+        to: end
+"#;
+
+        let mut builder = FlowBuilder::from_yaml_str(".", yaml).expect("builder");
+        let missing = builder.plan_execution_steps("main", &FlowContext::default());
+        assert!(matches!(missing, Err(FlowLoadError::TransformNotFound(node, f)) if node == "double" && f == "double"));
+
+        builder.register_transform(
+            "double",
+            Arc::new(|input: Value| {
+                input
+                    .as_f64()
+                    .map(|n| Value::from(n * 2.0))
+                    .ok_or_else(|| "expected a number".to_string())
+            }),
+        );
+
+        let mut ctx = FlowContext::default().with_var("amount", 6);
+        let steps = builder.plan_execution_steps_in("main", &mut ctx).expect("steps");
+        assert_eq!(ctx.vars["doubled"], Value::from(12.0));
+        assert_eq!(
+            steps,
+            vec![PlannedStep::Agent(PlannedAgent {
+                id: "big".to_string(),
+                params: None,
+                retry: None,
+                skip_if: None,
+            })]
+        );
+
+        let plan = builder
+            .plan_sequential_path("main", &FlowContext::default().with_var("amount", 2), &HashMap::new())
+            .expect("plan");
+        assert_eq!(plan[0].name(), "small");
+
+        let failed = builder.plan_execution_steps("main", &FlowContext::default().with_var("amount", "x"));
+        assert!(matches!(failed, Err(FlowLoadError::TransformFailed(_, message)) if message == "expected a number"));
+    }
+
+    const HUMAN_FLOW: &str = r#"
+agents:
+  - id: publish
+    model: m
+    system_prompt: p1
+  - id: revise
+    model: m
+    system_prompt: p2
+flows:
+  - id: main
+    entry: start
+    nodes:
+      - id: start
+        type: input
+      - id: review
+        type: human_in_loop
+        prompt: "Approve the draft?"
+        timeout_ms: 50
+      - id: publish_node
+        type: agent
+        agent: publish
+      - id: revise_node
+        type: agent
+        agent: revise
+      - id: end
+        type: output
+    edges:
+      - from: start
+        to: review
+      - from: review
+        to: publish_node
+        condition: "human_input == 'yes'"
+      - from: review
+        to: revise_node
+        condition: "else"
+      - from: publish_node
+        to: end
+      - from: revise_node
+        to: end
+"#;
+
+    #[tokio::test]
+    async fn human_input_populates_context_and_continues() {
+        let mock = Arc::new(MockHumanInputProvider::new(vec!["yes".to_string(), "no".to_string()]));
+        let builder = FlowBuilder::from_yaml_str(".", HUMAN_FLOW)
+            .expect("builder")
+            .with_human_input_provider(mock.clone());
+
+        assert!(matches!(
+            builder.plan_execution_steps("main", &FlowContext::default()),
+            Err(FlowLoadError::RuntimeRequired(node)) if node == "review"
+        ));
+
+        let mut ctx = FlowContext::default();
+        let steps = builder
+            .plan_execution_steps_interactive("main", &mut ctx)
+            .await
+            .expect("steps");
+        assert_eq!(ctx.vars["human_input"], Value::from("yes"));
+        assert_eq!(mock.prompts(), vec!["Approve the draft?".to_string()]);
+        assert_eq!(
+            steps,
+            vec![PlannedStep::Agent(PlannedAgent {
+                id: "publish".to_string(),
+                params: None,
+                retry: None,
+                skip_if: None,
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn human_input_times_out() {
+        struct SlowHuman;
+
+        #[async_trait::async_trait]
+        impl HumanInputProvider for SlowHuman {
+            async fn request_input(&self, _prompt: &str) -> Result<String, LLMError> {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                Ok("too late".to_string())
+            }
+        }
+
+        let builder = FlowBuilder::from_yaml_str(".", HUMAN_FLOW)
+            .expect("builder")
+            .with_human_input_provider(Arc::new(SlowHuman));
+        let result = builder
+            .plan_execution_steps_interactive("main", &mut FlowContext::default())
+            .await;
+        assert!(matches!(result, Err(FlowRunError::HumanInput(LLMError::Timeout))));
+    }
+
+    #[tokio::test]
+    async fn human_input_is_asked_on_every_loop_visit() {
+        let yaml = r#"
+agents:
+  - id: publish
+    model: m
+    system_prompt: p1
+flows:
+  - id: main
+    entry: start
+    nodes:
+      - id: start
+        type: input
+      - id: count
+        type: transform
+        function: increment
+        input_var: rounds
+        output_var: rounds
+      - id: review
+        type: human_in_loop
+        prompt: "Approve the draft?"
+      - id: check
+        type: condition
+        expression: "human_input == 'yes'"
+        true_branch: publish_node
+        false_branch: count
+      - id: publish_node
+        type: agent
+        agent: publish
+      - id: end
+        type: output
+    edges:
+      - from: start
+        to: count
+      - from: count
+        to: review
+      - from: review
+        to: check
+      - from: publish_node
+        to: end
+"#;
+        let mock = Arc::new(MockHumanInputProvider::new(vec!["no".to_string(), "yes".to_string()]));
+        let mut builder = FlowBuilder::from_yaml_str(".", yaml)
+            .expect("builder")
+            .with_human_input_provider(mock.clone());
+        let transforms = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&transforms);
+        builder.register_transform(
+            "increment",
+            Arc::new(move |input: Value| {
+                *counter.lock().unwrap() += 1;
+                Ok(Value::from(input.as_u64().unwrap_or_default() + 1))
+            }),
+        );
+
+        let mut ctx = FlowContext::default();
+        let steps = builder
+            .plan_execution_steps_interactive("main", &mut ctx)
+            .await
+            .expect("steps");
+        assert_eq!(steps.len(), 1);
+        assert_eq!(mock.prompts().len(), 2);
+        assert_eq!(*transforms.lock().unwrap(), 2);
+        assert_eq!(ctx.vars["rounds"], Value::from(2));
+        assert_eq!(
+            ctx.human_inputs,
+            vec![("review".to_string(), "no".to_string()), ("review".to_string(), "yes".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn map_each_runs_agent_per_item() {
+        let yaml = r#"
+agents:
+  - id: summarizer
+    model: m
+    system_prompt: Summarize the document.
+  - id: reporter
+    model: m
+    system_prompt: Report.
+flows:
+  - id: main
+    entry: start
+    nodes:
+      - id: start
+        type: input
+      - id: summarize_all
+        type: map_each
+        agent: summarizer
+        input_var: documents
+        output_var: summaries
+        concurrency: 2
+      - id: report
+        type: agent
+        agent: reporter
+      - id: end
+        type: output
+    edges:
+      - from: start
+        to: summarize_all
+      - from: summarize_all
+        to: report
+      - from: report
+        to: end
+"#;
+
+        let builder = FlowBuilder::from_yaml_str(".", yaml).expect("builder");
+        let mut provider = ScriptedProvider::new();
+        provider.echo_user_messages();
+        let provider: Arc<dyn LLMProvider> = Arc::new(provider);
+
+        let mut ctx = FlowContext::default().with_var("documents", serde_json::json!(["one", "two", "three"]));
+        let steps = builder
+            .plan_execution_steps_with_agents("main", &mut ctx, provider.clone(), &HashMap::new())
+            .await
+            .expect("steps");
+        assert_eq!(ctx.vars["summaries"], serde_json::json!(["one", "two", "three"]));
+        assert_eq!(steps.len(), 1);
+
+        assert!(matches!(
+            builder.plan_execution_steps("main", &FlowContext::default().with_var("documents", "one")),
+            Err(FlowLoadError::MapEachInput(node, var)) if node == "summarize_all" && var == "documents"
+        ));
+        assert!(matches!(
+            builder
+                .plan_execution_steps_interactive(
+                    "main",
+                    &mut FlowContext::default().with_var("documents", serde_json::json!([1]))
+                )
+                .await,
+            Err(FlowRunError::Load(FlowLoadError::RuntimeRequired(node))) if node == "summarize_all"
+        ));
+    }
+
+    const RETRY_FLOW: &str = r#"
+agents:
+  - id: writer
+    model: m
+    system_prompt: Write.
+flows:
+  - id: main
+    entry: start
+    nodes:
+      - id: start
+        type: input
+      - id: guard
+        type: retry_on_fail
+        target_node: draft
+        max_retries: 3
+        backoff_ms: 1
+      - id: draft
+        type: agent
+        agent: writer
+      - id: end
+        type: output
+    edges:
+      - from: start
+        to: guard
+      - from: draft
+        to: end
+"#;
+
+    #[test]
+    fn retry_guards_apply_only_within_their_flow() {
+        let yaml = RETRY_FLOW.to_string()
+            + r#"
+  - id: unguarded
+    entry: start
+    nodes:
+      - id: start
+        type: input
+      - id: draft
+        type: agent
+        agent: writer
+      - id: end
+        type: output
+    edges:
+      - from: start
+        to: draft
+      - from: draft
+        to: end
+"#;
+        let builder = FlowBuilder::from_yaml_str(".", &yaml).expect("builder");
+        let retry_of = |flow_id: &str| match &builder.plan_execution_steps(flow_id, &FlowContext::default()).unwrap()[..] {
+            [PlannedStep::Agent(agent)] => agent.retry.clone(),
+            steps => panic!("unexpected plan: {steps:?}"),
+        };
+
+        assert_eq!(retry_of("main").map(|retry| retry.max_retries), Some(3));
+        assert_eq!(retry_of("unguarded"), None);
+    }
+
+    #[tokio::test]
+    async fn retry_on_fail_retries_agent_node() {
+        let builder = FlowBuilder::from_yaml_str(".", RETRY_FLOW).expect("builder");
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&events);
+        let builder = builder.with_progress_callback(move |event| seen.lock().unwrap().push(event.clone()));
+
+        let mut provider = ScriptedProvider::from_scripted_turns(&[ScriptedTurn {
+            agent: "writer".to_string(),
+            response: "draft".to_string(),
+            latency_ms: None,
+        }]);
+        provider.inject_transient_errors(2, LLMError::Provider("rate limited".to_string()));
+        let provider = Arc::new(provider);
+
+        let (run, _) = builder
+            .run_sequential_flow::<fn(&SequentialEvent)>(
+                "main",
+                &FlowContext::default(),
+                &HashMap::new(),
+                provider.clone(),
+                "task".to_string(),
+                None,
+            )
+            .await
+            .expect("run");
+        assert_eq!(run.final_output.as_deref(), Some("draft"));
+        assert_eq!(provider.calls(), 3);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[1],
+            FlowProgressEvent::RetryAttempt { node_id, attempt: 2, reason }
+                if node_id == "draft" && reason.contains("rate limited")
+        ));
+    }
+
+    #[tokio::test]
+    async fn retry_on_fail_retries_non_transient_agent_errors() {
+        let builder = FlowBuilder::from_yaml_str(".", RETRY_FLOW).expect("builder");
+        let mut provider = ScriptedProvider::with_responses(&["draft"]);
+        provider.inject_transient_errors(2, LLMError::InvalidResponse("no choices"));
+        let provider = Arc::new(provider);
+
+        let (run, _) = builder
+            .run_sequential_flow::<fn(&SequentialEvent)>(
+                "main",
+                &FlowContext::default(),
+                &HashMap::new(),
+                provider.clone(),
+                "task".to_string(),
+                None,
+            )
+            .await
+            .expect("run");
+        assert_eq!(run.final_output.as_deref(), Some("draft"));
+        assert_eq!(provider.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn retry_on_fail_retries_tool_node() {
+        let yaml = RETRY_FLOW.replace("target_node: draft", "target_node: fetch").replace(
+            "      - id: draft\n",
+            "      - id: fetch\n        type: tool\n        tool: fetcher\n      - id: draft\n",
+        ) + "      - from: fetch\n        to: draft\n";
+        let builder = FlowBuilder::from_yaml_str(".", &yaml).expect("builder");
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&events);
+        let builder = builder.with_progress_callback(move |event| seen.lock().unwrap().push(event.clone()));
+
+        let attempts = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&attempts);
+        let mut registry = FunctionRegistry::new();
+        registry.register(crate::functions::kernel_fn_sync("fetch", "", Vec::new(), move |_| {
+            let mut attempts = counter.lock().unwrap();
+            *attempts += 1;
+            match *attempts {
+                n if n < 3 => Err(LLMError::InvalidResponse("empty body")),
+                _ => Ok(Value::from("fetched")),
+            }
+        }));
+        let registries = HashMap::from([("fetcher".to_string(), Arc::new(registry))]);
+
+        let (run, tool_runs) = builder
+            .run_sequential_flow::<fn(&SequentialEvent)>(
+                "main",
+                &FlowContext::default(),
+                &registries,
+                Arc::new(ScriptedProvider::with_responses(&["draft"])),
+                "task".to_string(),
+                None,
+            )
+            .await
+            .expect("run");
+        assert_eq!(run.final_output.as_deref(), Some("draft"));
+        assert_eq!(tool_runs[0].value, Value::from("fetched"));
+        assert_eq!(*attempts.lock().unwrap(), 3);
+        assert!(matches!(
+            &events.lock().unwrap()[..],
+            [FlowProgressEvent::RetryAttempt { node_id, attempt: 1, .. }, FlowProgressEvent::RetryAttempt { attempt: 2, .. }]
+                if node_id == "fetch"
+        ));
+    }
+
+    #[tokio::test]
+    async fn retry_on_fail_restores_context_and_returns_first_error() {
+        let yaml = RETRY_FLOW.replace(
+            "type: agent\n        agent: writer",
+            "type: transform\n        function: flaky\n        input_var: text\n        output_var: text",
+        );
+        let mut builder = FlowBuilder::from_yaml_str(".", &yaml).expect("builder");
+        let attempts = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&attempts);
+        builder.register_transform(
+            "flaky",
+            Arc::new(move |input: Value| {
+                let mut attempts = counter.lock().unwrap();
+                *attempts += 1;
+                match *attempts {
+                    n if n < 3 => Err(format!("attempt {n} failed")),
+                    _ => Ok(Value::from(format!("{}!", input.as_str().unwrap_or_default()))),
+                }
+            }),
+        );
+
+        let mut ctx = FlowContext::default().with_var("text", "hi");
+        builder
+            .plan_execution_steps_interactive("main", &mut ctx)
+            .await
+            .expect("steps");
+        assert_eq!(ctx.vars["text"], Value::from("hi!"));
+        assert_eq!(*attempts.lock().unwrap(), 3);
+
+        let failures = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&failures);
+        builder.register_transform(
+            "flaky",
+            Arc::new(move |_: Value| {
+                let mut failures = counter.lock().unwrap();
+                *failures += 1;
+                Err(format!("attempt {failures} failed"))
+            }),
+        );
+        let error = builder
+            .plan_execution_steps_interactive("main", &mut FlowContext::default().with_var("text", "hi"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            FlowRunError::Load(FlowLoadError::TransformFailed(node, message))
+                if node == "draft" && message == "attempt 1 failed"
+        ));
+        assert_eq!(*failures.lock().unwrap(), 4);
+    }
+
     #[test]
     fn plans_loop_with_iteration_guard() {
         let yaml = r#"
@@ -2216,6 +3619,180 @@ flows:
         assert_eq!(plan[1].name(), "main_agent");
     }
 
+    #[test]
+    fn scoped_contexts_keep_same_named_variables_apart() {
+        let mut ctx = FlowContext::default().with_var("output", "root");
+        ctx.scope("left").set("output", "from left");
+        ctx.scope("right").set("output", "from right");
+
+        assert_eq!(ctx.scope("left").get("output"), Some(&Value::from("from left")));
+        assert_eq!(ctx.scope("right").get("output"), Some(&Value::from("from right")));
+        assert_eq!(ctx.get("output"), Some(&Value::from("root")));
+
+        let source = FlowContext::default().with_var("output", 1).with_var("score", 0.5);
+        ctx.merge_scope("review", source);
+        assert_eq!(ctx.get("review.output"), Some(&Value::from(1)));
+        assert_eq!(ctx.get("review.score"), Some(&Value::from(0.5)));
+        assert!(ctx.get("score").is_none());
+    }
+
+    #[test]
+    fn subflow_writes_land_in_their_own_scope() {
+        let yaml = r#"
+agents:
+  - id: writer
+    model: m
+    system_prompt: write
+flows:
+  - id: first
+    entry: f_start
+    nodes:
+      - id: f_start
+        type: input
+      - id: f_tag
+        type: transform
+        function: tag_first
+        input_var: topic
+        output_var: output
+      - id: f_end
+        type: output
+    edges:
+      - from: f_start
+        to: f_tag
+      - from: f_tag
+        to: f_end
+  - id: second
+    entry: s_start
+    nodes:
+      - id: s_start
+        type: input
+      - id: s_tag
+        type: transform
+        function: tag_second
+        input_var: topic
+        output_var: output
+      - id: s_end
+        type: output
+    edges:
+      - from: s_start
+        to: s_tag
+      - from: s_tag
+        to: s_end
+  - id: main
+    entry: start
+    nodes:
+      - id: start
+        type: input
+      - id: one
+        type: subflow
+        flow: first
+      - id: two
+        type: subflow
+        flow: second
+      - id: write
+        type: agent
+        agent: writer
+      - id: end
+        type: output
+    edges:
+      - from: start
+        to: one
+      - from: one
+        to: two
+      - from: two
+        to: write
+      - from: write
+        to: end
+"#;
+
+        let mut builder = FlowBuilder::from_yaml_str(".", yaml).expect("builder");
+        builder.register_transform("tag_first", Arc::new(|input: Value| Ok(Value::from(format!("first: {input}")))));
+        builder.register_transform("tag_second", Arc::new(|input: Value| Ok(Value::from(format!("second: {input}")))));
+
+        let mut ctx = FlowContext::default().with_var("topic", "rust");
+        builder.plan_execution_steps_in("main", &mut ctx).expect("steps");
+        assert_eq!(ctx.get("first.output"), Some(&Value::from("first: \"rust\"")));
+        assert_eq!(ctx.get("second.output"), Some(&Value::from("second: \"rust\"")));
+        assert!(ctx.get("output").is_none());
+        assert!(ctx.get("first.topic").is_none());
+    }
+
+    #[test]
+    fn tracking_records_each_node_write() {
+        let yaml = r#"
+agents:
+  - id: writer
+    model: m
+    system_prompt: write
+flows:
+  - id: main
+    entry: start
+    nodes:
+      - id: start
+        type: input
+      - id: trim
+        type: transform
+        function: trim
+        input_var: draft
+        output_var: draft
+      - id: upper
+        type: transform
+        function: upper
+        input_var: draft
+        output_var: title
+      - id: measure
+        type: transform
+        function: measure
+        input_var: title
+        output_var: length
+      - id: write
+        type: agent
+        agent: writer
+      - id: end
+        type: output
+    edges:
+      - from: start
+        to: trim
+      - from: trim
+        to: upper
+      - from: upper
+        to: measure
+      - from: measure
+        to: write
+      - from: write
+        to: end
+"#;
+
+        let mut builder = FlowBuilder::from_yaml_str(".", yaml).expect("builder");
+        let text = |input: &Value| input.as_str().map(str::to_string).ok_or_else(|| "expected text".to_string());
+        builder.register_transform("trim", Arc::new(move |input: Value| Ok(Value::from(text(&input)?.trim()))));
+        builder.register_transform("upper", Arc::new(move |input: Value| Ok(Value::from(text(&input)?.to_uppercase()))));
+        builder.register_transform("measure", Arc::new(move |input: Value| Ok(Value::from(text(&input)?.len()))));
+
+        let mut ctx = FlowContext::default().with_var("draft", "  hello ").with_tracking(true);
+        builder.plan_execution_steps_in("main", &mut ctx).expect("steps");
+
+        let log = ctx.mutation_log();
+        assert_eq!(log.len(), 3);
+        let writes: Vec<(Option<&str>, &str)> =
+            log.iter().map(|m| (m.node_id.as_deref(), m.key.as_str())).collect();
+        assert_eq!(
+            writes,
+            vec![(Some("trim"), "draft"), (Some("upper"), "title"), (Some("measure"), "length")]
+        );
+        assert_eq!(log[0].old_value, Some(Value::from("  hello ")));
+        assert_eq!(log[0].new_value, Value::from("hello"));
+        assert_eq!(log[2].new_value, Value::from(5));
+
+        let draft = ctx.mutations_for_key("draft");
+        assert_eq!(draft.len(), 1);
+        assert_eq!(draft[0].node_id.as_deref(), Some("trim"));
+
+        let mut untracked = FlowContext::default();
+        untracked.set("output", 1);
+        assert!(untracked.mutation_log().is_empty());
+    }
+
     #[test]
     fn plans_parallel_execution_steps() {
         let yaml = r#"
@@ -2519,7 +4096,7 @@ flows:
         let steps = builder
             .plan_execution_steps("main", &FlowContext::default())
             .expect("steps");
-        assert!(matches!(&steps[0], PlannedStep::Tool { tool, arguments: None } if tool == "t1"));
+        assert!(matches!(&steps[0], PlannedStep::Tool { tool, arguments: None, retry: None } if tool == "t1"));
     }
 
     #[test]
@@ -1,14 +1,19 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 use async_trait::async_trait;
+pub mod acl;
+pub mod analytics;
 pub mod http;
+pub mod remote;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize, Serializer};
 use serde::ser::SerializeStruct;
 use serde_json::Value;
 
 use crate::LLMError;
+use acl::FunctionAcl;
+use analytics::FunctionAnalyticsStore;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionDefinition {
@@ -307,15 +312,101 @@ pub trait KernelFunction: Send + Sync {
     fn definition(&self) -> FunctionDefinition;
 
     async fn invoke(&self, arguments: &Value) -> Result<Value, LLMError>;
+
+    /// OpenAPI 3.1 operation object describing this function, if it publishes one
+    /// (see `#[kernel_function(openapi = true)]`).
+    fn openapi_operation(&self) -> Option<Value> {
+        None
+    }
 }
 
 pub type DynKernelFunction = Arc<dyn KernelFunction>;
 
+pub use futures_util::future::BoxFuture;
+
+type KernelFnHandler = dyn Fn(Value) -> BoxFuture<'static, Result<Value, LLMError>> + Send + Sync;
+
+struct ClosureKernelFunction {
+    definition: FunctionDefinition,
+    handler: Box<KernelFnHandler>,
+}
+
+#[async_trait]
+impl KernelFunction for ClosureKernelFunction {
+    fn definition(&self) -> FunctionDefinition {
+        self.definition.clone()
+    }
+
+    async fn invoke(&self, arguments: &Value) -> Result<Value, LLMError> {
+        (self.handler)(arguments.clone()).await
+    }
+}
+
+fn closure_definition(name: &str, description: &str, params: Vec<FunctionParameter>) -> FunctionDefinition {
+    let mut definition = FunctionDefinition::new(name);
+    if !description.is_empty() {
+        definition = definition.with_description(description);
+    }
+    for param in params {
+        definition.add_parameter(param);
+    }
+    definition
+}
+
+/// Build a kernel function from an async closure that receives the raw JSON arguments.
+pub fn kernel_fn<F>(name: &str, description: &str, params: Vec<FunctionParameter>, f: F) -> DynKernelFunction
+where
+    F: Fn(Value) -> BoxFuture<'static, Result<Value, LLMError>> + Send + Sync + 'static,
+{
+    Arc::new(ClosureKernelFunction {
+        definition: closure_definition(name, description, params),
+        handler: Box::new(f),
+    })
+}
+
+/// Build a kernel function from a synchronous closure that receives the raw JSON arguments.
+pub fn kernel_fn_sync<F>(name: &str, description: &str, params: Vec<FunctionParameter>, f: F) -> DynKernelFunction
+where
+    F: Fn(Value) -> Result<Value, LLMError> + Send + Sync + 'static,
+{
+    kernel_fn(name, description, params, move |arguments| {
+        let result = f(arguments);
+        Box::pin(async move { result })
+    })
+}
+
+/// Exposes an existing function under a different name, for namespaced registries.
+struct RenamedKernelFunction {
+    definition: FunctionDefinition,
+    inner: DynKernelFunction,
+}
+
+#[async_trait]
+impl KernelFunction for RenamedKernelFunction {
+    fn definition(&self) -> FunctionDefinition {
+        self.definition.clone()
+    }
+
+    async fn invoke(&self, arguments: &Value) -> Result<Value, LLMError> {
+        self.inner.invoke(arguments).await
+    }
+
+    fn openapi_operation(&self) -> Option<Value> {
+        let mut operation = self.inner.openapi_operation()?;
+        if let Some(object) = operation.as_object_mut() {
+            object.insert("operationId".to_string(), Value::String(self.definition.name.clone()));
+        }
+        Some(operation)
+    }
+}
+
 #[derive(Default)]
 pub struct FunctionRegistry {
     functions: BTreeMap<String, DynKernelFunction>,
     cached_definitions: std::sync::Mutex<Option<Vec<FunctionDefinition>>>,
     cached_tools: std::sync::Mutex<Option<Vec<Tool>>>,
+    acl: Option<Arc<FunctionAcl>>,
+    analytics: Option<Arc<FunctionAnalyticsStore>>,
 }
 
 impl FunctionRegistry {
@@ -324,9 +415,23 @@ impl FunctionRegistry {
             functions: BTreeMap::new(),
             cached_definitions: std::sync::Mutex::new(None),
             cached_tools: std::sync::Mutex::new(None),
+            acl: None,
+            analytics: None,
         }
     }
 
+    /// Check every invocation against `acl`; see [`FunctionRegistry::invoke_as`].
+    pub fn with_acl(mut self, acl: Arc<FunctionAcl>) -> Self {
+        self.acl = Some(acl);
+        self
+    }
+
+    /// Record the duration and outcome of every invocation in `analytics`.
+    pub fn with_analytics(mut self, analytics: Arc<FunctionAnalyticsStore>) -> Self {
+        self.analytics = Some(analytics);
+        self
+    }
+
     pub fn register(&mut self, function: DynKernelFunction) {
         let name = function.definition().name;
         self.functions.insert(name, function);
@@ -342,13 +447,54 @@ impl FunctionRegistry {
         }
     }
 
+    pub fn remove(&mut self, name: &str) -> Option<DynKernelFunction> {
+        let removed = self.functions.remove(name);
+        self.invalidate_cache();
+        removed
+    }
+
+    /// Copies `other`'s functions, and its ACL and analytics store if this registry has none.
     pub fn extend_from(&mut self, other: &FunctionRegistry) {
         for (name, func) in &other.functions {
             self.functions.insert(name.clone(), func.clone());
         }
+        if self.acl.is_none() {
+            self.acl = other.acl.clone();
+        }
+        if self.analytics.is_none() {
+            self.analytics = other.analytics.clone();
+        }
         self.invalidate_cache();
     }
 
+    /// Copies `other`'s functions under the name `{prefix}__{name}`, so registries with
+    /// overlapping function names can be merged. Providers only accept tool names made of
+    /// ASCII letters, digits, `_` and `-`, so other characters in `prefix` become `_`.
+    pub fn extend_from_prefixed(&mut self, other: &FunctionRegistry, prefix: &str) {
+        let prefix: String = prefix
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        for (name, func) in &other.functions {
+            let prefixed = format!("{prefix}__{name}");
+            let mut definition = func.definition();
+            definition.name = prefixed.clone();
+            let renamed = RenamedKernelFunction {
+                definition,
+                inner: Arc::clone(func),
+            };
+            self.functions.insert(prefixed, Arc::new(renamed));
+        }
+        self.invalidate_cache();
+    }
+
+    /// [`FunctionRegistry::extend_from_prefixed`] for every `(prefix, registry)` entry.
+    pub fn extend_from_namespaced(&mut self, registries: HashMap<String, Arc<FunctionRegistry>>) {
+        for (prefix, registry) in &registries {
+            self.extend_from_prefixed(registry, prefix);
+        }
+    }
+
     fn invalidate_cache(&mut self) {
         *self.cached_definitions.lock().unwrap() = None;
         *self.cached_tools.lock().unwrap() = None;
@@ -358,6 +504,11 @@ impl FunctionRegistry {
         self.functions.get(name)
     }
 
+    /// Registered function names, sorted.
+    pub fn names(&self) -> Vec<String> {
+        self.functions.keys().cloned().collect()
+    }
+
     pub fn definitions(&self) -> Vec<FunctionDefinition> {
         let mut cache = self.cached_definitions.lock().unwrap();
         if let Some(ref defs) = *cache {
@@ -384,11 +535,94 @@ impl FunctionRegistry {
         tools
     }
 
+    /// Build an OpenAPI 3.1 document with one `POST /functions/{name}` operation for every
+    /// registered function that publishes an operation fragment.
+    pub fn openapi_spec(&self, title: &str, version: &str) -> Value {
+        let mut paths = serde_json::Map::new();
+        for (name, function) in &self.functions {
+            if let Some(operation) = function.openapi_operation() {
+                paths.insert(
+                    format!("/functions/{name}"),
+                    serde_json::json!({ "post": operation }),
+                );
+            }
+        }
+
+        serde_json::json!({
+            "openapi": "3.1.0",
+            "info": { "title": title, "version": version },
+            "paths": paths,
+        })
+    }
+
+    /// Invoke without a caller identity. With an ACL set, only rules for the agent pattern `*`
+    /// can allow the call.
     pub async fn invoke(&self, call: &FunctionCall) -> Result<Value, LLMError> {
-        let function = self
-            .get(&call.name)
-            .ok_or_else(|| LLMError::UnknownFunction(call.name.clone()))?;
-        function.invoke(&call.arguments).await
+        self.invoke_as(call, "").await
+    }
+
+    /// Invoke on behalf of `agent_name`, denying the call if the registry's ACL forbids it.
+    pub async fn invoke_as(&self, call: &FunctionCall, agent_name: &str) -> Result<Value, LLMError> {
+        let invocation = async {
+            let result = match self.get(&call.name) {
+                Some(_) if self.acl.as_ref().is_some_and(|acl| !acl.is_allowed(agent_name, &call.name)) => {
+                    Err(LLMError::PermissionDenied(format!(
+                        "agent `{agent_name}` may not call `{}`",
+                        call.name
+                    )))
+                }
+                Some(function) => {
+                    let started = std::time::Instant::now();
+                    let result = function.invoke(&call.arguments).await;
+                    if let Some(analytics) = &self.analytics {
+                        analytics.record(call, started.elapsed(), result.is_ok());
+                    }
+                    result
+                }
+                None => Err(LLMError::UnknownFunction(call.name.clone())),
+            };
+            #[cfg(feature = "tracing")]
+            match &result {
+                Ok(value) => tracing::debug!(result = %value, "tool call finished"),
+                Err(error) => tracing::warn!(%error, "tool call failed"),
+            }
+            result
+        };
+
+        #[cfg(feature = "tracing")]
+        let invocation = tracing::Instrument::instrument(
+            invocation,
+            tracing::info_span!("function.invoke", function.name = %call.name),
+        );
+        invocation.await
+    }
+
+    /// Invoke all `calls` concurrently; `result[i]` always belongs to `calls[i]`, whatever order
+    /// the calls finish in.
+    pub async fn invoke_all_ordered(&self, calls: &[FunctionCall]) -> Vec<Result<Value, LLMError>> {
+        let mut results = futures_util::future::join_all(
+            calls
+                .iter()
+                .enumerate()
+                .map(|(index, call)| async move { (index, self.invoke(call).await) }),
+        )
+        .await;
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Invoke all `calls` concurrently, keying each result by the id it was given (e.g. the
+    /// tool call id from the model). A repeated id keeps the result of its last call.
+    pub async fn invoke_all_with_ids(
+        &self,
+        calls: &[(String, FunctionCall)],
+    ) -> HashMap<String, Result<Value, LLMError>> {
+        let function_calls: Vec<FunctionCall> = calls.iter().map(|(_, call)| call.clone()).collect();
+        calls
+            .iter()
+            .map(|(id, _)| id.clone())
+            .zip(self.invoke_all_ordered(&function_calls).await)
+            .collect()
     }
 }
 
@@ -397,6 +631,32 @@ pub fn json_schema_for<T: JsonSchema>() -> Value {
     serde_json::to_value(schema.schema).expect("schema serialization should not fail")
 }
 
+/// OpenAPI 3.1 operation object for a function taking `definition`'s parameters as a JSON
+/// request body and answering with `return_schema`.
+pub fn openapi_operation(definition: &FunctionDefinition, return_schema: Value) -> Value {
+    let mut operation = serde_json::json!({
+        "operationId": definition.name,
+        "requestBody": {
+            "required": true,
+            "content": {
+                "application/json": { "schema": definition.parameters },
+            },
+        },
+        "responses": {
+            "200": {
+                "description": "Successful response",
+                "content": {
+                    "application/json": { "schema": return_schema },
+                },
+            },
+        },
+    });
+    if let Some(description) = &definition.description {
+        operation["description"] = Value::String(description.clone());
+    }
+    operation
+}
+
 pub fn to_value<T: serde::Serialize>(value: T) -> Value {
     serde_json::to_value(value).expect("value serialization should not fail")
 }
@@ -451,3 +711,156 @@ pub enum ToolChoiceKind {
 pub struct ToolChoiceFunction {
     pub name: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn text_param() -> FunctionParameter {
+        FunctionParameter::new("text", json_schema_for::<String>()).with_description("Input text")
+    }
+
+    fn text_argument(arguments: &Value) -> Result<String, LLMError> {
+        arguments
+            .get("text")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| LLMError::InvalidFunctionArguments("missing text".to_string()))
+    }
+
+    #[tokio::test]
+    async fn closure_functions_register_and_invoke() {
+        let mut registry = FunctionRegistry::new();
+        registry.register(kernel_fn_sync(
+            "strlen",
+            "Length of a string",
+            vec![text_param()],
+            |arguments| Ok(json!(text_argument(&arguments)?.len())),
+        ));
+        registry.register(kernel_fn(
+            "strlen_async",
+            "Length of a string",
+            vec![text_param()],
+            |arguments| Box::pin(async move { Ok(json!(text_argument(&arguments)?.chars().count())) }),
+        ));
+
+        let definition = registry.get("strlen").unwrap().definition();
+        assert_eq!(definition.name, "strlen");
+        assert_eq!(definition.description.as_deref(), Some("Length of a string"));
+        assert_eq!(definition.parameters.required, vec!["text".to_string()]);
+        assert!(definition.parameters.properties.contains_key("text"));
+
+        let result = registry
+            .invoke(&FunctionCall::new("strlen", json!({ "text": "hello" })))
+            .await
+            .unwrap();
+        assert_eq!(result, json!(5));
+        let result = registry
+            .invoke(&FunctionCall::new("strlen_async", json!({ "text": "héllo" })))
+            .await
+            .unwrap();
+        assert_eq!(result, json!(5));
+        assert!(registry
+            .invoke(&FunctionCall::new("strlen", json!({})))
+            .await
+            .is_err());
+    }
+
+    fn delayed_registry(finished: Arc<std::sync::Mutex<Vec<u64>>>) -> FunctionRegistry {
+        let mut registry = FunctionRegistry::new();
+        registry.register(kernel_fn("delayed", "Answer after a delay", Vec::new(), move |arguments| {
+            let finished = Arc::clone(&finished);
+            Box::pin(async move {
+                let ms = arguments["ms"].as_u64().unwrap_or_default();
+                tokio::time::sleep(std::time::Duration::from_millis(ms)).await;
+                finished.lock().unwrap().push(ms);
+                Ok(json!(ms))
+            })
+        }));
+        registry
+    }
+
+    #[tokio::test]
+    async fn invoke_all_ordered_keeps_call_order() {
+        let finished = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let registry = delayed_registry(Arc::clone(&finished));
+        let calls: Vec<_> = [30, 20, 10]
+            .into_iter()
+            .map(|ms| FunctionCall::new("delayed", json!({ "ms": ms })))
+            .collect();
+
+        let results = registry.invoke_all_ordered(&calls).await;
+
+        assert_eq!(*finished.lock().unwrap(), vec![10, 20, 30]);
+        let values: Vec<Value> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(values, vec![json!(30), json!(20), json!(10)]);
+    }
+
+    #[tokio::test]
+    async fn invoke_all_with_ids_keys_results_by_id() {
+        let registry = delayed_registry(Arc::new(std::sync::Mutex::new(Vec::new())));
+        let calls = vec![
+            ("call_a".to_string(), FunctionCall::new("delayed", json!({ "ms": 20 }))),
+            ("call_b".to_string(), FunctionCall::new("delayed", json!({ "ms": 5 }))),
+            ("call_c".to_string(), FunctionCall::new("missing", json!({}))),
+        ];
+
+        let results = registry.invoke_all_with_ids(&calls).await;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results["call_a"].as_ref().unwrap(), &json!(20));
+        assert_eq!(results["call_b"].as_ref().unwrap(), &json!(5));
+        assert!(matches!(results["call_c"], Err(LLMError::UnknownFunction(_))));
+    }
+
+    #[tokio::test]
+    async fn namespaced_extend_dispatches_on_prefixed_name() {
+        let mut math = FunctionRegistry::new();
+        math.register(kernel_fn_sync("add", "Add numbers", Vec::new(), |args| {
+            Ok(json!(args["a"].as_i64().unwrap_or_default() + args["b"].as_i64().unwrap_or_default()))
+        }));
+        let mut physics = FunctionRegistry::new();
+        physics.register(kernel_fn_sync("add", "Add vectors", Vec::new(), |_| Ok(json!("vector"))));
+
+        let mut registry = FunctionRegistry::new();
+        registry.extend_from_namespaced(HashMap::from([
+            ("math".to_string(), Arc::new(math)),
+            ("physics".to_string(), Arc::new(physics)),
+        ]));
+
+        assert_eq!(registry.names(), vec!["math__add".to_string(), "physics__add".to_string()]);
+        let definitions = registry.definitions();
+        assert_eq!(definitions[0].name, "math__add");
+        assert_eq!(definitions[1].description.as_deref(), Some("Add vectors"));
+
+        let sum = registry
+            .invoke(&FunctionCall::new("math__add", json!({ "a": 2, "b": 3 })))
+            .await
+            .unwrap();
+        assert_eq!(sum, json!(5));
+        let vector = registry
+            .invoke(&FunctionCall::new("physics__add", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(vector, json!("vector"));
+        assert!(matches!(
+            registry.invoke(&FunctionCall::new("add", json!({}))).await,
+            Err(LLMError::UnknownFunction(_))
+        ));
+    }
+
+    #[test]
+    fn prefixed_names_are_valid_tool_names() {
+        let mut tools = FunctionRegistry::new();
+        tools.register(kernel_fn_sync("search", "Search", Vec::new(), |_| Ok(json!(null))));
+        let mut registry = FunctionRegistry::new();
+        registry.extend_from_prefixed(&tools, "web.v2 tools");
+
+        let pattern = regex::Regex::new("^[a-zA-Z0-9_-]{1,64}$").unwrap();
+        for tool in registry.tools() {
+            assert!(pattern.is_match(&tool.function.name), "{}", tool.function.name);
+        }
+        assert_eq!(registry.names(), vec!["web_v2_tools__search".to_string()]);
+    }
+}
@@ -0,0 +1,124 @@
+/// Which agents may call which functions. Agent and function patterns may use `*` to match
+/// any run of characters. Rules are checked in declaration order and the first rule matching
+/// both the agent and the function decides; calls no rule matches are denied.
+#[derive(Debug, Clone, Default)]
+pub struct FunctionAcl {
+    rules: Vec<AclRule>,
+}
+
+#[derive(Debug, Clone)]
+struct AclRule {
+    allow: bool,
+    agent_pattern: String,
+    functions: Vec<String>,
+}
+
+impl AclRule {
+    fn matches(&self, agent_name: &str, function_name: &str) -> bool {
+        wildcard_match(&self.agent_pattern, agent_name)
+            && self
+                .functions
+                .iter()
+                .any(|pattern| wildcard_match(pattern, function_name))
+    }
+}
+
+impl FunctionAcl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn allow(self, agent_pattern: &str, functions: &[&str]) -> Self {
+        self.rule(true, agent_pattern, functions)
+    }
+
+    pub fn deny(self, agent_pattern: &str, functions: &[&str]) -> Self {
+        self.rule(false, agent_pattern, functions)
+    }
+
+    fn rule(mut self, allow: bool, agent_pattern: &str, functions: &[&str]) -> Self {
+        self.rules.push(AclRule {
+            allow,
+            agent_pattern: agent_pattern.to_string(),
+            functions: functions.iter().map(|name| name.to_string()).collect(),
+        });
+        self
+    }
+
+    pub fn is_allowed(&self, agent_name: &str, function_name: &str) -> bool {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(agent_name, function_name))
+            .is_some_and(|rule| rule.allow)
+    }
+}
+
+/// Glob match where `*` matches any (possibly empty) run of characters.
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == value;
+    };
+    let Some(mut remaining) = value.strip_prefix(prefix) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = rest.split('*').collect();
+    let suffix = parts.pop().unwrap_or_default();
+    for part in parts {
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.len() >= suffix.len() && remaining.ends_with(suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::functions::{kernel_fn_sync, FunctionCall, FunctionRegistry};
+    use crate::LLMError;
+
+    #[test]
+    fn wildcard_patterns() {
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("analyst*", "analyst_v2"));
+        assert!(wildcard_match("*_agent", "billing_agent"));
+        assert!(wildcard_match("a*b*c", "axxbyyc"));
+        assert!(!wildcard_match("a*b*c", "axxcyyb"));
+        assert!(!wildcard_match("ab*ba", "aba"));
+        assert!(!wildcard_match("search", "search_web"));
+    }
+
+    #[test]
+    fn first_matching_rule_wins_and_unmatched_calls_are_denied() {
+        let acl = FunctionAcl::new()
+            .deny("analyst_intern", &["*"])
+            .allow("analyst*", &["search"])
+            .allow("*", &["ping"]);
+
+        assert!(acl.is_allowed("analyst_v2", "search"));
+        assert!(!acl.is_allowed("billing_agent", "search"));
+        assert!(!acl.is_allowed("analyst_intern", "search"));
+        assert!(!acl.is_allowed("analyst_intern", "ping"));
+        assert!(acl.is_allowed("billing_agent", "ping"));
+        assert!(!acl.is_allowed("analyst_v2", "delete"));
+    }
+
+    #[tokio::test]
+    async fn registry_checks_acl_before_invoking() {
+        let mut registry = FunctionRegistry::new()
+            .with_acl(Arc::new(FunctionAcl::new().allow("analyst*", &["search"])));
+        registry.register(kernel_fn_sync("search", "", Vec::new(), |_| Ok(json!("results"))));
+        let call = FunctionCall::new("search", json!({}));
+
+        assert_eq!(registry.invoke_as(&call, "analyst_v2").await.unwrap(), json!("results"));
+        let denied = registry.invoke_as(&call, "billing_agent").await.unwrap_err();
+        assert!(matches!(denied, LLMError::PermissionDenied(_)));
+        assert!(registry.invoke(&call).await.is_err());
+    }
+}
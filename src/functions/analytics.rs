@@ -0,0 +1,193 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::functions::FunctionCall;
+
+/// Calls kept by [`FunctionAnalyticsStore::new`].
+pub const DEFAULT_MAX_RECORDS: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionCallRecord {
+    pub name: String,
+    pub duration: Duration,
+    pub success: bool,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Aggregate over every recorded call of one function.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionStats {
+    pub name: String,
+    pub call_count: u64,
+    /// Share of failed calls, between 0 and 1.
+    pub failure_rate: f64,
+    pub avg_duration: Duration,
+}
+
+/// Records function calls made through a registry (see
+/// [`crate::FunctionRegistry::with_analytics`]) and ranks functions by usage, failures and latency.
+/// Only the most recent calls are kept, [`DEFAULT_MAX_RECORDS`] unless set with
+/// [`FunctionAnalyticsStore::with_max_records`]; stats cover the calls still kept.
+#[derive(Debug)]
+pub struct FunctionAnalyticsStore {
+    records: Arc<RwLock<VecDeque<FunctionCallRecord>>>,
+    max_records: usize,
+}
+
+impl Default for FunctionAnalyticsStore {
+    fn default() -> Self {
+        Self {
+            records: Arc::default(),
+            max_records: DEFAULT_MAX_RECORDS,
+        }
+    }
+}
+
+impl FunctionAnalyticsStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `max_records` calls, dropping the oldest first.
+    pub fn with_max_records(mut self, max_records: usize) -> Self {
+        self.max_records = max_records.max(1);
+        self
+    }
+
+    pub fn record(&self, call: &FunctionCall, duration: Duration, success: bool) {
+        let mut records = self.records.write().unwrap();
+        if records.len() == self.max_records {
+            records.pop_front();
+        }
+        records.push_back(FunctionCallRecord {
+            name: call.name.clone(),
+            duration,
+            success,
+            timestamp: Utc::now(),
+        });
+    }
+
+    /// Kept calls, oldest first.
+    pub fn records(&self) -> Vec<FunctionCallRecord> {
+        self.records.read().unwrap().iter().cloned().collect()
+    }
+
+    /// Stats for every recorded function, sorted by name.
+    pub fn stats(&self) -> Vec<FunctionStats> {
+        let mut totals: BTreeMap<String, (u64, u64, Duration)> = BTreeMap::new();
+        for record in self.records.read().unwrap().iter() {
+            let (calls, failures, duration) = totals.entry(record.name.clone()).or_default();
+            *calls += 1;
+            *failures += u64::from(!record.success);
+            *duration += record.duration;
+        }
+
+        totals
+            .into_iter()
+            .map(|(name, (calls, failures, duration))| FunctionStats {
+                name,
+                call_count: calls,
+                failure_rate: failures as f64 / calls as f64,
+                avg_duration: duration.div_f64(calls as f64),
+            })
+            .collect()
+    }
+
+    pub fn top_n_by_call_count(&self, n: usize) -> Vec<FunctionStats> {
+        self.top_n(n, |a, b| b.call_count.cmp(&a.call_count))
+    }
+
+    pub fn top_n_by_failure_rate(&self, n: usize) -> Vec<FunctionStats> {
+        self.top_n(n, |a, b| b.failure_rate.total_cmp(&a.failure_rate))
+    }
+
+    pub fn top_n_by_avg_duration(&self, n: usize) -> Vec<FunctionStats> {
+        self.top_n(n, |a, b| b.avg_duration.cmp(&a.avg_duration))
+    }
+
+    /// Ties keep name order, since [`FunctionAnalyticsStore::stats`] is sorted by name.
+    fn top_n(&self, n: usize, order: impl Fn(&FunctionStats, &FunctionStats) -> Ordering) -> Vec<FunctionStats> {
+        let mut stats = self.stats();
+        stats.sort_by(order);
+        stats.truncate(n);
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::functions::{kernel_fn_sync, FunctionRegistry};
+    use crate::LLMError;
+
+    #[test]
+    fn ranks_functions_by_calls_failures_and_duration() {
+        let store = FunctionAnalyticsStore::new();
+        let search = FunctionCall::new("search", json!({}));
+        let add = FunctionCall::new("add", json!({}));
+        for i in 0..100 {
+            store.record(&search, Duration::from_millis(10), i < 80);
+        }
+        for _ in 0..10 {
+            store.record(&add, Duration::from_millis(50), true);
+        }
+
+        let by_failures = store.top_n_by_failure_rate(1);
+        assert_eq!(by_failures.len(), 1);
+        assert_eq!(by_failures[0].name, "search");
+        assert!((by_failures[0].failure_rate - 0.2).abs() < f64::EPSILON);
+
+        let by_calls = store.top_n_by_call_count(2);
+        assert_eq!(by_calls[0].name, "search");
+        assert_eq!(by_calls[0].call_count, 100);
+        assert_eq!(by_calls[1].call_count, 10);
+
+        let by_duration = store.top_n_by_avg_duration(1);
+        assert_eq!(by_duration[0].name, "add");
+        assert_eq!(by_duration[0].avg_duration, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn keeps_only_the_most_recent_calls() {
+        let store = FunctionAnalyticsStore::new().with_max_records(3);
+        for (name, millis) in [("old", 1), ("search", 10), ("search", 20), ("search", 30)] {
+            store.record(&FunctionCall::new(name, json!({})), Duration::from_millis(millis), true);
+        }
+
+        assert_eq!(store.records().len(), 3);
+        let stats = store.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].call_count, stats[0].avg_duration), (3, Duration::from_millis(20)));
+    }
+
+    #[tokio::test]
+    async fn registry_records_invocations() {
+        let store = Arc::new(FunctionAnalyticsStore::new());
+        let mut registry = FunctionRegistry::new().with_analytics(Arc::clone(&store));
+        registry.register(kernel_fn_sync("flaky", "", Vec::new(), |args| {
+            if args["fail"] == json!(true) {
+                Err(LLMError::InvalidFunctionArguments("asked to fail".to_string()))
+            } else {
+                Ok(json!("ok"))
+            }
+        }));
+
+        registry.invoke(&FunctionCall::new("flaky", json!({}))).await.unwrap();
+        registry
+            .invoke(&FunctionCall::new("flaky", json!({ "fail": true })))
+            .await
+            .unwrap_err();
+
+        let stats = store.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].call_count, 2);
+        assert!((stats[0].failure_rate - 0.5).abs() < f64::EPSILON);
+    }
+}
@@ -70,6 +70,20 @@ impl ParamSpec {
         }
         schema
     }
+
+    pub(crate) fn to_parameter(&self, name: &str) -> FunctionParameter {
+        let mut fp = FunctionParameter::new(name, self.to_schema());
+        if let Some(desc) = &self.description {
+            fp = fp.with_description(desc.clone());
+        }
+        if !self.required.unwrap_or(true) {
+            fp = fp.optional();
+        }
+        if let Some(default) = &self.default {
+            fp = fp.with_default(default.clone());
+        }
+        fp
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -158,17 +172,7 @@ fn build_definition(name: String, spec: &HttpToolSpec) -> FunctionDefinition {
     }
 
     for (param, meta) in spec.query.iter().chain(spec.body.iter()) {
-        let mut fp = FunctionParameter::new(param, meta.to_schema());
-        if let Some(desc) = &meta.description {
-            fp = fp.with_description(desc.clone());
-        }
-        if !meta.required.unwrap_or(true) {
-            fp = fp.optional();
-        }
-        if let Some(default) = &meta.default {
-            fp = fp.with_default(default.clone());
-        }
-        def.add_parameter(fp);
+        def.add_parameter(meta.to_parameter(param));
     }
 
     def
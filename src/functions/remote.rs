@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::task::JoinHandle;
+
+use crate::bench::{StubKernelFunction, ToolSpec};
+use crate::error::LLMError;
+use crate::functions::{FunctionDefinition, FunctionRegistry, KernelFunction};
+
+/// Forwards every invocation to `handler_url` as a JSON `POST` of the call arguments and
+/// returns the response body.
+#[derive(Clone)]
+pub struct HttpProxyKernelFunction {
+    definition: FunctionDefinition,
+    handler_url: String,
+    client: reqwest::Client,
+}
+
+impl HttpProxyKernelFunction {
+    pub fn new(name: impl Into<String>, handler_url: impl Into<String>) -> Self {
+        Self {
+            definition: FunctionDefinition::new(name),
+            handler_url: handler_url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_definition(mut self, definition: FunctionDefinition) -> Self {
+        self.definition = definition;
+        self
+    }
+}
+
+#[async_trait]
+impl KernelFunction for HttpProxyKernelFunction {
+    fn definition(&self) -> FunctionDefinition {
+        self.definition.clone()
+    }
+
+    async fn invoke(&self, arguments: &Value) -> Result<Value, LLMError> {
+        let response = self.client.post(&self.handler_url).json(arguments).send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            return Err(LLMError::FunctionExecution {
+                function: self.definition.name.clone(),
+                message: format!("handler returned {status}: {text}"),
+            });
+        }
+        Ok(serde_json::from_str(&text).unwrap_or(Value::String(text)))
+    }
+}
+
+/// GET a JSON array of [`ToolSpec`]s from a tool registry endpoint.
+async fn fetch_tool_specs(
+    client: &reqwest::Client,
+    url: &str,
+    api_key: Option<&str>,
+) -> Result<Vec<ToolSpec>, LLMError> {
+    let mut request = client.get(url);
+    if let Some(api_key) = api_key {
+        request = request.bearer_auth(api_key);
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(LLMError::Provider(format!("tool registry returned {status}")));
+    }
+    Ok(serde_json::from_str(&response.text().await?)?)
+}
+
+impl FunctionRegistry {
+    /// Register a stub function for every tool listed at `url`, in the same format as
+    /// `BenchCase::tools`. Returns the definitions that were registered.
+    pub async fn load_from_url(
+        &mut self,
+        url: &str,
+        api_key: Option<&str>,
+    ) -> Result<Vec<FunctionDefinition>, LLMError> {
+        let specs = fetch_tool_specs(&reqwest::Client::new(), url, api_key).await?;
+        let mut definitions = Vec::with_capacity(specs.len());
+        for spec in specs {
+            let function = Arc::new(StubKernelFunction::new(spec));
+            definitions.push(function.definition());
+            self.register(function);
+        }
+        Ok(definitions)
+    }
+
+    /// Refresh `registry` from `url` every `interval` in the background. Only tools whose spec
+    /// changed since the last refresh are re-registered; tools that disappear from the remote
+    /// are removed. Failed refreshes keep the current functions.
+    pub fn sync_with_remote(
+        registry: Arc<RwLock<FunctionRegistry>>,
+        url: impl Into<String>,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let url = url.into();
+        tokio::spawn(async move {
+            let client = reqwest::Client::new();
+            let mut known: HashMap<String, Value> = HashMap::new();
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let Ok(specs) = fetch_tool_specs(&client, &url, None).await else {
+                    continue;
+                };
+
+                let mut latest = HashMap::with_capacity(specs.len());
+                let mut registry = registry.write().unwrap();
+                for spec in specs {
+                    let raw = serde_json::to_value(&spec).unwrap_or(Value::Null);
+                    if known.get(&spec.name) != Some(&raw) {
+                        registry.register(Arc::new(StubKernelFunction::new(spec.clone())));
+                    }
+                    latest.insert(spec.name, raw);
+                }
+                for name in known.keys().filter(|name| !latest.contains_key(*name)) {
+                    registry.remove(name);
+                }
+                known = latest;
+            }
+        })
+    }
+
+    /// Register `name` as a function that forwards invocations to `handler_url`.
+    pub fn with_remote_stub(mut self, name: &str, handler_url: &str) -> Self {
+        self.register(Arc::new(HttpProxyKernelFunction::new(name, handler_url)));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::functions::FunctionCall;
+
+    fn tools(names: &[(&str, &str)]) -> Value {
+        Value::Array(
+            names
+                .iter()
+                .map(|(name, answer)| {
+                    json!({
+                        "name": name,
+                        "description": format!("{name} tool"),
+                        "default": { "kind": "ok", "value": answer },
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn loads_stub_functions_from_registry_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tools"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(tools(&[("lookup", "found"), ("ping", "pong")])))
+            .mount(&server)
+            .await;
+
+        let mut registry = FunctionRegistry::new();
+        let definitions = registry
+            .load_from_url(&format!("{}/tools", server.uri()), Some("secret"))
+            .await
+            .unwrap();
+
+        assert_eq!(definitions.len(), 2);
+        assert_eq!(definitions[0].description.as_deref(), Some("lookup tool"));
+        let result = registry.invoke(&FunctionCall::new("ping", json!({}))).await.unwrap();
+        assert_eq!(result, json!("pong"));
+    }
+
+    #[tokio::test]
+    async fn sync_picks_up_changed_and_removed_tools() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(tools(&[("lookup", "v1"), ("old", "x")])))
+            .mount(&server)
+            .await;
+
+        let registry = Arc::new(RwLock::new(FunctionRegistry::new()));
+        let handle = FunctionRegistry::sync_with_remote(
+            Arc::clone(&registry),
+            server.uri(),
+            Duration::from_millis(10),
+        );
+
+        let wait_for = |name: &'static str, present: bool| {
+            let registry = Arc::clone(&registry);
+            async move {
+                for _ in 0..200 {
+                    if registry.read().unwrap().get(name).is_some() == present {
+                        return;
+                    }
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                }
+                panic!("{name} never became present={present}");
+            }
+        };
+        wait_for("old", true).await;
+
+        server.reset().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(tools(&[("lookup", "v2"), ("new", "y")])))
+            .mount(&server)
+            .await;
+        wait_for("new", true).await;
+        wait_for("old", false).await;
+        handle.abort();
+
+        let lookup = registry.read().unwrap().get("lookup").cloned().unwrap();
+        assert_eq!(lookup.invoke(&json!({})).await.unwrap(), json!("v2"));
+    }
+
+    #[tokio::test]
+    async fn remote_stub_posts_arguments_to_handler() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/handle"))
+            .and(body_json(json!({ "city": "Berlin" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "temp": 21 })))
+            .mount(&server)
+            .await;
+
+        let registry = FunctionRegistry::new().with_remote_stub("weather", &format!("{}/handle", server.uri()));
+        let result = registry
+            .invoke(&FunctionCall::new("weather", json!({ "city": "Berlin" })))
+            .await
+            .unwrap();
+        assert_eq!(result, json!({ "temp": 21 }));
+    }
+}
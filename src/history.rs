@@ -1,24 +1,103 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::providers::EmbeddingProvider;
 use crate::types::{ChatMessage, CompletionRequest, MessageRole};
 use crate::{LLMError, LLMProvider};
 
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "tiktoken")]
+pub mod tiktoken;
+
 #[derive(Debug, Clone, Default)]
 pub struct ChatHistory {
     messages: Vec<ChatMessage>,
+    retriever: Option<Arc<SemanticRetriever>>,
+    ttl: Option<Duration>,
+    auto_purge: bool,
 }
 
 impl ChatHistory {
     pub fn new() -> Self {
-        Self { messages: Vec::new() }
+        Self::default()
     }
 
     pub fn with_messages(messages: Vec<ChatMessage>) -> Self {
-        Self { messages }
+        Self {
+            messages,
+            ..Self::default()
+        }
+    }
+
+    /// Expire messages older than `retention`. Messages pushed from now on are stamped with
+    /// [`ChatMessage::created_at`] if they do not carry a timestamp already; messages without
+    /// a timestamp never expire.
+    pub fn with_ttl(mut self, retention: Duration) -> Self {
+        self.ttl = Some(retention);
+        self
+    }
+
+    /// Run [`ChatHistory::purge_expired`] after every [`ChatHistory::push`].
+    pub fn with_auto_purge(mut self, auto_purge: bool) -> Self {
+        self.auto_purge = auto_purge;
+        self
+    }
+
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Remove messages older than the configured retention. Returns the number removed.
+    pub fn purge_expired(&mut self) -> usize {
+        self.purge_expired_at(Utc::now())
+    }
+
+    /// Like [`ChatHistory::purge_expired`] but measured against `now` instead of the wall clock.
+    pub fn purge_expired_at(&mut self, now: DateTime<Utc>) -> usize {
+        let Some(ttl) = self.ttl else {
+            return 0;
+        };
+        let Ok(retention) = chrono::Duration::from_std(ttl) else {
+            return 0;
+        };
+        let cutoff = now - retention;
+
+        let before = self.messages.len();
+        self.messages
+            .retain(|message| !matches!(message.created_at, Some(created_at) if created_at < cutoff));
+        before - self.messages.len()
+    }
+
+    /// Index every message pushed into this history with `retriever`, including the
+    /// messages already present. The retriever keeps its copies even when the history is
+    /// compressed, so older turns remain retrievable.
+    pub fn with_semantic_retriever(mut self, retriever: Arc<SemanticRetriever>) -> Self {
+        for message in &self.messages {
+            retriever.index(message.clone());
+        }
+        self.retriever = Some(retriever);
+        self
+    }
+
+    pub fn semantic_retriever(&self) -> Option<&Arc<SemanticRetriever>> {
+        self.retriever.as_ref()
     }
 
-    pub fn push(&mut self, message: ChatMessage) {
+    pub fn push(&mut self, mut message: ChatMessage) {
+        if self.ttl.is_some() && message.created_at.is_none() {
+            message.created_at = Some(Utc::now());
+        }
+        if let Some(retriever) = &self.retriever {
+            retriever.index(message.clone());
+        }
         self.messages.push(message);
+        if self.auto_purge {
+            self.purge_expired();
+        }
     }
 
     pub fn push_user(&mut self, content: impl Into<String>) {
@@ -77,11 +156,310 @@ impl ChatHistory {
         compressor.compress(self)
     }
 
+    /// Capture the first `turn` messages. Indices past the end capture the whole history.
+    pub fn snapshot_at(&self, turn: usize) -> ChatHistorySnapshot {
+        let turn = turn.min(self.messages.len());
+        ChatHistorySnapshot {
+            messages: self.messages[..turn].to_vec(),
+        }
+    }
+
+    /// Capture the entire history.
+    pub fn snapshot(&self) -> ChatHistorySnapshot {
+        self.snapshot_at(self.messages.len())
+    }
+
+    /// Build a new, independent history from `snapshot`.
+    pub fn restore(snapshot: ChatHistorySnapshot) -> ChatHistory {
+        ChatHistory::with_messages(snapshot.messages)
+    }
+
+    /// Branch off an independent copy of the current state. The fork does not share the
+    /// semantic retriever, so messages pushed to either branch stay local to it.
+    pub fn fork(&self) -> ChatHistory {
+        ChatHistory::restore(self.snapshot())
+    }
+
+    /// Parse an OpenAI chat completions `messages` array. `content` may be a string, `null`,
+    /// or an array of `text` / `image_url` parts.
+    pub fn from_openai_json(json: &str) -> Result<Self, serde_json::Error> {
+        let raw: Vec<OpenAIMessage> = serde_json::from_str(json)?;
+        Ok(ChatHistory::with_messages(
+            raw.into_iter().map(OpenAIMessage::into_chat_message).collect(),
+        ))
+    }
+
+    /// Serialize the history as an OpenAI chat completions `messages` array.
+    pub fn to_openai_json(&self) -> String {
+        let raw: Vec<OpenAIMessage> = self.messages.iter().map(OpenAIMessage::from).collect();
+        serde_json::to_string(&raw).expect("chat messages serialize to JSON")
+    }
+
     pub fn append(&mut self, other: &mut ChatHistory) {
+        if let Some(retriever) = &self.retriever {
+            for message in &other.messages {
+                retriever.index(message.clone());
+            }
+        }
         self.messages.append(&mut other.messages);
     }
 }
 
+/// Wire shape of a message in the OpenAI chat completions format.
+#[derive(Serialize, Deserialize)]
+struct OpenAIMessage {
+    role: MessageRole,
+    #[serde(default)]
+    content: Option<OpenAIContent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "crate::providers::deserialize_null_as_empty_vec"
+    )]
+    tool_calls: Vec<crate::functions::ToolCall>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum OpenAIContent {
+    Text(String),
+    Parts(Vec<OpenAIContentPart>),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OpenAIContentPart {
+    Text { text: String },
+    ImageUrl { image_url: OpenAIImageUrl },
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpenAIImageUrl {
+    url: String,
+}
+
+impl OpenAIMessage {
+    fn into_chat_message(self) -> ChatMessage {
+        let mut images = Vec::new();
+        let content = match self.content {
+            None => None,
+            Some(OpenAIContent::Text(text)) => Some(text),
+            Some(OpenAIContent::Parts(parts)) => {
+                let mut texts = Vec::new();
+                for part in parts {
+                    match part {
+                        OpenAIContentPart::Text { text } => texts.push(text),
+                        OpenAIContentPart::ImageUrl { image_url } => images.push(image_url.url),
+                    }
+                }
+                (!texts.is_empty()).then(|| texts.join("\n"))
+            }
+        };
+
+        let mut message = match (self.role, self.tool_call_id) {
+            (MessageRole::Tool, Some(id)) => ChatMessage::tool(id, content.unwrap_or_default()),
+            (role, tool_call_id) => {
+                let mut message = ChatMessage::new(role, String::new());
+                message.content = content;
+                message.tool_call_id = tool_call_id;
+                message
+            }
+        };
+        message.name = self.name;
+        message.tool_calls = self.tool_calls;
+        message.images = images;
+        message
+    }
+}
+
+impl From<&ChatMessage> for OpenAIMessage {
+    fn from(message: &ChatMessage) -> Self {
+        let content = if message.images.is_empty() {
+            message.content.clone().map(OpenAIContent::Text)
+        } else {
+            let mut parts: Vec<OpenAIContentPart> = message
+                .content
+                .iter()
+                .map(|text| OpenAIContentPart::Text { text: text.clone() })
+                .collect();
+            parts.extend(message.images.iter().map(|url| OpenAIContentPart::ImageUrl {
+                image_url: OpenAIImageUrl { url: url.clone() },
+            }));
+            Some(OpenAIContent::Parts(parts))
+        };
+
+        Self {
+            role: message.role.clone(),
+            content,
+            name: message.name.clone(),
+            tool_call_id: message.tool_call_id.clone(),
+            tool_calls: message.tool_calls.clone(),
+        }
+    }
+}
+
+/// Serializable checkpoint of a [`ChatHistory`], used to replay a conversation from a
+/// given turn (e.g. for evaluation or A/B comparisons of agent responses).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatHistorySnapshot {
+    messages: Vec<ChatMessage>,
+}
+
+impl ChatHistorySnapshot {
+    pub fn from_messages(messages: Vec<ChatMessage>) -> Self {
+        Self { messages }
+    }
+
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// Number of messages captured, i.e. the turn the snapshot was taken at.
+    pub fn turn(&self) -> usize {
+        self.messages.len()
+    }
+}
+
+/// Embedding-backed store that returns the stored messages most similar to a query.
+///
+/// Messages are queued by [`SemanticRetriever::index`] and embedded in a single batch on
+/// the next [`SemanticRetriever::retrieve`] (or explicit [`SemanticRetriever::flush`]).
+/// Orchestrators can use it to pick relevant prior turns before building a request:
+/// `retriever.retrieve(user_message, k).await?`.
+pub struct SemanticRetriever {
+    embedder: Arc<dyn EmbeddingProvider>,
+    pending: Mutex<Vec<ChatMessage>>,
+    entries: Mutex<Vec<(ChatMessage, Vec<f32>)>>,
+}
+
+impl SemanticRetriever {
+    pub fn new(embedder: Arc<dyn EmbeddingProvider>) -> Self {
+        Self {
+            embedder,
+            pending: Mutex::new(Vec::new()),
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Queue a message for embedding. Messages without text content are ignored.
+    pub fn index(&self, message: ChatMessage) {
+        if message.text().is_some_and(|text| !text.trim().is_empty()) {
+            self.pending.lock().unwrap().push(message);
+        }
+    }
+
+    /// Embed all queued messages.
+    pub async fn flush(&self) -> Result<(), LLMError> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let inputs = pending
+            .iter()
+            .map(|message| message.text().unwrap_or_default().to_string())
+            .collect();
+
+        let vectors = match self.embedder.embed(inputs).await {
+            Ok(vectors) => vectors,
+            Err(err) => {
+                // Put the batch back so a later flush can retry it.
+                let mut queue = self.pending.lock().unwrap();
+                let newer = std::mem::replace(&mut *queue, pending);
+                queue.extend(newer);
+                return Err(err);
+            }
+        };
+
+        self.entries
+            .lock()
+            .unwrap()
+            .extend(pending.into_iter().zip(vectors));
+        Ok(())
+    }
+
+    /// Return up to `k` stored messages ordered by cosine similarity to `query`.
+    pub async fn retrieve(&self, query: &str, k: usize) -> Result<Vec<ChatMessage>, LLMError> {
+        Ok(self
+            .retrieve_scored(query, k)
+            .await?
+            .into_iter()
+            .map(|(message, _)| message)
+            .collect())
+    }
+
+    /// Like [`SemanticRetriever::retrieve`] but also returns the similarity score.
+    pub async fn retrieve_scored(
+        &self,
+        query: &str,
+        k: usize,
+    ) -> Result<Vec<(ChatMessage, f32)>, LLMError> {
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+
+        self.flush().await?;
+
+        let query_vector = self
+            .embedder
+            .embed(vec![query.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(LLMError::InvalidResponse("embedding provider returned no vector"))?;
+
+        let entries = self.entries.lock().unwrap();
+        let mut scored: Vec<(ChatMessage, f32)> = entries
+            .iter()
+            .map(|(message, vector)| (message.clone(), cosine_similarity(&query_vector, vector)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(k);
+        Ok(scored)
+    }
+
+    /// Number of stored messages, including ones not embedded yet.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len() + self.pending.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.pending.lock().unwrap().clear();
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+impl std::fmt::Debug for SemanticRetriever {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SemanticRetriever")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a.sqrt() * norm_b.sqrt())
+    }
+}
+
 pub trait ChatHistoryCompressor {
     fn compress(&mut self, history: &mut ChatHistory) -> bool;
 }
@@ -207,6 +585,97 @@ where
     }
 }
 
+/// Counts the tokens a set of messages would occupy in a prompt.
+pub trait TokenCounter: Send + Sync {
+    fn count(&self, messages: &[ChatMessage]) -> u32;
+
+    /// [`TokenCounter::count`] plus the 4 tokens OpenAI's chat format spends on each
+    /// message's role and delimiters.
+    fn count_messages(&self, messages: &[ChatMessage]) -> u32 {
+        self.count(messages) + MESSAGE_OVERHEAD_TOKENS * messages.len() as u32
+    }
+}
+
+const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+
+/// Cheap token estimate of one token per four characters of message text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CharEstimateTokenCounter;
+
+impl TokenCounter for CharEstimateTokenCounter {
+    fn count(&self, messages: &[ChatMessage]) -> u32 {
+        messages
+            .iter()
+            .filter_map(|message| message.text())
+            .map(|text| text.chars().count().div_ceil(4) as u32)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CompressionEvent {
+    MessageEvicted {
+        role: MessageRole,
+        content_preview: String,
+    },
+}
+
+type CompressionEventCallback = Arc<dyn Fn(&CompressionEvent) + Send + Sync>;
+
+/// Compressor that evicts the oldest non-system messages until the history fits a token budget.
+pub struct TokenBudgetCompressor {
+    max_tokens: u32,
+    token_counter: Arc<dyn TokenCounter>,
+    event_callback: Option<CompressionEventCallback>,
+}
+
+impl TokenBudgetCompressor {
+    pub fn new(max_tokens: u32, token_counter: Arc<dyn TokenCounter>) -> Self {
+        Self {
+            max_tokens,
+            token_counter,
+            event_callback: None,
+        }
+    }
+
+    pub fn with_event_callback(mut self, callback: impl Fn(&CompressionEvent) + Send + Sync + 'static) -> Self {
+        self.event_callback = Some(Arc::new(callback));
+        self
+    }
+
+    fn emit(&self, event: &CompressionEvent) {
+        if let Some(callback) = &self.event_callback {
+            callback(event);
+        }
+    }
+}
+
+impl ChatHistoryCompressor for TokenBudgetCompressor {
+    fn compress(&mut self, history: &mut ChatHistory) -> bool {
+        let mut changed = false;
+
+        while self.token_counter.count(&history.messages) > self.max_tokens {
+            let Some(position) = history
+                .messages
+                .iter()
+                .position(|message| message.role != MessageRole::System)
+            else {
+                break;
+            };
+
+            let evicted = history.messages.remove(position);
+            changed = true;
+            let content_preview = evicted.text().unwrap_or_default().chars().take(80).collect();
+            self.emit(&CompressionEvent::MessageEvicted {
+                role: evicted.role,
+                content_preview,
+            });
+        }
+
+        changed
+    }
+}
+
 pub struct LLMHistoryCompressor {
     provider: Arc<dyn LLMProvider>,
     model: String,
@@ -261,29 +730,7 @@ impl LLMHistoryCompressor {
     }
 
     fn build_summary_prompt(&self, messages: &[ChatMessage]) -> Vec<ChatMessage> {
-        let mut buffer = String::new();
-
-        for message in messages {
-            if let Some(text) = message.text() {
-                let role = match message.role {
-                    MessageRole::System => "System".to_string(),
-                    MessageRole::User => "User".to_string(),
-                    MessageRole::Assistant => message
-                        .name
-                        .as_deref()
-                        .map(|name| format!("Assistant::{name}"))
-                        .unwrap_or_else(|| "Assistant".to_string()),
-                    MessageRole::Tool => "Tool".to_string(),
-                };
-
-                buffer.push_str(&format!("[{role}] {text}\n"));
-            }
-        }
-
-        vec![
-            ChatMessage::system(self.summarizer_instructions.clone()),
-            ChatMessage::user(format!("Conversation so far:\n{}", buffer.trim())),
-        ]
+        build_summary_prompt(&self.summarizer_instructions, messages)
     }
 
     pub async fn compress(&self, history: &mut ChatHistory) -> Result<bool, LLMError> {
@@ -324,10 +771,302 @@ impl LLMHistoryCompressor {
     }
 }
 
+fn build_summary_prompt(instructions: &str, messages: &[ChatMessage]) -> Vec<ChatMessage> {
+    let mut buffer = String::new();
+
+    for message in messages {
+        if let Some(text) = message.text() {
+            let role = match message.role {
+                MessageRole::System => "System".to_string(),
+                MessageRole::User => "User".to_string(),
+                MessageRole::Assistant => message
+                    .name
+                    .as_deref()
+                    .map(|name| format!("Assistant::{name}"))
+                    .unwrap_or_else(|| "Assistant".to_string()),
+                MessageRole::Tool => "Tool".to_string(),
+            };
+
+            buffer.push_str(&format!("[{role}] {text}\n"));
+        }
+    }
+
+    vec![
+        ChatMessage::system(instructions.to_string()),
+        ChatMessage::user(format!("Conversation so far:\n{}", buffer.trim())),
+    ]
+}
+
+/// LLM-backed compressor that summarizes old turns in fixed-size segments and then, level by
+/// level, folds those summaries into higher-level summaries.
+///
+/// At every level the most recent summary is kept verbatim and only the earlier ones are
+/// condensed further, so the compressed history reads from coarse to fine:
+/// `Summary level N`, ..., `Summary level 1`, followed by the retained recent messages.
+pub struct HierarchicalSummarizer {
+    provider: Arc<dyn LLMProvider>,
+    model: String,
+    summarizer_instructions: String,
+    max_messages: usize,
+    retain_messages: usize,
+    segment_size: usize,
+    max_levels: usize,
+}
+
+impl HierarchicalSummarizer {
+    pub fn new(provider: Arc<dyn LLMProvider>, model: impl Into<String>) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            summarizer_instructions: "Summarize the following conversation succinctly while preserving key facts.".into(),
+            max_messages: 20,
+            retain_messages: 6,
+            segment_size: 10,
+            max_levels: 2,
+        }
+    }
+
+    /// Number of messages the history may hold before it is compressed.
+    pub fn with_max_messages(mut self, max_messages: usize) -> Self {
+        self.max_messages = max_messages.max(2);
+        self
+    }
+
+    pub fn with_retain_messages(mut self, retain_messages: usize) -> Self {
+        self.retain_messages = retain_messages.max(1);
+        self
+    }
+
+    /// Number of items (messages or lower-level summaries) folded into one summary.
+    pub fn with_segment_size(mut self, segment_size: usize) -> Self {
+        self.segment_size = segment_size.max(2);
+        self
+    }
+
+    /// Highest summary level to produce. `1` yields a flat list of segment summaries.
+    pub fn with_max_levels(mut self, max_levels: usize) -> Self {
+        self.max_levels = max_levels.max(1);
+        self
+    }
+
+    pub fn with_instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.summarizer_instructions = instructions.into();
+        self
+    }
+
+    async fn summarize_segment(&self, messages: &[ChatMessage]) -> Result<Option<String>, LLMError> {
+        let prompt = build_summary_prompt(&self.summarizer_instructions, messages);
+        let request = CompletionRequest::new(self.model.clone(), prompt);
+        let response = self.provider.complete(request).await?;
+        Ok(response
+            .message
+            .text()
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty()))
+    }
+
+    async fn summarize_level(
+        &self,
+        items: &[ChatMessage],
+        level: usize,
+    ) -> Result<Vec<ChatMessage>, LLMError> {
+        let mut summaries = Vec::new();
+        for segment in items.chunks(self.segment_size) {
+            if let Some(text) = self.summarize_segment(segment).await? {
+                let mut summary = ChatMessage::system(format!("Summary level {level}: {text}"));
+                summary.name = Some("history-summary".to_string());
+                summaries.push(summary);
+            }
+        }
+        Ok(summaries)
+    }
+
+    /// Compress `history` using the configured provider. Returns `Ok(true)` if it changed.
+    pub async fn compress_async(&self, history: &mut ChatHistory) -> Result<bool, LLMError> {
+        if history.len() <= self.max_messages {
+            return Ok(false);
+        }
+
+        let retain = self
+            .retain_messages
+            .min(self.max_messages.saturating_sub(1))
+            .min(history.len());
+        let boundary = history.len().saturating_sub(retain);
+        if boundary == 0 {
+            return Ok(false);
+        }
+
+        let mut current = self.summarize_level(&history.messages[..boundary], 1).await?;
+        if current.is_empty() {
+            return Ok(false);
+        }
+
+        // Finer-grained summaries kept verbatim, most recent level first.
+        let mut kept: Vec<ChatMessage> = Vec::new();
+        let mut level = 1;
+        while current.len() > 1 && level < self.max_levels {
+            let latest = current.pop().expect("non-empty level");
+            kept.push(latest);
+
+            level += 1;
+            let next = self.summarize_level(&current, level).await?;
+            if next.is_empty() {
+                current.push(kept.pop().expect("just pushed"));
+                break;
+            }
+            current = next;
+        }
+
+        let mut compressed = current;
+        compressed.extend(kept.into_iter().rev());
+
+        history.messages.drain(..boundary);
+        history.messages.splice(0..0, compressed);
+        Ok(true)
+    }
+}
+
+impl ChatHistoryCompressor for HierarchicalSummarizer {
+    fn compress(&mut self, history: &mut ChatHistory) -> bool {
+        block_on_compression(self.compress_async(history))
+    }
+}
+
+/// Drive an async compression from the synchronous [`ChatHistoryCompressor`] interface.
+///
+/// Blocks in place on a multi-thread Tokio runtime. A current-thread runtime cannot be
+/// blocked in place, so there, as when no runtime is running, the compression runs to
+/// completion on a helper thread with its own runtime. Failures are logged and reported as
+/// "not compressed"; call `compress_async` to handle them.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables, clippy::manual_unwrap_or_default))]
+fn block_on_compression<F>(future: F) -> bool
+where
+    F: std::future::Future<Output = Result<bool, LLMError>> + Send,
+{
+    use tokio::runtime::{Builder, Handle, RuntimeFlavor};
+
+    let run_on_new_runtime = |future: F| {
+        Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| LLMError::Provider(format!("failed to start compression runtime: {err}")))
+            .and_then(|runtime| runtime.block_on(future))
+    };
+
+    let result = match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| handle.block_on(future))
+        }
+        Ok(_) => std::thread::scope(|scope| {
+            scope
+                .spawn(|| run_on_new_runtime(future))
+                .join()
+                .unwrap_or_else(|_| Err(LLMError::Provider("history compression panicked".to_string())))
+        }),
+        Err(_) => run_on_new_runtime(future),
+    };
+
+    match result {
+        Ok(compressed) => compressed,
+        Err(err) => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(%err, "history compression failed; history left unchanged");
+            false
+        }
+    }
+}
+
+/// Compressor that keeps the most recent turns plus the older turns most relevant to the
+/// latest user message, measured by embedding cosine similarity.
+///
+/// Half of `max_messages` goes to recency and half to relevance. Selected messages keep their
+/// original order, so the compressed history still reads as a conversation.
+pub struct RelevanceWindowCompressor {
+    embedder: Arc<dyn EmbeddingProvider>,
+    max_messages: usize,
+}
+
+impl RelevanceWindowCompressor {
+    pub fn new(embedder: Arc<dyn EmbeddingProvider>, max_messages: usize) -> Self {
+        Self {
+            embedder,
+            max_messages: max_messages.max(2),
+        }
+    }
+
+    /// Compress `history` using the configured embedder. Returns `Ok(true)` if it changed.
+    pub async fn compress_async(&self, history: &mut ChatHistory) -> Result<bool, LLMError> {
+        if history.len() <= self.max_messages {
+            return Ok(false);
+        }
+
+        let query = match history
+            .messages
+            .iter()
+            .rev()
+            .find(|message| message.role == MessageRole::User)
+            .and_then(|message| message.text())
+        {
+            Some(text) if !text.trim().is_empty() => text.to_string(),
+            _ => return Ok(false),
+        };
+
+        let relevant_slots = self.max_messages / 2;
+        let recent_start = history.len() - (self.max_messages - relevant_slots);
+
+        let candidates: Vec<(usize, String)> = history.messages[..recent_start]
+            .iter()
+            .enumerate()
+            .filter_map(|(index, message)| message.text().map(|text| (index, text.to_string())))
+            .filter(|(_, text)| !text.trim().is_empty())
+            .collect();
+
+        let mut inputs: Vec<String> = candidates.iter().map(|(_, text)| text.clone()).collect();
+        inputs.push(query);
+        let mut vectors = self.embedder.embed(inputs).await?;
+        let query_vector = vectors
+            .pop()
+            .ok_or(LLMError::InvalidResponse("embedding provider returned no vector"))?;
+        if vectors.len() != candidates.len() {
+            return Err(LLMError::InvalidResponse("embedding count did not match input count"));
+        }
+
+        let mut scored: Vec<(usize, f32)> = candidates
+            .iter()
+            .zip(&vectors)
+            .map(|((index, _), vector)| (*index, cosine_similarity(&query_vector, vector)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut keep: Vec<usize> = scored
+            .into_iter()
+            .take(relevant_slots)
+            .map(|(index, _)| index)
+            .collect();
+        keep.extend(recent_start..history.len());
+        keep.sort_unstable();
+        keep.dedup();
+
+        let mut index = 0;
+        history.messages.retain(|_| {
+            let retained = keep.binary_search(&index).is_ok();
+            index += 1;
+            retained
+        });
+        Ok(true)
+    }
+}
+
+impl ChatHistoryCompressor for RelevanceWindowCompressor {
+    fn compress(&mut self, history: &mut ChatHistory) -> bool {
+        block_on_compression(self.compress_async(history))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::providers::LLMProvider;
+    use crate::providers::{EmbeddingProvider, LLMProvider};
     use crate::LLMError;
     use async_trait::async_trait;
     use crate::types::MessageRole;
@@ -371,6 +1110,102 @@ mod tests {
         assert_eq!(history.len(), 2);
     }
 
+    #[test]
+    fn restored_snapshot_diverges_from_original() {
+        let mut original = ChatHistory::new().with_ttl(Duration::from_secs(60));
+        for index in 0..5 {
+            original.push_user(format!("Message {index}"));
+        }
+
+        let snapshot = original.snapshot_at(3);
+        let encoded = serde_json::to_string(&snapshot).expect("serialize");
+        let decoded: ChatHistorySnapshot = serde_json::from_str(&encoded).expect("deserialize");
+        assert_eq!(decoded.turn(), 3);
+        assert_eq!(decoded.messages()[0].created_at, original.messages()[0].created_at);
+        assert!(decoded.messages()[0].created_at.is_some());
+
+        let mut branch = ChatHistory::restore(decoded);
+        branch.push_assistant("Alternative reply");
+        branch.push_user("Follow-up");
+
+        assert_eq!(original.len(), 5);
+        assert_eq!(branch.len(), 5);
+        assert_eq!(branch.messages()[2].text(), Some("Message 2"));
+        assert_eq!(original.messages()[3].text(), Some("Message 3"));
+        assert_eq!(branch.messages()[3].text(), Some("Alternative reply"));
+
+        let mut fork = original.fork();
+        fork.push_user("Only in fork");
+        assert_eq!(fork.len(), 6);
+        assert_eq!(original.len(), 5);
+    }
+
+    #[test]
+    fn openai_json_round_trips() {
+        let json = r#"[{"role":"system","content":"You are a travel agent."},{"role":"user","content":"Weather in Paris?"},{"role":"assistant","content":null,"tool_calls":[{"id":"call_1","type":"function","function":{"name":"get_weather","arguments":"{\"city\":\"Paris\"}"}}]},{"role":"tool","content":"{\"temp\":21}","tool_call_id":"call_1"},{"role":"assistant","content":"It is 21 degrees in Paris."}]"#;
+
+        let history = ChatHistory::from_openai_json(json).expect("parse");
+        assert_eq!(history.len(), 5);
+
+        let call = &history.messages()[2];
+        assert_eq!(call.role, MessageRole::Assistant);
+        assert!(call.content.is_none());
+        assert_eq!(call.tool_calls.len(), 1);
+        assert_eq!(call.tool_calls[0].function.name, "get_weather");
+        assert_eq!(call.tool_calls[0].function.arguments["city"], "Paris");
+
+        let tool = &history.messages()[3];
+        assert_eq!(tool.role, MessageRole::Tool);
+        assert_eq!(tool.tool_call_id.as_deref(), Some("call_1"));
+
+        assert_eq!(history.to_openai_json(), json);
+    }
+
+    #[test]
+    fn openai_json_accepts_content_parts() {
+        let json = r#"[{"role":"user","content":[{"type":"text","text":"What is this?"},{"type":"image_url","image_url":{"url":"data:image/png;base64,AAAA"}}]}]"#;
+        let history = ChatHistory::from_openai_json(json).expect("parse");
+        let message = &history.messages()[0];
+        assert_eq!(message.text(), Some("What is this?"));
+        assert_eq!(message.images, vec!["data:image/png;base64,AAAA".to_string()]);
+        assert_eq!(history.to_openai_json(), json);
+    }
+
+    #[test]
+    fn purge_expired_removes_old_messages() {
+        let start = Utc::now();
+        let mut history = ChatHistory::new().with_ttl(Duration::from_secs(60));
+        for index in 0..3 {
+            history.push(
+                ChatMessage::user(format!("old {index}"))
+                    .with_created_at(start - chrono::Duration::minutes(10)),
+            );
+        }
+        history.push(ChatMessage::user("fresh").with_created_at(start));
+        history.push(ChatMessage::user("untimed"));
+        assert!(history.last().unwrap().created_at.is_some());
+
+        assert_eq!(history.purge_expired_at(start), 3);
+        assert_eq!(history.len(), 2);
+
+        let later = start + chrono::Duration::hours(1);
+        assert_eq!(history.purge_expired_at(later), 2);
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn auto_purge_runs_on_push() {
+        let mut history = ChatHistory::new()
+            .with_ttl(Duration::from_secs(60))
+            .with_auto_purge(true);
+        history.push(
+            ChatMessage::user("stale").with_created_at(Utc::now() - chrono::Duration::hours(1)),
+        );
+        history.push_user("current");
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.last().unwrap().text(), Some("current"));
+    }
+
     struct StubProvider {
         response: Mutex<String>,
     }
@@ -399,6 +1234,158 @@ mod tests {
         }
     }
 
+    struct BagOfWordsEmbedder;
+
+    #[async_trait]
+    impl EmbeddingProvider for BagOfWordsEmbedder {
+        async fn embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            Ok(inputs
+                .iter()
+                .map(|input| {
+                    let mut vector = vec![0.0f32; 64];
+                    for word in input.to_lowercase().split_whitespace() {
+                        let bucket = word.bytes().fold(0usize, |acc, b| acc * 31 + b as usize) % 64;
+                        vector[bucket] += 1.0;
+                    }
+                    vector
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn semantic_retriever_finds_similar_message() {
+        let retriever = Arc::new(SemanticRetriever::new(Arc::new(BagOfWordsEmbedder)));
+        let mut history = ChatHistory::new().with_semantic_retriever(retriever.clone());
+
+        let topics = [
+            "the weather is sunny today",
+            "my favourite colour is green",
+            "rust borrow checker rules",
+            "quarterly revenue grew strongly in europe",
+            "cats sleep most of the day",
+            "the train leaves at noon",
+            "pasta needs salted water",
+            "mountains are covered in snow",
+            "the meeting moved to friday",
+            "guitars have six strings",
+        ];
+        for topic in topics {
+            history.push_user(topic);
+        }
+        assert_eq!(retriever.len(), 10);
+
+        let results = retriever
+            .retrieve("how much did revenue grow in europe", 2)
+            .await
+            .expect("retrieve");
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .any(|message| message.text() == Some(topics[3])));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn hierarchical_summarizer_builds_levels() {
+        let provider = Arc::new(StubProvider::new("A concise summary.")) as Arc<dyn LLMProvider>;
+        let mut compressor = HierarchicalSummarizer::new(provider, "test-model")
+            .with_segment_size(10)
+            .with_max_levels(2);
+
+        let mut history = ChatHistory::new();
+        for index in 0..15 {
+            history.push_user(format!("Message {index}"));
+            history.push_assistant(format!("Reply {index}"));
+        }
+        assert_eq!(history.len(), 30);
+
+        assert!(history.compress(&mut compressor));
+        assert!(history.len() < 30);
+
+        let texts: Vec<&str> = history.iter().filter_map(|m| m.text()).collect();
+        assert!(texts[0].starts_with("Summary level 2"));
+        assert!(texts[1].starts_with("Summary level 1"));
+        assert_eq!(history.messages()[1].role, MessageRole::System);
+        assert_eq!(history.last().and_then(|m| m.text()), Some("Reply 14"));
+    }
+
+    #[tokio::test]
+    async fn hierarchical_summarizer_compresses_on_current_thread_runtime() {
+        let provider = Arc::new(StubProvider::new("A concise summary.")) as Arc<dyn LLMProvider>;
+        let mut compressor = HierarchicalSummarizer::new(provider, "test-model").with_segment_size(10);
+
+        let mut history = ChatHistory::new();
+        for index in 0..15 {
+            history.push_user(format!("Message {index}"));
+            history.push_assistant(format!("Reply {index}"));
+        }
+
+        assert!(history.compress(&mut compressor));
+        assert!(history.len() < 30);
+        assert!(history.messages()[0].text().unwrap_or_default().starts_with("Summary level"));
+    }
+
+    #[test]
+    fn count_messages_adds_per_message_overhead() {
+        let messages = [ChatMessage::system("Be brief."), ChatMessage::user("12345678")];
+        assert_eq!(CharEstimateTokenCounter.count(&messages), 5);
+        assert_eq!(CharEstimateTokenCounter.count_messages(&messages), 13);
+    }
+
+    #[test]
+    fn token_budget_evicts_oldest_non_system_messages() {
+        let mut history = ChatHistory::new();
+        history.push_system("Be brief.");
+        for index in 0..10 {
+            history.push_user(format!("{index}{}", "x".repeat(199)));
+        }
+
+        let counter = Arc::new(CharEstimateTokenCounter);
+        assert!(counter.count(history.messages()) > 500);
+
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let sink = evicted.clone();
+        let mut compressor = TokenBudgetCompressor::new(100, counter.clone())
+            .with_event_callback(move |event| sink.lock().unwrap().push(event.clone()));
+
+        assert!(history.compress(&mut compressor));
+        assert!(counter.count(history.messages()) <= 100);
+        assert_eq!(history.messages()[0].role, MessageRole::System);
+        assert!(history.last().unwrap().text().unwrap().starts_with('9'));
+
+        let evicted = evicted.lock().unwrap();
+        assert_eq!(evicted.len(), 9);
+        let CompressionEvent::MessageEvicted { role, content_preview } = &evicted[0];
+        assert_eq!(*role, MessageRole::User);
+        assert!(content_preview.starts_with('0'));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn relevance_window_keeps_old_but_relevant_turns() {
+        let mut history = ChatHistory::new();
+        for index in 0..5 {
+            history.push_user(format!("weather forecast sunny rain {index}"));
+        }
+        for index in 5..14 {
+            history.push_assistant(format!("unrelated filler chatter {index}"));
+        }
+        for index in 14..19 {
+            history.push_user(format!("travel flights hotel booking {index}"));
+        }
+        history.push_user("any weather forecast update");
+        assert_eq!(history.len(), 20);
+
+        let mut compressor = RelevanceWindowCompressor::new(Arc::new(BagOfWordsEmbedder), 10);
+        assert!(history.compress(&mut compressor));
+        assert_eq!(history.len(), 10);
+
+        let texts: Vec<&str> = history.iter().filter_map(|m| m.text()).collect();
+        for (index, text) in texts.iter().take(5).enumerate() {
+            assert_eq!(*text, format!("weather forecast sunny rain {index}"));
+        }
+        assert_eq!(texts.last(), Some(&"any weather forecast update"));
+    }
+
     #[tokio::test]
     async fn llm_compressor_uses_provider() {
         let provider = Arc::new(StubProvider::new("A concise summary."));
@@ -0,0 +1,524 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+
+use super::ChatHistory;
+use crate::types::{ChatMessage, MessageMetadata, MessageRole};
+
+/// A message matched by [`SqliteChatHistory::search`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub session_id: String,
+    pub turn_index: u32,
+    pub role: String,
+    /// Excerpt of the content with matched terms wrapped in `[` and `]`.
+    pub content_snippet: String,
+    /// FTS5 `bm25` score; lower values are better matches.
+    pub rank: f64,
+}
+
+/// SQLite-backed chat history for a single session.
+///
+/// Every message is stored as one row in the `sessions` table, keyed by session ID and
+/// turn index, so a conversation survives process restarts. The connection pool runs in WAL
+/// journal mode, which lets several readers share the database with one writer.
+#[derive(Debug, Clone)]
+pub struct SqliteChatHistory {
+    pool: SqlitePool,
+    session_id: String,
+}
+
+impl SqliteChatHistory {
+    /// Open (or create) the database at `db_path` and bind to `session_id`.
+    pub async fn new(db_path: &str, session_id: &str) -> Result<Self, sqlx::Error> {
+        let pool = connect(db_path).await?;
+        Ok(Self {
+            pool,
+            session_id: session_id.to_string(),
+        })
+    }
+
+    /// List every session ID stored in the database at `db_path`.
+    pub async fn list_sessions(db_path: &str) -> Result<Vec<String>, sqlx::Error> {
+        let pool = connect(db_path).await?;
+        let rows = sqlx::query("SELECT DISTINCT session_id FROM sessions ORDER BY session_id")
+            .fetch_all(&pool)
+            .await?;
+        pool.close().await;
+        rows.iter().map(|row| row.try_get("session_id")).collect()
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    pub async fn push(&self, message: ChatMessage) -> Result<(), sqlx::Error> {
+        let tool_calls = encode_tool_calls(&message)?;
+        let metadata = encode_metadata(&message)?;
+
+        sqlx::query(
+            "INSERT INTO sessions (session_id, turn_index, role, content, name, tool_call_id, tool_calls, created_at, metadata) \
+             VALUES (?1, (SELECT COALESCE(MAX(turn_index) + 1, 0) FROM sessions WHERE session_id = ?1), ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )
+        .bind(&self.session_id)
+        .bind(role_to_str(&message.role))
+        .bind(&message.content)
+        .bind(&message.name)
+        .bind(&message.tool_call_id)
+        .bind(tool_calls)
+        .bind(message.created_at.unwrap_or_else(Utc::now).timestamp())
+        .bind(metadata)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn push_user(&self, content: impl Into<String>) -> Result<(), sqlx::Error> {
+        self.push(ChatMessage::user(content)).await
+    }
+
+    pub async fn push_assistant(&self, content: impl Into<String>) -> Result<(), sqlx::Error> {
+        self.push(ChatMessage::assistant(content)).await
+    }
+
+    pub async fn push_system(&self, content: impl Into<String>) -> Result<(), sqlx::Error> {
+        self.push(ChatMessage::system(content)).await
+    }
+
+    pub async fn push_tool(
+        &self,
+        id: impl Into<String>,
+        content: impl Into<String>,
+    ) -> Result<(), sqlx::Error> {
+        self.push(ChatMessage::tool(id, content)).await
+    }
+
+    /// Append every message of an in-memory history to this session.
+    pub async fn extend(&self, history: &ChatHistory) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let next: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(MAX(turn_index) + 1, 0) FROM sessions WHERE session_id = ?1",
+        )
+        .bind(&self.session_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (offset, message) in history.iter().enumerate() {
+            let tool_calls = encode_tool_calls(message)?;
+            let metadata = encode_metadata(message)?;
+
+            sqlx::query(
+                "INSERT INTO sessions (session_id, turn_index, role, content, name, tool_call_id, tool_calls, created_at, metadata) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )
+            .bind(&self.session_id)
+            .bind(next + offset as i64)
+            .bind(role_to_str(&message.role))
+            .bind(&message.content)
+            .bind(&message.name)
+            .bind(&message.tool_call_id)
+            .bind(tool_calls)
+            .bind(message.created_at.unwrap_or_else(Utc::now).timestamp())
+            .bind(metadata)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await
+    }
+
+    /// Load all messages of this session in turn order.
+    pub async fn messages(&self) -> Result<Vec<ChatMessage>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT role, content, name, tool_call_id, tool_calls, created_at, metadata FROM sessions \
+             WHERE session_id = ?1 ORDER BY turn_index",
+        )
+        .bind(&self.session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_message).collect()
+    }
+
+    /// Load this session into an in-memory [`ChatHistory`].
+    pub async fn load(&self) -> Result<ChatHistory, sqlx::Error> {
+        Ok(ChatHistory::with_messages(self.messages().await?))
+    }
+
+    pub async fn last(&self) -> Result<Option<ChatMessage>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT role, content, name, tool_call_id, tool_calls, created_at, metadata FROM sessions \
+             WHERE session_id = ?1 ORDER BY turn_index DESC LIMIT 1",
+        )
+        .bind(&self.session_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(row_to_message).transpose()
+    }
+
+    pub async fn len(&self) -> Result<usize, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE session_id = ?1")
+            .bind(&self.session_id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count as usize)
+    }
+
+    pub async fn is_empty(&self) -> Result<bool, sqlx::Error> {
+        Ok(self.len().await? == 0)
+    }
+
+    /// Remove all messages of this session.
+    pub async fn clear(&self) -> Result<(), sqlx::Error> {
+        let session_id = self.session_id.clone();
+        self.delete_session(&session_id).await
+    }
+
+    /// Remove all messages stored under `session_id` in this database.
+    pub async fn delete_session(&self, session_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM sessions WHERE session_id = ?1")
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Delete messages of this session recorded more than `retention` ago. Returns the
+    /// number of messages removed.
+    pub async fn purge_expired(&self, retention: Duration) -> Result<usize, sqlx::Error> {
+        let retention = chrono::Duration::from_std(retention)
+            .map_err(|err| sqlx::Error::Protocol(err.to_string()))?;
+        let cutoff = (Utc::now() - retention).timestamp();
+        let result = sqlx::query("DELETE FROM sessions WHERE session_id = ?1 AND created_at < ?2")
+            .bind(&self.session_id)
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    /// Full-text search over every session in this database. `query` uses FTS5 query syntax;
+    /// hits are ordered best match first.
+    pub async fn search(&self, query: &str) -> Result<Vec<SearchHit>, sqlx::Error> {
+        self.run_search(query, None).await
+    }
+
+    /// Full-text search restricted to `session_id`.
+    pub async fn search_in_session(
+        &self,
+        session_id: &str,
+        query: &str,
+    ) -> Result<Vec<SearchHit>, sqlx::Error> {
+        self.run_search(query, Some(session_id)).await
+    }
+
+    async fn run_search(
+        &self,
+        query: &str,
+        session_id: Option<&str>,
+    ) -> Result<Vec<SearchHit>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT s.session_id, s.turn_index, s.role, \
+                    snippet(sessions_fts, 0, '[', ']', '...', 12) AS content_snippet, \
+                    bm25(sessions_fts) AS rank \
+             FROM sessions_fts JOIN sessions s ON s.rowid = sessions_fts.rowid \
+             WHERE sessions_fts MATCH ?1 AND (?2 IS NULL OR s.session_id = ?2) \
+             ORDER BY rank",
+        )
+        .bind(query)
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let turn_index: i64 = row.try_get("turn_index")?;
+                Ok(SearchHit {
+                    session_id: row.try_get("session_id")?,
+                    turn_index: turn_index as u32,
+                    role: row.try_get("role")?,
+                    content_snippet: row.try_get("content_snippet")?,
+                    rank: row.try_get("rank")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Close the underlying connection pool.
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+}
+
+async fn connect(db_path: &str) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::from_str(db_path)
+        .or_else(|_| SqliteConnectOptions::from_str(&format!("sqlite://{db_path}")))?
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal);
+
+    let pool = SqlitePoolOptions::new().connect_with(options).await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS sessions (\
+            session_id TEXT NOT NULL, \
+            turn_index INTEGER NOT NULL, \
+            role TEXT NOT NULL, \
+            content TEXT, \
+            name TEXT, \
+            tool_call_id TEXT, \
+            tool_calls TEXT, \
+            created_at INTEGER NOT NULL, \
+            metadata TEXT, \
+            PRIMARY KEY (session_id, turn_index)\
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    migrate_metadata_column(&pool).await?;
+
+    migrate_full_text_index(&pool).await?;
+
+    Ok(pool)
+}
+
+/// Add the `metadata` column to databases created before messages carried metadata.
+async fn migrate_metadata_column(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let exists: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name = 'metadata'",
+    )
+    .fetch_one(pool)
+    .await?;
+    if exists == 0 {
+        sqlx::query("ALTER TABLE sessions ADD COLUMN metadata TEXT")
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Create the FTS5 index over `sessions.content` and the triggers that keep it in sync.
+/// Databases created before the index existed are backfilled once.
+async fn migrate_full_text_index(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let exists: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'sessions_fts'",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let statements = [
+        "CREATE VIRTUAL TABLE IF NOT EXISTS sessions_fts \
+            USING fts5(content, content = 'sessions', content_rowid = 'rowid')",
+        "CREATE TRIGGER IF NOT EXISTS sessions_fts_insert AFTER INSERT ON sessions BEGIN \
+            INSERT INTO sessions_fts (rowid, content) VALUES (new.rowid, new.content); \
+        END",
+        "CREATE TRIGGER IF NOT EXISTS sessions_fts_delete AFTER DELETE ON sessions BEGIN \
+            INSERT INTO sessions_fts (sessions_fts, rowid, content) VALUES ('delete', old.rowid, old.content); \
+        END",
+        "CREATE TRIGGER IF NOT EXISTS sessions_fts_update AFTER UPDATE ON sessions BEGIN \
+            INSERT INTO sessions_fts (sessions_fts, rowid, content) VALUES ('delete', old.rowid, old.content); \
+            INSERT INTO sessions_fts (rowid, content) VALUES (new.rowid, new.content); \
+        END",
+    ];
+    for statement in statements {
+        sqlx::query(statement).execute(pool).await?;
+    }
+
+    if exists == 0 {
+        sqlx::query("INSERT INTO sessions_fts (sessions_fts) VALUES ('rebuild')")
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn role_to_str(role: &MessageRole) -> &'static str {
+    match role {
+        MessageRole::System => "system",
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::Tool => "tool",
+    }
+}
+
+fn role_from_str(role: &str) -> Result<MessageRole, sqlx::Error> {
+    match role {
+        "system" => Ok(MessageRole::System),
+        "user" => Ok(MessageRole::User),
+        "assistant" => Ok(MessageRole::Assistant),
+        "tool" => Ok(MessageRole::Tool),
+        other => Err(sqlx::Error::Decode(
+            format!("unknown message role `{other}`").into(),
+        )),
+    }
+}
+
+fn encode_tool_calls(message: &ChatMessage) -> Result<Option<String>, sqlx::Error> {
+    if message.tool_calls.is_empty() {
+        return Ok(None);
+    }
+    serde_json::to_string(&message.tool_calls)
+        .map(Some)
+        .map_err(|err| sqlx::Error::Encode(Box::new(err)))
+}
+
+fn encode_metadata(message: &ChatMessage) -> Result<Option<String>, sqlx::Error> {
+    message
+        .metadata
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|err| sqlx::Error::Encode(Box::new(err)))
+}
+
+fn row_to_message(row: &sqlx::sqlite::SqliteRow) -> Result<ChatMessage, sqlx::Error> {
+    let role: String = row.try_get("role")?;
+    let content: Option<String> = row.try_get("content")?;
+    let tool_calls: Option<String> = row.try_get("tool_calls")?;
+    let metadata: Option<String> = row.try_get("metadata")?;
+
+    let mut message = ChatMessage::new(role_from_str(&role)?, String::new());
+    message.content = content;
+    message.name = row.try_get("name")?;
+    message.tool_call_id = row.try_get("tool_call_id")?;
+    let created_at: i64 = row.try_get("created_at")?;
+    message.created_at = DateTime::from_timestamp(created_at, 0);
+    if let Some(raw) = tool_calls {
+        message.tool_calls =
+            serde_json::from_str(&raw).map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+    }
+    if let Some(raw) = metadata {
+        let metadata: MessageMetadata =
+            serde_json::from_str(&raw).map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+        message.metadata = Some(metadata);
+    }
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "denkwerk-history-{}-{}.db",
+            name,
+            std::process::id()
+        ));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+        path.display().to_string()
+    }
+
+    #[tokio::test]
+    async fn history_survives_reconnect() {
+        let db = temp_db("reconnect");
+        {
+            let history = SqliteChatHistory::new(&db, "session-a").await.unwrap();
+            history.push_system("You are helpful.").await.unwrap();
+            history.push_user("Hi").await.unwrap();
+            history.push_assistant("Hello!").await.unwrap();
+            history.push_user("What is 2 + 2?").await.unwrap();
+            history.push_tool("call_1", "4").await.unwrap();
+            history.close().await;
+        }
+
+        let history = SqliteChatHistory::new(&db, "session-a").await.unwrap();
+        let messages = history.messages().await.unwrap();
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[0].role, MessageRole::System);
+        assert_eq!(messages[3].text(), Some("What is 2 + 2?"));
+        assert_eq!(messages[4].tool_call_id.as_deref(), Some("call_1"));
+
+        let other = SqliteChatHistory::new(&db, "session-b").await.unwrap();
+        other.push_user("separate").await.unwrap();
+        assert_eq!(
+            SqliteChatHistory::list_sessions(&db).await.unwrap(),
+            vec!["session-a".to_string(), "session-b".to_string()]
+        );
+
+        history.delete_session("session-b").await.unwrap();
+        assert!(other.is_empty().await.unwrap());
+        assert_eq!(history.len().await.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn metadata_round_trips() {
+        let db = temp_db("metadata");
+        let history = SqliteChatHistory::new(&db, "session").await.unwrap();
+        let created_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut message = ChatMessage::user("Hi")
+            .with_created_at(created_at)
+            .with_source("planner")
+            .with_tag("audit");
+        message
+            .metadata
+            .as_mut()
+            .unwrap()
+            .custom
+            .insert("trace".to_string(), serde_json::json!({ "step": 3 }));
+        history.push(message.clone()).await.unwrap();
+        history.push_assistant("Hello!").await.unwrap();
+
+        let messages = history.messages().await.unwrap();
+        assert_eq!(messages[0].metadata, message.metadata);
+        assert_eq!(messages[0].created_at, Some(created_at));
+        assert!(messages[1].metadata.is_none());
+    }
+
+    #[tokio::test]
+    async fn purge_expired_deletes_old_rows() {
+        let db = temp_db("purge");
+        let history = SqliteChatHistory::new(&db, "session").await.unwrap();
+        let old = Utc::now() - chrono::Duration::days(2);
+        for index in 0..3 {
+            history
+                .push(ChatMessage::user(format!("old {index}")).with_created_at(old))
+                .await
+                .unwrap();
+        }
+        history.push_user("recent").await.unwrap();
+
+        let removed = history.purge_expired(Duration::from_secs(3600)).await.unwrap();
+        assert_eq!(removed, 3);
+        let messages = history.messages().await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].created_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn search_finds_matching_session() {
+        let db = temp_db("search");
+        let support = SqliteChatHistory::new(&db, "support").await.unwrap();
+        support.push_user("My invoice from March is wrong").await.unwrap();
+        support.push_assistant("Let me look into that for you.").await.unwrap();
+        support.push_user("Thanks").await.unwrap();
+
+        let sales = SqliteChatHistory::new(&db, "sales").await.unwrap();
+        sales.push_user("Do you offer discounts?").await.unwrap();
+        sales.push_assistant("Yes, for annual plans.").await.unwrap();
+
+        let hits = sales.search("invoice").await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "support");
+        assert_eq!(hits[0].turn_index, 0);
+        assert_eq!(hits[0].role, "user");
+        assert!(hits[0].content_snippet.contains("[invoice]"));
+
+        assert!(sales.search_in_session("sales", "invoice").await.unwrap().is_empty());
+        assert_eq!(sales.search_in_session("support", "invoice").await.unwrap().len(), 1);
+
+        support.clear().await.unwrap();
+        assert!(sales.search("invoice").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::{Arc, OnceLock};
+
+use tiktoken_rs::CoreBPE;
+
+use super::TokenCounter;
+use crate::types::{ChatMessage, CompletionRequest};
+use crate::LLMError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TiktokenEncoding {
+    Cl100kBase,
+    O200kBase,
+}
+
+/// Model name prefixes and their encodings. More specific prefixes come first, so
+/// `gpt-4o` is matched before `gpt-4`.
+const MODEL_ENCODINGS: &[(&str, TiktokenEncoding)] = &[
+    ("gpt-4o", TiktokenEncoding::O200kBase),
+    ("gpt-4.1", TiktokenEncoding::O200kBase),
+    ("gpt-4.5", TiktokenEncoding::O200kBase),
+    ("gpt-5", TiktokenEncoding::O200kBase),
+    ("o1", TiktokenEncoding::O200kBase),
+    ("o3", TiktokenEncoding::O200kBase),
+    ("o4", TiktokenEncoding::O200kBase),
+    ("chatgpt-4o", TiktokenEncoding::O200kBase),
+    ("gpt-4", TiktokenEncoding::Cl100kBase),
+    ("gpt-3.5-turbo", TiktokenEncoding::Cl100kBase),
+    ("text-embedding-3", TiktokenEncoding::Cl100kBase),
+    ("text-embedding-ada-002", TiktokenEncoding::Cl100kBase),
+];
+
+/// Encoding for `model`, ignoring a provider prefix such as OpenRouter's `openai/`.
+pub fn encoding_for_model(model: &str) -> Option<TiktokenEncoding> {
+    let model = model.rsplit('/').next().unwrap_or(model);
+    MODEL_ENCODINGS
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, encoding)| *encoding)
+}
+
+/// Exact token counts for OpenAI models, using the model's BPE encoding.
+#[derive(Clone)]
+pub struct TiktokenCounter {
+    encoding: TiktokenEncoding,
+    bpe: Arc<CoreBPE>,
+}
+
+impl TiktokenCounter {
+    pub fn new(encoding: TiktokenEncoding) -> Result<Self, LLMError> {
+        let bpe = match encoding {
+            TiktokenEncoding::Cl100kBase => tiktoken_rs::cl100k_base(),
+            TiktokenEncoding::O200kBase => tiktoken_rs::o200k_base(),
+        }
+        .map_err(|err| LLMError::Provider(format!("failed to load tiktoken encoding: {err}")))?;
+        Ok(Self {
+            encoding,
+            bpe: Arc::new(bpe),
+        })
+    }
+
+    /// Counter for `model`; see [`encoding_for_model`] for the supported names.
+    pub fn for_model(model_name: &str) -> Result<Self, LLMError> {
+        let encoding = encoding_for_model(model_name).ok_or_else(|| {
+            LLMError::Provider(format!("no tiktoken encoding known for model `{model_name}`"))
+        })?;
+        Self::new(encoding)
+    }
+
+    pub fn encoding(&self) -> TiktokenEncoding {
+        self.encoding
+    }
+
+    pub fn count_text(&self, text: &str) -> u32 {
+        self.bpe.encode_ordinary(text).len() as u32
+    }
+
+    /// Prompt tokens of `request`: its messages with their formatting overhead, plus the
+    /// serialized tool definitions.
+    pub fn count_request(&self, request: &CompletionRequest) -> u32 {
+        let messages = self.count_messages(&request.messages);
+        if request.tools.is_empty() {
+            return messages;
+        }
+        messages + serde_json::to_string(&request.tools).map_or(0, |json| self.count_text(&json))
+    }
+}
+
+/// Counter for `model` whose encoding is loaded once per process, or `None` when the
+/// model has no known encoding.
+pub fn shared_counter(model: &str) -> Option<TiktokenCounter> {
+    static CL100K_BASE: OnceLock<Option<TiktokenCounter>> = OnceLock::new();
+    static O200K_BASE: OnceLock<Option<TiktokenCounter>> = OnceLock::new();
+
+    let encoding = encoding_for_model(model)?;
+    let counter = match encoding {
+        TiktokenEncoding::Cl100kBase => &CL100K_BASE,
+        TiktokenEncoding::O200kBase => &O200K_BASE,
+    };
+    counter
+        .get_or_init(|| TiktokenCounter::new(encoding).ok())
+        .clone()
+}
+
+impl TokenCounter for TiktokenCounter {
+    fn count(&self, messages: &[ChatMessage]) -> u32 {
+        messages
+            .iter()
+            .filter_map(|message| message.text())
+            .map(|text| self.count_text(text))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_encoding_by_model_name() {
+        assert_eq!(encoding_for_model("gpt-4o-mini"), Some(TiktokenEncoding::O200kBase));
+        assert_eq!(encoding_for_model("openai/gpt-4o"), Some(TiktokenEncoding::O200kBase));
+        assert_eq!(encoding_for_model("gpt-4-turbo"), Some(TiktokenEncoding::Cl100kBase));
+        assert_eq!(encoding_for_model("gpt-3.5-turbo-0125"), Some(TiktokenEncoding::Cl100kBase));
+        assert_eq!(encoding_for_model("llama3"), None);
+        assert!(TiktokenCounter::for_model("llama3").is_err());
+    }
+
+    #[test]
+    fn counts_known_messages() {
+        let counter = TiktokenCounter::for_model("gpt-4").unwrap();
+        assert_eq!(counter.encoding(), TiktokenEncoding::Cl100kBase);
+
+        let messages = [ChatMessage::user("Hello world")];
+        let tokens = counter.count(&messages);
+        assert!(tokens.abs_diff(2) <= 1, "expected about 2 tokens, got {tokens}");
+        assert_eq!(counter.count_messages(&messages), tokens + 4);
+    }
+
+    #[test]
+    fn special_token_text_is_counted_as_plain_text() {
+        let counter = TiktokenCounter::for_model("gpt-4").unwrap();
+        assert!(counter.count_text("<|endoftext|>") > 1);
+    }
+
+    #[test]
+    fn counts_requests_with_shared_counter() {
+        let request = CompletionRequest::new("gpt-4o", vec![ChatMessage::user("Hello world")]);
+        let counter = shared_counter(&request.model).unwrap();
+        assert_eq!(counter.encoding(), TiktokenEncoding::O200kBase);
+        assert_eq!(counter.count_request(&request), counter.count_messages(&request.messages));
+        assert!(shared_counter("llama3").is_none());
+    }
+}
@@ -11,13 +11,38 @@ pub mod bench;
 pub mod shared_state;
 pub mod metrics;
 pub mod skills;
+pub mod budget;
+pub mod testing;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+#[cfg(feature = "http-server")]
+pub mod server;
 
  pub use error::LLMError;
- pub use providers::LLMProvider;
+ pub use providers::{EmbeddingProvider, LLMProvider, ProviderEmbedder};
+#[cfg(feature = "tracing")]
+pub use providers::logging::LoggingProvider;
+pub use providers::proxy::ProxyProvider;
+pub use providers::retry::{
+    ExponentialRetryPolicy, LinearRetryPolicy, NoRetry, RetryDecisionPolicy, RetryingProvider,
+};
+pub use providers::dedup::DeduplicatingProvider;
+pub use providers::cache::CachedProvider;
+pub use providers::fallback::FallbackProvider;
+pub use providers::load_balancer::{BackendStats, RoundRobinProvider};
+pub use providers::rate_limit::RateLimitedProvider;
+pub use providers::mock::{MockLLMProvider, MockResponse};
+pub use providers::circuit_breaker::{CircuitBreakerConfig, CircuitBreakerProvider, CircuitState};
+pub use providers::registry::ProviderRegistry;
+#[cfg(feature = "recording")]
+pub use providers::recording::{CassetteEntry, RecordingProvider, ReplayProvider};
+pub use budget::{BudgetEnforcingProvider, BudgetError, BudgetManager, WithBudget};
  pub use providers::ollama::{Ollama, OllamaConfig, ThinkMode};
+pub use providers::gemini::{Gemini, GeminiConfig};
+pub use providers::groq::{Groq, GroqBuilder};
 pub use types::{
-    ChatMessage, CompletionRequest, CompletionResponse, CompletionStream, ImageUploadRequest,
-    ImageUploadResponse, MessageRole, ProviderCapabilities, ReasoningEffort, ReasoningTrace,
+    AudioFormat, ChatMessage, CompletionRequest, CompletionResponse, CompletionStream, ContentPart, CorrelationId, ImageUploadRequest,
+    ImageUploadResponse, MessageMetadata, MessageRole, ProviderCapabilities, ReasoningEffort, ReasoningTrace,
     StreamEvent, TokenUsage, EmbeddingRequest, EmbeddingResponse, Embedding, EmbeddingUsage,
     ModelInfo, ModelPricing, ModelCapabilities, ReasoningConfig,
 };
@@ -25,14 +50,19 @@ pub use functions::{
     DynKernelFunction, FunctionCall, FunctionDefinition, FunctionRegistry, Tool, ToolCall,
     ToolCallType, ToolChoice, ToolChoiceFunction, ToolChoiceKind, ToolChoiceSimple,
 };
-pub use agents::{Agent, AgentError};
+pub use functions::acl::FunctionAcl;
+pub use functions::analytics::{FunctionAnalyticsStore, FunctionCallRecord, FunctionStats};
+pub use agents::{Agent, AgentCapability, AgentError, AgentHook, OutputSchema, SharedHistoryCompressor};
 pub use flows::handoffflow::{
     AgentAction,
+    CapabilityRouter,
+    ConsultationResult,
     HandoffEvent,
     HandoffOrchestrator,
     HandoffSession,
     HandoffTurn,
 };
+pub use flows::human_input::{HumanInputProvider, MockHumanInputProvider, StdinHumanInputProvider};
 pub use flows::spec::{
     AgentDefinition as FlowAgentDefinition,
     CallSettings as FlowCallSettings,
@@ -53,6 +83,8 @@ pub use flows::spec::{
     FlowBuilder,
     FlowLoadError,
     FlowContext,
+    ContextMutation,
+    ScopedFlowContext,
     NodeInput,
     NodeLayout,
     NodeOutput,
@@ -62,6 +94,8 @@ pub use flows::spec::{
     PlannedStep,
     ExecutionStep,
     ToolRunResult,
+    TransformFn,
+    FlowProgressEvent,
 };
 pub use skills::{SkillDefinition, SkillResult, SkillRuntime, SkillStub};
 pub use flows::magentic::{
@@ -72,15 +106,21 @@ pub use flows::magentic::{
     MagenticRun,
 };
 pub use flows::sequential::{
+    SequentialContext,
+    SequentialRunHandle,
     SequentialEvent,
     SequentialOrchestrator,
     SequentialRun,
 };
 pub use flows::concurrent::{
+    ConcatAggregator,
+    ConcurrentAggregator,
     ConcurrentEvent,
     ConcurrentOrchestrator,
     ConcurrentResult,
     ConcurrentRun,
+    SummaryAggregator,
+    WeightedConcatAggregator,
 };
 pub use flows::group_chat::{
     GroupChatEvent,
@@ -106,30 +146,66 @@ pub use flows::flow_builder::{
     FlowError,
 };
 pub use shared_state::{
+    AuditEntry,
+    AuditLog,
+    AuditedSharedStateStore,
+    InMemoryAuditLog,
     InMemorySharedStateStore,
+    JsonlFileAuditLog,
+    MutationKind,
     SharedStateContext,
     SharedStateContextExt,
     SharedStateEntry,
     SharedStateExtensions,
+    SharedStateSnapshot,
+    StateKey,
+    StateMutation,
+    StateOperation,
 };
 pub use metrics::{
-    AgentMetrics, AggregatedMetrics, CostMetrics, ErrorMetrics, ExecutionMetrics, ExecutionTimer,
-    FunctionCallMetrics, InMemoryMetricsCollector, MetricsCollector, TokenUsageMetrics, WithMetrics,
+    AgentMetrics, AggregatedMetrics, AnomalyAlert, AnomalyDetector, AppendJsonlMetricsCollector,
+    CostMetrics, ErrorMetrics, ExecutionMetrics, ExecutionTimer, FunctionCallMetrics,
+    InMemoryMetricsCollector, MetricsCollector, TokenUsageMetrics, WithMetrics,
 };
+#[cfg(feature = "tracing")]
+pub use metrics::TracingAgentHook;
  pub use plugins::math;
+ pub use plugins::web_search::{web_search_kernel, SearchBackend, SearchResult, WebSearchFunction};
+ pub use plugins::code_exec::{code_exec_kernel, CodeExecFunction, CodeExecResult, CodeSandbox, ProcessSandbox};
+ pub use plugins::http_client::{HttpAuth, HttpClientFunction, HttpMethod};
+ pub use plugins::vector_store::{
+     vector_search_kernel, Document, InMemoryVectorStore, VectorSearchFunction, VectorStore,
+ };
+ #[cfg(feature = "sql-plugin")]
+ pub use plugins::database::{connect_read_only, database_query_kernel, DatabaseQueryFunction};
+ #[cfg(feature = "wasm-sandbox")]
+ pub use plugins::code_exec::WasmSandbox;
+ #[cfg(feature = "http-server")]
+ pub use server::AgentServer;
  pub use schemars::JsonSchema;
- pub use denkwerk_macros::{kernel_function, kernel_module};
+ pub use denkwerk_macros::{kernel_function, kernel_module, shared_state_keys};
  pub use eval::{
      scenario::{DecisionSource, EvalScenario, ExpectStep, ExpectedTrace, ScriptedTurn},
      report::{CaseReport, EvalReport},
      runner::EvalRunner,
+     adversarial::{AdversarialTestGenerator, AttackType},
  };
  pub use history::{
     ChatHistory,
     ChatHistoryCompressor,
+    ChatHistorySnapshot,
     ChatHistorySummarizer,
+    CharEstimateTokenCounter,
+    CompressionEvent,
     ConciseSummarizer,
     FixedWindowCompressor,
+    HierarchicalSummarizer,
     NoopChatHistoryCompressor,
+    RelevanceWindowCompressor,
+    SemanticRetriever,
+    TokenBudgetCompressor,
+    TokenCounter,
 };
+#[cfg(feature = "tiktoken")]
+pub use history::tiktoken::{TiktokenCounter, TiktokenEncoding};
 extern crate self as denkwerk;
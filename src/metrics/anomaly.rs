@@ -0,0 +1,231 @@
+use std::{collections::VecDeque, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use super::AgentMetrics;
+
+/// Baseline size used by [`AnomalyDetector::from_metrics`] and
+/// [`super::InMemoryMetricsCollector::with_anomaly_detector`].
+pub const DEFAULT_BASELINE_WINDOW: usize = 100;
+
+/// Sensitivity used by [`AnomalyDetector::from_metrics`].
+pub const DEFAULT_SENSITIVITY: f64 = 3.0;
+
+/// Fewest baseline samples before the detector starts raising alerts.
+const MIN_BASELINE_SAMPLES: usize = 10;
+
+/// Consecutive alerts after which the detector treats the durations as the new normal.
+const REBASELINE_AFTER_ALERTS: usize = 10;
+
+/// A duration whose z-score against the baseline exceeded the detector's sensitivity.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyAlert {
+    pub z_score: f64,
+    pub duration_ms: u64,
+    /// Longest duration the baseline would still have accepted.
+    pub threshold_ms: u64,
+}
+
+/// Flags execution durations that lie more than `sensitivity` standard deviations above
+/// the mean of the last `baseline_window` normal durations. Mean and variance are kept up
+/// to date with Welford's online algorithm; anomalies are left out of the baseline so a
+/// spike does not hide the next one. After [`REBASELINE_AFTER_ALERTS`] consecutive alerts
+/// the latency is assumed to have shifted for good (a slower model, say): the baseline is
+/// rebuilt from those durations and alerting resumes against the new level.
+#[derive(Debug, Clone)]
+pub struct AnomalyDetector {
+    baseline_window: usize,
+    sensitivity: f64,
+    window: VecDeque<f64>,
+    mean: f64,
+    /// Sum of squared differences from the mean (Welford's `M2`).
+    squared_deviations: f64,
+    /// Durations of the current run of consecutive alerts.
+    consecutive_alerts: Vec<f64>,
+}
+
+impl AnomalyDetector {
+    pub fn new(baseline_window: usize, sensitivity: f64) -> Self {
+        let baseline_window = baseline_window.max(2);
+        Self {
+            baseline_window,
+            sensitivity,
+            window: VecDeque::with_capacity(baseline_window),
+            mean: 0.0,
+            squared_deviations: 0.0,
+            consecutive_alerts: Vec::new(),
+        }
+    }
+
+    /// Detector with the default window and sensitivity, its baseline filled with the
+    /// execution durations of `metrics`.
+    pub fn from_metrics(metrics: &[AgentMetrics]) -> Self {
+        let mut detector = Self::new(DEFAULT_BASELINE_WINDOW, DEFAULT_SENSITIVITY);
+        for metric in metrics {
+            detector.push(millis(metric.execution.total_duration));
+        }
+        detector
+    }
+
+    pub fn sensitivity(&self) -> f64 {
+        self.sensitivity
+    }
+
+    pub fn mean_ms(&self) -> f64 {
+        self.mean
+    }
+
+    /// Sample standard deviation of the baseline, in milliseconds.
+    pub fn std_dev_ms(&self) -> f64 {
+        if self.window.len() < 2 {
+            return 0.0;
+        }
+        (self.squared_deviations / (self.window.len() - 1) as f64).sqrt()
+    }
+
+    /// Check `duration` against the baseline. Durations that are not anomalous join it, as
+    /// do anomalous ones once enough of them arrive in a row.
+    pub fn record(&mut self, duration: Duration) -> Option<AnomalyAlert> {
+        let value = millis(duration);
+        let std_dev = self.std_dev_ms();
+        if self.window.len() >= MIN_BASELINE_SAMPLES.min(self.baseline_window) && std_dev > 0.0 {
+            let z_score = (value - self.mean) / std_dev;
+            if z_score > self.sensitivity {
+                let alert = AnomalyAlert {
+                    z_score,
+                    duration_ms: value.round() as u64,
+                    threshold_ms: (self.mean + self.sensitivity * std_dev).round() as u64,
+                };
+                self.consecutive_alerts.push(value);
+                if self.consecutive_alerts.len() >= REBASELINE_AFTER_ALERTS {
+                    self.rebaseline();
+                }
+                return Some(alert);
+            }
+        }
+
+        self.consecutive_alerts.clear();
+        self.push(value);
+        None
+    }
+
+    fn rebaseline(&mut self) {
+        self.window.clear();
+        self.mean = 0.0;
+        self.squared_deviations = 0.0;
+        for value in std::mem::take(&mut self.consecutive_alerts) {
+            self.push(value);
+        }
+    }
+
+    fn push(&mut self, value: f64) {
+        if self.window.len() == self.baseline_window {
+            if let Some(oldest) = self.window.pop_front() {
+                self.remove(oldest);
+            }
+        }
+
+        self.window.push_back(value);
+        let delta = value - self.mean;
+        self.mean += delta / self.window.len() as f64;
+        self.squared_deviations += delta * (value - self.mean);
+    }
+
+    /// Welford's update run backwards, for samples leaving the window.
+    fn remove(&mut self, value: f64) {
+        let remaining = self.window.len();
+        if remaining == 0 {
+            self.mean = 0.0;
+            self.squared_deviations = 0.0;
+            return;
+        }
+
+        let delta = value - self.mean;
+        self.mean -= delta / remaining as f64;
+        self.squared_deviations = (self.squared_deviations - delta * (value - self.mean)).max(0.0);
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Normally distributed durations around 100ms (σ = 10ms), via Box-Muller over a
+    /// fixed linear congruential generator so the test is deterministic.
+    fn normal_durations(count: usize) -> Vec<Duration> {
+        let mut state: u64 = 42;
+        let mut uniform = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 11) as f64 + 1.0) / (1u64 << 53) as f64
+        };
+        (0..count)
+            .map(|_| {
+                let normal = (-2.0 * uniform().ln()).sqrt() * (2.0 * std::f64::consts::PI * uniform()).cos();
+                Duration::from_secs_f64((100.0 + 10.0 * normal) / 1000.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flags_ten_fold_outlier() {
+        let mut detector = AnomalyDetector::new(100, 3.0);
+        for duration in normal_durations(100) {
+            assert!(detector.record(duration).is_none(), "normal duration flagged: {duration:?}");
+        }
+
+        let alert = detector.record(Duration::from_millis(1000)).expect("outlier should alert");
+        assert!(alert.z_score > detector.sensitivity());
+        assert_eq!(alert.duration_ms, 1000);
+        assert!(alert.threshold_ms > 100 && alert.threshold_ms < 200);
+    }
+
+    #[test]
+    fn rebaselines_after_a_lasting_shift() {
+        let mut detector = AnomalyDetector::new(100, 3.0);
+        for duration in normal_durations(100) {
+            detector.record(duration);
+        }
+
+        let shifted: Vec<Duration> = normal_durations(20)
+            .into_iter()
+            .map(|duration| duration * 10)
+            .collect();
+        for duration in &shifted[..REBASELINE_AFTER_ALERTS] {
+            assert!(detector.record(*duration).is_some());
+        }
+        assert!((detector.mean_ms() - 1000.0).abs() < 100.0);
+        for duration in &shifted[REBASELINE_AFTER_ALERTS..] {
+            assert!(detector.record(*duration).is_none(), "shifted duration flagged: {duration:?}");
+        }
+    }
+
+    #[test]
+    fn rolling_statistics_match_window() {
+        let mut detector = AnomalyDetector::new(3, 100.0);
+        for millis in [10, 20, 30, 40, 50] {
+            detector.record(Duration::from_millis(millis));
+        }
+        assert!((detector.mean_ms() - 40.0).abs() < 1e-9);
+        assert!((detector.std_dev_ms() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn baseline_from_metrics() {
+        let metrics: Vec<AgentMetrics> = normal_durations(50)
+            .into_iter()
+            .map(|duration| {
+                let mut metrics = AgentMetrics::new("writer".to_string());
+                metrics.execution.total_duration = duration;
+                metrics
+            })
+            .collect();
+
+        let mut detector = AnomalyDetector::from_metrics(&metrics);
+        assert!((detector.mean_ms() - 100.0).abs() < 10.0);
+        assert!(detector.record(Duration::from_secs(1)).is_some());
+    }
+}
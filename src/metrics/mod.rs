@@ -1,13 +1,23 @@
 use std::{
     collections::HashMap,
-    sync::Arc,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
-use crate::TokenUsage;
+use crate::{types::CorrelationId, TokenUsage};
+
+pub mod anomaly;
+pub mod persistence;
+#[cfg(feature = "tracing")]
+mod tracing_hook;
+
+pub use anomaly::{AnomalyAlert, AnomalyDetector};
+pub use persistence::AppendJsonlMetricsCollector;
+#[cfg(feature = "tracing")]
+pub use tracing_hook::TracingAgentHook;
 
 /// Comprehensive metrics for agent execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +42,10 @@ pub struct AgentMetrics {
 
     /// Timing information
     pub timestamp: DateTime<Utc>,
+
+    /// Request the metrics were recorded for, when run inside a correlated session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<CorrelationId>,
 }
 
 /// Execution-related metrics
@@ -109,9 +123,15 @@ impl AgentMetrics {
             errors: ErrorMetrics::default(),
             cost: CostMetrics::default(),
             timestamp: Utc::now(),
+            correlation_id: None,
         }
     }
 
+    pub fn with_correlation_id(mut self, id: CorrelationId) -> Self {
+        self.correlation_id = Some(id);
+        self
+    }
+
     /// Record token usage
     pub fn record_token_usage(&mut self, usage: &TokenUsage, input_cost: f64, output_cost: f64) {
         self.token_usage.input_tokens += usage.prompt_tokens;
@@ -323,10 +343,14 @@ pub struct AggregatedMetrics {
     pub time_range: (DateTime<Utc>, DateTime<Utc>),
 }
 
+type AnomalyCallback = Arc<dyn Fn(AnomalyAlert) + Send + Sync>;
+
 /// In-memory metrics collector implementation
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct InMemoryMetricsCollector {
     metrics: Arc<std::sync::RwLock<Vec<AgentMetrics>>>,
+    anomaly_detector: Option<Mutex<AnomalyDetector>>,
+    on_anomaly: Option<AnomalyCallback>,
 }
 
 impl InMemoryMetricsCollector {
@@ -337,83 +361,52 @@ impl InMemoryMetricsCollector {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             metrics: Arc::new(std::sync::RwLock::new(Vec::with_capacity(capacity))),
+            ..Self::default()
+        }
+    }
+
+    /// Check every recorded execution duration for latency spikes, see [`AnomalyDetector`].
+    /// Metrics already recorded form the initial baseline.
+    pub fn with_anomaly_detector(mut self, sensitivity: f64) -> Self {
+        let mut detector = AnomalyDetector::new(anomaly::DEFAULT_BASELINE_WINDOW, sensitivity);
+        for metrics in self.metrics.read().unwrap().iter() {
+            detector.record(metrics.execution.total_duration);
         }
+        self.anomaly_detector = Some(Mutex::new(detector));
+        self
+    }
+
+    /// Called with every alert raised by the anomaly detector.
+    pub fn on_anomaly(mut self, callback: impl Fn(AnomalyAlert) + Send + Sync + 'static) -> Self {
+        self.on_anomaly = Some(Arc::new(callback));
+        self
+    }
+}
+
+impl std::fmt::Debug for InMemoryMetricsCollector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InMemoryMetricsCollector")
+            .field("metrics", &self.metrics)
+            .field("anomaly_detector", &self.anomaly_detector)
+            .finish_non_exhaustive()
     }
 }
 
 impl MetricsCollector for InMemoryMetricsCollector {
     fn record_metrics(&self, metrics: AgentMetrics) {
+        if let Some(detector) = &self.anomaly_detector {
+            let alert = detector.lock().unwrap().record(metrics.execution.total_duration);
+            if let (Some(alert), Some(callback)) = (alert, &self.on_anomaly) {
+                callback(alert);
+            }
+        }
         let mut metrics_lock = self.metrics.write().unwrap();
         metrics_lock.push(metrics);
     }
 
     fn get_aggregated_metrics(&self) -> AggregatedMetrics {
         let metrics_lock = self.metrics.read().unwrap();
-        let metrics = &*metrics_lock;
-
-        if metrics.is_empty() {
-            return AggregatedMetrics {
-                total_executions: 0,
-                total_cost_usd: 0.0,
-                total_tokens: 0,
-                average_success_rate: 0.0,
-                average_execution_time: Duration::default(),
-                by_agent: HashMap::new(),
-                cost_breakdown: HashMap::new(),
-                time_range: (Utc::now(), Utc::now()),
-            };
-        }
-
-        let mut by_agent: HashMap<String, Vec<AgentMetrics>> = HashMap::new();
-        let mut total_cost = 0.0;
-        let mut total_tokens = 0u64;
-        let mut total_success_rate = 0.0;
-        let mut total_execution_time = Duration::default();
-
-        let mut min_time = metrics[0].timestamp;
-        let mut max_time = metrics[0].timestamp;
-
-        for metric in metrics {
-            by_agent
-                .entry(metric.agent_name.clone())
-                .or_default()
-                .push(metric.clone());
-
-            total_cost += metric.cost.estimated_cost_usd;
-            total_tokens += metric.token_usage.total_tokens as u64;
-            total_success_rate += metric.success_rate();
-            total_execution_time += metric.execution.total_duration;
-
-            if metric.timestamp < min_time {
-                min_time = metric.timestamp;
-            }
-            if metric.timestamp > max_time {
-                max_time = metric.timestamp;
-            }
-        }
-
-        let execution_count = metrics.len();
-        let average_success_rate = total_success_rate / execution_count as f64;
-        let average_execution_time = total_execution_time / execution_count as u32;
-
-        // Aggregate cost breakdown
-        let mut cost_breakdown = HashMap::new();
-        for metric in metrics {
-            for (category, cost) in &metric.cost.cost_breakdown {
-                *cost_breakdown.entry(category.clone()).or_insert(0.0) += cost;
-            }
-        }
-
-        AggregatedMetrics {
-            total_executions: execution_count,
-            total_cost_usd: total_cost,
-            total_tokens,
-            average_success_rate,
-            average_execution_time,
-            by_agent,
-            cost_breakdown,
-            time_range: (min_time, max_time),
-        }
+        aggregate_metrics(&metrics_lock)
     }
 
     fn get_agent_metrics(&self, agent_name: &str) -> Option<Vec<AgentMetrics>> {
@@ -433,6 +426,73 @@ impl MetricsCollector for InMemoryMetricsCollector {
     }
 }
 
+/// Aggregate a slice of recorded metrics into summary statistics
+pub(crate) fn aggregate_metrics(metrics: &[AgentMetrics]) -> AggregatedMetrics {
+    if metrics.is_empty() {
+        return AggregatedMetrics {
+            total_executions: 0,
+            total_cost_usd: 0.0,
+            total_tokens: 0,
+            average_success_rate: 0.0,
+            average_execution_time: Duration::default(),
+            by_agent: HashMap::new(),
+            cost_breakdown: HashMap::new(),
+            time_range: (Utc::now(), Utc::now()),
+        };
+    }
+
+    let mut by_agent: HashMap<String, Vec<AgentMetrics>> = HashMap::new();
+    let mut total_cost = 0.0;
+    let mut total_tokens = 0u64;
+    let mut total_success_rate = 0.0;
+    let mut total_execution_time = Duration::default();
+
+    let mut min_time = metrics[0].timestamp;
+    let mut max_time = metrics[0].timestamp;
+
+    for metric in metrics {
+        by_agent
+            .entry(metric.agent_name.clone())
+            .or_default()
+            .push(metric.clone());
+
+        total_cost += metric.cost.estimated_cost_usd;
+        total_tokens += metric.token_usage.total_tokens as u64;
+        total_success_rate += metric.success_rate();
+        total_execution_time += metric.execution.total_duration;
+
+        if metric.timestamp < min_time {
+            min_time = metric.timestamp;
+        }
+        if metric.timestamp > max_time {
+            max_time = metric.timestamp;
+        }
+    }
+
+    let execution_count = metrics.len();
+    let average_success_rate = total_success_rate / execution_count as f64;
+    let average_execution_time = total_execution_time / execution_count as u32;
+
+    // Aggregate cost breakdown
+    let mut cost_breakdown = HashMap::new();
+    for metric in metrics {
+        for (category, cost) in &metric.cost.cost_breakdown {
+            *cost_breakdown.entry(category.clone()).or_insert(0.0) += cost;
+        }
+    }
+
+    AggregatedMetrics {
+        total_executions: execution_count,
+        total_cost_usd: total_cost,
+        total_tokens,
+        average_success_rate,
+        average_execution_time,
+        by_agent,
+        cost_breakdown,
+        time_range: (min_time, max_time),
+    }
+}
+
 /// Helper struct for timing agent execution
 pub struct ExecutionTimer {
     start_time: Instant,
@@ -530,4 +590,24 @@ mod tests {
         assert_eq!(aggregated.total_executions, 1);
         assert_eq!(aggregated.by_agent.get("test_agent").unwrap().len(), 1);
     }
+
+    #[test]
+    fn test_collector_reports_latency_anomaly() {
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&alerts);
+        let collector = InMemoryMetricsCollector::new()
+            .with_anomaly_detector(3.0)
+            .on_anomaly(move |alert| sink.lock().unwrap().push(alert));
+
+        for millis in [95, 105, 100, 98, 102, 97, 103, 99, 101, 100, 1000] {
+            let mut metrics = AgentMetrics::new("test_agent".to_string());
+            metrics.execution.total_duration = Duration::from_millis(millis);
+            collector.record_metrics(metrics);
+        }
+
+        let alerts = alerts.lock().unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].duration_ms, 1000);
+        assert_eq!(collector.get_aggregated_metrics().total_executions, 11);
+    }
 }
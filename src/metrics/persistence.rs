@@ -0,0 +1,249 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use super::{aggregate_metrics, AgentMetrics, AggregatedMetrics, MetricsCollector};
+
+/// Metrics collector that appends every record as a JSON line to a file on disk.
+///
+/// Records survive restarts: a fresh collector pointed at the same file lazily
+/// reads the existing lines back the first time metrics are queried. When a
+/// size limit is configured the active file is rotated to `{path}.1` and only
+/// that single previous generation is kept.
+#[derive(Debug)]
+pub struct AppendJsonlMetricsCollector {
+    path: PathBuf,
+    state: Mutex<State>,
+    max_size_bytes: Option<u64>,
+}
+
+/// The open file and the records read from it, behind one lock so appends and lazy
+/// loads cannot interleave.
+#[derive(Debug)]
+struct State {
+    file: File,
+    cache: Option<Vec<AgentMetrics>>,
+}
+
+impl AppendJsonlMetricsCollector {
+    /// Open (or create) the JSONL file at `path`. Existing records are read on first query.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, io::Error> {
+        let path = path.as_ref().to_path_buf();
+        let file = open_append(&path)?;
+        Ok(Self {
+            path,
+            state: Mutex::new(State { file, cache: None }),
+            max_size_bytes: None,
+        })
+    }
+
+    /// Open the JSONL file at `path` and eagerly populate the in-memory cache from it.
+    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, io::Error> {
+        let collector = Self::new(path)?;
+        let records = collector.read_from_disk()?;
+        collector.state.lock().unwrap().cache = Some(records);
+        Ok(collector)
+    }
+
+    /// Rotate the file to `{path}.1` once appending would exceed `max_size_bytes`.
+    pub fn with_max_size_bytes(mut self, max_size_bytes: u64) -> Self {
+        self.max_size_bytes = Some(max_size_bytes);
+        self
+    }
+
+    /// Path of the active JSONL file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn rotated_path(&self) -> PathBuf {
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(".1");
+        PathBuf::from(rotated)
+    }
+
+    fn read_from_disk(&self) -> Result<Vec<AgentMetrics>, io::Error> {
+        let mut records = Vec::new();
+        for path in [self.rotated_path(), self.path.clone()] {
+            if path.exists() {
+                records.extend(read_jsonl(&path)?);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Append `metrics` to the file, rotating first if the size limit would be exceeded.
+    /// A rotation drops the previous `{path}.1`, so a loaded cache is re-read to match.
+    fn append(&self, state: &mut State, metrics: &AgentMetrics) -> Result<(), io::Error> {
+        let mut line = serde_json::to_vec(metrics).map_err(io::Error::other)?;
+        line.push(b'\n');
+
+        if let Some(limit) = self.max_size_bytes {
+            let current = state.file.metadata()?.len();
+            if current > 0 && current + line.len() as u64 > limit {
+                fs::rename(&self.path, self.rotated_path())?;
+                state.file = open_append(&self.path)?;
+                if state.cache.is_some() {
+                    state.cache = self.read_from_disk().ok();
+                }
+            }
+        }
+
+        state.file.write_all(&line)?;
+        state.file.flush()
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn with_cache<R>(&self, f: impl FnOnce(&[AgentMetrics]) -> R) -> R {
+        let mut state = self.state.lock().unwrap();
+        if state.cache.is_none() {
+            let records = self.read_from_disk().unwrap_or_else(|err| {
+                #[cfg(feature = "tracing")]
+                #[cfg(feature = "tracing")]
+            tracing::warn!(path = ?self.path, %err, "failed to read metrics");
+                Vec::new()
+            });
+            state.cache = Some(records);
+        }
+        f(state.cache.as_deref().unwrap_or_default())
+    }
+}
+
+impl MetricsCollector for AppendJsonlMetricsCollector {
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn record_metrics(&self, metrics: AgentMetrics) {
+        let mut state = self.state.lock().unwrap();
+        if let Err(err) = self.append(&mut state, &metrics) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(path = ?self.path, %err, "failed to persist metrics");
+        }
+
+        if let Some(records) = state.cache.as_mut() {
+            records.push(metrics);
+        }
+    }
+
+    fn get_aggregated_metrics(&self) -> AggregatedMetrics {
+        self.with_cache(aggregate_metrics)
+    }
+
+    fn get_agent_metrics(&self, agent_name: &str) -> Option<Vec<AgentMetrics>> {
+        Some(self.with_cache(|records| {
+            records
+                .iter()
+                .filter(|m| m.agent_name == agent_name)
+                .cloned()
+                .collect()
+        }))
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn clear_metrics(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Err(err) = state.file.set_len(0) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(path = ?self.path, %err, "failed to truncate metrics file");
+        }
+        let _ = fs::remove_file(self.rotated_path());
+        state.cache = Some(Vec::new());
+    }
+}
+
+fn open_append(path: &Path) -> Result<File, io::Error> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn read_jsonl(path: &Path) -> Result<Vec<AgentMetrics>, io::Error> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "denkwerk-metrics-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let mut rotated = path.clone().into_os_string();
+        rotated.push(".1");
+        let _ = fs::remove_file(PathBuf::from(rotated));
+        path
+    }
+
+    fn sample(name: &str) -> AgentMetrics {
+        let mut metrics = AgentMetrics::new(name.to_string());
+        metrics.finalize(true, 42, 1);
+        metrics
+    }
+
+    #[test]
+    fn metrics_survive_reopening_the_file() {
+        let path = temp_path("reopen");
+        {
+            let collector = AppendJsonlMetricsCollector::new(&path).unwrap();
+            for _ in 0..5 {
+                collector.record_metrics(sample("writer"));
+            }
+        }
+
+        let collector = AppendJsonlMetricsCollector::new(&path).unwrap();
+        assert_eq!(collector.get_aggregated_metrics().total_executions, 5);
+
+        let loaded = AppendJsonlMetricsCollector::load_from_file(&path).unwrap();
+        loaded.record_metrics(sample("writer"));
+        assert_eq!(loaded.get_aggregated_metrics().total_executions, 6);
+        assert_eq!(loaded.get_agent_metrics("writer").unwrap().len(), 6);
+
+        loaded.clear_metrics();
+        assert_eq!(loaded.get_aggregated_metrics().total_executions, 0);
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn rotates_when_size_limit_is_exceeded() {
+        let path = temp_path("rotate");
+        let collector = AppendJsonlMetricsCollector::new(&path)
+            .unwrap()
+            .with_max_size_bytes(64);
+
+        collector.record_metrics(sample("a"));
+        collector.record_metrics(sample("b"));
+
+        let rotated = collector.rotated_path();
+        assert_eq!(read_jsonl(&rotated).unwrap().len(), 1);
+        assert_eq!(read_jsonl(&path).unwrap().len(), 1);
+
+        let reopened = AppendJsonlMetricsCollector::new(&path).unwrap();
+        assert_eq!(reopened.get_aggregated_metrics().total_executions, 2);
+
+        // The first record is dropped from disk by the next rotation, and from the cache too.
+        assert_eq!(collector.get_aggregated_metrics().total_executions, 2);
+        collector.record_metrics(sample("c"));
+        assert_eq!(collector.get_aggregated_metrics().total_executions, 2);
+        let names: Vec<String> = collector
+            .with_cache(|records| records.iter().map(|m| m.agent_name.clone()).collect());
+        assert_eq!(names, ["b", "c"]);
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&rotated);
+    }
+}
@@ -0,0 +1,27 @@
+use serde_json::Value;
+
+use crate::{agents::AgentHook, LLMError};
+
+/// [`AgentHook`] that reports agent turns and tool calls as `tracing` events.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingAgentHook;
+
+impl AgentHook for TracingAgentHook {
+    fn on_turn_start(&self, agent: &str, model: &str) {
+        tracing::info!(agent.name = agent, agent.model = model, "agent turn started");
+    }
+
+    fn on_tool_result(&self, agent: &str, tool: &str, result: Result<&Value, &LLMError>) {
+        match result {
+            Ok(_) => tracing::info!(agent.name = agent, tool.name = tool, "tool call succeeded"),
+            Err(error) => tracing::warn!(agent.name = agent, tool.name = tool, %error, "tool call failed"),
+        }
+    }
+
+    fn on_turn_end(&self, agent: &str, result: Result<&str, &LLMError>) {
+        match result {
+            Ok(response) => tracing::info!(agent.name = agent, response.len = response.len(), "agent turn finished"),
+            Err(error) => tracing::warn!(agent.name = agent, %error, "agent turn failed"),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::error::LLMError;
+use crate::functions::{json_schema_for, DynKernelFunction, FunctionDefinition, FunctionParameter, KernelFunction};
+
+const FUNCTION_NAME: &str = "execute_code";
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeExecResult {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+}
+
+/// Runs untrusted code for a [`CodeExecFunction`].
+#[async_trait]
+pub trait CodeSandbox: Send + Sync {
+    async fn execute(&self, language: &str, code: &str, timeout_ms: u64) -> Result<CodeExecResult, LLMError>;
+}
+
+/// Environment variables passed on to child interpreters by default.
+const DEFAULT_ENV_ALLOWLIST: [&str; 4] = ["PATH", "LANG", "LC_ALL", "TZ"];
+
+/// Runs code in a child interpreter process (`python3` or `node` by default) that is killed
+/// when it exceeds its timeout.
+///
+/// This is not isolation: the child runs as the current user, with full filesystem and
+/// network access. It starts in a fresh temporary directory, which is also its `HOME`,
+/// and sees only the allowlisted environment variables, so provider API keys and the
+/// parent's working directory are not handed to it. Use `WasmSandbox` or an OS-level
+/// sandbox for code that must not touch the host.
+#[derive(Debug, Clone)]
+pub struct ProcessSandbox {
+    interpreters: HashMap<String, (String, Vec<String>)>,
+    env_allowlist: Vec<String>,
+}
+
+impl Default for ProcessSandbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessSandbox {
+    pub fn new() -> Self {
+        let python = ("python3".to_string(), vec!["-".to_string()]);
+        let node = ("node".to_string(), vec!["-".to_string()]);
+        let interpreters = [
+            ("python", python.clone()),
+            ("python3", python),
+            ("javascript", node.clone()),
+            ("js", node),
+        ]
+        .into_iter()
+        .map(|(language, interpreter)| (language.to_string(), interpreter))
+        .collect();
+        Self {
+            interpreters,
+            env_allowlist: DEFAULT_ENV_ALLOWLIST.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    /// Run `language` with `program args...`, feeding the code on stdin.
+    pub fn with_interpreter(
+        mut self,
+        language: impl Into<String>,
+        program: impl Into<String>,
+        args: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.interpreters.insert(
+            language.into().to_lowercase(),
+            (program.into(), args.into_iter().map(Into::into).collect()),
+        );
+        self
+    }
+
+    /// Environment variables copied from this process into the child, replacing the default
+    /// `PATH`, `LANG`, `LC_ALL` and `TZ`.
+    pub fn with_env_allowlist(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.env_allowlist = names.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Working directory for one run, removed when dropped.
+struct ScratchDir(std::path::PathBuf);
+
+impl ScratchDir {
+    fn create() -> Result<Self, LLMError> {
+        let path = std::env::temp_dir().join(format!("denkwerk-exec-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&path).map_err(execution_error)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[async_trait]
+impl CodeSandbox for ProcessSandbox {
+    async fn execute(&self, language: &str, code: &str, timeout_ms: u64) -> Result<CodeExecResult, LLMError> {
+        let (program, args) = self
+            .interpreters
+            .get(&language.to_lowercase())
+            .ok_or_else(|| LLMError::InvalidFunctionArguments(format!("unsupported language: {language}")))?;
+
+        let scratch = ScratchDir::create()?;
+        let env = self
+            .env_allowlist
+            .iter()
+            .filter_map(|name| std::env::var_os(name).map(|value| (name.clone(), value)));
+        let mut child = Command::new(program)
+            .args(args)
+            .env_clear()
+            .envs(env)
+            .env("HOME", &scratch.0)
+            .env("TMPDIR", &scratch.0)
+            .current_dir(&scratch.0)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| execution_error(format!("failed to start {program}: {err}")))?;
+
+        // Stdin is written while the output is read, so a child that prints before it has
+        // read all of its input cannot fill its pipe and block both sides.
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let write_stdin = async move {
+            let written = stdin.write_all(code.as_bytes()).await;
+            drop(stdin);
+            match written {
+                Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => Err(execution_error(err)),
+                _ => Ok(()),
+            }
+        };
+
+        // Dropping the pending future drops the child, which kills it.
+        let (written, output) = tokio::time::timeout(
+            Duration::from_millis(timeout_ms),
+            async { tokio::join!(write_stdin, child.wait_with_output()) },
+        )
+        .await
+        .map_err(|_| LLMError::Timeout)?;
+        written?;
+        let output = output.map_err(execution_error)?;
+
+        Ok(CodeExecResult {
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            exit_code: output.status.code().unwrap_or(-1),
+        })
+    }
+}
+
+/// How often [`WasmSandbox`] advances its engine's epoch; run timeouts are counted in these.
+#[cfg(feature = "wasm-sandbox")]
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Runs WebAssembly text modules with `wasmtime`. The module's exported `main` function is
+/// called and its `i32` result becomes the exit code; there is no stdout or stderr yet.
+///
+/// A background thread advances the engine's epoch every 10ms for as long as the sandbox
+/// lives, and each run's store traps once its own deadline, counted in those ticks, passes.
+/// A timeout therefore stops only the run that exceeded it.
+#[cfg(feature = "wasm-sandbox")]
+#[derive(Clone)]
+pub struct WasmSandbox {
+    engine: wasmtime::Engine,
+}
+
+#[cfg(feature = "wasm-sandbox")]
+impl WasmSandbox {
+    pub fn new() -> Result<Self, LLMError> {
+        let mut config = wasmtime::Config::new();
+        config.epoch_interruption(true);
+        let engine = wasmtime::Engine::new(&config).map_err(execution_error)?;
+
+        let ticker = engine.weak();
+        std::thread::Builder::new()
+            .name("denkwerk-wasm-epoch".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(EPOCH_TICK);
+                match ticker.upgrade() {
+                    Some(engine) => engine.increment_epoch(),
+                    None => break,
+                }
+            })
+            .map_err(execution_error)?;
+
+        Ok(Self { engine })
+    }
+}
+
+#[cfg(feature = "wasm-sandbox")]
+#[async_trait]
+impl CodeSandbox for WasmSandbox {
+    async fn execute(&self, language: &str, code: &str, timeout_ms: u64) -> Result<CodeExecResult, LLMError> {
+        if !matches!(language.to_lowercase().as_str(), "wasm" | "wat") {
+            return Err(LLMError::InvalidFunctionArguments(format!("unsupported language: {language}")));
+        }
+
+        let engine = self.engine.clone();
+        let code = code.to_string();
+        let deadline_ticks = timeout_ms.div_ceil(EPOCH_TICK.as_millis() as u64).max(1);
+        let run = tokio::task::spawn_blocking(move || -> Result<i32, LLMError> {
+            let module = wasmtime::Module::new(&engine, code).map_err(execution_error)?;
+            let mut store = wasmtime::Store::new(&engine, ());
+            store.set_epoch_deadline(deadline_ticks);
+            let instance = wasmtime::Instance::new(&mut store, &module, &[]).map_err(execution_error)?;
+            let main = instance
+                .get_typed_func::<(), i32>(&mut store, "main")
+                .map_err(execution_error)?;
+            main.call(&mut store, ()).map_err(|err| {
+                if err.downcast_ref::<wasmtime::Trap>() == Some(&wasmtime::Trap::Interrupt) {
+                    LLMError::Timeout
+                } else {
+                    execution_error(err)
+                }
+            })
+        });
+        let exit_code = run.await.map_err(execution_error)??;
+
+        Ok(CodeExecResult {
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_code,
+        })
+    }
+}
+
+fn execution_error(error: impl ToString) -> LLMError {
+    LLMError::FunctionExecution {
+        function: FUNCTION_NAME.to_string(),
+        message: error.to_string(),
+    }
+}
+
+/// Kernel function that lets agents run code in a [`CodeSandbox`].
+#[derive(Clone)]
+pub struct CodeExecFunction {
+    sandbox: Arc<dyn CodeSandbox>,
+}
+
+impl CodeExecFunction {
+    pub fn new(sandbox: Arc<dyn CodeSandbox>) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[async_trait]
+impl KernelFunction for CodeExecFunction {
+    fn definition(&self) -> FunctionDefinition {
+        let mut function = FunctionDefinition::new(FUNCTION_NAME)
+            .with_description("Run a code snippet in a sandbox and return its stdout, stderr and exit code.");
+        function.add_parameter(
+            FunctionParameter::new("language", json_schema_for::<String>())
+                .with_description("The language of the snippet, e.g. python or javascript."),
+        );
+        function.add_parameter(
+            FunctionParameter::new("code", json_schema_for::<String>()).with_description("The code to run."),
+        );
+        function.add_parameter(
+            FunctionParameter::new("timeout_ms", json_schema_for::<u64>())
+                .with_description("How long the code may run before it is stopped (default 10000).")
+                .optional(),
+        );
+        function
+    }
+
+    async fn invoke(&self, arguments: &Value) -> Result<Value, LLMError> {
+        let field = |name: &str| {
+            arguments
+                .get(name)
+                .and_then(Value::as_str)
+                .ok_or_else(|| LLMError::InvalidFunctionArguments(format!("{name} must be a string")))
+        };
+        let language = field("language")?;
+        let code = field("code")?;
+        let timeout_ms = match arguments.get("timeout_ms") {
+            None | Some(Value::Null) => DEFAULT_TIMEOUT_MS,
+            Some(value) => value
+                .as_u64()
+                .ok_or_else(|| LLMError::InvalidFunctionArguments("timeout_ms must be a positive integer".into()))?,
+        };
+
+        let result = self.sandbox.execute(language, code, timeout_ms).await?;
+        Ok(serde_json::to_value(result)?)
+    }
+}
+
+pub fn code_exec_kernel(sandbox: Arc<dyn CodeSandbox>) -> DynKernelFunction {
+    Arc::new(CodeExecFunction::new(sandbox))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn python_prints_hello() {
+        let result = ProcessSandbox::new()
+            .execute("python", "print('hello')", 10_000)
+            .await
+            .unwrap();
+        assert_eq!(result.stdout, "hello\n");
+        assert_eq!(result.exit_code, 0);
+    }
+
+    #[tokio::test]
+    async fn kernel_returns_result_json_and_stops_slow_code() {
+        let function = CodeExecFunction::new(Arc::new(ProcessSandbox::new()));
+        let value = function
+            .invoke(&json!({ "language": "python", "code": "import sys; sys.exit(3)" }))
+            .await
+            .unwrap();
+        assert_eq!(value, json!({ "stdout": "", "stderr": "", "exit_code": 3 }));
+
+        let error = function
+            .invoke(&json!({ "language": "python", "code": "import time; time.sleep(10)", "timeout_ms": 200 }))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, LLMError::Timeout));
+
+        let error = function
+            .invoke(&json!({ "language": "cobol", "code": "" }))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, LLMError::InvalidFunctionArguments(_)));
+    }
+
+    #[tokio::test]
+    async fn child_gets_scratch_dir_and_allowlisted_env_only() {
+        let sandbox = ProcessSandbox::new().with_interpreter("sh", "sh", ["-c", "pwd; env"]);
+        let result = sandbox.execute("sh", "", 10_000).await.unwrap();
+
+        let mut lines = result.stdout.lines();
+        let cwd = lines.next().unwrap();
+        assert!(cwd.contains("denkwerk-exec-"), "ran in {cwd}");
+        assert!(!std::path::Path::new(cwd).exists(), "scratch dir was not removed");
+
+        let names: Vec<&str> = lines.filter_map(|line| line.split_once('=')).map(|(name, _)| name).collect();
+        let allowed = ["PATH", "LANG", "LC_ALL", "TZ", "HOME", "TMPDIR", "PWD", "SHLVL", "_"];
+        assert!(names.iter().all(|name| allowed.contains(name)), "leaked env: {names:?}");
+    }
+
+    #[tokio::test]
+    async fn large_input_and_output_do_not_deadlock() {
+        let sandbox = ProcessSandbox::new()
+            .with_interpreter("sh", "sh", ["-c", "head -c 200000 /dev/zero | tr '\\0' x; cat > /dev/null"]);
+        let code = "y".repeat(200_000);
+        let result = sandbox.execute("sh", &code, 10_000).await.unwrap();
+        assert_eq!(result.stdout.len(), 200_000);
+        assert_eq!(result.exit_code, 0);
+    }
+
+    #[cfg(feature = "wasm-sandbox")]
+    #[tokio::test]
+    async fn wasm_timeout_stops_only_the_slow_run() {
+        let sandbox = WasmSandbox::new().unwrap();
+        let slow = r#"(module (func (export "main") (result i32) (loop $spin (br $spin)) i32.const 0))"#;
+        let fast = r#"(module (func (export "main") (result i32) i32.const 1))"#;
+
+        let long_running = tokio::spawn({
+            let sandbox = sandbox.clone();
+            async move { sandbox.execute("wat", slow, 2_000).await }
+        });
+        let error = sandbox.execute("wat", slow, 50).await.unwrap_err();
+        assert!(matches!(error, LLMError::Timeout));
+        assert_eq!(sandbox.execute("wat", fast, 1_000).await.unwrap().exit_code, 1);
+        assert!(!long_running.is_finished());
+        long_running.abort();
+    }
+
+    #[cfg(feature = "wasm-sandbox")]
+    #[tokio::test]
+    async fn wasm_main_result_is_the_exit_code() {
+        let module = r#"(module (func (export "main") (result i32) i32.const 7))"#;
+        let result = WasmSandbox::new().unwrap().execute("wat", module, 5_000).await.unwrap();
+        assert_eq!(result.exit_code, 7);
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::any::{AnyPoolOptions, AnyRow};
+use sqlx::AnyPool;
+use sqlx::{Column, Executor, Row};
+
+use crate::error::LLMError;
+use crate::functions::{json_schema_for, DynKernelFunction, FunctionDefinition, FunctionParameter, KernelFunction};
+
+const FUNCTION_NAME: &str = "database_query";
+
+/// Keywords that may follow a table (and its alias) in a `FROM` or `JOIN` clause.
+const CLAUSE_KEYWORDS: [&str; 22] = [
+    "where", "join", "inner", "left", "right", "full", "cross", "outer", "natural", "on", "using",
+    "group", "order", "limit", "offset", "having", "union", "except", "intersect", "window",
+    "as", "select",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Literal,
+    Punct(char),
+}
+
+impl Token {
+    fn is_word(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn is_clause_keyword(word: &str) -> bool {
+    CLAUSE_KEYWORDS.iter().any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+fn denied(reason: impl Into<String>) -> LLMError {
+    LLMError::PermissionDenied(reason.into())
+}
+
+/// Splits `sql` into words, literals and punctuation. Quoted identifiers and comments are
+/// rejected outright, since they could hide a table name from the allowlist check.
+fn tokenize(sql: &str) -> Result<Vec<Token>, LLMError> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            c if c.is_whitespace() => {}
+            '\'' => {
+                loop {
+                    match chars.next() {
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                        }
+                        Some('\'') => break,
+                        Some(_) => {}
+                        None => return Err(denied("unterminated string literal")),
+                    }
+                }
+                tokens.push(Token::Literal);
+            }
+            '"' | '`' | '[' => return Err(denied("quoted identifiers are not allowed")),
+            '-' if chars.peek() == Some(&'-') => return Err(denied("comments are not allowed")),
+            '/' if chars.peek() == Some(&'*') => return Err(denied("comments are not allowed")),
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_ascii_alphanumeric() || next == '_' || next == '.' || next == '$') {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            c if c.is_ascii() => tokens.push(Token::Punct(c)),
+            _ => return Err(denied("non-ASCII characters outside string literals are not allowed")),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Opens a pool on `url` whose connections refuse writes: SQLite connections run with
+/// `PRAGMA query_only`, Postgres and MySQL sessions are set to read-only transactions.
+pub async fn connect_read_only(url: &str) -> Result<AnyPool, LLMError> {
+    sqlx::any::install_default_drivers();
+    AnyPoolOptions::new()
+        .after_connect(|connection, _| {
+            Box::pin(async move {
+                let backend = connection.backend_name().to_lowercase();
+                let statement = if backend.contains("sqlite") {
+                    "PRAGMA query_only = ON"
+                } else if backend.contains("postgres") {
+                    "SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY"
+                } else {
+                    "SET SESSION TRANSACTION READ ONLY"
+                };
+                connection.execute(statement).await?;
+                Ok(())
+            })
+        })
+        .connect(url)
+        .await
+        .map_err(execution_error)
+}
+
+/// Read-only SQL kernel function. Queries must be a single `SELECT` that only reads from the
+/// allowed tables; anything else is rejected with [`LLMError::PermissionDenied`].
+///
+/// The check is deliberately strict and rejects queries it cannot fully account for:
+/// quoted identifiers, comments, comma joins and table-valued functions are refused, and
+/// every `FROM` or `JOIN` must be followed by an allowed table or a subquery. Pass a pool
+/// from [`connect_read_only`] so the database refuses writes as well.
+#[derive(Debug, Clone)]
+pub struct DatabaseQueryFunction {
+    pool: AnyPool,
+    allowed_tables: Vec<String>,
+}
+
+impl DatabaseQueryFunction {
+    pub fn new(pool: AnyPool, allowed_tables: Vec<String>) -> Self {
+        Self { pool, allowed_tables }
+    }
+
+    /// Connects to `DATABASE_URL` through [`connect_read_only`].
+    pub async fn from_env(allowed_tables: Vec<String>) -> Result<Self, LLMError> {
+        let url = std::env::var("DATABASE_URL").map_err(|_| LLMError::MissingApiKey("DATABASE_URL"))?;
+        Ok(Self::new(connect_read_only(&url).await?, allowed_tables))
+    }
+
+    pub fn allowed_tables(&self) -> &[String] {
+        &self.allowed_tables
+    }
+
+    /// Checks that `sql` is a single `SELECT` over allowed tables.
+    pub fn validate(&self, sql: &str) -> Result<(), LLMError> {
+        let statement = sql.trim().trim_end_matches(';').trim();
+        let tokens = tokenize(statement)?;
+        if !tokens.first().is_some_and(|token| token.is_word("select")) {
+            return Err(denied("only SELECT statements are allowed"));
+        }
+        if tokens.contains(&Token::Punct(';')) {
+            return Err(denied("only a single statement is allowed"));
+        }
+
+        for (index, token) in tokens.iter().enumerate() {
+            if token.is_word("from") || token.is_word("join") {
+                self.check_table_reference(&tokens[index + 1..])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the tokens right after a `FROM` or `JOIN`: an allowed table with an optional
+    /// alias, or a parenthesized subquery.
+    fn check_table_reference(&self, tokens: &[Token]) -> Result<(), LLMError> {
+        let table = match tokens.first() {
+            Some(Token::Punct('(')) if tokens.get(1).is_some_and(|token| token.is_word("select")) => {
+                return Ok(());
+            }
+            Some(Token::Word(table)) if !is_clause_keyword(table) => table,
+            _ => return Err(denied("FROM and JOIN must name a table or a subquery")),
+        };
+        if !self.allowed_tables.iter().any(|allowed| allowed.eq_ignore_ascii_case(table)) {
+            return Err(denied(format!("table {table} is not allowed")));
+        }
+
+        let mut rest = &tokens[1..];
+        if rest.first() == Some(&Token::Punct('(')) {
+            return Err(denied("table-valued functions are not allowed"));
+        }
+        if rest.first().is_some_and(|token| token.is_word("as")) {
+            rest = rest.get(2..).unwrap_or_default();
+        } else if matches!(rest.first(), Some(Token::Word(alias)) if !is_clause_keyword(alias)) {
+            rest = &rest[1..];
+        }
+        match rest.first() {
+            None | Some(Token::Punct(')')) | Some(Token::Word(_)) => Ok(()),
+            Some(Token::Punct(',')) => Err(denied("comma joins are not allowed; use JOIN")),
+            Some(_) => Err(denied("unexpected token after a table reference")),
+        }
+    }
+
+    pub async fn query(&self, sql: &str, params: &[Value]) -> Result<Vec<HashMap<String, Value>>, LLMError> {
+        self.validate(sql)?;
+
+        let mut query = sqlx::query(sql);
+        for param in params {
+            query = match param {
+                Value::Null => query.bind(None::<String>),
+                Value::Bool(b) => query.bind(*b),
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => query.bind(i),
+                    None => query.bind(n.as_f64().unwrap_or_default()),
+                },
+                Value::String(s) => query.bind(s.clone()),
+                other => query.bind(other.to_string()),
+            };
+        }
+
+        let rows = query.fetch_all(&self.pool).await.map_err(execution_error)?;
+        Ok(rows.iter().map(row_to_map).collect())
+    }
+}
+
+fn row_to_map(row: &AnyRow) -> HashMap<String, Value> {
+    row.columns()
+        .iter()
+        .map(|column| {
+            let index = column.ordinal();
+            let value = if let Ok(v) = row.try_get::<Option<i64>, _>(index) {
+                v.map(Value::from)
+            } else if let Ok(v) = row.try_get::<Option<f64>, _>(index) {
+                v.map(Value::from)
+            } else if let Ok(v) = row.try_get::<Option<String>, _>(index) {
+                v.map(Value::from)
+            } else if let Ok(v) = row.try_get::<Option<bool>, _>(index) {
+                v.map(Value::from)
+            } else {
+                None
+            };
+            (column.name().to_string(), value.unwrap_or(Value::Null))
+        })
+        .collect()
+}
+
+fn execution_error(error: impl ToString) -> LLMError {
+    LLMError::FunctionExecution {
+        function: FUNCTION_NAME.to_string(),
+        message: error.to_string(),
+    }
+}
+
+#[async_trait]
+impl KernelFunction for DatabaseQueryFunction {
+    fn definition(&self) -> FunctionDefinition {
+        let mut function = FunctionDefinition::new(FUNCTION_NAME).with_description(format!(
+            "Run a read-only SQL SELECT against these tables: {}.",
+            self.allowed_tables.join(", ")
+        ));
+        function.add_parameter(
+            FunctionParameter::new("sql", json_schema_for::<String>())
+                .with_description("A single SELECT statement; use ? placeholders for params."),
+        );
+        function.add_parameter(
+            FunctionParameter::new("params", json_schema_for::<Vec<Value>>())
+                .with_description("Values bound to the placeholders, in order.")
+                .optional(),
+        );
+        function
+    }
+
+    async fn invoke(&self, arguments: &Value) -> Result<Value, LLMError> {
+        let sql = arguments
+            .get("sql")
+            .and_then(Value::as_str)
+            .ok_or_else(|| LLMError::InvalidFunctionArguments("sql must be a string".into()))?;
+        let params = match arguments.get("params") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(values)) => values.clone(),
+            Some(_) => return Err(LLMError::InvalidFunctionArguments("params must be an array".into())),
+        };
+
+        let rows = self.query(sql, &params).await?;
+        Ok(serde_json::to_value(rows)?)
+    }
+}
+
+pub fn database_query_kernel(pool: AnyPool, allowed_tables: Vec<String>) -> DynKernelFunction {
+    Arc::new(DatabaseQueryFunction::new(pool, allowed_tables))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sqlx::any::AnyPoolOptions;
+
+    async fn function() -> DatabaseQueryFunction {
+        sqlx::any::install_default_drivers();
+        // A single connection keeps every query on the same in-memory database.
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for statement in [
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, score REAL)",
+            "CREATE TABLE secrets (value TEXT)",
+            "INSERT INTO users (id, name, score) VALUES (1, 'ada', 9.5), (2, 'grace', NULL)",
+        ] {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        DatabaseQueryFunction::new(pool, vec!["users".to_string()])
+    }
+
+    #[tokio::test]
+    async fn select_returns_rows_as_json() {
+        let function = function().await;
+        let value = function
+            .invoke(&json!({ "sql": "SELECT id, name, score FROM users WHERE id >= ? ORDER BY id", "params": [1] }))
+            .await
+            .unwrap();
+        assert_eq!(
+            value,
+            json!([
+                { "id": 1, "name": "ada", "score": 9.5 },
+                { "id": 2, "name": "grace", "score": null }
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_writes_and_other_tables() {
+        let function = function().await;
+        for sql in [
+            "DELETE FROM users",
+            "SELECT * FROM secrets",
+            "SELECT u.name FROM users u JOIN secrets s ON 1 = 1",
+            "SELECT 1; DROP TABLE users",
+            "SELECT * FROM users, secrets",
+            "SELECT * FROM users u, secrets",
+            "SELECT * FROM \"secrets\"",
+            "SELECT * FROM [secrets]",
+            "SELECT * FROM `secrets`",
+            "SELECT * FROM(secrets)",
+            "SELECT * FROM pragma_table_info('secrets')",
+            "SELECT * FROM users WHERE name IN (SELECT value FROM secrets)",
+            "SELECT (SELECT value FROM secrets) FROM users",
+            "SELECT * FROM users -- FROM secrets",
+            "SELECT * FROM users /* */ JOIN secrets",
+        ] {
+            let error = function.invoke(&json!({ "sql": sql })).await.unwrap_err();
+            assert!(matches!(error, LLMError::PermissionDenied(_)), "{sql}");
+        }
+
+        for sql in [
+            "SELECT name FROM users AS u WHERE u.name = 'it''s, FROM secrets'",
+            "SELECT COUNT(*) FROM (SELECT id FROM users) WHERE 1 = 1",
+            "SELECT a.name FROM users a JOIN users b ON a.id = b.id ORDER BY a.id",
+        ] {
+            function.validate(sql).unwrap_or_else(|err| panic!("{sql}: {err}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn read_only_pool_refuses_writes() {
+        let pool = connect_read_only("sqlite::memory:").await.unwrap();
+        assert_eq!(sqlx::query("SELECT 1").fetch_all(&pool).await.unwrap().len(), 1);
+        assert!(sqlx::query("CREATE TABLE t (x INTEGER)").execute(&pool).await.is_err());
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::LLMError;
+use crate::functions::http::ParamSpec;
+use crate::functions::{FunctionDefinition, KernelFunction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl HttpMethod {
+    fn as_reqwest(self) -> Method {
+        match self {
+            HttpMethod::Get => Method::GET,
+            HttpMethod::Post => Method::POST,
+            HttpMethod::Put => Method::PUT,
+            HttpMethod::Patch => Method::PATCH,
+            HttpMethod::Delete => Method::DELETE,
+        }
+    }
+
+    /// Whether arguments travel as a JSON body rather than as query parameters.
+    fn sends_body(self) -> bool {
+        matches!(self, HttpMethod::Post | HttpMethod::Put | HttpMethod::Patch)
+    }
+}
+
+/// Credentials attached to every request. `Debug` output leaves the secrets out.
+#[derive(Clone, PartialEq, Eq)]
+pub enum HttpAuth {
+    Bearer(String),
+    ApiKey { header: String, key: String },
+}
+
+impl std::fmt::Debug for HttpAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpAuth::Bearer(_) => f.debug_tuple("Bearer").field(&"[redacted]").finish(),
+            HttpAuth::ApiKey { header, .. } => f
+                .debug_struct("ApiKey")
+                .field("header", header)
+                .field("key", &"[redacted]")
+                .finish(),
+        }
+    }
+}
+
+/// `auth` section of a spec file. Values written as `${VAR}` are read from the environment
+/// when the spec is loaded.
+#[derive(Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AuthSpec {
+    Bearer { token: String },
+    ApiKey { header: String, key: String },
+}
+
+impl std::fmt::Debug for AuthSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthSpec::Bearer { .. } => f.debug_struct("Bearer").field("token", &"[redacted]").finish(),
+            AuthSpec::ApiKey { header, .. } => f
+                .debug_struct("ApiKey")
+                .field("header", header)
+                .field("key", &"[redacted]")
+                .finish(),
+        }
+    }
+}
+
+impl AuthSpec {
+    fn resolve(self) -> io::Result<HttpAuth> {
+        Ok(match self {
+            AuthSpec::Bearer { token } => HttpAuth::Bearer(expand_env(token)?),
+            AuthSpec::ApiKey { header, key } => HttpAuth::ApiKey {
+                header,
+                key: expand_env(key)?,
+            },
+        })
+    }
+}
+
+fn expand_env(value: String) -> io::Result<String> {
+    match value.strip_prefix("${").and_then(|rest| rest.strip_suffix('}')) {
+        Some(var) => std::env::var(var).map_err(|_| {
+            io::Error::new(io::ErrorKind::NotFound, format!("missing env var {var} for http auth"))
+        }),
+        None => Ok(value),
+    }
+}
+
+/// YAML description of an endpoint, loaded by [`HttpClientFunction::from_spec_file`].
+#[derive(Debug, Clone, Deserialize)]
+struct HttpClientSpec {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    base_url: String,
+    method: HttpMethod,
+    #[serde(default)]
+    auth: Option<AuthSpec>,
+    #[serde(default)]
+    parameters: BTreeMap<String, ParamSpec>,
+}
+
+/// Calls a REST endpoint, sending the arguments as query parameters (GET, DELETE) or as a
+/// JSON body (POST, PUT, PATCH). Returns `{ "status": .., "body": .. }` like
+/// [`crate::functions::http::HttpFunction`].
+#[derive(Debug, Clone)]
+pub struct HttpClientFunction {
+    pub name: String,
+    pub base_url: String,
+    pub method: HttpMethod,
+    pub auth: Option<HttpAuth>,
+    definition: FunctionDefinition,
+    client: reqwest::Client,
+}
+
+impl HttpClientFunction {
+    pub fn new(definition: FunctionDefinition, base_url: impl Into<String>, method: HttpMethod) -> Self {
+        Self {
+            name: definition.name.clone(),
+            base_url: base_url.into(),
+            method,
+            auth: None,
+            definition,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    pub fn from_spec_file(path: &Path) -> Result<Arc<dyn KernelFunction>, io::Error> {
+        let content = std::fs::read_to_string(path)?;
+        let spec: HttpClientSpec =
+            serde_yaml::from_str(&content).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        let mut definition = FunctionDefinition::new(spec.name);
+        if let Some(description) = spec.description {
+            definition = definition.with_description(description);
+        }
+        for (name, param) in &spec.parameters {
+            definition.add_parameter(param.to_parameter(name));
+        }
+
+        let mut function = Self::new(definition, spec.base_url, spec.method);
+        if let Some(auth) = spec.auth {
+            function = function.with_auth(auth.resolve()?);
+        }
+        Ok(Arc::new(function))
+    }
+
+    fn execution_error(&self, error: impl ToString) -> LLMError {
+        LLMError::FunctionExecution {
+            function: self.name.clone(),
+            message: error.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl KernelFunction for HttpClientFunction {
+    fn definition(&self) -> FunctionDefinition {
+        self.definition.clone()
+    }
+
+    async fn invoke(&self, arguments: &Value) -> Result<Value, LLMError> {
+        let args = arguments
+            .as_object()
+            .ok_or_else(|| LLMError::InvalidFunctionArguments("arguments must be an object".into()))?;
+
+        let mut request = self.client.request(self.method.as_reqwest(), &self.base_url);
+        request = match &self.auth {
+            Some(HttpAuth::Bearer(token)) => request.bearer_auth(token),
+            Some(HttpAuth::ApiKey { header, key }) => request.header(header, key),
+            None => request,
+        };
+        request = if self.method.sends_body() {
+            request.json(args)
+        } else {
+            let pairs: Vec<(&str, String)> = args
+                .iter()
+                .map(|(key, value)| match value {
+                    Value::String(s) => (key.as_str(), s.clone()),
+                    other => (key.as_str(), other.to_string()),
+                })
+                .collect();
+            request.query(&pairs)
+        };
+
+        let response = request.send().await.map_err(|err| self.execution_error(err))?;
+        let status = response.status();
+        let is_json = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|h| h.to_str().ok())
+            .is_some_and(|content_type| content_type.contains("application/json"));
+
+        let body = if is_json {
+            response.json::<Value>().await.map_err(|err| self.execution_error(err))?
+        } else {
+            Value::String(response.text().await.map_err(|err| self.execution_error(err))?)
+        };
+        Ok(json!({ "status": status.as_u16(), "body": body }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn write_spec(name: &str, yaml: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("denkwerk_http_client_{name}_{}.yaml", std::process::id()));
+        std::fs::write(&path, yaml).unwrap();
+        path
+    }
+
+    #[test]
+    fn debug_output_hides_credentials() {
+        let bearer = format!("{:?}", HttpAuth::Bearer("sk-secret".to_string()));
+        let api_key = format!("{:?}", HttpAuth::ApiKey { header: "X-Api-Key".to_string(), key: "sk-secret".to_string() });
+        assert!(!bearer.contains("sk-secret") && !api_key.contains("sk-secret"));
+        assert!(api_key.contains("X-Api-Key"));
+    }
+
+    #[tokio::test]
+    async fn get_sends_query_params_with_bearer_auth() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/weather"))
+            .and(query_param("city", "Berlin"))
+            .and(query_param("days", "2"))
+            .and(header("Authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "forecast": ["sun", "rain"] })))
+            .mount(&server)
+            .await;
+
+        let spec = write_spec(
+            "get",
+            &format!(
+                r#"
+name: get_weather
+description: Look up the forecast.
+base_url: {}/weather
+method: GET
+auth:
+  type: bearer
+  token: secret
+parameters:
+  city:
+    type: string
+  days:
+    type: integer
+    required: false
+"#,
+                server.uri()
+            ),
+        );
+        let function = HttpClientFunction::from_spec_file(&spec).unwrap();
+        let definition = function.definition();
+        assert_eq!(definition.name, "get_weather");
+        assert_eq!(definition.parameters.required, vec!["city".to_string()]);
+
+        let value = function.invoke(&json!({ "city": "Berlin", "days": 2 })).await.unwrap();
+        assert_eq!(value, json!({ "status": 200, "body": { "forecast": ["sun", "rain"] } }));
+    }
+
+    #[tokio::test]
+    async fn post_sends_json_body_with_api_key_from_env() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/issues"))
+            .and(header("X-Api-Key", "from-env"))
+            .and(body_json(json!({ "title": "Bug" })))
+            .respond_with(ResponseTemplate::new(201).set_body_string("created"))
+            .mount(&server)
+            .await;
+
+        std::env::set_var("DENKWERK_HTTP_CLIENT_TEST_KEY", "from-env");
+        let spec = write_spec(
+            "post",
+            &format!(
+                r#"
+name: create_issue
+base_url: {}/issues
+method: POST
+auth:
+  type: api_key
+  header: X-Api-Key
+  key: ${{DENKWERK_HTTP_CLIENT_TEST_KEY}}
+parameters:
+  title:
+    type: string
+"#,
+                server.uri()
+            ),
+        );
+        let function = HttpClientFunction::from_spec_file(&spec).unwrap();
+        let value = function.invoke(&json!({ "title": "Bug" })).await.unwrap();
+        assert_eq!(value, json!({ "status": 201, "body": "created" }));
+    }
+}
@@ -1 +1,7 @@
 pub mod math;
+pub mod web_search;
+pub mod code_exec;
+pub mod http_client;
+#[cfg(feature = "sql-plugin")]
+pub mod database;
+pub mod vector_store;
@@ -0,0 +1,216 @@
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::LLMError;
+use crate::functions::{json_schema_for, DynKernelFunction, FunctionDefinition, FunctionParameter, KernelFunction};
+use crate::history::cosine_similarity;
+use crate::providers::EmbeddingProvider;
+
+const FUNCTION_NAME: &str = "vector_search";
+const DEFAULT_K: u32 = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub id: String,
+    pub content: String,
+    #[serde(default)]
+    pub metadata: Value,
+    /// Similarity to the query; higher is closer.
+    #[serde(default)]
+    pub score: f32,
+}
+
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// The `k` documents closest to `query_embedding`, best first.
+    async fn search(&self, query_embedding: &[f32], k: usize) -> Vec<Document>;
+}
+
+/// Brute-force cosine similarity over documents held in memory. Meant for tests and small
+/// corpora.
+#[derive(Debug, Default)]
+pub struct InMemoryVectorStore {
+    entries: RwLock<Vec<(Document, Vec<f32>)>>,
+}
+
+impl InMemoryVectorStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&self, id: impl Into<String>, content: impl Into<String>, metadata: Value, embedding: Vec<f32>) {
+        let document = Document {
+            id: id.into(),
+            content: content.into(),
+            metadata,
+            score: 0.0,
+        };
+        self.entries.write().expect("vector store lock").push((document, embedding));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().expect("vector store lock").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl VectorStore for InMemoryVectorStore {
+    async fn search(&self, query_embedding: &[f32], k: usize) -> Vec<Document> {
+        let entries = self.entries.read().expect("vector store lock");
+        let mut scored: Vec<Document> = entries
+            .iter()
+            .map(|(document, embedding)| Document {
+                score: cosine_similarity(query_embedding, embedding),
+                ..document.clone()
+            })
+            .collect();
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored.truncate(k);
+        scored
+    }
+}
+
+type MetadataFilter = Arc<dyn Fn(&Value) -> bool + Send + Sync>;
+
+/// Kernel function that embeds a query and returns the closest documents from a
+/// [`VectorStore`].
+#[derive(Clone)]
+pub struct VectorSearchFunction {
+    store: Arc<dyn VectorStore>,
+    embedder: Arc<dyn EmbeddingProvider>,
+    filter: Option<MetadataFilter>,
+}
+
+impl VectorSearchFunction {
+    pub fn new(store: Arc<dyn VectorStore>, embedder: Arc<dyn EmbeddingProvider>) -> Self {
+        Self {
+            store,
+            embedder,
+            filter: None,
+        }
+    }
+
+    /// Drop results whose metadata fails `filter`. Filtering happens after the top `k` are
+    /// retrieved, so fewer than `k` documents may be returned.
+    pub fn with_metadata_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&Value) -> bool + Send + Sync + 'static,
+    {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    pub async fn search(&self, query: &str, k: usize) -> Result<Vec<Document>, LLMError> {
+        let embedding = self
+            .embedder
+            .embed(vec![query.to_string()])
+            .await?
+            .pop()
+            .ok_or(LLMError::InvalidResponse("embedder returned no vector for the query"))?;
+
+        let mut documents = self.store.search(&embedding, k).await;
+        if let Some(filter) = &self.filter {
+            documents.retain(|document| filter(&document.metadata));
+        }
+        Ok(documents)
+    }
+}
+
+#[async_trait]
+impl KernelFunction for VectorSearchFunction {
+    fn definition(&self) -> FunctionDefinition {
+        let mut function = FunctionDefinition::new(FUNCTION_NAME)
+            .with_description("Find the documents most relevant to a query.");
+        function.add_parameter(
+            FunctionParameter::new("query", json_schema_for::<String>()).with_description("What to search for."),
+        );
+        function.add_parameter(
+            FunctionParameter::new("k", json_schema_for::<u32>())
+                .with_description("How many documents to return (default 5).")
+                .optional(),
+        );
+        function
+    }
+
+    async fn invoke(&self, arguments: &Value) -> Result<Value, LLMError> {
+        let query = arguments
+            .get("query")
+            .and_then(Value::as_str)
+            .ok_or_else(|| LLMError::InvalidFunctionArguments("query must be a string".into()))?;
+        let k = match arguments.get("k") {
+            None | Some(Value::Null) => DEFAULT_K as usize,
+            Some(value) => value
+                .as_u64()
+                .ok_or_else(|| LLMError::InvalidFunctionArguments("k must be a positive integer".into()))?
+                as usize,
+        };
+
+        let documents = self.search(query, k).await?;
+        Ok(serde_json::to_value(documents)?)
+    }
+}
+
+pub fn vector_search_kernel(store: Arc<dyn VectorStore>, embedder: Arc<dyn EmbeddingProvider>) -> DynKernelFunction {
+    Arc::new(VectorSearchFunction::new(store, embedder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Embeds a query by the topic words it mentions.
+    struct TopicEmbedder;
+
+    #[async_trait]
+    impl EmbeddingProvider for TopicEmbedder {
+        async fn embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+            Ok(inputs
+                .iter()
+                .map(|input| {
+                    ["rust", "cooking", "travel"]
+                        .iter()
+                        .map(|topic| if input.contains(topic) { 1.0 } else { 0.0 })
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    fn store() -> Arc<InMemoryVectorStore> {
+        let store = InMemoryVectorStore::new();
+        store.add("borrowck", "The borrow checker", json!({ "lang": "en" }), vec![0.9, 0.1, 0.0]);
+        store.add("pasta", "Cooking pasta", json!({ "lang": "it" }), vec![0.1, 0.9, 0.1]);
+        store.add("lisbon", "A week in Lisbon", json!({ "lang": "pt" }), vec![0.0, 0.2, 0.9]);
+        Arc::new(store)
+    }
+
+    #[tokio::test]
+    async fn top_result_is_the_closest_document() {
+        let function = VectorSearchFunction::new(store(), Arc::new(TopicEmbedder));
+        let value = function
+            .invoke(&json!({ "query": "tips for cooking dinner", "k": 1 }))
+            .await
+            .unwrap();
+        let documents: Vec<Document> = serde_json::from_value(value).unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].id, "pasta");
+        assert!(documents[0].score > 0.9);
+    }
+
+    #[tokio::test]
+    async fn metadata_filter_drops_results() {
+        let function = VectorSearchFunction::new(store(), Arc::new(TopicEmbedder))
+            .with_metadata_filter(|metadata| metadata["lang"] != "it");
+        let documents = function.search("cooking", 2).await.unwrap();
+        let ids: Vec<&str> = documents.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["lisbon"]);
+    }
+}
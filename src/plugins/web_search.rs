@@ -0,0 +1,328 @@
+use std::env;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::LLMError;
+use crate::functions::{json_schema_for, DynKernelFunction, FunctionDefinition, FunctionParameter, KernelFunction};
+
+const FUNCTION_NAME: &str = "web_search";
+const DEFAULT_NUM_RESULTS: u32 = 5;
+const BRAVE_BASE_URL: &str = "https://api.search.brave.com";
+const SERPAPI_BASE_URL: &str = "https://serpapi.com";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// The search API a [`WebSearchFunction`] queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchBackend {
+    Brave,
+    SerpApi,
+}
+
+impl SearchBackend {
+    fn default_base_url(self) -> &'static str {
+        match self {
+            SearchBackend::Brave => BRAVE_BASE_URL,
+            SearchBackend::SerpApi => SERPAPI_BASE_URL,
+        }
+    }
+}
+
+/// Kernel function that searches the web through the Brave Search or SerpAPI REST API.
+#[derive(Clone)]
+pub struct WebSearchFunction {
+    client: reqwest::Client,
+    api_key: String,
+    backend: SearchBackend,
+    base_url: String,
+}
+
+impl std::fmt::Debug for WebSearchFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebSearchFunction")
+            .field("api_key", &"[redacted]")
+            .field("backend", &self.backend)
+            .field("base_url", &self.base_url)
+            .finish()
+    }
+}
+
+impl WebSearchFunction {
+    /// Searches with Brave; use [`WebSearchFunction::with_backend`] for SerpAPI.
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_key,
+            backend: SearchBackend::Brave,
+            base_url: BRAVE_BASE_URL.to_string(),
+        }
+    }
+
+    /// Reads `BRAVE_API_KEY`, falling back to `SERP_API_KEY` for SerpAPI.
+    pub fn from_env() -> Result<Self, LLMError> {
+        if let Ok(key) = env::var("BRAVE_API_KEY") {
+            return Ok(Self::new(key));
+        }
+        let key = env::var("SERP_API_KEY").map_err(|_| LLMError::MissingApiKey("BRAVE_API_KEY or SERP_API_KEY"))?;
+        Ok(Self::new(key).with_backend(SearchBackend::SerpApi))
+    }
+
+    pub fn with_backend(mut self, backend: SearchBackend) -> Self {
+        self.backend = backend;
+        self.base_url = backend.default_base_url().to_string();
+        self
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn backend(&self) -> SearchBackend {
+        self.backend
+    }
+
+    pub async fn search(&self, query: &str, num_results: u32) -> Result<Vec<SearchResult>, LLMError> {
+        let request = match self.backend {
+            SearchBackend::Brave => self
+                .client
+                .get(format!("{}/res/v1/web/search", self.base_url))
+                .header("X-Subscription-Token", &self.api_key)
+                .query(&[("q", query.to_string()), ("count", num_results.to_string())]),
+            SearchBackend::SerpApi => self.client.get(format!("{}/search.json", self.base_url)).query(&[
+                ("engine", "google".to_string()),
+                ("q", query.to_string()),
+                ("num", num_results.to_string()),
+                ("api_key", self.api_key.clone()),
+            ]),
+        };
+
+        let response = request.send().await.map_err(execution_error)?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(execution_error(format!("search request failed with {status}: {body}")));
+        }
+        let body: Value = response.json().await.map_err(execution_error)?;
+
+        let mut results = match self.backend {
+            SearchBackend::Brave => parse_brave(body)?,
+            SearchBackend::SerpApi => parse_serpapi(body)?,
+        };
+        results.truncate(num_results as usize);
+        Ok(results)
+    }
+}
+
+#[derive(Deserialize)]
+struct BraveResponse {
+    #[serde(default)]
+    web: Option<BraveWeb>,
+}
+
+#[derive(Deserialize)]
+struct BraveWeb {
+    #[serde(default)]
+    results: Vec<BraveResult>,
+}
+
+#[derive(Deserialize)]
+struct BraveResult {
+    title: String,
+    url: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct SerpApiResponse {
+    #[serde(default)]
+    organic_results: Vec<SerpApiResult>,
+}
+
+#[derive(Deserialize)]
+struct SerpApiResult {
+    title: String,
+    link: String,
+    #[serde(default)]
+    snippet: String,
+}
+
+fn parse_brave(body: Value) -> Result<Vec<SearchResult>, LLMError> {
+    let response: BraveResponse = serde_json::from_value(body).map_err(execution_error)?;
+    Ok(response
+        .web
+        .map(|web| web.results)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| SearchResult {
+            title: r.title,
+            url: r.url,
+            snippet: r.description,
+        })
+        .collect())
+}
+
+fn parse_serpapi(body: Value) -> Result<Vec<SearchResult>, LLMError> {
+    let response: SerpApiResponse = serde_json::from_value(body).map_err(execution_error)?;
+    Ok(response
+        .organic_results
+        .into_iter()
+        .map(|r| SearchResult {
+            title: r.title,
+            url: r.link,
+            snippet: r.snippet,
+        })
+        .collect())
+}
+
+fn execution_error(error: impl ToString) -> LLMError {
+    LLMError::FunctionExecution {
+        function: FUNCTION_NAME.to_string(),
+        message: error.to_string(),
+    }
+}
+
+#[async_trait]
+impl KernelFunction for WebSearchFunction {
+    fn definition(&self) -> FunctionDefinition {
+        let mut function = FunctionDefinition::new(FUNCTION_NAME)
+            .with_description("Search the web and return the top results with their title, url and snippet.");
+        function.add_parameter(
+            FunctionParameter::new("query", json_schema_for::<String>()).with_description("The search query."),
+        );
+        function.add_parameter(
+            FunctionParameter::new("num_results", json_schema_for::<u32>())
+                .with_description("How many results to return (default 5).")
+                .optional(),
+        );
+        function
+    }
+
+    async fn invoke(&self, arguments: &Value) -> Result<Value, LLMError> {
+        let query = arguments
+            .get("query")
+            .and_then(Value::as_str)
+            .ok_or_else(|| LLMError::InvalidFunctionArguments("query must be a string".into()))?;
+        let num_results = match arguments.get("num_results") {
+            None | Some(Value::Null) => DEFAULT_NUM_RESULTS,
+            Some(value) => value
+                .as_u64()
+                .and_then(|n| u32::try_from(n).ok())
+                .ok_or_else(|| LLMError::InvalidFunctionArguments("num_results must be a positive integer".into()))?,
+        };
+
+        let results = self.search(query, num_results).await?;
+        Ok(serde_json::to_value(results)?)
+    }
+}
+
+pub fn web_search_kernel(api_key: String) -> DynKernelFunction {
+    Arc::new(WebSearchFunction::new(api_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn debug_output_hides_the_api_key() {
+        let function = WebSearchFunction::new("brave-secret".to_string());
+        let debug = format!("{function:?}");
+        assert!(!debug.contains("brave-secret"));
+        assert!(debug.contains("[redacted]"));
+    }
+
+    #[tokio::test]
+    async fn brave_results_are_deserialized() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/res/v1/web/search"))
+            .and(query_param("q", "rust async"))
+            .and(query_param("count", "2"))
+            .and(header("X-Subscription-Token", "key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "web": { "results": [
+                    { "title": "Async Book", "url": "https://rust-lang.github.io/async-book", "description": "Async in Rust" },
+                    { "title": "Tokio", "url": "https://tokio.rs", "description": "Runtime" }
+                ]}
+            })))
+            .mount(&server)
+            .await;
+
+        let function = WebSearchFunction::new("key".to_string()).with_base_url(server.uri());
+        let value = function
+            .invoke(&json!({ "query": "rust async", "num_results": 2 }))
+            .await
+            .unwrap();
+        let results: Vec<SearchResult> = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            results,
+            vec![
+                SearchResult {
+                    title: "Async Book".to_string(),
+                    url: "https://rust-lang.github.io/async-book".to_string(),
+                    snippet: "Async in Rust".to_string(),
+                },
+                SearchResult {
+                    title: "Tokio".to_string(),
+                    url: "https://tokio.rs".to_string(),
+                    snippet: "Runtime".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn serpapi_defaults_to_five_results() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search.json"))
+            .and(query_param("num", "5"))
+            .and(query_param("api_key", "key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "organic_results": [
+                    { "title": "Result", "link": "https://example.com", "snippet": "Example" }
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let function = WebSearchFunction::new("key".to_string())
+            .with_backend(SearchBackend::SerpApi)
+            .with_base_url(server.uri());
+        let value = function.invoke(&json!({ "query": "example" })).await.unwrap();
+        assert_eq!(
+            value,
+            json!([{ "title": "Result", "url": "https://example.com", "snippet": "Example" }])
+        );
+    }
+
+    #[tokio::test]
+    async fn http_failures_are_function_execution_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).set_body_string("rate limited"))
+            .mount(&server)
+            .await;
+
+        let function = WebSearchFunction::new("key".to_string()).with_base_url(server.uri());
+        let error = function.invoke(&json!({ "query": "anything" })).await.unwrap_err();
+        assert!(matches!(
+            error,
+            LLMError::FunctionExecution { function, message }
+                if function == "web_search" && message.contains("429") && message.contains("rate limited")
+        ));
+    }
+}
@@ -1,4 +1,8 @@
-use std::{env, time::Duration};
+use std::{
+    env,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_stream::try_stream;
 use async_trait::async_trait;
@@ -6,10 +10,11 @@ use futures_util::StreamExt;
 use reqwest::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tokio::sync::Mutex;
 
 use crate::{
     error::LLMError,
-    providers::LLMProvider,
+    providers::{openai::openai_token_count, LLMProvider},
     functions::{FunctionCall, Tool, ToolCall, ToolChoice},
     types::{
         ChatMessage, CompletionRequest, CompletionResponse, CompletionStream, MessageRole,
@@ -19,6 +24,11 @@ use crate::{
 };
 
 const DEFAULT_API_VERSION: &str = "2024-08-01-preview";
+const COGNITIVE_SERVICES_RESOURCE: &str = "https://cognitiveservices.azure.com";
+const DEFAULT_AUTHORITY_HOST: &str = "https://login.microsoftonline.com";
+const DEFAULT_IMDS_ENDPOINT: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+/// Tokens are refreshed once they are this close to expiring.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
 
 #[derive(Debug, Clone)]
 pub struct AzureOpenAIConfig {
@@ -49,10 +59,194 @@ impl AzureOpenAIConfig {
     }
 }
 
+/// Azure AD (Entra ID) credential used instead of an API key.
+#[derive(Clone)]
+pub enum AzureAdCredential {
+    /// OAuth2 client credentials flow for a service principal.
+    ClientSecret {
+        tenant_id: String,
+        client_id: String,
+        client_secret: String,
+        authority_host: String,
+    },
+    /// Managed identity via the instance metadata service (Azure VMs, AKS workload identity
+    /// sidecars). `client_id` selects a user-assigned identity.
+    ManagedIdentity {
+        client_id: Option<String>,
+        endpoint: String,
+    },
+}
+
+impl std::fmt::Debug for AzureAdCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ClientSecret { tenant_id, client_id, authority_host, .. } => f
+                .debug_struct("ClientSecret")
+                .field("tenant_id", tenant_id)
+                .field("client_id", client_id)
+                .field("client_secret", &"[redacted]")
+                .field("authority_host", authority_host)
+                .finish(),
+            Self::ManagedIdentity { client_id, endpoint } => f
+                .debug_struct("ManagedIdentity")
+                .field("client_id", client_id)
+                .field("endpoint", endpoint)
+                .finish(),
+        }
+    }
+}
+
+impl AzureAdCredential {
+    pub fn client_secret(
+        tenant_id: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self::ClientSecret {
+            tenant_id: tenant_id.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            authority_host: DEFAULT_AUTHORITY_HOST.to_string(),
+        }
+    }
+
+    pub fn managed_identity() -> Self {
+        Self::ManagedIdentity {
+            client_id: None,
+            endpoint: DEFAULT_IMDS_ENDPOINT.to_string(),
+        }
+    }
+
+    /// Use a user-assigned managed identity. No effect on client secret credentials.
+    pub fn with_client_id(mut self, id: impl Into<String>) -> Self {
+        if let Self::ManagedIdentity { client_id, .. } = &mut self {
+            *client_id = Some(id.into());
+        }
+        self
+    }
+
+    /// Override the authority host (sovereign clouds) or the metadata endpoint.
+    pub fn with_endpoint(mut self, url: impl Into<String>) -> Self {
+        match &mut self {
+            Self::ClientSecret { authority_host, .. } => *authority_host = url.into(),
+            Self::ManagedIdentity { endpoint, .. } => *endpoint = url.into(),
+        }
+        self
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AzureTokenResponse {
+    access_token: String,
+    #[serde(deserialize_with = "deserialize_expires_in")]
+    expires_in: u64,
+}
+
+/// The token endpoint returns `expires_in` as a number, the metadata service as a string.
+fn deserialize_expires_in<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ExpiresIn {
+        Number(u64),
+        Text(String),
+    }
+
+    match ExpiresIn::deserialize(deserializer)? {
+        ExpiresIn::Number(value) => Ok(value),
+        ExpiresIn::Text(text) => text.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Fetches Azure AD access tokens and caches them until shortly before they expire.
+struct AzureAdTokenSource {
+    credential: AzureAdCredential,
+    cached: Arc<Mutex<Option<(String, Instant)>>>,
+}
+
+impl std::fmt::Debug for AzureAdTokenSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AzureAdTokenSource")
+            .field("credential", &self.credential)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AzureAdTokenSource {
+    fn new(credential: AzureAdCredential) -> Self {
+        Self {
+            credential,
+            cached: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    async fn token(&self, client: &Client) -> Result<String, LLMError> {
+        let mut cached = self.cached.lock().await;
+        if let Some((token, expires_at)) = cached.as_ref() {
+            if Instant::now() + TOKEN_REFRESH_MARGIN < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+
+        let response = self.fetch(client).await?;
+        let expires_at = Instant::now() + Duration::from_secs(response.expires_in);
+        *cached = Some((response.access_token.clone(), expires_at));
+        Ok(response.access_token)
+    }
+
+    async fn fetch(&self, client: &Client) -> Result<AzureTokenResponse, LLMError> {
+        let request = match &self.credential {
+            AzureAdCredential::ClientSecret {
+                tenant_id,
+                client_id,
+                client_secret,
+                authority_host,
+            } => {
+                let scope = format!("{COGNITIVE_SERVICES_RESOURCE}/.default");
+                client
+                    .post(format!(
+                        "{}/{}/oauth2/v2.0/token",
+                        authority_host.trim_end_matches('/'),
+                        tenant_id
+                    ))
+                    .form(&[
+                        ("grant_type", "client_credentials"),
+                        ("client_id", client_id.as_str()),
+                        ("client_secret", client_secret.as_str()),
+                        ("scope", scope.as_str()),
+                    ])
+            }
+            AzureAdCredential::ManagedIdentity { client_id, endpoint } => {
+                let mut query = vec![
+                    ("api-version", "2018-02-01"),
+                    ("resource", COGNITIVE_SERVICES_RESOURCE),
+                ];
+                if let Some(client_id) = client_id {
+                    query.push(("client_id", client_id.as_str()));
+                }
+                client.get(endpoint).header("Metadata", "true").query(&query)
+            }
+        };
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(LLMError::Provider(format!(
+                "azure ad token request failed with status {status}: {text}"
+            )));
+        }
+        Ok(response.json().await?)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AzureOpenAI {
     client: Client,
     config: AzureOpenAIConfig,
+    token_source: Option<Arc<AzureAdTokenSource>>,
 }
 
 impl AzureOpenAI {
@@ -60,9 +254,18 @@ impl AzureOpenAI {
         Self::from_config(AzureOpenAIConfig::new(api_key, endpoint))
     }
 
+    /// Build from `AZURE_OPENAI_*` variables. With `AZURE_USE_AD_AUTH=true` no API key is
+    /// needed: `AZURE_TENANT_ID`, `AZURE_CLIENT_ID` and `AZURE_CLIENT_SECRET` select the client
+    /// secret flow, otherwise the managed identity (optionally `AZURE_CLIENT_ID`) is used.
     pub fn from_env() -> Result<Self, LLMError> {
-        let api_key = env::var("AZURE_OPENAI_KEY")
-            .map_err(|_| LLMError::MissingApiKey("AZURE_OPENAI_KEY"))?;
+        let use_ad_auth = env::var("AZURE_USE_AD_AUTH")
+            .map(|value| value.eq_ignore_ascii_case("true") || value == "1")
+            .unwrap_or(false);
+        let api_key = match env::var("AZURE_OPENAI_KEY") {
+            Ok(key) => key,
+            Err(_) if use_ad_auth => String::new(),
+            Err(_) => return Err(LLMError::MissingApiKey("AZURE_OPENAI_KEY")),
+        };
         let endpoint = env::var("AZURE_OPENAI_ENDPOINT")
             .map_err(|_| LLMError::MissingApiKey("AZURE_OPENAI_ENDPOINT"))?;
 
@@ -76,7 +279,24 @@ impl AzureOpenAI {
             }
         }
 
-        Self::from_config(config)
+        let provider = Self::from_config(config)?;
+        if !use_ad_auth {
+            return Ok(provider);
+        }
+
+        let client_id = env::var("AZURE_CLIENT_ID").ok();
+        match (env::var("AZURE_TENANT_ID"), &client_id, env::var("AZURE_CLIENT_SECRET")) {
+            (Ok(tenant_id), Some(client_id), Ok(client_secret)) => {
+                Ok(provider.with_azure_ad_auth(tenant_id, client_id.clone(), client_secret))
+            }
+            _ => {
+                let mut credential = AzureAdCredential::managed_identity();
+                if let Some(client_id) = client_id {
+                    credential = credential.with_client_id(client_id);
+                }
+                Ok(provider.with_azure_ad_credential(credential))
+            }
+        }
     }
 
     pub fn from_config(config: AzureOpenAIConfig) -> Result<Self, LLMError> {
@@ -84,7 +304,37 @@ impl AzureOpenAI {
             .timeout(config.request_timeout)
             .build()?;
 
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config,
+            token_source: None,
+        })
+    }
+
+    /// Authenticate with an Azure AD service principal instead of the API key.
+    pub fn with_azure_ad_auth(
+        self,
+        tenant_id: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        self.with_azure_ad_credential(AzureAdCredential::client_secret(
+            tenant_id,
+            client_id,
+            client_secret,
+        ))
+    }
+
+    /// Authenticate with the managed identity of the VM or pod.
+    pub fn with_managed_identity(self) -> Self {
+        self.with_azure_ad_credential(AzureAdCredential::managed_identity())
+    }
+
+    /// Authenticate with bearer tokens from `credential`; tokens are cached and refreshed
+    /// five minutes before they expire.
+    pub fn with_azure_ad_credential(mut self, credential: AzureAdCredential) -> Self {
+        self.token_source = Some(Arc::new(AzureAdTokenSource::new(credential)));
+        self
     }
 
     fn endpoint(&self, deployment: &str) -> String {
@@ -105,21 +355,26 @@ impl AzureOpenAI {
         )
     }
 
-    fn with_default_headers(&self, builder: RequestBuilder) -> RequestBuilder {
-        builder.header("api-key", &self.config.api_key)
+    async fn with_default_headers(&self, builder: RequestBuilder) -> Result<RequestBuilder, LLMError> {
+        match &self.token_source {
+            Some(source) => Ok(builder.bearer_auth(source.token(&self.client).await?)),
+            None => Ok(builder.header("api-key", &self.config.api_key)),
+        }
     }
 }
 
 /// Convert a `ChatMessage` to a JSON `Value`, building a multimodal content
-/// array when the message carries image attachments.
+/// array when the message carries image, file or audio attachments.
 fn chat_message_to_json(msg: &ChatMessage) -> Value {
-    if msg.images.is_empty() {
+    let attachments = super::attachment_content_parts(msg, "azure_openai", false);
+    if msg.images.is_empty() && attachments.is_empty() {
         // Fast path: normal text-only message.
         return serde_json::to_value(msg).unwrap_or_default();
     }
 
-    // Build multimodal content array: text block + image blocks.
-    let mut content_parts: Vec<Value> = Vec::with_capacity(1 + msg.images.len());
+    // Build multimodal content array: text block + image and attachment blocks.
+    let mut content_parts: Vec<Value> =
+        Vec::with_capacity(1 + msg.images.len() + attachments.len());
     if let Some(text) = &msg.content {
         content_parts.push(serde_json::json!({
             "type": "text",
@@ -132,6 +387,7 @@ fn chat_message_to_json(msg: &ChatMessage) -> Value {
             "image_url": { "url": image_url },
         }));
     }
+    content_parts.extend(attachments);
 
     let mut obj = serde_json::json!({
         "role": msg.role,
@@ -187,6 +443,7 @@ impl AzureChatRequestBody {
             tools,
             tool_choice,
             reasoning_effort,
+            correlation_id: _,
         } = request;
 
         Self {
@@ -264,6 +521,7 @@ struct ToolCallAccumulator {
     id: Option<String>,
     name: Option<String>,
     arguments: String,
+    emitted: bool,
 }
 
 impl ToolCallAccumulator {
@@ -287,6 +545,17 @@ impl ToolCallAccumulator {
         }
     }
 
+    /// Returns the finished call the first time its arguments form a balanced JSON object.
+    fn take_completed(&mut self) -> Option<ToolCall> {
+        if self.emitted || self.name.is_none() || !super::is_balanced_json_object(&self.arguments) {
+            return None;
+        }
+
+        let call = self.clone().build().ok()?;
+        self.emitted = true;
+        Some(call)
+    }
+
     fn build(self) -> Result<ToolCall, LLMError> {
         let name = self
             .name
@@ -346,15 +615,18 @@ struct AzureEmbedding {
 
 async fn parse_azure_error(response: reqwest::Response) -> LLMError {
     let status = response.status();
-    match response.text().await {
-        Ok(text) => {
-            if let Ok(envelope) = serde_json::from_str::<AzureErrorEnvelope>(&text) {
-                LLMError::Provider(envelope.error.message)
-            } else {
-                LLMError::Provider(format!("unexpected status {status}: {text}"))
-            }
-        }
-        Err(e) => LLMError::Provider(format!("unexpected status {status}: {e}")),
+    let retry_after = super::retry_after(response.headers());
+    let message = match response.text().await {
+        Ok(text) => match serde_json::from_str::<AzureErrorEnvelope>(&text) {
+            Ok(envelope) => envelope.error.message,
+            Err(_) => format!("unexpected status {status}: {text}"),
+        },
+        Err(e) => format!("unexpected status {status}: {e}"),
+    };
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        LLMError::RateLimited { message, retry_after }
+    } else {
+        LLMError::Provider(message)
     }
 }
 
@@ -364,10 +636,14 @@ impl LLMProvider for AzureOpenAI {
         &self,
         request: CompletionRequest,
     ) -> Result<CompletionResponse, LLMError> {
+        super::ensure_openai_audio_formats(&request.messages)?;
+        let correlation_id = request.correlation_id;
         let body = AzureChatRequestBody::from_request(request, None);
 
         let response = self
             .with_default_headers(self.client.post(self.endpoint(&body.model)))
+            .await?
+            .headers(super::request_id_headers(correlation_id))
             .json(&body)
             .send()
             .await?;
@@ -408,10 +684,14 @@ impl LLMProvider for AzureOpenAI {
         &self,
         request: CompletionRequest,
     ) -> Result<CompletionStream, LLMError> {
+        super::ensure_openai_audio_formats(&request.messages)?;
+        let correlation_id = request.correlation_id;
         let body = AzureChatRequestBody::from_request(request, Some(true));
 
         let response = self
             .with_default_headers(self.client.post(self.endpoint(&body.model)))
+            .await?
+            .headers(super::request_id_headers(correlation_id))
             .header("Accept", "text/event-stream")
             .header("Cache-Control", "no-cache")
             .json(&body)
@@ -481,6 +761,21 @@ impl LLMProvider for AzureOpenAI {
                             }
                         }
 
+                        // Calls whose arguments never balanced (or arrived as text) are reported here.
+                        let pending_calls: Vec<ToolCall> = if tool_call_accumulators.is_empty() {
+                            resolved_tool_calls.clone()
+                        } else {
+                            tool_call_accumulators
+                                .iter()
+                                .zip(&resolved_tool_calls)
+                                .filter(|(accumulator, _)| !accumulator.emitted)
+                                .map(|(_, call)| call.clone())
+                                .collect()
+                        };
+                        for call in pending_calls {
+                            yield StreamEvent::ToolCallComplete { call };
+                        }
+
                         let completion_message = ChatMessage {
                             role: MessageRole::Assistant,
                             content,
@@ -488,7 +783,10 @@ impl LLMProvider for AzureOpenAI {
                             tool_call_id: None,
                             tool_calls: resolved_tool_calls.clone(),
                             images: Vec::new(),
+                            attachments: Vec::new(),
                             thinking: None,
+                            created_at: None,
+                            metadata: None,
                         };
 
                         let completion = CompletionResponse {
@@ -550,6 +848,9 @@ impl LLMProvider for AzureOpenAI {
 
                                 if let Some(accumulator) = tool_call_accumulators.get_mut(index) {
                                     accumulator.update(&tool_delta);
+                                    if let Some(call) = accumulator.take_completed() {
+                                        yield StreamEvent::ToolCallComplete { call };
+                                    }
                                 }
                             }
                         }
@@ -582,6 +883,7 @@ impl LLMProvider for AzureOpenAI {
 
         let builder = self
             .with_default_headers(self.client.post(self.embeddings_endpoint(&request.model)))
+            .await?
             .json(&body);
 
         let response = builder.send().await?;
@@ -610,7 +912,12 @@ impl LLMProvider for AzureOpenAI {
     }
 
     fn capabilities(&self) -> ProviderCapabilities {
-        ProviderCapabilities::new(true, true, true, true)
+        ProviderCapabilities::new(true, true, true, true).with_structured_output(true)
+    }
+
+    /// Counted with the tokenizer of the model the deployment is named after, if known.
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        Ok(openai_token_count(request))
     }
 
     fn name(&self) -> &'static str {
@@ -619,3 +926,139 @@ impl LLMProvider for AzureOpenAI {
 }
 
 use super::{extract_data_payload, extract_sse_event};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn mount_token(server: &MockServer, token: &str, expires_in: u64) {
+        Mock::given(method("POST"))
+            .and(path("/tenant/oauth2/v2.0/token"))
+            .and(body_string_contains("grant_type=client_credentials"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "token_type": "Bearer",
+                "access_token": token,
+                "expires_in": expires_in,
+            })))
+            .mount(server)
+            .await;
+    }
+
+    #[test]
+    fn debug_output_hides_the_client_secret() {
+        let credential = AzureAdCredential::client_secret("tenant", "client", "hunter2");
+        let debug = format!("{credential:?}");
+        assert!(!debug.contains("hunter2"));
+        assert!(debug.contains("client") && debug.contains("[redacted]"));
+    }
+
+    fn provider(server: &MockServer) -> AzureOpenAI {
+        AzureOpenAI::new("unused-key", server.uri())
+            .unwrap()
+            .with_azure_ad_credential(
+                AzureAdCredential::client_secret("tenant", "client", "secret")
+                    .with_endpoint(server.uri()),
+            )
+    }
+
+    #[tokio::test]
+    async fn bearer_token_is_cached_until_close_to_expiry() {
+        let server = MockServer::start().await;
+        mount_token(&server, "long-lived", 3600).await;
+        let provider = provider(&server);
+        let source = provider.token_source.clone().unwrap();
+
+        assert_eq!(source.token(&provider.client).await.unwrap(), "long-lived");
+        assert_eq!(source.token(&provider.client).await.unwrap(), "long-lived");
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        // A token inside the refresh margin is fetched again on every use.
+        server.reset().await;
+        mount_token(&server, "short-lived", 60).await;
+        *source.cached.lock().await = Some(("stale".to_string(), Instant::now()));
+        assert_eq!(source.token(&provider.client).await.unwrap(), "short-lived");
+        assert_eq!(source.token(&provider.client).await.unwrap(), "short-lived");
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn completions_send_bearer_token() {
+        let server = MockServer::start().await;
+        mount_token(&server, "ad-token", 3600).await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/gpt-4o/chat/completions"))
+            .and(header("authorization", "Bearer ad-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": "hi" },
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let response = provider(&server)
+            .complete(CompletionRequest::new("gpt-4o", vec![ChatMessage::user("hello")]))
+            .await
+            .unwrap();
+        assert_eq!(response.message.text(), Some("hi"));
+    }
+
+    #[tokio::test]
+    async fn stream_emits_completed_tool_calls() {
+        use futures_util::StreamExt;
+
+        let server = MockServer::start().await;
+        mount_token(&server, "ad-token", 3600).await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/gpt-4o/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(include_str!(
+                        "../../tests/fixtures/openai_stream_tool_calls.sse"
+                    )),
+            )
+            .mount(&server)
+            .await;
+
+        let mut stream = provider(&server)
+            .stream_completion(CompletionRequest::new(
+                "gpt-4o",
+                vec![ChatMessage::user("weather and time in Berlin?")],
+            ))
+            .await
+            .unwrap();
+
+        let mut completed_calls = Vec::new();
+        while let Some(event) = stream.next().await {
+            if let StreamEvent::ToolCallComplete { call } = event.unwrap() {
+                completed_calls.push(call.function.name);
+            }
+        }
+        assert_eq!(completed_calls, vec!["get_weather", "get_time"]);
+    }
+
+    #[tokio::test]
+    async fn managed_identity_queries_metadata_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/metadata/identity/oauth2/token"))
+            .and(header("metadata", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "mi-token",
+                "expires_in": "3599",
+            })))
+            .mount(&server)
+            .await;
+
+        let source = AzureAdTokenSource::new(
+            AzureAdCredential::managed_identity()
+                .with_endpoint(format!("{}/metadata/identity/oauth2/token", server.uri())),
+        );
+        assert_eq!(source.token(&Client::new()).await.unwrap(), "mi-token");
+    }
+}
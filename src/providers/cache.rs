@@ -0,0 +1,270 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::{
+    providers::LLMProvider,
+    types::{
+        CompletionRequest, CompletionResponse, CompletionStream, EmbeddingRequest,
+        EmbeddingResponse, ImageUploadRequest, ImageUploadResponse, ModelInfo,
+        ProviderCapabilities,
+    },
+    LLMError,
+};
+
+struct CacheEntry {
+    response: CompletionResponse,
+    stored_at: Instant,
+    /// Value of the provider's use counter when the entry was last read or written.
+    last_used: AtomicU64,
+}
+
+/// Answers completions it has seen before from memory, e.g. when an eval suite is run
+/// again against the same cases. Requests are keyed by model, messages, tools and response
+/// format (see [`cache_key`]), so only sampling settings do not tell requests apart.
+/// Failed completions and streams are never cached.
+pub struct CachedProvider<P: LLMProvider> {
+    inner: P,
+    entries: RwLock<HashMap<String, CacheEntry>>,
+    ttl: Option<Duration>,
+    max_entries: Option<usize>,
+    uses: AtomicU64,
+}
+
+impl<P: LLMProvider> CachedProvider<P> {
+    /// Cache without expiry or size limit.
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            entries: RwLock::new(HashMap::new()),
+            ttl: None,
+            max_entries: None,
+            uses: AtomicU64::new(0),
+        }
+    }
+
+    /// Treat entries older than `ttl` as misses.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Keep at most `max_entries` responses, evicting the least recently used first.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries.max(1));
+        self
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries.write().unwrap().clear();
+    }
+
+    fn is_fresh(&self, entry: &CacheEntry) -> bool {
+        self.ttl.is_none_or(|ttl| entry.stored_at.elapsed() < ttl)
+    }
+
+    fn cached(&self, key: &str) -> Option<CompletionResponse> {
+        let entries = self.entries.read().unwrap();
+        let entry = entries.get(key).filter(|entry| self.is_fresh(entry))?;
+        entry
+            .last_used
+            .store(self.uses.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+        Some(entry.response.clone())
+    }
+
+    fn store(&self, key: String, response: &CompletionResponse) {
+        let mut entries = self.entries.write().unwrap();
+        entries.retain(|_, entry| self.is_fresh(entry));
+        entries.insert(
+            key,
+            CacheEntry {
+                response: response.clone(),
+                stored_at: Instant::now(),
+                last_used: AtomicU64::new(self.uses.fetch_add(1, Ordering::Relaxed)),
+            },
+        );
+
+        let Some(max_entries) = self.max_entries else {
+            return;
+        };
+        while entries.len() > max_entries {
+            let least_recent = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+                .map(|(key, _)| key.clone());
+            match least_recent {
+                Some(key) => entries.remove(&key),
+                None => break,
+            };
+        }
+    }
+}
+
+/// Cache key over the model, tools, response format and every message's role, text,
+/// images, attachments and tool calls. The key is the full JSON encoding rather than a
+/// hash, so different requests can never share an entry.
+pub fn cache_key(request: &CompletionRequest) -> String {
+    let messages: Vec<_> = request
+        .messages
+        .iter()
+        .map(|message| {
+            serde_json::json!([
+                message.role,
+                message.content,
+                message.images,
+                message.attachments,
+                message.tool_call_id,
+                message.tool_calls,
+            ])
+        })
+        .collect();
+    serde_json::json!([request.model, messages, request.tools, request.response_format]).to_string()
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for CachedProvider<P> {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let key = cache_key(&request);
+        if let Some(response) = self.cached(&key) {
+            return Ok(response);
+        }
+
+        let response = self.inner.complete(request).await?;
+        self.store(key, &response);
+        Ok(response)
+    }
+
+    async fn stream_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionStream, LLMError> {
+        self.inner.stream_completion(request).await
+    }
+
+    async fn upload_image(
+        &self,
+        request: ImageUploadRequest,
+    ) -> Result<ImageUploadResponse, LLMError> {
+        self.inner.upload_image(request).await
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, LLMError> {
+        self.inner.create_embeddings(request).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
+        self.inner.model_info(id).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LLMError> {
+        self.inner.list_models().await
+    }
+
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        self.inner.count_tokens(request).await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{functions::FunctionDefinition, providers::scripted::ScriptedProvider, types::ChatMessage};
+
+    fn cached() -> CachedProvider<ScriptedProvider> {
+        CachedProvider::new(ScriptedProvider::with_responses(&["first", "second", "third", "fourth"]))
+    }
+
+    fn request(text: &str) -> CompletionRequest {
+        CompletionRequest::new("m", vec![ChatMessage::system("Be brief."), ChatMessage::user(text)])
+    }
+
+    async fn reply(provider: &CachedProvider<ScriptedProvider>, text: &str) -> String {
+        let response = provider.complete(request(text)).await.unwrap();
+        response.message.text().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn repeated_requests_hit_the_cache() {
+        let provider = cached();
+        assert_eq!(reply(&provider, "hi").await, "first");
+        assert_eq!(reply(&provider, "hi").await, "first");
+        assert_eq!(reply(&provider, "bye").await, "second");
+        assert_eq!(provider.inner().calls(), 2);
+        assert_eq!(provider.len(), 2);
+
+        let other_model = CompletionRequest { model: "other".to_string(), ..request("hi") };
+        assert_ne!(cache_key(&other_model), cache_key(&request("hi")));
+    }
+
+    #[test]
+    fn key_covers_images_tools_and_response_format() {
+        let base = cache_key(&request("hi"));
+        let mut with_image = request("hi");
+        with_image.messages[1].images.push("data:image/png;base64,iVBO".to_string());
+        let with_tool = request("hi").with_tools([FunctionDefinition::new("lookup").to_tool()]);
+        let with_format = request("hi").with_response_format(serde_json::json!({ "type": "json_object" }));
+
+        let keys = [base, cache_key(&with_image), cache_key(&with_tool), cache_key(&with_format)];
+        for (index, key) in keys.iter().enumerate() {
+            assert!(keys[index + 1..].iter().all(|other| other != key), "{key}");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn entries_expire_after_ttl() {
+        let provider = cached().with_ttl(Duration::from_secs(60));
+        assert_eq!(reply(&provider, "hi").await, "first");
+
+        tokio::time::advance(Duration::from_secs(59)).await;
+        assert_eq!(reply(&provider, "hi").await, "first");
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(reply(&provider, "hi").await, "second");
+        assert_eq!(provider.inner().calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used_entry() {
+        let provider = cached().with_max_entries(2);
+        reply(&provider, "a").await;
+        reply(&provider, "b").await;
+        reply(&provider, "a").await;
+        reply(&provider, "c").await;
+        assert_eq!(provider.len(), 2);
+
+        assert_eq!(reply(&provider, "a").await, "first");
+        assert_eq!(reply(&provider, "b").await, "fourth");
+        assert_eq!(provider.inner().calls(), 4);
+    }
+}
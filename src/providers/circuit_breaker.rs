@@ -0,0 +1,257 @@
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::{
+    providers::LLMProvider,
+    types::{
+        CompletionRequest, CompletionResponse, CompletionStream, EmbeddingRequest,
+        EmbeddingResponse, ImageUploadRequest, ImageUploadResponse, ModelInfo,
+        ProviderCapabilities,
+    },
+    LLMError,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through to the provider.
+    Closed,
+    /// The provider is failing; calls are rejected without reaching it.
+    Open,
+    /// The reset timeout has passed; calls go through to probe for recovery.
+    HalfOpen,
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit.
+    pub failure_threshold: usize,
+    /// How long the circuit stays open before probing the provider again.
+    pub reset_timeout: Duration,
+    /// Consecutive successes while half-open needed to close the circuit.
+    pub success_threshold: usize,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            reset_timeout: Duration::from_secs(30),
+            success_threshold: 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    consecutive_failures: usize,
+    consecutive_successes: usize,
+    opened_at: Option<Instant>,
+    half_open: bool,
+}
+
+impl BreakerState {
+    fn current(&mut self, reset_timeout: Duration) -> CircuitState {
+        if let Some(opened_at) = self.opened_at {
+            if opened_at.elapsed() < reset_timeout {
+                return CircuitState::Open;
+            }
+            self.opened_at = None;
+            self.half_open = true;
+            self.consecutive_successes = 0;
+        }
+
+        if self.half_open {
+            CircuitState::HalfOpen
+        } else {
+            CircuitState::Closed
+        }
+    }
+}
+
+/// Fails fast with [`LLMError::CircuitOpen`] once the wrapped provider has failed
+/// `failure_threshold` times in a row, so a provider outage doesn't stall every agent call.
+/// Completions and opening a stream count towards the breaker; other calls pass through.
+pub struct CircuitBreakerProvider<P: LLMProvider> {
+    inner: P,
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+impl<P: LLMProvider> CircuitBreakerProvider<P> {
+    pub fn new(inner: P, config: CircuitBreakerConfig) -> Self {
+        Self {
+            inner,
+            config,
+            state: Mutex::new(BreakerState {
+                consecutive_failures: 0,
+                consecutive_successes: 0,
+                opened_at: None,
+                half_open: false,
+            }),
+        }
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state.lock().unwrap().current(self.config.reset_timeout)
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        if state.half_open {
+            state.consecutive_successes += 1;
+            if state.consecutive_successes >= self.config.success_threshold {
+                state.half_open = false;
+                state.consecutive_successes = 0;
+            }
+        }
+    }
+
+    fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        if state.half_open || state.consecutive_failures >= self.config.failure_threshold {
+            state.opened_at = Some(Instant::now());
+            state.half_open = false;
+            state.consecutive_successes = 0;
+        }
+    }
+
+    async fn guarded<T, Fut>(&self, call: Fut) -> Result<T, LLMError>
+    where
+        Fut: std::future::Future<Output = Result<T, LLMError>>,
+    {
+        if self.state() == CircuitState::Open {
+            return Err(LLMError::CircuitOpen);
+        }
+
+        let result = call.await;
+        match &result {
+            Ok(_) => self.record_success(),
+            Err(_) => self.record_failure(),
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for CircuitBreakerProvider<P> {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.guarded(self.inner.complete(request)).await
+    }
+
+    async fn stream_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionStream, LLMError> {
+        self.guarded(self.inner.stream_completion(request)).await
+    }
+
+    async fn upload_image(
+        &self,
+        request: ImageUploadRequest,
+    ) -> Result<ImageUploadResponse, LLMError> {
+        self.inner.upload_image(request).await
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, LLMError> {
+        self.inner.create_embeddings(request).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
+        self.inner.model_info(id).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LLMError> {
+        self.inner.list_models().await
+    }
+
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        self.inner.count_tokens(request).await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{providers::scripted::ScriptedProvider, types::ChatMessage};
+
+    fn breaker(failures: usize) -> CircuitBreakerProvider<ScriptedProvider> {
+        let mut provider = ScriptedProvider::with_responses(&["done"]);
+        provider.inject_transient_errors(failures, LLMError::Provider("unavailable".to_string()));
+        CircuitBreakerProvider::new(
+            provider,
+            CircuitBreakerConfig {
+                failure_threshold: 3,
+                reset_timeout: Duration::from_secs(10),
+                success_threshold: 1,
+            },
+        )
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest::new("m", vec![ChatMessage::user("hi")])
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opens_after_failures_and_recovers_after_timeout() {
+        let provider = breaker(3);
+        for _ in 0..3 {
+            assert!(matches!(provider.complete(request()).await, Err(LLMError::Provider(_))));
+        }
+        assert_eq!(provider.state(), CircuitState::Open);
+
+        let error = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(error, LLMError::CircuitOpen));
+        assert_eq!(provider.inner().calls(), 3);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(provider.state(), CircuitState::HalfOpen);
+
+        let response = provider.complete(request()).await.unwrap();
+        assert_eq!(response.message.text(), Some("done"));
+        assert_eq!(provider.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_probe_reopens_the_circuit() {
+        let provider = breaker(4);
+        for _ in 0..3 {
+            let _ = provider.complete(request()).await;
+        }
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(provider.state(), CircuitState::HalfOpen);
+
+        assert!(matches!(provider.complete(request()).await, Err(LLMError::Provider(_))));
+        assert_eq!(provider.state(), CircuitState::Open);
+        assert!(matches!(provider.complete(request()).await, Err(LLMError::CircuitOpen)));
+    }
+
+    #[tokio::test]
+    async fn success_resets_the_failure_count() {
+        let provider = breaker(2);
+        for _ in 0..2 {
+            let _ = provider.complete(request()).await;
+        }
+        provider.complete(request()).await.unwrap();
+        assert_eq!(provider.state(), CircuitState::Closed);
+        assert_eq!(provider.state.lock().unwrap().consecutive_failures, 0);
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::{
+    providers::LLMProvider,
+    types::{
+        CompletionRequest, CompletionResponse, CompletionStream, EmbeddingRequest,
+        EmbeddingResponse, ImageUploadRequest, ImageUploadResponse, MessageRole, ModelInfo,
+        ProviderCapabilities,
+    },
+    LLMError,
+};
+
+type ResponseCache = Arc<Mutex<HashMap<String, (CompletionResponse, Instant)>>>;
+
+/// Answers repeated completions from a short-lived cache so retry storms aren't billed twice.
+/// Requests are keyed by model, system prompt, last user message and every tool call and
+/// result after it; earlier turns are ignored. Failed completions are never cached.
+pub struct DeduplicatingProvider<P: LLMProvider> {
+    inner: P,
+    dedup_window: Duration,
+    cache: ResponseCache,
+}
+
+impl<P: LLMProvider> DeduplicatingProvider<P> {
+    pub fn new(inner: P, dedup_window: Duration) -> Self {
+        Self {
+            inner,
+            dedup_window,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn cached(&self, key: &str) -> Option<CompletionResponse> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(key)
+            .filter(|(_, stored_at)| stored_at.elapsed() < self.dedup_window)
+            .map(|(response, _)| response.clone())
+    }
+
+    fn store(&self, key: String, response: &CompletionResponse) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (_, stored_at)| stored_at.elapsed() < self.dedup_window);
+        cache.insert(key, (response.clone(), Instant::now()));
+    }
+}
+
+/// Idempotency key over `(model, system prompt, last user message, messages after it)`, so
+/// each round of a tool loop gets its own key.
+pub fn idempotency_key(request: &CompletionRequest) -> String {
+    let mut hasher = DefaultHasher::new();
+    request.model.hash(&mut hasher);
+    for message in request.messages.iter().filter(|m| m.role == MessageRole::System) {
+        message.text().hash(&mut hasher);
+    }
+    let last_user = request
+        .messages
+        .iter()
+        .rposition(|m| m.role == MessageRole::User);
+    let turn = match last_user {
+        Some(index) => &request.messages[index..],
+        None => &[],
+    };
+    for message in turn {
+        std::mem::discriminant(&message.role).hash(&mut hasher);
+        message.text().hash(&mut hasher);
+        message.tool_call_id.hash(&mut hasher);
+        for call in &message.tool_calls {
+            call.id.hash(&mut hasher);
+            call.function.name.hash(&mut hasher);
+            call.function.arguments.to_string().hash(&mut hasher);
+        }
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for DeduplicatingProvider<P> {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let key = idempotency_key(&request);
+        if let Some(response) = self.cached(&key) {
+            return Ok(response);
+        }
+
+        let response = self.inner.complete(request).await?;
+        self.store(key, &response);
+        Ok(response)
+    }
+
+    async fn stream_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionStream, LLMError> {
+        self.inner.stream_completion(request).await
+    }
+
+    async fn upload_image(
+        &self,
+        request: ImageUploadRequest,
+    ) -> Result<ImageUploadResponse, LLMError> {
+        self.inner.upload_image(request).await
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, LLMError> {
+        self.inner.create_embeddings(request).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
+        self.inner.model_info(id).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LLMError> {
+        self.inner.list_models().await
+    }
+
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        self.inner.count_tokens(request).await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        agents::Agent,
+        functions::{FunctionCall, FunctionRegistry, ToolCall},
+        providers::scripted::ScriptedProvider,
+        types::ChatMessage,
+    };
+
+    fn dedup() -> DeduplicatingProvider<ScriptedProvider> {
+        let provider = ScriptedProvider::with_responses(&["first", "second"]);
+        DeduplicatingProvider::new(provider, Duration::from_secs(5))
+    }
+
+    fn request(user: &str) -> CompletionRequest {
+        CompletionRequest::new(
+            "m",
+            vec![ChatMessage::system("Be brief."), ChatMessage::user(user)],
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn identical_requests_within_window_hit_provider_once() {
+        let provider = dedup();
+        let first = provider.complete(request("hi")).await.unwrap();
+        let second = provider.complete(request("hi")).await.unwrap();
+
+        assert_eq!(provider.inner().calls(), 1);
+        assert_eq!(first.message.text(), Some("first"));
+        assert_eq!(second.message.text(), Some("first"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn requests_beyond_window_hit_provider_again() {
+        let provider = dedup();
+        provider.complete(request("hi")).await.unwrap();
+        tokio::time::advance(Duration::from_secs(5)).await;
+        let second = provider.complete(request("hi")).await.unwrap();
+
+        assert_eq!(provider.inner().calls(), 2);
+        assert_eq!(second.message.text(), Some("second"));
+    }
+
+    #[test]
+    fn key_ignores_messages_between_system_and_last_user() {
+        let mut with_tool_turn = request("hi");
+        with_tool_turn.messages.insert(1, ChatMessage::assistant("thinking"));
+        assert_eq!(idempotency_key(&request("hi")), idempotency_key(&with_tool_turn));
+        assert_ne!(idempotency_key(&request("hi")), idempotency_key(&request("bye")));
+    }
+
+    #[test]
+    fn key_covers_tool_calls_and_results_after_last_user() {
+        let call = ToolCall::new(FunctionCall::new("lookup", serde_json::json!({ "q": "x" }))).with_id("call_1");
+        let mut with_call = request("hi");
+        let mut assistant = ChatMessage::assistant("");
+        assistant.tool_calls.push(call);
+        with_call.messages.push(assistant);
+        let mut with_result = with_call.clone();
+        with_result.messages.push(ChatMessage::tool("call_1", "found"));
+
+        assert_ne!(idempotency_key(&request("hi")), idempotency_key(&with_call));
+        assert_ne!(idempotency_key(&with_call), idempotency_key(&with_result));
+    }
+
+    #[crate::kernel_function(name = "lookup_weather", description = "Look up the weather")]
+    fn lookup_weather(city: String) -> Result<String, String> {
+        Ok(format!("sunny in {city}"))
+    }
+
+    #[tokio::test]
+    async fn tool_round_trip_reaches_the_provider_each_round() {
+        let mut registry = FunctionRegistry::new();
+        registry.register(lookup_weather_kernel());
+        let agent = Agent::from_string("Forecaster", "Answer weather questions.")
+            .with_function_registry(Arc::new(registry));
+        let mut scripted = ScriptedProvider::new();
+        scripted.add_tool_call_response(
+            "lookup_weather",
+            serde_json::json!({ "city": "Berlin" }),
+            serde_json::json!("It is sunny."),
+        );
+        let provider = DeduplicatingProvider::new(scripted, Duration::from_secs(5));
+
+        let turn = agent
+            .execute(&provider, "m", &[ChatMessage::user("Use lookup_weather for Berlin")])
+            .await
+            .unwrap();
+        assert_eq!(turn.raw_content, "It is sunny.");
+        assert_eq!(provider.inner().calls(), 2);
+    }
+}
@@ -0,0 +1,285 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{
+    providers::LLMProvider,
+    types::{
+        CompletionRequest, CompletionResponse, CompletionStream, EmbeddingRequest,
+        EmbeddingResponse, ImageUploadRequest, ImageUploadResponse, ModelInfo,
+        ProviderCapabilities,
+    },
+    LLMError,
+};
+
+type ErrorFilter = Arc<dyn Fn(&LLMError) -> bool + Send + Sync>;
+
+/// Tries its providers in order until one succeeds, e.g. a secondary account once the
+/// primary's quota runs out. If every provider fails, the last error is returned.
+#[derive(Clone)]
+pub struct FallbackProvider {
+    providers: Vec<Arc<dyn LLMProvider>>,
+    should_fall_back: Option<ErrorFilter>,
+}
+
+impl FallbackProvider {
+    pub fn new(providers: Vec<Arc<dyn LLMProvider>>) -> Self {
+        Self {
+            providers,
+            should_fall_back: None,
+        }
+    }
+
+    /// Only move on to the next provider for errors `filter` accepts; others are returned
+    /// right away. By default every error falls back.
+    pub fn on_error<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&LLMError) -> bool + Send + Sync + 'static,
+    {
+        self.should_fall_back = Some(Arc::new(filter));
+        self
+    }
+
+    pub fn providers(&self) -> &[Arc<dyn LLMProvider>] {
+        &self.providers
+    }
+
+    async fn first_success<'a, T, F, Fut>(&'a self, mut call: F) -> Result<T, LLMError>
+    where
+        F: FnMut(&'a Arc<dyn LLMProvider>) -> Fut,
+        Fut: std::future::Future<Output = Result<T, LLMError>>,
+    {
+        let mut last_error = LLMError::Provider("fallback provider has no providers".to_string());
+        for provider in &self.providers {
+            match call(provider).await {
+                Ok(value) => return Ok(value),
+                Err(error) => {
+                    let falls_back = self.should_fall_back.as_ref().is_none_or(|filter| filter(&error));
+                    if !falls_back {
+                        return Err(error);
+                    }
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(provider = provider.name(), %error, "provider failed, trying the next one");
+                    last_error = error;
+                }
+            }
+        }
+        Err(last_error)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for FallbackProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.first_success(|provider| provider.complete(request.clone()))
+            .await
+    }
+
+    /// Falls back only while opening the stream; errors mid-stream are passed through.
+    async fn stream_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionStream, LLMError> {
+        self.first_success(|provider| provider.stream_completion(request.clone()))
+            .await
+    }
+
+    async fn upload_image(
+        &self,
+        request: ImageUploadRequest,
+    ) -> Result<ImageUploadResponse, LLMError> {
+        self.first_success(|provider| provider.upload_image(request.clone()))
+            .await
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, LLMError> {
+        self.first_success(|provider| provider.create_embeddings(request.clone()))
+            .await
+    }
+
+    async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
+        self.first_success(|provider| provider.model_info(id)).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LLMError> {
+        self.first_success(|provider| provider.list_models()).await
+    }
+
+    /// Counted by the first provider, which serves calls whenever it is healthy.
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        match self.providers.first() {
+            Some(provider) => provider.count_tokens(request).await,
+            None => Ok(request.estimated_token_count()),
+        }
+    }
+
+    /// What every wrapped provider supports, since any of them may end up serving a call.
+    /// The tokens-per-minute limit is the lowest one reported.
+    fn capabilities(&self) -> ProviderCapabilities {
+        shared_capabilities(&self.providers)
+    }
+
+    fn name(&self) -> &'static str {
+        "fallback"
+    }
+}
+
+/// What every provider in `providers` supports, with the lowest tokens-per-minute limit.
+pub(crate) fn shared_capabilities(providers: &[Arc<dyn LLMProvider>]) -> ProviderCapabilities {
+    let mut providers = providers.iter().map(|provider| provider.capabilities());
+    let Some(first) = providers.next() else {
+        return ProviderCapabilities::default();
+    };
+
+    providers.fold(first, |all, caps| ProviderCapabilities {
+        supports_streaming: all.supports_streaming && caps.supports_streaming,
+        supports_reasoning_stream: all.supports_reasoning_stream && caps.supports_reasoning_stream,
+        supports_image_uploads: all.supports_image_uploads && caps.supports_image_uploads,
+        supports_embeddings: all.supports_embeddings && caps.supports_embeddings,
+        supports_structured_output: all.supports_structured_output && caps.supports_structured_output,
+        supports_tools: all.supports_tools && caps.supports_tools,
+        max_tokens_per_minute: match (all.max_tokens_per_minute, caps.max_tokens_per_minute) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (limit, None) | (None, limit) => limit,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{providers::scripted::ScriptedProvider, types::ChatMessage};
+
+    fn answering(response: &str) -> Arc<ScriptedProvider> {
+        Arc::new(ScriptedProvider::with_responses(&[response]))
+    }
+
+    fn failing(error: LLMError) -> Arc<ScriptedProvider> {
+        let mut provider = ScriptedProvider::new();
+        provider.inject_transient_errors(1, error);
+        Arc::new(provider)
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest::new("m", vec![ChatMessage::user("hi")])
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_next_provider() {
+        let primary = failing(LLMError::Provider("429 quota exhausted".to_string()));
+        let secondary = answering("from secondary");
+        let provider = FallbackProvider::new(vec![primary.clone(), secondary.clone()]);
+
+        let response = provider.complete(request()).await.unwrap();
+        assert_eq!(response.message.text(), Some("from secondary"));
+        assert_eq!((primary.calls(), secondary.calls()), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn returns_last_error_when_all_fail() {
+        let provider = FallbackProvider::new(vec![
+            failing(LLMError::Provider("429 first".to_string())),
+            failing(LLMError::Provider("429 second".to_string())),
+        ]);
+
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(err, LLMError::Provider(ref message) if message == "429 second"));
+    }
+
+    #[tokio::test]
+    async fn filter_stops_on_errors_it_rejects() {
+        let secondary = answering("from secondary");
+        let provider = FallbackProvider::new(vec![failing(LLMError::MissingApiKey("OPENROUTER_API_KEY")), secondary.clone()])
+            .on_error(LLMError::is_transient);
+
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(err, LLMError::MissingApiKey(_)));
+        assert_eq!(secondary.calls(), 0);
+    }
+
+    struct Capable(ProviderCapabilities);
+
+    #[async_trait]
+    impl LLMProvider for Capable {
+        async fn complete(&self, _request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+            Err(LLMError::Unsupported("completions"))
+        }
+
+        fn capabilities(&self) -> ProviderCapabilities {
+            self.0
+        }
+
+        fn name(&self) -> &'static str {
+            "capable"
+        }
+    }
+
+    #[test]
+    fn capabilities_are_the_intersection() {
+        let provider = FallbackProvider::new(vec![
+            Arc::new(Capable(ProviderCapabilities::new(true, true, true, true).with_structured_output(true))),
+            Arc::new(Capable(
+                ProviderCapabilities::new(true, false, true, false).with_max_tokens_per_minute(Some(6_000)),
+            )),
+        ]);
+
+        let caps = provider.capabilities();
+        assert!(caps.supports_streaming && caps.supports_image_uploads && caps.supports_tools);
+        assert!(!caps.supports_reasoning_stream && !caps.supports_embeddings && !caps.supports_structured_output);
+        assert_eq!(caps.max_tokens_per_minute, Some(6_000));
+    }
+
+    struct Counting(u32);
+
+    #[async_trait]
+    impl LLMProvider for Counting {
+        async fn complete(&self, _request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+            Err(LLMError::Unsupported("completions"))
+        }
+
+        async fn count_tokens(&self, _request: &CompletionRequest) -> Result<u32, LLMError> {
+            Ok(self.0)
+        }
+
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+    }
+
+    #[tokio::test]
+    async fn token_counts_come_from_the_first_provider() {
+        let provider = FallbackProvider::new(vec![Arc::new(Counting(42)), Arc::new(Counting(7))]);
+        assert_eq!(provider.count_tokens(&request()).await.unwrap(), 42);
+    }
+
+    struct Embedder;
+
+    #[async_trait]
+    impl LLMProvider for Embedder {
+        async fn complete(&self, _request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+            Err(LLMError::Unsupported("completions"))
+        }
+
+        async fn create_embeddings(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse, LLMError> {
+            Ok(EmbeddingResponse { data: Vec::new(), model: request.model, usage: None })
+        }
+
+        fn name(&self) -> &'static str {
+            "embedder"
+        }
+    }
+
+    #[tokio::test]
+    async fn embeddings_and_models_fall_back_too() {
+        let provider = FallbackProvider::new(vec![Arc::new(Counting(1)), Arc::new(Embedder)]);
+        let request = EmbeddingRequest::new("text-embedding-3-small", vec!["hi".to_string()]);
+        let response = provider.create_embeddings(request).await.unwrap();
+        assert_eq!(response.model, "text-embedding-3-small");
+
+        let err = provider.list_models().await.unwrap_err();
+        assert!(matches!(err, LLMError::Unsupported("model list")));
+    }
+}
@@ -0,0 +1,880 @@
+//! Google Gemini provider — Generative Language REST API (`generateContent`).
+//!
+//! Gemini differs from the OpenAI shape in a few places this module translates:
+//! * System messages become `systemInstruction`; `assistant` is called `model`.
+//! * Tool calls and results are `functionCall` / `functionResponse` parts, matched by
+//!   function name rather than call id. Calls without an id get a random one, and each
+//!   result takes its name from the call in the nearest preceding assistant turn.
+//! * Function declarations accept only an OpenAPI subset of JSON Schema.
+//!
+//! Streaming uses `:streamGenerateContent?alt=sse`, where every event is a complete
+//! `GenerateContentResponse` chunk.
+
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_stream::try_stream;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    error::LLMError,
+    functions::{FunctionCall, Tool, ToolCall, ToolChoice, ToolChoiceSimple},
+    providers::{extract_data_payload, extract_sse_event, retry_after, LLMProvider},
+    types::{
+        ChatMessage, CompletionRequest, CompletionResponse, CompletionStream, MessageRole,
+        ProviderCapabilities, ReasoningTrace, StreamEvent, TokenUsage,
+    },
+};
+
+const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Schema keywords Gemini accepts in function declarations and `responseSchema`.
+const SCHEMA_KEYWORDS: [&str; 18] = [
+    "type",
+    "format",
+    "title",
+    "description",
+    "nullable",
+    "enum",
+    "maxItems",
+    "minItems",
+    "properties",
+    "required",
+    "minProperties",
+    "maxProperties",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "anyOf",
+    "items",
+    "propertyOrdering",
+];
+
+#[derive(Debug, Clone)]
+pub struct GeminiConfig {
+    pub api_key: String,
+    pub base_url: String,
+    pub request_timeout: Duration,
+}
+
+impl GeminiConfig {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            request_timeout: Duration::from_secs(60),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Gemini {
+    client: Client,
+    config: GeminiConfig,
+    active_model: Arc<Mutex<Option<String>>>,
+}
+
+impl Gemini {
+    pub fn new(api_key: impl Into<String>) -> Result<Self, LLMError> {
+        Self::from_config(GeminiConfig::new(api_key))
+    }
+
+    pub fn from_env() -> Result<Self, LLMError> {
+        let api_key =
+            env::var("GEMINI_API_KEY").map_err(|_| LLMError::MissingApiKey("GEMINI_API_KEY"))?;
+        let mut config = GeminiConfig::new(api_key);
+
+        if let Ok(base_url) = env::var("GEMINI_BASE_URL") {
+            config.base_url = base_url;
+        }
+
+        Self::from_config(config)
+    }
+
+    pub fn from_config(config: GeminiConfig) -> Result<Self, LLMError> {
+        let client = Client::builder().timeout(config.request_timeout).build()?;
+        Ok(Self {
+            client,
+            config,
+            active_model: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// `{base_url}/models/{model}:{method}`; a `models/` prefix on the model is accepted.
+    fn endpoint(&self, model: &str, method: &str) -> String {
+        format!(
+            "{}/models/{}:{}",
+            self.config.base_url.trim_end_matches('/'),
+            model.trim_start_matches("models/"),
+            method
+        )
+    }
+
+    fn prepare(&self, builder: RequestBuilder) -> RequestBuilder {
+        builder
+            .header("x-goog-api-key", &self.config.api_key)
+            .header("Content-Type", "application/json")
+    }
+
+    fn set_active_model(&self, model: &str) {
+        *self.active_model.lock().unwrap() = Some(model.to_string());
+    }
+
+    async fn send(&self, request: CompletionRequest, stream: bool) -> Result<reqwest::Response, LLMError> {
+        self.set_active_model(&request.model);
+        let builder = if stream {
+            self.client
+                .post(self.endpoint(&request.model, "streamGenerateContent"))
+                .query(&[("alt", "sse")])
+        } else {
+            self.client.post(self.endpoint(&request.model, "generateContent"))
+        };
+
+        let correlation_id = request.correlation_id;
+        let body = build_request_body(request)?;
+        let response = self
+            .prepare(builder)
+            .headers(super::request_id_headers(correlation_id))
+            .json(&body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after = retry_after(response.headers());
+            let text = response.text().await.unwrap_or_default();
+            return Err(parse_error_text(status, retry_after, &text));
+        }
+        Ok(response)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Request mapping
+// ---------------------------------------------------------------------------
+
+fn build_request_body(request: CompletionRequest) -> Result<Value, LLMError> {
+    let CompletionRequest {
+        model: _,
+        messages,
+        max_tokens,
+        temperature,
+        top_p,
+        response_format,
+        tools,
+        tool_choice,
+        reasoning_effort: _,
+        correlation_id: _,
+    } = request;
+
+    let mut body = Map::new();
+
+    let system: Vec<Value> = messages
+        .iter()
+        .filter(|m| matches!(m.role, MessageRole::System))
+        .filter_map(|m| m.content.as_ref())
+        .map(|text| json!({ "text": text }))
+        .collect();
+    if !system.is_empty() {
+        body.insert("systemInstruction".into(), json!({ "parts": system }));
+    }
+    body.insert("contents".into(), Value::Array(contents_from_messages(&messages)?));
+
+    if !tools.is_empty() {
+        let declarations: Vec<Value> = tools.iter().map(function_declaration).collect();
+        body.insert("tools".into(), json!([{ "functionDeclarations": declarations }]));
+    }
+    if let Some(choice) = tool_choice {
+        body.insert("toolConfig".into(), tool_config(&choice));
+    }
+
+    let mut generation = Map::new();
+    if let Some(tokens) = max_tokens {
+        generation.insert("maxOutputTokens".into(), json!(tokens));
+    }
+    if let Some(t) = temperature {
+        generation.insert("temperature".into(), json!(t));
+    }
+    if let Some(p) = top_p {
+        generation.insert("topP".into(), json!(p));
+    }
+    if let Some(format) = response_format {
+        match format.get("type").and_then(Value::as_str) {
+            Some("json_object") => {
+                generation.insert("responseMimeType".into(), json!("application/json"));
+            }
+            Some("json_schema") => {
+                generation.insert("responseMimeType".into(), json!("application/json"));
+                if let Some(schema) = format.get("json_schema").and_then(|js| js.get("schema")) {
+                    generation.insert("responseSchema".into(), gemini_schema(schema));
+                }
+            }
+            _ => {}
+        }
+    }
+    if !generation.is_empty() {
+        body.insert("generationConfig".into(), Value::Object(generation));
+    }
+
+    Ok(Value::Object(body))
+}
+
+/// Turn the conversation into Gemini `contents`, merging consecutive turns of the same
+/// role (e.g. several tool results) into one.
+fn contents_from_messages(messages: &[ChatMessage]) -> Result<Vec<Value>, LLMError> {
+    // Tool results only carry the call id; Gemini wants the function name. Ids are only
+    // looked up among the calls of the latest assistant turn, since they may be reused.
+    let mut call_names: HashMap<&str, &str> = HashMap::new();
+
+    let mut contents: Vec<(&'static str, Vec<Value>)> = Vec::new();
+    for message in messages {
+        let (role, parts) = match message.role {
+            MessageRole::System => continue,
+            MessageRole::User => ("user", user_parts(message)?),
+            MessageRole::Assistant => {
+                call_names = message
+                    .tool_calls
+                    .iter()
+                    .filter_map(|call| Some((call.id.as_deref()?, call.function.name.as_str())))
+                    .collect();
+                ("model", model_parts(message))
+            }
+            MessageRole::Tool => {
+                let name = message
+                    .name
+                    .as_deref()
+                    .or_else(|| message.tool_call_id.as_deref().and_then(|id| call_names.get(id).copied()))
+                    .ok_or_else(|| {
+                        LLMError::Provider("gemini: tool result does not match any earlier tool call".to_string())
+                    })?;
+                ("user", vec![function_response_part(name, message.content.as_deref().unwrap_or_default())])
+            }
+        };
+        if parts.is_empty() {
+            continue;
+        }
+
+        match contents.last_mut() {
+            Some((last_role, last_parts)) if *last_role == role => last_parts.extend(parts),
+            _ => contents.push((role, parts)),
+        }
+    }
+
+    Ok(contents
+        .into_iter()
+        .map(|(role, parts)| json!({ "role": role, "parts": parts }))
+        .collect())
+}
+
+fn user_parts(message: &ChatMessage) -> Result<Vec<Value>, LLMError> {
+    let mut parts = Vec::new();
+    if let Some(text) = message.content.as_deref().filter(|t| !t.is_empty()) {
+        parts.push(json!({ "text": text }));
+    }
+    for image in &message.images {
+        parts.push(inline_image_part(image)?);
+    }
+    #[cfg(feature = "tracing")]
+    if !message.attachments.is_empty() {
+        tracing::warn!(
+            count = message.attachments.len(),
+            "gemini provider does not send file or audio attachments; dropping"
+        );
+    }
+    Ok(parts)
+}
+
+fn model_parts(message: &ChatMessage) -> Vec<Value> {
+    let mut parts = Vec::new();
+    if let Some(text) = message.content.as_deref().filter(|t| !t.is_empty()) {
+        parts.push(json!({ "text": text }));
+    }
+    for call in &message.tool_calls {
+        parts.push(json!({
+            "functionCall": { "name": call.function.name, "args": call.function.arguments }
+        }));
+    }
+    parts
+}
+
+/// Tool output that is a JSON object is passed through; anything else is wrapped.
+fn function_response_part(name: &str, content: &str) -> Value {
+    let response = match serde_json::from_str::<Value>(content) {
+        Ok(value @ Value::Object(_)) => value,
+        _ => json!({ "content": content }),
+    };
+    json!({ "functionResponse": { "name": name, "response": response } })
+}
+
+/// Gemini only takes inline base64 image data, not remote URLs.
+fn inline_image_part(image: &str) -> Result<Value, LLMError> {
+    let Some(rest) = image.strip_prefix("data:") else {
+        return Err(LLMError::InvalidResponse(
+            "gemini requires images as base64 data URLs; remote URLs are not supported",
+        ));
+    };
+    let (mime_type, data) = rest
+        .split_once(";base64,")
+        .ok_or(LLMError::InvalidResponse("image data URL is not base64 encoded"))?;
+    Ok(json!({ "inlineData": { "mimeType": mime_type, "data": data } }))
+}
+
+fn function_declaration(tool: &Tool) -> Value {
+    let mut declaration = Map::new();
+    declaration.insert("name".into(), Value::String(tool.function.name.clone()));
+    if let Some(description) = &tool.function.description {
+        declaration.insert("description".into(), Value::String(description.clone()));
+    }
+    if !tool.function.parameters.properties.is_empty() {
+        let parameters = serde_json::to_value(&tool.function.parameters).unwrap_or_default();
+        declaration.insert("parameters".into(), gemini_schema(&parameters));
+    }
+    Value::Object(declaration)
+}
+
+/// Reduce a JSON Schema to the subset Gemini accepts. `"type": ["T", "null"]` becomes
+/// `"type": "T", "nullable": true`; unsupported keywords are dropped.
+fn gemini_schema(schema: &Value) -> Value {
+    let Value::Object(object) = schema else {
+        return schema.clone();
+    };
+
+    let mut out = Map::new();
+    for (key, value) in object {
+        if !SCHEMA_KEYWORDS.contains(&key.as_str()) {
+            continue;
+        }
+        let value = match (key.as_str(), value) {
+            ("type", Value::Array(types)) => {
+                let mut concrete = types.iter().filter(|t| t.as_str() != Some("null"));
+                if types.len() > 1 && concrete.clone().count() < types.len() {
+                    out.insert("nullable".into(), Value::Bool(true));
+                }
+                concrete.next().cloned().unwrap_or(Value::String("string".into()))
+            }
+            ("properties", Value::Object(properties)) => Value::Object(
+                properties
+                    .iter()
+                    .map(|(name, property)| (name.clone(), gemini_schema(property)))
+                    .collect(),
+            ),
+            ("items", items) => gemini_schema(items),
+            ("anyOf", Value::Array(variants)) => Value::Array(variants.iter().map(gemini_schema).collect()),
+            _ => value.clone(),
+        };
+        out.insert(key.clone(), value);
+    }
+    Value::Object(out)
+}
+
+fn tool_config(choice: &ToolChoice) -> Value {
+    let config = match choice {
+        ToolChoice::Simple(ToolChoiceSimple::Auto) => json!({ "mode": "AUTO" }),
+        ToolChoice::Simple(ToolChoiceSimple::None) => json!({ "mode": "NONE" }),
+        ToolChoice::Simple(ToolChoiceSimple::Required) => json!({ "mode": "ANY" }),
+        ToolChoice::Function { function, .. } => {
+            json!({ "mode": "ANY", "allowedFunctionNames": [function.name] })
+        }
+    };
+    json!({ "functionCallingConfig": config })
+}
+
+// ---------------------------------------------------------------------------
+// Response types
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    #[serde(default)]
+    usage_metadata: Option<UsageMetadata>,
+    #[serde(default)]
+    prompt_feedback: Option<PromptFeedback>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    #[serde(default)]
+    content: Option<CandidateContent>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct CandidateContent {
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Part {
+    #[serde(default)]
+    text: Option<String>,
+    /// Set on thought summaries from thinking models.
+    #[serde(default)]
+    thought: bool,
+    #[serde(default)]
+    function_call: Option<GeminiFunctionCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiFunctionCall {
+    #[serde(default)]
+    id: Option<String>,
+    name: String,
+    #[serde(default)]
+    args: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    candidates_token_count: u32,
+    #[serde(default)]
+    total_token_count: Option<u32>,
+    #[serde(default)]
+    cached_content_token_count: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptFeedback {
+    #[serde(default)]
+    block_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiErrorEnvelope {
+    error: GeminiError,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiError {
+    message: String,
+}
+
+impl From<UsageMetadata> for TokenUsage {
+    fn from(usage: UsageMetadata) -> Self {
+        TokenUsage {
+            prompt_tokens: usage.prompt_token_count,
+            completion_tokens: usage.candidates_token_count,
+            total_tokens: usage
+                .total_token_count
+                .unwrap_or(usage.prompt_token_count + usage.candidates_token_count),
+            cached_tokens: usage.cached_content_token_count,
+        }
+    }
+}
+
+/// Text, thought text and tool calls of one response (or stream chunk). Gemini rarely sends
+/// call ids, so missing ones are generated.
+#[derive(Debug, Default)]
+struct CandidateOutput {
+    text: String,
+    thoughts: String,
+    tool_calls: Vec<ToolCall>,
+    finish_reason: Option<String>,
+}
+
+impl GenerateContentResponse {
+    fn into_output(self) -> Result<CandidateOutput, LLMError> {
+        let Some(candidate) = self.candidates.into_iter().next() else {
+            if let Some(reason) = self.prompt_feedback.and_then(|f| f.block_reason) {
+                return Err(LLMError::Provider(format!("gemini blocked the prompt: {reason}")));
+            }
+            return Ok(CandidateOutput::default());
+        };
+
+        let mut output = CandidateOutput {
+            finish_reason: candidate.finish_reason,
+            ..CandidateOutput::default()
+        };
+        for part in candidate.content.unwrap_or_default().parts {
+            if let Some(text) = part.text {
+                if part.thought {
+                    output.thoughts.push_str(&text);
+                } else {
+                    output.text.push_str(&text);
+                }
+            }
+            if let Some(call) = part.function_call {
+                let mut tool_call = ToolCall::new(FunctionCall::new(call.name, call.args));
+                tool_call.id = Some(call.id.unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple())));
+                output.tool_calls.push(tool_call);
+            }
+        }
+        Ok(output)
+    }
+}
+
+fn parse_error_text(status: reqwest::StatusCode, retry_after: Option<Duration>, text: &str) -> LLMError {
+    let message = match serde_json::from_str::<GeminiErrorEnvelope>(text) {
+        Ok(envelope) if status.is_server_error() => format!("{status}: {}", envelope.error.message),
+        Ok(envelope) => envelope.error.message,
+        Err(_) => format!("unexpected status {status}: {text}"),
+    };
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        LLMError::RateLimited { message, retry_after }
+    } else {
+        LLMError::Provider(message)
+    }
+}
+
+fn assistant_message(text: String, tool_calls: Vec<ToolCall>, thoughts: &str) -> ChatMessage {
+    let mut message = ChatMessage::assistant(text);
+    if message.content.as_deref() == Some("") {
+        message.content = None;
+    }
+    message.tool_calls = tool_calls;
+    message.thinking = Some(thoughts.to_string()).filter(|t| !t.is_empty());
+    message
+}
+
+fn reasoning_from(thoughts: &str, finish_reason: Option<String>) -> Option<Vec<ReasoningTrace>> {
+    if thoughts.is_empty() {
+        return None;
+    }
+    Some(vec![ReasoningTrace {
+        content: thoughts.to_string(),
+        finish_reason,
+    }])
+}
+
+// ---------------------------------------------------------------------------
+// LLMProvider impl
+// ---------------------------------------------------------------------------
+
+#[async_trait]
+impl LLMProvider for Gemini {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let response = self.send(request, false).await?;
+        let mut parsed: GenerateContentResponse = response.json().await?;
+        let usage = parsed.usage_metadata.take().map(TokenUsage::from);
+        let output = parsed.into_output()?;
+
+        Ok(CompletionResponse {
+            reasoning: reasoning_from(&output.thoughts, output.finish_reason.clone()),
+            message: assistant_message(output.text, output.tool_calls, &output.thoughts),
+            usage,
+        })
+    }
+
+    async fn stream_completion(&self, request: CompletionRequest) -> Result<CompletionStream, LLMError> {
+        let response = self.send(request, true).await?;
+
+        let stream = try_stream! {
+            let mut body_stream = response.bytes_stream();
+            let mut buffer = Vec::<u8>::new();
+            let mut text = String::new();
+            let mut thoughts = String::new();
+            let mut tool_calls: Vec<ToolCall> = Vec::new();
+            let mut usage: Option<TokenUsage> = None;
+            let mut finish_reason: Option<String> = None;
+
+            while let Some(chunk) = body_stream.next().await {
+                buffer.extend_from_slice(&chunk?);
+
+                while let Some(event) = extract_sse_event(&mut buffer) {
+                    let payload = extract_data_payload(&event)?;
+                    if payload.is_empty() {
+                        continue;
+                    }
+
+                    let mut parsed: GenerateContentResponse = serde_json::from_str(&payload)?;
+                    if let Some(chunk_usage) = parsed.usage_metadata.take() {
+                        usage = Some(chunk_usage.into());
+                    }
+                    let output = parsed.into_output()?;
+
+                    if !output.text.is_empty() {
+                        text.push_str(&output.text);
+                        yield StreamEvent::MessageDelta(output.text);
+                    }
+                    if !output.thoughts.is_empty() {
+                        thoughts.push_str(&output.thoughts);
+                        yield StreamEvent::ReasoningDelta(output.thoughts);
+                    }
+                    // Gemini sends each function call whole, never split across chunks.
+                    for call in output.tool_calls {
+                        yield StreamEvent::ToolCallDelta {
+                            index: tool_calls.len(),
+                            arguments: call.function.arguments.to_string(),
+                        };
+                        yield StreamEvent::ToolCallComplete { call: call.clone() };
+                        tool_calls.push(call);
+                    }
+                    if output.finish_reason.is_some() {
+                        finish_reason = output.finish_reason;
+                    }
+                }
+            }
+
+            yield StreamEvent::Completed(CompletionResponse {
+                reasoning: reasoning_from(&thoughts, finish_reason),
+                message: assistant_message(text, tool_calls, &thoughts),
+                usage,
+            });
+        };
+
+        Ok(Box::pin(stream))
+    }
+
+    /// Image input is reported for the most recently requested model when its name marks
+    /// it as multimodal (`vision` or `pro`).
+    fn capabilities(&self) -> ProviderCapabilities {
+        let multimodal = self
+            .active_model
+            .lock()
+            .unwrap()
+            .as_deref()
+            .is_some_and(is_multimodal_model);
+        ProviderCapabilities::new(true, false, multimodal, false).with_structured_output(true)
+    }
+
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+}
+
+fn is_multimodal_model(model: &str) -> bool {
+    let model = model.to_ascii_lowercase();
+    model.contains("vision") || model.contains("pro")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::{json_schema_for, FunctionDefinition, FunctionParameter};
+    use wiremock::matchers::{body_partial_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn weather_tool() -> Tool {
+        let mut definition = FunctionDefinition::new("get_weather").with_description("Look up the weather");
+        definition.add_parameter(FunctionParameter::new("city", json_schema_for::<String>()));
+        definition.add_parameter(FunctionParameter::new("days", json_schema_for::<Option<u32>>()).optional());
+        definition.to_tool()
+    }
+
+    fn provider(server: &MockServer) -> Gemini {
+        Gemini::from_config(GeminiConfig::new("test-key").with_base_url(server.uri())).unwrap()
+    }
+
+    #[test]
+    fn maps_roles_tool_calls_and_results() {
+        let mut assistant = ChatMessage::assistant("");
+        let mut call = ToolCall::new(FunctionCall::new("get_weather", json!({ "city": "Oslo" })));
+        call.id = Some("call_0".to_string());
+        assistant.tool_calls = vec![call];
+
+        let messages = vec![
+            ChatMessage::system("Be brief."),
+            ChatMessage::user("Weather in Oslo?"),
+            assistant,
+            ChatMessage::tool("call_0", r#"{"temp": 3}"#),
+            ChatMessage::tool("call_0", "windy"),
+        ];
+        let body = build_request_body(
+            CompletionRequest::new("gemini-1.5-pro", messages).with_tools(vec![weather_tool()]),
+        )
+        .unwrap();
+
+        assert_eq!(body["systemInstruction"], json!({ "parts": [{ "text": "Be brief." }] }));
+        assert_eq!(
+            body["contents"],
+            json!([
+                { "role": "user", "parts": [{ "text": "Weather in Oslo?" }] },
+                { "role": "model", "parts": [{ "functionCall": { "name": "get_weather", "args": { "city": "Oslo" } } }] },
+                { "role": "user", "parts": [
+                    { "functionResponse": { "name": "get_weather", "response": { "temp": 3 } } },
+                    { "functionResponse": { "name": "get_weather", "response": { "content": "windy" } } },
+                ] },
+            ])
+        );
+
+        let declaration = &body["tools"][0]["functionDeclarations"][0];
+        assert_eq!(declaration["name"], "get_weather");
+        assert!(declaration["parameters"].get("additional_properties").is_none());
+        assert_eq!(declaration["parameters"]["properties"]["days"]["nullable"], true);
+        assert_eq!(declaration["parameters"]["required"], json!(["city"]));
+    }
+
+    #[tokio::test]
+    async fn complete_parses_function_calls_and_usage() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-1.5-pro:generateContent"))
+            .and(header("x-goog-api-key", "test-key"))
+            .and(body_partial_json(json!({ "contents": [{ "role": "user", "parts": [{ "text": "Weather?" }] }] })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "candidates": [{
+                    "content": { "role": "model", "parts": [
+                        { "text": "Checking." },
+                        { "functionCall": { "name": "get_weather", "args": { "city": "Oslo" } } },
+                    ] },
+                    "finishReason": "STOP",
+                }],
+                "usageMetadata": { "promptTokenCount": 12, "candidatesTokenCount": 5, "totalTokenCount": 17 },
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let gemini = provider(&server);
+        let request = CompletionRequest::new("gemini-1.5-pro", vec![ChatMessage::user("Weather?")]);
+        let response = gemini.complete(request).await.unwrap();
+
+        assert_eq!(response.message.text(), Some("Checking."));
+        assert_eq!(response.message.tool_calls.len(), 1);
+        assert_eq!(response.message.tool_calls[0].function.name, "get_weather");
+        assert_eq!(response.message.tool_calls[0].function.arguments, json!({ "city": "Oslo" }));
+        let usage = response.usage.unwrap();
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.total_tokens), (12, 5, 17));
+        assert!(gemini.capabilities().supports_image_uploads);
+    }
+
+    #[tokio::test]
+    async fn stream_emits_deltas_and_completion() {
+        let server = MockServer::start().await;
+        let sse = concat!(
+            "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"Hel\"}]}}]}\n\n",
+            "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"lo\"}]},\"finishReason\":\"STOP\"}],",
+            "\"usageMetadata\":{\"promptTokenCount\":3,\"candidatesTokenCount\":2}}\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-1.5-flash:streamGenerateContent"))
+            .and(query_param("alt", "sse"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(sse))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let gemini = provider(&server);
+        let request = CompletionRequest::new("gemini-1.5-flash", vec![ChatMessage::user("hi")]);
+        let events: Vec<_> = gemini.stream_completion(request).await.unwrap().collect().await;
+
+        let deltas: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                Ok(StreamEvent::MessageDelta(text)) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deltas, ["Hel", "lo"]);
+        let Some(Ok(StreamEvent::Completed(response))) = events.last() else {
+            panic!("stream did not complete: {events:?}");
+        };
+        assert_eq!(response.message.text(), Some("Hello"));
+        assert_eq!(response.usage.as_ref().map(|u| u.total_tokens), Some(5));
+        assert!(!gemini.capabilities().supports_image_uploads);
+    }
+
+    #[tokio::test]
+    async fn api_errors_are_reported() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "error": { "code": 400, "message": "API key not valid.", "status": "INVALID_ARGUMENT" }
+            })))
+            .mount(&server)
+            .await;
+
+        let request = CompletionRequest::new("gemini-1.5-pro", vec![ChatMessage::user("hi")]);
+        let err = provider(&server).complete(request).await.unwrap_err();
+        assert!(matches!(err, LLMError::Provider(ref message) if message == "API key not valid."));
+    }
+
+    #[tokio::test]
+    async fn rate_limits_and_server_errors_keep_their_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/models/limited:generateContent"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "7").set_body_json(json!({
+                "error": { "code": 429, "message": "Resource exhausted.", "status": "RESOURCE_EXHAUSTED" }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/models/down:generateContent"))
+            .respond_with(ResponseTemplate::new(503).set_body_json(json!({
+                "error": { "code": 503, "message": "The model is overloaded.", "status": "UNAVAILABLE" }
+            })))
+            .mount(&server)
+            .await;
+
+        let gemini = provider(&server);
+        let err = gemini
+            .complete(CompletionRequest::new("limited", vec![ChatMessage::user("hi")]))
+            .await
+            .unwrap_err();
+        assert!(err.is_transient());
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(7)));
+
+        let err = gemini
+            .complete(CompletionRequest::new("down", vec![ChatMessage::user("hi")]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LLMError::Provider(ref message) if message.contains("503")));
+    }
+
+    #[test]
+    fn tool_results_resolve_names_from_the_latest_assistant_turn() {
+        let assistant_calling = |name: &str| {
+            let mut assistant = ChatMessage::assistant("");
+            let mut call = ToolCall::new(FunctionCall::new(name, json!({})));
+            call.id = Some("call_0".to_string());
+            assistant.tool_calls = vec![call];
+            assistant
+        };
+        let messages = vec![
+            ChatMessage::user("Weather, then time?"),
+            assistant_calling("get_weather"),
+            ChatMessage::tool("call_0", "sunny"),
+            assistant_calling("get_time"),
+            ChatMessage::tool("call_0", "noon"),
+        ];
+
+        let contents = contents_from_messages(&messages).unwrap();
+        assert_eq!(contents[2]["parts"][0]["functionResponse"]["name"], "get_weather");
+        assert_eq!(contents[4]["parts"][0]["functionResponse"]["name"], "get_time");
+    }
+
+    #[test]
+    fn generated_call_ids_are_unique_across_responses() {
+        let response = || -> GenerateContentResponse {
+            serde_json::from_value(json!({
+                "candidates": [{ "content": { "role": "model", "parts": [
+                    { "functionCall": { "name": "get_weather", "args": {} } },
+                ] } }],
+            }))
+            .unwrap()
+        };
+        let first = response().into_output().unwrap().tool_calls.remove(0);
+        let second = response().into_output().unwrap().tool_calls.remove(0);
+        assert_ne!(first.id, second.id);
+    }
+}
@@ -0,0 +1,215 @@
+//! Groq provider — Groq's OpenAI-compatible API, served through [`OpenAI`] with Groq's
+//! base URL and key. Adds per-model tokens-per-minute limits to the capabilities.
+
+use std::{
+    env,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+
+use crate::{
+    error::LLMError,
+    providers::{
+        openai::{OpenAI, OpenAIConfig},
+        LLMProvider,
+    },
+    types::{CompletionRequest, CompletionResponse, CompletionStream, ProviderCapabilities},
+};
+
+const DEFAULT_BASE_URL: &str = "https://api.groq.com/openai/v1";
+
+/// Tokens per minute Groq's free tier grants each model family; unknown models get the
+/// most conservative limit.
+const TOKENS_PER_MINUTE: [(&str, u32); 6] = [
+    ("llama-3.1-8b", 6_000),
+    ("llama-3.3-70b", 12_000),
+    ("llama-4", 30_000),
+    ("gemma2", 15_000),
+    ("mixtral", 5_000),
+    ("qwen", 6_000),
+];
+const DEFAULT_TOKENS_PER_MINUTE: u32 = 5_000;
+
+/// Tokens per minute for `model` on Groq's free tier.
+pub fn tokens_per_minute(model: &str) -> u32 {
+    let model = model.to_ascii_lowercase();
+    TOKENS_PER_MINUTE
+        .iter()
+        .find(|(prefix, _)| model.contains(prefix))
+        .map_or(DEFAULT_TOKENS_PER_MINUTE, |(_, limit)| *limit)
+}
+
+#[derive(Debug, Clone)]
+pub struct GroqBuilder {
+    api_key: String,
+    base_url: String,
+    timeout: Duration,
+    max_tokens_per_minute: Option<u32>,
+}
+
+impl GroqBuilder {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            timeout: Duration::from_secs(30),
+            max_tokens_per_minute: None,
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn with_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.timeout = Duration::from_millis(timeout_ms);
+        self
+    }
+
+    /// Report this limit for every model instead of the free-tier one, for paid plans.
+    pub fn with_max_tokens_per_minute(mut self, limit: u32) -> Self {
+        self.max_tokens_per_minute = Some(limit);
+        self
+    }
+
+    pub fn build(self) -> Result<Groq, LLMError> {
+        let config = OpenAIConfig::new(self.api_key)
+            .with_base_url(self.base_url)
+            .with_timeout(self.timeout);
+        Ok(Groq {
+            inner: OpenAI::from_config(config)?,
+            active_model: Arc::new(Mutex::new(None)),
+            max_tokens_per_minute: self.max_tokens_per_minute,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Groq {
+    inner: OpenAI,
+    active_model: Arc<Mutex<Option<String>>>,
+    max_tokens_per_minute: Option<u32>,
+}
+
+impl Groq {
+    pub fn new(api_key: impl Into<String>) -> Result<Self, LLMError> {
+        GroqBuilder::new(api_key).build()
+    }
+
+    pub fn builder(api_key: impl Into<String>) -> GroqBuilder {
+        GroqBuilder::new(api_key)
+    }
+
+    pub fn from_env() -> Result<Self, LLMError> {
+        let api_key =
+            env::var("GROQ_API_KEY").map_err(|_| LLMError::MissingApiKey("GROQ_API_KEY"))?;
+        let mut builder = GroqBuilder::new(api_key);
+
+        if let Ok(base_url) = env::var("GROQ_BASE_URL") {
+            builder = builder.with_base_url(base_url);
+        }
+        if let Ok(timeout_ms) = env::var("GROQ_REQUEST_TIMEOUT_MS") {
+            if let Ok(ms) = timeout_ms.parse::<u64>() {
+                builder = builder.with_timeout_ms(ms);
+            }
+        }
+
+        builder.build()
+    }
+
+    fn set_active_model(&self, model: &str) {
+        *self.active_model.lock().unwrap() = Some(model.to_string());
+    }
+}
+
+#[async_trait]
+impl LLMProvider for Groq {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.set_active_model(&request.model);
+        self.inner.complete(request).await
+    }
+
+    async fn stream_completion(&self, request: CompletionRequest) -> Result<CompletionStream, LLMError> {
+        self.set_active_model(&request.model);
+        self.inner.stream_completion(request).await
+    }
+
+    /// The tokens-per-minute limit and image support follow the most recently requested
+    /// model; before the first request no limit is reported.
+    fn capabilities(&self) -> ProviderCapabilities {
+        let model = self.active_model.lock().unwrap().clone();
+        let vision = model
+            .as_deref()
+            .is_some_and(|model| model.contains("vision") || model.contains("llama-4"));
+        let limit = self
+            .max_tokens_per_minute
+            .or_else(|| model.as_deref().map(tokens_per_minute));
+        ProviderCapabilities::new(true, false, vision, false).with_max_tokens_per_minute(limit)
+    }
+
+    fn name(&self) -> &'static str {
+        "groq"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ChatMessage;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn completes_through_groq_endpoint_and_reports_model_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(header("authorization", "Bearer gsk-test"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": { "name": "lookup", "arguments": "{\"q\":\"rust\"}" }
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let groq = Groq::builder("gsk-test")
+            .with_base_url(server.uri())
+            .with_timeout_ms(5_000)
+            .build()
+            .unwrap();
+        assert_eq!(groq.capabilities().max_tokens_per_minute, None);
+
+        let request = CompletionRequest::new("llama-3.3-70b-versatile", vec![ChatMessage::user("search")]);
+        let response = groq.complete(request).await.unwrap();
+        assert_eq!(response.message.tool_calls[0].function.name, "lookup");
+        assert_eq!(groq.capabilities().max_tokens_per_minute, Some(12_000));
+        assert_eq!(groq.name(), "groq");
+    }
+
+    #[test]
+    fn limits_follow_model_family_unless_overridden() {
+        assert_eq!(tokens_per_minute("llama-3.1-8b-instant"), 6_000);
+        assert_eq!(tokens_per_minute("mixtral-8x7b-32768"), 5_000);
+        assert_eq!(tokens_per_minute("something-new"), DEFAULT_TOKENS_PER_MINUTE);
+
+        let groq = Groq::builder("gsk-test").with_max_tokens_per_minute(250_000).build().unwrap();
+        groq.set_active_model("gemma2-9b-it");
+        assert_eq!(groq.capabilities().max_tokens_per_minute, Some(250_000));
+    }
+}
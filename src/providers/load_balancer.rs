@@ -0,0 +1,249 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::{
+    providers::{fallback::shared_capabilities, LLMProvider},
+    types::{
+        CompletionRequest, CompletionResponse, CompletionStream, EmbeddingRequest,
+        EmbeddingResponse, ImageUploadRequest, ImageUploadResponse, ProviderCapabilities,
+    },
+    LLMError,
+};
+
+/// Cooldown used by [`RoundRobinProvider::new`].
+pub const DEFAULT_COOLDOWN_MS: u64 = 30_000;
+
+/// Calls made through a [`RoundRobinProvider`] to one of its providers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackendStats {
+    pub calls: u64,
+    pub errors: u64,
+    /// Mean latency over all calls, failed ones included.
+    pub average_latency: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Backend {
+    calls: u64,
+    errors: u64,
+    total_latency: Duration,
+    last_error_at: Option<Instant>,
+}
+
+/// Spreads requests over several providers, e.g. one per API key, handing each call to
+/// the next provider in turn. A provider that returned a transient error (see
+/// [`LLMError::is_transient`]) within the last `cooldown_ms` is skipped; when every provider is cooling down calls fail with [`LLMError::Unavailable`].
+/// Failed calls are returned as-is rather than retried on another provider.
+pub struct RoundRobinProvider {
+    providers: Vec<Arc<dyn LLMProvider>>,
+    backends: Vec<Mutex<Backend>>,
+    next: AtomicUsize,
+    cooldown: Duration,
+}
+
+impl RoundRobinProvider {
+    pub fn new(providers: Vec<Arc<dyn LLMProvider>>) -> Self {
+        let backends = providers.iter().map(|_| Mutex::new(Backend::default())).collect();
+        Self {
+            providers,
+            backends,
+            next: AtomicUsize::new(0),
+            cooldown: Duration::from_millis(DEFAULT_COOLDOWN_MS),
+        }
+    }
+
+    /// How long a provider is skipped after it returned a transient error.
+    pub fn with_cooldown_ms(mut self, cooldown_ms: u64) -> Self {
+        self.cooldown = Duration::from_millis(cooldown_ms);
+        self
+    }
+
+    pub fn providers(&self) -> &[Arc<dyn LLMProvider>] {
+        &self.providers
+    }
+
+    /// Per-provider statistics, in the order the providers were given.
+    pub fn stats(&self) -> Vec<BackendStats> {
+        self.backends
+            .iter()
+            .map(|backend| {
+                let backend = backend.lock().unwrap();
+                let average_latency = match u32::try_from(backend.calls) {
+                    Ok(calls) if calls > 0 => backend.total_latency / calls,
+                    _ => Duration::ZERO,
+                };
+                BackendStats {
+                    calls: backend.calls,
+                    errors: backend.errors,
+                    average_latency,
+                }
+            })
+            .collect()
+    }
+
+    /// Index of the next provider in turn that is not cooling down.
+    fn pick(&self) -> Option<usize> {
+        let count = self.providers.len();
+        if count == 0 {
+            return None;
+        }
+
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        (0..count).map(|offset| (start + offset) % count).find(|&index| {
+            let backend = self.backends[index].lock().unwrap();
+            backend
+                .last_error_at
+                .is_none_or(|failed_at| failed_at.elapsed() >= self.cooldown)
+        })
+    }
+
+    async fn dispatch<'a, T, F, Fut>(&'a self, call: F) -> Result<T, LLMError>
+    where
+        F: FnOnce(&'a Arc<dyn LLMProvider>) -> Fut,
+        Fut: std::future::Future<Output = Result<T, LLMError>>,
+    {
+        let index = self.pick().ok_or(LLMError::Unavailable)?;
+        let started = Instant::now();
+        let result = call(&self.providers[index]).await;
+
+        let mut backend = self.backends[index].lock().unwrap();
+        backend.calls += 1;
+        backend.total_latency += started.elapsed();
+        if let Err(error) = &result {
+            backend.errors += 1;
+            if error.is_transient() {
+                backend.last_error_at = Some(Instant::now());
+            }
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl LLMProvider for RoundRobinProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.dispatch(|provider| provider.complete(request)).await
+    }
+
+    async fn stream_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionStream, LLMError> {
+        self.dispatch(|provider| provider.stream_completion(request))
+            .await
+    }
+
+    async fn upload_image(
+        &self,
+        request: ImageUploadRequest,
+    ) -> Result<ImageUploadResponse, LLMError> {
+        self.dispatch(|provider| provider.upload_image(request)).await
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, LLMError> {
+        self.dispatch(|provider| provider.create_embeddings(request))
+            .await
+    }
+
+    /// Counted by the first provider; the providers are expected to serve the same models.
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        match self.providers.first() {
+            Some(provider) => provider.count_tokens(request).await,
+            None => Ok(request.estimated_token_count()),
+        }
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        shared_capabilities(&self.providers)
+    }
+
+    fn name(&self) -> &'static str {
+        "round_robin"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{providers::scripted::ScriptedProvider, types::ChatMessage};
+
+    fn backend(failures: usize) -> Arc<ScriptedProvider> {
+        backend_failing_with(failures, LLMError::Provider("429 quota exhausted".to_string()))
+    }
+
+    fn backend_failing_with(failures: usize, error: LLMError) -> Arc<ScriptedProvider> {
+        let mut provider = ScriptedProvider::with_responses(&["ok", "ok", "ok"]);
+        provider.inject_transient_errors(failures, error);
+        Arc::new(provider)
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest::new("m", vec![ChatMessage::user("hi")])
+    }
+
+    #[tokio::test]
+    async fn rotates_through_providers() {
+        let backends = [backend(0), backend(0), backend(0)];
+        let provider = RoundRobinProvider::new(backends.iter().map(|b| b.clone() as Arc<dyn LLMProvider>).collect());
+
+        for _ in 0..6 {
+            provider.complete(request()).await.unwrap();
+        }
+        assert!(backends.iter().all(|b| b.calls() == 2));
+        assert!(provider.stats().iter().all(|stats| stats.calls == 2 && stats.errors == 0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn skips_failed_provider_until_cooldown_passes() {
+        let failing = backend(1);
+        let healthy = backend(0);
+        let provider = RoundRobinProvider::new(vec![failing.clone(), healthy.clone()]).with_cooldown_ms(1_000);
+
+        assert!(provider.complete(request()).await.is_err());
+        provider.complete(request()).await.unwrap();
+        provider.complete(request()).await.unwrap();
+        assert_eq!((failing.calls(), healthy.calls()), (1, 2));
+
+        tokio::time::advance(Duration::from_millis(1_000)).await;
+        for _ in 0..2 {
+            provider.complete(request()).await.unwrap();
+        }
+        assert_eq!((failing.calls(), healthy.calls()), (2, 3));
+
+        let stats = provider.stats();
+        assert_eq!((stats[0].calls, stats[0].errors), (2, 1));
+        assert_eq!((stats[1].calls, stats[1].errors), (3, 0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unavailable_when_all_are_cooling_down() {
+        let provider = RoundRobinProvider::new(vec![backend(1), backend(1)]);
+        assert!(provider.complete(request()).await.is_err());
+        assert!(provider.complete(request()).await.is_err());
+
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(err, LLMError::Unavailable));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn non_transient_errors_do_not_start_a_cooldown() {
+        let rejecting = backend_failing_with(1, LLMError::InvalidFunctionArguments("bad request".to_string()));
+        let provider = RoundRobinProvider::new(vec![rejecting.clone()]);
+
+        assert!(provider.complete(request()).await.is_err());
+        provider.complete(request()).await.unwrap();
+        assert_eq!(rejecting.calls(), 2);
+        assert_eq!(provider.stats()[0].errors, 1);
+    }
+}
@@ -0,0 +1,256 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::Level;
+
+use crate::{
+    history::{CharEstimateTokenCounter, TokenCounter},
+    providers::LLMProvider,
+    types::{
+        ChatMessage, CompletionRequest, CompletionResponse, CompletionStream, EmbeddingRequest,
+        EmbeddingResponse, ImageUploadRequest, ImageUploadResponse, ModelInfo,
+        ProviderCapabilities,
+    },
+    LLMError,
+};
+
+const PREVIEW_CHARS: usize = 200;
+
+type Redaction = Arc<dyn Fn(&[ChatMessage]) -> Vec<ChatMessage> + Send + Sync>;
+
+/// Emits a tracing event at a level chosen at runtime.
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)+) => {
+        match $level {
+            Level::TRACE => tracing::event!(Level::TRACE, $($arg)+),
+            Level::DEBUG => tracing::event!(Level::DEBUG, $($arg)+),
+            Level::INFO => tracing::event!(Level::INFO, $($arg)+),
+            Level::WARN => tracing::event!(Level::WARN, $($arg)+),
+            Level::ERROR => tracing::event!(Level::ERROR, $($arg)+),
+        }
+    };
+}
+
+/// Wraps a provider and logs every completion request and response through `tracing`.
+pub struct LoggingProvider<P: LLMProvider> {
+    inner: P,
+    log_level: Level,
+    redaction: Option<Redaction>,
+}
+
+impl<P: LLMProvider> LoggingProvider<P> {
+    pub fn new(inner: P, log_level: Level) -> Self {
+        Self {
+            inner,
+            log_level,
+            redaction: None,
+        }
+    }
+
+    /// Rewrite messages before any of their content reaches the log.
+    pub fn with_redaction<F>(mut self, redaction: F) -> Self
+    where
+        F: Fn(&[ChatMessage]) -> Vec<ChatMessage> + Send + Sync + 'static,
+    {
+        self.redaction = Some(Arc::new(redaction));
+        self
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn loggable(&self, messages: &[ChatMessage]) -> Vec<ChatMessage> {
+        match &self.redaction {
+            Some(redact) => redact(messages),
+            None => messages.to_vec(),
+        }
+    }
+
+    fn log_request(&self, request: &CompletionRequest) {
+        let messages = self.loggable(&request.messages);
+        let last_message = messages
+            .last()
+            .and_then(ChatMessage::text)
+            .map(preview)
+            .unwrap_or_default();
+
+        log_at!(
+            self.log_level,
+            provider = self.inner.name(),
+            model = %request.model,
+            message_count = request.messages.len(),
+            estimated_input_tokens = CharEstimateTokenCounter.count(&request.messages),
+            has_tools = !request.tools.is_empty(),
+            last_message = %last_message,
+            "llm request"
+        );
+    }
+
+    fn log_response(&self, model: &str, response: &CompletionResponse) {
+        let message = self
+            .loggable(std::slice::from_ref(&response.message))
+            .into_iter()
+            .next();
+        let text = message
+            .as_ref()
+            .and_then(ChatMessage::text)
+            .map(preview)
+            .unwrap_or_default();
+        let finish_reason = response
+            .reasoning
+            .as_ref()
+            .and_then(|traces| traces.iter().find_map(|trace| trace.finish_reason.clone()))
+            .unwrap_or_else(|| {
+                if response.message.tool_calls.is_empty() {
+                    "stop".to_string()
+                } else {
+                    "tool_calls".to_string()
+                }
+            });
+        let usage = response.usage.as_ref();
+
+        log_at!(
+            self.log_level,
+            provider = self.inner.name(),
+            model = %model,
+            finish_reason = %finish_reason,
+            prompt_tokens = usage.map(|u| u.prompt_tokens),
+            completion_tokens = usage.map(|u| u.completion_tokens),
+            total_tokens = usage.map(|u| u.total_tokens),
+            response = %text,
+            "llm response"
+        );
+    }
+}
+
+fn preview(text: &str) -> String {
+    match text.char_indices().nth(PREVIEW_CHARS) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for LoggingProvider<P> {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.log_request(&request);
+        let model = request.model.clone();
+
+        match self.inner.complete(request).await {
+            Ok(response) => {
+                self.log_response(&model, &response);
+                Ok(response)
+            }
+            Err(error) => {
+                log_at!(
+                    self.log_level,
+                    provider = self.inner.name(),
+                    model = %model,
+                    error = %error,
+                    "llm request failed"
+                );
+                Err(error)
+            }
+        }
+    }
+
+    async fn stream_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionStream, LLMError> {
+        self.log_request(&request);
+        self.inner.stream_completion(request).await
+    }
+
+    async fn upload_image(
+        &self,
+        request: ImageUploadRequest,
+    ) -> Result<ImageUploadResponse, LLMError> {
+        self.inner.upload_image(request).await
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, LLMError> {
+        self.inner.create_embeddings(request).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
+        self.inner.model_info(id).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LLMError> {
+        self.inner.list_models().await
+    }
+
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        self.inner.count_tokens(request).await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::scripted::ScriptedProvider;
+    use tracing_test::traced_test;
+
+    fn provider() -> LoggingProvider<ScriptedProvider> {
+        let scripted = ScriptedProvider::with_responses(&["The capital is Paris"]);
+        LoggingProvider::new(scripted, Level::DEBUG)
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn logs_model_and_message_count() {
+        let request = CompletionRequest::new(
+            "gpt-test-model",
+            vec![ChatMessage::system("Be brief."), ChatMessage::user("Capital of France?")],
+        );
+        provider().complete(request).await.unwrap();
+
+        assert!(logs_contain("model=gpt-test-model"));
+        assert!(logs_contain("message_count=2"));
+        assert!(logs_contain("has_tools=false"));
+        assert!(logs_contain("response=The capital is Paris"));
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn redaction_runs_before_logging() {
+        let provider = provider().with_redaction(|messages| {
+            messages
+                .iter()
+                .map(|message| ChatMessage {
+                    content: Some("[redacted]".to_string()),
+                    ..message.clone()
+                })
+                .collect()
+        });
+        let request = CompletionRequest::new(
+            "gpt-test-model",
+            vec![ChatMessage::user("my card is 4111 1111 1111 1111")],
+        );
+        provider.complete(request).await.unwrap();
+
+        assert!(logs_contain("last_message=[redacted]"));
+        assert!(!logs_contain("4111"));
+        assert!(!logs_contain("Paris"));
+    }
+
+    #[test]
+    fn previews_are_truncated() {
+        let long = "x".repeat(500);
+        assert_eq!(preview(&long).chars().count(), PREVIEW_CHARS + 1);
+        assert_eq!(preview("short"), "short");
+    }
+}
@@ -0,0 +1,250 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+
+use crate::{
+    functions::ToolCall,
+    providers::LLMProvider,
+    types::{ChatMessage, CompletionRequest, CompletionResponse, MessageRole},
+    LLMError,
+};
+
+/// What a [`MockLLMProvider`] answers with: plain assistant text or a complete response.
+#[derive(Debug, Clone)]
+pub enum MockResponse {
+    Text(String),
+    Completion(Box<CompletionResponse>),
+}
+
+impl MockResponse {
+    fn to_completion(&self) -> CompletionResponse {
+        match self {
+            MockResponse::Text(text) => CompletionResponse {
+                message: ChatMessage::assistant(text.clone()),
+                usage: None,
+                reasoning: None,
+            },
+            MockResponse::Completion(response) => response.as_ref().clone(),
+        }
+    }
+}
+
+impl From<&str> for MockResponse {
+    fn from(text: &str) -> Self {
+        MockResponse::Text(text.to_string())
+    }
+}
+
+impl From<String> for MockResponse {
+    fn from(text: String) -> Self {
+        MockResponse::Text(text)
+    }
+}
+
+impl From<CompletionResponse> for MockResponse {
+    fn from(response: CompletionResponse) -> Self {
+        MockResponse::Completion(Box::new(response))
+    }
+}
+
+struct Expectation {
+    needle: String,
+    response: MockResponse,
+    expected_times: Option<usize>,
+    times_matched: AtomicUsize,
+}
+
+/// Test provider that answers based on request content instead of turn order.
+///
+/// Expectations are matched against the last message of a request, and only when that
+/// message comes from the user; the first matching expectation in registration order
+/// answers. Anything else, such as the follow-up request carrying a tool result, gets the
+/// [`MockLLMProvider::default_response`].
+#[derive(Default)]
+pub struct MockLLMProvider {
+    expectations: Vec<Expectation>,
+    default_response: Option<MockResponse>,
+}
+
+impl MockLLMProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer with `response` when the user's message contains `needle`.
+    pub fn expect_message_containing(
+        &mut self,
+        needle: &str,
+        response: impl Into<MockResponse>,
+    ) -> &mut Self {
+        self.expectations.push(Expectation {
+            needle: needle.to_string(),
+            response: response.into(),
+            expected_times: None,
+            times_matched: AtomicUsize::new(0),
+        });
+        self
+    }
+
+    /// Answer with a call of `response` when the user's message mentions `function_name`.
+    pub fn expect_tool_call_request(&mut self, function_name: &str, response: ToolCall) -> &mut Self {
+        let mut message = ChatMessage::assistant("");
+        message.content = None;
+        message.tool_calls = vec![response];
+        self.expect_message_containing(
+            function_name,
+            CompletionResponse {
+                message,
+                usage: None,
+                reasoning: None,
+            },
+        )
+    }
+
+    /// Require the most recently added expectation to match exactly `times` times; checked
+    /// by [`MockLLMProvider::verify`].
+    pub fn expect_exactly(&mut self, times: usize) -> &mut Self {
+        if let Some(expectation) = self.expectations.last_mut() {
+            expectation.expected_times = Some(times);
+        }
+        self
+    }
+
+    /// Answer requests no expectation matches. Without one, such requests fail.
+    pub fn default_response(&mut self, response: impl Into<MockResponse>) -> &mut Self {
+        self.default_response = Some(response.into());
+        self
+    }
+
+    /// How often the expectation at `index` (in registration order) has matched.
+    pub fn times_matched(&self, index: usize) -> usize {
+        self.expectations
+            .get(index)
+            .map_or(0, |expectation| expectation.times_matched.load(Ordering::SeqCst))
+    }
+
+    /// Panics if an expectation registered with [`MockLLMProvider::expect_exactly`] did not
+    /// match the required number of times.
+    pub fn verify(&self) {
+        let failures: Vec<String> = self
+            .expectations
+            .iter()
+            .filter_map(|expectation| {
+                let expected = expectation.expected_times?;
+                let matched = expectation.times_matched.load(Ordering::SeqCst);
+                (matched != expected).then(|| {
+                    format!(
+                        "expectation for `{}` matched {matched} times, expected {expected}",
+                        expectation.needle
+                    )
+                })
+            })
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    fn respond(&self, request: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let user_input = request
+            .messages
+            .last()
+            .filter(|message| message.role == MessageRole::User)
+            .and_then(|message| message.text());
+
+        let matched = user_input.and_then(|input| {
+            self.expectations
+                .iter()
+                .find(|expectation| input.contains(expectation.needle.as_str()))
+        });
+        if let Some(expectation) = matched {
+            expectation.times_matched.fetch_add(1, Ordering::SeqCst);
+            return Ok(expectation.response.to_completion());
+        }
+
+        self.default_response
+            .as_ref()
+            .map(MockResponse::to_completion)
+            .ok_or_else(|| LLMError::Provider("no mock expectation matched the request".to_string()))
+    }
+}
+
+#[async_trait]
+impl LLMProvider for MockLLMProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.respond(&request)
+    }
+
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::functions::FunctionCall;
+
+    async fn reply(provider: &MockLLMProvider, messages: Vec<ChatMessage>) -> Result<ChatMessage, LLMError> {
+        provider
+            .complete(CompletionRequest::new("mock", messages))
+            .await
+            .map(|response| response.message)
+    }
+
+    #[tokio::test]
+    async fn tool_call_expectation_matches_and_verifies() {
+        let mut provider = MockLLMProvider::new();
+        provider
+            .expect_tool_call_request(
+                "calculate",
+                ToolCall::new(FunctionCall::new("calculate", json!({ "expression": "2 + 3" })))
+                    .with_id("call_1"),
+            )
+            .expect_exactly(1)
+            .expect_message_containing("hello", "hi there")
+            .default_response("The answer is 5");
+
+        let question = ChatMessage::user("please calculate 2 + 3");
+        let call = reply(&provider, vec![question.clone()]).await.unwrap();
+        assert_eq!(call.tool_calls.len(), 1);
+        assert_eq!(call.tool_calls[0].function.name, "calculate");
+
+        let answer = reply(&provider, vec![question, call, ChatMessage::tool("call_1", "5")])
+            .await
+            .unwrap();
+        assert_eq!(answer.text(), Some("The answer is 5"));
+
+        let greeting = reply(&provider, vec![ChatMessage::user("hello")]).await.unwrap();
+        assert_eq!(greeting.text(), Some("hi there"));
+        assert_eq!(provider.times_matched(0), 1);
+        assert_eq!(provider.times_matched(1), 1);
+        provider.verify();
+    }
+
+    #[tokio::test]
+    async fn first_matching_expectation_wins() {
+        let mut provider = MockLLMProvider::new();
+        provider
+            .expect_message_containing("weather", "sunny")
+            .expect_message_containing("weather in Berlin", "cloudy");
+
+        let reply = reply(&provider, vec![ChatMessage::user("weather in Berlin?")]).await.unwrap();
+        assert_eq!(reply.text(), Some("sunny"));
+        assert_eq!(provider.times_matched(1), 0);
+    }
+
+    #[tokio::test]
+    async fn unmatched_request_without_default_fails() {
+        let provider = MockLLMProvider::new();
+        assert!(reply(&provider, vec![ChatMessage::user("anything")]).await.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "matched 0 times, expected 2")]
+    fn verify_panics_on_unmet_expectation() {
+        let mut provider = MockLLMProvider::new();
+        provider.expect_message_containing("ping", "pong").expect_exactly(2);
+        provider.verify();
+    }
+}
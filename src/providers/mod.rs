@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use serde::Deserialize;
 
 use crate::types::{
-    CompletionRequest, CompletionResponse, CompletionStream, ImageUploadRequest,
+    AudioFormat, ChatMessage, CompletionRequest, CompletionResponse, CompletionStream, ContentPart, CorrelationId, ImageUploadRequest,
     ImageUploadResponse, ProviderCapabilities, EmbeddingRequest, EmbeddingResponse, ModelInfo,
 };
 use crate::LLMError;
@@ -10,8 +10,24 @@ use crate::LLMError;
 pub mod openai;
 pub mod openrouter;
 pub mod ollama;
+pub mod gemini;
+pub mod groq;
 pub mod scripted;
 pub mod azure_openai;
+pub mod registry;
+#[cfg(feature = "tracing")]
+pub mod logging;
+pub mod proxy;
+pub mod retry;
+pub mod cache;
+pub mod circuit_breaker;
+pub mod dedup;
+pub mod fallback;
+pub mod load_balancer;
+pub mod rate_limit;
+pub mod mock;
+#[cfg(feature = "recording")]
+pub mod recording;
 
 /// A single content block in a streaming delta. All OpenAI-compatible APIs use this shape
 /// for structured content, but the standard chat completions API sends `delta.content` as
@@ -163,6 +179,76 @@ pub(crate) fn parse_text_tool_calls(content: &str) -> (Vec<crate::functions::Too
     (calls, cleaned)
 }
 
+/// `X-Request-ID` header for a request's correlation id; empty when the request has none.
+pub(crate) fn request_id_headers(correlation_id: Option<CorrelationId>) -> reqwest::header::HeaderMap {
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Some(id) = correlation_id {
+        if let Ok(value) = reqwest::header::HeaderValue::from_str(&id.to_string()) {
+            headers.insert("X-Request-ID", value);
+        }
+    }
+    headers
+}
+
+/// OpenAI-style content parts for a message's file and audio attachments. File parts are
+/// dropped with a warning unless `supports_files`, since they reference uploads on the
+/// provider's side.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn attachment_content_parts(
+    msg: &ChatMessage,
+    provider: &str,
+    supports_files: bool,
+) -> Vec<serde_json::Value> {
+    msg.attachments
+        .iter()
+        .filter_map(|attachment| match attachment {
+            ContentPart::File { file_id, name } if supports_files => Some(serde_json::json!({
+                "type": "file",
+                "file": { "file_id": file_id, "filename": name },
+            })),
+            #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+            ContentPart::File { name, .. } => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(provider, file = %name, "provider does not support file attachments; dropping");
+                None
+            }
+            ContentPart::Audio { url, format } => Some(serde_json::json!({
+                "type": "input_audio",
+                "input_audio": { "data": base64_payload(url), "format": format.as_str() },
+            })),
+        })
+        .collect()
+}
+
+/// Fail on audio attachments in a format OpenAI's `input_audio` does not take; it only
+/// accepts wav and mp3.
+pub(crate) fn ensure_openai_audio_formats(messages: &[ChatMessage]) -> Result<(), LLMError> {
+    let unsupported = messages
+        .iter()
+        .flat_map(|message| &message.attachments)
+        .any(|attachment| matches!(attachment, ContentPart::Audio { format: AudioFormat::Webm, .. }));
+    if unsupported {
+        return Err(LLMError::Unsupported("webm audio input; convert it to wav or mp3"));
+    }
+    Ok(())
+}
+
+/// Delay asked for by a `Retry-After` header, given either in seconds or as an HTTP date.
+pub(crate) fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<std::time::Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(std::time::Duration::from_secs(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().ok()
+}
+
+/// Strip the `data:...;base64,` prefix from a data URL; other strings are returned as-is.
+fn base64_payload(url: &str) -> &str {
+    url.find("base64,")
+        .map_or(url, |index| &url[index + "base64,".len()..])
+}
+
 /// Pop one SSE event (terminated by `\n\n` or `\r\n\r\n`) from a byte buffer.
 /// Returns `None` if no complete event is buffered yet.
 pub(crate) fn extract_sse_event(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
@@ -181,6 +267,45 @@ pub(crate) fn extract_sse_event(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
     None
 }
 
+/// Whether streamed tool-call arguments form a complete JSON object, i.e. the outer braces
+/// are balanced. Braces inside string literals are ignored.
+pub(crate) fn is_balanced_json_object(arguments: &str) -> bool {
+    let trimmed = arguments.trim();
+    if !trimmed.starts_with('{') {
+        return false;
+    }
+
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (offset, ch) in trimmed.char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match ch {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return offset + 1 == trimmed.len();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
 /// Join all `data:` lines from an SSE event into a single payload string.
 pub(crate) fn extract_data_payload(event: &[u8]) -> Result<String, LLMError> {
     let text = std::str::from_utf8(event)
@@ -236,13 +361,73 @@ pub trait LLMProvider: Send + Sync {
         Err(LLMError::Unsupported("model list"))
     }
 
+    /// Prompt tokens `request` would use, without running the completion. Defaults to
+    /// [`CompletionRequest::estimated_token_count`]; providers that know their tokenizer
+    /// return an exact count.
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        Ok(request.estimated_token_count())
+    }
+
     fn name(&self) -> &'static str;
 }
 
+/// Minimal embedding surface used by components that only need vectors (e.g. semantic
+/// history retrieval). Any [`LLMProvider`] can be adapted through [`ProviderEmbedder`].
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Embed each input string, returning one vector per input in the same order.
+    async fn embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError>;
+}
+
+/// Adapts an [`LLMProvider`] embedding endpoint to [`EmbeddingProvider`].
+pub struct ProviderEmbedder {
+    provider: std::sync::Arc<dyn LLMProvider>,
+    model: String,
+}
+
+impl ProviderEmbedder {
+    pub fn new(provider: std::sync::Arc<dyn LLMProvider>, model: impl Into<String>) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for ProviderEmbedder {
+    async fn embed(&self, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        let expected = inputs.len();
+        let response = self
+            .provider
+            .create_embeddings(EmbeddingRequest::new(self.model.clone(), inputs))
+            .await?;
+
+        let mut data = response.data;
+        data.sort_by_key(|embedding| embedding.index);
+        if data.len() != expected {
+            return Err(LLMError::InvalidResponse("embedding count did not match input count"));
+        }
+
+        Ok(data.into_iter().map(|embedding| embedding.embedding).collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn detects_balanced_tool_call_arguments() {
+        assert!(is_balanced_json_object(r#"{"city": "Berlin"}"#));
+        assert!(is_balanced_json_object(r#"{"a": {"b": [1, 2]}}"#));
+        assert!(is_balanced_json_object(r#"{"text": "closing } brace \" quote"}"#));
+        assert!(!is_balanced_json_object(r#"{"city": "Ber"#));
+        assert!(!is_balanced_json_object(r#"{"text": "}"#));
+        assert!(!is_balanced_json_object(""));
+        assert!(!is_balanced_json_object("[1]"));
+    }
+
     #[test]
     fn parse_kimi_k2_tool_calls() {
         let content = "Some preamble text\n<|tool_calls_section_begin|>\n<|tool_call_begin|>functions.code_execution:13<|tool_call_argument_begin|>{\"language\": \"bash\", \"code\": \"echo hello\"}<|tool_call_end|>\n<|tool_calls_section_end|>";
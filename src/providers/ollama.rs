@@ -8,7 +8,11 @@
 //!
 //! The native streaming protocol is NDJSON (one JSON object per line), not SSE.
 
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use async_stream::try_stream;
 use async_trait::async_trait;
@@ -50,6 +54,8 @@ impl Default for ThinkMode {
 #[derive(Debug, Clone)]
 pub struct OllamaConfig {
     pub base_url: String,
+    /// Model used when a request leaves `model` empty.
+    pub model: Option<String>,
     pub request_timeout: Duration,
     pub keep_alive: String,
     pub num_ctx: Option<u32>,
@@ -63,6 +69,7 @@ impl OllamaConfig {
     pub fn new() -> Self {
         Self {
             base_url: DEFAULT_BASE_URL.to_string(),
+            model: None,
             request_timeout: Duration::from_secs(120),
             keep_alive: "30m".to_string(),
             num_ctx: None,
@@ -76,6 +83,11 @@ impl OllamaConfig {
         self
     }
 
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
     pub fn with_keep_alive(mut self, duration: impl Into<String>) -> Self {
         self.keep_alive = duration.into();
         self
@@ -107,6 +119,9 @@ impl Default for OllamaConfig {
 pub struct Ollama {
     client: Client,
     config: OllamaConfig,
+    /// Capabilities reported by `/api/show`, keyed by model.
+    model_capabilities: Arc<Mutex<HashMap<String, ModelCapabilities>>>,
+    active_model: Arc<Mutex<Option<String>>>,
 }
 
 impl Ollama {
@@ -114,9 +129,19 @@ impl Ollama {
         Self::from_config(OllamaConfig::new())
     }
 
+    /// Provider for `model` served at `base_url`, e.g. `http://localhost:11434`.
+    pub fn for_model(base_url: impl Into<String>, model: impl Into<String>) -> Result<Self, LLMError> {
+        Self::from_config(OllamaConfig::new().with_base_url(base_url).with_model(model))
+    }
+
     pub fn from_config(config: OllamaConfig) -> Result<Self, LLMError> {
         let client = Client::builder().timeout(config.request_timeout).build()?;
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config,
+            model_capabilities: Arc::new(Mutex::new(HashMap::new())),
+            active_model: Arc::new(Mutex::new(None)),
+        })
     }
 
     pub fn from_env() -> Result<Self, LLMError> {
@@ -126,6 +151,7 @@ impl Ollama {
             std::env::var("OLLAMA_KEEP_ALIVE").unwrap_or_else(|_| "30m".to_string());
         let config = OllamaConfig {
             base_url,
+            model: std::env::var("OLLAMA_MODEL").ok(),
             keep_alive,
             ..OllamaConfig::new()
         };
@@ -144,6 +170,48 @@ impl Ollama {
         builder.header("Content-Type", "application/json")
     }
 
+    /// Spell out the common case of no local server next to the bare connection error, which
+    /// is kept as [`LLMError::Http`] so callers still see it as transient.
+    fn request_error(&self, err: reqwest::Error) -> LLMError {
+        #[cfg(feature = "tracing")]
+        if err.is_connect() {
+            tracing::warn!(
+                base_url = %self.config.base_url,
+                error = %err,
+                "Ollama server is unreachable; is `ollama serve` running?"
+            );
+        }
+        LLMError::Http(err)
+    }
+
+    fn resolve_model(&self, model: String) -> Result<String, LLMError> {
+        if !model.is_empty() {
+            return Ok(model);
+        }
+        self.config.model.clone().ok_or_else(|| {
+            LLMError::Provider("no model given in the request or OllamaConfig::with_model".to_string())
+        })
+    }
+
+    fn set_active_model(&self, model: &str) {
+        *self.active_model.lock().unwrap() = Some(model.to_string());
+    }
+
+    /// Look up the active model (the last one requested, else the configured one) with
+    /// `/api/show` so [`LLMProvider::capabilities`] reports what it actually supports.
+    pub async fn probe_capabilities(&self) -> Result<ProviderCapabilities, LLMError> {
+        let model = self
+            .active_model
+            .lock()
+            .unwrap()
+            .clone()
+            .or_else(|| self.config.model.clone())
+            .ok_or_else(|| LLMError::Provider("no model to probe".to_string()))?;
+        self.set_active_model(&model);
+        self.model_info(&model).await?;
+        Ok(self.capabilities())
+    }
+
     /// Fetch the model's max context length (tokens) from `/api/show`.
     /// Returns `None` if the server doesn't advertise one for this model.
     pub async fn max_context_length(&self, model: &str) -> Result<Option<u32>, LLMError> {
@@ -151,7 +219,8 @@ impl Ollama {
             .prepare(self.client.post(self.endpoint("api/show")))
             .json(&json!({ "model": model }))
             .send()
-            .await?;
+            .await
+            .map_err(|err| self.request_error(err))?;
 
         let status = response.status();
         if !status.is_success() {
@@ -191,8 +260,12 @@ impl Ollama {
             tools,
             tool_choice: _,
             reasoning_effort,
+            correlation_id: _,
         } = request;
 
+        let model = self.resolve_model(model)?;
+        self.set_active_model(&model);
+
         let mut body = Map::new();
         body.insert("model".into(), Value::String(model));
         body.insert(
@@ -280,6 +353,14 @@ fn message_to_ollama(msg: &ChatMessage, preserve_thinking: bool) -> Result<Value
         obj.insert("images".into(), Value::Array(images?));
     }
 
+    #[cfg(feature = "tracing")]
+    if !msg.attachments.is_empty() {
+        tracing::warn!(
+            count = msg.attachments.len(),
+            "ollama does not support file or audio attachments; dropping"
+        );
+    }
+
     if matches!(msg.role, MessageRole::Assistant) {
         if preserve_thinking {
             if let Some(thinking) = &msg.thinking {
@@ -549,7 +630,10 @@ fn assistant_message_from(
         tool_call_id: None,
         tool_calls,
         images: Vec::new(),
+        attachments: Vec::new(),
         thinking: thinking.filter(|s| !s.is_empty()),
+        created_at: None,
+        metadata: None,
     }
 }
 
@@ -563,13 +647,16 @@ impl LLMProvider for Ollama {
         &self,
         request: CompletionRequest,
     ) -> Result<CompletionResponse, LLMError> {
+        let correlation_id = request.correlation_id;
         let body = self.build_chat_body(request, false)?;
 
         let response = self
             .prepare(self.client.post(self.endpoint("api/chat")))
+            .headers(super::request_id_headers(correlation_id))
             .json(&body)
             .send()
-            .await?;
+            .await
+            .map_err(|err| self.request_error(err))?;
 
         let status = response.status();
         if !status.is_success() {
@@ -622,13 +709,16 @@ impl LLMProvider for Ollama {
         &self,
         request: CompletionRequest,
     ) -> Result<CompletionStream, LLMError> {
+        let correlation_id = request.correlation_id;
         let body = self.build_chat_body(request, true)?;
 
         let response = self
             .prepare(self.client.post(self.endpoint("api/chat")))
+            .headers(super::request_id_headers(correlation_id))
             .json(&body)
             .send()
-            .await?;
+            .await
+            .map_err(|err| self.request_error(err))?;
 
         let status = response.status();
         if !status.is_success() {
@@ -731,7 +821,10 @@ impl LLMProvider for Ollama {
                 tool_call_id: None,
                 tool_calls,
                 images: Vec::new(),
+                attachments: Vec::new(),
                 thinking: if thinking_buf.is_empty() { None } else { Some(thinking_buf) },
+                created_at: None,
+                metadata: None,
             };
 
             yield StreamEvent::Completed(CompletionResponse {
@@ -757,7 +850,8 @@ impl LLMProvider for Ollama {
             .prepare(self.client.post(self.endpoint("api/embed")))
             .json(&body)
             .send()
-            .await?;
+            .await
+            .map_err(|err| self.request_error(err))?;
 
         let status = response.status();
         if !status.is_success() {
@@ -795,8 +889,19 @@ impl LLMProvider for Ollama {
         })
     }
 
+    /// Tool and image support follow the active model once `/api/show` has reported on it
+    /// (see [`Ollama::probe_capabilities`]); until then tools are assumed.
     fn capabilities(&self) -> ProviderCapabilities {
-        ProviderCapabilities::new(true, true, false, true)
+        let defaults = ProviderCapabilities::new(true, true, false, true).with_structured_output(true);
+        let active_model = self.active_model.lock().unwrap().clone();
+        let probed = active_model.and_then(|model| self.model_capabilities.lock().unwrap().get(&model).cloned());
+        match probed {
+            Some(caps) => ProviderCapabilities {
+                supports_image_uploads: caps.supports_vision,
+                ..defaults.with_tools(caps.supports_tools)
+            },
+            None => defaults,
+        }
     }
 
     async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
@@ -804,7 +909,8 @@ impl LLMProvider for Ollama {
             .prepare(self.client.post(self.endpoint("api/show")))
             .json(&json!({ "model": id }))
             .send()
-            .await?;
+            .await
+            .map_err(|err| self.request_error(err))?;
 
         let status = response.status();
         if !status.is_success() {
@@ -819,19 +925,25 @@ impl LLMProvider for Ollama {
 
         let max_context = max_context_from_model_info(&parsed.model_info);
 
-        Ok(model_info_from_show(
+        let info = model_info_from_show(
             id,
             parsed.capabilities,
             parsed.details.unwrap_or_default(),
             max_context,
-        ))
+        );
+        self.model_capabilities
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), info.capabilities.clone());
+        Ok(info)
     }
 
     async fn list_models(&self) -> Result<Vec<ModelInfo>, LLMError> {
         let response = self
             .prepare(self.client.get(self.endpoint("api/tags")))
             .send()
-            .await?;
+            .await
+            .map_err(|err| self.request_error(err))?;
 
         let status = response.status();
         if !status.is_success() {
@@ -1029,6 +1141,14 @@ mod tests {
         assert_eq!(tc["function"]["arguments"]["city"], "sf");
     }
 
+    #[test]
+    fn attachments_are_dropped() {
+        let msg = ChatMessage::user_with_file("Summarize", "file-123", "report.pdf");
+        let v = message_to_ollama(&msg, false).unwrap();
+        assert_eq!(v["content"], "Summarize");
+        assert!(v.get("file").is_none());
+    }
+
     #[test]
     fn think_mode_resolution() {
         let auto = Ollama::from_config(OllamaConfig::new()).unwrap();
@@ -1109,4 +1229,83 @@ mod tests {
         assert_eq!(u.total_tokens, 15);
         assert!(usage_from_counts(None, None).is_none());
     }
+
+    #[tokio::test]
+    async fn unreachable_server_is_reported() {
+        let ollama = Ollama::for_model("http://127.0.0.1:1", "llama3").unwrap();
+        let request = CompletionRequest::new("", vec![ChatMessage::user("hi")]);
+        let err = ollama.complete(request).await.unwrap_err();
+        assert!(matches!(&err, LLMError::Http(error) if error.is_connect()), "{err}");
+        assert!(err.is_transient());
+    }
+
+    #[tokio::test]
+    async fn configured_model_is_used_and_streamed() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let ndjson = concat!(
+            r#"{"message":{"role":"assistant","content":"Hel"},"done":false}"#,
+            "\n",
+            r#"{"message":{"role":"assistant","content":"lo"},"done":true,"eval_count":2}"#,
+            "\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({
+                "model": "mixtral",
+                "stream": true,
+                "messages": [{ "role": "system", "content": "Be brief." }, { "role": "user", "content": "hi" }],
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_string(ndjson))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let ollama = Ollama::for_model(server.uri(), "mixtral").unwrap();
+        let request = CompletionRequest::new("", vec![ChatMessage::system("Be brief."), ChatMessage::user("hi")]);
+        let events: Vec<_> = ollama.stream_completion(request).await.unwrap().collect().await;
+
+        let deltas: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                Ok(StreamEvent::MessageDelta(text)) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deltas, ["Hel", "lo"]);
+        assert!(matches!(
+            events.last(),
+            Some(Ok(StreamEvent::Completed(response))) if response.message.text() == Some("Hello")
+        ));
+    }
+
+    #[tokio::test]
+    async fn capabilities_follow_probed_model() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/show"))
+            .and(body_partial_json(json!({ "model": "llama2" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "capabilities": ["completion"] })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/show"))
+            .and(body_partial_json(json!({ "model": "llama3" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "capabilities": ["completion", "tools"] })))
+            .mount(&server)
+            .await;
+
+        let ollama = Ollama::for_model(server.uri(), "llama2").unwrap();
+        assert!(ollama.capabilities().supports_tools);
+        assert!(!ollama.probe_capabilities().await.unwrap().supports_tools);
+        assert!(!ollama.capabilities().supports_tools);
+
+        ollama.set_active_model("llama3");
+        assert!(ollama.probe_capabilities().await.unwrap().supports_tools);
+    }
 }
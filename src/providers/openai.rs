@@ -127,15 +127,17 @@ impl OpenAI {
 }
 
 /// Convert a `ChatMessage` to a JSON `Value`, building a multimodal content
-/// array when the message carries image attachments.
+/// array when the message carries image, file or audio attachments.
 fn chat_message_to_json(msg: &ChatMessage) -> Value {
-    if msg.images.is_empty() {
+    let attachments = super::attachment_content_parts(msg, "openai", true);
+    if msg.images.is_empty() && attachments.is_empty() {
         // Fast path: normal text-only message.
         return serde_json::to_value(msg).unwrap_or_default();
     }
 
-    // Build multimodal content array: text block + image blocks.
-    let mut content_parts: Vec<Value> = Vec::with_capacity(1 + msg.images.len());
+    // Build multimodal content array: text block + image and attachment blocks.
+    let mut content_parts: Vec<Value> =
+        Vec::with_capacity(1 + msg.images.len() + attachments.len());
     if let Some(text) = &msg.content {
         content_parts.push(serde_json::json!({
             "type": "text",
@@ -148,6 +150,7 @@ fn chat_message_to_json(msg: &ChatMessage) -> Value {
             "image_url": { "url": image_url },
         }));
     }
+    content_parts.extend(attachments);
 
     let mut obj = serde_json::json!({
         "role": msg.role,
@@ -249,6 +252,7 @@ struct ToolCallAccumulator {
     id: Option<String>,
     name: Option<String>,
     arguments: String,
+    emitted: bool,
 }
 
 impl ToolCallAccumulator {
@@ -272,6 +276,17 @@ impl ToolCallAccumulator {
         }
     }
 
+    /// Returns the finished call the first time its arguments form a balanced JSON object.
+    fn take_completed(&mut self) -> Option<ToolCall> {
+        if self.emitted || self.name.is_none() || !super::is_balanced_json_object(&self.arguments) {
+            return None;
+        }
+
+        let call = self.clone().build().ok()?;
+        self.emitted = true;
+        Some(call)
+    }
+
     fn build(self) -> Result<ToolCall, LLMError> {
         let name = self
             .name
@@ -368,12 +383,36 @@ fn body_without_temperature(body: &OpenAIRequestBody) -> Result<Value, LLMError>
     Ok(value)
 }
 
+/// Error for an unsuccessful response, using the message of an OpenAI error body when there
+/// is one. A 429 keeps the delay from its `Retry-After` header.
+fn status_error(status: StatusCode, retry_after: Option<Duration>, text: &str) -> LLMError {
+    let message = serde_json::from_str::<OpenAIErrorEnvelope>(text)
+        .map(|error| error.error.message)
+        .unwrap_or_else(|_| format!("unexpected status {status}: {text}"));
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        LLMError::RateLimited { message, retry_after }
+    } else {
+        LLMError::Provider(message)
+    }
+}
+
+/// Exact prompt tokens for OpenAI models when the `tiktoken` feature is on, the offline
+/// estimate otherwise. Shared with the Azure provider, whose deployments run the same models.
+pub(crate) fn openai_token_count(request: &CompletionRequest) -> u32 {
+    #[cfg(feature = "tiktoken")]
+    if let Some(counter) = crate::history::tiktoken::shared_counter(&request.model) {
+        return counter.count_request(request);
+    }
+    request.estimated_token_count()
+}
+
 #[async_trait]
 impl LLMProvider for OpenAI {
     async fn complete(
         &self,
         request: CompletionRequest,
     ) -> Result<CompletionResponse, LLMError> {
+        super::ensure_openai_audio_formats(&request.messages)?;
         let CompletionRequest {
             model,
             messages,
@@ -384,6 +423,7 @@ impl LLMProvider for OpenAI {
             tools,
             tool_choice,
             reasoning_effort,
+            correlation_id,
         } = request;
 
         let body = OpenAIRequestBody {
@@ -401,17 +441,20 @@ impl LLMProvider for OpenAI {
 
         let builder = self
             .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+            .headers(super::request_id_headers(correlation_id))
             .json(&body);
 
         let mut response = builder.send().await?;
         let mut status = response.status();
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
             if should_retry_with_completion_tokens(status, &text) {
                 let fallback_body = body_with_max_completion_tokens(&body)?;
                 response = self
                     .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+                    .headers(super::request_id_headers(correlation_id))
                     .json(&fallback_body)
                     .send()
                     .await?;
@@ -420,24 +463,20 @@ impl LLMProvider for OpenAI {
                 let fallback_body = body_without_temperature(&body)?;
                 response = self
                     .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+                    .headers(super::request_id_headers(correlation_id))
                     .json(&fallback_body)
                     .send()
                     .await?;
                 status = response.status();
-            } else if let Ok(error) = serde_json::from_str::<OpenAIErrorEnvelope>(&text) {
-                return Err(LLMError::Provider(error.error.message));
             } else {
-                return Err(LLMError::Provider(format!("unexpected status {status}: {text}")));
+                return Err(status_error(status, retry_after, &text));
             }
         }
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
-            if let Ok(error) = serde_json::from_str::<OpenAIErrorEnvelope>(&text) {
-                return Err(LLMError::Provider(error.error.message));
-            }
-
-            return Err(LLMError::Provider(format!("unexpected status {status}: {text}")));
+            return Err(status_error(status, retry_after, &text));
         }
 
         let parsed: ChatCompletionResponse = response.json().await?;
@@ -472,6 +511,7 @@ impl LLMProvider for OpenAI {
         &self,
         request: CompletionRequest,
     ) -> Result<CompletionStream, LLMError> {
+        super::ensure_openai_audio_formats(&request.messages)?;
         let CompletionRequest {
             model,
             messages,
@@ -482,6 +522,7 @@ impl LLMProvider for OpenAI {
             tools,
             tool_choice,
             reasoning_effort,
+            correlation_id,
         } = request;
 
         let body = OpenAIRequestBody {
@@ -499,6 +540,7 @@ impl LLMProvider for OpenAI {
 
         let builder = self
             .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+            .headers(super::request_id_headers(correlation_id))
             .header("Accept", "text/event-stream")
             .header("Cache-Control", "no-cache")
             .json(&body);
@@ -507,11 +549,13 @@ impl LLMProvider for OpenAI {
         let mut status = response.status();
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
             if should_retry_with_completion_tokens(status, &text) {
                 let fallback_body = body_with_max_completion_tokens(&body)?;
                 response = self
                     .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+                    .headers(super::request_id_headers(correlation_id))
                     .header("Accept", "text/event-stream")
                     .header("Cache-Control", "no-cache")
                     .json(&fallback_body)
@@ -522,26 +566,22 @@ impl LLMProvider for OpenAI {
                 let fallback_body = body_without_temperature(&body)?;
                 response = self
                     .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+                    .headers(super::request_id_headers(correlation_id))
                     .header("Accept", "text/event-stream")
                     .header("Cache-Control", "no-cache")
                     .json(&fallback_body)
                     .send()
                     .await?;
                 status = response.status();
-            } else if let Ok(error) = serde_json::from_str::<OpenAIErrorEnvelope>(&text) {
-                return Err(LLMError::Provider(error.error.message));
             } else {
-                return Err(LLMError::Provider(format!("unexpected status {status}: {text}")));
+                return Err(status_error(status, retry_after, &text));
             }
         }
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
-            if let Ok(error) = serde_json::from_str::<OpenAIErrorEnvelope>(&text) {
-                return Err(LLMError::Provider(error.error.message));
-            }
-
-            return Err(LLMError::Provider(format!("unexpected status {status}: {text}")));
+            return Err(status_error(status, retry_after, &text));
         }
 
         let stream = try_stream! {
@@ -603,6 +643,21 @@ impl LLMProvider for OpenAI {
                             }
                         }
 
+                        // Calls whose arguments never balanced (or arrived as text) are reported here.
+                        let pending_calls: Vec<ToolCall> = if tool_call_accumulators.is_empty() {
+                            resolved_tool_calls.clone()
+                        } else {
+                            tool_call_accumulators
+                                .iter()
+                                .zip(&resolved_tool_calls)
+                                .filter(|(accumulator, _)| !accumulator.emitted)
+                                .map(|(_, call)| call.clone())
+                                .collect()
+                        };
+                        for call in pending_calls {
+                            yield StreamEvent::ToolCallComplete { call };
+                        }
+
                         let completion_message = ChatMessage {
                             role: MessageRole::Assistant,
                             content,
@@ -610,7 +665,10 @@ impl LLMProvider for OpenAI {
                             tool_call_id: None,
                             tool_calls: resolved_tool_calls.clone(),
                             images: Vec::new(),
+                            attachments: Vec::new(),
                             thinking: None,
+                            created_at: None,
+                            metadata: None,
                         };
 
                         let completion = CompletionResponse {
@@ -672,6 +730,9 @@ impl LLMProvider for OpenAI {
 
                                 if let Some(accumulator) = tool_call_accumulators.get_mut(index) {
                                     accumulator.update(&tool_delta);
+                                    if let Some(call) = accumulator.take_completed() {
+                                        yield StreamEvent::ToolCallComplete { call };
+                                    }
                                 }
                             }
                         }
@@ -718,12 +779,9 @@ impl LLMProvider for OpenAI {
         let status = response.status();
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
-            if let Ok(error) = serde_json::from_str::<OpenAIErrorEnvelope>(&text) {
-                return Err(LLMError::Provider(error.error.message));
-            }
-
-            return Err(LLMError::Provider(format!("unexpected status {status}: {text}")));
+            return Err(status_error(status, retry_after, &text));
         }
 
         let parsed: FileUploadResponse = response.json().await?;
@@ -754,12 +812,9 @@ impl LLMProvider for OpenAI {
         let status = response.status();
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
-            if let Ok(error) = serde_json::from_str::<OpenAIErrorEnvelope>(&text) {
-                return Err(LLMError::Provider(error.error.message));
-            }
-
-            return Err(LLMError::Provider(format!("unexpected status {status}: {text}")));
+            return Err(status_error(status, retry_after, &text));
         }
 
         let parsed: OpenAIEmbeddingResponse = response.json().await?;
@@ -776,7 +831,11 @@ impl LLMProvider for OpenAI {
     }
 
     fn capabilities(&self) -> ProviderCapabilities {
-        ProviderCapabilities::new(true, true, true, true)
+        ProviderCapabilities::new(true, true, true, true).with_structured_output(true)
+    }
+
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        Ok(openai_token_count(request))
     }
 
     fn name(&self) -> &'static str {
@@ -784,3 +843,132 @@ impl LLMProvider for OpenAI {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AudioFormat, ContentPart};
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn chat_message_to_json_includes_file_and_audio_parts() {
+        let msg = ChatMessage::user_with_file("Summarize", "file-123", "report.pdf").with_attachment(
+            ContentPart::Audio {
+                url: "data:audio/wav;base64,UklG".to_string(),
+                format: AudioFormat::Wav,
+            },
+        );
+        let json = chat_message_to_json(&msg);
+
+        let content = json["content"].as_array().expect("content should be an array");
+        assert_eq!(content.len(), 3);
+        assert_eq!(content[0]["text"], "Summarize");
+        assert_eq!(content[1]["type"], "file");
+        assert_eq!(content[1]["file"]["file_id"], "file-123");
+        assert_eq!(content[1]["file"]["filename"], "report.pdf");
+        assert_eq!(content[2]["type"], "input_audio");
+        assert_eq!(content[2]["input_audio"]["data"], "UklG");
+        assert_eq!(content[2]["input_audio"]["format"], "wav");
+    }
+
+    #[tokio::test]
+    async fn webm_audio_is_rejected_before_sending() {
+        let server = MockServer::start().await;
+        let provider =
+            OpenAI::from_config(OpenAIConfig::new("test-key").with_base_url(server.uri())).unwrap();
+        let message = ChatMessage::user("Transcribe").with_attachment(ContentPart::Audio {
+            url: "GkXf".to_string(),
+            format: AudioFormat::Webm,
+        });
+
+        let error = provider
+            .complete(CompletionRequest::new("gpt-4o-audio-preview", vec![message]))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, LLMError::Unsupported(_)), "{error}");
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn stream_emits_tool_call_complete_events() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({ "stream": true })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(include_str!(
+                        "../../tests/fixtures/openai_stream_tool_calls.sse"
+                    )),
+            )
+            .mount(&server)
+            .await;
+
+        let provider =
+            OpenAI::from_config(OpenAIConfig::new("test-key").with_base_url(server.uri())).unwrap();
+        let mut stream = provider
+            .stream_completion(CompletionRequest::new(
+                "gpt-4o-mini",
+                vec![ChatMessage::user("weather and time in Berlin?")],
+            ))
+            .await
+            .unwrap();
+
+        let mut deltas = 0;
+        let mut completed_calls = Vec::new();
+        let mut final_response = None;
+        while let Some(event) = stream.next().await {
+            match event.unwrap() {
+                StreamEvent::ToolCallDelta { .. } => deltas += 1,
+                StreamEvent::ToolCallComplete { call } => {
+                    // Each call is reported once, before the stream completes.
+                    assert!(final_response.is_none());
+                    completed_calls.push(call);
+                }
+                StreamEvent::Completed(response) => final_response = Some(response),
+                StreamEvent::MessageDelta(_) | StreamEvent::ReasoningDelta(_) => {}
+            }
+        }
+
+        assert_eq!(deltas, 7);
+        assert_eq!(completed_calls.len(), 2);
+        assert_eq!(completed_calls[0].id.as_deref(), Some("call_weather_berlin"));
+        assert_eq!(completed_calls[0].function.name, "get_weather");
+        assert_eq!(completed_calls[0].function.arguments["city"], "Berlin");
+        assert_eq!(completed_calls[0].function.arguments["unit"], "{celsius}");
+        assert_eq!(completed_calls[1].function.name, "get_time");
+        assert_eq!(completed_calls[1].function.arguments["timezone"], "Europe/Berlin");
+
+        let response = final_response.expect("stream should complete");
+        assert_eq!(response.message.tool_calls.len(), 2);
+        assert_eq!(response.usage.map(|usage| usage.total_tokens), Some(123));
+    }
+
+    #[tokio::test]
+    async fn rate_limit_keeps_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("retry-after", "7")
+                    .set_body_json(serde_json::json!({
+                        "error": { "message": "Rate limit reached for gpt-4o-mini" }
+                    })),
+            )
+            .mount(&server)
+            .await;
+
+        let provider =
+            OpenAI::from_config(OpenAIConfig::new("test-key").with_base_url(server.uri())).unwrap();
+        let error = provider
+            .complete(CompletionRequest::new("gpt-4o-mini", vec![ChatMessage::user("hi")]))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, LLMError::RateLimited { ref message, .. } if message.starts_with("Rate limit")));
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(7)));
+    }
+}
@@ -52,6 +52,8 @@ pub struct OpenRouter {
     client: Client,
     config: OpenRouterConfig,
     model_catalog_cache: Arc<RwLock<Option<ModelCatalogCache>>>,
+    model_list_cache: Arc<RwLock<Option<ModelListCache>>>,
+    active_model: Arc<std::sync::Mutex<Option<String>>>,
 }
 
 impl OpenRouter {
@@ -74,9 +76,74 @@ impl OpenRouter {
             client,
             config,
             model_catalog_cache: Arc::new(RwLock::new(None)),
+            model_list_cache: Arc::new(RwLock::new(None)),
+            active_model: Arc::new(std::sync::Mutex::new(None)),
         })
     }
 
+    /// List the models served by the public `/models` endpoint. Results are cached for
+    /// `model_catalog_ttl`. [`LLMProvider::list_models`] still returns the richer catalog.
+    pub async fn list_models(&self) -> Result<Vec<OpenRouterModel>, LLMError> {
+        {
+            let cache = self.model_list_cache.read().await;
+            if let Some(cached) = cache.as_ref() {
+                if cached.fetched_at.elapsed() <= self.config.model_catalog_ttl {
+                    return Ok(cached.models.clone());
+                }
+            }
+        }
+
+        let response = self
+            .with_default_headers(self.client.get(self.endpoint("models")))
+            .send()
+            .await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(LLMError::Provider(format!(
+                "unexpected status {status}: {text}"
+            )));
+        }
+
+        let parsed: OpenRouterModelsResponse = response.json().await?;
+        let models: Vec<OpenRouterModel> = parsed
+            .data
+            .into_iter()
+            .map(OpenRouterApiModel::into_model)
+            .collect();
+
+        *self.model_list_cache.write().await = Some(ModelListCache {
+            fetched_at: Instant::now(),
+            models: models.clone(),
+        });
+        Ok(models)
+    }
+
+    /// The tool-capable model with the lowest prompt price, or `None` if the list can't be
+    /// fetched. Models without a fixed price (such as routers) are skipped.
+    pub async fn find_cheapest_model_with_tools(&self) -> Option<OpenRouterModel> {
+        let models = self.list_models().await.ok()?;
+        models
+            .into_iter()
+            .filter(|model| model.supports_tools)
+            .filter(|model| model.pricing.prompt_per_token.is_some_and(|price| price >= 0.0))
+            .min_by(|a, b| {
+                a.cost_per_1k_prompt_tokens()
+                    .total_cmp(&b.cost_per_1k_prompt_tokens())
+                    .then_with(|| {
+                        let completion = |model: &OpenRouterModel| {
+                            model.pricing.completion_per_token.unwrap_or(f64::MAX)
+                        };
+                        completion(a).total_cmp(&completion(b))
+                    })
+            })
+    }
+
+    fn set_active_model(&self, model: &str) {
+        *self.active_model.lock().unwrap() = Some(model.to_string());
+    }
+
     fn endpoint(&self, path: &str) -> String {
         format!(
             "{}/{}",
@@ -169,15 +236,17 @@ struct OpenRouterRequestBody {
 }
 
 /// Convert a `ChatMessage` to a JSON `Value`, building a multimodal content
-/// array when the message carries image attachments.
+/// array when the message carries image, file or audio attachments.
 fn chat_message_to_json(msg: &ChatMessage) -> Value {
-    if msg.images.is_empty() {
+    let attachments = super::attachment_content_parts(msg, "openrouter", false);
+    if msg.images.is_empty() && attachments.is_empty() {
         // Fast path: normal text-only message.
         return serde_json::to_value(msg).unwrap_or_default();
     }
 
-    // Build multimodal content array: text block + image blocks.
-    let mut content_parts: Vec<Value> = Vec::with_capacity(1 + msg.images.len());
+    // Build multimodal content array: text block + image and attachment blocks.
+    let mut content_parts: Vec<Value> =
+        Vec::with_capacity(1 + msg.images.len() + attachments.len());
     if let Some(text) = &msg.content {
         content_parts.push(serde_json::json!({
             "type": "text",
@@ -190,6 +259,7 @@ fn chat_message_to_json(msg: &ChatMessage) -> Value {
             "image_url": { "url": image_url },
         }));
     }
+    content_parts.extend(attachments);
 
     let mut obj = serde_json::json!({
         "role": msg.role,
@@ -297,6 +367,93 @@ struct OpenRouterCatalogResponse {
     data: Vec<OpenRouterCatalogModel>,
 }
 
+/// A model entry from OpenRouter's public `/models` endpoint.
+#[derive(Debug, Clone)]
+pub struct OpenRouterModel {
+    pub id: String,
+    pub context_length: u32,
+    pub pricing: ModelPricing,
+    pub supports_tools: bool,
+    pub supports_vision: bool,
+}
+
+impl OpenRouterModel {
+    /// Price of 1000 prompt tokens in USD; zero when the price is unknown.
+    pub fn cost_per_1k_prompt_tokens(&self) -> f64 {
+        self.pricing.prompt_per_token.unwrap_or(0.0) * 1000.0
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterModelsResponse {
+    data: Vec<OpenRouterApiModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterApiModel {
+    id: String,
+    #[serde(default)]
+    context_length: Option<u32>,
+    #[serde(default)]
+    pricing: OpenRouterApiPricing,
+    #[serde(default)]
+    architecture: OpenRouterApiArchitecture,
+    #[serde(default, deserialize_with = "super::deserialize_null_as_empty_vec")]
+    supported_parameters: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct OpenRouterApiPricing {
+    #[serde(default)]
+    prompt: Option<PriceValue>,
+    #[serde(default)]
+    completion: Option<PriceValue>,
+    #[serde(default)]
+    image: Option<PriceValue>,
+    #[serde(default)]
+    request: Option<PriceValue>,
+    #[serde(default)]
+    web_search: Option<PriceValue>,
+    #[serde(default)]
+    internal_reasoning: Option<PriceValue>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct OpenRouterApiArchitecture {
+    #[serde(default, deserialize_with = "super::deserialize_null_as_empty_vec")]
+    input_modalities: Vec<String>,
+}
+
+impl OpenRouterApiModel {
+    fn into_model(self) -> OpenRouterModel {
+        OpenRouterModel {
+            supports_tools: self.supported_parameters.iter().any(|item| item == "tools"),
+            supports_vision: self
+                .architecture
+                .input_modalities
+                .iter()
+                .any(|modality| modality == "image"),
+            id: self.id,
+            context_length: self.context_length.unwrap_or(0),
+            pricing: ModelPricing {
+                prompt_per_token: parse_price(self.pricing.prompt),
+                completion_per_token: parse_price(self.pricing.completion),
+                image_per_token: parse_price(self.pricing.image),
+                request_per_call: parse_price(self.pricing.request),
+                web_search_per_call: parse_price(self.pricing.web_search),
+                internal_reasoning_per_token: parse_price(self.pricing.internal_reasoning),
+                ..ModelPricing::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ModelListCache {
+    fetched_at: Instant,
+    models: Vec<OpenRouterModel>,
+}
+
 #[derive(Debug, Clone)]
 struct ModelCatalogCache {
     fetched_at: Instant,
@@ -333,6 +490,21 @@ struct OpenRouterCatalogModel {
     features: Option<OpenRouterModelFeatures>,
 }
 
+/// The error for a failed call: [`LLMError::RateLimited`] for HTTP 429, otherwise the
+/// API's error message.
+fn status_error(status: StatusCode, retry_after: Option<Duration>, text: &str) -> LLMError {
+    let message = serde_json::from_str::<OpenRouterErrorBody>(text)
+        .ok()
+        .and_then(|body| body.error)
+        .map(|error| error.message)
+        .unwrap_or_else(|| format!("unexpected status {status}: {text}"));
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        LLMError::RateLimited { message, retry_after }
+    } else {
+        LLMError::Provider(message)
+    }
+}
+
 fn should_retry_with_completion_tokens(status: StatusCode, text: &str) -> bool {
     status == StatusCode::BAD_REQUEST
         && text.contains("max_tokens")
@@ -548,8 +720,10 @@ impl LLMProvider for OpenRouter {
             tools,
             tool_choice,
             reasoning_effort,
+            correlation_id,
         } = request;
 
+        self.set_active_model(&model);
         let body = OpenRouterRequestBody {
             model,
             messages: messages.iter().map(chat_message_to_json).collect(),
@@ -565,17 +739,20 @@ impl LLMProvider for OpenRouter {
 
         let builder = self
             .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+            .headers(super::request_id_headers(correlation_id))
             .json(&body);
 
         let mut response = builder.send().await?;
         let mut status = response.status();
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
             if should_retry_with_completion_tokens(status, &text) {
                 let fallback_body = body_with_max_completion_tokens(&body)?;
                 response = self
                     .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+                    .headers(super::request_id_headers(correlation_id))
                     .json(&fallback_body)
                     .send()
                     .await?;
@@ -584,35 +761,20 @@ impl LLMProvider for OpenRouter {
                 let fallback_body = body_without_temperature(&body)?;
                 response = self
                     .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+                    .headers(super::request_id_headers(correlation_id))
                     .json(&fallback_body)
                     .send()
                     .await?;
                 status = response.status();
-            } else if let Ok(error_body) = serde_json::from_str::<OpenRouterErrorBody>(&text) {
-                if let Some(error) = error_body.error {
-                    return Err(LLMError::Provider(error.message));
-                }
-                return Err(LLMError::Provider(format!(
-                    "unexpected status {status}: {text}"
-                )));
             } else {
-                return Err(LLMError::Provider(format!(
-                    "unexpected status {status}: {text}"
-                )));
+                return Err(status_error(status, retry_after, &text));
             }
         }
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
-            if let Ok(error_body) = serde_json::from_str::<OpenRouterErrorBody>(&text) {
-                if let Some(error) = error_body.error {
-                    return Err(LLMError::Provider(error.message));
-                }
-            }
-
-            return Err(LLMError::Provider(format!(
-                "unexpected status {status}: {text}"
-            )));
+            return Err(status_error(status, retry_after, &text));
         }
 
         let parsed: OpenRouterResponseBody = response.json().await?;
@@ -663,8 +825,10 @@ impl LLMProvider for OpenRouter {
             tools,
             tool_choice,
             reasoning_effort,
+            correlation_id,
         } = request;
 
+        self.set_active_model(&model);
         let body = OpenRouterRequestBody {
             model,
             messages: messages.iter().map(chat_message_to_json).collect(),
@@ -680,6 +844,7 @@ impl LLMProvider for OpenRouter {
 
         let builder = self
             .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+            .headers(super::request_id_headers(correlation_id))
             .header("Accept", "text/event-stream")
             .header("Cache-Control", "no-cache")
             .json(&body);
@@ -688,11 +853,13 @@ impl LLMProvider for OpenRouter {
         let mut status = response.status();
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
             if should_retry_with_completion_tokens(status, &text) {
                 let fallback_body = body_with_max_completion_tokens(&body)?;
                 response = self
                     .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+                    .headers(super::request_id_headers(correlation_id))
                     .header("Accept", "text/event-stream")
                     .header("Cache-Control", "no-cache")
                     .json(&fallback_body)
@@ -703,37 +870,22 @@ impl LLMProvider for OpenRouter {
                 let fallback_body = body_without_temperature(&body)?;
                 response = self
                     .with_default_headers(self.client.post(self.endpoint("chat/completions")))
+                    .headers(super::request_id_headers(correlation_id))
                     .header("Accept", "text/event-stream")
                     .header("Cache-Control", "no-cache")
                     .json(&fallback_body)
                     .send()
                     .await?;
                 status = response.status();
-            } else if let Ok(error_body) = serde_json::from_str::<OpenRouterErrorBody>(&text) {
-                if let Some(error) = error_body.error {
-                    return Err(LLMError::Provider(error.message));
-                }
-                return Err(LLMError::Provider(format!(
-                    "unexpected status {status}: {text}"
-                )));
             } else {
-                return Err(LLMError::Provider(format!(
-                    "unexpected status {status}: {text}"
-                )));
+                return Err(status_error(status, retry_after, &text));
             }
         }
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
-            if let Ok(error_body) = serde_json::from_str::<OpenRouterErrorBody>(&text) {
-                if let Some(error) = error_body.error {
-                    return Err(LLMError::Provider(error.message));
-                }
-            }
-
-            return Err(LLMError::Provider(format!(
-                "unexpected status {status}: {text}"
-            )));
+            return Err(status_error(status, retry_after, &text));
         }
 
         let stream = try_stream! {
@@ -879,16 +1031,9 @@ impl LLMProvider for OpenRouter {
         let status = response.status();
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
-            if let Ok(error_body) = serde_json::from_str::<OpenRouterErrorBody>(&text) {
-                if let Some(error) = error_body.error {
-                    return Err(LLMError::Provider(error.message));
-                }
-            }
-
-            return Err(LLMError::Provider(format!(
-                "unexpected status {status}: {text}"
-            )));
+            return Err(status_error(status, retry_after, &text));
         }
 
         let parsed: serde_json::Value = response.json().await?;
@@ -929,16 +1074,9 @@ impl LLMProvider for OpenRouter {
         let status = response.status();
 
         if !status.is_success() {
+            let retry_after = super::retry_after(response.headers());
             let text = response.text().await?;
-            if let Ok(error_body) = serde_json::from_str::<OpenRouterErrorBody>(&text) {
-                if let Some(error) = error_body.error {
-                    return Err(LLMError::Provider(error.message));
-                }
-            }
-
-            return Err(LLMError::Provider(format!(
-                "unexpected status {status}: {text}"
-            )));
+            return Err(status_error(status, retry_after, &text));
         }
 
         let parsed: OpenRouterEmbeddingResponse = response.json().await?;
@@ -954,12 +1092,28 @@ impl LLMProvider for OpenRouter {
         })
     }
 
+    /// Image support follows the most recently requested model when the model list has been
+    /// fetched; otherwise every capability is assumed.
     fn capabilities(&self) -> ProviderCapabilities {
-        ProviderCapabilities::new(true, true, true, true)
+        let active_model = self.active_model.lock().unwrap().clone();
+        let live_model = active_model.and_then(|id| {
+            let cache = self.model_list_cache.try_read().ok()?;
+            cache
+                .as_ref()?
+                .models
+                .iter()
+                .find(|model| model.id == id)
+                .cloned()
+        });
+
+        match live_model {
+            Some(model) => ProviderCapabilities::new(true, true, model.supports_vision, true),
+            None => ProviderCapabilities::new(true, true, true, true),
+        }
     }
 
     async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
-        let models = self.list_models().await?;
+        let models = self.fetch_model_catalog().await?;
         models
             .into_iter()
             .find(|model| model.id == id)
@@ -1047,6 +1201,105 @@ mod tests {
             Some("data:image/png;base64,AAAA")
         );
     }
+
+    #[test]
+    fn chat_message_to_json_drops_file_attachments() {
+        let msg = ChatMessage::user_with_file("Summarize", "file-123", "report.pdf");
+        let json = chat_message_to_json(&msg);
+        assert_eq!(json["content"].as_str(), Some("Summarize"));
+    }
+
+    async fn mock_models_server() -> wiremock::MockServer {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .and(header("authorization", "Bearer test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(include_str!(
+                "../../tests/fixtures/openrouter_api_models_sample.json"
+            )))
+            .expect(1)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn mock_provider(server: &wiremock::MockServer) -> OpenRouter {
+        let mut config = OpenRouterConfig::new("test-key");
+        config.base_url = server.uri();
+        OpenRouter::from_config(config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn list_models_parses_and_caches_models_endpoint() {
+        let server = mock_models_server().await;
+        let provider = mock_provider(&server);
+
+        let models = provider.list_models().await.unwrap();
+        assert_eq!(models.len(), 4);
+
+        let first = &models[0];
+        assert_eq!(first.id, "openai/gpt-4o-mini");
+        assert_eq!(first.context_length, 128000);
+        assert!(first.supports_tools);
+        assert!(first.supports_vision);
+        assert!((first.cost_per_1k_prompt_tokens() - 0.00015).abs() < 1e-12);
+
+        let llama = &models[2];
+        assert!(!llama.supports_tools);
+        assert!(!llama.supports_vision);
+
+        // Served from the cache; the mock only expects a single request.
+        let again = provider.list_models().await.unwrap();
+        assert_eq!(again.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn finds_cheapest_tool_capable_model() {
+        let server = mock_models_server().await;
+        let provider = mock_provider(&server);
+
+        let cheapest = provider.find_cheapest_model_with_tools().await.unwrap();
+        assert_eq!(cheapest.id, "mistralai/mistral-small");
+    }
+
+    #[tokio::test]
+    async fn capabilities_follow_active_model_vision_support() {
+        let server = mock_models_server().await;
+        let provider = mock_provider(&server);
+        provider.list_models().await.unwrap();
+
+        assert!(provider.capabilities().supports_image_uploads);
+
+        provider.set_active_model("mistralai/mistral-small");
+        assert!(!provider.capabilities().supports_image_uploads);
+
+        provider.set_active_model("openai/gpt-4o-mini");
+        assert!(provider.capabilities().supports_image_uploads);
+    }
+    #[tokio::test]
+    async fn rate_limit_responses_carry_retry_after() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("retry-after", "3")
+                    .set_body_json(serde_json::json!({ "error": { "message": "Rate limit exceeded" } })),
+            )
+            .mount(&server)
+            .await;
+
+        let request = CompletionRequest::new("openai/gpt-4o-mini", vec![ChatMessage::user("hi")]);
+        let err = mock_provider(&server).complete(request).await.unwrap_err();
+        assert!(matches!(err, LLMError::RateLimited { ref message, .. } if message == "Rate limit exceeded"));
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(3)));
+    }
 }
 
 use super::{extract_data_payload, extract_sse_event};
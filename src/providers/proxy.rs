@@ -0,0 +1,249 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+
+use crate::{
+    providers::LLMProvider,
+    types::{
+        ChatMessage, CompletionRequest, CompletionResponse, CompletionStream, EmbeddingRequest,
+        EmbeddingResponse, ImageUploadRequest, ImageUploadResponse, MessageRole, ModelInfo,
+        ProviderCapabilities, StreamEvent,
+    },
+    LLMError,
+};
+
+type RequestTransform = Arc<dyn Fn(CompletionRequest) -> CompletionRequest + Send + Sync>;
+type ResponseTransform = Arc<dyn Fn(CompletionResponse) -> CompletionResponse + Send + Sync>;
+
+/// Rewrites requests and responses around an inner provider. Transforms run in the order
+/// they were registered.
+#[derive(Clone)]
+pub struct ProxyProvider {
+    inner: Arc<dyn LLMProvider>,
+    request_transforms: Vec<RequestTransform>,
+    response_transforms: Vec<ResponseTransform>,
+}
+
+impl ProxyProvider {
+    pub fn new(inner: Arc<dyn LLMProvider>) -> Self {
+        Self {
+            inner,
+            request_transforms: Vec::new(),
+            response_transforms: Vec::new(),
+        }
+    }
+
+    pub fn with_request_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(CompletionRequest) -> CompletionRequest + Send + Sync + 'static,
+    {
+        self.request_transforms.push(Arc::new(transform));
+        self
+    }
+
+    pub fn with_response_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(CompletionResponse) -> CompletionResponse + Send + Sync + 'static,
+    {
+        self.response_transforms.push(Arc::new(transform));
+        self
+    }
+
+    /// Prepend `prompt` as a system message unless the request already contains it.
+    pub fn with_forced_system_prompt(self, prompt: &str) -> Self {
+        let prompt = prompt.to_string();
+        self.with_request_transform(move |mut request| {
+            let present = request.messages.iter().any(|message| {
+                message.role == MessageRole::System && message.text() == Some(prompt.as_str())
+            });
+            if !present {
+                request.messages.insert(0, ChatMessage::system(prompt.clone()));
+            }
+            request
+        })
+    }
+
+    /// Send requests for model `original` to `replacement` instead.
+    pub fn with_model_override(self, original: &str, replacement: &str) -> Self {
+        let original = original.to_string();
+        let replacement = replacement.to_string();
+        self.with_request_transform(move |mut request| {
+            if request.model == original {
+                request.model = replacement.clone();
+            }
+            request
+        })
+    }
+
+    pub fn inner(&self) -> Arc<dyn LLMProvider> {
+        Arc::clone(&self.inner)
+    }
+
+    fn transform_request(&self, request: CompletionRequest) -> CompletionRequest {
+        self.request_transforms
+            .iter()
+            .fold(request, |request, transform| transform(request))
+    }
+
+    fn transform_response(&self, response: CompletionResponse) -> CompletionResponse {
+        apply_response_transforms(&self.response_transforms, response)
+    }
+}
+
+fn apply_response_transforms(
+    transforms: &[ResponseTransform],
+    response: CompletionResponse,
+) -> CompletionResponse {
+    transforms
+        .iter()
+        .fold(response, |response, transform| transform(response))
+}
+
+#[async_trait]
+impl LLMProvider for ProxyProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let response = self.inner.complete(self.transform_request(request)).await?;
+        Ok(self.transform_response(response))
+    }
+
+    /// Response transforms apply to the final [`StreamEvent::Completed`] response only.
+    async fn stream_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionStream, LLMError> {
+        let stream = self
+            .inner
+            .stream_completion(self.transform_request(request))
+            .await?;
+        if self.response_transforms.is_empty() {
+            return Ok(stream);
+        }
+
+        let transforms = self.response_transforms.clone();
+        Ok(Box::pin(stream.map(move |event| match event {
+            Ok(StreamEvent::Completed(response)) => Ok(StreamEvent::Completed(
+                apply_response_transforms(&transforms, response),
+            )),
+            other => other,
+        })))
+    }
+
+    async fn upload_image(
+        &self,
+        request: ImageUploadRequest,
+    ) -> Result<ImageUploadResponse, LLMError> {
+        self.inner.upload_image(request).await
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, LLMError> {
+        self.inner.create_embeddings(request).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
+        self.inner.model_info(id).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LLMError> {
+        self.inner.list_models().await
+    }
+
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        self.inner
+            .count_tokens(&self.transform_request(request.clone()))
+            .await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingProvider {
+        requests: Mutex<Vec<CompletionRequest>>,
+    }
+
+    #[async_trait]
+    impl LLMProvider for RecordingProvider {
+        async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+            self.requests.lock().unwrap().push(request);
+            Ok(CompletionResponse {
+                message: ChatMessage::assistant("ok"),
+                usage: None,
+                reasoning: None,
+            })
+        }
+
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+    }
+
+    #[tokio::test]
+    async fn forced_system_prompt_is_prepended_once() {
+        let inner = Arc::new(RecordingProvider::default());
+        let proxy = ProxyProvider::new(inner.clone())
+            .with_forced_system_prompt("Follow company policy.")
+            .with_model_override("gpt-4", "gpt-4o")
+            .with_response_transform(|mut response| {
+                response.message.content = Some("rewritten".to_string());
+                response
+            });
+
+        let response = proxy
+            .complete(CompletionRequest::new("gpt-4", vec![ChatMessage::user("hi")]))
+            .await
+            .unwrap();
+        assert_eq!(response.message.text(), Some("rewritten"));
+
+        proxy
+            .complete(CompletionRequest::new(
+                "other",
+                vec![ChatMessage::system("Follow company policy."), ChatMessage::user("hi")],
+            ))
+            .await
+            .unwrap();
+
+        let requests = inner.requests.lock().unwrap();
+        assert_eq!(requests[0].model, "gpt-4o");
+        assert_eq!(requests[0].messages.len(), 2);
+        assert_eq!(requests[0].messages[0].role, MessageRole::System);
+        assert_eq!(requests[0].messages[0].text(), Some("Follow company policy."));
+        assert_eq!(requests[1].model, "other");
+        assert_eq!(requests[1].messages.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn request_transforms_run_in_registration_order() {
+        let inner = Arc::new(RecordingProvider::default());
+        let proxy = ProxyProvider::new(inner.clone())
+            .with_request_transform(|mut request| {
+                request.model.push_str("-a");
+                request
+            })
+            .with_request_transform(|mut request| {
+                request.model.push_str("-b");
+                request
+            });
+
+        proxy
+            .complete(CompletionRequest::new("model", Vec::new()))
+            .await
+            .unwrap();
+        assert_eq!(inner.requests.lock().unwrap()[0].model, "model-a-b");
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use tokio::{
+    sync::Semaphore,
+    task::JoinHandle,
+    time::{sleep_until, Instant},
+};
+
+use crate::{
+    providers::LLMProvider,
+    types::{
+        CompletionRequest, CompletionResponse, CompletionStream, EmbeddingRequest,
+        EmbeddingResponse, ImageUploadRequest, ImageUploadResponse, ModelInfo,
+        ProviderCapabilities,
+    },
+    LLMError,
+};
+
+/// How often the background task tops up the buckets.
+const REFILL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wait used for a 429 that came without a `Retry-After` header.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+const DEFAULT_MAX_RETRIES: usize = 3;
+
+/// A semaphore used as a token bucket: permits are spent for good and put back by the
+/// refill task, never above `capacity`.
+struct Bucket {
+    permits: Semaphore,
+    capacity: usize,
+    /// Permits added per refill tick; the fraction carries over to the next tick.
+    per_tick: f64,
+}
+
+impl Bucket {
+    /// `None` for a limit of zero, which means no limit.
+    fn new(capacity: u32, per_second: f64) -> Option<Arc<Self>> {
+        let capacity = usize::try_from(capacity).ok().filter(|&capacity| capacity > 0)?;
+        Some(Arc::new(Self {
+            permits: Semaphore::new(capacity),
+            capacity,
+            per_tick: per_second * REFILL_INTERVAL.as_secs_f64(),
+        }))
+    }
+
+    async fn take(&self, count: usize) {
+        let count = count.clamp(1, self.capacity) as u32;
+        self.permits
+            .acquire_many(count)
+            .await
+            .expect("bucket semaphore is never closed")
+            .forget();
+    }
+
+    fn refill(&self, carry: &mut f64) {
+        *carry += self.per_tick;
+        let whole = carry.floor();
+        *carry -= whole;
+        let missing = self.capacity.saturating_sub(self.permits.available_permits());
+        self.permits.add_permits((whole as usize).min(missing));
+    }
+}
+
+/// Throttles completions to `rps` requests per second and `tpm` tokens per minute, so
+/// concurrent agents sharing one provider stay under its rate limits; a limit of zero
+/// disables that limit. Each request spends its [`LLMProvider::count_tokens`] count plus
+/// its `max_tokens` from the token bucket. A [`LLMError::RateLimited`] reply pauses the
+/// buckets for the `Retry-After` delay and the call is retried, up to `max_retries` times;
+/// only some providers report 429s that way (see the variant). Other calls pass through.
+///
+/// The buckets are refilled by a background task, so the provider must be created inside
+/// a Tokio runtime. The task stops when the provider is dropped.
+pub struct RateLimitedProvider<P: LLMProvider> {
+    inner: P,
+    requests: Option<Arc<Bucket>>,
+    tokens: Option<Arc<Bucket>>,
+    paused_until: Mutex<Option<Instant>>,
+    max_retries: usize,
+    refill: JoinHandle<()>,
+}
+
+impl<P: LLMProvider> RateLimitedProvider<P> {
+    pub fn new(inner: P, rps: u32, tpm: u32) -> Self {
+        let requests = Bucket::new(rps, f64::from(rps));
+        let tokens = Bucket::new(tpm, f64::from(tpm) / 60.0);
+
+        let refill = tokio::spawn({
+            let (requests, tokens) = (requests.clone(), tokens.clone());
+            async move {
+                let mut interval = tokio::time::interval(REFILL_INTERVAL);
+                interval.tick().await;
+                let (mut request_carry, mut token_carry) = (0.0, 0.0);
+                loop {
+                    interval.tick().await;
+                    if let Some(requests) = &requests {
+                        requests.refill(&mut request_carry);
+                    }
+                    if let Some(tokens) = &tokens {
+                        tokens.refill(&mut token_carry);
+                    }
+                }
+            }
+        });
+
+        Self {
+            inner,
+            requests,
+            tokens,
+            paused_until: Mutex::new(None),
+            max_retries: DEFAULT_MAX_RETRIES,
+            refill,
+        }
+    }
+
+    /// Rate-limit replies retried before the error is returned. Defaults to 3.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Wait until any `Retry-After` pause is over and the buckets have room for `tokens`.
+    async fn wait_for_capacity(&self, tokens: u32) {
+        loop {
+            let paused_until = *self.paused_until.lock().unwrap();
+            match paused_until {
+                Some(until) if until > Instant::now() => sleep_until(until).await,
+                _ => break,
+            }
+        }
+        if let Some(requests) = &self.requests {
+            requests.take(1).await;
+        }
+        if let Some(bucket) = &self.tokens {
+            bucket.take(tokens as usize).await;
+        }
+    }
+
+    fn pause_for(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut paused_until = self.paused_until.lock().unwrap();
+        if paused_until.is_none_or(|current| current < until) {
+            *paused_until = Some(until);
+        }
+    }
+
+    async fn throttled<T, F, Fut>(&self, request: &CompletionRequest, mut call: F) -> Result<T, LLMError>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, LLMError>>,
+    {
+        let prompt = self
+            .inner
+            .count_tokens(request)
+            .await
+            .unwrap_or_else(|_| request.estimated_token_count());
+        let tokens = prompt.saturating_add(request.max_tokens.unwrap_or(0));
+
+        let mut attempt = 0;
+        loop {
+            self.wait_for_capacity(tokens).await;
+            match call().await {
+                Err(error @ LLMError::RateLimited { .. }) if attempt < self.max_retries => {
+                    let delay = error.retry_after().unwrap_or(DEFAULT_RETRY_AFTER);
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(attempt, ?delay, %error, "rate limited, retrying after delay");
+                    self.pause_for(delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<P: LLMProvider> Drop for RateLimitedProvider<P> {
+    fn drop(&mut self) {
+        self.refill.abort();
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for RateLimitedProvider<P> {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.throttled(&request, || self.inner.complete(request.clone()))
+            .await
+    }
+
+    async fn stream_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionStream, LLMError> {
+        self.throttled(&request, || self.inner.stream_completion(request.clone()))
+            .await
+    }
+
+    async fn upload_image(
+        &self,
+        request: ImageUploadRequest,
+    ) -> Result<ImageUploadResponse, LLMError> {
+        self.inner.upload_image(request).await
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, LLMError> {
+        self.inner.create_embeddings(request).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
+        self.inner.model_info(id).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LLMError> {
+        self.inner.list_models().await
+    }
+
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        self.inner.count_tokens(request).await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{providers::scripted::ScriptedProvider, types::ChatMessage};
+
+    fn scripted(responses: usize) -> ScriptedProvider {
+        ScriptedProvider::with_responses(&vec!["ok"; responses])
+    }
+
+    fn rate_limited(retry_after: Option<Duration>) -> LLMError {
+        LLMError::RateLimited {
+            message: "Too Many Requests".to_string(),
+            retry_after,
+        }
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest::new("m", vec![ChatMessage::user("hi")])
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn spaces_requests_to_the_request_rate() {
+        let provider = RateLimitedProvider::new(scripted(4), 2, 100_000);
+        let started = Instant::now();
+        for _ in 0..4 {
+            provider.complete(request()).await.unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(900), "took {:?}", started.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_for_the_token_budget() {
+        let request = request().with_max_tokens(595);
+        let provider = RateLimitedProvider::new(scripted(3), 100, 1_200);
+        assert_eq!(provider.count_tokens(&request).await.unwrap(), 5);
+
+        let started = Instant::now();
+        provider.complete(request.clone()).await.unwrap();
+        provider.complete(request.clone()).await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        // 600 more tokens at 20 per second.
+        provider.complete(request).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(30), "took {:?}", started.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_after_the_requested_delay() {
+        let mut inner = scripted(1);
+        inner.inject_transient_errors(1, rate_limited(Some(Duration::from_secs(2))));
+        let provider = RateLimitedProvider::new(inner, 10, 100_000);
+
+        let started = Instant::now();
+        let response = provider.complete(request()).await.unwrap();
+        assert_eq!(response.message.text(), Some("ok"));
+        assert_eq!(provider.inner().calls(), 2);
+        assert!(started.elapsed() >= Duration::from_secs(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_max_retries() {
+        let mut inner = scripted(1);
+        inner.inject_transient_errors(3, rate_limited(None));
+        let provider = RateLimitedProvider::new(inner, 10, 100_000).with_max_retries(2);
+
+        let error = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(error, LLMError::RateLimited { .. }));
+        assert_eq!(provider.inner().calls(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn zero_limits_are_unlimited() {
+        let provider = RateLimitedProvider::new(scripted(3), 0, 0);
+        let started = Instant::now();
+        for _ in 0..3 {
+            provider.complete(request().with_max_tokens(1_000)).await.unwrap();
+        }
+        assert_eq!(started.elapsed(), Duration::ZERO);
+    }
+}
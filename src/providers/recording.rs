@@ -0,0 +1,330 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    providers::LLMProvider,
+    types::{
+        ChatMessage, CompletionRequest, CompletionResponse, CompletionStream, EmbeddingRequest,
+        EmbeddingResponse, ImageUploadRequest, ImageUploadResponse, ModelInfo,
+        ProviderCapabilities, StreamEvent,
+    },
+    LLMError,
+};
+
+/// One line of a cassette: a request and the response it got.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CassetteEntry {
+    pub request: CompletionRequest,
+    pub response: CompletionResponse,
+}
+
+/// Wraps a provider and appends every successful completion to a JSONL cassette that a
+/// [`ReplayProvider`] can play back later. Streams are recorded by their final
+/// [`StreamEvent::Completed`] response. Failed requests are not recorded.
+pub struct RecordingProvider<P: LLMProvider> {
+    inner: P,
+    file: Arc<Mutex<File>>,
+}
+
+impl<P: LLMProvider> RecordingProvider<P> {
+    /// Record into the cassette at `path`, appending if it already exists.
+    pub fn new(inner: P, path: &Path) -> Result<Self, io::Error> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            inner,
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+fn record(file: &Mutex<File>, entry: &CassetteEntry) -> Result<(), LLMError> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    file.lock()
+        .unwrap()
+        .write_all(line.as_bytes())
+        .map_err(|err| LLMError::Provider(format!("failed to write cassette: {err}")))
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for RecordingProvider<P> {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let response = self.inner.complete(request.clone()).await?;
+        record(
+            &self.file,
+            &CassetteEntry {
+                request,
+                response: response.clone(),
+            },
+        )?;
+        Ok(response)
+    }
+
+    async fn stream_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionStream, LLMError> {
+        let stream = self.inner.stream_completion(request.clone()).await?;
+        let file = Arc::clone(&self.file);
+        Ok(Box::pin(stream.map(move |event| match event {
+            Ok(StreamEvent::Completed(response)) => {
+                record(
+                    &file,
+                    &CassetteEntry {
+                        request: request.clone(),
+                        response: response.clone(),
+                    },
+                )?;
+                Ok(StreamEvent::Completed(response))
+            }
+            other => other,
+        })))
+    }
+
+    async fn upload_image(
+        &self,
+        request: ImageUploadRequest,
+    ) -> Result<ImageUploadResponse, LLMError> {
+        self.inner.upload_image(request).await
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, LLMError> {
+        self.inner.create_embeddings(request).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
+        self.inner.model_info(id).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LLMError> {
+        self.inner.list_models().await
+    }
+
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        self.inner.count_tokens(request).await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+/// Answers completions from a cassette written by [`RecordingProvider`], in recorded order
+/// and without any network access. A request whose messages differ from the recorded one
+/// is logged as a warning, or panics with [`ReplayProvider::with_strict_matching`].
+pub struct ReplayProvider {
+    entries: Vec<CassetteEntry>,
+    cursor: Mutex<usize>,
+    strict: bool,
+}
+
+impl ReplayProvider {
+    pub fn new(path: &Path) -> Result<Self, io::Error> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            entries.push(entry);
+        }
+
+        Ok(Self {
+            entries,
+            cursor: Mutex::new(0),
+            strict: false,
+        })
+    }
+
+    /// Panic instead of warning when a request does not match the recording.
+    pub fn with_strict_matching(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Recorded pairs not replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.entries.len() - *self.cursor.lock().unwrap()
+    }
+
+    fn next_entry(&self) -> Result<(usize, &CassetteEntry), LLMError> {
+        let mut cursor = self.cursor.lock().unwrap();
+        let index = *cursor;
+        let entry = self
+            .entries
+            .get(index)
+            .ok_or_else(|| LLMError::Provider(format!("cassette exhausted after {index} responses")))?;
+        *cursor += 1;
+        Ok((index, entry))
+    }
+}
+
+/// Messages as compared during replay; timestamps differ between runs and are left out.
+fn comparable(messages: &[ChatMessage]) -> Result<serde_json::Value, serde_json::Error> {
+    let messages: Vec<_> = messages
+        .iter()
+        .cloned()
+        .map(|mut message| {
+            message.created_at = None;
+            message
+        })
+        .collect();
+    serde_json::to_value(messages)
+}
+
+#[async_trait]
+impl LLMProvider for ReplayProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let (index, entry) = self.next_entry()?;
+        if comparable(&request.messages)? != comparable(&entry.request.messages)? {
+            if self.strict {
+                panic!("request {index} does not match the recorded request");
+            }
+            tracing::warn!(index, "replayed request does not match the recorded request");
+        }
+        Ok(entry.response.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{providers::scripted::ScriptedProvider, types::ChatMessage};
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "denkwerk-cassette-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn request(text: &str) -> CompletionRequest {
+        CompletionRequest::new("scripted", vec![ChatMessage::user(text)])
+    }
+
+    async fn record(path: &Path) -> Vec<Option<String>> {
+        let inner = ScriptedProvider::with_responses(&["first answer", "second answer"]);
+        let provider = RecordingProvider::new(inner, path).unwrap();
+
+        let mut replies = Vec::new();
+        for text in ["first question", "second question"] {
+            let response = provider.complete(request(text)).await.unwrap();
+            replies.push(response.message.text().map(str::to_string));
+        }
+        replies
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_responses_in_order() {
+        let path = temp_path("replay");
+        let recorded = record(&path).await;
+
+        let replay = ReplayProvider::new(&path).unwrap().with_strict_matching(true);
+        assert_eq!(replay.remaining(), 2);
+        for (text, expected) in ["first question", "second question"].into_iter().zip(&recorded) {
+            let response = replay.complete(request(text)).await.unwrap();
+            assert_eq!(response.message.text().map(str::to_string), *expected);
+        }
+        assert_eq!(recorded[1].as_deref(), Some("second answer"));
+        assert!(replay.complete(request("third question")).await.is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "does not match the recorded request")]
+    async fn strict_matching_panics_on_different_request() {
+        let path = temp_path("strict");
+        record(&path).await;
+
+        let replay = ReplayProvider::new(&path).unwrap().with_strict_matching(true);
+        let _ = std::fs::remove_file(&path);
+        let _ = replay.complete(request("something else")).await;
+    }
+
+    #[tracing_test::traced_test]
+    #[tokio::test]
+    async fn lenient_matching_warns_and_still_replays() {
+        let path = temp_path("lenient");
+        record(&path).await;
+
+        let replay = ReplayProvider::new(&path).unwrap();
+        let response = replay.complete(request("something else")).await.unwrap();
+        assert_eq!(response.message.text(), Some("first answer"));
+        assert!(logs_contain("does not match the recorded request"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    struct Streaming;
+
+    #[async_trait]
+    impl LLMProvider for Streaming {
+        async fn complete(&self, _request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+            Err(LLMError::Unsupported("completions"))
+        }
+
+        async fn stream_completion(&self, _request: CompletionRequest) -> Result<CompletionStream, LLMError> {
+            let response = CompletionResponse {
+                message: ChatMessage::assistant("streamed answer"),
+                usage: None,
+                reasoning: None,
+            };
+            Ok(Box::pin(futures_util::stream::iter([
+                Ok(StreamEvent::MessageDelta("streamed answer".to_string())),
+                Ok(StreamEvent::Completed(response)),
+            ])))
+        }
+
+        fn name(&self) -> &'static str {
+            "streaming"
+        }
+    }
+
+    #[tokio::test]
+    async fn records_the_completed_stream_response() {
+        let path = temp_path("stream");
+        let provider = RecordingProvider::new(Streaming, &path).unwrap();
+        let events: Vec<_> = provider
+            .stream_completion(request("question"))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(events.len(), 2);
+
+        let replay = ReplayProvider::new(&path).unwrap().with_strict_matching(true);
+        let response = replay.complete(request("question")).await.unwrap();
+        assert_eq!(response.message.text(), Some("streamed answer"));
+        let _ = std::fs::remove_file(&path);
+    }
+}
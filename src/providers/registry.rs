@@ -0,0 +1,225 @@
+use std::{collections::HashMap, env, sync::Arc};
+
+use crate::{
+    providers::{
+        azure_openai::AzureOpenAI, gemini::Gemini, groq::Groq, ollama::Ollama, openai::OpenAI,
+        openrouter::OpenRouter, LLMProvider,
+    },
+    types::{ChatMessage, CompletionRequest, CompletionResponse},
+    LLMError,
+};
+
+/// Named providers with a default and optional fallbacks, so applications can swap
+/// providers per environment without touching call sites.
+#[derive(Clone, Default)]
+pub struct ProviderRegistry {
+    providers: HashMap<String, Arc<dyn LLMProvider>>,
+    default: Option<String>,
+    fallbacks: Vec<String>,
+}
+
+impl ProviderRegistry {
+    pub fn new() -> Self {
+        <Self as Default>::default()
+    }
+
+    /// Build a registry from `DENKWERK_PROVIDER` (the default) and the comma-separated
+    /// `DENKWERK_PROVIDER_FALLBACK`. Each provider is configured from its own environment
+    /// variables; providers that fail to configure are skipped.
+    pub fn from_env() -> Self {
+        let mut registry = Self::new();
+        let primary = env::var("DENKWERK_PROVIDER").ok();
+        let fallbacks = env::var("DENKWERK_PROVIDER_FALLBACK").unwrap_or_default();
+
+        for name in primary
+            .iter()
+            .map(String::as_str)
+            .chain(fallbacks.split(','))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if registry.providers.contains_key(name) {
+                continue;
+            }
+            match provider_from_env(name) {
+                Ok(provider) => {
+                    registry.register(name, provider);
+                }
+                #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+                Err(error) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!("skipping provider {name}: {error}");
+                }
+            }
+        }
+
+        if let Some(primary) = primary.as_deref().map(str::trim) {
+            let _ = registry.set_default(primary);
+        }
+        registry.fallbacks = fallbacks
+            .split(',')
+            .map(str::trim)
+            .filter(|name| registry.providers.contains_key(*name))
+            .map(str::to_string)
+            .collect();
+        registry
+    }
+
+    /// Register `provider` under `name`, returning any provider previously registered there.
+    pub fn register(
+        &mut self,
+        name: &str,
+        provider: Arc<dyn LLMProvider>,
+    ) -> Option<Arc<dyn LLMProvider>> {
+        self.providers.insert(name.to_string(), provider)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn LLMProvider>> {
+        self.providers.get(name).cloned()
+    }
+
+    /// Make the provider registered under `name` the default.
+    pub fn set_default(&mut self, name: &str) -> Result<(), LLMError> {
+        if !self.providers.contains_key(name) {
+            return Err(LLMError::Configuration(format!("unknown provider: {name}")));
+        }
+        self.default = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Providers tried in order when the default provider fails.
+    pub fn with_fallbacks<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.fallbacks = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn default_provider(&self) -> Option<Arc<dyn LLMProvider>> {
+        self.default.as_deref().and_then(|name| self.get(name))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.providers.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Complete with the default provider, retrying on the fallbacks if it fails.
+    pub async fn complete(
+        &self,
+        model: impl Into<String>,
+        messages: Vec<ChatMessage>,
+    ) -> Result<CompletionResponse, LLMError> {
+        let default = self
+            .default
+            .as_deref()
+            .ok_or_else(|| LLMError::Configuration("no default provider configured".to_string()))?;
+        let request = CompletionRequest::new(model, messages);
+
+        let mut result = self.complete_request(default, request.clone()).await;
+        for fallback in self.fallbacks.iter().filter(|name| name.as_str() != default) {
+            if result.is_ok() {
+                break;
+            }
+            result = self.complete_request(fallback, request.clone()).await;
+        }
+        result
+    }
+
+    /// Complete with the provider registered under `provider_name`.
+    pub async fn complete_with(
+        &self,
+        provider_name: &str,
+        model: impl Into<String>,
+        messages: Vec<ChatMessage>,
+    ) -> Result<CompletionResponse, LLMError> {
+        self.complete_request(provider_name, CompletionRequest::new(model, messages))
+            .await
+    }
+
+    async fn complete_request(
+        &self,
+        provider_name: &str,
+        request: CompletionRequest,
+    ) -> Result<CompletionResponse, LLMError> {
+        let provider = self
+            .get(provider_name)
+            .ok_or_else(|| LLMError::Configuration(format!("unknown provider: {provider_name}")))?;
+        provider.complete(request).await
+    }
+}
+
+/// Create a provider by name (`openai`, `openrouter`, `ollama`, `azure-openai`, `gemini`, `groq`) from its
+/// environment variables.
+pub fn provider_from_env(name: &str) -> Result<Arc<dyn LLMProvider>, LLMError> {
+    let provider: Arc<dyn LLMProvider> = match name {
+        "openai" => Arc::new(OpenAI::from_env()?),
+        "openrouter" => Arc::new(OpenRouter::from_env()?),
+        "ollama" => Arc::new(Ollama::from_env()?),
+        "azure-openai" | "azure" => Arc::new(AzureOpenAI::from_env()?),
+        "gemini" => Arc::new(Gemini::from_env()?),
+        "groq" => Arc::new(Groq::from_env()?),
+        other => return Err(LLMError::Configuration(format!("unknown provider: {other}"))),
+    };
+    Ok(provider)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::scripted::ScriptedProvider;
+
+    fn scripted(response: &str) -> Arc<dyn LLMProvider> {
+        Arc::new(ScriptedProvider::with_responses(&[response]))
+    }
+
+    #[tokio::test]
+    async fn dispatches_to_default_and_named_providers() {
+        let mut registry = ProviderRegistry::new();
+        registry.register("dev", scripted("from dev"));
+        registry.register("prod", scripted("from prod"));
+        let error = registry.complete("model", vec![ChatMessage::user("hi")]).await.unwrap_err();
+        assert!(matches!(error, LLMError::Configuration(_)), "{error}");
+        registry.set_default("prod").unwrap();
+        assert!(matches!(registry.set_default("staging"), Err(LLMError::Configuration(_))));
+
+        let response = registry
+            .complete("model", vec![ChatMessage::user("hi")])
+            .await
+            .unwrap();
+        assert_eq!(response.message.text(), Some("from prod"));
+
+        let response = registry
+            .complete_with("dev", "model", vec![ChatMessage::user("hi")])
+            .await
+            .unwrap();
+        assert_eq!(response.message.text(), Some("from dev"));
+        assert!(registry
+            .complete_with("missing", "model", vec![ChatMessage::user("hi")])
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn falls_back_when_default_fails() {
+        let mut registry = ProviderRegistry::new();
+        registry.register("primary", Arc::new(ScriptedProvider::new()));
+        registry.register("backup", scripted("from backup"));
+        registry.set_default("primary").unwrap();
+        let registry = registry.with_fallbacks(["backup"]);
+
+        let response = registry
+            .complete("model", vec![ChatMessage::user("hi")])
+            .await
+            .unwrap();
+        assert_eq!(response.message.text(), Some("from backup"));
+    }
+
+    #[test]
+    fn rejects_unknown_provider_names() {
+        assert!(provider_from_env("made-up").is_err());
+    }
+}
@@ -0,0 +1,422 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+
+use crate::{
+    providers::LLMProvider,
+    types::{
+        CompletionRequest, CompletionResponse, CompletionStream, EmbeddingRequest,
+        EmbeddingResponse, ImageUploadRequest, ImageUploadResponse, ModelInfo,
+        ProviderCapabilities,
+    },
+    LLMError,
+};
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Decides whether a failed call is retried and how long to wait first. `attempt` is the
+/// number of retries already made, so it is zero when the first call fails.
+pub trait RetryDecisionPolicy: Send + Sync {
+    fn should_retry(&self, error: &LLMError, attempt: usize) -> bool;
+
+    fn backoff(&self, attempt: usize) -> Duration;
+
+    /// Narrow this policy to errors for which [`LLMError::is_transient`] holds.
+    fn transient_only(self) -> TransientOnly<Self>
+    where
+        Self: Sized,
+    {
+        TransientOnly { inner: self }
+    }
+
+    /// Spread each backoff randomly by up to 20% in either direction when `enabled`, so
+    /// clients failing together do not retry in lockstep.
+    fn with_jitter(self, enabled: bool) -> Jittered<Self>
+    where
+        Self: Sized,
+    {
+        Jittered { inner: self, enabled }
+    }
+}
+
+/// Retries [`LLMError::is_retryable`] errors up to `max_attempts` times, waiting
+/// `initial_ms * multiplier^attempt` milliseconds, capped at `max_ms`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExponentialRetryPolicy {
+    pub max_attempts: usize,
+    pub initial_ms: u64,
+    pub multiplier: f64,
+    pub max_ms: u64,
+}
+
+impl ExponentialRetryPolicy {
+    /// Doubles `backoff` on every retry, capped at 30 seconds.
+    pub fn new(max_attempts: usize, backoff: Duration) -> Self {
+        Self {
+            max_attempts,
+            initial_ms: backoff.as_millis() as u64,
+            multiplier: 2.0,
+            max_ms: MAX_BACKOFF.as_millis() as u64,
+        }
+    }
+}
+
+impl Default for ExponentialRetryPolicy {
+    fn default() -> Self {
+        Self::new(3, Duration::from_millis(500))
+    }
+}
+
+impl RetryDecisionPolicy for ExponentialRetryPolicy {
+    fn should_retry(&self, error: &LLMError, attempt: usize) -> bool {
+        attempt < self.max_attempts && error.is_retryable()
+    }
+
+    fn backoff(&self, attempt: usize) -> Duration {
+        let exponent = i32::try_from(attempt).unwrap_or(i32::MAX);
+        let delay = self.initial_ms as f64 * self.multiplier.powi(exponent);
+        Duration::from_millis(delay.min(self.max_ms as f64) as u64)
+    }
+}
+
+/// Retries [`LLMError::is_retryable`] errors up to `max_attempts` times, waiting
+/// `initial_ms + increment_ms * attempt` milliseconds, capped at `max_ms`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinearRetryPolicy {
+    pub max_attempts: usize,
+    pub initial_ms: u64,
+    pub increment_ms: u64,
+    pub max_ms: u64,
+}
+
+impl RetryDecisionPolicy for LinearRetryPolicy {
+    fn should_retry(&self, error: &LLMError, attempt: usize) -> bool {
+        attempt < self.max_attempts && error.is_retryable()
+    }
+
+    fn backoff(&self, attempt: usize) -> Duration {
+        let delay = self
+            .increment_ms
+            .saturating_mul(attempt as u64)
+            .saturating_add(self.initial_ms);
+        Duration::from_millis(delay.min(self.max_ms))
+    }
+}
+
+/// Never retries.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoRetry;
+
+impl RetryDecisionPolicy for NoRetry {
+    fn should_retry(&self, _error: &LLMError, _attempt: usize) -> bool {
+        false
+    }
+
+    fn backoff(&self, _attempt: usize) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// See [`RetryDecisionPolicy::transient_only`].
+#[derive(Debug, Clone)]
+pub struct TransientOnly<P> {
+    inner: P,
+}
+
+impl<P: RetryDecisionPolicy> RetryDecisionPolicy for TransientOnly<P> {
+    fn should_retry(&self, error: &LLMError, attempt: usize) -> bool {
+        error.is_transient() && self.inner.should_retry(error, attempt)
+    }
+
+    fn backoff(&self, attempt: usize) -> Duration {
+        self.inner.backoff(attempt)
+    }
+}
+
+/// See [`RetryDecisionPolicy::with_jitter`].
+#[derive(Debug, Clone)]
+pub struct Jittered<P> {
+    inner: P,
+    enabled: bool,
+}
+
+impl<P: RetryDecisionPolicy> RetryDecisionPolicy for Jittered<P> {
+    fn should_retry(&self, error: &LLMError, attempt: usize) -> bool {
+        self.inner.should_retry(error, attempt)
+    }
+
+    fn backoff(&self, attempt: usize) -> Duration {
+        let delay = self.inner.backoff(attempt);
+        if !self.enabled {
+            return delay;
+        }
+        // A freshly seeded std hasher is a cheap source of randomness in [0, 1).
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        delay.mul_f64(0.8 + 0.4 * random)
+    }
+}
+
+type RetryCallback = Arc<dyn Fn(u32, &LLMError) + Send + Sync>;
+
+/// Retries failed completions as decided by its [`RetryDecisionPolicy`]. Once the policy
+/// gives up, the first error is returned.
+#[derive(Clone)]
+pub struct RetryingProvider {
+    inner: Arc<dyn LLMProvider>,
+    policy: Arc<dyn RetryDecisionPolicy>,
+    on_retry: Option<RetryCallback>,
+}
+
+impl RetryingProvider {
+    pub fn new(inner: Arc<dyn LLMProvider>, policy: Arc<dyn RetryDecisionPolicy>) -> Self {
+        Self {
+            inner,
+            policy,
+            on_retry: None,
+        }
+    }
+
+    /// Called with the retry number (starting at one) and the error before each retry.
+    pub fn with_retry_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(u32, &LLMError) + Send + Sync + 'static,
+    {
+        self.on_retry = Some(Arc::new(callback));
+        self
+    }
+
+    pub fn inner(&self) -> Arc<dyn LLMProvider> {
+        Arc::clone(&self.inner)
+    }
+
+    async fn with_retries<T, F, Fut>(&self, mut call: F) -> Result<T, LLMError>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, LLMError>>,
+    {
+        let mut first_error = None;
+        let mut attempt = 0;
+        loop {
+            match call().await {
+                Ok(value) => return Ok(value),
+                Err(error) if self.policy.should_retry(&error, attempt) => {
+                    if let Some(callback) = &self.on_retry {
+                        callback(attempt as u32 + 1, &error);
+                    }
+                    first_error.get_or_insert(error);
+                    tokio::time::sleep(self.policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Err(error) => return Err(first_error.unwrap_or(error)),
+            }
+        }
+    }
+}
+
+/// Delay before retry `attempt` (starting at zero): `backoff * 2^attempt`, capped at 30s.
+pub fn retry_delay(backoff: Duration, attempt: u32) -> Duration {
+    backoff
+        .checked_mul(2u32.saturating_pow(attempt))
+        .map_or(MAX_BACKOFF, |delay| delay.min(MAX_BACKOFF))
+}
+
+#[async_trait]
+impl LLMProvider for RetryingProvider {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.with_retries(|| self.inner.complete(request.clone()))
+            .await
+    }
+
+    /// Only opening the stream is retried; errors mid-stream are passed through.
+    async fn stream_completion(
+        &self,
+        request: CompletionRequest,
+    ) -> Result<CompletionStream, LLMError> {
+        self.with_retries(|| self.inner.stream_completion(request.clone()))
+            .await
+    }
+
+    async fn upload_image(
+        &self,
+        request: ImageUploadRequest,
+    ) -> Result<ImageUploadResponse, LLMError> {
+        self.inner.upload_image(request).await
+    }
+
+    async fn create_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, LLMError> {
+        self.inner.create_embeddings(request).await
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    async fn model_info(&self, id: &str) -> Result<ModelInfo, LLMError> {
+        self.inner.model_info(id).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LLMError> {
+        self.inner.list_models().await
+    }
+
+    async fn count_tokens(&self, request: &CompletionRequest) -> Result<u32, LLMError> {
+        self.inner.count_tokens(request).await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{providers::scripted::ScriptedProvider, types::ChatMessage};
+
+    fn flaky(failures: usize, error: LLMError) -> Arc<ScriptedProvider> {
+        let mut provider = ScriptedProvider::with_responses(&["done"]);
+        provider.inject_transient_errors(failures, error);
+        Arc::new(provider)
+    }
+
+    fn rate_limited() -> LLMError {
+        LLMError::Provider("rate limited".to_string())
+    }
+
+    fn exponential(max_attempts: usize) -> Arc<dyn RetryDecisionPolicy> {
+        Arc::new(ExponentialRetryPolicy::new(max_attempts, Duration::from_millis(1)))
+    }
+
+    async fn complete(provider: &RetryingProvider) -> Result<CompletionResponse, LLMError> {
+        provider
+            .complete(CompletionRequest::new("m", vec![ChatMessage::user("hi")]))
+            .await
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let inner = flaky(2, rate_limited());
+        let retries = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&retries);
+        let provider = RetryingProvider::new(inner.clone(), exponential(3))
+            .with_retry_callback(move |attempt, error| {
+                seen.lock().unwrap().push((attempt, error.to_string()))
+            });
+
+        let response = complete(&provider).await.unwrap();
+        assert_eq!(response.message.text(), Some("done"));
+        assert_eq!(inner.calls(), 3);
+        assert_eq!(
+            *retries.lock().unwrap(),
+            vec![
+                (1, "provider error: rate limited".to_string()),
+                (2, "provider error: rate limited".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_first_error_when_retries_run_out() {
+        let inner = flaky(5, rate_limited());
+        let provider = RetryingProvider::new(inner.clone(), exponential(1));
+        let error = complete(&provider).await.unwrap_err();
+        assert!(matches!(error, LLMError::Provider(message) if message == "rate limited"));
+        assert_eq!(inner.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limits_up_to_max_attempts_but_not_permission_errors() {
+        let policy: Arc<dyn RetryDecisionPolicy> =
+            Arc::new(ExponentialRetryPolicy::new(2, Duration::from_millis(1)).transient_only());
+
+        let inner = flaky(5, rate_limited());
+        complete(&RetryingProvider::new(inner.clone(), Arc::clone(&policy)))
+            .await
+            .unwrap_err();
+        assert_eq!(inner.calls(), 3);
+
+        let inner = flaky(5, LLMError::PermissionDenied("no access".to_string()));
+        let error = complete(&RetryingProvider::new(inner.clone(), policy)).await.unwrap_err();
+        assert!(matches!(error, LLMError::PermissionDenied(_)));
+        assert_eq!(inner.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn no_retry_policy_fails_immediately() {
+        let inner = flaky(1, rate_limited());
+        complete(&RetryingProvider::new(inner.clone(), Arc::new(NoRetry)))
+            .await
+            .unwrap_err();
+        assert_eq!(inner.calls(), 1);
+    }
+
+    #[test]
+    fn transient_only_skips_function_execution_errors() {
+        let error = LLMError::FunctionExecution {
+            function: "weather".to_string(),
+            message: "handler returned 502".to_string(),
+        };
+        let policy = ExponentialRetryPolicy::default();
+        assert!(policy.should_retry(&error, 0));
+        assert!(!policy.transient_only().should_retry(&error, 0));
+    }
+
+    #[test]
+    fn policy_backoffs_grow_and_are_capped() {
+        let exponential = ExponentialRetryPolicy {
+            max_attempts: 10,
+            initial_ms: 100,
+            multiplier: 3.0,
+            max_ms: 2_000,
+        };
+        assert_eq!(exponential.backoff(0), Duration::from_millis(100));
+        assert_eq!(exponential.backoff(2), Duration::from_millis(900));
+        assert_eq!(exponential.backoff(5), Duration::from_millis(2_000));
+        assert_eq!(exponential.backoff(usize::MAX), Duration::from_millis(2_000));
+
+        let linear = LinearRetryPolicy {
+            max_attempts: 10,
+            initial_ms: 100,
+            increment_ms: 50,
+            max_ms: 300,
+        };
+        assert_eq!(linear.backoff(0), Duration::from_millis(100));
+        assert_eq!(linear.backoff(2), Duration::from_millis(200));
+        assert_eq!(linear.backoff(9), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn jitter_stays_within_twenty_percent() {
+        let base = LinearRetryPolicy {
+            max_attempts: 1,
+            initial_ms: 1_000,
+            increment_ms: 0,
+            max_ms: 1_000,
+        };
+        assert_eq!(base.clone().with_jitter(false).backoff(0), Duration::from_millis(1_000));
+        let jittered = base.with_jitter(true);
+        for _ in 0..100 {
+            let delay = jittered.backoff(0);
+            assert!(delay >= Duration::from_millis(800) && delay <= Duration::from_millis(1_200));
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_and_is_capped() {
+        let backoff = Duration::from_millis(100);
+        assert_eq!(retry_delay(backoff, 0), Duration::from_millis(100));
+        assert_eq!(retry_delay(backoff, 3), Duration::from_millis(800));
+        assert_eq!(retry_delay(backoff, 20), MAX_BACKOFF);
+        assert_eq!(retry_delay(backoff, 40), MAX_BACKOFF);
+    }
+}
@@ -1,62 +1,453 @@
+use std::{collections::HashMap, sync::Mutex};
+
 use async_trait::async_trait;
+use regex::Regex;
+use serde_json::Value;
 
 use crate::{
     eval::scenario::ScriptedTurn,
+    functions::{FunctionCall, ToolCall},
     providers::LLMProvider,
-    types::{ChatMessage, CompletionRequest, CompletionResponse},
+    types::{ChatMessage, CompletionRequest, CompletionResponse, MessageRole},
     LLMError,
 };
 
 pub struct ScriptedProvider {
-    responses: Vec<String>,
+    responses: Vec<ChatMessage>,
+    expected_inputs: HashMap<usize, Regex>,
+    rules: Vec<ScriptedRule>,
+    agent_errors: Vec<(String, LLMError)>,
+    agent_responses: Vec<(String, String)>,
+    state: Mutex<ScriptedState>,
+}
+
+enum ScriptedMatcher {
+    Pattern(Regex),
+    Contains(String),
+}
+
+impl ScriptedMatcher {
+    fn matches(&self, input: &str) -> bool {
+        match self {
+            ScriptedMatcher::Pattern(pattern) => pattern.is_match(input),
+            ScriptedMatcher::Contains(needle) => input.contains(needle.as_str()),
+        }
+    }
+}
+
+enum ScriptedReply {
+    Text(String),
+    Echo,
+    ToolCall {
+        name: String,
+        arguments: Value,
+        result: Value,
+    },
+}
+
+struct ScriptedRule {
+    matcher: ScriptedMatcher,
+    reply: ScriptedReply,
+}
+
+#[derive(Default)]
+struct ScriptedState {
     current: usize,
+    calls: usize,
+    turn_errors: HashMap<usize, LLMError>,
+    transient_errors: Option<(usize, LLMError)>,
+    issued_calls: usize,
+    /// Final answers for scripted tool calls, keyed by tool call id.
+    pending_results: HashMap<String, Value>,
 }
 
 impl ScriptedProvider {
     pub fn new() -> Self {
-        Self {
-            responses: Vec::new(),
-            current: 0,
-        }
+        Self::from_assistant_messages(Vec::new())
     }
 
     pub fn from_scripted_turns(turns: &[ScriptedTurn]) -> Self {
-        let responses = turns.iter().map(|t| t.response.clone()).collect();
+        Self::from_assistant_messages(
+            turns
+                .iter()
+                .map(|t| ChatMessage::assistant(t.response.clone()))
+                .collect(),
+        )
+    }
+
+    /// Reply with `responses` in order, one per call.
+    pub fn with_responses(responses: &[&str]) -> Self {
+        Self::from_assistant_messages(responses.iter().map(|&response| ChatMessage::assistant(response)).collect())
+    }
+
+    /// Reply with `responses` in order, tool calls included.
+    pub(crate) fn from_assistant_messages(responses: Vec<ChatMessage>) -> Self {
         Self {
             responses,
-            current: 0,
+            expected_inputs: HashMap::new(),
+            rules: Vec::new(),
+            agent_errors: Vec::new(),
+            agent_responses: Vec::new(),
+            state: Mutex::new(ScriptedState::default()),
+        }
+    }
+
+    /// Reply with `response` whenever the last user message matches `pattern`. Rules are
+    /// checked in insertion order before the turn-order queue.
+    pub fn add_response_for_pattern(&mut self, pattern: Regex, response: ScriptedTurn) {
+        self.rules.push(ScriptedRule {
+            matcher: ScriptedMatcher::Pattern(pattern),
+            reply: ScriptedReply::Text(response.response),
+        });
+    }
+
+    /// Reply with `response` whenever the last user message contains `contains`.
+    pub fn add_response_for_message(&mut self, contains: &str, response: ScriptedTurn) {
+        self.rules.push(ScriptedRule {
+            matcher: ScriptedMatcher::Contains(contains.to_string()),
+            reply: ScriptedReply::Text(response.response),
+        });
+    }
+
+    /// Reply to every user message with its own text. Earlier rules take precedence.
+    pub fn echo_user_messages(&mut self) {
+        self.rules.push(ScriptedRule {
+            matcher: ScriptedMatcher::Contains(String::new()),
+            reply: ScriptedReply::Echo,
+        });
+    }
+
+    /// Call `tool_name` with `args_match` whenever the last user message mentions the tool.
+    /// Once the tool result is sent back, the provider answers with `result`.
+    pub fn add_tool_call_response(&mut self, tool_name: &str, args_match: Value, result: Value) {
+        let pattern = Regex::new(&regex::escape(tool_name)).expect("escaped tool name is a valid regex");
+        self.add_tool_call_response_for_pattern(pattern, tool_name, args_match, result);
+    }
+
+    /// Like [`ScriptedProvider::add_tool_call_response`], triggered by a custom pattern.
+    pub fn add_tool_call_response_for_pattern(
+        &mut self,
+        pattern: Regex,
+        tool_name: &str,
+        args_match: Value,
+        result: Value,
+    ) {
+        self.rules.push(ScriptedRule {
+            matcher: ScriptedMatcher::Pattern(pattern),
+            reply: ScriptedReply::ToolCall {
+                name: tool_name.to_string(),
+                arguments: args_match,
+                result,
+            },
+        });
+    }
+
+    /// Fail the call with index `turn` (zero-based) once; later calls behave normally and
+    /// the failed call does not consume a scripted response.
+    pub fn inject_error_at_turn(&mut self, turn: usize, error: LLMError) {
+        self.state.get_mut().unwrap().turn_errors.insert(turn, error);
+    }
+
+    /// Fail the call with index `turn` (zero-based) unless the last user message of its
+    /// request matches `pattern`.
+    pub fn expect_input_at_turn(&mut self, turn: usize, pattern: Regex) {
+        self.expected_inputs.insert(turn, pattern);
+    }
+
+    /// Fail every call whose system prompt mentions `agent_name`.
+    pub fn inject_error_for_agent(&mut self, agent_name: &str, error: LLMError) {
+        self.agent_errors.push((agent_name.to_string(), error));
+    }
+
+    /// Reply with `response` to every call whose system prompt mentions `agent_name`. Checked
+    /// before the message rules, so concurrent agents get their own answers.
+    pub fn add_response_for_agent(&mut self, agent_name: &str, response: ScriptedTurn) {
+        self.agent_responses.push((agent_name.to_string(), response.response));
+    }
+
+    /// Fail the first `count` calls with `error`, then succeed.
+    pub fn inject_transient_errors(&mut self, count: usize, error: LLMError) {
+        self.state.get_mut().unwrap().transient_errors = Some((count, error));
+    }
+
+    /// Number of completion calls received so far, including failed ones.
+    pub fn calls(&self) -> usize {
+        self.state.lock().unwrap().calls
+    }
+
+    fn injected_error(&self, state: &mut ScriptedState, request: &CompletionRequest) -> Option<LLMError> {
+        let call = state.calls;
+        state.calls += 1;
+
+        if let Some((count, error)) = &state.transient_errors {
+            if call < *count {
+                return Some(replicate_error(error));
+            }
+        }
+
+        if let Some(error) = state.turn_errors.remove(&call) {
+            return Some(error);
+        }
+
+        if let Some(pattern) = self.expected_inputs.get(&call) {
+            let input = last_user_message(request).unwrap_or_default();
+            if !pattern.is_match(input) {
+                return Some(LLMError::Provider(format!(
+                    "call {call}: last user message `{input}` does not match `{pattern}`"
+                )));
+            }
         }
+
+        self.agent_errors
+            .iter()
+            .find(|(agent_name, _)| system_prompt_mentions(request, agent_name))
+            .map(|(_, error)| replicate_error(error))
+    }
+
+    fn next_response(&self, state: &mut ScriptedState) -> Option<ChatMessage> {
+        let response = self.responses.get(state.current)?.clone();
+        state.current += 1;
+        Some(response)
+    }
+
+    fn respond(&self, request: &CompletionRequest) -> Result<ChatMessage, LLMError> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(error) = self.injected_error(&mut state, request) {
+            return Err(error);
+        }
+
+        // A tool result for one of our scripted calls gets the scripted final answer.
+        if let Some(last) = request.messages.last() {
+            if last.role == MessageRole::Tool {
+                let result = last
+                    .tool_call_id
+                    .as_ref()
+                    .and_then(|id| state.pending_results.remove(id));
+                if let Some(result) = result {
+                    let content = match result {
+                        Value::String(text) => text,
+                        other => other.to_string(),
+                    };
+                    return Ok(ChatMessage::assistant(content));
+                }
+            }
+        }
+
+        if let Some((_, response)) = self
+            .agent_responses
+            .iter()
+            .find(|(agent_name, _)| system_prompt_mentions(request, agent_name))
+        {
+            return Ok(ChatMessage::assistant(response.clone()));
+        }
+
+        if let Some(input) = last_user_message(request) {
+            if let Some(rule) = self.rules.iter().find(|rule| rule.matcher.matches(input)) {
+                return Ok(match &rule.reply {
+                    ScriptedReply::Text(text) => ChatMessage::assistant(text.clone()),
+                    ScriptedReply::Echo => ChatMessage::assistant(input),
+                    ScriptedReply::ToolCall { name, arguments, result } => {
+                        let id = format!("scripted_call_{}", state.issued_calls);
+                        state.issued_calls += 1;
+                        state.pending_results.insert(id.clone(), result.clone());
+
+                        let mut message = ChatMessage::assistant("");
+                        message.content = None;
+                        message.tool_calls = vec![ToolCall {
+                            id: Some(id),
+                            ..ToolCall::new(FunctionCall {
+                                name: name.clone(),
+                                arguments: arguments.clone(),
+                                raw_arguments: Some(arguments.to_string()),
+                            })
+                        }];
+                        message
+                    }
+                });
+            }
+        }
+
+        self.next_response(&mut state)
+            .ok_or_else(|| LLMError::Provider("no more scripted responses".to_string()))
     }
+}
+
+fn system_prompt_mentions(request: &CompletionRequest, agent_name: &str) -> bool {
+    request
+        .messages
+        .iter()
+        .filter(|message| message.role == MessageRole::System)
+        .filter_map(|message| message.content.as_deref())
+        .any(|prompt| prompt.contains(agent_name))
+}
 
-    fn next_response(&mut self) -> Option<String> {
-        if self.current < self.responses.len() {
-            let response = self.responses[self.current].clone();
-            self.current += 1;
-            Some(response)
-        } else {
-            None
+fn last_user_message(request: &CompletionRequest) -> Option<&str> {
+    request
+        .messages
+        .iter()
+        .rev()
+        .find(|message| message.role == MessageRole::User)
+        .and_then(|message| message.content.as_deref())
+}
+
+/// `LLMError` isn't `Clone`; errors wrapping foreign types are reproduced by message.
+fn replicate_error(error: &LLMError) -> LLMError {
+    match error {
+        LLMError::Provider(message) => LLMError::Provider(message.clone()),
+        LLMError::MissingApiKey(key) => LLMError::MissingApiKey(key),
+        LLMError::InvalidResponse(message) => LLMError::InvalidResponse(message),
+        LLMError::Unsupported(feature) => LLMError::Unsupported(feature),
+        LLMError::Configuration(message) => LLMError::Configuration(message.clone()),
+        LLMError::UnknownFunction(name) => LLMError::UnknownFunction(name.clone()),
+        LLMError::InvalidFunctionArguments(message) => {
+            LLMError::InvalidFunctionArguments(message.clone())
+        }
+        LLMError::FunctionExecution { function, message } => LLMError::FunctionExecution {
+            function: function.clone(),
+            message: message.clone(),
+        },
+        LLMError::Timeout => LLMError::Timeout,
+        LLMError::CircuitOpen => LLMError::CircuitOpen,
+        LLMError::Unavailable => LLMError::Unavailable,
+        LLMError::RateLimited { message, retry_after } => LLMError::RateLimited {
+            message: message.clone(),
+            retry_after: *retry_after,
+        },
+        LLMError::Budget(error) => LLMError::Budget(error.clone()),
+        LLMError::WithContext { inner, agent, turn } => {
+            replicate_error(inner).with_agent_context(agent.clone(), *turn)
         }
+        LLMError::PermissionDenied(message) => LLMError::PermissionDenied(message.clone()),
+        LLMError::SchemaViolation(errors) => LLMError::SchemaViolation(errors.clone()),
+        LLMError::Http(_) | LLMError::Serialization(_) => LLMError::Provider(error.to_string()),
     }
 }
 
 #[async_trait]
 impl LLMProvider for ScriptedProvider {
-    async fn complete(&self, _request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
-        let provider = self as *const Self as *mut Self;
-        unsafe {
-            if let Some(response) = (*provider).next_response() {
-                Ok(CompletionResponse {
-                    message: ChatMessage::assistant(response),
-                    usage: None,
-                    reasoning: None,
-                })
-            } else {
-                Err(LLMError::Provider("no more scripted responses".to_string()))
-            }
-        }
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        Ok(CompletionResponse {
+            message: self.respond(&request)?,
+            usage: None,
+            reasoning: None,
+        })
     }
 
     fn name(&self) -> &'static str {
         "scripted"
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(response: &str) -> ScriptedTurn {
+        ScriptedTurn {
+            agent: "assistant".to_string(),
+            response: response.to_string(),
+            latency_ms: None,
+        }
+    }
+
+    async fn reply(provider: &ScriptedProvider, messages: Vec<ChatMessage>) -> ChatMessage {
+        provider
+            .complete(CompletionRequest::new("scripted", messages))
+            .await
+            .unwrap()
+            .message
+    }
+
+    #[tokio::test]
+    async fn pattern_rules_take_precedence_over_turn_order() {
+        let mut provider = ScriptedProvider::from_scripted_turns(&[turn("first"), turn("second")]);
+        provider.add_response_for_pattern(Regex::new("(?i)weather").unwrap(), turn("sunny"));
+        provider.add_response_for_message("bye", turn("goodbye"));
+
+        let weather = reply(&provider, vec![ChatMessage::user("What's the Weather?")]).await;
+        assert_eq!(weather.text(), Some("sunny"));
+        let bye = reply(&provider, vec![ChatMessage::user("ok bye")]).await;
+        assert_eq!(bye.text(), Some("goodbye"));
+        let fallback = reply(&provider, vec![ChatMessage::user("hello")]).await;
+        assert_eq!(fallback.text(), Some("first"));
+    }
+
+    #[tokio::test]
+    async fn matching_input_triggers_scripted_tool_call() {
+        let mut provider = ScriptedProvider::new();
+        provider.add_tool_call_response_for_pattern(
+            Regex::new(".*calculate.*").unwrap(),
+            "add",
+            serde_json::json!({ "a": 2, "b": 3 }),
+            serde_json::json!("The sum is 5"),
+        );
+
+        let question = ChatMessage::user("please calculate 2 + 3");
+        let call_message = reply(&provider, vec![question.clone()]).await;
+        assert_eq!(call_message.tool_calls.len(), 1);
+        let call = &call_message.tool_calls[0];
+        assert_eq!(call.function.name, "add");
+        assert_eq!(call.function.arguments, serde_json::json!({ "a": 2, "b": 3 }));
+
+        let id = call.id.clone().unwrap();
+        let answer = reply(
+            &provider,
+            vec![question, call_message.clone(), ChatMessage::tool(id, "5")],
+        )
+        .await;
+        assert_eq!(answer.text(), Some("The sum is 5"));
+
+        let unmatched = provider
+            .complete(CompletionRequest::new("scripted", vec![ChatMessage::user("hello")]))
+            .await;
+        assert!(unmatched.is_err());
+    }
+
+    #[tokio::test]
+    async fn turn_errors_fire_once() {
+        let mut provider = ScriptedProvider::from_scripted_turns(&[turn("one"), turn("two")]);
+        provider.inject_error_at_turn(1, LLMError::Provider("timeout".into()));
+
+        assert_eq!(reply(&provider, vec![ChatMessage::user("a")]).await.text(), Some("one"));
+        let err = provider
+            .complete(CompletionRequest::new("scripted", vec![ChatMessage::user("b")]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "provider error: timeout");
+        assert_eq!(reply(&provider, vec![ChatMessage::user("c")]).await.text(), Some("two"));
+    }
+
+    #[tokio::test]
+    async fn transient_and_agent_errors() {
+        let mut provider = ScriptedProvider::from_scripted_turns(&[turn("ok"), turn("again")]);
+        provider.inject_transient_errors(2, LLMError::Provider("rate limited".into()));
+        provider.inject_error_for_agent("Critic", LLMError::Unsupported("critique"));
+
+        let request = || CompletionRequest::new("scripted", vec![ChatMessage::user("hi")]);
+        assert!(provider.complete(request()).await.is_err());
+        assert!(provider.complete(request()).await.is_err());
+        assert_eq!(provider.complete(request()).await.unwrap().message.text(), Some("ok"));
+
+        let critic = CompletionRequest::new(
+            "scripted",
+            vec![ChatMessage::system("You are Critic."), ChatMessage::user("review")],
+        );
+        let err = provider.complete(critic).await.unwrap_err();
+        assert!(matches!(err, LLMError::Unsupported("critique")));
+        assert_eq!(provider.complete(request()).await.unwrap().message.text(), Some("again"));
+    }
+
+    #[tokio::test]
+    async fn tool_name_mentions_trigger_tool_calls() {
+        let mut provider = ScriptedProvider::new();
+        provider.add_tool_call_response(
+            "calculate",
+            serde_json::json!({ "expression": "6*7" }),
+            serde_json::json!({ "value": 42 }),
+        );
+
+        let message = reply(&provider, vec![ChatMessage::user("calculate 6*7")]).await;
+        assert_eq!(message.tool_calls[0].function.name, "calculate");
+    }
+}
@@ -0,0 +1,336 @@
+//! REST API around a [`HandoffOrchestrator`], enabled by the `http-server` feature.
+//!
+//! - `POST /sessions` starts a session: `{"agent": "..."}` (optional) → `{session_id, active_agent}`
+//! - `POST /sessions/{id}/send` sends a message: `{message, agent?}` → `{reply, events, active_agent}`
+//! - `GET /sessions/{id}` returns the session transcript
+//! - `DELETE /sessions/{id}` drops the session
+
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{Mutex, RwLock},
+    task::JoinHandle,
+};
+
+use crate::{
+    flows::handoffflow::{HandoffEvent, HandoffOrchestrator},
+    types::{ChatMessage, CorrelationId},
+    AgentError, LLMError,
+};
+
+/// Everything needed to resume a [`crate::flows::handoffflow::HandoffSession`] between requests.
+struct StoredSession {
+    transcript: Vec<ChatMessage>,
+    active_agent: String,
+    remaining_handoffs: Option<usize>,
+    correlation_id: Option<CorrelationId>,
+}
+
+type SessionMap = HashMap<String, Arc<Mutex<StoredSession>>>;
+
+#[derive(Clone)]
+struct ServerState {
+    orchestrator: Arc<HandoffOrchestrator>,
+    default_agent: Option<String>,
+    sessions: Arc<RwLock<SessionMap>>,
+}
+
+pub struct AgentServer {
+    state: ServerState,
+}
+
+impl AgentServer {
+    pub fn new(orchestrator: Arc<HandoffOrchestrator>) -> Self {
+        Self {
+            state: ServerState {
+                orchestrator,
+                default_agent: None,
+                sessions: Arc::new(RwLock::new(HashMap::new())),
+            },
+        }
+    }
+
+    /// Agent that new sessions start with when `POST /sessions` names none.
+    pub fn with_default_agent(mut self, agent: impl Into<String>) -> Self {
+        self.state.default_agent = Some(agent.into());
+        self
+    }
+
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/sessions", post(create_session))
+            .route("/sessions/{id}", get(get_session).delete(delete_session))
+            .route("/sessions/{id}/send", post(send_message))
+            .with_state(self.state.clone())
+    }
+
+    /// Binds `addr` and serves in the background. Returns the bound address, which differs
+    /// from `addr` when binding port 0.
+    pub async fn bind(
+        self,
+        addr: SocketAddr,
+    ) -> std::io::Result<(SocketAddr, JoinHandle<std::io::Result<()>>)> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let router = self.router();
+        let handle = tokio::spawn(async move { axum::serve(listener, router).await });
+        Ok((local_addr, handle))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CreateSessionRequest {
+    agent: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateSessionResponse {
+    session_id: String,
+    active_agent: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendRequest {
+    message: String,
+    /// Switches the session to this agent before sending.
+    agent: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SendResponse {
+    reply: Option<String>,
+    events: Vec<HandoffEvent>,
+    active_agent: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionResponse {
+    session_id: String,
+    active_agent: String,
+    transcript: Vec<ChatMessage>,
+}
+
+#[derive(Debug)]
+enum ServerError {
+    SessionNotFound(String),
+    MissingAgent,
+    Agent(AgentError),
+}
+
+impl From<AgentError> for ServerError {
+    fn from(error: AgentError) -> Self {
+        Self::Agent(error)
+    }
+}
+
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            ServerError::SessionNotFound(_) => StatusCode::NOT_FOUND,
+            ServerError::MissingAgent | ServerError::Agent(AgentError::UnknownAgent(_)) => {
+                StatusCode::BAD_REQUEST
+            }
+            ServerError::Agent(AgentError::ProviderTimeout) => StatusCode::GATEWAY_TIMEOUT,
+            ServerError::Agent(AgentError::Provider(error))
+                if matches!(error.root_cause(), LLMError::Timeout) =>
+            {
+                StatusCode::GATEWAY_TIMEOUT
+            }
+            ServerError::Agent(AgentError::Provider(_)) => StatusCode::BAD_GATEWAY,
+            ServerError::Agent(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let message = match self {
+            ServerError::SessionNotFound(id) => format!("unknown session: {id}"),
+            ServerError::MissingAgent => "no agent given and no default agent configured".to_string(),
+            ServerError::Agent(error) => error.to_string(),
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+async fn find_session(
+    state: &ServerState,
+    id: &str,
+) -> Result<Arc<Mutex<StoredSession>>, ServerError> {
+    state
+        .sessions
+        .read()
+        .await
+        .get(id)
+        .cloned()
+        .ok_or_else(|| ServerError::SessionNotFound(id.to_string()))
+}
+
+async fn create_session(
+    State(state): State<ServerState>,
+    body: Option<Json<CreateSessionRequest>>,
+) -> Result<(StatusCode, Json<CreateSessionResponse>), ServerError> {
+    let Json(body) = body.unwrap_or_default();
+    let agent = body
+        .agent
+        .or_else(|| state.default_agent.clone())
+        .ok_or(ServerError::MissingAgent)?;
+    let session = state.orchestrator.session(agent)?;
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let active_agent = session.active_agent().to_string();
+    let stored = StoredSession {
+        transcript: Vec::new(),
+        active_agent: active_agent.clone(),
+        remaining_handoffs: session.max_handoffs(),
+        correlation_id: None,
+    };
+    state
+        .sessions
+        .write()
+        .await
+        .insert(session_id.clone(), Arc::new(Mutex::new(stored)));
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateSessionResponse {
+            session_id,
+            active_agent,
+        }),
+    ))
+}
+
+async fn send_message(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+    Json(body): Json<SendRequest>,
+) -> Result<Json<SendResponse>, ServerError> {
+    let stored = find_session(&state, &id).await?;
+    let mut stored = stored.lock().await;
+
+    let agent = body.agent.unwrap_or_else(|| stored.active_agent.clone());
+    let mut session = state.orchestrator.session(agent)?;
+    session.set_history(stored.transcript.clone());
+    session.set_max_handoffs(stored.remaining_handoffs);
+    if let Some(correlation_id) = stored.correlation_id {
+        session.set_correlation_id(correlation_id);
+    }
+
+    let turn = session.send(body.message).await?;
+
+    stored.transcript = session.transcript().to_vec();
+    stored.active_agent = session.active_agent().to_string();
+    stored.remaining_handoffs = session.max_handoffs();
+    stored.correlation_id = session.correlation_id();
+
+    Ok(Json(SendResponse {
+        reply: turn.reply,
+        events: turn.events,
+        active_agent: stored.active_agent.clone(),
+    }))
+}
+
+async fn get_session(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+) -> Result<Json<SessionResponse>, ServerError> {
+    let stored = find_session(&state, &id).await?;
+    let stored = stored.lock().await;
+    Ok(Json(SessionResponse {
+        session_id: id,
+        active_agent: stored.active_agent.clone(),
+        transcript: stored.transcript.clone(),
+    }))
+}
+
+async fn delete_session(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ServerError> {
+    state
+        .sessions
+        .write()
+        .await
+        .remove(&id)
+        .map(|_| StatusCode::NO_CONTENT)
+        .ok_or(ServerError::SessionNotFound(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{providers::scripted::ScriptedProvider, Agent, ScriptedTurn};
+
+    async fn start_server() -> String {
+        let provider = ScriptedProvider::from_scripted_turns(&[ScriptedTurn {
+            agent: "support".to_string(),
+            response: "Happy to help!".to_string(),
+            latency_ms: None,
+        }]);
+        let mut orchestrator = HandoffOrchestrator::new(Arc::new(provider), "model");
+        orchestrator.register_agent(Agent::from_string("support", "Help the user."));
+
+        let server = AgentServer::new(Arc::new(orchestrator)).with_default_agent("support");
+        let (addr, _handle) = server.bind(SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn creates_session_and_replies_to_messages() {
+        let base = start_server().await;
+        let client = reqwest::Client::new();
+
+        let created: serde_json::Value = client
+            .post(format!("{base}/sessions"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(created["active_agent"], "support");
+        let id = created["session_id"].as_str().unwrap();
+
+        let response = client
+            .post(format!("{base}/sessions/{id}/send"))
+            .json(&serde_json::json!({ "message": "I need help" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let turn: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(turn["reply"], "Happy to help!");
+        assert_eq!(turn["active_agent"], "support");
+        assert_eq!(turn["events"][0]["type"], "message");
+
+        let session: serde_json::Value = client
+            .get(format!("{base}/sessions/{id}"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(session["transcript"].as_array().unwrap().len(), 2);
+
+        let deleted = client.delete(format!("{base}/sessions/{id}")).send().await.unwrap();
+        assert_eq!(deleted.status(), reqwest::StatusCode::NO_CONTENT);
+        let missing = client.get(format!("{base}/sessions/{id}")).send().await.unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_agents() {
+        let base = start_server().await;
+        let response = reqwest::Client::new()
+            .post(format!("{base}/sessions"))
+            .json(&serde_json::json!({ "agent": "nobody" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    }
+}
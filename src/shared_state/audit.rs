@@ -0,0 +1,337 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::SharedStateContext;
+use crate::LLMError;
+
+/// Kind of state mutation recorded in an [`AuditEntry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateOperation {
+    Set,
+    Delete,
+    Clear,
+    CompareAndSwap,
+}
+
+/// Who changed which state entry, when, and from what to what
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub operation: StateOperation,
+    /// State ID; `*` for [`StateOperation::Clear`]
+    pub key: String,
+    pub scope: Option<String>,
+    pub actor: String,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Sink for audit entries
+#[async_trait]
+pub trait AuditLog: Send + Sync {
+    async fn log(&self, entry: AuditEntry) -> Result<(), LLMError>;
+
+    /// Entries retained in memory, if the log keeps any
+    fn entries(&self) -> Vec<AuditEntry> {
+        Vec::new()
+    }
+}
+
+/// Audit log that keeps every entry in memory, mainly for tests
+#[derive(Debug, Default)]
+pub struct InMemoryAuditLog {
+    entries: Mutex<Vec<AuditEntry>>,
+}
+
+impl InMemoryAuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl AuditLog for InMemoryAuditLog {
+    async fn log(&self, entry: AuditEntry) -> Result<(), LLMError> {
+        self.entries.lock().unwrap().push(entry);
+        Ok(())
+    }
+
+    fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+}
+
+/// Audit log that appends every entry as a JSON line to a file on disk
+#[derive(Debug)]
+pub struct JsonlFileAuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl JsonlFileAuditLog {
+    /// Open (or create) the JSONL file at `path` for appending.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl AuditLog for JsonlFileAuditLog {
+    async fn log(&self, entry: AuditEntry) -> Result<(), LLMError> {
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        self.file
+            .lock()
+            .unwrap()
+            .write_all(&line)
+            .map_err(|error| LLMError::Provider(format!("audit log error: {error}")))
+    }
+}
+
+/// Shared state store wrapper that records every successful mutation to an [`AuditLog`].
+///
+/// Old values are read from the wrapped store just before each write, so under concurrent
+/// writers they describe what this actor saw rather than a serialized history.
+pub struct AuditedSharedStateStore<S: SharedStateContext + ?Sized> {
+    inner: Arc<S>,
+    log: Arc<dyn AuditLog>,
+    actor: String,
+}
+
+impl<S: SharedStateContext + ?Sized> AuditedSharedStateStore<S> {
+    pub fn new(inner: Arc<S>, log: Arc<dyn AuditLog>) -> Self {
+        Self {
+            inner,
+            log,
+            actor: "unknown".to_string(),
+        }
+    }
+
+    /// Handle that shares the store and log but attributes mutations to `name`.
+    pub fn with_actor(&self, name: &str) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            log: Arc::clone(&self.log),
+            actor: name.to_string(),
+        }
+    }
+
+    pub fn actor(&self) -> &str {
+        &self.actor
+    }
+
+    pub fn inner(&self) -> &Arc<S> {
+        &self.inner
+    }
+
+    /// Entries retained by the audit log; empty for logs that don't keep entries in memory.
+    pub fn audit_entries(&self) -> Vec<AuditEntry> {
+        self.log.entries()
+    }
+
+    async fn record(
+        &self,
+        operation: StateOperation,
+        key: &str,
+        scope: Option<&str>,
+        old_value: Option<Value>,
+        new_value: Option<Value>,
+    ) -> Result<(), LLMError> {
+        self.log
+            .log(AuditEntry {
+                operation,
+                key: key.to_string(),
+                scope: scope.map(str::to_string),
+                actor: self.actor.clone(),
+                old_value,
+                new_value,
+                timestamp: Utc::now(),
+            })
+            .await
+    }
+}
+
+impl<S: SharedStateContext + ?Sized> Clone for AuditedSharedStateStore<S> {
+    fn clone(&self) -> Self {
+        self.with_actor(&self.actor)
+    }
+}
+
+impl<S: SharedStateContext + ?Sized> std::fmt::Debug for AuditedSharedStateStore<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditedSharedStateStore")
+            .field("actor", &self.actor)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<S: SharedStateContext + ?Sized> SharedStateContext for AuditedSharedStateStore<S> {
+    async fn queue_state_update(
+        &self,
+        id: String,
+        value: Value,
+        scope: Option<String>,
+        ttl: Option<Duration>,
+    ) -> Result<(), LLMError> {
+        let old_value = self.inner.read_state(&id, scope.as_deref()).await?;
+        self.inner
+            .queue_state_update(id.clone(), value.clone(), scope.clone(), ttl)
+            .await?;
+        self.record(StateOperation::Set, &id, scope.as_deref(), old_value, Some(value))
+            .await
+    }
+
+    async fn read_state(
+        &self,
+        id: &str,
+        scope: Option<&str>,
+    ) -> Result<Option<Value>, LLMError> {
+        self.inner.read_state(id, scope).await
+    }
+
+    async fn list_state_ids(&self, scope: Option<&str>) -> Result<Vec<String>, LLMError> {
+        self.inner.list_state_ids(scope).await
+    }
+
+    async fn remove_state(
+        &self,
+        id: &str,
+        scope: Option<&str>,
+    ) -> Result<bool, LLMError> {
+        let old_value = self.inner.read_state(id, scope).await?;
+        let removed = self.inner.remove_state(id, scope).await?;
+        if removed {
+            self.record(StateOperation::Delete, id, scope, old_value, None)
+                .await?;
+        }
+        Ok(removed)
+    }
+
+    async fn clear_states(&self, scope: Option<&str>) -> Result<usize, LLMError> {
+        let removed = self.inner.clear_states(scope).await?;
+        if removed > 0 {
+            self.record(StateOperation::Clear, "*", scope, None, None)
+                .await?;
+        }
+        Ok(removed)
+    }
+
+    async fn compare_and_swap(
+        &self,
+        id: &str,
+        scope: Option<&str>,
+        expected: &Value,
+        new_value: Value,
+    ) -> Result<bool, LLMError> {
+        let swapped = self
+            .inner
+            .compare_and_swap(id, scope, expected, new_value.clone())
+            .await?;
+        if swapped {
+            self.record(
+                StateOperation::CompareAndSwap,
+                id,
+                scope,
+                Some(expected.clone()),
+                Some(new_value),
+            )
+            .await?;
+        }
+        Ok(swapped)
+    }
+
+    fn subscribe(&self, id: &str, scope: Option<&str>) -> tokio::sync::watch::Receiver<Option<Value>> {
+        self.inner.subscribe(id, scope)
+    }
+
+    fn watch_prefix(&self, prefix: &str) -> futures_util::stream::BoxStream<'static, (String, Option<Value>)> {
+        self.inner.watch_prefix(prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared_state::InMemorySharedStateStore;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn records_actor_for_each_set() {
+        let log = Arc::new(InMemoryAuditLog::new());
+        let store = AuditedSharedStateStore::new(
+            Arc::new(InMemorySharedStateStore::new()),
+            log.clone(),
+        );
+        let planner = store.with_actor("planner");
+        let reviewer = store.with_actor("reviewer");
+
+        planner
+            .queue_state_update("plan".to_string(), json!("draft"), None, None)
+            .await
+            .unwrap();
+        reviewer
+            .queue_state_update("plan".to_string(), json!("approved"), None, None)
+            .await
+            .unwrap();
+        planner
+            .queue_state_scoped("owner".to_string(), json!("ada"), "team".to_string())
+            .await
+            .unwrap();
+
+        let entries = store.audit_entries();
+        assert_eq!(entries.len(), 3);
+        let actors: Vec<&str> = entries.iter().map(|entry| entry.actor.as_str()).collect();
+        assert_eq!(actors, ["planner", "reviewer", "planner"]);
+        assert!(entries.iter().all(|entry| entry.operation == StateOperation::Set));
+        assert_eq!(entries[1].old_value, Some(json!("draft")));
+        assert_eq!(entries[1].new_value, Some(json!("approved")));
+        assert_eq!(entries[2].scope.as_deref(), Some("team"));
+    }
+
+    #[tokio::test]
+    async fn jsonl_log_appends_lines() {
+        let path = std::env::temp_dir().join(format!("denkwerk-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let log = Arc::new(JsonlFileAuditLog::new(&path).unwrap());
+        let store = AuditedSharedStateStore::new(Arc::new(InMemorySharedStateStore::new()), log)
+            .with_actor("writer");
+
+        store
+            .queue_state_update("plan".to_string(), json!(1), None, None)
+            .await
+            .unwrap();
+        store.remove_state("plan", None).await.unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let entries: Vec<AuditEntry> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].operation, StateOperation::Delete);
+        assert_eq!(entries[1].old_value, Some(json!(1)));
+        assert!(store.audit_entries().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
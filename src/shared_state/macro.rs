@@ -0,0 +1,43 @@
+//! Typed shared state keys.
+//!
+//! [`shared_state_keys!`](crate::shared_state_keys) generates [`StateKey`] markers together
+//! with `get_*` / `set_*` helpers built on [`read_typed`] and [`write_typed`], so callers
+//! stop passing raw key strings and untyped JSON around.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::SharedStateContext;
+use crate::LLMError;
+
+/// A shared state key with a fixed value type.
+pub trait StateKey {
+    /// Key passed to the underlying store
+    const KEY: &'static str;
+    /// Type stored under the key
+    type Value: Serialize + DeserializeOwned + Send;
+}
+
+/// Read and deserialize the value stored under `K`.
+pub async fn read_typed<K, S>(store: &S, scope: Option<&str>) -> Result<Option<K::Value>, LLMError>
+where
+    K: StateKey,
+    S: SharedStateContext + ?Sized,
+{
+    match store.read_state(K::KEY, scope).await? {
+        Some(value) => Ok(Some(serde_json::from_value(value)?)),
+        None => Ok(None),
+    }
+}
+
+/// Serialize `value` and store it under `K`.
+pub async fn write_typed<K, S>(store: &S, scope: Option<&str>, value: K::Value) -> Result<(), LLMError>
+where
+    K: StateKey,
+    S: SharedStateContext + ?Sized,
+{
+    let value = serde_json::to_value(value)?;
+    store
+        .queue_state_update(K::KEY.to_string(), value, scope.map(str::to_string), None)
+        .await
+}
@@ -1,13 +1,30 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
 use async_trait::async_trait;
+use futures_core::Stream;
+use futures_util::stream::{BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, watch, RwLock};
+use tokio_stream::wrappers::{BroadcastStream, WatchStream};
 
 use crate::LLMError;
 
+pub mod audit;
+mod r#macro;
+#[cfg(feature = "postgres-state")]
+pub mod postgres;
+#[cfg(feature = "redis-state")]
+pub mod redis;
+
+pub use audit::{
+    AuditEntry, AuditLog, AuditedSharedStateStore, InMemoryAuditLog, JsonlFileAuditLog,
+    StateOperation,
+};
+pub use r#macro::{read_typed, write_typed, StateKey};
+
 /// Represents a shared state entry with metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SharedStateEntry {
@@ -19,6 +36,9 @@ pub struct SharedStateEntry {
     pub created_at: chrono::DateTime<chrono::Utc>,
     /// Optional description of the state
     pub description: Option<String>,
+    /// Optional time-to-live after which the entry is treated as absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<Duration>,
 }
 
 impl SharedStateEntry {
@@ -28,6 +48,7 @@ impl SharedStateEntry {
             scope: None,
             created_at: chrono::Utc::now(),
             description: None,
+            ttl: None,
         }
     }
 
@@ -40,17 +61,39 @@ impl SharedStateEntry {
         self.description = Some(description.into());
         self
     }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Deadline after which the entry expires, if it has a TTL
+    pub fn expires_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let ttl = chrono::Duration::from_std(self.ttl?).ok()?;
+        Some(self.created_at + ttl)
+    }
+
+    /// Whether the entry has expired at `now`
+    pub fn is_expired_at(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.expires_at().is_some_and(|deadline| deadline < now)
+    }
+
+    /// Whether the entry has expired
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(chrono::Utc::now())
+    }
 }
 
 /// Trait for shared state operations within workflows
 #[async_trait]
 pub trait SharedStateContext: Send + Sync {
-    /// Store a value with the given ID and optional scope
+    /// Store a value with the given ID, optional scope and optional time-to-live
     async fn queue_state_update(
         &self,
         id: String,
         value: Value,
         scope: Option<String>,
+        ttl: Option<Duration>,
     ) -> Result<(), LLMError>;
 
     /// Store a value with the given ID and scope name
@@ -60,7 +103,7 @@ pub trait SharedStateContext: Send + Sync {
         value: Value,
         scope_name: String,
     ) -> Result<(), LLMError> {
-        self.queue_state_update(id, value, Some(scope_name)).await
+        self.queue_state_update(id, value, Some(scope_name), None).await
     }
 
     /// Read a value by ID, optionally filtered by scope
@@ -91,12 +134,174 @@ pub trait SharedStateContext: Send + Sync {
 
     /// Clear all states, optionally filtered by scope
     async fn clear_states(&self, scope: Option<&str>) -> Result<usize, LLMError>;
+
+    /// Atomically replace a value if it still equals `expected`.
+    ///
+    /// Returns `Ok(true)` when the update was applied and `Ok(false)` when the current value
+    /// differs (or the entry is absent), in which case the caller should re-read and retry.
+    async fn compare_and_swap(
+        &self,
+        _id: &str,
+        _scope: Option<&str>,
+        _expected: &Value,
+        _new_value: Value,
+    ) -> Result<bool, LLMError> {
+        Err(LLMError::Unsupported("compare_and_swap"))
+    }
+
+    /// Watch a state entry for changes. The receiver observes the new value after every
+    /// update and `None` once the entry is removed or expires.
+    ///
+    /// Stores without change notification return a receiver whose sender is already gone,
+    /// so `changed()` resolves with an error instead of waiting forever.
+    fn subscribe(&self, _id: &str, _scope: Option<&str>) -> watch::Receiver<Option<Value>> {
+        watch::channel(None).1
+    }
+
+    /// Stream `(key, value)` for every change to a key starting with `prefix`, where `key`
+    /// is the store key (`{scope}:{id}` for scoped entries) and `value` is `None` once the
+    /// entry is removed or expires.
+    ///
+    /// Stores without change notification return a stream that ends immediately.
+    fn watch_prefix(&self, _prefix: &str) -> BoxStream<'static, (String, Option<Value>)> {
+        futures_util::stream::empty().boxed()
+    }
+}
+
+/// Changes buffered per prefix watcher before the slowest one starts missing the oldest.
+const CHANGE_CHANNEL_CAPACITY: usize = 256;
+
+type StateChange = (String, Option<Value>);
+
+#[derive(Debug, Default)]
+struct StateWatchers {
+    keys: std::sync::Mutex<HashMap<String, watch::Sender<Option<Value>>>>,
+    /// Every change, for prefix watchers; created by the first one.
+    changes: OnceLock<broadcast::Sender<StateChange>>,
+}
+
+impl StateWatchers {
+    fn changes(&self) -> broadcast::Receiver<StateChange> {
+        self.changes
+            .get_or_init(|| broadcast::channel(CHANGE_CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+}
+
+/// Publish `value` to subscribers of `key`, dropping the channel once nobody listens.
+fn notify_watchers(watchers: &StateWatchers, key: &str, value: Option<Value>) {
+    if let Some(changes) = watchers.changes.get() {
+        let _ = changes.send((key.to_string(), value.clone()));
+    }
+
+    let mut keys = watchers.keys.lock().unwrap();
+    if let Some(sender) = keys.get(key) {
+        if sender.receiver_count() == 0 {
+            keys.remove(key);
+        } else {
+            sender.send_replace(value);
+        }
+    }
+}
+
+/// Changes from `receiver` to keys starting with `prefix`. Changes missed by falling more
+/// than [`CHANGE_CHANNEL_CAPACITY`] behind are skipped.
+fn prefix_stream(
+    receiver: broadcast::Receiver<StateChange>,
+    prefix: String,
+) -> impl Stream<Item = StateChange> + Send + 'static {
+    BroadcastStream::new(receiver).filter_map(move |change| {
+        let change = change.ok().filter(|(key, _)| key.starts_with(&prefix));
+        futures_util::future::ready(change)
+    })
+}
+
+/// Point-in-time copy of an [`InMemorySharedStateStore`], keyed like the store itself
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SharedStateSnapshot(pub HashMap<String, SharedStateEntry>);
+
+impl SharedStateSnapshot {
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, serde_json::Error> {
+        Self::deserialize(value)
+    }
+
+    pub fn entries(&self) -> &HashMap<String, SharedStateEntry> {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Kind of write recorded in a store's mutation history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MutationKind {
+    Set,
+    Delete,
+    Clear,
+}
+
+/// A single recorded write to an [`InMemorySharedStateStore`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateMutation {
+    /// Store key, `{scope}:{id}` for scoped entries
+    pub key: String,
+    /// Scope of the entry, `None` for unscoped entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub operation: MutationKind,
+}
+
+#[derive(Debug)]
+struct MutationLog {
+    entries: VecDeque<StateMutation>,
+    capacity: usize,
+}
+
+impl MutationLog {
+    fn record(
+        &mut self,
+        key: &str,
+        scope: Option<&str>,
+        old_value: Option<Value>,
+        new_value: Option<Value>,
+        operation: MutationKind,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(StateMutation {
+            key: key.to_string(),
+            scope: scope.map(str::to_string),
+            old_value,
+            new_value,
+            timestamp: chrono::Utc::now(),
+            operation,
+        });
+    }
 }
 
 /// In-memory shared state store implementation
 #[derive(Debug, Default)]
 pub struct InMemorySharedStateStore {
     states: Arc<RwLock<HashMap<String, SharedStateEntry>>>,
+    watchers: Arc<StateWatchers>,
+    history: Option<Arc<std::sync::Mutex<MutationLog>>>,
 }
 
 impl InMemorySharedStateStore {
@@ -104,6 +309,120 @@ impl InMemorySharedStateStore {
         Self::default()
     }
 
+    /// Periodically drop expired entries in a background task. Expired entries are already
+    /// hidden from reads; this only bounds memory. Must be called inside a Tokio runtime. The
+    /// task stops once the store is dropped.
+    pub fn with_cleanup_interval(self, interval: Duration) -> Self {
+        let states = Arc::downgrade(&self.states);
+        let watchers = Arc::downgrade(&self.watchers);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let (Some(states), Some(watchers)) = (states.upgrade(), watchers.upgrade()) else {
+                    break;
+                };
+                let now = chrono::Utc::now();
+                let mut expired = Vec::new();
+                states.write().await.retain(|key, entry| {
+                    let keep = !entry.is_expired_at(now);
+                    if !keep {
+                        expired.push(key.clone());
+                    }
+                    keep
+                });
+                for key in expired {
+                    notify_watchers(&watchers, &key, None);
+                }
+            }
+        });
+        self
+    }
+
+    /// Record the last `capacity` writes, evicting the oldest once full. Entries dropped by
+    /// expiry are not recorded.
+    pub fn with_history(mut self, capacity: usize) -> Self {
+        self.history = Some(Arc::new(std::sync::Mutex::new(MutationLog {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        })));
+        self
+    }
+
+    /// Snapshot of the recorded writes, oldest first. Empty unless [`Self::with_history`] was used.
+    pub fn mutation_history(&self) -> Vec<StateMutation> {
+        self.history
+            .as_ref()
+            .map(|log| log.lock().unwrap().entries.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Copy all live entries, e.g. to checkpoint a long-running flow. Expired entries are skipped.
+    pub async fn snapshot(&self) -> SharedStateSnapshot {
+        let states = self.states.read().await;
+        let now = chrono::Utc::now();
+        SharedStateSnapshot(
+            states
+                .iter()
+                .filter(|(_, entry)| !entry.is_expired_at(now))
+                .map(|(key, entry)| (key.clone(), entry.clone()))
+                .collect(),
+        )
+    }
+
+    /// Create a new store pre-populated from `snapshot`.
+    pub fn restore(snapshot: SharedStateSnapshot) -> Self {
+        Self {
+            states: Arc::new(RwLock::new(snapshot.0)),
+            ..Self::default()
+        }
+    }
+
+    /// Build a store holding the state produced by applying `history` in order to an empty store.
+    pub fn replay_history(history: &[StateMutation]) -> Self {
+        let mut states = HashMap::new();
+        for mutation in history {
+            match (&mutation.operation, &mutation.new_value) {
+                (MutationKind::Set, Some(value)) => {
+                    let mut entry = SharedStateEntry::new(value.clone());
+                    entry.scope = Some(mutation.scope.clone().unwrap_or_default());
+                    entry.created_at = mutation.timestamp;
+                    states.insert(mutation.key.clone(), entry);
+                }
+                _ => {
+                    states.remove(&mutation.key);
+                }
+            }
+        }
+
+        Self::restore(SharedStateSnapshot(states))
+    }
+
+    /// Stream the value of an entry after every change, `None` once it is removed or
+    /// expires. Changes made faster than the stream is polled are coalesced into the latest.
+    pub fn watch_key(&self, id: &str, scope: Option<&str>) -> impl Stream<Item = Option<Value>> + Send + 'static {
+        WatchStream::from_changes(self.subscribe(id, scope))
+    }
+
+    /// Stream every change to the store, for debugging. See [`SharedStateContext::watch_prefix`].
+    pub fn watch_all(&self) -> impl Stream<Item = (String, Option<Value>)> + Send + 'static {
+        prefix_stream(self.watchers.changes(), String::new())
+    }
+
+    fn record_mutation(
+        &self,
+        key: &str,
+        scope: Option<&str>,
+        old_value: Option<Value>,
+        new_value: Option<Value>,
+        operation: MutationKind,
+    ) {
+        if let Some(log) = &self.history {
+            log.lock().unwrap().record(key, scope, old_value, new_value, operation);
+        }
+    }
+
     fn generate_key(&self, id: &str, scope: Option<&str>) -> String {
         match scope {
             Some(scope) => format!("{}:{}", scope, id),
@@ -119,12 +438,25 @@ impl SharedStateContext for InMemorySharedStateStore {
         id: String,
         value: Value,
         scope: Option<String>,
+        ttl: Option<Duration>,
     ) -> Result<(), LLMError> {
         let key = self.generate_key(&id, scope.as_deref());
-        let entry = SharedStateEntry::new(value).with_scope(scope.unwrap_or_default());
+        let mut entry = SharedStateEntry::new(value).with_scope(scope.clone().unwrap_or_default());
+        entry.ttl = ttl;
 
+        let value = entry.value.clone();
         let mut states = self.states.write().await;
-        states.insert(key, entry);
+        let previous = states.insert(key.clone(), entry);
+        self.record_mutation(
+            &key,
+            scope.as_deref(),
+            previous.map(|entry| entry.value),
+            Some(value.clone()),
+            MutationKind::Set,
+        );
+        drop(states);
+
+        notify_watchers(&self.watchers, &key, Some(value));
         Ok(())
     }
 
@@ -134,15 +466,31 @@ impl SharedStateContext for InMemorySharedStateStore {
         scope: Option<&str>,
     ) -> Result<Option<Value>, LLMError> {
         let key = self.generate_key(id, scope);
-        let states = self.states.read().await;
-        Ok(states.get(&key).map(|entry| entry.value.clone()))
+        {
+            let states = self.states.read().await;
+            match states.get(&key) {
+                Some(entry) if !entry.is_expired() => return Ok(Some(entry.value.clone())),
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+
+        let mut states = self.states.write().await;
+        if states.get(&key).is_some_and(SharedStateEntry::is_expired) {
+            states.remove(&key);
+            drop(states);
+            notify_watchers(&self.watchers, &key, None);
+        }
+        Ok(None)
     }
 
     async fn list_state_ids(&self, scope: Option<&str>) -> Result<Vec<String>, LLMError> {
         let states = self.states.read().await;
+        let now = chrono::Utc::now();
         let ids: Vec<String> = states
-            .keys()
-            .filter_map(|key| {
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired_at(now))
+            .filter_map(|(key, _)| {
                 match scope {
                     Some(scope_filter) => {
                         if key.starts_with(&format!("{}:", scope_filter)) {
@@ -168,31 +516,91 @@ impl SharedStateContext for InMemorySharedStateStore {
     ) -> Result<bool, LLMError> {
         let key = self.generate_key(id, scope);
         let mut states = self.states.write().await;
-        Ok(states.remove(&key).is_some())
+        let removed = match states.remove(&key) {
+            Some(entry) => {
+                self.record_mutation(&key, scope, Some(entry.value), None, MutationKind::Delete);
+                true
+            }
+            None => false,
+        };
+        drop(states);
+
+        if removed {
+            notify_watchers(&self.watchers, &key, None);
+        }
+        Ok(removed)
     }
 
     async fn clear_states(&self, scope: Option<&str>) -> Result<usize, LLMError> {
         let mut states = self.states.write().await;
-        match scope {
+        let keys_to_remove: Vec<String> = match scope {
             Some(scope_filter) => {
                 let prefix = format!("{}:", scope_filter);
-                let keys_to_remove: Vec<String> = states
+                states
                     .keys()
                     .filter(|key| key.starts_with(&prefix) || *key == scope_filter)
                     .cloned()
-                    .collect();
-                let count = keys_to_remove.len();
-                for key in keys_to_remove {
-                    states.remove(&key);
-                }
-                Ok(count)
+                    .collect()
             }
-            None => {
-                let count = states.len();
-                states.clear();
-                Ok(count)
+            None => states.keys().cloned().collect(),
+        };
+        for key in &keys_to_remove {
+            if let Some(entry) = states.remove(key) {
+                let scope = entry.scope.as_deref().filter(|scope| !scope.is_empty());
+                self.record_mutation(key, scope, Some(entry.value), None, MutationKind::Clear);
             }
         }
+        drop(states);
+
+        for key in &keys_to_remove {
+            notify_watchers(&self.watchers, key, None);
+        }
+        Ok(keys_to_remove.len())
+    }
+
+    async fn compare_and_swap(
+        &self,
+        id: &str,
+        scope: Option<&str>,
+        expected: &Value,
+        new_value: Value,
+    ) -> Result<bool, LLMError> {
+        let key = self.generate_key(id, scope);
+        let mut states = self.states.write().await;
+        match states.get_mut(&key) {
+            Some(entry) if !entry.is_expired() && entry.value == *expected => {
+                let previous = std::mem::replace(&mut entry.value, new_value.clone());
+                self.record_mutation(&key, scope, Some(previous), Some(new_value.clone()), MutationKind::Set);
+            }
+            _ => return Ok(false),
+        }
+        drop(states);
+
+        notify_watchers(&self.watchers, &key, Some(new_value));
+        Ok(true)
+    }
+
+    fn subscribe(&self, id: &str, scope: Option<&str>) -> watch::Receiver<Option<Value>> {
+        let key = self.generate_key(id, scope);
+        let mut watchers = self.watchers.keys.lock().unwrap();
+        if let Some(sender) = watchers.get(&key) {
+            return sender.subscribe();
+        }
+
+        // Seed with the current value when the map is not locked for writing right now.
+        let current = self.states.try_read().ok().and_then(|states| {
+            states
+                .get(&key)
+                .filter(|entry| !entry.is_expired())
+                .map(|entry| entry.value.clone())
+        });
+        let (sender, receiver) = watch::channel(current);
+        watchers.insert(key, sender);
+        receiver
+    }
+
+    fn watch_prefix(&self, prefix: &str) -> BoxStream<'static, (String, Option<Value>)> {
+        prefix_stream(self.watchers.changes(), prefix.to_string()).boxed()
     }
 }
 
@@ -214,7 +622,7 @@ impl<'a, C: SharedStateContext + ?Sized> SharedStateExtensions<'a, C> {
         scope: Option<String>,
     ) -> Result<(), LLMError> {
         self.context
-            .queue_state_update(id, Value::String(value), scope)
+            .queue_state_update(id, Value::String(value), scope, None)
             .await
     }
 
@@ -227,7 +635,7 @@ impl<'a, C: SharedStateContext + ?Sized> SharedStateExtensions<'a, C> {
     ) -> Result<(), LLMError> {
         let json_value = serde_json::to_value(value)
             .map_err(|e| LLMError::Serialization(e))?;
-        self.context.queue_state_update(id, json_value, scope).await
+        self.context.queue_state_update(id, json_value, scope, None).await
     }
 
     /// Read a string value
@@ -280,7 +688,7 @@ mod tests {
 
         // Store a value
         store
-            .queue_state_update("test_key".to_string(), json!("test_value"), None)
+            .queue_state_update("test_key".to_string(), json!("test_value"), None, None)
             .await
             .unwrap();
 
@@ -330,7 +738,7 @@ mod tests {
 
         // Store multiple values
         store
-            .queue_state_update("key1".to_string(), json!("value1"), None)
+            .queue_state_update("key1".to_string(), json!("value1"), None, None)
             .await
             .unwrap();
         store
@@ -353,4 +761,290 @@ mod tests {
         let remaining_ids = store.list_state_ids(None).await.unwrap();
         assert_eq!(remaining_ids.len(), 1);
     }
+
+    #[tokio::test]
+    async fn test_expired_state_reads_as_absent() {
+        let store = InMemorySharedStateStore::new();
+
+        store
+            .queue_state_update(
+                "token".to_string(),
+                json!("secret"),
+                None,
+                Some(Duration::from_millis(100)),
+            )
+            .await
+            .unwrap();
+        store
+            .queue_state_update("durable".to_string(), json!(1), None, None)
+            .await
+            .unwrap();
+        assert_eq!(store.read_state("token", None).await.unwrap(), Some(json!("secret")));
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+
+        assert_eq!(store.read_state("token", None).await.unwrap(), None);
+        assert_eq!(store.list_state_ids(None).await.unwrap(), vec!["durable"]);
+        assert_eq!(store.states.read().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_task_drops_expired_entries() {
+        let store = InMemorySharedStateStore::new().with_cleanup_interval(Duration::from_millis(20));
+
+        store
+            .queue_state_update("counter".to_string(), json!(3), None, Some(Duration::from_millis(10)))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(store.states.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_observes_updates_and_removal() {
+        let store = Arc::new(InMemorySharedStateStore::new());
+        let mut receiver = store.subscribe("status", Some("build"));
+        assert_eq!(*receiver.borrow(), None);
+
+        let writer = store.clone();
+        tokio::spawn(async move {
+            writer
+                .queue_state_scoped("status".to_string(), json!("green"), "build".to_string())
+                .await
+                .unwrap();
+        });
+
+        receiver.changed().await.unwrap();
+        assert_eq!(*receiver.borrow_and_update(), Some(json!("green")));
+
+        store.remove_state("status", Some("build")).await.unwrap();
+        receiver.changed().await.unwrap();
+        assert_eq!(*receiver.borrow_and_update(), None);
+    }
+
+    #[tokio::test]
+    async fn test_watch_key_streams_each_update() {
+        let store = Arc::new(InMemorySharedStateStore::new());
+        let mut stream = store.watch_key("result", None);
+        let (events, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let watcher = tokio::spawn(async move {
+            while let Some(value) = stream.next().await {
+                events.send(value).unwrap();
+            }
+        });
+
+        for value in [json!("draft"), json!("final")] {
+            store
+                .queue_state_update("result".to_string(), value.clone(), None, None)
+                .await
+                .unwrap();
+            assert_eq!(received.recv().await, Some(Some(value)));
+        }
+        let extra = tokio::time::timeout(Duration::from_millis(50), received.recv()).await;
+        assert!(extra.is_err(), "unexpected event: {extra:?}");
+        watcher.abort();
+    }
+
+    #[tokio::test]
+    async fn test_watch_prefix_streams_matching_keys() {
+        let store = Arc::new(InMemorySharedStateStore::new());
+        let stream = store.watch_prefix("job:");
+        let all = store.watch_all();
+
+        let writer = store.clone();
+        tokio::spawn(async move {
+            writer
+                .queue_state_scoped("status".to_string(), json!("running"), "job".to_string())
+                .await
+                .unwrap();
+            writer
+                .queue_state_update("other".to_string(), json!(1), None, None)
+                .await
+                .unwrap();
+            writer.remove_state("status", Some("job")).await.unwrap();
+        });
+
+        let changes: Vec<_> = stream.take(2).collect().await;
+        assert_eq!(
+            changes,
+            vec![
+                ("job:status".to_string(), Some(json!("running"))),
+                ("job:status".to_string(), None),
+            ]
+        );
+        let keys: Vec<_> = all.take(3).map(|(key, _)| key).collect().await;
+        assert_eq!(keys, ["job:status", "other", "job:status"]);
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_swap_rejects_stale_values() {
+        let store = InMemorySharedStateStore::new();
+        assert!(!store
+            .compare_and_swap("counter", None, &json!(0), json!(1))
+            .await
+            .unwrap());
+
+        store
+            .queue_state_update("counter".to_string(), json!(0), None, None)
+            .await
+            .unwrap();
+        assert!(store
+            .compare_and_swap("counter", None, &json!(0), json!(1))
+            .await
+            .unwrap());
+        assert!(!store
+            .compare_and_swap("counter", None, &json!(0), json!(2))
+            .await
+            .unwrap());
+        assert_eq!(store.read_state("counter", None).await.unwrap(), Some(json!(1)));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_compare_and_swap_loses_no_updates() {
+        let store = Arc::new(InMemorySharedStateStore::new());
+        store
+            .queue_state_scoped("counter".to_string(), json!(0), "jobs".to_string())
+            .await
+            .unwrap();
+
+        let tasks: Vec<_> = (0..100)
+            .map(|_| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    loop {
+                        let current = store
+                            .read_state_scoped("counter", "jobs")
+                            .await
+                            .unwrap()
+                            .unwrap();
+                        let next = json!(current.as_u64().unwrap() + 1);
+                        if store
+                            .compare_and_swap("counter", Some("jobs"), &current, next)
+                            .await
+                            .unwrap()
+                        {
+                            break;
+                        }
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(
+            store.read_state_scoped("counter", "jobs").await.unwrap(),
+            Some(json!(100))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mutation_history_replays_to_same_state() {
+        let store = InMemorySharedStateStore::new().with_history(16);
+        store
+            .queue_state_update("plan".to_string(), json!("draft"), None, None)
+            .await
+            .unwrap();
+        store
+            .queue_state_scoped("owner".to_string(), json!("ada"), "team".to_string())
+            .await
+            .unwrap();
+        store
+            .queue_state_update("plan".to_string(), json!("final"), None, None)
+            .await
+            .unwrap();
+        store
+            .queue_state_update("scratch".to_string(), json!(1), None, None)
+            .await
+            .unwrap();
+        store.remove_state("scratch", None).await.unwrap();
+
+        let history = store.mutation_history();
+        assert_eq!(history.len(), 5);
+        assert_eq!(history[2].old_value, Some(json!("draft")));
+        assert_eq!(history[4].operation, MutationKind::Delete);
+
+        let replayed = InMemorySharedStateStore::replay_history(&history);
+        assert_eq!(replayed.read_state("plan", None).await.unwrap(), Some(json!("final")));
+        assert_eq!(
+            replayed.read_state_scoped("owner", "team").await.unwrap(),
+            Some(json!("ada"))
+        );
+        assert_eq!(replayed.read_state("scratch", None).await.unwrap(), None);
+        assert!(replayed.mutation_history().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_keeps_colons_in_unscoped_ids() {
+        let store = InMemorySharedStateStore::new().with_history(10);
+        store
+            .queue_state_update("v1:plan".to_string(), json!("draft"), None, None)
+            .await
+            .unwrap();
+        store
+            .queue_state_scoped("owner".to_string(), json!("ada"), "team".to_string())
+            .await
+            .unwrap();
+
+        let history = store.mutation_history();
+        assert_eq!(history[0].scope, None);
+        assert_eq!(history[1].scope.as_deref(), Some("team"));
+
+        let snapshot = InMemorySharedStateStore::replay_history(&history).snapshot().await;
+        assert_eq!(snapshot.entries()["v1:plan"].scope.as_deref(), Some(""));
+        assert_eq!(snapshot.entries()["team:owner"].scope.as_deref(), Some("team"));
+    }
+
+    #[tokio::test]
+    async fn test_mutation_history_evicts_oldest() {
+        let store = InMemorySharedStateStore::new().with_history(2);
+        for value in 0..3 {
+            store
+                .queue_state_update("counter".to_string(), json!(value), None, None)
+                .await
+                .unwrap();
+        }
+        store.clear_states(None).await.unwrap();
+
+        let history = store.mutation_history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].new_value, Some(json!(2)));
+        assert_eq!(history[1].operation, MutationKind::Clear);
+        assert_eq!(history[1].old_value, Some(json!(2)));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_restore_round_trip() {
+        let store = InMemorySharedStateStore::new();
+        for (id, value) in [("plan", json!("ship it")), ("owner", json!("ada")), ("budget", json!(42))] {
+            store
+                .queue_state_scoped(id.to_string(), value, "project".to_string())
+                .await
+                .unwrap();
+        }
+
+        let snapshot = store.snapshot().await;
+        assert_eq!(snapshot.len(), 3);
+        let snapshot = SharedStateSnapshot::from_json(&snapshot.to_json()).unwrap();
+
+        let restored = InMemorySharedStateStore::restore(snapshot);
+        assert_eq!(
+            restored.read_state_scoped("plan", "project").await.unwrap(),
+            Some(json!("ship it"))
+        );
+        assert_eq!(
+            restored.read_state_scoped("owner", "project").await.unwrap(),
+            Some(json!("ada"))
+        );
+        assert_eq!(
+            restored.read_state_scoped("budget", "project").await.unwrap(),
+            Some(json!(42))
+        );
+
+        store.clear_states(None).await.unwrap();
+        assert_eq!(restored.list_state_ids(Some("project")).await.unwrap().len(), 3);
+    }
 }
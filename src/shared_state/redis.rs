@@ -0,0 +1,297 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use deadpool::managed::{self, Metrics, Object, Pool, RecycleResult};
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use serde_json::Value;
+
+use super::{SharedStateContext, SharedStateEntry};
+use crate::LLMError;
+
+const KEY_PREFIX: &str = "denkwerk";
+
+/// Hash field holding the JSON encoding of the [`SharedStateEntry`].
+const ENTRY_FIELD: &str = "entry";
+
+/// Overwrite the entry field of `KEYS[1]` with `ARGV[2]` only if it still holds `ARGV[1]`.
+/// `HSET` leaves the key's expiry alone.
+const COMPARE_AND_SWAP_SCRIPT: &str = r#"
+if redis.call('HGET', KEYS[1], 'entry') == ARGV[1] then
+    redis.call('HSET', KEYS[1], 'entry', ARGV[2])
+    return 1
+end
+return 0
+"#;
+
+/// Hands out multiplexed connections to the pool and pings them before reuse.
+struct ConnectionFactory {
+    client: redis::Client,
+}
+
+impl managed::Manager for ConnectionFactory {
+    type Type = MultiplexedConnection;
+    type Error = redis::RedisError;
+
+    async fn create(&self) -> Result<MultiplexedConnection, redis::RedisError> {
+        self.client.get_multiplexed_async_connection().await
+    }
+
+    async fn recycle(&self, connection: &mut MultiplexedConnection, _: &Metrics) -> RecycleResult<redis::RedisError> {
+        redis::cmd("PING").query_async::<()>(connection).await?;
+        Ok(())
+    }
+}
+
+/// Redis-backed shared state store.
+///
+/// Each entry is stored under `denkwerk:{scope}:{id}` as a hash whose `entry` field holds
+/// the JSON encoding of a [`SharedStateEntry`]; unscoped entries use an empty scope
+/// (`denkwerk::{id}`). Entries with a TTL get a `PEXPIRE`, so Redis expires them on its
+/// own. Connections come from a pool and are pinged before reuse, so a broken connection
+/// is replaced instead of failing later calls; a single store can be cloned and shared
+/// freely.
+#[derive(Clone)]
+pub struct RedisSharedStateStore {
+    pool: Pool<ConnectionFactory>,
+}
+
+impl RedisSharedStateStore {
+    /// Connect to Redis, e.g. `redis://127.0.0.1:6379/0`. One connection is opened up
+    /// front so an unreachable server is reported here.
+    pub async fn new(connection_str: &str) -> Result<Self, redis::RedisError> {
+        let client = redis::Client::open(connection_str)?;
+        let pool = Pool::builder(ConnectionFactory { client }).build().map_err(|err| {
+            redis::RedisError::from((redis::ErrorKind::ClientError, "failed to build connection pool", err.to_string()))
+        })?;
+        let store = Self { pool };
+        let connection = store.connection().await.map_err(|err| match err {
+            managed::PoolError::Backend(err) => err,
+            other => redis::RedisError::from((redis::ErrorKind::IoError, "failed to connect", other.to_string())),
+        })?;
+        drop(connection);
+        Ok(store)
+    }
+
+    async fn connection(&self) -> Result<Object<ConnectionFactory>, managed::PoolError<redis::RedisError>> {
+        self.pool.get().await
+    }
+
+    async fn pooled(&self) -> Result<Object<ConnectionFactory>, LLMError> {
+        self.connection().await.map_err(|err| LLMError::Provider(format!("redis error: {err}")))
+    }
+
+    fn key(id: &str, scope: Option<&str>) -> String {
+        format!("{}:{}:{}", KEY_PREFIX, scope.unwrap_or_default(), id)
+    }
+
+    fn scan_pattern(scope: Option<&str>) -> String {
+        match scope {
+            Some(scope) => format!("{}:{}:*", KEY_PREFIX, escape_glob(scope)),
+            None => format!("{}:*", KEY_PREFIX),
+        }
+    }
+
+    /// Map a stored key back to the ID reported by [`SharedStateContext::list_state_ids`].
+    /// Unscoped listings report scoped entries as `{scope}:{id}`, matching the in-memory store.
+    fn display_id(key: &str, scope: Option<&str>) -> Option<String> {
+        let rest = key.strip_prefix(KEY_PREFIX)?.strip_prefix(':')?;
+        match scope {
+            Some(scope) => rest
+                .strip_prefix(scope)
+                .and_then(|rest| rest.strip_prefix(':'))
+                .map(str::to_string),
+            None => Some(match rest.strip_prefix(':') {
+                Some(id) => id.to_string(),
+                None => rest.to_string(),
+            }),
+        }
+    }
+
+    async fn scan_keys(&self, scope: Option<&str>) -> Result<Vec<String>, LLMError> {
+        let mut connection = self.pooled().await?;
+        let mut iter: redis::AsyncIter<String> = connection
+            .scan_match(Self::scan_pattern(scope))
+            .await
+            .map_err(redis_error)?;
+
+        let mut keys = Vec::new();
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+}
+
+impl std::fmt::Debug for RedisSharedStateStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisSharedStateStore").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl SharedStateContext for RedisSharedStateStore {
+    async fn queue_state_update(
+        &self,
+        id: String,
+        value: Value,
+        scope: Option<String>,
+        ttl: Option<Duration>,
+    ) -> Result<(), LLMError> {
+        let key = Self::key(&id, scope.as_deref());
+        let mut entry = SharedStateEntry::new(value);
+        entry.scope = scope;
+        entry.ttl = ttl;
+        let payload = serde_json::to_string(&entry)?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic().hset(&key, ENTRY_FIELD, payload).ignore();
+        match ttl {
+            // Redis rejects a zero expiry, so round sub-millisecond TTLs up.
+            Some(ttl) => pipe.pexpire(&key, (ttl.as_millis() as i64).max(1)).ignore(),
+            None => pipe.persist(&key).ignore(),
+        };
+
+        let mut connection = self.pooled().await?;
+        pipe.query_async::<()>(&mut *connection).await.map_err(redis_error)
+    }
+
+    async fn read_state(
+        &self,
+        id: &str,
+        scope: Option<&str>,
+    ) -> Result<Option<Value>, LLMError> {
+        let mut connection = self.pooled().await?;
+        let payload: Option<String> = connection
+            .hget(Self::key(id, scope), ENTRY_FIELD)
+            .await
+            .map_err(redis_error)?;
+
+        match payload {
+            Some(payload) => {
+                let entry: SharedStateEntry = serde_json::from_str(&payload)?;
+                Ok(Some(entry.value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn compare_and_swap(
+        &self,
+        id: &str,
+        scope: Option<&str>,
+        expected: &Value,
+        new_value: Value,
+    ) -> Result<bool, LLMError> {
+        let key = Self::key(id, scope);
+        let mut connection = self.pooled().await?;
+        let Some(current_payload): Option<String> =
+            connection.hget(&key, ENTRY_FIELD).await.map_err(redis_error)?
+        else {
+            return Ok(false);
+        };
+
+        let mut entry: SharedStateEntry = serde_json::from_str(&current_payload)?;
+        if entry.value != *expected {
+            return Ok(false);
+        }
+        entry.value = new_value;
+        let payload = serde_json::to_string(&entry)?;
+
+        // The script re-checks the raw payload, so a concurrent writer between GET and here
+        // makes the swap fail instead of being overwritten.
+        let swapped: i64 = redis::Script::new(COMPARE_AND_SWAP_SCRIPT)
+            .key(key)
+            .arg(current_payload)
+            .arg(payload)
+            .invoke_async(&mut *connection)
+            .await
+            .map_err(redis_error)?;
+        Ok(swapped == 1)
+    }
+
+    async fn list_state_ids(&self, scope: Option<&str>) -> Result<Vec<String>, LLMError> {
+        let keys = self.scan_keys(scope).await?;
+        let mut ids: Vec<String> = keys
+            .iter()
+            .filter_map(|key| Self::display_id(key, scope))
+            .collect();
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    async fn remove_state(
+        &self,
+        id: &str,
+        scope: Option<&str>,
+    ) -> Result<bool, LLMError> {
+        let mut connection = self.pooled().await?;
+        let removed: usize = connection
+            .del(Self::key(id, scope))
+            .await
+            .map_err(redis_error)?;
+        Ok(removed > 0)
+    }
+
+    async fn clear_states(&self, scope: Option<&str>) -> Result<usize, LLMError> {
+        let keys = self.scan_keys(scope).await?;
+        if keys.is_empty() {
+            return Ok(0);
+        }
+
+        let mut connection = self.pooled().await?;
+        let removed: usize = connection.del(keys).await.map_err(redis_error)?;
+        Ok(removed)
+    }
+}
+
+fn redis_error(error: redis::RedisError) -> LLMError {
+    LLMError::Provider(format!("redis error: {error}"))
+}
+
+/// Escape Redis glob metacharacters so a scope name is matched literally by `SCAN MATCH`.
+fn escape_glob(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if matches!(ch, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_follow_scope_layout() {
+        assert_eq!(RedisSharedStateStore::key("plan", Some("team")), "denkwerk:team:plan");
+        assert_eq!(RedisSharedStateStore::key("plan", None), "denkwerk::plan");
+        assert_eq!(RedisSharedStateStore::scan_pattern(Some("a*b")), "denkwerk:a\\*b:*");
+    }
+
+    #[test]
+    fn display_ids_match_in_memory_listing() {
+        assert_eq!(
+            RedisSharedStateStore::display_id("denkwerk::plan", None).as_deref(),
+            Some("plan")
+        );
+        assert_eq!(
+            RedisSharedStateStore::display_id("denkwerk:team:plan", None).as_deref(),
+            Some("team:plan")
+        );
+        assert_eq!(
+            RedisSharedStateStore::display_id("denkwerk:team:plan", Some("team")).as_deref(),
+            Some("plan")
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_urls_are_rejected_before_connecting() {
+        let error = RedisSharedStateStore::new("not a redis url").await.unwrap_err();
+        assert_eq!(error.kind(), redis::ErrorKind::InvalidClientConfig);
+    }
+}
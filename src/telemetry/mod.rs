@@ -0,0 +1,172 @@
+//! OpenTelemetry tracing for agents and orchestrators, enabled by the `telemetry` feature.
+//!
+//! Spans are created through the global tracer provider, so installing one with
+//! [`init_tracer`] (or `opentelemetry::global::set_tracer_provider`) is all that is needed:
+//!
+//! - `handoff.session.send`: one per [`crate::flows::handoffflow::HandoffSession::send`]
+//! - `sequential.run`: one per [`crate::flows::sequential::SequentialOrchestrator::run`]
+//! - `agent.execute`: one per agent call, a child of the orchestrator span
+//! - `tool.invoke`: one per tool call, a child of the agent span
+
+use std::fmt::Display;
+use std::future::Future;
+
+use opentelemetry::global;
+use opentelemetry::trace::{FutureExt, SpanRef, Status, TraceContextExt, TraceError, Tracer};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{runtime, Resource};
+
+use crate::flows::handoffflow::AgentTurn;
+
+const TRACER_NAME: &str = "denkwerk";
+
+/// Export spans over OTLP/HTTP to `exporter_endpoint` (the collector's traces URL, e.g.
+/// `http://localhost:4318/v1/traces`) and install the provider globally. Call
+/// `shutdown()` on the returned provider before exiting to flush pending spans.
+pub fn init_tracer(service_name: &str, exporter_endpoint: &str) -> Result<TracerProvider, TraceError> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(exporter_endpoint)
+        .build()?;
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new([KeyValue::new("service.name", service_name.to_string())]))
+        .build();
+    global::set_tracer_provider(provider.clone());
+    Ok(provider)
+}
+
+/// Start span `name` under `parent` and return a context carrying it.
+pub(crate) fn start_span(name: &'static str, parent: &Context, attributes: Vec<KeyValue>) -> Context {
+    let tracer = global::tracer(TRACER_NAME);
+    let span = tracer
+        .span_builder(name)
+        .with_attributes(attributes)
+        .start_with_context(&tracer, parent);
+    parent.with_span(span)
+}
+
+/// Context for an agent call: the current span if there is one, otherwise the context the
+/// agent was given via `Agent::with_otel_context`.
+pub(crate) fn agent_parent(otel_context: Option<&Context>) -> Context {
+    let current = Context::current();
+    match otel_context {
+        Some(cx) if !current.has_active_span() => cx.clone(),
+        _ => current,
+    }
+}
+
+/// Run `future` inside the span carried by `cx`, then end the span, marking it as failed on
+/// error. `on_ok` can record attributes from a successful result.
+pub(crate) async fn traced<T, E, F>(cx: Context, future: F, on_ok: impl FnOnce(&SpanRef<'_>, &T)) -> Result<T, E>
+where
+    E: Display,
+    F: Future<Output = Result<T, E>>,
+{
+    let result = future.with_context(cx.clone()).await;
+    let span = cx.span();
+    match &result {
+        Ok(value) => on_ok(&span, value),
+        Err(error) => span.set_status(Status::error(error.to_string())),
+    }
+    span.end();
+    result
+}
+
+pub(crate) fn record_agent_turn(span: &SpanRef<'_>, turn: &AgentTurn) {
+    if let Some(usage) = &turn.usage {
+        span.set_attribute(KeyValue::new("token.input", i64::from(usage.prompt_tokens)));
+        span.set_attribute(KeyValue::new("token.output", i64::from(usage.completion_tokens)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use opentelemetry::trace::SpanId;
+    use opentelemetry::Value;
+    use opentelemetry_sdk::export::trace::SpanData;
+    use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+    use serde_json::json;
+
+    use super::*;
+    use crate::eval::scenario::ScriptedTurn;
+    use crate::flows::handoffflow::HandoffOrchestrator;
+    use crate::flows::sequential::SequentialOrchestrator;
+    use crate::functions::FunctionRegistry;
+    use crate::providers::scripted::ScriptedProvider;
+    use crate::{kernel_function, Agent};
+
+    #[kernel_function(name = "lookup_weather", description = "Look up the weather")]
+    fn lookup_weather(city: String) -> Result<String, String> {
+        Ok(format!("sunny in {city}"))
+    }
+
+    fn attribute(span: &SpanData, key: &str) -> Option<Value> {
+        span.attributes.iter().find(|kv| kv.key.as_str() == key).map(|kv| kv.value.clone())
+    }
+
+    fn children<'a>(spans: &'a [SpanData], parent: &SpanData, name: &str) -> Vec<&'a SpanData> {
+        spans
+            .iter()
+            .filter(|span| span.name == name && span.parent_span_id == parent.span_context.span_id())
+            .collect()
+    }
+
+    // A single test, because the tracer provider is process-wide.
+    #[tokio::test]
+    async fn orchestrator_agent_and_tool_spans_are_nested() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+        global::set_tracer_provider(provider.clone());
+
+        let mut registry = FunctionRegistry::new();
+        registry.register(lookup_weather_kernel());
+        let forecaster = Agent::from_string("Forecaster", "Answer weather questions.")
+            .with_function_registry(Arc::new(registry));
+        let mut scripted = ScriptedProvider::new();
+        scripted.add_tool_call_response("lookup_weather", json!({ "city": "Berlin" }), json!("It is sunny."));
+        let mut handoff = HandoffOrchestrator::new(Arc::new(scripted), "m");
+        handoff.register_agent(forecaster);
+        let mut session = handoff.session("Forecaster").unwrap();
+        session.send("Use lookup_weather for Berlin").await.unwrap();
+
+        let turn = |agent: &str, response: &str| ScriptedTurn {
+            agent: agent.to_string(),
+            response: response.to_string(),
+            latency_ms: None,
+        };
+        let sequential = SequentialOrchestrator::new(
+            Arc::new(ScriptedProvider::from_scripted_turns(&[turn("a", "one"), turn("b", "two")])),
+            "m",
+        )
+        .with_agents(vec![Agent::from_string("a", "first"), Agent::from_string("b", "second")]);
+        sequential.run("task").await.unwrap();
+
+        let _ = provider.force_flush();
+        let spans = exporter.get_finished_spans().unwrap();
+
+        let send = spans
+            .iter()
+            .find(|span| span.name == "handoff.session.send" && attribute(span, "agent.name") == Some("Forecaster".into()))
+            .expect("handoff span");
+        assert_eq!(send.parent_span_id, SpanId::INVALID);
+        assert_eq!(attribute(send, "turn.index"), Some(Value::I64(0)));
+        let agents = children(&spans, send, "agent.execute");
+        assert_eq!(agents.len(), 1);
+        assert_eq!(attribute(agents[0], "agent.model"), Some("m".into()));
+        let tools = children(&spans, agents[0], "tool.invoke");
+        assert_eq!(tools.len(), 1);
+        assert_eq!(attribute(tools[0], "tool.name"), Some("lookup_weather".into()));
+
+        let run = spans
+            .iter()
+            .find(|span| span.name == "sequential.run" && attribute(span, "step.count") == Some(Value::I64(2)))
+            .expect("sequential span");
+        assert_eq!(run.parent_span_id, SpanId::INVALID);
+        assert_eq!(children(&spans, run, "agent.execute").len(), 2);
+    }
+}
@@ -0,0 +1,119 @@
+//! Assertions over a [`crate::HandoffTurn`]. Failures print the turn's events.
+
+/// Panics unless an agent called `function` during `turn`.
+///
+/// ```
+/// use denkwerk::{assert_agent_calls, CorrelationId, HandoffEvent, HandoffTurn};
+///
+/// let turn = HandoffTurn {
+///     reply: None,
+///     events: vec![HandoffEvent::ToolCall { agent: "math".into(), function: "add".into() }],
+///     metrics: None,
+///     correlation_id: CorrelationId::new(),
+/// };
+/// assert_agent_calls!(turn, "add");
+/// ```
+#[macro_export]
+macro_rules! assert_agent_calls {
+    ($turn:expr, $function:expr $(,)?) => {{
+        let turn = &$turn;
+        let function: &str = $function;
+        let called = turn.events.iter().any(|event| {
+            matches!(event, $crate::HandoffEvent::ToolCall { function: called, .. } if called == function)
+        });
+        assert!(called, "expected a call to `{}`, events were: {:#?}", function, turn.events);
+    }};
+}
+
+/// Panics unless `turn` handed the conversation off to `agent`.
+#[macro_export]
+macro_rules! assert_handoff_to {
+    ($turn:expr, $agent:expr $(,)?) => {{
+        let turn = &$turn;
+        let agent: &str = $agent;
+        let handed_off = turn
+            .events
+            .iter()
+            .any(|event| matches!(event, $crate::HandoffEvent::HandOff { to, .. } if to == agent));
+        assert!(handed_off, "expected a handoff to `{}`, events were: {:#?}", agent, turn.events);
+    }};
+}
+
+/// Panics unless the reply of `turn` contains `needle`.
+#[macro_export]
+macro_rules! assert_final_reply_contains {
+    ($turn:expr, $needle:expr $(,)?) => {{
+        let turn = &$turn;
+        let needle: &str = $needle;
+        let contains = turn.reply.as_deref().is_some_and(|reply| reply.contains(needle));
+        assert!(
+            contains,
+            "expected the reply to contain `{}`, reply was {:?}, events were: {:#?}",
+            needle, turn.reply, turn.events
+        );
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use crate::flows::handoffflow::{HandoffOrchestrator, HandoffTurn};
+    use crate::functions::{kernel_fn_sync, FunctionRegistry};
+    use crate::testing::ConversationFixture;
+    use crate::{Agent, CorrelationId, HandoffEvent};
+
+    async fn scripted_turn() -> HandoffTurn {
+        let provider = ConversationFixture::new()
+            .assistant_with_tool_call("lookup", json!({ "city": "Berlin" }))
+            .assistant_with_tool_call("handoff", json!({ "to": "writer" }))
+            .assistant("It is 21 degrees in Berlin.")
+            .into_scripted_provider();
+
+        let mut functions = FunctionRegistry::new();
+        functions.register(kernel_fn_sync("lookup", "", Vec::new(), |_| Ok(json!({ "temp": 21 }))));
+        let mut orchestrator = HandoffOrchestrator::new(Arc::new(provider), "scripted");
+        orchestrator.register_agent(Agent::from_string("router", "Route").with_function_registry(Arc::new(functions)));
+        orchestrator.register_agent(Agent::from_string("writer", "Write"));
+
+        let mut session = orchestrator.session("router").unwrap();
+        session.send("weather in Berlin?").await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn macros_pass_on_scripted_turn() {
+        let turn = scripted_turn().await;
+        assert_agent_calls!(turn, "lookup");
+        assert_handoff_to!(turn, "writer");
+        assert_final_reply_contains!(turn, "21 degrees");
+    }
+
+    fn turn(events: Vec<HandoffEvent>) -> HandoffTurn {
+        HandoffTurn {
+            reply: Some("done".to_string()),
+            events,
+            metrics: None,
+            correlation_id: CorrelationId::new(),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a call to `lookup`")]
+    fn agent_calls_reports_missing_call() {
+        assert_agent_calls!(turn(vec![HandoffEvent::Completed { agent: "router".into() }]), "lookup");
+    }
+
+    #[test]
+    #[should_panic(expected = "Completed")]
+    fn handoff_failure_lists_events() {
+        assert_handoff_to!(turn(vec![HandoffEvent::Completed { agent: "router".into() }]), "writer");
+    }
+
+    #[test]
+    #[should_panic(expected = "reply was Some(\"done\")")]
+    fn final_reply_reports_actual_reply() {
+        assert_final_reply_contains!(turn(Vec::new()), "weather");
+    }
+}
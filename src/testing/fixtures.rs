@@ -0,0 +1,169 @@
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{
+    functions::{FunctionCall, ToolCall},
+    providers::scripted::ScriptedProvider,
+    types::{ChatMessage, MessageRole},
+};
+
+/// Builds a conversation turn by turn, either as plain messages or as a [`ScriptedProvider`]
+/// that plays back the assistant side.
+///
+/// ```
+/// use denkwerk::testing::ConversationFixture;
+/// use serde_json::json;
+///
+/// let messages = ConversationFixture::new()
+///     .user("What is 2 + 3?")
+///     .assistant_with_tool_call("add", json!({ "a": 2, "b": 3 }))
+///     .tool_result("call_1", json!(5))
+///     .assistant("5")
+///     .messages();
+/// assert_eq!(messages.len(), 4);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConversationFixture {
+    messages: Vec<ChatMessage>,
+    tool_calls: usize,
+    /// Patterns the model's input must match, keyed by the index of the model call.
+    expected_inputs: Vec<(usize, Regex)>,
+}
+
+impl ConversationFixture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn user(mut self, text: impl Into<String>) -> Self {
+        self.messages.push(ChatMessage::user(text));
+        self
+    }
+
+    pub fn assistant(mut self, text: impl Into<String>) -> Self {
+        self.messages.push(ChatMessage::assistant(text));
+        self
+    }
+
+    /// An assistant turn calling `name`. Tool call ids are assigned in order: `call_1`,
+    /// `call_2`, ...
+    pub fn assistant_with_tool_call(mut self, name: &str, arguments: Value) -> Self {
+        self.tool_calls += 1;
+        let call = ToolCall::new(FunctionCall::new(name, arguments))
+            .with_id(format!("call_{}", self.tool_calls));
+        let mut message = ChatMessage::assistant("");
+        message.content = None;
+        self.messages.push(message.with_tool_calls(vec![call]));
+        self
+    }
+
+    /// The result of tool call `id`. String results are used as-is; other JSON values are
+    /// serialized.
+    pub fn tool_result(mut self, id: &str, result: Value) -> Self {
+        let content = match result {
+            Value::String(text) => text,
+            other => other.to_string(),
+        };
+        self.messages.push(ChatMessage::tool(id, content));
+        self
+    }
+
+    /// Require the next model call of the provider built by
+    /// [`ConversationFixture::into_scripted_provider`] to carry a last user message matching
+    /// `pattern`; otherwise that call fails. Panics if `pattern` is not a valid regex.
+    pub fn expect_agent_called_with(mut self, pattern: &str) -> Self {
+        let pattern = Regex::new(pattern)
+            .unwrap_or_else(|err| panic!("invalid pattern `{pattern}`: {err}"));
+        let next_call = self.assistant_turns().count();
+        self.expected_inputs.push((next_call, pattern));
+        self
+    }
+
+    pub fn messages(&self) -> Vec<ChatMessage> {
+        self.messages.clone()
+    }
+
+    /// A provider that answers successive calls with the fixture's assistant turns, tool
+    /// calls included.
+    pub fn into_scripted_provider(self) -> ScriptedProvider {
+        let responses = self.assistant_turns().cloned().collect();
+        let mut provider = ScriptedProvider::from_assistant_messages(responses);
+        for (call, pattern) in self.expected_inputs {
+            provider.expect_input_at_turn(call, pattern);
+        }
+        provider
+    }
+
+    fn assistant_turns(&self) -> impl Iterator<Item = &ChatMessage> {
+        self.messages
+            .iter()
+            .filter(|message| message.role == MessageRole::Assistant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{types::CompletionRequest, LLMProvider};
+
+    fn fixture() -> ConversationFixture {
+        ConversationFixture::new()
+            .user("What is 2 + 3?")
+            .expect_agent_called_with("2 \\+ 3")
+            .assistant_with_tool_call("add", json!({ "a": 2, "b": 3 }))
+            .tool_result("call_1", json!(5))
+            .assistant("The sum is 5")
+    }
+
+    #[test]
+    fn builds_messages_with_sequential_tool_call_ids() {
+        let messages = fixture().messages();
+
+        let roles: Vec<_> = messages.iter().map(|message| message.role.clone()).collect();
+        assert_eq!(
+            roles,
+            vec![MessageRole::User, MessageRole::Assistant, MessageRole::Tool, MessageRole::Assistant]
+        );
+        assert_eq!(messages[0].text(), Some("What is 2 + 3?"));
+        assert_eq!(messages[1].text(), None);
+        assert_eq!(messages[1].tool_calls[0].id.as_deref(), Some("call_1"));
+        assert_eq!(messages[1].tool_calls[0].function.arguments, json!({ "a": 2, "b": 3 }));
+        assert_eq!(messages[2].tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(messages[2].text(), Some("5"));
+        assert_eq!(messages[3].text(), Some("The sum is 5"));
+
+        let second = ConversationFixture::new()
+            .assistant_with_tool_call("a", json!({}))
+            .assistant_with_tool_call("b", json!({}))
+            .messages();
+        assert_eq!(second[1].tool_calls[0].id.as_deref(), Some("call_2"));
+    }
+
+    #[tokio::test]
+    async fn scripted_provider_plays_back_assistant_turns() {
+        let messages = fixture().messages();
+        let provider = fixture().into_scripted_provider();
+
+        let call = provider
+            .complete(CompletionRequest::new("m", messages[..1].to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(call.message.tool_calls[0].function.name, "add");
+        let answer = provider
+            .complete(CompletionRequest::new("m", messages[..3].to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(answer.message.text(), Some("The sum is 5"));
+    }
+
+    #[tokio::test]
+    async fn unexpected_input_fails_the_call() {
+        let provider = fixture().into_scripted_provider();
+        let result = provider
+            .complete(CompletionRequest::new("m", vec![ChatMessage::user("What is 4 + 4?")]))
+            .await;
+        assert!(result.is_err());
+    }
+}
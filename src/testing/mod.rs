@@ -0,0 +1,9 @@
+//! Helpers for writing tests against agents and flows.
+
+pub mod assertions;
+pub mod fixtures;
+#[cfg(feature = "testing")]
+pub mod strategies;
+
+pub use crate::{assert_agent_calls, assert_final_reply_contains, assert_handoff_to};
+pub use fixtures::ConversationFixture;
@@ -0,0 +1,101 @@
+//! `proptest` strategies for the core request types, behind the `testing` feature.
+
+use proptest::collection::{hash_map, vec};
+use proptest::prelude::*;
+use serde_json::{Map, Value};
+
+use crate::functions::FunctionCall;
+use crate::types::{ChatMessage, CompletionRequest};
+
+/// Any JSON scalar; arrays and objects nest these up to three levels deep.
+fn any_json_value() -> BoxedStrategy<Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        any::<String>().prop_map(Value::String),
+    ];
+    leaf.prop_recursive(3, 32, 4, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..4).prop_map(Value::Array),
+            hash_map("[a-z_]{1,16}", inner, 0..4).prop_map(|fields| Value::Object(fields.into_iter().collect())),
+        ]
+    })
+    .boxed()
+}
+
+/// A system, user, assistant or tool message with arbitrary text.
+pub fn any_chat_message() -> BoxedStrategy<ChatMessage> {
+    prop_oneof![
+        any::<String>().prop_map(ChatMessage::system),
+        any::<String>().prop_map(ChatMessage::user),
+        any::<String>().prop_map(ChatMessage::assistant),
+        ("call_[0-9]{1,4}", any::<String>()).prop_map(|(id, content)| ChatMessage::tool(id, content)),
+    ]
+    .boxed()
+}
+
+pub fn any_completion_request() -> BoxedStrategy<CompletionRequest> {
+    (any::<String>(), vec(any_chat_message(), 0..8))
+        .prop_map(|(model, messages)| CompletionRequest::new(model, messages))
+        .boxed()
+}
+
+/// A call named like `[a-z_]{1,64}` whose arguments are always a JSON object.
+pub fn any_function_call() -> BoxedStrategy<FunctionCall> {
+    ("[a-z_]{1,64}", hash_map("[a-z_]{1,16}", any_json_value(), 0..6))
+        .prop_map(|(name, arguments)| {
+            FunctionCall::new(name, Value::Object(arguments.into_iter().collect::<Map<_, _>>()))
+        })
+        .boxed()
+}
+
+impl Arbitrary for ChatMessage {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any_chat_message()
+    }
+}
+
+impl Arbitrary for CompletionRequest {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any_completion_request()
+    }
+}
+
+impl Arbitrary for FunctionCall {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any_function_call()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{scripted::ScriptedProvider, LLMProvider};
+
+    proptest! {
+        #[test]
+        fn scripted_provider_handles_any_request(request in any_completion_request()) {
+            let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+            let mut provider = ScriptedProvider::new();
+            provider.echo_user_messages();
+            let _ = runtime.block_on(provider.complete(request));
+        }
+
+        #[test]
+        fn function_calls_have_valid_names_and_object_arguments(call in any::<FunctionCall>()) {
+            prop_assert!(!call.name.is_empty() && call.name.len() <= 64);
+            prop_assert!(call.name.chars().all(|c| c.is_ascii_lowercase() || c == '_'));
+            prop_assert!(call.arguments.is_object());
+        }
+    }
+}
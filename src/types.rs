@@ -1,9 +1,12 @@
+use chrono::{DateTime, Utc};
 use futures_core::Stream;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::pin::Pin;
+use std::{collections::HashMap, fmt, future::Future, pin::Pin, str::FromStr, time::Duration};
+use uuid::Uuid;
 
 use crate::functions::{FunctionRegistry, Tool, ToolCall, ToolChoice};
+use crate::history::{CharEstimateTokenCounter, TokenCounter};
 
 /// Controls the reasoning effort for models that support extended thinking.
 ///
@@ -18,6 +21,57 @@ pub enum ReasoningEffort {
     High,
 }
 
+/// Identifies one user request across every agent hop and provider call it causes.
+/// Providers forward it as the `X-Request-ID` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CorrelationId(Uuid);
+
+tokio::task_local! {
+    static CURRENT_CORRELATION_ID: CorrelationId;
+}
+
+impl CorrelationId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    pub fn as_uuid(&self) -> &Uuid {
+        &self.0
+    }
+
+    /// The id of the enclosing [`CorrelationId::scope`], if any.
+    pub fn current() -> Option<Self> {
+        CURRENT_CORRELATION_ID.try_with(|id| *id).ok()
+    }
+
+    /// Runs `future` with this id as [`CorrelationId::current`], so agents running inside it
+    /// tag their completion requests with it.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT_CORRELATION_ID.scope(self, future).await
+    }
+}
+
+impl Default for CorrelationId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for CorrelationId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(Self)
+    }
+}
+
 pub type CompletionStream =
     Pin<Box<dyn Stream<Item = Result<StreamEvent, crate::LLMError>> + Send>>;
 
@@ -30,6 +84,46 @@ pub enum MessageRole {
     Tool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    Mp3,
+    Wav,
+    Webm,
+}
+
+impl AudioFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Webm => "webm",
+        }
+    }
+}
+
+/// Non-image attachment on a [`ChatMessage`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    /// A file previously uploaded to the provider.
+    File { file_id: String, name: String },
+    /// Audio clip; `url` is a data URL (`data:audio/wav;base64,...`) or bare base64.
+    Audio { url: String, format: AudioFormat },
+}
+
+/// Bookkeeping about where a message came from, for auditing and analytics.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MessageMetadata {
+    /// The process that produced the message, e.g. an agent or tool name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub custom: HashMap<String, Value>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
     pub role: MessageRole,
@@ -45,11 +139,23 @@ pub struct ChatMessage {
     /// Skipped during normal serde; the provider serializer handles these specially.
     #[serde(skip)]
     pub images: Vec<String>,
+    /// File and audio attachments, serialized for each provider by its own request builder;
+    /// providers that cannot send a kind of attachment drop it with a warning.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<ContentPart>,
     /// Provider-separated reasoning/thinking trace tied to this message. Populated by
     /// providers that expose thinking as a distinct field (e.g. Ollama native API) and
     /// echoed back on subsequent turns when the provider preserves thinking.
     #[serde(skip)]
     pub thinking: Option<String>,
+    /// When the message was recorded. Set by histories that enforce retention (see
+    /// [`crate::history::ChatHistory::with_ttl`]); never sent to providers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Kept in history snapshots and by [`crate::history::sqlite::SqliteChatHistory`]; never
+    /// sent to providers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<MessageMetadata>,
 }
 
 impl ChatMessage {
@@ -61,7 +167,10 @@ impl ChatMessage {
             tool_call_id: None,
             tool_calls: Vec::new(),
             images: Vec::new(),
+            attachments: Vec::new(),
             thinking: None,
+            created_at: None,
+            metadata: None,
         }
     }
 
@@ -85,7 +194,10 @@ impl ChatMessage {
             tool_call_id: Some(id.into()),
             tool_calls: Vec::new(),
             images: Vec::new(),
+            attachments: Vec::new(),
             thinking: None,
+            created_at: None,
+            metadata: None,
         }
     }
 
@@ -98,16 +210,62 @@ impl ChatMessage {
             tool_call_id: None,
             tool_calls: Vec::new(),
             images,
+            attachments: Vec::new(),
             thinking: None,
+            created_at: None,
+            metadata: None,
         }
     }
 
+    /// Create a user message referencing a file uploaded to the provider.
+    pub fn user_with_file(
+        content: impl Into<String>,
+        file_id: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        Self::user(content).with_attachment(ContentPart::File {
+            file_id: file_id.into(),
+            name: name.into(),
+        })
+    }
+
+    pub fn with_attachment(mut self, attachment: ContentPart) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
     /// Attach a reasoning/thinking trace to this message.
     pub fn with_thinking(mut self, thinking: impl Into<String>) -> Self {
         self.thinking = Some(thinking.into());
         self
     }
 
+    pub fn with_created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.metadata_mut().source = Some(source.into());
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.metadata_mut().tags.push(tag.into());
+        self
+    }
+
+    fn metadata_mut(&mut self) -> &mut MessageMetadata {
+        self.metadata.get_or_insert_with(MessageMetadata::default)
+    }
+
+    /// Time since [`ChatMessage::created_at`]; `None` when it is not set. Timestamps in the
+    /// future count as zero.
+    pub fn age(&self) -> Option<Duration> {
+        let created_at = self.created_at?;
+        Some((Utc::now() - created_at).to_std().unwrap_or_default())
+    }
+
     pub fn with_tool_calls(mut self, tool_calls: Vec<ToolCall>) -> Self {
         self.tool_calls = tool_calls;
         self
@@ -137,6 +295,9 @@ pub struct CompletionRequest {
     /// Optional reasoning effort level for models that support extended thinking.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning_effort: Option<ReasoningEffort>,
+    /// Forwarded to the provider as `X-Request-ID`; never part of the request body.
+    #[serde(skip)]
+    pub correlation_id: Option<CorrelationId>,
 }
 
 impl CompletionRequest {
@@ -151,9 +312,15 @@ impl CompletionRequest {
             tools: Vec::new(),
             tool_choice: None,
             reasoning_effort: None,
+            correlation_id: None,
         }
     }
 
+    pub fn with_correlation_id(mut self, id: CorrelationId) -> Self {
+        self.correlation_id = Some(id);
+        self
+    }
+
     pub fn with_max_tokens(mut self, value: u32) -> Self {
         self.max_tokens = Some(value);
         self
@@ -184,6 +351,29 @@ impl CompletionRequest {
         self
     }
 
+    /// Request structured output matching `schema`, sent as an OpenAI `json_schema`
+    /// response format. With `strict`, the provider guarantees schema-conforming output.
+    pub fn with_response_schema(self, schema: Value, strict: bool) -> Self {
+        self.with_response_format(serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "response",
+                "strict": strict,
+                "schema": schema,
+            },
+        }))
+    }
+
+    /// Offline prompt size estimate of one token per four characters of message text and
+    /// tool definitions, plus the per-message formatting overhead.
+    pub fn estimated_token_count(&self) -> u32 {
+        let messages = CharEstimateTokenCounter.count_messages(&self.messages);
+        if self.tools.is_empty() {
+            return messages;
+        }
+        messages + serde_json::to_string(&self.tools).map_or(0, |json| json.len().div_ceil(4) as u32)
+    }
+
     pub fn with_tool(mut self, tool: Tool) -> Self {
         self.tools.push(tool);
         self
@@ -237,11 +427,16 @@ pub struct ReasoningTrace {
     pub finish_reason: Option<String>,
 }
 
+// `Completed` is sent once per stream, so its size is not worth a box.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum StreamEvent {
     MessageDelta(String),
     ReasoningDelta(String),
     ToolCallDelta { index: usize, arguments: String },
+    /// A streamed tool call whose arguments have been fully received.
+    ToolCallComplete { call: ToolCall },
     Completed(CompletionResponse),
 }
 
@@ -335,6 +530,10 @@ pub struct ProviderCapabilities {
     pub supports_reasoning_stream: bool,
     pub supports_image_uploads: bool,
     pub supports_embeddings: bool,
+    pub supports_structured_output: bool,
+    pub supports_tools: bool,
+    /// Token throughput the provider allows per minute, when it publishes a limit.
+    pub max_tokens_per_minute: Option<u32>,
 }
 
 impl ProviderCapabilities {
@@ -349,8 +548,26 @@ impl ProviderCapabilities {
             supports_reasoning_stream,
             supports_image_uploads,
             supports_embeddings,
+            supports_structured_output: false,
+            supports_tools: true,
+            max_tokens_per_minute: None,
         }
     }
+
+    pub const fn with_max_tokens_per_minute(mut self, limit: Option<u32>) -> Self {
+        self.max_tokens_per_minute = limit;
+        self
+    }
+
+    pub const fn with_tools(mut self, supported: bool) -> Self {
+        self.supports_tools = supported;
+        self
+    }
+
+    pub const fn with_structured_output(mut self, supported: bool) -> Self {
+        self.supports_structured_output = supported;
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -404,7 +621,71 @@ pub struct ModelInfo {
 
 #[cfg(test)]
 mod tests {
-    use super::{CompletionRequest, EmbeddingRequest};
+    use std::str::FromStr;
+
+    use super::{
+        AudioFormat, ChatMessage, CompletionRequest, ContentPart, CorrelationId, EmbeddingRequest,
+        MessageMetadata,
+    };
+    use crate::functions::FunctionDefinition;
+
+    #[test]
+    fn correlation_id_round_trips_and_rejects_non_uuids() {
+        let id = CorrelationId::new();
+        assert_eq!(CorrelationId::from_str(&id.to_string()).unwrap(), id);
+        assert!(CorrelationId::from_str("not-a-uuid").is_err());
+
+        let request = CompletionRequest::new("m", Vec::new()).with_correlation_id(id);
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(request.correlation_id, Some(id));
+        assert!(body.get("correlation_id").is_none());
+    }
+
+    #[test]
+    fn file_attachments_leave_text_and_serialized_body_alone() {
+        let message = ChatMessage::user_with_file("Summarize", "file-123", "report.pdf");
+        assert_eq!(message.text(), Some("Summarize"));
+        assert_eq!(
+            message.attachments,
+            vec![ContentPart::File {
+                file_id: "file-123".to_string(),
+                name: "report.pdf".to_string(),
+            }]
+        );
+        let restored: ChatMessage = serde_json::from_value(serde_json::to_value(&message).unwrap()).unwrap();
+        assert_eq!(restored.attachments, message.attachments);
+
+        let audio: ContentPart =
+            serde_json::from_value(serde_json::json!({ "type": "audio", "url": "u", "format": "webm" })).unwrap();
+        assert_eq!(audio, ContentPart::Audio { url: "u".to_string(), format: AudioFormat::Webm });
+    }
+
+    #[test]
+    fn metadata_builders_and_age() {
+        let created_at = chrono::Utc::now() - chrono::Duration::seconds(90);
+        let message = ChatMessage::user("hi")
+            .with_created_at(created_at)
+            .with_source("planner")
+            .with_tag("audit")
+            .with_tag("billing");
+
+        let metadata = message.metadata.as_ref().unwrap();
+        assert_eq!(metadata.source.as_deref(), Some("planner"));
+        assert_eq!(metadata.tags, vec!["audit".to_string(), "billing".to_string()]);
+        assert_eq!(message.created_at, Some(created_at));
+
+        let age = message.age().unwrap();
+        assert!(age >= std::time::Duration::from_secs(90) && age < std::time::Duration::from_secs(95));
+        assert!(ChatMessage::user("no timestamp").age().is_none());
+
+        let restored: ChatMessage = serde_json::from_value(serde_json::to_value(&message).unwrap()).unwrap();
+        assert_eq!(restored.metadata, message.metadata);
+        assert!(serde_json::to_value(ChatMessage::user("plain")).unwrap().get("metadata").is_none());
+        assert_eq!(
+            serde_json::to_value(MessageMetadata::default()).unwrap(),
+            serde_json::json!({})
+        );
+    }
 
     #[test]
     fn embedding_request_defaults_dimensions_to_none() {
@@ -428,4 +709,15 @@ mod tests {
             .without_max_tokens();
         assert!(request.max_tokens.is_none());
     }
+
+    #[test]
+    fn estimated_token_count_covers_messages_and_tools() {
+        let request = CompletionRequest::new("m", vec![ChatMessage::user("abcdefgh")]);
+        assert_eq!(request.estimated_token_count(), 2 + 4);
+
+        let with_tool = request
+            .clone()
+            .with_tool(FunctionDefinition::new("lookup").with_description("Look up an order").into());
+        assert!(with_tool.estimated_token_count() > request.estimated_token_count());
+    }
 }
@@ -0,0 +1,75 @@
+use std::{path::PathBuf, process::Command};
+
+const FLOW: &str = r#"
+agents:
+  - id: writer
+    model: scripted
+    system_prompt: Write things.
+flows:
+  - id: main
+    entry: start
+    nodes:
+      - id: start
+        type: input
+      - id: write
+        type: agent
+        agent: writer
+      - id: end
+        type: output
+    edges:
+      - from: start
+        to: write
+      - from: write
+        to: end
+"#;
+
+fn write_flow(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("denkwerk_flow_run_{}_{name}.yaml", std::process::id()));
+    std::fs::write(&path, FLOW).unwrap();
+    path
+}
+
+fn flow_run(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_flow_run"))
+        .args(args)
+        .env("DENKWERK_SCRIPTED", "1")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn runs_flow_and_prints_text_output() {
+    let file = write_flow("text");
+    let output = flow_run(&["--file", file.to_str().unwrap(), "--task", "summarize this"]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "summarize this");
+}
+
+#[test]
+fn prints_json_output() {
+    let file = write_flow("json");
+    let output = flow_run(&[
+        "--file",
+        file.to_str().unwrap(),
+        "--task",
+        "hello",
+        "--context",
+        "tone=\"formal\"",
+        "--output-format",
+        "json",
+    ]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed["final_output"], "hello");
+}
+
+#[test]
+fn unknown_flow_exits_with_error() {
+    let file = write_flow("missing");
+    let output = flow_run(&["--file", file.to_str().unwrap(), "--flow", "nope", "--task", "hi"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("flow `nope` failed"));
+}
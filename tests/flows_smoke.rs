@@ -278,6 +278,7 @@ async fn handoff_session_triggers_real_handoff_and_target_responds() {
         .iter()
         .map(|e| match e {
             HandoffEvent::Message { agent, .. } => format!("msg:{agent}"),
+            HandoffEvent::ToolCall { agent, function } => format!("tool:{agent}:{function}"),
             HandoffEvent::HandOff { from, to, .. } => format!("handoff:{from}->{to}"),
             HandoffEvent::Completed { agent } => format!("done:{agent}"),
         })
@@ -175,7 +175,7 @@ async fn streaming_reaches_completed() {
                 got_completed = true;
                 final_content = resp.message.content.unwrap_or_default();
             }
-            StreamEvent::ToolCallDelta { .. } => {}
+            _ => {}
         }
     }
 
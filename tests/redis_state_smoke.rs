@@ -0,0 +1,112 @@
+//! Live round-trip tests for the Redis shared state backend.
+//!
+//! All tests are `#[ignore]` by default and need the `redis-state` feature. Start a
+//! throwaway Redis (e.g. `docker run --rm -p 6379:6379 redis:7`) and run:
+//!
+//! ```sh
+//! REDIS_SMOKE_URL=redis://127.0.0.1:6379/15 \
+//!   cargo test --features redis-state --test redis_state_smoke -- --ignored --test-threads=1
+//! ```
+//!
+//! The tests clear every `denkwerk:*` key in the selected database, so point them at a
+//! scratch database.
+
+#![cfg(feature = "redis-state")]
+
+use denkwerk::shared_state::redis::RedisSharedStateStore;
+use denkwerk::SharedStateContext;
+use serde_json::json;
+
+async fn store() -> RedisSharedStateStore {
+    let url = std::env::var("REDIS_SMOKE_URL").expect("REDIS_SMOKE_URL not set");
+    let store = RedisSharedStateStore::new(&url).await.expect("redis connect");
+    store.clear_states(None).await.expect("clear");
+    store
+}
+
+#[tokio::test]
+#[ignore = "requires REDIS_SMOKE_URL"]
+async fn unscoped_state_round_trips() {
+    let store = store().await;
+
+    store
+        .queue_state_update("greeting".to_string(), json!({"text": "hello"}), None, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        store.read_state("greeting", None).await.unwrap(),
+        Some(json!({"text": "hello"}))
+    );
+    assert_eq!(store.list_state_ids(None).await.unwrap(), vec!["greeting"]);
+
+    assert!(store.remove_state("greeting", None).await.unwrap());
+    assert!(!store.remove_state("greeting", None).await.unwrap());
+    assert_eq!(store.read_state("greeting", None).await.unwrap(), None);
+}
+
+#[tokio::test]
+#[ignore = "requires REDIS_SMOKE_URL"]
+async fn scoped_state_round_trips() {
+    let store = store().await;
+
+    store
+        .queue_state_scoped("plan".to_string(), json!("a"), "team-a".to_string())
+        .await
+        .unwrap();
+    store
+        .queue_state_scoped("plan".to_string(), json!("b"), "team-b".to_string())
+        .await
+        .unwrap();
+    store
+        .queue_state_update("global".to_string(), json!(1), None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(store.read_state_scoped("plan", "team-a").await.unwrap(), Some(json!("a")));
+    assert_eq!(store.read_state_scoped("plan", "team-b").await.unwrap(), Some(json!("b")));
+    assert_eq!(store.read_state("plan", None).await.unwrap(), None);
+    assert_eq!(store.list_state_ids(Some("team-a")).await.unwrap(), vec!["plan"]);
+    assert_eq!(
+        store.list_state_ids(None).await.unwrap(),
+        vec!["global", "team-a:plan", "team-b:plan"]
+    );
+
+    assert_eq!(store.clear_states(Some("team-a")).await.unwrap(), 1);
+    assert_eq!(store.clear_states(None).await.unwrap(), 2);
+}
+
+#[tokio::test]
+#[ignore = "requires REDIS_SMOKE_URL"]
+async fn expired_state_reads_as_absent() {
+    let store = store().await;
+
+    store
+        .queue_state_update(
+            "token".to_string(),
+            json!("secret"),
+            None,
+            Some(std::time::Duration::from_millis(100)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(store.read_state("token", None).await.unwrap(), Some(json!("secret")));
+
+    tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+    assert_eq!(store.read_state("token", None).await.unwrap(), None);
+}
+
+#[tokio::test]
+#[ignore = "requires REDIS_SMOKE_URL"]
+async fn compare_and_swap_applies_only_on_match() {
+    let store = store().await;
+
+    store
+        .queue_state_update("counter".to_string(), json!(0), None, None)
+        .await
+        .unwrap();
+    assert!(store.compare_and_swap("counter", None, &json!(0), json!(1)).await.unwrap());
+    assert!(!store.compare_and_swap("counter", None, &json!(0), json!(2)).await.unwrap());
+    assert_eq!(store.read_state("counter", None).await.unwrap(), Some(json!(1)));
+
+    store.clear_states(None).await.unwrap();
+}